@@ -0,0 +1,552 @@
+//! Compare reports from several locations (`aggregate`)
+//!
+//! Agents in different network segments run the same suite; their reports
+//! (`test -o json`, `--record` sessions, or the files a `serve` controller
+//! keeps) are merged into one matrix with a row per scenario and a column per
+//! location, so a scenario that works from one site and not another stands
+//! out. A report's location is its `location` field, or else its file name.
+
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use console::style;
+use serde::Serialize;
+
+use crate::error::{AppError, Result};
+use crate::output::TestReport;
+use crate::services::{FailureKind, TestResult};
+
+/// A report and the location it came from
+#[derive(Debug, Clone)]
+pub struct LocationReport {
+    pub location: String,
+    pub report: TestReport,
+}
+
+/// Outcome of a scenario at one location
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CellStatus {
+    Passed,
+    Failed,
+    Skipped,
+    Throttled,
+    ExpectedFailure,
+}
+
+impl CellStatus {
+    /// Status of one scenario result
+    pub fn of(result: &TestResult) -> Self {
+        if result.success {
+            Self::Passed
+        } else if result.is_throttled() {
+            Self::Throttled
+        } else if result.is_expected_failure() {
+            Self::ExpectedFailure
+        } else if result.is_skipped() {
+            Self::Skipped
+        } else {
+            Self::Failed
+        }
+    }
+}
+
+/// A scenario's result at one location
+#[derive(Debug, Clone, Serialize)]
+pub struct Cell {
+    pub status: CellStatus,
+    pub duration_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_kind: Option<FailureKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A scenario across the locations
+#[derive(Debug, Clone, Serialize)]
+pub struct MatrixRow {
+    /// Service label, with its target and region if any
+    pub service: String,
+    pub scenario_id: String,
+    pub scenario_name: String,
+    /// Result by location; locations that didn't run the scenario are missing
+    pub cells: BTreeMap<String, Cell>,
+}
+
+impl MatrixRow {
+    /// Whether the locations disagree, or some didn't run the scenario
+    pub fn differs(&self, locations: usize) -> bool {
+        let mut statuses = self.cells.values().map(|cell| cell.status);
+        let first = statuses.next();
+        self.cells.len() < locations || statuses.any(|status| Some(status) != first)
+    }
+}
+
+/// One column of the matrix
+#[derive(Debug, Clone, Serialize)]
+pub struct LocationSummary {
+    pub name: String,
+    pub timestamp: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub egress_ip: Option<IpAddr>,
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+}
+
+/// Scenarios by location
+#[derive(Debug, Clone, Serialize)]
+pub struct Matrix {
+    pub locations: Vec<LocationSummary>,
+    pub rows: Vec<MatrixRow>,
+}
+
+impl Matrix {
+    /// Matrix of `reports`, with locations in the given order and rows in
+    /// the order scenarios first appear
+    pub fn new(reports: &[LocationReport]) -> Self {
+        let mut rows: Vec<MatrixRow> = Vec::new();
+        let mut index: HashMap<(String, String), usize> = HashMap::new();
+        for LocationReport { location, report } in reports {
+            for service in &report.services {
+                let label = service.label();
+                for result in &service.results {
+                    let key = (label.clone(), result.scenario_id.clone());
+                    let row = *index.entry(key).or_insert_with(|| {
+                        rows.push(MatrixRow {
+                            service: label.clone(),
+                            scenario_id: result.scenario_id.clone(),
+                            scenario_name: result.scenario_name.clone(),
+                            cells: BTreeMap::new(),
+                        });
+                        rows.len() - 1
+                    });
+                    let status = CellStatus::of(result);
+                    rows[row].cells.insert(
+                        location.clone(),
+                        Cell {
+                            status,
+                            duration_ms: result.duration_ms,
+                            failure_kind: result
+                                .failure_kind
+                                .filter(|_| status == CellStatus::Failed),
+                            error: result
+                                .error
+                                .clone()
+                                .filter(|_| status == CellStatus::Failed),
+                        },
+                    );
+                }
+            }
+        }
+
+        let locations = reports
+            .iter()
+            .map(|LocationReport { location, report }| LocationSummary {
+                name: location.clone(),
+                timestamp: report.timestamp,
+                region: report.region.clone(),
+                egress_ip: report.environment.as_ref().and_then(|e| e.egress_ip),
+                passed: report.summary.passed,
+                failed: report.summary.failed,
+                skipped: report.summary.skipped,
+            })
+            .collect();
+        Self { locations, rows }
+    }
+
+    /// Keep only the rows whose outcome differs between locations
+    pub fn retain_differences(&mut self) {
+        let locations = self.locations.len();
+        self.rows.retain(|row| row.differs(locations));
+    }
+
+    /// Whether any location has a failed scenario
+    pub fn has_failures(&self) -> bool {
+        self.locations.iter().any(|l| l.failed > 0)
+    }
+}
+
+/// Load the reports in `paths` (files, or directories of `.json` files),
+/// keeping the newest report of each location
+pub fn load_reports(paths: &[PathBuf]) -> Result<Vec<LocationReport>> {
+    let mut newest: BTreeMap<String, TestReport> = BTreeMap::new();
+    for path in paths {
+        let files = if path.is_dir() {
+            let mut files: Vec<PathBuf> = std::fs::read_dir(path)?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+                .collect();
+            files.sort();
+            files
+        } else {
+            vec![path.clone()]
+        };
+        for file in files {
+            let report = match read_report(&file) {
+                Ok(report) => report,
+                // Directories may hold other JSON files
+                Err(e) if path.is_dir() => {
+                    tracing::warn!(file = %file.display(), error = %e, "skipping file");
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            let location = report
+                .location
+                .clone()
+                .unwrap_or_else(|| location_from_file_name(&file));
+            if newest
+                .get(&location)
+                .is_none_or(|kept| kept.timestamp < report.timestamp)
+            {
+                newest.insert(location, report);
+            }
+        }
+    }
+    if newest.is_empty() {
+        return Err(AppError::InvalidInput(
+            "No reports found; pass report files written by 'test -o json' or directories of them"
+                .to_string(),
+        ));
+    }
+    Ok(newest
+        .into_iter()
+        .map(|(location, report)| LocationReport { location, report })
+        .collect())
+}
+
+/// Read a report file
+fn read_report(path: &Path) -> Result<TestReport> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            AppError::FileNotFound(path.display().to_string())
+        } else {
+            AppError::Io(e)
+        }
+    })?;
+    parse_report(&content).map_err(|e| {
+        AppError::InvalidInput(format!(
+            "{} is not a report written by 'test -o json': {}",
+            path.display(),
+            e
+        ))
+    })
+}
+
+/// Parse a report, or the report inside a `--record` session or webhook payload
+pub fn parse_report(json: &str) -> serde_json::Result<TestReport> {
+    let mut value: serde_json::Value = serde_json::from_str(json)?;
+    if let Some(report) = value.get_mut("report") {
+        value = report.take();
+    }
+    serde_json::from_value(value)
+}
+
+/// File name without the extension and a `-<timestamp>` suffix as written
+/// by `serve --history-dir`
+fn location_from_file_name(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    match stem.rsplit_once('-') {
+        Some((name, stamp)) if stamp.len() == 16 && stamp.ends_with('Z') => name.to_string(),
+        _ => stem,
+    }
+}
+
+/// Short text of a cell: its mark and the duration or failure kind
+fn cell_text(cell: Option<&Cell>, use_colors: bool) -> String {
+    let Some(cell) = cell else {
+        return "-".to_string();
+    };
+    let mark = match (cell.status, use_colors) {
+        (CellStatus::Passed, true) => "\u{2713}",
+        (CellStatus::Failed, true) => "\u{2717}",
+        (CellStatus::Skipped, true) => "\u{25CB}",
+        (CellStatus::Throttled, true) => "\u{29D6}",
+        (CellStatus::ExpectedFailure, true) => "\u{2298}",
+        (CellStatus::Passed, false) => "[PASS]",
+        (CellStatus::Failed, false) => "[FAIL]",
+        (CellStatus::Skipped, false) => "[SKIP]",
+        (CellStatus::Throttled, false) => "[THROTTLED]",
+        (CellStatus::ExpectedFailure, false) => "[XFAIL]",
+    };
+    match cell.status {
+        CellStatus::Failed => format!(
+            "{} {}",
+            mark,
+            cell.failure_kind.unwrap_or(FailureKind::Other)
+        ),
+        CellStatus::Skipped => mark.to_string(),
+        _ => format!("{} {}ms", mark, cell.duration_ms),
+    }
+}
+
+/// Format a matrix for human-readable output
+pub fn format_matrix(matrix: &Matrix, use_colors: bool) -> String {
+    let mut output = String::new();
+    output.push_str("\nConnectivity by Location\n");
+    output.push_str("========================\n\n");
+
+    let name_width = matrix
+        .locations
+        .iter()
+        .map(|l| l.name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(8);
+    for location in &matrix.locations {
+        let mut line = format!(
+            "{:<width$}  {}  passed {:>3} | failed {:>3} | skipped {:>3}",
+            location.name,
+            location.timestamp.format("%Y-%m-%d %H:%M UTC"),
+            location.passed,
+            location.failed,
+            location.skipped,
+            width = name_width
+        );
+        if let Some(ip) = location.egress_ip {
+            line.push_str(&format!(" | egress {}", ip));
+        }
+        if use_colors && location.failed > 0 {
+            output.push_str(&format!("  {}\n", style(line).red()));
+        } else {
+            output.push_str(&format!("  {}\n", line));
+        }
+    }
+    output.push('\n');
+
+    if matrix.rows.is_empty() {
+        output.push_str("  No scenarios to compare\n");
+        return output;
+    }
+    let labels: Vec<String> = matrix
+        .rows
+        .iter()
+        .map(|row| format!("{} / {}", row.service, row.scenario_name))
+        .collect();
+    let label_width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let widths: Vec<usize> = matrix
+        .locations
+        .iter()
+        .map(|location| {
+            matrix
+                .rows
+                .iter()
+                .map(|row| {
+                    cell_text(row.cells.get(&location.name), use_colors)
+                        .chars()
+                        .count()
+                })
+                .chain([location.name.chars().count()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut header = format!("  {:<width$}", "", width = label_width);
+    for (location, width) in matrix.locations.iter().zip(&widths) {
+        header.push_str(&format!("  {:<width$}", location.name, width = width));
+    }
+    output.push_str(header.trim_end());
+    output.push('\n');
+    for (row, label) in matrix.rows.iter().zip(&labels) {
+        let mut line = format!("  {:<width$}", label, width = label_width);
+        for (location, width) in matrix.locations.iter().zip(&widths) {
+            let cell = row.cells.get(&location.name);
+            let text = format!("{:<width$}", cell_text(cell, use_colors), width = width);
+            let text = match cell.map(|c| c.status) {
+                Some(CellStatus::Passed) if use_colors => style(text).green().to_string(),
+                Some(CellStatus::Failed) if use_colors => style(text).red().to_string(),
+                Some(CellStatus::Skipped | CellStatus::Throttled) if use_colors => {
+                    style(text).yellow().to_string()
+                }
+                Some(CellStatus::ExpectedFailure) if use_colors => style(text).cyan().to_string(),
+                _ => text,
+            };
+            line.push_str(&format!("  {}", text));
+        }
+        output.push_str(line.trim_end());
+        output.push('\n');
+    }
+
+    // The errors behind the failed cells
+    let failures: Vec<String> = matrix
+        .rows
+        .iter()
+        .flat_map(|row| {
+            row.cells
+                .iter()
+                .filter(|(_, cell)| cell.status == CellStatus::Failed)
+                .map(move |(location, cell)| {
+                    format!(
+                        "{} - {} / {}: {}",
+                        location,
+                        row.service,
+                        row.scenario_name,
+                        cell.error.as_deref().unwrap_or("failed")
+                    )
+                })
+        })
+        .collect();
+    if !failures.is_empty() {
+        output.push_str("\nFailures\n");
+        for failure in failures {
+            if use_colors {
+                output.push_str(&format!("  {}\n", style(failure).dim()));
+            } else {
+                output.push_str(&format!("  {}\n", failure));
+            }
+        }
+    }
+
+    let locations = matrix.locations.len();
+    let differing = matrix.rows.iter().filter(|r| r.differs(locations)).count();
+    output.push_str(&format!(
+        "\n{} locations, {} scenarios, {} differ between locations\n",
+        locations,
+        matrix.rows.len(),
+        differing
+    ));
+    output
+}
+
+/// Format a matrix as a Markdown table
+pub fn format_matrix_markdown(matrix: &Matrix) -> String {
+    let mut md = String::new();
+    md.push_str("# Connectivity by Location\n\n");
+    md.push_str("| Location | Report time | Region | Passed | Failed | Skipped |\n");
+    md.push_str("|----------|-------------|--------|--------|--------|---------|\n");
+    for location in &matrix.locations {
+        md.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} |\n",
+            location.name,
+            location.timestamp.format("%Y-%m-%d %H:%M UTC"),
+            location.region.as_deref().unwrap_or("-"),
+            location.passed,
+            location.failed,
+            location.skipped
+        ));
+    }
+
+    md.push_str("\n| Service | Scenario |");
+    for location in &matrix.locations {
+        md.push_str(&format!(" {} |", location.name));
+    }
+    md.push_str("\n|---------|----------|");
+    for _ in &matrix.locations {
+        md.push_str("---|");
+    }
+    md.push('\n');
+    for row in &matrix.rows {
+        md.push_str(&format!("| {} | {} |", row.service, row.scenario_name));
+        for location in &matrix.locations {
+            let text = match row.cells.get(&location.name) {
+                None => "-".to_string(),
+                Some(cell) => match cell.status {
+                    CellStatus::Passed => format!("✅ {} ms", cell.duration_ms),
+                    CellStatus::Failed => {
+                        format!("❌ {}", cell.failure_kind.unwrap_or(FailureKind::Other))
+                    }
+                    CellStatus::Skipped => "⏭️ Skip".to_string(),
+                    CellStatus::Throttled => "⏳ Throttled".to_string(),
+                    CellStatus::ExpectedFailure => "☑️ Expected failure".to_string(),
+                },
+            };
+            md.push_str(&format!(" {} |", text));
+        }
+        md.push('\n');
+    }
+    md
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::ServiceTestResults;
+
+    fn report(location: &str, tts: TestResult) -> TestReport {
+        TestReport::new(vec![ServiceTestResults {
+            service_name: "Speech".to_string(),
+            endpoint: "https://eastus.api.cognitive.microsoft.com".to_string(),
+            region: None,
+            target: None,
+            total_duration_ms: 600,
+            results: vec![
+                TestResult::success("voices_list", "Get Voices List", 250),
+                tts,
+            ],
+        }])
+        .with_location(location.to_string())
+    }
+
+    fn matrix() -> Matrix {
+        let hq = report("hq", TestResult::success("tts", "Text to Speech", 350));
+        let branch = report(
+            "branch",
+            TestResult::failure("tts", "Text to Speech", 20, "DNS lookup failed".to_string())
+                .with_failure_kind(FailureKind::Dns),
+        );
+        Matrix::new(&[
+            LocationReport {
+                location: "hq".to_string(),
+                report: hq,
+            },
+            LocationReport {
+                location: "branch".to_string(),
+                report: branch,
+            },
+        ])
+    }
+
+    #[test]
+    fn test_matrix() {
+        let mut matrix = matrix();
+        assert_eq!(matrix.rows.len(), 2);
+        assert!(matrix.has_failures());
+        let tts = &matrix.rows[1];
+        assert_eq!(tts.cells["hq"].status, CellStatus::Passed);
+        assert_eq!(tts.cells["branch"].failure_kind, Some(FailureKind::Dns));
+
+        matrix.retain_differences();
+        assert_eq!(matrix.rows.len(), 1);
+        assert_eq!(matrix.rows[0].scenario_id, "tts");
+
+        let output = format_matrix(&matrix, false);
+        assert!(output.contains("Speech / Text to Speech  [PASS] 350ms  [FAIL] DNS"));
+        assert!(output.contains("branch - Speech / Text to Speech: DNS lookup failed"));
+        let md = format_matrix_markdown(&matrix);
+        assert!(md.contains("| Speech | Text to Speech | ✅ 350 ms | ❌ DNS |"));
+    }
+
+    #[test]
+    fn test_load_reports_keeps_newest_per_location() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut old = report("hq", TestResult::success("tts", "Text to Speech", 350));
+        old.timestamp = "2024-03-01T10:00:00Z".parse().unwrap();
+        let new = report("hq", TestResult::success("tts", "Text to Speech", 300));
+        let unnamed = TestReport::new(Vec::new());
+        let write = |name: &str, json: String| std::fs::write(dir.path().join(name), json).unwrap();
+        write("a.json", serde_json::to_string(&old).unwrap());
+        write("b.json", serde_json::to_string(&new).unwrap());
+        // A --record session wraps the report
+        write(
+            "branch-20240301T100500Z.json",
+            serde_json::json!({ "report": unnamed }).to_string(),
+        );
+        write("notes.json", "{}".to_string());
+
+        let reports = load_reports(&[dir.path().to_path_buf()]).unwrap();
+        let locations: Vec<&str> = reports.iter().map(|r| r.location.as_str()).collect();
+        assert_eq!(locations, ["branch", "hq"]);
+        assert_eq!(reports[1].report.timestamp, new.timestamp);
+
+        assert!(load_reports(&[dir.path().join("notes.json")]).is_err());
+    }
+}
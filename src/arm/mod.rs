@@ -0,0 +1,1437 @@
+//! Azure Resource Manager (control plane) helpers.
+//!
+//! Used to discover Cognitive Services accounts in a subscription, read their
+//! endpoints and network configuration, and fetch keys so a test run can be
+//! populated without copying values from the portal.
+
+use crate::config::{Cloud, Config, ServiceConfig};
+use crate::error::{sanitize_error, AppError, Result};
+use crate::output::TestReport;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::Duration;
+
+const ACCOUNTS_API_VERSION: &str = "2023-05-01";
+const SUBSCRIPTIONS_API_VERSION: &str = "2022-12-01";
+const ROLE_ASSIGNMENTS_API_VERSION: &str = "2022-04-01";
+
+/// Role suggested for data-plane access to any Azure AI service
+pub const COGNITIVE_SERVICES_USER: &str = "Cognitive Services User";
+/// Narrower role suggested when only Azure OpenAI is called
+const OPENAI_USER: &str = "Cognitive Services OpenAI User";
+
+/// What a built-in role allows on the data plane
+#[derive(Debug, Clone, Copy)]
+enum RoleAccess {
+    /// Every service of the account
+    AllServices,
+    /// Only these services
+    Services(&'static [&'static str]),
+    /// Management operations only (no data actions)
+    ManagementOnly,
+}
+
+/// Built-in roles by role definition ID (the same in every tenant)
+const BUILTIN_ROLES: &[(&str, &str, RoleAccess)] = &[
+    (
+        "a97b65f3-24c7-4388-baec-2e87135dc908",
+        COGNITIVE_SERVICES_USER,
+        RoleAccess::AllServices,
+    ),
+    (
+        "53ca6127-db72-4b80-b1b0-d745d6d5456d",
+        "Azure AI User",
+        RoleAccess::AllServices,
+    ),
+    (
+        "5e0bd9bd-7b93-4f28-af87-19fc36ad61bd",
+        OPENAI_USER,
+        RoleAccess::Services(&["openai"]),
+    ),
+    (
+        "a001fd3d-188f-4b5d-821b-7da978bf7442",
+        "Cognitive Services OpenAI Contributor",
+        RoleAccess::Services(&["openai"]),
+    ),
+    (
+        "f2dc8367-1007-4938-bd23-fe263f013447",
+        "Cognitive Services Speech User",
+        RoleAccess::Services(&["speech"]),
+    ),
+    (
+        "0e75ca1e-0464-4b4d-8b93-68208a576181",
+        "Cognitive Services Speech Contributor",
+        RoleAccess::Services(&["speech"]),
+    ),
+    (
+        "7628b7b8-a8b2-4cdc-b46f-e9b35248918e",
+        "Cognitive Services Language Reader",
+        RoleAccess::Services(&["language"]),
+    ),
+    (
+        "f2310ca1-dc64-4889-bb49-c8e0fa3d47a8",
+        "Cognitive Services Language Writer",
+        RoleAccess::Services(&["language"]),
+    ),
+    (
+        "f07febfe-79bc-46b1-8b37-790e26e6e498",
+        "Cognitive Services Language Owner",
+        RoleAccess::Services(&["language"]),
+    ),
+    (
+        "25fbc0a9-bd7c-42a3-aa1a-3b75d497ee68",
+        "Cognitive Services Contributor",
+        RoleAccess::ManagementOnly,
+    ),
+    (
+        "8e3af657-a8ff-443c-a75c-2fe8c4bcb635",
+        "Owner",
+        RoleAccess::ManagementOnly,
+    ),
+    (
+        "b24988ac-6180-42a0-ab88-20f7382dd24c",
+        "Contributor",
+        RoleAccess::ManagementOnly,
+    ),
+    (
+        "acdd72a7-3385-48ef-bd42-f606fba81ae7",
+        "Reader",
+        RoleAccess::ManagementOnly,
+    ),
+];
+
+/// A Cognitive Services account discovered via ARM
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CognitiveAccount {
+    /// Full ARM resource ID
+    pub id: String,
+    /// Account name
+    pub name: String,
+    /// Account kind (e.g., "SpeechServices", "CognitiveServices")
+    pub kind: String,
+    /// Azure region
+    pub location: String,
+    /// SKU name (e.g., "S0", "F0")
+    pub sku: Option<String>,
+    /// Primary endpoint
+    pub endpoint: Option<String>,
+    /// Public network access setting ("Enabled"/"Disabled")
+    pub public_network_access: Option<String>,
+    /// Whether key-based (local) auth is disabled
+    pub disable_local_auth: bool,
+    /// Network ACLs (firewall)
+    pub network_acls: Option<NetworkAcls>,
+    /// Number of private endpoint connections
+    pub private_endpoint_connections: usize,
+}
+
+impl CognitiveAccount {
+    /// Services in this tool that can be tested against this account
+    pub fn service_names(&self) -> Vec<&'static str> {
+        match self.kind.as_str() {
+            "SpeechServices" => vec!["speech"],
+            "TextTranslation" => vec!["translator"],
+            "TextAnalytics" | "Language" => vec!["language"],
+            "ComputerVision" => vec!["vision"],
+            "FormRecognizer" => vec!["document_intelligence"],
+            "OpenAI" => vec!["openai"],
+            "CognitiveServices" => vec![
+                "speech",
+                "translator",
+                "language",
+                "vision",
+                "document_intelligence",
+            ],
+            "AIServices" => vec![
+                "speech",
+                "translator",
+                "language",
+                "vision",
+                "document_intelligence",
+                "ai_foundry",
+                "openai",
+            ],
+            _ => vec![],
+        }
+    }
+}
+
+/// Network ACLs configured on an account
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkAcls {
+    /// "Allow" or "Deny"
+    #[serde(default)]
+    pub default_action: String,
+    /// Allowed public IPs / CIDR ranges
+    #[serde(default, deserialize_with = "deserialize_ip_rules")]
+    pub ip_rules: Vec<String>,
+    /// Allowed virtual network subnet IDs
+    #[serde(default, deserialize_with = "deserialize_vnet_rules")]
+    pub virtual_network_rules: Vec<String>,
+}
+
+/// ARM returns `[{"value": "1.2.3.4"}]`; our own JSON output uses plain strings
+fn deserialize_ip_rules<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct IpRule {
+        value: String,
+    }
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Rule {
+        Object(IpRule),
+        Plain(String),
+    }
+    let rules: Option<Vec<Rule>> = Option::deserialize(deserializer)?;
+    Ok(rules
+        .unwrap_or_default()
+        .into_iter()
+        .map(|r| match r {
+            Rule::Object(o) => o.value,
+            Rule::Plain(s) => s,
+        })
+        .collect())
+}
+
+/// ARM returns `[{"id": "/subscriptions/.../subnets/x"}]`
+fn deserialize_vnet_rules<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct VnetRule {
+        id: String,
+    }
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Rule {
+        Object(VnetRule),
+        Plain(String),
+    }
+    let rules: Option<Vec<Rule>> = Option::deserialize(deserializer)?;
+    Ok(rules
+        .unwrap_or_default()
+        .into_iter()
+        .map(|r| match r {
+            Rule::Object(o) => o.id,
+            Rule::Plain(s) => s,
+        })
+        .collect())
+}
+
+/// Raw ARM account representation
+#[derive(Debug, Deserialize)]
+struct RawAccount {
+    id: String,
+    name: String,
+    #[serde(default)]
+    kind: String,
+    #[serde(default)]
+    location: String,
+    sku: Option<RawSku>,
+    #[serde(default)]
+    properties: RawProperties,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawSku {
+    name: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawProperties {
+    endpoint: Option<String>,
+    public_network_access: Option<String>,
+    disable_local_auth: Option<bool>,
+    network_acls: Option<NetworkAcls>,
+    private_endpoint_connections: Option<Vec<serde_json::Value>>,
+}
+
+impl From<RawAccount> for CognitiveAccount {
+    fn from(raw: RawAccount) -> Self {
+        Self {
+            id: raw.id,
+            name: raw.name,
+            kind: raw.kind,
+            location: raw.location,
+            sku: raw.sku.map(|s| s.name),
+            endpoint: raw.properties.endpoint,
+            public_network_access: raw.properties.public_network_access,
+            disable_local_auth: raw.properties.disable_local_auth.unwrap_or(false),
+            network_acls: raw.properties.network_acls,
+            private_endpoint_connections: raw
+                .properties
+                .private_endpoint_connections
+                .map(|p| p.len())
+                .unwrap_or(0),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ListResponse<T> {
+    value: Vec<T>,
+    #[serde(rename = "nextLink")]
+    next_link: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawSubscription {
+    subscription_id: String,
+}
+
+/// A role assignment that applies to a resource (made on it or inherited
+/// from its resource group, subscription, or a group the principal is in)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoleAssignment {
+    /// Full role definition resource ID
+    pub role_definition_id: String,
+    /// Scope the role was assigned at
+    pub scope: String,
+}
+
+impl RoleAssignment {
+    fn builtin(&self) -> Option<(&'static str, RoleAccess)> {
+        let id = self.role_definition_id.rsplit('/').next()?;
+        BUILTIN_ROLES
+            .iter()
+            .find(|(role_id, _, _)| role_id.eq_ignore_ascii_case(id))
+            .map(|(_, name, access)| (*name, *access))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RawRoleAssignment {
+    properties: RoleAssignment,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListKeysResponse {
+    key1: String,
+}
+
+/// A quota counter of an account, or of a subscription in a region (`usages`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaUsage {
+    /// Counter ID (e.g., "OpenAI.Standard.gpt-4o")
+    pub name: String,
+    /// Counter description from ARM
+    pub display_name: String,
+    pub current_value: f64,
+    pub limit: f64,
+    /// Unit of the counter (e.g., "Count")
+    pub unit: String,
+    /// Window the counter resets after (e.g., "00:01:00"); none for totals
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub period: Option<String>,
+}
+
+impl QuotaUsage {
+    /// Share of the limit in use, when there is a limit
+    pub fn fraction_used(&self) -> Option<f64> {
+        (self.limit > 0.0).then(|| self.current_value / self.limit)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawUsage {
+    name: RawUsageName,
+    #[serde(default)]
+    current_value: f64,
+    #[serde(default)]
+    limit: f64,
+    #[serde(default)]
+    unit: String,
+    quota_period: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawUsageName {
+    value: String,
+    localized_value: Option<String>,
+}
+
+impl From<RawUsage> for QuotaUsage {
+    fn from(raw: RawUsage) -> Self {
+        Self {
+            display_name: raw
+                .name
+                .localized_value
+                .filter(|v| !v.is_empty())
+                .unwrap_or_else(|| raw.name.value.clone()),
+            name: raw.name.value,
+            current_value: raw.current_value,
+            limit: raw.limit,
+            unit: raw.unit,
+            period: raw.quota_period,
+        }
+    }
+}
+
+/// A model deployment and the rate limits its capacity grants
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Deployment {
+    pub name: String,
+    pub model: Option<String>,
+    pub model_version: Option<String>,
+    /// Deployment type (e.g., "Standard", "GlobalStandard", "ProvisionedManaged")
+    pub sku: Option<String>,
+    /// Capacity units (thousands of tokens per minute for Standard deployments)
+    pub capacity: Option<u64>,
+    pub requests_per_minute: Option<u64>,
+    pub tokens_per_minute: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawDeployment {
+    name: String,
+    sku: Option<RawDeploymentSku>,
+    #[serde(default)]
+    properties: RawDeploymentProperties,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawDeploymentSku {
+    name: String,
+    capacity: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawDeploymentProperties {
+    model: Option<RawModel>,
+    #[serde(default)]
+    rate_limits: Vec<RawRateLimit>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawModel {
+    name: String,
+    version: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawRateLimit {
+    key: String,
+    /// Seconds
+    renewal_period: f64,
+    count: f64,
+}
+
+impl From<RawDeployment> for Deployment {
+    fn from(raw: RawDeployment) -> Self {
+        let per_minute = |key: &str| {
+            raw.properties
+                .rate_limits
+                .iter()
+                .find(|l| l.key == key && l.renewal_period > 0.0)
+                .map(|l| (l.count * 60.0 / l.renewal_period).round() as u64)
+        };
+        let requests_per_minute = per_minute("request");
+        let tokens_per_minute = per_minute("token");
+        let (model, model_version) = match raw.properties.model {
+            Some(model) => (Some(model.name), model.version),
+            None => (None, None),
+        };
+        Self {
+            name: raw.name,
+            model,
+            model_version,
+            sku: raw.sku.as_ref().map(|s| s.name.clone()),
+            capacity: raw.sku.and_then(|s| s.capacity),
+            requests_per_minute,
+            tokens_per_minute,
+        }
+    }
+}
+
+/// Obtain an ARM access token from the Azure CLI (`az account get-access-token`)
+pub fn get_token_from_azure_cli(cloud: Cloud) -> Result<String> {
+    azure_cli_token(cloud.arm_endpoint(), "ARM", ", or pass --arm-token")
+}
+
+/// Obtain an access token for another resource (e.g. Key Vault) from the Azure CLI
+pub fn get_resource_token_from_azure_cli(resource: &str, label: &str) -> Result<String> {
+    azure_cli_token(resource, label, "")
+}
+
+fn azure_cli_token(resource: &str, label: &str, alternative: &str) -> Result<String> {
+    let program = if cfg!(windows) { "az.cmd" } else { "az" };
+    let output = std::process::Command::new(program)
+        .args([
+            "account",
+            "get-access-token",
+            "--resource",
+            resource,
+            "--query",
+            "accessToken",
+            "--output",
+            "tsv",
+        ])
+        .output()
+        .map_err(|e| {
+            AppError::Auth(format!(
+                "Could not run Azure CLI ('az'). Install it and run 'az login'{}: {}",
+                alternative, e
+            ))
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::Auth(format!(
+            "Azure CLI could not provide a token for {} (run 'az login'): {}",
+            label,
+            stderr.trim()
+        )));
+    }
+
+    let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if token.is_empty() {
+        return Err(AppError::Auth(format!(
+            "Azure CLI returned an empty token for {}",
+            label
+        )));
+    }
+    Ok(token)
+}
+
+/// Minimal ARM client for Cognitive Services accounts
+pub struct ArmClient {
+    client: Client,
+    cloud: Cloud,
+    token: String,
+}
+
+impl ArmClient {
+    pub fn new(cloud: Cloud, token: String, timeout: Duration) -> Result<Self> {
+        let client = crate::network::configure_client(Client::builder())
+            .timeout(timeout)
+            .build()
+            .map_err(|e| AppError::Network(e.to_string()))?;
+        Ok(Self {
+            client,
+            cloud,
+            token,
+        })
+    }
+
+    /// GET an ARM URL (absolute, or a path relative to the ARM endpoint)
+    async fn get_json(&self, url: &str) -> Result<serde_json::Value> {
+        let url = if url.starts_with("https://") {
+            url.to_string()
+        } else {
+            format!("{}{}", self.cloud.arm_endpoint(), url)
+        };
+        let response = self
+            .client
+            .get(&url)
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .map_err(|e| {
+                AppError::Network(format!(
+                    "Could not reach Azure Resource Manager ({}): {}",
+                    self.cloud.arm_endpoint(),
+                    e
+                ))
+            })?;
+        Self::parse_response(response).await
+    }
+
+    async fn parse_response(response: reqwest::Response) -> Result<serde_json::Value> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response.json().await?);
+        }
+        let body = response.text().await.unwrap_or_default();
+        let message = format!(
+            "ARM request failed with HTTP {}: {}",
+            status,
+            sanitize_error(&body, status.as_u16())
+        );
+        if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            Err(AppError::Auth(message))
+        } else {
+            Err(AppError::Service {
+                service: "arm".to_string(),
+                message,
+            })
+        }
+    }
+
+    /// Follow nextLink pagination and collect all items
+    async fn list_all<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        let mut next = Some(url.to_string());
+        while let Some(url) = next {
+            let page: ListResponse<T> = serde_json::from_value(self.get_json(&url).await?)?;
+            items.extend(page.value);
+            next = page.next_link;
+        }
+        Ok(items)
+    }
+
+    /// List subscription IDs visible to the token
+    pub async fn list_subscriptions(&self) -> Result<Vec<String>> {
+        let subs: Vec<RawSubscription> = self
+            .list_all(&format!(
+                "/subscriptions?api-version={}",
+                SUBSCRIPTIONS_API_VERSION
+            ))
+            .await?;
+        Ok(subs.into_iter().map(|s| s.subscription_id).collect())
+    }
+
+    /// List Cognitive Services accounts in a subscription
+    pub async fn list_accounts(&self, subscription_id: &str) -> Result<Vec<CognitiveAccount>> {
+        let raw: Vec<RawAccount> = self
+            .list_all(&format!(
+                "/subscriptions/{}/providers/Microsoft.CognitiveServices/accounts?api-version={}",
+                subscription_id, ACCOUNTS_API_VERSION
+            ))
+            .await?;
+        Ok(raw.into_iter().map(CognitiveAccount::from).collect())
+    }
+
+    /// Get a single account by its ARM resource ID
+    pub async fn get_account(&self, resource_id: &str) -> Result<CognitiveAccount> {
+        let value = self
+            .get_json(&format!(
+                "{}?api-version={}",
+                resource_id.trim_end_matches('/'),
+                ACCOUNTS_API_VERSION
+            ))
+            .await?;
+        let raw: RawAccount = serde_json::from_value(value)?;
+        Ok(raw.into())
+    }
+
+    /// Fetch the primary key of an account
+    pub async fn list_keys(&self, resource_id: &str) -> Result<String> {
+        let url = format!(
+            "{}{}/listKeys?api-version={}",
+            self.cloud.arm_endpoint(),
+            resource_id.trim_end_matches('/'),
+            ACCOUNTS_API_VERSION
+        );
+        let response = self
+            .client
+            .post(&url)
+            .bearer_auth(&self.token)
+            .header("Content-Length", "0")
+            .send()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to call listKeys: {}", e)))?;
+        let keys: ListKeysResponse = serde_json::from_value(Self::parse_response(response).await?)?;
+        Ok(keys.key1)
+    }
+
+    /// Usage counters and their limits of an account
+    pub async fn list_usages(&self, resource_id: &str) -> Result<Vec<QuotaUsage>> {
+        let raw: Vec<RawUsage> = self
+            .list_all(&format!(
+                "{}/usages?api-version={}",
+                resource_id.trim_end_matches('/'),
+                ACCOUNTS_API_VERSION
+            ))
+            .await?;
+        Ok(raw.into_iter().map(QuotaUsage::from).collect())
+    }
+
+    /// Model deployments of an account (Azure OpenAI and AI Services)
+    pub async fn list_deployments(&self, resource_id: &str) -> Result<Vec<Deployment>> {
+        let raw: Vec<RawDeployment> = self
+            .list_all(&format!(
+                "{}/deployments?api-version={}",
+                resource_id.trim_end_matches('/'),
+                ACCOUNTS_API_VERSION
+            ))
+            .await?;
+        Ok(raw.into_iter().map(Deployment::from).collect())
+    }
+
+    /// Model quota of a subscription in a region, shared by its accounts there
+    pub async fn list_location_usages(
+        &self,
+        subscription_id: &str,
+        location: &str,
+    ) -> Result<Vec<QuotaUsage>> {
+        let raw: Vec<RawUsage> = self
+            .list_all(&format!(
+                "/subscriptions/{}/providers/Microsoft.CognitiveServices/locations/{}/usages?api-version={}",
+                subscription_id, location, ACCOUNTS_API_VERSION
+            ))
+            .await?;
+        Ok(raw.into_iter().map(QuotaUsage::from).collect())
+    }
+
+    /// Role assignments of `principal_id` (an Entra object ID) that apply to
+    /// a resource, including inherited and group-based ones
+    pub async fn list_role_assignments(
+        &self,
+        resource_id: &str,
+        principal_id: &str,
+    ) -> Result<Vec<RoleAssignment>> {
+        let raw: Vec<RawRoleAssignment> = self
+            .list_all(&format!(
+                "{}/providers/Microsoft.Authorization/roleAssignments?api-version={}&$filter=assignedTo('{}')",
+                resource_id.trim_end_matches('/'),
+                ROLE_ASSIGNMENTS_API_VERSION,
+                principal_id
+            ))
+            .await?;
+        Ok(raw.into_iter().map(|r| r.properties).collect())
+    }
+}
+
+/// Build a config populated from discovered accounts.
+///
+/// Single-service accounts take precedence over multi-service ones, then the
+/// first account found for each service wins. Keys are included only when
+/// present in `keys` (keyed by resource ID).
+pub fn config_from_accounts(
+    base: &Config,
+    accounts: &[CognitiveAccount],
+    keys: &HashMap<String, String>,
+) -> Config {
+    let mut config = base.clone();
+    config.services.clear();
+
+    let mut ordered: Vec<&CognitiveAccount> = accounts.iter().collect();
+    ordered.sort_by_key(|a| a.service_names().len());
+
+    for account in ordered {
+        for service in account.service_names() {
+            config
+                .services
+                .entry(service.to_string())
+                .or_insert_with(|| ServiceConfig {
+                    enabled: true,
+                    region: Some(account.location.clone()),
+                    endpoint: account
+                        .endpoint
+                        .as_ref()
+                        .map(|e| e.trim_end_matches('/').to_string()),
+                    api_key: keys.get(&account.id).cloned(),
+                    ..Default::default()
+                });
+        }
+    }
+
+    config
+}
+
+/// Check whether an IP matches an ARM IP rule (single address or IPv4 CIDR)
+pub fn ip_matches_rule(ip: IpAddr, rule: &str) -> bool {
+    let (addr, prefix) = match rule.split_once('/') {
+        Some((addr, prefix)) => (addr, prefix.parse::<u32>().ok()),
+        None => (rule, None),
+    };
+    let rule_ip: IpAddr = match addr.trim().parse() {
+        Ok(ip) => ip,
+        Err(_) => return false,
+    };
+
+    match (ip, rule_ip, prefix) {
+        (_, _, None) => ip == rule_ip,
+        (IpAddr::V4(ip), IpAddr::V4(net), Some(prefix)) if prefix <= 32 => {
+            let mask = if prefix == 0 {
+                0
+            } else {
+                u32::MAX << (32 - prefix)
+            };
+            u32::from(ip) & mask == u32::from(net) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Whether an address is in a private (RFC 1918 / unique local) range
+pub fn is_private_address(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => v4.is_private() || v4.is_loopback(),
+        IpAddr::V6(v6) => (v6.segments()[0] & 0xfe00) == 0xfc00 || v6.is_loopback(),
+    }
+}
+
+/// Whether an egress IP can reach an account through its public firewall
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum AllowlistStatus {
+    /// Public network access is disabled; only private endpoints work
+    PublicAccessDisabled,
+    /// The firewall allows all public traffic
+    Open,
+    /// Default action is Deny and the IP matches an IP rule
+    Allowed { rule: String },
+    /// Default action is Deny and the IP matches none of the IP rules
+    NotAllowed { rule_count: usize },
+}
+
+impl AllowlistStatus {
+    /// Whether traffic from the IP is expected to be accepted
+    pub fn is_allowed(&self) -> bool {
+        matches!(
+            self,
+            AllowlistStatus::Open | AllowlistStatus::Allowed { .. }
+        )
+    }
+}
+
+/// Check an egress IP against an account's public network access and IP rules
+pub fn check_allowlist(account: &CognitiveAccount, ip: IpAddr) -> AllowlistStatus {
+    let public_disabled = account
+        .public_network_access
+        .as_deref()
+        .map(|p| p.eq_ignore_ascii_case("Disabled"))
+        .unwrap_or(false);
+    if public_disabled {
+        return AllowlistStatus::PublicAccessDisabled;
+    }
+    match account
+        .network_acls
+        .as_ref()
+        .filter(|a| a.default_action.eq_ignore_ascii_case("Deny"))
+    {
+        None => AllowlistStatus::Open,
+        Some(acls) => match acls.ip_rules.iter().find(|r| ip_matches_rule(ip, r)) {
+            Some(rule) => AllowlistStatus::Allowed { rule: rule.clone() },
+            None => AllowlistStatus::NotAllowed {
+                rule_count: acls.ip_rules.len(),
+            },
+        },
+    }
+}
+
+/// Subscription ID in an ARM resource ID
+pub fn subscription_of(resource_id: &str) -> Option<&str> {
+    let mut parts = resource_id.trim_matches('/').split('/');
+    parts
+        .position(|p| p.eq_ignore_ascii_case("subscriptions"))
+        .and_then(|_| parts.next())
+        .filter(|id| !id.is_empty())
+}
+
+/// Split an account resource ID into (resource group, account name)
+fn resource_group_and_name(resource_id: &str) -> Option<(&str, &str)> {
+    let parts: Vec<&str> = resource_id.trim_matches('/').split('/').collect();
+    let group = parts
+        .iter()
+        .position(|p| p.eq_ignore_ascii_case("resourceGroups"))
+        .and_then(|i| parts.get(i + 1))?;
+    let name = parts.last()?;
+    Some((group, name))
+}
+
+/// Egress IP discovery result, optionally checked against an account's firewall
+#[derive(Debug, Clone, Serialize)]
+pub struct EgressCheck {
+    /// Public IP this machine egresses from
+    pub egress_ip: IpAddr,
+    /// Echo endpoint that reported the IP
+    pub echo_url: String,
+    /// Account the IP was checked against
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account: Option<CognitiveAccount>,
+    /// Firewall verdict for the IP
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowlist: Option<AllowlistStatus>,
+}
+
+/// Format an egress check for human-readable output
+pub fn format_egress_check(check: &EgressCheck, use_colors: bool) -> String {
+    use console::style;
+
+    let mut output = String::new();
+    output.push_str(&format!(
+        "\nEgress IP: {} (reported by {})\n",
+        if use_colors {
+            style(check.egress_ip.to_string()).bold().to_string()
+        } else {
+            check.egress_ip.to_string()
+        },
+        check.echo_url
+    ));
+
+    let (Some(account), Some(status)) = (&check.account, &check.allowlist) else {
+        output.push_str("\nPass --resource-id to check this IP against the resource's firewall.\n");
+        return output;
+    };
+
+    output.push_str(&format!(
+        "\nResource: {} ({}, {})\n",
+        account.name, account.kind, account.location
+    ));
+    let (mark, message) = match status {
+        AllowlistStatus::PublicAccessDisabled => (
+            false,
+            "Public network access is disabled; this IP cannot reach the resource. Connect \
+             through a private endpoint."
+                .to_string(),
+        ),
+        AllowlistStatus::Open => (
+            true,
+            "Firewall allows all networks; no IP rule is needed.".to_string(),
+        ),
+        AllowlistStatus::Allowed { rule } => (
+            true,
+            format!("{} is allowlisted by IP rule {}.", check.egress_ip, rule),
+        ),
+        AllowlistStatus::NotAllowed { rule_count } => (
+            false,
+            format!(
+                "{} is NOT in the {} allowlisted IP rule(s); requests will get HTTP 403.",
+                check.egress_ip, rule_count
+            ),
+        ),
+    };
+    let mark = match (mark, use_colors) {
+        (true, true) => style("\u{2713}").green().to_string(),
+        (false, true) => style("\u{2717}").red().to_string(),
+        (true, false) => "[OK]".to_string(),
+        (false, false) => "[FAIL]".to_string(),
+    };
+    output.push_str(&format!("  {} {}\n", mark, message));
+
+    if let AllowlistStatus::NotAllowed { .. } = status {
+        if let Some((group, name)) = resource_group_and_name(&account.id) {
+            output.push_str(&format!(
+                "\n  To allow it:\n    az cognitiveservices account network-rule add -g {} -n {} --ip-address {}\n",
+                group, name, check.egress_ip
+            ));
+        }
+        output.push_str(
+            "\n  If your network egresses through several IPs (NAT pool, proxy farm), each one \
+             must be allowlisted.\n",
+        );
+    }
+
+    output
+}
+
+/// Correlate an account's network configuration with observed test results.
+///
+/// `client_ip` is this machine's public egress IP (if known) and `resolved` the
+/// addresses the account endpoint resolved to from here.
+pub fn analyze_network_access(
+    account: &CognitiveAccount,
+    client_ip: Option<IpAddr>,
+    resolved: &[IpAddr],
+    report: &TestReport,
+) -> Vec<String> {
+    let mut findings = Vec::new();
+
+    let forbidden = report
+        .services
+        .iter()
+        .flat_map(|s| &s.results)
+        .filter(|r| !r.success && r.http_status == Some(403))
+        .count();
+    let explains = |msg: String| -> String {
+        if forbidden > 0 {
+            format!(
+                "{} This explains the {} HTTP 403 result(s).",
+                msg, forbidden
+            )
+        } else {
+            msg
+        }
+    };
+
+    let via_private_endpoint = !resolved.is_empty() && resolved.iter().all(is_private_address);
+    let resolved_list = resolved
+        .iter()
+        .map(|ip| ip.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let public_disabled = account
+        .public_network_access
+        .as_deref()
+        .map(|p| p.eq_ignore_ascii_case("Disabled"))
+        .unwrap_or(false);
+
+    if via_private_endpoint {
+        findings.push(format!(
+            "Endpoint resolves to private address(es) {}; traffic uses a private endpoint, so \
+             public IP firewall rules do not apply.",
+            resolved_list
+        ));
+    } else if public_disabled {
+        let mut msg = "Public network access is disabled on this resource".to_string();
+        if account.private_endpoint_connections > 0 && !resolved.is_empty() {
+            msg.push_str(&format!(
+                ", but the endpoint resolves to public address(es) {}. DNS is not pointing at \
+                 the private endpoint; check the privatelink.cognitiveservices.azure.com zone \
+                 and its VNet link.",
+                resolved_list
+            ));
+        } else {
+            msg.push_str("; connect through a private endpoint.");
+        }
+        findings.push(explains(msg));
+    } else if let Some(acls) = account
+        .network_acls
+        .as_ref()
+        .filter(|a| a.default_action.eq_ignore_ascii_case("Deny"))
+    {
+        match client_ip {
+            Some(ip) => match check_allowlist(account, ip) {
+                AllowlistStatus::Allowed { rule } => findings.push(format!(
+                    "Firewall default action is Deny; your public IP {} is allowlisted by rule {}.",
+                    ip, rule
+                )),
+                _ => findings.push(explains(format!(
+                    "Firewall default action is Deny and your public IP {} is not in the {} \
+                     allowlisted IP rule(s).",
+                    ip,
+                    acls.ip_rules.len()
+                ))),
+            },
+            None => findings.push(explains(
+                "Firewall default action is Deny; your public IP could not be determined to \
+                 compare against the IP rules."
+                    .to_string(),
+            )),
+        }
+        if !acls.virtual_network_rules.is_empty() {
+            findings.push(format!(
+                "{} virtual network rule(s) allow traffic from specific subnets via service \
+                 endpoints.",
+                acls.virtual_network_rules.len()
+            ));
+        }
+    } else if forbidden > 0 {
+        findings.push(format!(
+            "Network ACLs allow public traffic, so the {} HTTP 403 result(s) are likely an \
+             authorization (RBAC or key) problem rather than networking.",
+            forbidden
+        ));
+    }
+
+    findings
+}
+
+/// Correlate an account's auth configuration with observed test results
+pub fn analyze_auth_config(account: &CognitiveAccount, report: &TestReport) -> Vec<String> {
+    let auth_failures = report
+        .services
+        .iter()
+        .flat_map(|s| &s.results)
+        .filter(|r| !r.success && matches!(r.http_status, Some(401) | Some(403)))
+        .count();
+
+    if account.disable_local_auth && auth_failures > 0 {
+        vec![format!(
+            "Confirmed via ARM: key-based auth is disabled on {} (disableLocalAuth=true). \
+             The {} HTTP 401/403 result(s) are expected with an API key; use Entra ID auth.",
+            account.name, auth_failures
+        )]
+    } else {
+        vec![]
+    }
+}
+
+/// Check that the identity behind the bearer token holds a data-plane role
+/// for each of `services` on the account.
+///
+/// `principal_id` is the token's object ID and `principal` how to name it.
+/// Missing roles are reported with the `az` command that grants them.
+pub fn analyze_role_assignments(
+    account: &CognitiveAccount,
+    principal_id: &str,
+    principal: &str,
+    assignments: &[RoleAssignment],
+    services: &[&str],
+    report: &TestReport,
+) -> Vec<String> {
+    let auth_failures = report
+        .services
+        .iter()
+        .flat_map(|s| &s.results)
+        .filter(|r| !r.success && matches!(r.http_status, Some(401) | Some(403)))
+        .count();
+
+    let roles: Vec<_> = assignments.iter().filter_map(|a| a.builtin()).collect();
+    let custom = assignments.len() - roles.len();
+    let covers = |service: &&str| {
+        roles.iter().any(|(_, access)| match access {
+            RoleAccess::AllServices => true,
+            RoleAccess::Services(list) => list.contains(service),
+            RoleAccess::ManagementOnly => false,
+        })
+    };
+    let missing: Vec<&str> = services.iter().filter(|s| !covers(s)).copied().collect();
+
+    let mut held: Vec<&str> = roles.iter().map(|(name, _)| *name).collect();
+    held.sort_unstable();
+    held.dedup();
+    let held = if held.is_empty() {
+        "no built-in roles".to_string()
+    } else {
+        held.join(", ")
+    };
+
+    if missing.is_empty() {
+        let mut msg = format!(
+            "Confirmed via ARM: {} holds {} on {}.",
+            principal, held, account.name
+        );
+        if auth_failures > 0 {
+            msg.push_str(&format!(
+                " The {} HTTP 401/403 result(s) are not a missing role; new role assignments \
+                 can take up to 10 minutes to apply.",
+                auth_failures
+            ));
+        }
+        return vec![msg];
+    }
+
+    let role = if missing.iter().all(|s| *s == "openai") {
+        OPENAI_USER
+    } else {
+        COGNITIVE_SERVICES_USER
+    };
+    let mut msg = format!(
+        "{} has no data-plane role on {} for {} (holds {}",
+        principal,
+        account.name,
+        missing.join(", "),
+        held
+    );
+    if custom > 0 {
+        msg.push_str(&format!(", plus {} custom role(s) not checked", custom));
+    }
+    msg.push(')');
+    if roles
+        .iter()
+        .any(|(_, access)| matches!(access, RoleAccess::ManagementOnly))
+    {
+        msg.push_str("; management roles such as Owner and Contributor don't grant data access");
+    }
+    if auth_failures > 0 {
+        msg.push_str(&format!(
+            ". This explains the {} HTTP 401/403 result(s)",
+            auth_failures
+        ));
+    }
+    msg.push_str(&format!(
+        ". Grant it with: az role assignment create --assignee {} --role \"{}\" --scope {}",
+        principal_id,
+        role,
+        account.id.trim_end_matches('/')
+    ));
+    vec![msg]
+}
+
+/// Format discovered accounts for display
+pub fn format_accounts(accounts: &[CognitiveAccount]) -> String {
+    use console::style;
+
+    let mut output = String::new();
+    output.push_str("\nDiscovered Cognitive Services Accounts\n");
+    output.push_str("======================================\n\n");
+
+    if accounts.is_empty() {
+        output.push_str("  No accounts found.\n");
+        return output;
+    }
+
+    for account in accounts {
+        output.push_str(&format!(
+            "{} ({}, {}, {})\n",
+            style(&account.name).bold(),
+            account.kind,
+            account.location,
+            account.sku.as_deref().unwrap_or("unknown SKU")
+        ));
+        output.push_str(&format!(
+            "  Endpoint: {}\n",
+            account.endpoint.as_deref().unwrap_or("-")
+        ));
+        output.push_str(&format!(
+            "  Public network access: {}\n",
+            account
+                .public_network_access
+                .as_deref()
+                .unwrap_or("Enabled")
+        ));
+        if let Some(acls) = &account.network_acls {
+            let default_action = if acls.default_action.is_empty() {
+                "Allow"
+            } else {
+                &acls.default_action
+            };
+            output.push_str(&format!(
+                "  Network ACLs: default {} ({} IP rules, {} VNet rules)\n",
+                default_action,
+                acls.ip_rules.len(),
+                acls.virtual_network_rules.len()
+            ));
+        }
+        if account.private_endpoint_connections > 0 {
+            output.push_str(&format!(
+                "  Private endpoints: {}\n",
+                account.private_endpoint_connections
+            ));
+        }
+        if account.disable_local_auth {
+            output.push_str(&format!(
+                "  {}\n",
+                style("Key auth disabled (disableLocalAuth=true)").yellow()
+            ));
+        }
+        let services = account.service_names();
+        if !services.is_empty() {
+            output.push_str(&format!(
+                "  Testable services: {}\n",
+                style(services.join(", ")).cyan()
+            ));
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_account() -> serde_json::Value {
+        serde_json::json!({
+            "id": "/subscriptions/sub/resourceGroups/rg/providers/Microsoft.CognitiveServices/accounts/speech1",
+            "name": "speech1",
+            "kind": "SpeechServices",
+            "location": "eastus",
+            "sku": {"name": "S0"},
+            "properties": {
+                "endpoint": "https://speech1.cognitiveservices.azure.com/",
+                "publicNetworkAccess": "Enabled",
+                "disableLocalAuth": true,
+                "networkAcls": {
+                    "defaultAction": "Deny",
+                    "ipRules": [{"value": "203.0.113.0/24"}],
+                    "virtualNetworkRules": []
+                },
+                "privateEndpointConnections": [{"id": "pe1"}]
+            }
+        })
+    }
+
+    #[test]
+    fn test_ip_matches_rule() {
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        assert!(ip_matches_rule(ip, "203.0.113.0/24"));
+        assert!(ip_matches_rule(ip, "203.0.113.7"));
+        assert!(!ip_matches_rule(ip, "198.51.100.0/24"));
+        assert!(ip_matches_rule(ip, "0.0.0.0/0"));
+        assert!(!ip_matches_rule(ip, "not-an-ip"));
+    }
+
+    #[test]
+    fn test_analyze_network_access_deny_not_allowlisted() {
+        use crate::services::{ServiceTestResults, TestResult};
+
+        let mut account: CognitiveAccount = serde_json::from_value::<RawAccount>(sample_account())
+            .unwrap()
+            .into();
+        account.private_endpoint_connections = 0;
+        let report = TestReport::new(vec![ServiceTestResults {
+            service_name: "Speech".to_string(),
+            endpoint: "https://speech1.cognitiveservices.azure.com".to_string(),
+            region: None,
+            target: None,
+            results: vec![
+                TestResult::failure("tts", "TTS", 10, "Forbidden".to_string())
+                    .with_http_status(403),
+            ],
+            total_duration_ms: 10,
+        }]);
+
+        let findings = analyze_network_access(
+            &account,
+            Some("198.51.100.1".parse().unwrap()),
+            &["20.1.2.3".parse().unwrap()],
+            &report,
+        );
+        assert!(findings[0].contains("198.51.100.1 is not in the 1 allowlisted"));
+        assert!(findings[0].contains("403"));
+
+        let findings =
+            analyze_network_access(&account, None, &["10.0.0.5".parse().unwrap()], &report);
+        assert!(findings[0].contains("private endpoint"));
+    }
+
+    #[test]
+    fn test_analyze_role_assignments() {
+        let account: CognitiveAccount = serde_json::from_value::<RawAccount>(sample_account())
+            .unwrap()
+            .into();
+        let assignments: Vec<RoleAssignment> = serde_json::from_value::<
+            ListResponse<RawRoleAssignment>,
+        >(serde_json::json!({"value": [
+            {"properties": {
+                "roleDefinitionId": "/subscriptions/sub/providers/Microsoft.Authorization/roleDefinitions/b24988ac-6180-42a0-ab88-20f7382dd24c",
+                "scope": "/subscriptions/sub/resourceGroups/rg"
+            }},
+            {"properties": {
+                "roleDefinitionId": "/subscriptions/sub/providers/Microsoft.Authorization/roleDefinitions/5e0bd9bd-7b93-4f28-af87-19fc36ad61bd",
+                "scope": account.id
+            }}
+        ]}))
+        .unwrap()
+        .value
+        .into_iter()
+        .map(|r| r.properties)
+        .collect();
+        let report = TestReport::new(vec![]);
+
+        let findings = analyze_role_assignments(
+            &account,
+            "oid-1",
+            "ana@contoso.com",
+            &assignments,
+            &["openai"],
+            &report,
+        );
+        assert!(
+            findings[0].starts_with("Confirmed via ARM"),
+            "{}",
+            findings[0]
+        );
+
+        let findings = analyze_role_assignments(
+            &account,
+            "oid-1",
+            "ana@contoso.com",
+            &assignments,
+            &["speech", "openai"],
+            &report,
+        );
+        assert!(
+            findings[0].contains("for speech (holds Cognitive Services OpenAI User, Contributor"),
+            "{}",
+            findings[0]
+        );
+        assert!(findings[0].ends_with(&format!(
+            "az role assignment create --assignee oid-1 --role \"Cognitive Services User\" --scope {}",
+            account.id
+        )));
+    }
+
+    #[test]
+    fn test_check_allowlist() {
+        let mut account: CognitiveAccount = serde_json::from_value::<RawAccount>(sample_account())
+            .unwrap()
+            .into();
+        assert_eq!(
+            check_allowlist(&account, "203.0.113.7".parse().unwrap()),
+            AllowlistStatus::Allowed {
+                rule: "203.0.113.0/24".to_string()
+            }
+        );
+        let blocked = check_allowlist(&account, "198.51.100.1".parse().unwrap());
+        assert_eq!(blocked, AllowlistStatus::NotAllowed { rule_count: 1 });
+        assert!(!blocked.is_allowed());
+
+        let check = EgressCheck {
+            egress_ip: "198.51.100.1".parse().unwrap(),
+            echo_url: "https://api.ipify.org".to_string(),
+            account: Some(account.clone()),
+            allowlist: Some(blocked),
+        };
+        let output = format_egress_check(&check, false);
+        assert!(output.contains("[FAIL] 198.51.100.1 is NOT in the 1 allowlisted"));
+        assert!(output.contains("network-rule add -g rg -n speech1 --ip-address 198.51.100.1"));
+
+        account.public_network_access = Some("Disabled".to_string());
+        assert_eq!(
+            check_allowlist(&account, "203.0.113.7".parse().unwrap()),
+            AllowlistStatus::PublicAccessDisabled
+        );
+    }
+
+    #[test]
+    fn test_parse_account() {
+        let raw: RawAccount = serde_json::from_value(sample_account()).unwrap();
+        let account = CognitiveAccount::from(raw);
+        assert_eq!(account.sku.as_deref(), Some("S0"));
+        assert!(account.disable_local_auth);
+        assert_eq!(account.private_endpoint_connections, 1);
+        let acls = account.network_acls.unwrap();
+        assert_eq!(acls.default_action, "Deny");
+        assert_eq!(acls.ip_rules, vec!["203.0.113.0/24".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_usages_and_deployments() {
+        let usage: QuotaUsage = serde_json::from_value::<RawUsage>(serde_json::json!({
+            "name": {"value": "OpenAI.Standard.gpt-4o", "localizedValue": "Tokens Per Minute (thousands) - GPT-4o"},
+            "currentValue": 430,
+            "limit": 450,
+            "unit": "Count"
+        }))
+        .unwrap()
+        .into();
+        assert_eq!(usage.display_name, "Tokens Per Minute (thousands) - GPT-4o");
+        assert!(usage.fraction_used().unwrap() > 0.95);
+
+        let deployment: Deployment = serde_json::from_value::<RawDeployment>(serde_json::json!({
+            "name": "chat",
+            "sku": {"name": "Standard", "capacity": 30},
+            "properties": {
+                "model": {"format": "OpenAI", "name": "gpt-4o", "version": "2024-08-06"},
+                "rateLimits": [
+                    {"key": "request", "renewalPeriod": 10, "count": 30},
+                    {"key": "token", "renewalPeriod": 60, "count": 30000}
+                ]
+            }
+        }))
+        .unwrap()
+        .into();
+        assert_eq!(deployment.model.as_deref(), Some("gpt-4o"));
+        assert_eq!(deployment.requests_per_minute, Some(180));
+        assert_eq!(deployment.tokens_per_minute, Some(30000));
+        assert_eq!(
+            subscription_of("/subscriptions/sub-1/resourceGroups/rg/providers/x"),
+            Some("sub-1")
+        );
+    }
+
+    #[test]
+    fn test_config_from_accounts_prefers_single_service() {
+        let speech: CognitiveAccount = serde_json::from_value::<RawAccount>(sample_account())
+            .unwrap()
+            .into();
+        let mut multi = speech.clone();
+        multi.id = "multi".to_string();
+        multi.kind = "CognitiveServices".to_string();
+        multi.location = "westus".to_string();
+
+        let mut keys = HashMap::new();
+        keys.insert(speech.id.clone(), "key".to_string());
+
+        let config = config_from_accounts(&Config::default_config(), &[multi, speech], &keys);
+        assert_eq!(config.services.len(), 5);
+        assert_eq!(config.services["speech"].region.as_deref(), Some("eastus"));
+        assert_eq!(config.services["speech"].api_key.as_deref(), Some("key"));
+        assert_eq!(config.services["vision"].region.as_deref(), Some("westus"));
+        assert_eq!(
+            config.services["speech"].endpoint.as_deref(),
+            Some("https://speech1.cognitiveservices.azure.com")
+        );
+    }
+}
@@ -0,0 +1,111 @@
+//! Scenario output saved to disk (`test --save-artifacts <dir>`)
+//!
+//! Scenarios that get real output back (synthesized audio, transcripts,
+//! Vision analysis, Document Intelligence results) normally count the bytes
+//! and drop them. With `--save-artifacts`, that output is written to a
+//! directory under stable names, `<service>-<scenario>.<ext>`, so the voice
+//! of a region can be listened to and results compared between runs. Region
+//! sweeps and named targets get a subdirectory per region and target.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::error::Result;
+
+/// Directory that artifacts of a run (or one region or target of it) are saved in
+#[derive(Debug, Clone)]
+pub struct ArtifactDir {
+    dir: PathBuf,
+    /// Files saved across the whole run, shared by subdirectories
+    saved: Arc<AtomicUsize>,
+}
+
+impl ArtifactDir {
+    /// Create the artifact directory
+    pub fn create(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            saved: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    /// Subdirectory for one region or target, created when the first file is saved
+    pub fn subdir(&self, name: &str) -> Self {
+        Self {
+            dir: self.dir.join(sanitize(name)),
+            saved: self.saved.clone(),
+        }
+    }
+
+    /// Number of files saved so far, in this directory and its subdirectories
+    pub fn saved(&self) -> usize {
+        self.saved.load(Ordering::Relaxed)
+    }
+
+    /// Stable file name for a scenario's artifact
+    pub fn file_name(service: &str, scenario: &str, extension: &str) -> String {
+        format!("{}-{}.{}", sanitize(service), sanitize(scenario), extension)
+    }
+
+    /// Write a scenario's artifact, replacing the one from an earlier run
+    pub fn save(
+        &self,
+        service: &str,
+        scenario: &str,
+        extension: &str,
+        data: &[u8],
+    ) -> Result<PathBuf> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(Self::file_name(service, scenario, extension));
+        std::fs::write(&path, data)?;
+        self.saved.fetch_add(1, Ordering::Relaxed);
+        Ok(path)
+    }
+}
+
+/// Keep names usable as file names on every platform
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_artifacts() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().join("artifacts");
+        let artifacts = ArtifactDir::create(&dir).unwrap();
+        let path = artifacts.save("speech", "tts", "mp3", b"ID3").unwrap();
+        assert_eq!(path, dir.join("speech-tts.mp3"));
+
+        // A second run overwrites the same file
+        artifacts
+            .save("speech", "tts", "mp3", b"ID3 again")
+            .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"ID3 again");
+
+        let target = artifacts.subdir("eastus").subdir("prod/west");
+        let path = target
+            .save("vision", "analyze_image", "json", b"{}")
+            .unwrap();
+        assert_eq!(
+            path,
+            dir.join("eastus")
+                .join("prod_west")
+                .join("vision-analyze_image.json")
+        );
+        assert_eq!(artifacts.saved(), 3);
+    }
+}
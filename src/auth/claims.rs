@@ -0,0 +1,391 @@
+use crate::config::Cloud;
+use crate::error::{AppError, Result};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Audience of tokens issued by the Cognitive Services token service (STS)
+const STS_AUDIENCE: &str = "urn:ms.cognitiveservices";
+
+/// Tolerated difference between the local clock and the token issuer's
+pub const CLOCK_SKEW_SECS: i64 = 300;
+
+/// Audiences users commonly pass by mistake, and what they are for
+const KNOWN_AUDIENCES: &[(&str, &str)] = &[
+    ("https://management.azure.com", "Azure Resource Manager"),
+    (
+        "https://management.core.windows.net",
+        "Azure Resource Manager",
+    ),
+    (
+        "https://management.chinacloudapi.cn",
+        "Azure Resource Manager",
+    ),
+    ("https://graph.microsoft.com", "Microsoft Graph"),
+    ("00000003-0000-0000-c000-000000000000", "Microsoft Graph"),
+    ("https://vault.azure.net", "Key Vault"),
+    ("https://storage.azure.com", "Azure Storage"),
+];
+
+/// Claims of a bearer token (JWT), decoded locally without verifying the signature
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TokenClaims {
+    /// Audience: the resource the token was issued for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+    /// Issuer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    /// Tenant that issued the token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tid: Option<String>,
+    /// Application the token was requested by (v1 tokens)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub appid: Option<String>,
+    /// Application the token was requested by (v2 tokens)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub azp: Option<String>,
+    /// Object id of the user, service principal, or managed identity
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oid: Option<String>,
+    /// Signed-in user
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upn: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preferred_username: Option<String>,
+    /// Delegated permissions (space-separated)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scp: Option<String>,
+    /// Application roles
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+    /// Issued at, in seconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat: Option<i64>,
+    /// Start of validity, in seconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nbf: Option<i64>,
+    /// Expiry, in seconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exp: Option<i64>,
+}
+
+/// Why a token won't be accepted by Azure AI services
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenProblem {
+    Expired(DateTime<Utc>),
+    NotYetValid(DateTime<Utc>),
+    /// Issued for another resource (the audience, if any)
+    WrongAudience(Option<String>),
+}
+
+impl std::fmt::Display for TokenProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenProblem::Expired(at) => write!(
+                f,
+                "expired at {} ({} minutes ago)",
+                at.format("%Y-%m-%d %H:%M:%S UTC"),
+                (Utc::now() - *at).num_minutes()
+            ),
+            TokenProblem::NotYetValid(at) => {
+                write!(
+                    f,
+                    "isn't valid until {}",
+                    at.format("%Y-%m-%d %H:%M:%S UTC")
+                )
+            }
+            TokenProblem::WrongAudience(None) => write!(f, "has no audience"),
+            TokenProblem::WrongAudience(Some(aud)) => {
+                let normalized = aud.trim_end_matches('/').to_lowercase();
+                match KNOWN_AUDIENCES
+                    .iter()
+                    .find(|(known, _)| *known == normalized)
+                {
+                    Some((_, name)) => write!(f, "is for {} ('{}')", name, aud),
+                    None => write!(f, "is for '{}'", aud),
+                }
+                .and_then(|_| write!(f, ", not for Azure AI services"))
+            }
+        }
+    }
+}
+
+impl TokenProblem {
+    /// How to get a token that works
+    pub fn hint(&self, cloud: Cloud) -> String {
+        let resource = cognitive_resource(cloud);
+        match self {
+            TokenProblem::Expired(_) => format!(
+                "Get a new token, e.g. 'az account get-access-token --resource {}', or run 'login' again.",
+                resource
+            ),
+            TokenProblem::NotYetValid(_) => {
+                "The local clock is behind the token issuer's; sync the system clock (NTP).".to_string()
+            }
+            TokenProblem::WrongAudience(_) => format!(
+                "Request the token for {}, e.g. 'az account get-access-token --resource {}'.",
+                resource, resource
+            ),
+        }
+    }
+}
+
+/// Resource (token audience) of Azure AI services in `cloud`
+fn cognitive_resource(cloud: Cloud) -> &'static str {
+    cloud.cognitive_scope().trim_end_matches("/.default")
+}
+
+fn timestamp(secs: Option<i64>) -> Option<DateTime<Utc>> {
+    secs.and_then(|secs| DateTime::from_timestamp(secs, 0))
+}
+
+impl TokenClaims {
+    /// Decode the payload of a JWT (`header.payload.signature`)
+    pub fn decode(token: &str) -> Result<Self> {
+        let mut parts = token.trim().split('.');
+        let payload = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(_), Some(payload), Some(_), None) => payload,
+            _ => {
+                return Err(AppError::InvalidBearerToken(
+                    "Token is not a JWT (expected three dot-separated parts)".to_string(),
+                ))
+            }
+        };
+        let json = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(payload.trim_end_matches('='))
+            .map_err(|e| {
+                AppError::InvalidBearerToken(format!("Token payload isn't base64: {}", e))
+            })?;
+        serde_json::from_slice(&json)
+            .map_err(|e| AppError::InvalidBearerToken(format!("Token payload isn't JSON: {}", e)))
+    }
+
+    /// When the token expires
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        timestamp(self.exp)
+    }
+
+    /// When the token becomes valid
+    pub fn not_before(&self) -> Option<DateTime<Utc>> {
+        timestamp(self.nbf)
+    }
+
+    /// Application the token was requested by
+    pub fn app_id(&self) -> Option<&str> {
+        self.appid.as_deref().or(self.azp.as_deref())
+    }
+
+    /// Signed-in user, or the object id of an application identity
+    pub fn identity(&self) -> Option<&str> {
+        self.upn
+            .as_deref()
+            .or(self.preferred_username.as_deref())
+            .or(self.oid.as_deref())
+    }
+
+    /// Whether the token is for Azure AI services in `cloud` (an Entra token
+    /// for Cognitive Services, or one issued by the token service)
+    pub fn is_cognitive_audience(&self, cloud: Cloud) -> bool {
+        let resource = cognitive_resource(cloud);
+        self.aud
+            .as_deref()
+            .map(|aud| aud.trim_end_matches('/'))
+            .is_some_and(|aud| aud.eq_ignore_ascii_case(resource) || aud == STS_AUDIENCE)
+    }
+
+    /// Reasons Azure AI services in `cloud` would reject the token at `now`
+    pub fn problems(&self, cloud: Cloud, now: DateTime<Utc>) -> Vec<TokenProblem> {
+        let mut problems = Vec::new();
+        if let Some(expires_at) = self.expires_at().filter(|at| *at <= now) {
+            problems.push(TokenProblem::Expired(expires_at));
+        }
+        if let Some(not_before) = self
+            .not_before()
+            .filter(|at| (*at - now).num_seconds() > CLOCK_SKEW_SECS)
+        {
+            problems.push(TokenProblem::NotYetValid(not_before));
+        }
+        if !self.is_cognitive_audience(cloud) {
+            problems.push(TokenProblem::WrongAudience(self.aud.clone()));
+        }
+        problems
+    }
+
+    /// Claims worth showing, one `Label: value` line each
+    pub fn summary_lines(&self, now: DateTime<Utc>) -> Vec<String> {
+        let format_time = |at: DateTime<Utc>| at.format("%Y-%m-%d %H:%M:%S UTC").to_string();
+        let mut lines = Vec::new();
+        let mut push = |label: &str, value: Option<String>| {
+            if let Some(value) = value {
+                lines.push(format!("{:<11} {}", format!("{}:", label), value));
+            }
+        };
+        push("Audience", self.aud.clone());
+        push("Tenant", self.tid.clone());
+        push("Issuer", self.iss.clone());
+        push("App ID", self.app_id().map(str::to_string));
+        push("Identity", self.identity().map(str::to_string));
+        push("Scopes", self.scp.clone());
+        push(
+            "Roles",
+            (!self.roles.is_empty()).then(|| self.roles.join(", ")),
+        );
+        push("Issued", timestamp(self.iat).map(format_time));
+        push("Not before", self.not_before().map(format_time));
+        push(
+            "Expires",
+            self.expires_at().map(|at| {
+                let minutes = (at - now).num_minutes();
+                if at > now {
+                    format!("{} (in {} minutes)", format_time(at), minutes)
+                } else {
+                    format!("{} ({} minutes ago)", format_time(at), -minutes)
+                }
+            }),
+        );
+        lines
+    }
+}
+
+/// A token's claims and the problems Azure AI services would have with it (`token inspect`)
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenInspection {
+    pub claims: TokenClaims,
+    /// Minutes until the token expires (negative once expired)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_in_minutes: Option<i64>,
+    pub warnings: Vec<TokenWarning>,
+}
+
+/// A problem with a token and how to fix it
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenWarning {
+    pub message: String,
+    pub hint: String,
+}
+
+impl TokenInspection {
+    /// Decode `token` and check it for Azure AI services in `cloud`
+    pub fn new(token: &str, cloud: Cloud) -> Result<Self> {
+        let claims = TokenClaims::decode(token)?;
+        let now = Utc::now();
+        let warnings = claims
+            .problems(cloud, now)
+            .into_iter()
+            .map(|problem| TokenWarning {
+                message: format!("Token {}", problem),
+                hint: problem.hint(cloud),
+            })
+            .collect();
+        Ok(Self {
+            expires_in_minutes: claims.expires_at().map(|at| (at - now).num_minutes()),
+            claims,
+            warnings,
+        })
+    }
+
+    /// Whether Azure AI services should accept the token
+    pub fn is_usable(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+/// Render a token inspection for the console
+pub fn format_token_inspection(inspection: &TokenInspection, use_colors: bool) -> String {
+    use console::style;
+
+    let mut output = String::from("\nToken Claims\n");
+    for line in inspection.claims.summary_lines(Utc::now()) {
+        output.push_str(&format!("  {}\n", line));
+    }
+    output.push('\n');
+    if inspection.is_usable() {
+        let mark = if use_colors {
+            style("\u{2713}").green().to_string()
+        } else {
+            "[OK]".to_string()
+        };
+        output.push_str(&format!(
+            "{} Token is for Azure AI services and unexpired\n",
+            mark
+        ));
+    }
+    for warning in &inspection.warnings {
+        if use_colors {
+            output.push_str(&format!(
+                "{} {}\n  {} {}\n",
+                style("!").yellow(),
+                style(&warning.message).yellow(),
+                style("Hint:").yellow(),
+                style(&warning.hint).dim()
+            ));
+        } else {
+            output.push_str(&format!(
+                "! {}\n  Hint: {}\n",
+                warning.message, warning.hint
+            ));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jwt(claims: serde_json::Value) -> String {
+        let engine = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        format!(
+            "{}.{}.sig",
+            engine.encode(r#"{"alg":"RS256","typ":"JWT"}"#),
+            engine.encode(claims.to_string())
+        )
+    }
+
+    #[test]
+    fn test_decode_claims() {
+        let token = jwt(serde_json::json!({
+            "aud": "https://cognitiveservices.azure.com/",
+            "tid": "contoso-tenant",
+            "appid": "04b07795-8ddb-461a-bbee-02f9e1bf7b46",
+            "upn": "user@contoso.com",
+            "scp": "user_impersonation",
+            "exp": 1_900_000_000,
+            "ignored": true
+        }));
+        let claims = TokenClaims::decode(&token).unwrap();
+        assert_eq!(claims.tid.as_deref(), Some("contoso-tenant"));
+        assert_eq!(claims.expires_at().unwrap().timestamp(), 1_900_000_000);
+        assert!(claims.not_before().is_none());
+        assert_eq!(claims.identity(), Some("user@contoso.com"));
+        assert!(claims.is_cognitive_audience(Cloud::Global));
+        assert!(!claims.is_cognitive_audience(Cloud::China));
+
+        let now = DateTime::from_timestamp(1_899_996_400, 0).unwrap();
+        assert!(claims.problems(Cloud::Global, now).is_empty());
+        let lines = claims.summary_lines(now);
+        assert!(
+            lines.contains(&"Scopes:     user_impersonation".to_string()),
+            "{:?}",
+            lines
+        );
+        assert!(
+            lines.last().unwrap().ends_with("(in 60 minutes)"),
+            "{:?}",
+            lines
+        );
+
+        // An expired Resource Manager token, the usual mistake
+        let arm = jwt(serde_json::json!({"aud": "https://management.azure.com/", "exp": 1}));
+        let arm = TokenClaims::decode(&arm).unwrap();
+        assert!(!arm.is_cognitive_audience(Cloud::Global));
+        let problems = arm.problems(Cloud::Global, now);
+        assert!(matches!(problems[0], TokenProblem::Expired(_)));
+        assert_eq!(
+            problems[1].to_string(),
+            "is for Azure Resource Manager ('https://management.azure.com/'), not for Azure AI services"
+        );
+        assert!(TokenClaims::decode("opaque-token").is_err());
+    }
+}
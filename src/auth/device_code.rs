@@ -58,6 +58,12 @@ impl DeviceCodeAuth {
         self
     }
 
+    /// Request a token for another scope (e.g. Key Vault) instead of Cognitive Services
+    pub fn with_scope(mut self, scope: &str) -> Self {
+        self.scope = scope.to_string();
+        self
+    }
+
     /// Authenticate and return token with metadata.
     /// This is the public API for the login command.
     pub async fn authenticate(&self) -> Result<TokenResult> {
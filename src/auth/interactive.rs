@@ -53,6 +53,12 @@ impl InteractiveAuth {
         self
     }
 
+    /// Request a token for another scope (e.g. Key Vault) instead of Cognitive Services
+    pub fn with_scope(mut self, scope: &str) -> Self {
+        self.scope = scope.to_string();
+        self
+    }
+
     /// Authenticate and return token with metadata.
     /// This is the public API for the login command.
     pub async fn authenticate(&self) -> Result<TokenResult> {
@@ -175,7 +181,7 @@ impl InteractiveAuth {
         let login_endpoint = self.cloud.login_endpoint();
         let token_url = format!("{}/{}/oauth2/v2.0/token", login_endpoint, self.tenant_id);
 
-        let client = reqwest::Client::builder()
+        let client = crate::network::configure_client(reqwest::Client::builder())
             .timeout(Duration::from_secs(30))
             .build()
             .map_err(|e| AppError::Auth(format!("Failed to create HTTP client: {}", e)))?;
@@ -1,20 +1,78 @@
-use super::{AuthProvider, Credentials};
+use super::{AuthProvider, Credentials, TokenClaims};
 use crate::config::Cloud;
 use crate::error::{AppError, Result};
 use async_trait::async_trait;
+use chrono::Utc;
 use reqwest::Client;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::env;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+/// IMDS token endpoint on Azure VMs and VM scale sets
+const IMDS_TOKEN_ENDPOINT: &str = "http://169.254.169.254/metadata/identity/oauth2/token";
 
 /// Managed Identity endpoint types
 enum ManagedIdentityEndpoint {
     /// App Service / Container Apps (has identity endpoint and header)
     AppService { endpoint: String, header: String },
+    /// Azure Arc-enabled server (local himds agent, challenge-response)
+    AzureArc { endpoint: String },
     /// Virtual Machine (uses IMDS)
     VirtualMachine,
 }
 
+/// Where the managed identity token comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ManagedIdentityEnvironment {
+    /// Azure VM or scale set (IMDS at 169.254.169.254)
+    Imds,
+    /// App Service or Functions
+    AppService,
+    /// Container Apps
+    ContainerApps,
+    /// Azure Arc-enabled server (himds agent)
+    AzureArc,
+}
+
+impl std::fmt::Display for ManagedIdentityEnvironment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Imds => "Azure VM (IMDS)",
+            Self::AppService => "App Service",
+            Self::ContainerApps => "Container Apps",
+            Self::AzureArc => "Azure Arc (himds)",
+        })
+    }
+}
+
+/// Outcome of a managed identity token request, for `login --verbose`
+#[derive(Debug, Clone, Serialize)]
+pub struct ManagedIdentityDiagnostics {
+    pub environment: ManagedIdentityEnvironment,
+    /// Token endpoint that was called
+    pub endpoint: String,
+    /// Time to get the token (or the error)
+    pub latency_ms: u64,
+    /// User-assigned identity requested with a client ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requested_client_id: Option<String>,
+    /// Whether the token is for the requested user-assigned identity (unset
+    /// when none was requested or no token was issued)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id_honored: Option<bool>,
+    /// Object ID of the identity (the principal for role assignments)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub object_id: Option<String>,
+    /// Client (application) ID of the identity
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_in_minutes: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
 /// Response from managed identity token endpoint
 #[derive(Deserialize)]
 struct ManagedIdentityResponse {
@@ -60,23 +118,91 @@ impl ManagedIdentityAuth {
         })
     }
 
+    /// Request a token for another resource (e.g. Key Vault) instead of Cognitive Services
+    pub fn with_resource(mut self, resource: &str) -> Self {
+        self.resource = resource.to_string();
+        self
+    }
+
     /// Detect which managed identity endpoint to use based on environment variables
     fn detect_endpoint() -> Result<ManagedIdentityEndpoint> {
+        Ok(Self::endpoint_from(|name| env::var(name).ok()))
+    }
+
+    fn endpoint_from(var: impl Fn(&str) -> Option<String>) -> ManagedIdentityEndpoint {
         // Check for App Service / Container Apps identity
-        if let (Ok(endpoint), Ok(header)) =
-            (env::var("IDENTITY_ENDPOINT"), env::var("IDENTITY_HEADER"))
-        {
-            return Ok(ManagedIdentityEndpoint::AppService { endpoint, header });
+        if let (Some(endpoint), Some(header)) = (var("IDENTITY_ENDPOINT"), var("IDENTITY_HEADER")) {
+            return ManagedIdentityEndpoint::AppService { endpoint, header };
         }
 
-        // Check for legacy MSI endpoint (older VM setup)
-        if env::var("MSI_ENDPOINT").is_ok() {
-            return Ok(ManagedIdentityEndpoint::VirtualMachine);
+        // Azure Arc sets the identity endpoint along with IMDS_ENDPOINT, but no header
+        if let (Some(endpoint), Some(_)) = (var("IDENTITY_ENDPOINT"), var("IMDS_ENDPOINT")) {
+            return ManagedIdentityEndpoint::AzureArc { endpoint };
         }
 
-        // Default to IMDS (VM)
+        // Legacy MSI_ENDPOINT (older VM setup) and plain VMs both use IMDS.
         // Note: This will fail gracefully if not running on Azure VM
-        Ok(ManagedIdentityEndpoint::VirtualMachine)
+        ManagedIdentityEndpoint::VirtualMachine
+    }
+
+    /// The detected managed identity environment
+    pub fn environment(&self) -> ManagedIdentityEnvironment {
+        match &self.endpoint {
+            // Container Apps uses the App Service protocol
+            ManagedIdentityEndpoint::AppService { .. }
+                if env::var("CONTAINER_APP_NAME").is_ok() =>
+            {
+                ManagedIdentityEnvironment::ContainerApps
+            }
+            ManagedIdentityEndpoint::AppService { .. } => ManagedIdentityEnvironment::AppService,
+            ManagedIdentityEndpoint::AzureArc { .. } => ManagedIdentityEnvironment::AzureArc,
+            ManagedIdentityEndpoint::VirtualMachine => ManagedIdentityEnvironment::Imds,
+        }
+    }
+
+    fn token_endpoint(&self) -> &str {
+        match &self.endpoint {
+            ManagedIdentityEndpoint::AppService { endpoint, .. }
+            | ManagedIdentityEndpoint::AzureArc { endpoint } => endpoint,
+            ManagedIdentityEndpoint::VirtualMachine => IMDS_TOKEN_ENDPOINT,
+        }
+    }
+
+    /// Fetch a token and report the environment, latency, and which identity
+    /// the token was issued to
+    pub async fn diagnose(&self) -> (ManagedIdentityDiagnostics, Result<String>) {
+        let started = Instant::now();
+        let token = self.fetch_token().await;
+        let mut diagnostics = ManagedIdentityDiagnostics {
+            environment: self.environment(),
+            endpoint: self.token_endpoint().to_string(),
+            latency_ms: started.elapsed().as_millis() as u64,
+            requested_client_id: self.user_assigned_client_id.clone(),
+            client_id_honored: None,
+            object_id: None,
+            app_id: None,
+            expires_in_minutes: None,
+            error: None,
+        };
+        match &token {
+            Ok(token) => {
+                if let Ok(claims) = TokenClaims::decode(token) {
+                    diagnostics.client_id_honored =
+                        self.user_assigned_client_id.as_ref().map(|requested| {
+                            claims
+                                .app_id()
+                                .is_some_and(|id| id.eq_ignore_ascii_case(requested))
+                        });
+                    diagnostics.expires_in_minutes = claims
+                        .expires_at()
+                        .map(|expires_at| (expires_at - Utc::now()).num_minutes());
+                    diagnostics.app_id = claims.app_id().map(str::to_string);
+                    diagnostics.object_id = claims.oid;
+                }
+            }
+            Err(e) => diagnostics.error = Some(e.to_string()),
+        }
+        (diagnostics, token)
     }
 
     /// Fetch token from the appropriate managed identity endpoint
@@ -85,6 +211,7 @@ impl ManagedIdentityAuth {
             ManagedIdentityEndpoint::AppService { endpoint, header } => {
                 self.fetch_app_service_token(endpoint, header).await
             }
+            ManagedIdentityEndpoint::AzureArc { endpoint } => self.fetch_arc_token(endpoint).await,
             ManagedIdentityEndpoint::VirtualMachine => self.fetch_vm_token().await,
         }
     }
@@ -133,12 +260,88 @@ impl ManagedIdentityAuth {
         Ok(mi_response.access_token)
     }
 
+    /// Fetch token from the Azure Arc himds agent. The first request is
+    /// answered with a 401 naming a key file; reading it proves local admin
+    /// (or himds group) rights, and its content is sent back as Basic auth.
+    async fn fetch_arc_token(&self, endpoint: &str) -> Result<String> {
+        if self.user_assigned_client_id.is_some() {
+            return Err(AppError::ManagedIdentityNotAvailable(
+                "Azure Arc servers only have a system-assigned identity; remove the \
+                 user-assigned client ID"
+                    .to_string(),
+            ));
+        }
+        let url = format!(
+            "{}?api-version=2020-06-01&resource={}",
+            endpoint, self.resource
+        );
+        let unreachable = |e: reqwest::Error| {
+            AppError::ManagedIdentityNotAvailable(format!(
+                "Failed to reach Azure Arc identity endpoint: {}",
+                e
+            ))
+        };
+
+        let challenge = self
+            .client
+            .get(&url)
+            .header("Metadata", "true")
+            .send()
+            .await
+            .map_err(unreachable)?;
+        let key_file = challenge
+            .headers()
+            .get(reqwest::header::WWW_AUTHENTICATE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split_once("realm="))
+            .map(|(_, path)| path.trim().to_string())
+            .ok_or_else(|| {
+                AppError::ManagedIdentityNotAvailable(format!(
+                    "Azure Arc identity endpoint answered HTTP {} without a key challenge",
+                    challenge.status()
+                ))
+            })?;
+        let key = std::fs::read_to_string(&key_file).map_err(|e| {
+            AppError::ManagedIdentityNotAvailable(format!(
+                "Could not read the Azure Arc key file {} (needs local admin or the \
+                 himds group): {}",
+                key_file, e
+            ))
+        })?;
+
+        let response = self
+            .client
+            .get(&url)
+            .header("Metadata", "true")
+            .header("Authorization", format!("Basic {}", key.trim()))
+            .send()
+            .await
+            .map_err(unreachable)?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(AppError::ManagedIdentityNotAvailable(format!(
+                "HTTP {}: {}",
+                status, body
+            )));
+        }
+
+        let mi_response: ManagedIdentityResponse = response.json().await.map_err(|e| {
+            AppError::ManagedIdentityNotAvailable(format!("Failed to parse response: {}", e))
+        })?;
+
+        Ok(mi_response.access_token)
+    }
+
     /// Fetch token from VM IMDS endpoint
     async fn fetch_vm_token(&self) -> Result<String> {
         let mut url = format!(
-            "http://169.254.169.254/metadata/identity/oauth2/token\
-             ?api-version=2018-02-01&resource={}",
-            self.resource
+            "{}?api-version=2018-02-01&resource={}",
+            IMDS_TOKEN_ENDPOINT, self.resource
         );
 
         // Add client_id if using user-assigned identity
@@ -180,6 +383,58 @@ impl ManagedIdentityAuth {
     }
 }
 
+/// Format managed identity diagnostics for display
+pub fn format_managed_identity_diagnostics(
+    diagnostics: &ManagedIdentityDiagnostics,
+    use_colors: bool,
+) -> String {
+    use console::style;
+
+    let mut output = String::from("\nManaged Identity\n");
+    output.push_str(&format!("  Environment: {}\n", diagnostics.environment));
+    output.push_str(&format!("  Endpoint:    {}\n", diagnostics.endpoint));
+    output.push_str(&format!("  Latency:     {} ms\n", diagnostics.latency_ms));
+    output.push_str(&format!(
+        "  Identity:    {}\n",
+        match &diagnostics.requested_client_id {
+            Some(client_id) => format!("user-assigned (client ID {})", client_id),
+            None => "system-assigned (or the only user-assigned one)".to_string(),
+        }
+    ));
+    if let Some(app_id) = &diagnostics.app_id {
+        output.push_str(&format!("  App ID:      {}\n", app_id));
+    }
+    if let Some(object_id) = &diagnostics.object_id {
+        output.push_str(&format!("  Object ID:   {}\n", object_id));
+    }
+    if let Some(minutes) = diagnostics.expires_in_minutes {
+        output.push_str(&format!("  Expires in:  {} minutes\n", minutes));
+    }
+    output.push('\n');
+
+    let (ok, warn) = if use_colors {
+        (
+            style("\u{2713}").green().to_string(),
+            style("!").yellow().to_string(),
+        )
+    } else {
+        ("[OK]".to_string(), "!".to_string())
+    };
+    if let Some(error) = &diagnostics.error {
+        output.push_str(&format!("{} No token: {}\n", warn, error));
+    } else if diagnostics.client_id_honored == Some(false) {
+        output.push_str(&format!(
+            "{} The token is not for the requested identity (app ID {}); check that the \
+             user-assigned identity is attached to this resource\n",
+            warn,
+            diagnostics.app_id.as_deref().unwrap_or("unknown")
+        ));
+    } else {
+        output.push_str(&format!("{} Token issued\n", ok));
+    }
+    output
+}
+
 #[async_trait]
 impl AuthProvider for ManagedIdentityAuth {
     async fn get_credentials(&self) -> Result<Credentials> {
@@ -208,6 +463,35 @@ mod tests {
         // Should default to VM endpoint
     }
 
+    #[test]
+    fn test_endpoint_from_environment() {
+        let arc = ManagedIdentityAuth::endpoint_from(|name| match name {
+            "IDENTITY_ENDPOINT" => {
+                Some("http://localhost:40342/metadata/identity/oauth2/token".into())
+            }
+            "IMDS_ENDPOINT" => Some("http://localhost:40342".into()),
+            _ => None,
+        });
+        assert!(
+            matches!(arc, ManagedIdentityEndpoint::AzureArc { endpoint } if endpoint.contains("40342"))
+        );
+
+        let app_service = ManagedIdentityAuth::endpoint_from(|name| match name {
+            "IDENTITY_ENDPOINT" => Some("http://localhost:8081/msi/token".into()),
+            "IDENTITY_HEADER" => Some("secret".into()),
+            "IMDS_ENDPOINT" => Some("ignored".into()),
+            _ => None,
+        });
+        assert!(matches!(
+            app_service,
+            ManagedIdentityEndpoint::AppService { .. }
+        ));
+        assert!(matches!(
+            ManagedIdentityAuth::endpoint_from(|_| None),
+            ManagedIdentityEndpoint::VirtualMachine
+        ));
+    }
+
     #[test]
     fn test_detect_app_service_endpoint() {
         env::set_var("IDENTITY_ENDPOINT", "http://localhost:8081");
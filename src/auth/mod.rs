@@ -6,15 +6,23 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
+mod claims;
 mod device_code;
 mod interactive;
 mod managed_identity;
 mod manual_token;
 pub mod token_cache;
 
+pub use claims::{
+    format_token_inspection, TokenClaims, TokenInspection, TokenProblem, TokenWarning,
+    CLOCK_SKEW_SECS,
+};
 pub use device_code::{DeviceCodeAuth, TokenResult};
 pub use interactive::InteractiveAuth;
-pub use managed_identity::ManagedIdentityAuth;
+pub use managed_identity::{
+    format_managed_identity_diagnostics, ManagedIdentityAuth, ManagedIdentityDiagnostics,
+    ManagedIdentityEnvironment,
+};
 pub use manual_token::ManualTokenAuth;
 
 /// Token response from Entra ID
@@ -154,7 +162,7 @@ impl EntraTokenAuth {
             .clone()
             .ok_or_else(|| AppError::Auth("Missing client_secret for Entra auth".to_string()))?;
 
-        let client = Client::builder()
+        let client = crate::network::configure_client(Client::builder())
             .timeout(Duration::from_secs(DEFAULT_TIMEOUT_SECS))
             .build()
             .map_err(|e| AppError::Network(e.to_string()))?;
@@ -170,6 +178,12 @@ impl EntraTokenAuth {
         })
     }
 
+    /// Request a token for another scope (e.g. Key Vault) instead of Cognitive Services
+    pub fn with_scope(mut self, scope: &str) -> Self {
+        self.scope = scope.to_string();
+        self
+    }
+
     async fn fetch_token(&self) -> Result<(String, u64)> {
         let token_url = format!(
             "{}/{}/oauth2/v2.0/token",
@@ -248,7 +262,7 @@ pub struct CognitiveTokenAuth {
 
 impl CognitiveTokenAuth {
     pub fn new(api_key: String, region: &str, cloud: Cloud) -> Result<Self> {
-        let client = Client::builder()
+        let client = crate::network::configure_client(Client::builder())
             .timeout(Duration::from_secs(DEFAULT_TIMEOUT_SECS))
             .build()
             .map_err(|e| AppError::Network(e.to_string()))?;
@@ -317,6 +331,22 @@ impl AuthProvider for CognitiveTokenAuth {
     }
 }
 
+/// Token scope for a `--scope`/`--audience` value: a `.default` scope is kept,
+/// and an audience (resource URI or app ID URI) gets `/.default` appended
+pub fn scope_for(value: &str) -> String {
+    let value = value.trim();
+    if value.ends_with("/.default") {
+        value.to_string()
+    } else {
+        format!("{}/.default", value.trim_end_matches('/'))
+    }
+}
+
+/// Audience (resource) that a `.default` scope requests a token for
+pub fn audience_of(scope: &str) -> &str {
+    scope.trim_end_matches("/.default")
+}
+
 /// Authentication manager that supports multiple auth methods
 pub struct AuthManager {
     api_key: Option<ApiKeyAuth>,
@@ -422,6 +452,18 @@ impl AuthManager {
         })
     }
 
+    /// Request Entra tokens for `scope` (see [`scope_for`]) instead of the
+    /// cloud's Cognitive Services scope
+    pub fn with_scope(mut self, scope: &str) -> Self {
+        self.service_principal = self.service_principal.map(|sp| sp.with_scope(scope));
+        self.managed_identity = self
+            .managed_identity
+            .map(|mi| mi.with_resource(audience_of(scope)));
+        self.device_code = self.device_code.map(|dc| dc.with_scope(scope));
+        self.interactive = self.interactive.map(|ia| ia.with_scope(scope));
+        self
+    }
+
     /// Get the primary auth provider based on configuration
     pub fn get_provider(&self) -> Result<&dyn AuthProvider> {
         match self.default_method {
@@ -507,6 +549,78 @@ impl AuthManager {
     }
 }
 
+/// Get a token for another Azure resource (Key Vault, Storage) with the
+/// run's Entra credential. Key and token auth have no Entra credential of
+/// their own, so they use the service principal from the config when one is
+/// complete, and the Azure CLI login otherwise.
+pub async fn get_resource_token(
+    resource: &str,
+    label: &str,
+    auth_method: AuthMethod,
+    cloud: Cloud,
+    entra_config: Option<&EntraConfig>,
+    user_config: Option<&UserAuthConfig>,
+    quiet: bool,
+) -> Result<String> {
+    let scope = format!("{}/.default", resource);
+    let tenant_id = || {
+        user_config
+            .and_then(|c| c.tenant_id.clone())
+            .ok_or(AppError::MissingTenantId)
+    };
+    let client_id = || user_config.and_then(|c| c.client_id.clone());
+    let service_principal = entra_config
+        .filter(|c| c.tenant_id.is_some() && c.client_id.is_some() && c.client_secret.is_some());
+
+    let credentials = match auth_method {
+        AuthMethod::ServicePrincipal => {
+            let config = entra_config.cloned().unwrap_or_default();
+            EntraTokenAuth::new(&config, cloud)?
+                .with_scope(&scope)
+                .get_credentials()
+                .await?
+        }
+        AuthMethod::ManagedIdentity => {
+            let client_id = user_config.and_then(|c| c.managed_identity_client_id.clone());
+            ManagedIdentityAuth::new(&cloud, client_id)?
+                .with_resource(resource)
+                .get_credentials()
+                .await?
+        }
+        AuthMethod::DeviceCode => {
+            DeviceCodeAuth::new(tenant_id()?, client_id(), &cloud)?
+                .with_scope(&scope)
+                .with_quiet(quiet)
+                .get_credentials()
+                .await?
+        }
+        AuthMethod::Interactive => {
+            InteractiveAuth::new(tenant_id()?, client_id(), &cloud)?
+                .with_scope(&scope)
+                .with_quiet(quiet)
+                .get_credentials()
+                .await?
+        }
+        AuthMethod::Key | AuthMethod::Token | AuthMethod::Both => match service_principal {
+            Some(config) => {
+                EntraTokenAuth::new(config, cloud)?
+                    .with_scope(&scope)
+                    .get_credentials()
+                    .await?
+            }
+            None => return crate::arm::get_resource_token_from_azure_cli(resource, label),
+        },
+    };
+
+    match credentials {
+        Credentials::BearerToken(token) => Ok(token),
+        Credentials::ApiKey(_) => Err(AppError::Auth(format!(
+            "{} requires an Entra ID token",
+            label
+        ))),
+    }
+}
+
 /// Test result for authentication
 #[derive(Debug, Clone, Serialize)]
 pub struct AuthTestResult {
@@ -520,6 +634,26 @@ pub struct AuthTestResult {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_scope_for() {
+        assert_eq!(
+            scope_for("https://ai.azure.com"),
+            "https://ai.azure.com/.default"
+        );
+        assert_eq!(
+            scope_for("api://apim-backend/"),
+            "api://apim-backend/.default"
+        );
+        assert_eq!(
+            scope_for("https://cognitiveservices.azure.us/.default"),
+            "https://cognitiveservices.azure.us/.default"
+        );
+        assert_eq!(
+            audience_of(&scope_for("https://ai.azure.com")),
+            "https://ai.azure.com"
+        );
+    }
+
     #[test]
     fn test_api_key_auth() {
         let auth = ApiKeyAuth::new("test-key".to_string());
@@ -0,0 +1,442 @@
+//! Request/response capture (`test --capture <dir>`) and support bundles
+//!
+//! Every request sent through `TestContext::send` is recorded as one JSON line
+//! in `requests.jsonl`, with secrets redacted and bodies truncated. The
+//! `support-bundle` command zips a capture directory together with the
+//! redacted config and environment report. `test --record <file>` keeps the
+//! same exchanges together with the report in a single session file that
+//! `test --replay` renders again (see [`session`]).
+
+pub mod session;
+pub mod zip;
+
+use crate::config::{Config, GlobalConfig};
+use crate::error::Result;
+use crate::redact::is_sensitive_header;
+pub use crate::redact::{redact_headers, redact_url, REDACTED};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// File inside the capture directory holding one exchange per line
+pub const CAPTURE_FILE: &str = "requests.jsonl";
+
+/// Test report written next to the capture
+pub const REPORT_FILE: &str = "report.json";
+
+/// Client environment written next to the capture
+pub const ENVIRONMENT_FILE: &str = "environment.json";
+
+/// Longest request/response body kept in a capture
+pub const MAX_CAPTURED_BODY: usize = 4096;
+
+/// One recorded request and its response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapturedExchange {
+    pub timestamp: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scenario: Option<String>,
+    pub method: String,
+    pub url: String,
+    #[serde(default)]
+    pub request_headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    #[serde(default)]
+    pub response_headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_body: Option<String>,
+    pub duration_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl CapturedExchange {
+    /// Start recording a request
+    pub fn from_request(request: &reqwest::Request, extra_sensitive: &[String]) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            service: None,
+            scenario: None,
+            method: request.method().to_string(),
+            url: redact_url(request.url()),
+            request_headers: redact_headers(request.headers(), extra_sensitive),
+            request_body: request
+                .body()
+                .and_then(|b| b.as_bytes())
+                .map(summarize_body),
+            status: None,
+            response_headers: BTreeMap::new(),
+            response_body: None,
+            duration_ms: 0,
+            error: None,
+        }
+    }
+}
+
+/// Capture sink shared by all test contexts in a run
+pub struct CaptureLog {
+    file: Option<Mutex<std::fs::File>>,
+    /// Redacted exchanges kept for a session file (`--record`)
+    kept: Option<Mutex<Vec<CapturedExchange>>>,
+    scenario: Mutex<Option<(String, String)>>,
+    extra_sensitive: Vec<String>,
+}
+
+impl CaptureLog {
+    /// Create the capture directory and start a fresh capture file
+    pub fn create(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let file = std::fs::File::create(dir.join(CAPTURE_FILE))?;
+        Ok(Self {
+            file: Some(Mutex::new(file)),
+            kept: None,
+            scenario: Mutex::new(None),
+            extra_sensitive: Vec::new(),
+        })
+    }
+
+    /// Keep exchanges in memory only, for a session file
+    pub fn in_memory() -> Self {
+        Self {
+            file: None,
+            kept: None,
+            scenario: Mutex::new(None),
+            extra_sensitive: Vec::new(),
+        }
+        .keeping_exchanges()
+    }
+
+    /// Also keep every recorded exchange in memory (see [`CaptureLog::exchanges`])
+    pub fn keeping_exchanges(mut self) -> Self {
+        self.kept.get_or_insert_with(|| Mutex::new(Vec::new()));
+        self
+    }
+
+    /// Exchanges recorded so far, when they are being kept
+    pub fn exchanges(&self) -> Vec<CapturedExchange> {
+        self.kept
+            .as_ref()
+            .and_then(|kept| kept.lock().ok().map(|kept| kept.clone()))
+            .unwrap_or_default()
+    }
+
+    /// Also redact these header names (e.g. a custom API key header)
+    pub fn with_sensitive_headers(mut self, headers: Vec<String>) -> Self {
+        self.extra_sensitive = headers.into_iter().map(|h| h.to_lowercase()).collect();
+        self
+    }
+
+    pub fn sensitive_headers(&self) -> &[String] {
+        &self.extra_sensitive
+    }
+
+    /// Label subsequent exchanges with the scenario being run
+    pub fn set_scenario(&self, service: &str, scenario: &str) {
+        if let Ok(mut current) = self.scenario.lock() {
+            *current = Some((service.to_string(), scenario.to_string()));
+        }
+    }
+
+    /// Append an exchange to the capture file
+    pub fn record(&self, mut exchange: CapturedExchange) {
+        if let Ok(current) = self.scenario.lock() {
+            if let Some((service, scenario)) = current.as_ref() {
+                exchange.service = Some(service.clone());
+                exchange.scenario = Some(scenario.clone());
+            }
+        }
+        let Ok(line) = serde_json::to_string(&exchange) else {
+            return;
+        };
+        let line = crate::redact::redact(&line);
+        if let Some(Ok(mut file)) = self.file.as_ref().map(|f| f.lock()) {
+            // A failed capture write must not fail the test itself
+            let _ = writeln!(file, "{}", line);
+        }
+        if let Some(Ok(mut kept)) = self.kept.as_ref().map(|k| k.lock()) {
+            // Keep the redacted copy, so a session file holds no secrets either
+            if let Ok(exchange) = serde_json::from_str(&line) {
+                kept.push(exchange);
+            }
+        }
+    }
+}
+
+/// Text bodies truncated to `MAX_CAPTURED_BODY`; binary bodies as a size note
+pub fn summarize_body(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text) if text.len() > MAX_CAPTURED_BODY => {
+            let mut end = MAX_CAPTURED_BODY;
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            format!("{}... ({} bytes, truncated)", &text[..end], bytes.len())
+        }
+        Ok(text) => text.to_string(),
+        Err(_) => format!("<{} bytes binary>", bytes.len()),
+    }
+}
+
+/// Replace a secret that is set
+fn redact_value(value: &mut Option<String>) {
+    if value.is_some() {
+        *value = Some(REDACTED.to_string());
+    }
+}
+
+/// Replace the values of headers that carry credentials, including the
+/// configured API key header
+fn redact_header_values(headers: &mut BTreeMap<String, String>, sensitive: &[String]) {
+    for (name, value) in headers.iter_mut() {
+        if is_sensitive_header(name, sensitive) {
+            *value = REDACTED.to_string();
+        }
+    }
+}
+
+fn sensitive_headers(global: &GlobalConfig) -> Vec<String> {
+    global
+        .api_key_header
+        .iter()
+        .map(|h| h.to_lowercase())
+        .collect()
+}
+
+/// A copy of the config with keys, secrets, and tokens replaced, in the
+/// top-level settings and in every `[profile.<name>]`
+pub fn redacted_config(config: &Config) -> Config {
+    let mut config = config.clone();
+
+    for secret in config.secrets_mut() {
+        redact_value(secret);
+    }
+    // Blob targets may carry a SAS token
+    for target in config
+        .upload
+        .targets
+        .iter_mut()
+        .chain(config.serve.report_to.iter_mut())
+    {
+        *target = crate::redact::redact_with(target, &[]);
+    }
+
+    let sensitive = sensitive_headers(&config.global);
+    redact_header_values(&mut config.global.headers, &sensitive);
+    for probe in config.custom_scenarios.iter_mut() {
+        redact_header_values(&mut probe.headers, &sensitive);
+    }
+
+    for profile in config.profile.values_mut() {
+        if let Some(global) = profile.global.as_mut() {
+            let mut sensitive = sensitive_headers(global);
+            sensitive.extend(sensitive_headers(&config.global));
+            redact_header_values(&mut global.headers, &sensitive);
+        }
+    }
+    config
+}
+
+/// Zip a capture directory with the redacted config and environment report
+pub fn build_support_bundle(
+    capture_dir: &Path,
+    config: &Config,
+    environment: Option<&crate::environment::EnvironmentInfo>,
+) -> Result<Vec<u8>> {
+    let mut archive = zip::ZipWriter::new();
+
+    let mut names: Vec<PathBuf> = std::fs::read_dir(capture_dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .collect();
+    names.sort();
+    for path in names {
+        let Some(name) = path.file_name().map(|n| n.to_string_lossy().to_string()) else {
+            continue;
+        };
+        // A fresh environment report replaces the captured one
+        if name == ENVIRONMENT_FILE && environment.is_some() {
+            continue;
+        }
+        archive.add_file(&format!("capture/{}", name), &std::fs::read(&path)?);
+    }
+
+    let config_toml = toml::to_string_pretty(&redacted_config(config))
+        .map_err(|e| crate::error::AppError::Config(e.to_string()))?;
+    archive.add_file("config.redacted.toml", config_toml.as_bytes());
+
+    if let Some(environment) = environment {
+        let json = serde_json::to_string_pretty(environment)
+            .map_err(|e| crate::error::AppError::Config(e.to_string()))?;
+        archive.add_file(&format!("capture/{}", ENVIRONMENT_FILE), json.as_bytes());
+    }
+
+    Ok(archive.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_headers_and_url() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("Ocp-Apim-Subscription-Key", "secret".parse().unwrap());
+        headers.insert("x-gateway-key", "secret".parse().unwrap());
+        headers.insert("Content-Type", "application/json".parse().unwrap());
+
+        let redacted = redact_headers(&headers, &["x-gateway-key".to_string()]);
+        assert_eq!(redacted["ocp-apim-subscription-key"], REDACTED);
+        assert_eq!(redacted["x-gateway-key"], REDACTED);
+        assert_eq!(redacted["content-type"], "application/json");
+
+        let url = url::Url::parse(
+            "https://user:pw@eastus.api.cognitive.microsoft.com/sts?subscription-key=abc&api-version=1",
+        )
+        .unwrap();
+        let redacted = redact_url(&url);
+        assert!(!redacted.contains("abc") && !redacted.contains("pw"));
+        assert!(redacted.contains("api-version=1"));
+    }
+
+    #[test]
+    fn test_summarize_body() {
+        assert_eq!(summarize_body(b"{\"ok\":true}"), "{\"ok\":true}");
+        assert_eq!(summarize_body(&[0xff, 0xfe, 0x00]), "<3 bytes binary>");
+        let long = "é".repeat(MAX_CAPTURED_BODY);
+        assert!(summarize_body(long.as_bytes()).ends_with("truncated)"));
+    }
+
+    #[test]
+    fn test_redacted_config() {
+        let mut config = Config::default_config();
+        config.auth.entra.client_secret = Some("s3cret".to_string());
+        config.global.api_key_header = Some("x-gateway-key".to_string());
+        config
+            .global
+            .headers
+            .insert("x-gateway-key".to_string(), "k".to_string());
+        config
+            .services
+            .entry("speech".to_string())
+            .or_default()
+            .api_key = Some("key".to_string());
+        config.upload.token = Some("upl0ad-token".to_string());
+        config.serve.token = Some("c0ntroller-token".to_string());
+        config.upload.log_analytics_key = Some("w0rkspace-key".to_string());
+        config.upload.targets = vec![
+            "blob:https://acct.blob.core.windows.net/reports?sv=2022-11-02&sig=s4s".to_string(),
+        ];
+
+        let redacted = redacted_config(&config);
+        let text = toml::to_string(&redacted).unwrap();
+        assert!(!text.contains("s3cret"));
+        for secret in [
+            "upl0ad-token",
+            "c0ntroller-token",
+            "w0rkspace-key",
+            "sig=s4s",
+        ] {
+            assert!(!text.contains(secret), "{} in {}", secret, text);
+        }
+        assert_eq!(redacted.upload.token.as_deref(), Some(REDACTED));
+        assert_eq!(redacted.serve.token.as_deref(), Some(REDACTED));
+
+        // Profiles override the same settings, secrets included
+        let mut profile: crate::config::ConfigProfile = toml::from_str(
+            r#"
+            auth.entra.client_secret = "pr0file-secret"
+            auth.user.bearer_token = "pr0file-bearer"
+            services.speech.api_key = "pr0file-key"
+            global.headers = { "Ocp-Apim-Subscription-Key" = "pr0file-header" }
+            "#,
+        )
+        .unwrap();
+        profile.services.get_mut("speech").unwrap().region = Some("westus".to_string());
+        config.profile.insert("staging".to_string(), profile);
+        let text = toml::to_string(&redacted_config(&config)).unwrap();
+        for secret in [
+            "pr0file-secret",
+            "pr0file-bearer",
+            "pr0file-key",
+            "pr0file-header",
+        ] {
+            assert!(!text.contains(secret), "{} in {}", secret, text);
+        }
+        assert!(text.contains("westus"));
+        assert_eq!(redacted.upload.log_analytics_key.as_deref(), Some(REDACTED));
+        assert_eq!(redacted.global.headers["x-gateway-key"], REDACTED);
+        assert_eq!(
+            redacted.services["speech"].api_key.as_deref(),
+            Some(REDACTED)
+        );
+    }
+
+    #[test]
+    fn test_config_secrets_are_redacted_in_bundle_and_logs() {
+        let mut config: Config = toml::from_str(
+            r#"
+            auth.entra.client_secret = "m4rker-entra-secret"
+            auth.user.bearer_token = "m4rker-user-bearer"
+            serve.token = "m4rker-serve-token"
+            upload.token = "m4rker-upload-token"
+            upload.log_analytics_key = "m4rker-workspace-key"
+            services.speech.api_key = "m4rker-service-key"
+            services.speech.resources.west.api_key = "m4rker-resource-key"
+
+            [profile.staging]
+            auth.entra.client_secret = "m4rker-profile-entra-secret"
+            auth.user.bearer_token = "m4rker-profile-user-bearer"
+            services.speech.api_key = "m4rker-profile-service-key"
+            services.speech.resources.west.api_key = "m4rker-profile-resource-key"
+            "#,
+        )
+        .unwrap();
+        let markers: Vec<String> = config
+            .secrets_mut()
+            .into_iter()
+            .filter_map(|v| v.clone())
+            .collect();
+        // Every secret set above is on the shared list
+        assert_eq!(markers.len(), 11);
+        assert!(markers.iter().all(|m| m.starts_with("m4rker-")));
+
+        let bundle = toml::to_string(&redacted_config(&config)).unwrap();
+        assert!(!bundle.contains("m4rker-"), "{}", bundle);
+
+        crate::redact::register_config_secrets(&config);
+        let logs = crate::redact::redact(&markers.join(" "));
+        assert!(!logs.contains("m4rker-"), "{}", logs);
+    }
+
+    #[test]
+    fn test_capture_log_and_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = CaptureLog::create(dir.path()).unwrap();
+        log.set_scenario("speech", "voices_list");
+
+        let request = reqwest::Client::new()
+            .get("https://eastus.tts.speech.microsoft.com/cognitiveservices/voices/list")
+            .header("Ocp-Apim-Subscription-Key", "secret")
+            .build()
+            .unwrap();
+        let mut exchange = CapturedExchange::from_request(&request, &[]);
+        exchange.status = Some(200);
+        log.record(exchange);
+
+        let captured = std::fs::read_to_string(dir.path().join(CAPTURE_FILE)).unwrap();
+        assert!(captured.contains("\"scenario\":\"voices_list\""));
+        assert!(!captured.contains("secret"));
+
+        let bundle = build_support_bundle(dir.path(), &Config::default_config(), None).unwrap();
+        assert_eq!(&bundle[..2], b"PK");
+    }
+}
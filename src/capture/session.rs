@@ -0,0 +1,156 @@
+//! Recorded test sessions (`test --record` / `test --replay`)
+//!
+//! A session file holds a run's report and every request/response exchange,
+//! redacted the same way as a capture. A failing run recorded on a customer's
+//! machine can be replayed elsewhere in any output format, without the
+//! customer's network or credentials.
+
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::CapturedExchange;
+use crate::error::{AppError, Result};
+use crate::output::TestReport;
+
+/// Session file format written by this version
+pub const SESSION_FORMAT_VERSION: u32 = 1;
+
+/// A recorded run: its report and the exchanges behind it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    /// Session file format version
+    pub format_version: u32,
+    /// Version of the tool that recorded the session
+    pub tool_version: String,
+    pub recorded_at: DateTime<Utc>,
+    pub report: TestReport,
+    /// Requests in the order they were sent
+    #[serde(default)]
+    pub exchanges: Vec<CapturedExchange>,
+}
+
+impl Session {
+    pub fn new(report: TestReport, exchanges: Vec<CapturedExchange>) -> Self {
+        Self {
+            format_version: SESSION_FORMAT_VERSION,
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            recorded_at: Utc::now(),
+            report,
+            exchanges,
+        }
+    }
+
+    /// Write the session as JSON, with secrets redacted
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, crate::redact::redact(&json))?;
+        Ok(())
+    }
+
+    /// Read a session file written by `--record`
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                AppError::FileNotFound(path.display().to_string())
+            } else {
+                AppError::Io(e)
+            }
+        })?;
+        let session: Self = serde_json::from_str(&content).map_err(|e| {
+            AppError::InvalidInput(format!(
+                "{} is not a session file written by --record: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        if session.format_version > SESSION_FORMAT_VERSION {
+            return Err(AppError::InvalidInput(format!(
+                "{} was recorded by version {} in a newer session format; upgrade to replay it",
+                path.display(),
+                session.tool_version
+            )));
+        }
+        Ok(session)
+    }
+
+    /// One line per exchange, under a heading for each scenario that sent it
+    pub fn exchange_log(&self) -> String {
+        let mut log = String::new();
+        let mut current = None;
+        for exchange in &self.exchanges {
+            let scenario = (exchange.service.as_deref(), exchange.scenario.as_deref());
+            if current != Some(scenario) {
+                current = Some(scenario);
+                log.push_str(&format!(
+                    "{} / {}\n",
+                    scenario.0.unwrap_or("-"),
+                    scenario.1.unwrap_or("-")
+                ));
+            }
+            let outcome = match (exchange.status, &exchange.error) {
+                (_, Some(error)) => format!("error: {}", error),
+                (Some(status), None) => status.to_string(),
+                (None, None) => "no response".to_string(),
+            };
+            log.push_str(&format!(
+                "  {} {} -> {} ({} ms)\n",
+                exchange.method, exchange.url, outcome, exchange.duration_ms
+            ));
+        }
+        log
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::CaptureLog;
+    use crate::services::{ServiceTestResults, TestResult};
+
+    #[test]
+    fn test_session_round_trip() {
+        let log = CaptureLog::in_memory();
+        log.set_scenario("translator", "languages");
+        let request = reqwest::Client::new()
+            .get("https://api.cognitive.microsofttranslator.com/languages?api-version=3.0")
+            .header("Ocp-Apim-Subscription-Key", "0123456789abcdef")
+            .build()
+            .unwrap();
+        let mut exchange = CapturedExchange::from_request(&request, &[]);
+        exchange.status = Some(401);
+        exchange.duration_ms = 42;
+        log.record(exchange);
+
+        let report = TestReport::new(vec![ServiceTestResults {
+            service_name: "Translator".to_string(),
+            endpoint: "https://api.cognitive.microsofttranslator.com".to_string(),
+            region: None,
+            target: None,
+            results: vec![TestResult::failure(
+                "languages",
+                "Get Languages",
+                42,
+                "HTTP 401".to_string(),
+            )],
+            total_duration_ms: 42,
+        }]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.json");
+        Session::new(report, log.exchanges()).save(&path).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert!(!saved.contains("0123456789abcdef"));
+
+        let session = Session::load(&path).unwrap();
+        assert_eq!(session.report.summary.failed, 1);
+        assert_eq!(
+            session.exchange_log(),
+            "translator / languages\n  GET https://api.cognitive.microsofttranslator.com/languages?api-version=3.0 -> 401 (42 ms)\n"
+        );
+
+        std::fs::write(&path, "{\"report\": 1}").unwrap();
+        assert!(Session::load(&path).is_err());
+    }
+}
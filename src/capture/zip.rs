@@ -0,0 +1,156 @@
+//! Minimal ZIP archive writer (stored entries, no compression)
+
+use chrono::{Datelike, Local, Timelike};
+
+/// One file already written to the archive, kept for the central directory
+struct Entry {
+    name: String,
+    crc: u32,
+    size: u32,
+    offset: u32,
+}
+
+/// Builds a ZIP archive in memory
+pub struct ZipWriter {
+    data: Vec<u8>,
+    entries: Vec<Entry>,
+    dos_time: u16,
+    dos_date: u16,
+}
+
+impl Default for ZipWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ZipWriter {
+    pub fn new() -> Self {
+        let now = Local::now();
+        let dos_time =
+            ((now.hour() as u16) << 11) | ((now.minute() as u16) << 5) | (now.second() as u16 / 2);
+        let dos_date = (((now.year().clamp(1980, 2107) - 1980) as u16) << 9)
+            | ((now.month() as u16) << 5)
+            | now.day() as u16;
+        Self {
+            data: Vec::new(),
+            entries: Vec::new(),
+            dos_time,
+            dos_date,
+        }
+    }
+
+    /// Add a file to the archive
+    pub fn add_file(&mut self, name: &str, contents: &[u8]) {
+        let crc = crc32(contents);
+        let size = contents.len() as u32;
+        let offset = self.data.len() as u32;
+
+        // Local file header
+        self.put_u32(0x0403_4b50);
+        self.put_u16(20); // version needed to extract
+        self.put_u16(0x0800); // flags: UTF-8 names
+        self.put_u16(0); // method: stored
+        self.put_u16(self.dos_time);
+        self.put_u16(self.dos_date);
+        self.put_u32(crc);
+        self.put_u32(size);
+        self.put_u32(size);
+        self.put_u16(name.len() as u16);
+        self.put_u16(0); // extra field length
+        self.data.extend_from_slice(name.as_bytes());
+        self.data.extend_from_slice(contents);
+
+        self.entries.push(Entry {
+            name: name.to_string(),
+            crc,
+            size,
+            offset,
+        });
+    }
+
+    /// Write the central directory and return the archive bytes
+    pub fn finish(mut self) -> Vec<u8> {
+        let directory_offset = self.data.len() as u32;
+        let entries = std::mem::take(&mut self.entries);
+
+        for entry in &entries {
+            self.put_u32(0x0201_4b50);
+            self.put_u16(20); // version made by
+            self.put_u16(20); // version needed to extract
+            self.put_u16(0x0800);
+            self.put_u16(0);
+            self.put_u16(self.dos_time);
+            self.put_u16(self.dos_date);
+            self.put_u32(entry.crc);
+            self.put_u32(entry.size);
+            self.put_u32(entry.size);
+            self.put_u16(entry.name.len() as u16);
+            self.put_u16(0); // extra field length
+            self.put_u16(0); // comment length
+            self.put_u16(0); // disk number
+            self.put_u16(0); // internal attributes
+            self.put_u32(0); // external attributes
+            self.put_u32(entry.offset);
+            self.data.extend_from_slice(entry.name.as_bytes());
+        }
+
+        let directory_size = self.data.len() as u32 - directory_offset;
+        self.put_u32(0x0605_4b50);
+        self.put_u16(0);
+        self.put_u16(0);
+        self.put_u16(entries.len() as u16);
+        self.put_u16(entries.len() as u16);
+        self.put_u32(directory_size);
+        self.put_u32(directory_offset);
+        self.put_u16(0); // comment length
+
+        self.data
+    }
+
+    fn put_u16(&mut self, value: u16) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn put_u32(&mut self, value: u32) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+/// CRC-32 (IEEE) as used by ZIP
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_zip_layout() {
+        let mut zip = ZipWriter::new();
+        zip.add_file("a.txt", b"hello");
+        zip.add_file("dir/b.json", b"{}");
+        let bytes = zip.finish();
+
+        assert_eq!(&bytes[..4], &[0x50, 0x4b, 0x03, 0x04]);
+        // End of central directory: signature, then 2 entries
+        let eocd = bytes.len() - 22;
+        assert_eq!(&bytes[eocd..eocd + 4], &[0x50, 0x4b, 0x05, 0x06]);
+        assert_eq!(u16::from_le_bytes([bytes[eocd + 10], bytes[eocd + 11]]), 2);
+    }
+}
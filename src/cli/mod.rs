@@ -12,6 +12,10 @@ EXAMPLES:
   # Test all services with an API key
   azure-aitoolsconnect test --api-key YOUR_KEY --region eastus
 
+  # Read the API key from Key Vault instead of passing it
+  azure-aitoolsconnect test -s speech -r eastus \\
+    --keyvault-secret https://myvault.vault.azure.net/secrets/speech-key
+
   # Test speech service with device code (interactive login)
   azure-aitoolsconnect test -s speech --auth device-code --tenant YOUR_TENANT_ID \\
     --endpoint https://your-resource.cognitiveservices.azure.com
@@ -26,6 +30,77 @@ EXAMPLES:
   # Run specific test scenarios only
   azure-aitoolsconnect test -s speech --scenarios voices_list,tts --api-key KEY -r eastus
 
+  # Verify routing to a Custom Translator model
+  azure-aitoolsconnect test -s translator --translator-category CATEGORY_ID --api-key KEY -r eastus
+
+  # Analyze with a custom Document Intelligence model
+  azure-aitoolsconnect test -s document_intelligence --di-model my-invoice-model --api-key KEY -r eastus
+
+  # Test an AI Foundry project endpoint (services.ai.azure.com)
+  azure-aitoolsconnect test -s ai_foundry --auth device-code --tenant YOUR_TENANT_ID \\
+    --endpoint https://your-resource.services.ai.azure.com/api/projects/YOUR_PROJECT \\
+    --foundry-deployment gpt-4o-mini
+
+  # Test a serverless model endpoint (Llama, Phi, Mistral, ...)
+  azure-aitoolsconnect test -s model_inference --api-key ENDPOINT_KEY \\
+    --endpoint https://my-llama.eastus2.models.ai.azure.com
+
+  # Check the Azure OpenAI Assistants routes and realtime WebSocket
+  azure-aitoolsconnect test -s openai --api-key KEY --endpoint https://your-resource.openai.azure.com \\
+    --scenarios assistants_list,realtime_ws --realtime-deployment gpt-4o-realtime-preview
+
+  # Gate a deployment: fail on slow (>1500ms) or skipped scenarios too
+  azure-aitoolsconnect test -s speech --api-key KEY -r eastus \\
+    --max-latency-ms 1500 --fail-on-slow --fail-on failed+skipped
+
+  # Post to Slack when a scenario that passed last time fails (e.g. from cron)
+  azure-aitoolsconnect test -s all --api-key KEY -r eastus \\
+    --notify slack:https://hooks.slack.com/services/... --notify-on regression
+
+  # Email the on-call list on failures (SMTP credentials from the environment)
+  azure-aitoolsconnect test -s all --api-key KEY -r eastus \\
+    --email-to oncall@contoso.com --smtp-server smtp.office365.com --notify-on failure
+
+  # Record a failing run to a session file, then replay it elsewhere as Markdown
+  azure-aitoolsconnect test -s speech --api-key KEY -r eastus --record session.json
+  azure-aitoolsconnect test --replay session.json -o markdown
+
+  # Check an on-prem Language container (no key needed)
+  azure-aitoolsconnect test -s language --container --endpoint http://localhost:5000 \
+    --allow-insecure-http
+
+  # Check that Speech, Vision, and Document Intelligence actually process content
+  azure-aitoolsconnect test -s speech,vision,document_intelligence --api-key KEY -r eastus \
+    --realistic-payloads
+
+  # Check that connections survive a 10-minute async job (NAT/proxy idle timeouts)
+  azure-aitoolsconnect test -s document_intelligence --scenarios layout --api-key KEY -r eastus \
+    --long-poll 10m
+
+  # Keep the synthesized audio and analysis results to check them by hand
+  azure-aitoolsconnect test -s speech,vision --api-key KEY -r eastus --save-artifacts ./artifacts
+
+  # Quick check: only fast scenarios, skipping long-running operations
+  azure-aitoolsconnect test -s all --api-key KEY -r eastus \
+    --scenarios tag:fast --skip-scenarios tag:async-poll
+
+  # CI smoke run: reachability plus one authenticated call per service
+  azure-aitoolsconnect test -s all --api-key KEY -r eastus --profile smoke
+
+  # Compare regional endpoints in one run
+  azure-aitoolsconnect test -s speech --api-key KEY --regions eastus,westus2,westeurope
+
+  # Test the prod and staging resources defined in the config file
+  azure-aitoolsconnect test -s speech,translator --target prod,staging
+
+  # Explain 403s using the resource's firewall and private endpoint settings
+  azure-aitoolsconnect test -s speech --api-key KEY -r eastus \\
+    --resource-id /subscriptions/SUB/resourceGroups/RG/providers/Microsoft.CognitiveServices/accounts/NAME
+
+  # Test through an API Management gateway that expects the key in 'api-key'
+  azure-aitoolsconnect test -s translator --api-key KEY --api-key-header api-key \\
+    --endpoint https://contoso-apim.azure-api.net/translator -H 'x-env: prod'
+
   # Output as JSON for scripting
   azure-aitoolsconnect test -s translator --api-key KEY -r eastus -o json
 
@@ -49,6 +124,9 @@ EXAMPLES:
   # Use managed identity (on Azure VM/App Service)
   azure-aitoolsconnect login --auth managed-identity
 
+  # Show which managed identity environment and identity answered
+  azure-aitoolsconnect login --auth managed-identity --client-id MI_CLIENT_ID --verbose
+
   # Clear cached tokens
   azure-aitoolsconnect login --clear-cache";
 
@@ -60,8 +138,174 @@ EXAMPLES:
   # DNS resolution check only
   azure-aitoolsconnect diagnose --dns --region eastus
 
+  # Check that OCSP/CRL endpoints are reachable (TLS inspection environments)
+  azure-aitoolsconnect diagnose --revocation --region eastus
+
+  # Compare system DNS with public DNS over HTTPS (split-horizon, filtering)
+  azure-aitoolsconnect diagnose --doh --region eastus
+
+  # Flag endpoints that resolve into another region's address ranges
+  azure-aitoolsconnect diagnose --enrich --service-tags ServiceTags_Public.json --region eastus
+
+  # Separate network-layer loss from HTTP slowness with ICMP echo
+  azure-aitoolsconnect diagnose --icmp --latency --region eastus
+
+  # Check extra ports (container gateways, AMQP for hybrid relays)
+  azure-aitoolsconnect diagnose --ports 8443,5671,5672 --region eastus
+
+  # Check for connection limits with 50 simultaneous connections per endpoint
+  azure-aitoolsconnect diagnose --concurrency 50 --region eastus
+
   # Check a custom endpoint
-  azure-aitoolsconnect diagnose -e your-resource.cognitiveservices.azure.com -r eastus";
+  azure-aitoolsconnect diagnose -e your-resource.cognitiveservices.azure.com -r eastus
+
+  # Several regions, with a summary matrix
+  azure-aitoolsconnect diagnose --region eastus,westeurope
+
+  # Every host in a file (one host or URL per line, # comments)
+  azure-aitoolsconnect diagnose --endpoints-file hosts.txt";
+
+const DOCTOR_EXAMPLES: &str = "\
+EXAMPLES:
+  # Walk through every layer for all services
+  azure-aitoolsconnect doctor --api-key YOUR_KEY --region eastus
+
+  # Troubleshoot one service behind a private endpoint
+  azure-aitoolsconnect doctor -s language --api-key KEY \\
+    --endpoint https://your-resource.cognitiveservices.azure.com
+
+  # Check Entra ID access with a bearer token
+  azure-aitoolsconnect doctor -s speech --auth token --bearer-token eyJ... \\
+    --endpoint https://your-resource.cognitiveservices.azure.com";
+
+const EGRESS_IP_EXAMPLES: &str = "\
+EXAMPLES:
+  # Show the public IP Azure sees for this machine
+  azure-aitoolsconnect egress-ip
+
+  # Check whether that IP is on the resource's firewall allowlist
+  azure-aitoolsconnect egress-ip \\
+    --resource-id /subscriptions/SUB/resourceGroups/RG/providers/Microsoft.CognitiveServices/accounts/NAME";
+
+const USAGE_EXAMPLES: &str = "\
+EXAMPLES:
+  # Quota and usage of the accounts behind the configured endpoints
+  azure-aitoolsconnect usage --subscription SUB
+
+  # One account: usage counters, deployment rate limits, and regional model quota
+  azure-aitoolsconnect usage \\
+    --resource-id /subscriptions/SUB/resourceGroups/RG/providers/Microsoft.CognitiveServices/accounts/NAME
+
+  # Add the quota section to a test report, to tell throttling from quota exhaustion
+  azure-aitoolsconnect test -s openai --resource-id /subscriptions/.../accounts/NAME --usage";
+
+const TOKEN_INSPECT_EXAMPLES: &str = "\
+EXAMPLES:
+  # Check a token before using it with --auth token
+  azure-aitoolsconnect token inspect eyJ0eXAiOiJKV1Qi...
+
+  # Check what the Azure CLI hands out (an ARM token unless --resource is given)
+  az account get-access-token --query accessToken -o tsv | azure-aitoolsconnect token inspect -
+
+  # Claims and warnings as JSON
+  azure-aitoolsconnect token inspect -o json \"$AZURE_BEARER_TOKEN\"";
+
+const SUPPORT_BUNDLE_EXAMPLES: &str = "\
+EXAMPLES:
+  # Record every request while testing, then bundle it for a support case
+  azure-aitoolsconnect test -s speech --api-key KEY -r eastus --capture ./capture
+  azure-aitoolsconnect --config config.toml support-bundle --capture ./capture
+
+  # Choose the archive name
+  azure-aitoolsconnect support-bundle --capture ./capture --output case-2410150040001234.zip";
+
+const CAPABILITIES_EXAMPLES: &str = "\
+EXAMPLES:
+  # Show which scenarios are expected to work in a region
+  azure-aitoolsconnect capabilities --region swedencentral
+
+  # List all region-restricted scenarios and where they are offered
+  azure-aitoolsconnect capabilities
+
+  # Check a single service
+  azure-aitoolsconnect capabilities --region westus2 --service vision";
+
+const ENDPOINT_EXAMPLES: &str = "\
+EXAMPLES:
+  # URLs of every service in a region
+  azure-aitoolsconnect endpoint --region westeurope
+
+  # URLs of a resource with a custom subdomain (contoso.cognitiveservices.azure.com)
+  azure-aitoolsconnect endpoint -s speech --custom-domain contoso
+
+  # Check an endpoint before putting it in the config
+  azure-aitoolsconnect endpoint -s openai --endpoint https://contoso.openai.azure.com/";
+
+const DISCOVER_EXAMPLES: &str = "\
+EXAMPLES:
+  # List accounts using the Azure CLI login
+  azure-aitoolsconnect discover
+
+  # Limit to one subscription and write a ready-to-run config with keys
+  azure-aitoolsconnect discover --subscription SUB_ID --fetch-keys --write-config ./config.toml
+  azure-aitoolsconnect test --config ./config.toml
+
+  # Use an explicit ARM token
+  azure-aitoolsconnect discover --arm-token eyJ... -o json";
+
+const VALIDATE_EXAMPLES: &str = "\
+EXAMPLES:
+  # Check a config file for missing keys and unknown regions
+  azure-aitoolsconnect validate --config ./config.toml
+
+  # Also reject misspelled or unsupported keys (exit code 4)
+  azure-aitoolsconnect validate --config ./config.toml --strict
+
+  # Print the JSON Schema of the config file
+  azure-aitoolsconnect validate --schema > config.schema.json";
+
+const CONFIG_EXAMPLES: &str = "\
+EXAMPLES:
+  # Change a service's region, keeping the file's comments
+  azure-aitoolsconnect config set services.speech.region westus2
+
+  # Show a setting (the default when the file doesn't set it)
+  azure-aitoolsconnect config get auth.default_method
+
+  # Edit another file
+  azure-aitoolsconnect --config ./prod.toml config set global.timeout_seconds 60
+  azure-aitoolsconnect --config ./prod.toml config unset services.vision.endpoint";
+
+const SERVE_EXAMPLES: &str = "\
+EXAMPLES:
+  # Run the [[serve.jobs]] of config.toml, with status on 127.0.0.1:9464
+  azure-aitoolsconnect --config config.toml serve
+
+  # Listen on every interface (in a container), keep reports on disk, and log JSON lines
+  azure-aitoolsconnect --log-format json serve --listen 0.0.0.0:9464 --history-dir /var/lib/aitoolsconnect
+
+  # Run every job once at startup, then follow the schedules
+  azure-aitoolsconnect serve --run-now
+
+  # Scrape the status
+  curl http://127.0.0.1:9464/metrics
+
+  # Central controller: collect the reports of agents in other network segments
+  azure-aitoolsconnect serve --listen 0.0.0.0:9464 --accept-reports
+  curl http://controller:9464/matrix.json";
+
+const AGGREGATE_EXAMPLES: &str = "\
+EXAMPLES:
+  # Compare the reports of two sites
+  azure-aitoolsconnect test --location hq -o json --output-file hq.json
+  azure-aitoolsconnect test --location branch -o json --output-file branch.json
+  azure-aitoolsconnect aggregate hq.json branch.json
+
+  # The latest report of every agent a controller kept, differences only
+  azure-aitoolsconnect aggregate /var/lib/aitoolsconnect --differences
+
+  # A Markdown table for a ticket
+  azure-aitoolsconnect aggregate ./reports -o markdown --output-file matrix.md";
 
 /// Azure AI Services Connectivity Testing CLI Tool
 ///
@@ -74,11 +318,25 @@ EXAMPLES:
 #[command(propagate_version = true)]
 #[command(after_help = MAIN_EXAMPLES)]
 pub struct Cli {
-    /// Path to configuration file
+    /// Path to configuration file (default: ./config.toml, then the per-user
+    /// config file, if either exists)
     #[arg(short, long, global = true, env = "AZURE_AITOOLSCONNECT_CONFIG")]
     pub config: Option<PathBuf>,
 
-    /// Enable verbose output
+    /// Ignore configuration files found in the default locations
+    #[arg(
+        long,
+        global = true,
+        default_value_t = false,
+        conflicts_with = "config"
+    )]
+    pub no_config: bool,
+
+    /// Apply a named [profile.<name>] from the configuration file
+    #[arg(long, global = true, env = "AZURE_AITOOLSCONNECT_PROFILE")]
+    pub config_profile: Option<String>,
+
+    /// Enable verbose output (debug-level logs)
     #[arg(short, long, global = true, default_value_t = false)]
     pub verbose: bool,
 
@@ -86,6 +344,37 @@ pub struct Cli {
     #[arg(short, long, global = true, default_value_t = false)]
     pub quiet: bool,
 
+    /// Diagnostic log level (default: warn, or debug with --verbose)
+    #[arg(long, global = true, value_enum, env = "AZURE_AITOOLSCONNECT_LOG")]
+    pub log_level: Option<LogLevelArg>,
+
+    /// Write diagnostic logs to this file instead of stderr
+    #[arg(long, global = true)]
+    pub log_file: Option<PathBuf>,
+
+    /// Diagnostic log format
+    #[arg(long, global = true, value_enum, default_value_t = LogFormatArg::Text)]
+    pub log_format: LogFormatArg,
+
+    /// Show API keys, tokens, and SAS signatures in reports, logs, and captures
+    /// instead of redacting them
+    #[arg(long, global = true, default_value_t = false)]
+    pub show_secrets: bool,
+
+    /// Don't fall back to the Windows WinHTTP proxy when no proxy variable or
+    /// Internet Settings proxy is configured
+    #[arg(long, global = true, default_value_t = false)]
+    pub no_system_proxy: bool,
+
+    /// Don't trust the roots in the Windows certificate store
+    #[arg(long, global = true, default_value_t = false)]
+    pub no_system_certs: bool,
+
+    /// Language of console reports, hints, and prompts (default: from
+    /// LC_ALL, LC_MESSAGES, or LANG, else English)
+    #[arg(long, global = true, value_enum, env = "AZURE_AITOOLSCONNECT_LANG")]
+    pub lang: Option<LangArg>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -93,7 +382,7 @@ pub struct Cli {
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Run connectivity tests against Azure AI Services
-    Test(TestArgs),
+    Test(Box<TestArgs>),
 
     /// Authenticate and obtain a bearer token
     Login(LoginArgs),
@@ -101,6 +390,12 @@ pub enum Commands {
     /// Run network diagnostics
     Diagnose(DiagnoseArgs),
 
+    /// Guided troubleshooting: check each layer and stop at the first failure
+    Doctor(DoctorArgs),
+
+    /// Check this installation: TLS clients, CA roots, localhost listener, writable paths, bundled assets
+    Selftest(SelftestArgs),
+
     /// Initialize a new configuration file
     Init(InitArgs),
 
@@ -109,6 +404,45 @@ pub enum Commands {
 
     /// List available test scenarios for a service
     ListScenarios(ListScenariosArgs),
+
+    /// Show which scenarios are expected to work in a region
+    Capabilities(CapabilitiesArgs),
+
+    /// List known Azure regions for a cloud
+    ListRegions(ListRegionsArgs),
+
+    /// Show every URL the tool uses for a service, and check a custom endpoint
+    Endpoint(EndpointArgs),
+
+    /// Discover Cognitive Services accounts via Azure Resource Manager
+    Discover(DiscoverArgs),
+
+    /// Show this machine's public egress IP and check it against a resource's firewall
+    EgressIp(EgressIpArgs),
+
+    /// Show quota and usage of Cognitive Services accounts via Azure Resource Manager
+    Usage(UsageArgs),
+
+    /// Zip a --capture directory with the redacted config and environment for support
+    SupportBundle(SupportBundleArgs),
+
+    /// Work with bearer tokens
+    Token(TokenArgs),
+
+    /// List the environment variables the tool reads, their values, and what they override
+    Env(EnvArgs),
+
+    /// Inspect or edit the configuration file
+    #[command(after_help = CONFIG_EXAMPLES)]
+    Config(ConfigArgs),
+
+    /// Run the configured test suites on cron schedules and serve their status over HTTP
+    #[command(after_help = SERVE_EXAMPLES)]
+    Serve(ServeArgs),
+
+    /// Merge reports from several locations into one matrix of scenarios by location
+    #[command(after_help = AGGREGATE_EXAMPLES)]
+    Aggregate(AggregateArgs),
 }
 
 #[derive(Args, Debug)]
@@ -122,10 +456,23 @@ pub struct TestArgs {
     #[arg(long, env = "AZURE_AI_API_KEY")]
     pub api_key: Option<String>,
 
+    /// Read the API key from a Key Vault secret (https://<vault>.vault.azure.net/secrets/<name>);
+    /// takes precedence over --api-key
+    #[arg(long, env = "AZURE_KEYVAULT_SECRET")]
+    pub keyvault_secret: Option<String>,
+
     /// Azure region
     #[arg(short, long, env = "AZURE_REGION")]
     pub region: Option<String>,
 
+    /// Test several regions in one run (comma-separated); overrides --region
+    #[arg(long, value_delimiter = ',', conflicts_with = "endpoint")]
+    pub regions: Vec<String>,
+
+    /// Named resources from the config file to test (comma-separated)
+    #[arg(long = "target", value_delimiter = ',', conflicts_with_all = ["regions", "endpoint"])]
+    pub targets: Vec<String>,
+
     /// Authentication method
     #[arg(long, value_enum, default_value_t = AuthMethodArg::Key)]
     pub auth: AuthMethodArg,
@@ -138,6 +485,11 @@ pub struct TestArgs {
     #[arg(long, env = "AZURE_BEARER_TOKEN")]
     pub bearer_token: Option<String>,
 
+    /// Scope or audience for Entra ID tokens instead of the Cognitive Services
+    /// default, e.g. https://ai.azure.com or an API Management backend's app ID URI
+    #[arg(long, visible_alias = "audience", value_name = "SCOPE")]
+    pub scope: Option<String>,
+
     /// Output format
     #[arg(short, long, value_enum, default_value_t = OutputFormatArg::Human)]
     pub output: OutputFormatArg,
@@ -146,33 +498,298 @@ pub struct TestArgs {
     #[arg(long, value_enum, default_value_t = CloudArg::Global)]
     pub cloud: CloudArg,
 
-    /// Custom input file for testing (audio, image, or document)
+    /// Custom input file for testing (audio, image, or document), used by any
+    /// scenario without an input of its own type
     #[arg(long)]
     pub input_file: Option<PathBuf>,
 
+    /// Audio file for speech scenarios
+    #[arg(long, value_name = "PATH")]
+    pub audio_file: Option<PathBuf>,
+
+    /// Image file for vision scenarios
+    #[arg(long, value_name = "PATH")]
+    pub image_file: Option<PathBuf>,
+
+    /// Document (PDF or image) for Document Intelligence scenarios
+    #[arg(long, value_name = "PATH")]
+    pub document_file: Option<PathBuf>,
+
+    /// Text for language and translator scenarios
+    #[arg(long)]
+    pub text: Option<String>,
+
+    /// Send a spoken phrase, an image with text, and a PDF with text instead of
+    /// silence and blank images, and check that the services recognize them
+    #[arg(long, default_value_t = false)]
+    pub realistic_payloads: bool,
+
+    /// Check that Document Intelligence recognizes the expected content of the
+    /// --document-file ([custom_inputs] expected_content), catching uploads
+    /// altered in transit
+    #[arg(long, default_value_t = false)]
+    pub verify_content: bool,
+
+    /// Call the preview version of each API instead of GA, where one exists
+    #[arg(long, default_value_t = false)]
+    pub preview_apis: bool,
+
+    /// Poll Document Intelligence, summarization, and Read 3.2 jobs for this long (e.g. 10m),
+    /// to check that NAT and proxy connections survive long async operations
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration_arg)]
+    pub long_poll: Option<std::time::Duration>,
+
+    /// Seconds between polls with --long-poll
+    #[arg(long, value_name = "SECONDS", default_value_t = crate::services::long_poll::DEFAULT_INTERVAL_SECS, requires = "long_poll")]
+    pub poll_interval: u64,
+
     /// Request timeout in seconds
     #[arg(long, default_value_t = 30)]
     pub timeout: u64,
 
-    /// Test scenarios to run (comma-separated)
+    /// Time allowed for DNS resolution plus the TCP connect, in seconds (default: 10)
+    #[arg(long, value_name = "SECONDS")]
+    pub connect_timeout: Option<u64>,
+
+    /// Time allowed for the TLS handshake, in seconds (default: 10)
+    #[arg(long, value_name = "SECONDS")]
+    pub tls_timeout: Option<u64>,
+
+    /// Maximum requests per second across the whole run (avoids tripping 429s on S0/F0 tiers)
+    #[arg(long)]
+    pub max_rps: Option<f64>,
+
+    /// Retry a request once after its Retry-After delay when it gets HTTP 429
+    #[arg(long, default_value_t = false)]
+    pub retry_throttled: bool,
+
+    /// Count throttled (HTTP 429) results as failures instead of reporting them separately
+    #[arg(long, default_value_t = false)]
+    pub fail_on_throttle: bool,
+
+    /// Which results make the exit code non-zero (default: failed)
+    #[arg(long, value_enum)]
+    pub fail_on: Option<FailOnArg>,
+
+    /// Report successful scenarios slower than this as slow (a warning)
+    #[arg(long, value_name = "MS")]
+    pub max_latency_ms: Option<u64>,
+
+    /// Count slow results (over --max-latency-ms) as failures instead of warnings
+    #[arg(long, default_value_t = false)]
+    pub fail_on_slow: bool,
+
+    /// Test scenarios to run (comma-separated ids, or tag:<tag> such as tag:fast)
     #[arg(long, value_delimiter = ',')]
     pub scenarios: Option<Vec<String>>,
 
+    /// Test scenarios to skip (comma-separated ids, or tag:<tag> such as tag:async-poll)
+    #[arg(long, value_delimiter = ',', value_name = "SCENARIOS")]
+    pub skip_scenarios: Vec<String>,
+
+    /// Named scenario set: smoke (reachability plus one authenticated call per
+    /// service), standard, full, or a profile from [test_profiles] in the config
+    #[arg(long, value_name = "NAME", conflicts_with = "scenarios")]
+    pub profile: Option<String>,
+
     /// Custom endpoint URL (overrides region-based endpoint)
     #[arg(long)]
     pub endpoint: Option<String>,
 
+    /// Zero-trust check: fail the run if the services' public regional endpoints
+    /// are reachable, and test them through the private --endpoint only
+    #[arg(long, default_value_t = false)]
+    pub assert_blocked: bool,
+
+    /// The endpoint is a disconnected or on-prem Azure AI container (speech, language,
+    /// translator): run its /ready and /status health checks and the APIs it serves
+    #[arg(long, default_value_t = false)]
+    pub container: bool,
+
+    /// Accept plain http:// endpoints, such as a container on a private network
+    #[arg(long, default_value_t = false)]
+    pub allow_insecure_http: bool,
+
+    /// Extra header for every request, as 'Name: Value' (repeatable)
+    #[arg(short = 'H', long = "header", value_parser = parse_header_arg)]
+    pub headers: Vec<(String, String)>,
+
+    /// Header that carries the API key (e.g. for an API Management gateway)
+    #[arg(long)]
+    pub api_key_header: Option<String>,
+
     /// Write output to file
     #[arg(long)]
     pub output_file: Option<PathBuf>,
 
+    /// Show a live full-screen table of scenarios instead of the progress bar
+    #[arg(long, default_value_t = false)]
+    pub tui: bool,
+
+    /// POST the JSON report to this webhook when the run completes (repeatable)
+    #[arg(long, value_name = "URL", value_parser = parse_notify_url_arg)]
+    pub notify_url: Vec<crate::notify::NotifyTarget>,
+
+    /// Send a notification card: slack:<webhook-url>, teams:<webhook-url>, or webhook:<url> (repeatable)
+    #[arg(long, value_name = "KIND:URL", value_parser = parse_notify_arg)]
+    pub notify: Vec<crate::notify::NotifyTarget>,
+
+    /// When to notify: after every run, on failure (per --fail-on), or on regressions
+    #[arg(long, value_enum, default_value_t = NotifyOnArg::Always)]
+    pub notify_on: NotifyOnArg,
+
+    /// Email the report to this address, as --notify-on says (repeatable; needs --smtp-server)
+    #[arg(long, value_name = "ADDR", value_parser = parse_mailbox_arg, requires = "smtp_server")]
+    pub email_to: Vec<lettre::message::Mailbox>,
+
+    /// SMTP server for --email-to: host[:port] (STARTTLS, or TLS on 465) or an
+    /// smtp:// or smtps:// URL. Credentials come from AZURE_AITOOLSCONNECT_SMTP_USERNAME
+    /// and AZURE_AITOOLSCONNECT_SMTP_PASSWORD
+    #[arg(
+        long,
+        value_name = "HOST[:PORT]",
+        env = "AZURE_AITOOLSCONNECT_SMTP_SERVER"
+    )]
+    pub smtp_server: Option<String>,
+
+    /// Sender of report emails (default: the SMTP user name)
+    #[arg(long, value_name = "ADDR", value_parser = parse_mailbox_arg)]
+    pub email_from: Option<lettre::message::Mailbox>,
+
+    /// Email body: an HTML summary with the human report as plain-text alternative, or the human report only
+    #[arg(long, value_enum, default_value_t = EmailFormatArg::Html)]
+    pub email_format: EmailFormatArg,
+
+    /// Name of this site or network segment, recorded in the report for 'aggregate'
+    #[arg(long, value_name = "NAME")]
+    pub location: Option<String>,
+
+    /// Send the JSON report to a serve controller (http://<host>:9464/reports),
+    /// a blob container (blob:<container URL>), or a Log Analytics workspace
+    /// (log-analytics:<workspace ID>), besides the [upload] targets (repeatable)
+    #[arg(long, value_name = "TARGET", value_parser = parse_upload_target_arg)]
+    pub upload: Vec<crate::upload::UploadTarget>,
+
+    /// Deprecated alias of --upload
+    #[arg(long, value_name = "URL", value_parser = parse_upload_target_arg, hide = true)]
+    pub report_to: Vec<crate::upload::UploadTarget>,
+
+    /// Record sanitized request/response metadata for every scenario into this directory
+    #[arg(long, value_name = "DIR")]
+    pub capture: Option<PathBuf>,
+
+    /// Save scenario output (synthesized audio, transcripts, Vision and Document
+    /// Intelligence results) into this directory as <service>-<scenario>.<ext>
+    #[arg(long, value_name = "DIR")]
+    pub save_artifacts: Option<PathBuf>,
+
+    /// Run against an embedded mock server with canned Azure responses (offline self-check)
+    #[arg(long, default_value_t = false)]
+    pub mock: bool,
+
+    /// Print the effective settings and the scenarios that would run, without
+    /// authenticating or sending any request
+    #[arg(long, default_value_t = false, conflicts_with_all = ["mock", "replay", "tui"])]
+    pub dry_run: bool,
+
+    /// Print every request a run would send (method, URL, payload type) and
+    /// the hosts it reaches, without authenticating or sending anything
+    #[arg(long, default_value_t = false, conflicts_with_all = ["mock", "replay", "tui"])]
+    pub plan: bool,
+
+    /// Save the report and every sanitized request/response to a session file
+    #[arg(long, value_name = "FILE")]
+    pub record: Option<PathBuf>,
+
+    /// Render a session file written by --record instead of running tests
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["record", "capture", "save_artifacts", "mock", "tui"])]
+    pub replay: Option<PathBuf>,
+
     /// Display the bearer token after authentication (for use in curl/Postman)
     #[arg(long, default_value_t = false)]
     pub show_token: bool,
 
+    /// With --show-token, also decode the token's claims and warn about its audience and expiry
+    #[arg(long, default_value_t = false, requires = "show_token")]
+    pub decode: bool,
+
     /// Skip reading cached tokens from disk
     #[arg(long, default_value_t = false)]
     pub no_cache: bool,
+
+    /// Voice the voices_list scenario must find (e.g. en-GB-SoniaNeural); against a
+    /// text-to-speech container, the voice tts synthesizes with
+    #[arg(long, value_name = "VOICE")]
+    pub expect_voice: Option<String>,
+
+    /// Custom Neural Voice deployment ID for the custom_voice scenario
+    #[arg(long, value_name = "ID")]
+    pub custom_voice_deployment: Option<String>,
+
+    /// Custom Neural Voice name for the custom_voice scenario
+    #[arg(long, value_name = "VOICE")]
+    pub custom_voice: Option<String>,
+
+    /// Custom Translator category ID for the translate_category scenario
+    #[arg(long)]
+    pub translator_category: Option<String>,
+
+    /// Document Intelligence model ID for the custom_model scenario
+    #[arg(long)]
+    pub di_model: Option<String>,
+
+    /// API version for a service's requests, as 'SERVICE=VERSION' (repeatable);
+    /// for Document Intelligence, disables falling back to older versions
+    #[arg(long = "api-version", value_name = "SERVICE=VERSION", value_parser = parse_api_version_arg)]
+    pub api_versions: Vec<(String, String)>,
+
+    /// AI Foundry project for the agents_list scenario
+    #[arg(long)]
+    pub foundry_project: Option<String>,
+
+    /// AI Foundry model deployment for the model_inference scenario
+    #[arg(long)]
+    pub foundry_deployment: Option<String>,
+
+    /// Model deployment for the model_inference service (optional on serverless endpoints)
+    #[arg(long)]
+    pub inference_deployment: Option<String>,
+
+    /// Azure OpenAI chat deployment for the chat_completions scenario
+    #[arg(long)]
+    pub openai_deployment: Option<String>,
+
+    /// Azure OpenAI realtime deployment for the realtime_ws scenario
+    #[arg(long)]
+    pub realtime_deployment: Option<String>,
+
+    /// ARM resource ID of the tested account; inspects its network ACLs and
+    /// private endpoints to explain failures
+    #[arg(long)]
+    pub resource_id: Option<String>,
+
+    /// ARM access token for --resource-id (defaults to 'az account get-access-token')
+    #[arg(long, env = "AZURE_ARM_TOKEN", hide_env_values = true)]
+    pub arm_token: Option<String>,
+
+    /// Add the --resource-id account's quota and usage (deployment rate limits,
+    /// regional model quota) to the report, and flag quota near its limit
+    #[arg(long, default_value_t = false, requires = "resource_id")]
+    pub usage: bool,
+
+    /// Pricing tier of the tested resource (e.g. F0, S0): scenarios it doesn't
+    /// offer are skipped. Read from the --resource-id account when not given
+    #[arg(long, value_name = "SKU")]
+    pub sku: Option<String>,
+
+    /// Also look up the egress IP (via the IP echo service) and probe the Azure
+    /// platform endpoints (IMDS, wire server) for the environment report
+    #[arg(long, default_value_t = false, conflicts_with = "no_environment")]
+    pub environment: bool,
+
+    /// Don't capture the client environment (proxy, OS, DNS servers, clock skew) in the report
+    #[arg(long, default_value_t = false)]
+    pub no_environment: bool,
 }
 
 #[derive(Args, Debug)]
@@ -190,10 +807,16 @@ pub struct LoginArgs {
     #[arg(long, value_enum, default_value_t = CloudArg::Global)]
     pub cloud: CloudArg,
 
-    /// Custom public client ID (advanced)
+    /// Custom public client ID (advanced); with managed identity, the client ID
+    /// of a user-assigned identity (env: AZURE_MI_CLIENT_ID)
     #[arg(long)]
     pub client_id: Option<String>,
 
+    /// Scope or audience for Entra ID tokens instead of the Cognitive Services
+    /// default, e.g. https://ai.azure.com or an API Management backend's app ID URI
+    #[arg(long, visible_alias = "audience", value_name = "SCOPE")]
+    pub scope: Option<String>,
+
     /// Output format
     #[arg(short, long, value_enum, default_value_t = OutputFormatArg::Human)]
     pub output: OutputFormatArg,
@@ -222,18 +845,155 @@ pub struct DiagnoseArgs {
     #[arg(long, default_value_t = false)]
     pub latency: bool,
 
-    /// Target endpoint for diagnostics
-    #[arg(short, long)]
-    pub endpoint: Option<String>,
+    /// Check reachability of certificate revocation endpoints (OCSP/CRL)
+    #[arg(long, default_value_t = false)]
+    pub revocation: bool,
+
+    /// Compare system DNS answers with a public DNS-over-HTTPS resolver, flagging
+    /// split-horizon DNS and filtering
+    #[arg(long, default_value_t = false)]
+    pub doh: bool,
+
+    /// DNS-over-HTTPS resolver (JSON API) for --doh [default: https://cloudflare-dns.com/dns-query];
+    /// implies --doh
+    #[arg(long, value_name = "URL")]
+    pub doh_url: Option<String>,
+
+    /// Report each resolved address's reverse DNS name and owning network (ASN), looked up
+    /// through the DoH resolver
+    #[arg(long, default_value_t = false)]
+    pub enrich: bool,
+
+    /// Azure ServiceTags JSON file (ServiceTags_Public_*.json) to find the region of each
+    /// resolved address, flagging hosts that resolve into another region's ranges
+    #[arg(long, value_name = "PATH")]
+    pub service_tags: Option<PathBuf>,
+
+    /// Extra TCP ports to check, comma-separated: PORT checks every endpoint, HOST:PORT one host
+    /// (e.g. 8443,5671,gateway.contoso.com:5672)
+    #[arg(long, value_name = "PORTS", value_delimiter = ',', value_parser = parse_port_arg)]
+    pub ports: Vec<crate::network::PortSpec>,
+
+    /// Open N simultaneous connections to each endpoint, each with one lightweight request,
+    /// to find proxy or firewall connection limits that only show under load
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..=500))]
+    pub concurrency: Option<u16>,
+
+    /// Target endpoints for diagnostics, comma-separated host names or URLs; replaces the
+    /// region's endpoints
+    #[arg(short, long, value_delimiter = ',')]
+    pub endpoint: Vec<String>,
+
+    /// Read target endpoints from a file, one host name or URL per line ('#' starts a comment)
+    #[arg(long, value_name = "PATH")]
+    pub endpoints_file: Option<PathBuf>,
+
+    /// Send ICMP echo probes to each endpoint with the system ping, reporting packet loss,
+    /// jitter and path MTU (ICMP is often blocked; may need privileges on some systems)
+    #[arg(long, default_value_t = false)]
+    pub icmp: bool,
+
+    /// ICMP echo probes per endpoint for --icmp
+    #[arg(long, value_name = "N", default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..=100))]
+    pub icmp_count: u32,
+
+    /// Also look up the egress IP (via the IP echo service) and probe the Azure
+    /// platform endpoints (IMDS, wire server) for the environment report
+    #[arg(long, default_value_t = false, conflicts_with = "no_environment")]
+    pub environment: bool,
+
+    /// Don't capture the client environment (proxy, OS, DNS servers, clock skew)
+    #[arg(long, default_value_t = false)]
+    pub no_environment: bool,
+
+    /// Number of hosts to check at once
+    #[arg(long, value_name = "N", default_value_t = 8, value_parser = clap::value_parser!(u16).range(1..=64))]
+    pub parallel: u16,
+
+    /// Check one host at a time (same as --parallel 1), for low-bandwidth links or to keep
+    /// latency measurements from competing with each other
+    #[arg(long, default_value_t = false, conflicts_with = "parallel")]
+    pub sequential: bool,
+
+    /// Azure regions, comma-separated [default: eastus]
+    #[arg(
+        short,
+        long,
+        env = "AZURE_REGION",
+        value_delimiter = ',',
+        visible_alias = "regions"
+    )]
+    pub region: Vec<String>,
+
+    /// Cloud environment
+    #[arg(long, value_enum, default_value_t = CloudArg::Global)]
+    pub cloud: CloudArg,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormatArg::Human)]
+    pub output: OutputFormatArg,
+}
+
+#[derive(Args, Debug)]
+#[command(after_help = DOCTOR_EXAMPLES)]
+pub struct DoctorArgs {
+    /// Services to check (comma-separated, or 'all')
+    #[arg(short, long, default_value = "all", value_delimiter = ',')]
+    pub services: Vec<String>,
+
+    /// API key for authentication
+    #[arg(long, env = "AZURE_AI_API_KEY")]
+    pub api_key: Option<String>,
+
+    /// Read the API key from a Key Vault secret (https://<vault>.vault.azure.net/secrets/<name>);
+    /// takes precedence over --api-key
+    #[arg(long, env = "AZURE_KEYVAULT_SECRET")]
+    pub keyvault_secret: Option<String>,
 
     /// Azure region
     #[arg(short, long, env = "AZURE_REGION")]
     pub region: Option<String>,
 
+    /// Custom endpoint URL (overrides region-based endpoint)
+    #[arg(long)]
+    pub endpoint: Option<String>,
+
+    /// Authentication method
+    #[arg(long, value_enum, default_value_t = AuthMethodArg::Key)]
+    pub auth: AuthMethodArg,
+
+    /// Tenant ID (required for device-code auth method)
+    #[arg(long, env = "AZURE_USER_TENANT_ID")]
+    pub tenant: Option<String>,
+
+    /// Bearer token (for token auth method)
+    #[arg(long, env = "AZURE_BEARER_TOKEN")]
+    pub bearer_token: Option<String>,
+
+    /// Scope or audience for Entra ID tokens instead of the Cognitive Services
+    /// default, e.g. https://ai.azure.com or an API Management backend's app ID URI
+    #[arg(long, visible_alias = "audience", value_name = "SCOPE")]
+    pub scope: Option<String>,
+
     /// Cloud environment
     #[arg(long, value_enum, default_value_t = CloudArg::Global)]
     pub cloud: CloudArg,
 
+    /// Request timeout in seconds
+    #[arg(long, default_value_t = 30)]
+    pub timeout: u64,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormatArg::Human)]
+    pub output: OutputFormatArg,
+}
+
+#[derive(Args, Debug)]
+pub struct SelftestArgs {
+    /// Directory to check for writing output files
+    #[arg(long, default_value = ".")]
+    pub dir: PathBuf,
+
     /// Output format
     #[arg(short, long, value_enum, default_value_t = OutputFormatArg::Human)]
     pub output: OutputFormatArg,
@@ -241,9 +1001,13 @@ pub struct DiagnoseArgs {
 
 #[derive(Args, Debug)]
 pub struct InitArgs {
-    /// Output path for the configuration file
-    #[arg(short, long, default_value = "./config.toml")]
-    pub output: PathBuf,
+    /// Output path for the configuration file (default: ./config.toml)
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Write to the per-user config file, which every command finds without --config
+    #[arg(long, default_value_t = false, conflicts_with = "output")]
+    pub user: bool,
 
     /// Overwrite existing file
     #[arg(long, default_value_t = false)]
@@ -255,10 +1019,19 @@ pub struct InitArgs {
 }
 
 #[derive(Args, Debug)]
+#[command(after_help = VALIDATE_EXAMPLES)]
 pub struct ValidateArgs {
     /// Path to configuration file to validate
     #[arg(short, long, default_value = "./config.toml")]
     pub config: PathBuf,
+
+    /// Fail on keys that don't map to any setting (e.g. typos), with line and column
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Print the JSON Schema of the config file and exit
+    #[arg(long)]
+    pub schema: bool,
 }
 
 #[derive(Args, Debug)]
@@ -266,6 +1039,300 @@ pub struct ListScenariosArgs {
     /// Service to list scenarios for
     #[arg(short, long)]
     pub service: Option<String>,
+
+    /// Output format: a human-readable list, or with json a machine-readable
+    /// catalog (tags, input, credentials, and request of every scenario)
+    #[arg(short, long, value_enum, default_value_t = OutputFormatArg::Human)]
+    pub output: OutputFormatArg,
+}
+
+#[derive(Args, Debug)]
+#[command(after_help = CAPABILITIES_EXAMPLES)]
+pub struct CapabilitiesArgs {
+    /// Azure region to check (omit to list all region-restricted scenarios)
+    #[arg(short, long, env = "AZURE_REGION")]
+    pub region: Option<String>,
+
+    /// Cloud environment
+    #[arg(long, value_enum, default_value_t = CloudArg::Global)]
+    pub cloud: CloudArg,
+
+    /// Only show this service
+    #[arg(short, long)]
+    pub service: Option<String>,
+}
+
+#[derive(Args, Debug)]
+#[command(after_help = ENDPOINT_EXAMPLES)]
+pub struct EndpointArgs {
+    /// Services to show (comma-separated, or 'all')
+    #[arg(short, long, default_value = "all", value_delimiter = ',')]
+    pub services: Vec<String>,
+
+    /// Azure region
+    #[arg(short, long, env = "AZURE_REGION", default_value = "eastus")]
+    pub region: String,
+
+    /// Cloud environment
+    #[arg(long, value_enum, default_value_t = CloudArg::Global)]
+    pub cloud: CloudArg,
+
+    /// Custom subdomain of the resource (e.g. contoso), or its full host name
+    #[arg(long, value_name = "NAME", conflicts_with = "endpoint")]
+    pub custom_domain: Option<String>,
+
+    /// Endpoint to check, and to derive the URLs from
+    #[arg(long, value_name = "URL")]
+    pub endpoint: Option<String>,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormatArg::Human)]
+    pub output: OutputFormatArg,
+}
+
+#[derive(Args, Debug)]
+#[command(after_help = DISCOVER_EXAMPLES)]
+pub struct DiscoverArgs {
+    /// Subscription ID to search (defaults to all subscriptions visible to the token)
+    #[arg(long, env = "AZURE_SUBSCRIPTION_ID")]
+    pub subscription: Option<String>,
+
+    /// ARM access token (defaults to 'az account get-access-token')
+    #[arg(long, env = "AZURE_ARM_TOKEN", hide_env_values = true)]
+    pub arm_token: Option<String>,
+
+    /// Cloud environment
+    #[arg(long, value_enum, default_value_t = CloudArg::Global)]
+    pub cloud: CloudArg,
+
+    /// Fetch account keys (requires listKeys permission) for the generated config
+    #[arg(long, default_value_t = false)]
+    pub fetch_keys: bool,
+
+    /// Write a configuration file populated with the discovered endpoints
+    #[arg(long)]
+    pub write_config: Option<PathBuf>,
+
+    /// Overwrite the configuration file if it exists
+    #[arg(long, default_value_t = false)]
+    pub force: bool,
+
+    /// Request timeout in seconds
+    #[arg(short, long, default_value_t = 30)]
+    pub timeout: u64,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormatArg::Human)]
+    pub output: OutputFormatArg,
+}
+
+#[derive(Args, Debug)]
+#[command(after_help = EGRESS_IP_EXAMPLES)]
+pub struct EgressIpArgs {
+    /// ARM resource ID of the account whose IP rules to check
+    #[arg(long)]
+    pub resource_id: Option<String>,
+
+    /// ARM access token for --resource-id (defaults to 'az account get-access-token')
+    #[arg(long, env = "AZURE_ARM_TOKEN", hide_env_values = true)]
+    pub arm_token: Option<String>,
+
+    /// Cloud environment
+    #[arg(long, value_enum, default_value_t = CloudArg::Global)]
+    pub cloud: CloudArg,
+
+    /// Request timeout in seconds
+    #[arg(long, default_value_t = 30)]
+    pub timeout: u64,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormatArg::Human)]
+    pub output: OutputFormatArg,
+}
+
+#[derive(Args, Debug)]
+#[command(after_help = USAGE_EXAMPLES)]
+pub struct UsageArgs {
+    /// ARM resource ID of an account (repeatable; default: the accounts in
+    /// --subscription behind the configured endpoints, or all of them)
+    #[arg(long)]
+    pub resource_id: Vec<String>,
+
+    /// Subscription ID to search (defaults to all subscriptions visible to the token)
+    #[arg(long, env = "AZURE_SUBSCRIPTION_ID")]
+    pub subscription: Option<String>,
+
+    /// ARM access token (defaults to 'az account get-access-token')
+    #[arg(long, env = "AZURE_ARM_TOKEN", hide_env_values = true)]
+    pub arm_token: Option<String>,
+
+    /// Cloud environment
+    #[arg(long, value_enum, default_value_t = CloudArg::Global)]
+    pub cloud: CloudArg,
+
+    /// Request timeout in seconds
+    #[arg(long, default_value_t = 30)]
+    pub timeout: u64,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormatArg::Human)]
+    pub output: OutputFormatArg,
+}
+
+#[derive(Args, Debug)]
+pub struct EnvArgs {
+    /// Only list variables that are set
+    #[arg(long, default_value_t = false)]
+    pub set: bool,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormatArg::Human)]
+    pub output: OutputFormatArg,
+}
+
+#[derive(Args, Debug)]
+pub struct ServeArgs {
+    /// Address for /healthz, /last-report.json, /history.json, and /metrics
+    /// (default: [serve] listen, or 127.0.0.1:9464)
+    #[arg(long, value_name = "ADDR")]
+    pub listen: Option<std::net::SocketAddr>,
+
+    /// Reports kept per job (default: [serve] history, or 20)
+    #[arg(long, value_name = "COUNT")]
+    pub history: Option<usize>,
+
+    /// Also write each report to this directory as <job>-<timestamp>.json
+    #[arg(long, value_name = "DIR")]
+    pub history_dir: Option<PathBuf>,
+
+    /// Run every job once at startup, then follow the schedules
+    #[arg(long, default_value_t = false)]
+    pub run_now: bool,
+
+    /// Accept reports POSTed by agents to /reports (default: [serve] accept_reports)
+    #[arg(long, default_value_t = false)]
+    pub accept_reports: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct AggregateArgs {
+    /// Report files (from 'test -o json' or --record) or directories of them
+    #[arg(required = true, value_name = "PATH")]
+    pub paths: Vec<PathBuf>,
+
+    /// Only show scenarios whose outcome differs between locations
+    #[arg(long, default_value_t = false)]
+    pub differences: bool,
+
+    /// Output format (human, json, or markdown)
+    #[arg(short, long, value_enum, default_value_t = OutputFormatArg::Human)]
+    pub output: OutputFormatArg,
+
+    /// Write output to file
+    #[arg(long)]
+    pub output_file: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub command: ConfigCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Print a setting, e.g. `config get auth.default_method`
+    Get(ConfigGetArgs),
+
+    /// Change a setting, e.g. `config set services.speech.region westus2`
+    Set(ConfigSetArgs),
+
+    /// Remove a setting from the configuration file
+    Unset(ConfigGetArgs),
+
+    /// List the [profile.<name>] sections of the configuration file
+    ListProfiles(ListProfilesArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct ConfigGetArgs {
+    /// Dotted key (e.g. services.speech.region)
+    pub key: String,
+}
+
+#[derive(Args, Debug)]
+pub struct ConfigSetArgs {
+    /// Dotted key (e.g. services.speech.region)
+    pub key: String,
+
+    /// New value: a string, or a TOML value such as true, 60, or ["tts", "stt"]
+    pub value: String,
+}
+
+#[derive(Args, Debug)]
+pub struct ListProfilesArgs {
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormatArg::Human)]
+    pub output: OutputFormatArg,
+}
+
+#[derive(Args, Debug)]
+pub struct TokenArgs {
+    #[command(subcommand)]
+    pub command: TokenCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TokenCommand {
+    /// Decode a bearer token's claims locally and check its audience and expiry
+    Inspect(TokenInspectArgs),
+}
+
+#[derive(Args, Debug)]
+#[command(after_help = TOKEN_INSPECT_EXAMPLES)]
+pub struct TokenInspectArgs {
+    /// Bearer token to inspect ('-' reads it from stdin)
+    #[arg(env = "AZURE_BEARER_TOKEN", hide_env_values = true)]
+    pub token: String,
+
+    /// Cloud the token should be for
+    #[arg(long, value_enum, default_value_t = CloudArg::Global)]
+    pub cloud: CloudArg,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormatArg::Human)]
+    pub output: OutputFormatArg,
+}
+
+#[derive(Args, Debug)]
+#[command(after_help = SUPPORT_BUNDLE_EXAMPLES)]
+pub struct SupportBundleArgs {
+    /// Capture directory written by 'test --capture'
+    #[arg(long, value_name = "DIR")]
+    pub capture: PathBuf,
+
+    /// Archive to write (default: support-bundle-<timestamp>.zip)
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+
+    /// Don't refresh the environment report (keeps the one captured with the test run)
+    #[arg(long, default_value_t = false)]
+    pub no_environment: bool,
+
+    /// Timeout in seconds for environment checks
+    #[arg(long, default_value_t = 10)]
+    pub timeout: u64,
+}
+
+#[derive(Args, Debug)]
+pub struct ListRegionsArgs {
+    /// Cloud environment
+    #[arg(long, value_enum, default_value_t = CloudArg::Global)]
+    pub cloud: CloudArg,
+
+    /// Show which region-restricted scenarios of this service are unavailable per region
+    #[arg(short, long)]
+    pub service: Option<String>,
 }
 
 #[derive(ValueEnum, Clone, Debug, Default)]
@@ -322,12 +1389,74 @@ pub enum LoginAuthMethodArg {
     ManagedIdentity,
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum LogLevelArg {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevelArg> for tracing::level_filters::LevelFilter {
+    fn from(arg: LogLevelArg) -> Self {
+        match arg {
+            LogLevelArg::Off => Self::OFF,
+            LogLevelArg::Error => Self::ERROR,
+            LogLevelArg::Warn => Self::WARN,
+            LogLevelArg::Info => Self::INFO,
+            LogLevelArg::Debug => Self::DEBUG,
+            LogLevelArg::Trace => Self::TRACE,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum LangArg {
+    En,
+    #[value(name = "zh-CN", alias = "zh")]
+    ZhCn,
+}
+
+impl From<LangArg> for crate::i18n::Lang {
+    fn from(arg: LangArg) -> Self {
+        match arg {
+            LangArg::En => crate::i18n::Lang::En,
+            LangArg::ZhCn => crate::i18n::Lang::ZhCn,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum LogFormatArg {
+    #[default]
+    Text,
+    Json,
+}
+
+impl From<LogFormatArg> for crate::logging::LogFormat {
+    fn from(arg: LogFormatArg) -> Self {
+        match arg {
+            LogFormatArg::Text => crate::logging::LogFormat::Text,
+            LogFormatArg::Json => crate::logging::LogFormat::Json,
+        }
+    }
+}
+
 #[derive(ValueEnum, Clone, Debug, Default)]
 pub enum OutputFormatArg {
     #[default]
     Human,
     Json,
     Junit,
+    Markdown,
+    /// JUnit with per-scenario <system-out> and region/cloud properties (GitLab)
+    Gitlab,
+    /// TeamCity service messages
+    Teamcity,
+    /// One JSON object per scenario as it finishes, then a summary object
+    Ndjson,
 }
 
 impl From<OutputFormatArg> for crate::config::OutputFormat {
@@ -336,6 +1465,68 @@ impl From<OutputFormatArg> for crate::config::OutputFormat {
             OutputFormatArg::Human => crate::config::OutputFormat::Human,
             OutputFormatArg::Json => crate::config::OutputFormat::Json,
             OutputFormatArg::Junit => crate::config::OutputFormat::Junit,
+            OutputFormatArg::Markdown => crate::config::OutputFormat::Markdown,
+            OutputFormatArg::Gitlab => crate::config::OutputFormat::Gitlab,
+            OutputFormatArg::Teamcity => crate::config::OutputFormat::Teamcity,
+            OutputFormatArg::Ndjson => crate::config::OutputFormat::Ndjson,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum FailOnArg {
+    /// Fail on failed scenarios
+    Failed,
+    /// Fail on failed or skipped scenarios
+    #[value(name = "failed+skipped")]
+    FailedOrSkipped,
+    /// Never fail; exit 0 whenever the run completes
+    None,
+}
+
+impl From<FailOnArg> for crate::config::FailOn {
+    fn from(arg: FailOnArg) -> Self {
+        match arg {
+            FailOnArg::Failed => crate::config::FailOn::Failed,
+            FailOnArg::FailedOrSkipped => crate::config::FailOn::FailedOrSkipped,
+            FailOnArg::None => crate::config::FailOn::Never,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum NotifyOnArg {
+    /// After every run
+    Always,
+    /// When the run fails
+    Failure,
+    /// When a scenario that passed in the previous run fails
+    Regression,
+}
+
+impl From<NotifyOnArg> for crate::notify::NotifyOn {
+    fn from(arg: NotifyOnArg) -> Self {
+        match arg {
+            NotifyOnArg::Always => crate::notify::NotifyOn::Always,
+            NotifyOnArg::Failure => crate::notify::NotifyOn::Failure,
+            NotifyOnArg::Regression => crate::notify::NotifyOn::Regression,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum EmailFormatArg {
+    /// HTML summary with a plain-text alternative
+    Html,
+    /// The human-readable report as plain text
+    Human,
+}
+
+impl From<EmailFormatArg> for crate::notify::email::EmailFormat {
+    fn from(arg: EmailFormatArg) -> Self {
+        match arg {
+            EmailFormatArg::Html => crate::notify::email::EmailFormat::Html,
+            EmailFormatArg::Human => crate::notify::email::EmailFormat::Human,
         }
     }
 }
@@ -356,6 +1547,68 @@ impl From<CloudArg> for crate::config::Cloud {
     }
 }
 
+/// Parse a `--notify kind:url` argument
+pub fn parse_notify_arg(arg: &str) -> Result<crate::notify::NotifyTarget, String> {
+    arg.parse()
+        .map_err(|e: crate::error::AppError| e.to_string())
+}
+
+/// Parse a `diagnose --ports` entry
+pub fn parse_port_arg(arg: &str) -> Result<crate::network::PortSpec, String> {
+    arg.parse()
+        .map_err(|e: crate::error::AppError| e.to_string())
+}
+
+/// Parse an `--upload` argument
+pub fn parse_upload_target_arg(arg: &str) -> Result<crate::upload::UploadTarget, String> {
+    arg.parse()
+        .map_err(|e: crate::error::AppError| e.to_string())
+}
+
+/// Parse an `--email-to` or `--email-from` address (`ops@contoso.com` or `Ops <ops@contoso.com>`)
+pub fn parse_mailbox_arg(arg: &str) -> Result<lettre::message::Mailbox, String> {
+    arg.parse().map_err(|e: lettre::address::AddressError| {
+        format!("invalid email address '{}': {}", arg, e)
+    })
+}
+
+/// Parse a `--notify-url` argument (always a generic webhook)
+pub fn parse_notify_url_arg(arg: &str) -> Result<crate::notify::NotifyTarget, String> {
+    crate::notify::NotifyTarget::webhook(arg).map_err(|e| e.to_string())
+}
+
+/// Parse a duration argument such as `10m`
+pub fn parse_duration_arg(arg: &str) -> Result<std::time::Duration, String> {
+    crate::services::long_poll::parse_duration(arg).map_err(|e| e.to_string())
+}
+
+/// Parse a `--header 'Name: Value'` argument
+pub fn parse_header_arg(arg: &str) -> Result<(String, String), String> {
+    let (name, value) = arg
+        .split_once(':')
+        .ok_or_else(|| format!("expected 'Name: Value', got '{}'", arg))?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(format!("missing header name in '{}'", arg));
+    }
+    Ok((name.to_string(), value.trim().to_string()))
+}
+
+/// Parse an `--api-version SERVICE=VERSION` argument
+pub fn parse_api_version_arg(arg: &str) -> Result<(String, String), String> {
+    let (service, version) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected 'SERVICE=VERSION', got '{}'", arg))?;
+    let (service, version) = (service.trim(), version.trim());
+    if service.is_empty() || version.is_empty() {
+        return Err(format!("expected 'SERVICE=VERSION', got '{}'", arg));
+    }
+    Ok((
+        service.to_lowercase().replace('-', "_"),
+        version.to_string(),
+    ))
+}
+
 /// Parse services argument, handling "all" specially
 pub fn parse_services(services: &[String]) -> Vec<String> {
     if services.len() == 1 && services[0].to_lowercase() == "all" {
@@ -385,6 +1638,29 @@ mod tests {
         assert!(result.contains(&"speech".to_string()));
     }
 
+    #[test]
+    fn test_parse_header_arg() {
+        assert_eq!(
+            parse_header_arg("x-gateway-key: abc:123").unwrap(),
+            ("x-gateway-key".to_string(), "abc:123".to_string())
+        );
+        assert!(parse_header_arg("no-separator").is_err());
+        assert!(parse_header_arg(": value").is_err());
+    }
+
+    #[test]
+    fn test_parse_api_version_arg() {
+        assert_eq!(
+            parse_api_version_arg("document-intelligence=2023-07-31").unwrap(),
+            (
+                "document_intelligence".to_string(),
+                "2023-07-31".to_string()
+            )
+        );
+        assert!(parse_api_version_arg("2023-07-31").is_err());
+        assert!(parse_api_version_arg("document_intelligence=").is_err());
+    }
+
     #[test]
     fn test_parse_services_specific() {
         let result = parse_services(&["speech".to_string(), "translator".to_string()]);
@@ -434,4 +1710,28 @@ mod tests {
             _ => panic!("expected test command"),
         }
     }
+
+    #[test]
+    fn test_environment_lookups_are_opt_in() {
+        let cli = Cli::try_parse_from(["azure-aitoolsconnect", "test"]).unwrap();
+        match cli.command {
+            Commands::Test(args) => assert!(!args.environment),
+            _ => panic!("expected test command"),
+        }
+
+        let cli =
+            Cli::try_parse_from(["azure-aitoolsconnect", "diagnose", "--environment"]).unwrap();
+        match cli.command {
+            Commands::Diagnose(args) => assert!(args.environment),
+            _ => panic!("expected diagnose command"),
+        }
+
+        assert!(Cli::try_parse_from([
+            "azure-aitoolsconnect",
+            "test",
+            "--environment",
+            "--no-environment",
+        ])
+        .is_err());
+    }
 }
@@ -0,0 +1,240 @@
+//! Get, set, and unset single settings in a config file (`config` subcommand)
+//!
+//! Edits go through `toml_edit`, so comments, key order, and formatting of
+//! the rest of the file are kept. Every edit is validated against the config
+//! model before it is written: misspelled keys and values of the wrong type
+//! are rejected instead of being silently ignored later.
+
+use super::schema::parse_strict;
+use super::Config;
+use crate::error::{AppError, Result};
+use std::path::Path;
+use toml_edit::{DocumentMut, Item, Table, Value};
+
+/// Split a dotted key (`services.speech.region`) into its segments
+fn segments(key: &str) -> Result<Vec<&str>> {
+    let segments: Vec<&str> = key.split('.').collect();
+    if segments.iter().any(|s| s.trim().is_empty()) {
+        return Err(AppError::InvalidInput(format!(
+            "Invalid key '{}': use dotted names like services.speech.region",
+            key
+        )));
+    }
+    Ok(segments)
+}
+
+fn parse_document(content: &str) -> Result<DocumentMut> {
+    content
+        .parse::<DocumentMut>()
+        .map_err(|e| AppError::Config(format!("Cannot parse config: {}", e)))
+}
+
+/// Effective value of `key`: what the file sets, or the default the tool
+/// uses when the file doesn't set it. `None` when the key has no value
+pub fn get_value(content: &str, key: &str) -> Result<Option<String>> {
+    let segments = segments(key)?;
+    let config: Config = toml::from_str(content)?;
+    let root = toml::Value::try_from(&config).map_err(|e| AppError::Config(e.to_string()))?;
+
+    let mut item = &root;
+    for segment in &segments {
+        item = match item {
+            toml::Value::Table(table) => match table.get(*segment) {
+                Some(value) => value,
+                None => return Ok(None),
+            },
+            toml::Value::Array(array) => {
+                match segment.parse::<usize>().ok().and_then(|i| array.get(i)) {
+                    Some(value) => value,
+                    None => return Ok(None),
+                }
+            }
+            _ => return Ok(None),
+        };
+    }
+
+    Ok(Some(match item {
+        toml::Value::String(s) => s.clone(),
+        toml::Value::Table(table) => toml::to_string_pretty(table)
+            .map_err(|e| AppError::Config(e.to_string()))?
+            .trim_end()
+            .to_string(),
+        other => other.to_string(),
+    }))
+}
+
+/// Set `key` to `value` and return the new file content. `value` is read as
+/// a TOML value (`true`, `30`, `["tts", "stt"]`) when the setting accepts
+/// one, and as a plain string otherwise
+pub fn set_value(content: &str, key: &str, value: &str) -> Result<String> {
+    let segments = segments(key)?;
+    let mut candidates = Vec::new();
+    if let Ok(parsed) = value.parse::<Value>() {
+        candidates.push(parsed);
+    }
+    candidates.push(Value::from(value));
+
+    let mut last_error = None;
+    for candidate in candidates {
+        let mut document = parse_document(content)?;
+        insert(document.as_table_mut(), &segments, candidate)?;
+        let updated = document.to_string();
+        match check(&updated, key) {
+            Ok(()) => return Ok(updated),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| AppError::Config(format!("Cannot set '{}'", key))))
+}
+
+/// Remove `key` and return the new file content
+pub fn unset_value(content: &str, key: &str) -> Result<String> {
+    let segments = segments(key)?;
+    let mut document = parse_document(content)?;
+    let (last, parents) = segments.split_last().expect("segments are never empty");
+
+    let mut table = document.as_table_mut() as &mut dyn toml_edit::TableLike;
+    for segment in parents {
+        table = table
+            .get_mut(segment)
+            .and_then(Item::as_table_like_mut)
+            .ok_or_else(|| not_set(key))?;
+    }
+    table.remove(last).ok_or_else(|| not_set(key))?;
+
+    let updated = document.to_string();
+    parse_strict(&updated)?;
+    Ok(updated)
+}
+
+fn not_set(key: &str) -> AppError {
+    AppError::Config(format!("'{}' is not set in the config file", key))
+}
+
+/// Insert `value` at `segments`, creating tables along the way and keeping
+/// the comments around a value it replaces
+fn insert(root: &mut Table, segments: &[&str], mut value: Value) -> Result<()> {
+    let (last, parents) = segments.split_last().expect("segments are never empty");
+
+    let mut table = root as &mut dyn toml_edit::TableLike;
+    for segment in parents {
+        let entry = table.entry(segment).or_insert_with(|| {
+            let mut table = Table::new();
+            table.set_implicit(true);
+            Item::Table(table)
+        });
+        table = entry.as_table_like_mut().ok_or_else(|| {
+            AppError::Config(format!(
+                "'{}' is a value, not a table, in the config file",
+                segment
+            ))
+        })?;
+    }
+
+    match table.get_mut(last) {
+        Some(existing) => {
+            if let Some(old) = existing.as_value() {
+                *value.decor_mut() = old.decor().clone();
+            }
+            *existing = Item::Value(value);
+        }
+        None => {
+            table.insert(last, Item::Value(value));
+        }
+    }
+    Ok(())
+}
+
+/// The edited file must still load, and `key` must be a real setting
+fn check(content: &str, key: &str) -> Result<()> {
+    let (_, unknown) = parse_strict(content)?;
+    let covers = |path: &str| key == path || key.starts_with(&format!("{}.", path));
+    if let Some(unknown) = unknown.iter().find(|u| covers(&u.path)) {
+        return Err(AppError::Config(format!(
+            "Unknown setting '{}' (check the spelling; `validate --schema` lists every key)",
+            unknown.path
+        )));
+    }
+    Ok(())
+}
+
+/// Write `content` to `path` through a temporary file in the same directory,
+/// so an interrupted write never leaves a truncated config behind
+pub fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "config.toml".to_string());
+    let temp = path.with_file_name(format!(".{}.tmp", file_name));
+    std::fs::write(&temp, content)?;
+    std::fs::rename(&temp, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp);
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = "# Main settings\n[global]\ntimeout_seconds = 30 # seconds\n\n[services.speech]\n# Closest region\nregion = \"eastus\"\n";
+
+    #[test]
+    fn test_set_keeps_comments_and_types_values() {
+        let updated = set_value(CONTENT, "services.speech.region", "westus2").unwrap();
+        assert!(updated.contains("# Main settings"));
+        assert!(updated.contains("# Closest region\nregion = \"westus2\""));
+
+        let updated = set_value(&updated, "global.timeout_seconds", "60").unwrap();
+        assert!(updated.contains("timeout_seconds = 60 # seconds"));
+
+        // A number-looking key stays a string where the setting is a string
+        let updated = set_value(&updated, "services.translator.api_key", "12345").unwrap();
+        assert!(updated.contains("api_key = \"12345\""));
+        assert_eq!(
+            get_value(&updated, "services.translator.api_key")
+                .unwrap()
+                .as_deref(),
+            Some("12345")
+        );
+
+        let updated = set_value(
+            &updated,
+            "services.speech.test_scenarios",
+            "[\"tts\", \"stt\"]",
+        )
+        .unwrap();
+        let config: Config = toml::from_str(&updated).unwrap();
+        assert_eq!(config.services["speech"].test_scenarios, vec!["tts", "stt"]);
+    }
+
+    #[test]
+    fn test_set_rejects_unknown_keys_and_bad_values() {
+        let error = set_value(CONTENT, "services.speech.regoin", "westus2").unwrap_err();
+        assert!(error.to_string().contains("services.speech.regoin"));
+        assert!(set_value(CONTENT, "global.timeout_seconds", "soon").is_err());
+        assert!(set_value(CONTENT, "global..cloud", "china").is_err());
+    }
+
+    #[test]
+    fn test_get_and_unset() {
+        assert_eq!(
+            get_value(CONTENT, "services.speech.region")
+                .unwrap()
+                .as_deref(),
+            Some("eastus")
+        );
+        // Defaults are reported for keys the file doesn't set
+        assert_eq!(
+            get_value(CONTENT, "auth.default_method")
+                .unwrap()
+                .as_deref(),
+            Some("key")
+        );
+        assert_eq!(get_value(CONTENT, "services.vision.region").unwrap(), None);
+
+        let updated = unset_value(CONTENT, "services.speech.region").unwrap();
+        assert!(!updated.contains("eastus"));
+        assert!(unset_value(&updated, "services.speech.region").is_err());
+    }
+}
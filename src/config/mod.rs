@@ -1,17 +1,29 @@
+pub mod edit;
+pub mod env;
+pub mod regions;
+pub mod schema;
+
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
 use crate::error::{AppError, Result};
 
 /// Default request timeout in seconds
 pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
 
+/// Default time allowed for DNS resolution plus the TCP connect, in seconds
+pub const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+
+/// Default time allowed for the TLS handshake, in seconds
+pub const DEFAULT_TLS_TIMEOUT_SECS: u64 = 10;
+
 /// Maximum input file size in bytes (10MB)
 pub const MAX_INPUT_FILE_SIZE: u64 = 10 * 1024 * 1024;
 
 /// Cloud environment
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum Cloud {
     #[default]
@@ -30,20 +42,7 @@ impl Cloud {
 
     /// Get the cognitive services token endpoint for this cloud
     pub fn cognitive_token_endpoint(&self, region: &str) -> String {
-        match self {
-            Cloud::Global => {
-                format!(
-                    "https://{}.api.cognitive.microsoft.com/sts/v1.0/issueToken",
-                    region
-                )
-            }
-            Cloud::China => {
-                format!(
-                    "https://{}.api.cognitive.azure.cn/sts/v1.0/issueToken",
-                    region
-                )
-            }
-        }
+        self.cognitive_token_endpoint_for(region, None)
     }
 
     /// Get the cognitive services token endpoint, using a custom endpoint if provided
@@ -52,11 +51,9 @@ impl Cloud {
         region: &str,
         custom_endpoint: Option<&str>,
     ) -> String {
-        if let Some(endpoint) = custom_endpoint {
-            format!("{}/sts/v1.0/issueToken", endpoint.trim_end_matches('/'))
-        } else {
-            self.cognitive_token_endpoint(region)
-        }
+        let endpoint =
+            crate::services::endpoints::ServiceEndpoint::resolve(region, *self, custom_endpoint);
+        format!("{}/sts/v1.0/issueToken", endpoint.url())
     }
 
     /// Get the default cognitive services scope for Entra ID auth
@@ -66,6 +63,22 @@ impl Cloud {
             Cloud::China => "https://cognitiveservices.azure.cn/.default",
         }
     }
+
+    /// Get the Key Vault data-plane resource (token audience) for this cloud
+    pub fn keyvault_resource(&self) -> &'static str {
+        match self {
+            Cloud::Global => "https://vault.azure.net",
+            Cloud::China => "https://vault.azure.cn",
+        }
+    }
+
+    /// Get the Azure Resource Manager (control plane) endpoint for this cloud
+    pub fn arm_endpoint(&self) -> &'static str {
+        match self {
+            Cloud::Global => "https://management.azure.com",
+            Cloud::China => "https://management.chinacloudapi.cn",
+        }
+    }
 }
 
 impl std::fmt::Display for Cloud {
@@ -90,13 +103,20 @@ impl std::str::FromStr for Cloud {
 }
 
 /// Output format for test results
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum OutputFormat {
     #[default]
     Human,
     Json,
     Junit,
+    Markdown,
+    /// JUnit with per-scenario `<system-out>` and suite properties (GitLab)
+    Gitlab,
+    /// TeamCity service messages
+    Teamcity,
+    /// One JSON object per line: each scenario, then the summary
+    Ndjson,
 }
 
 impl std::fmt::Display for OutputFormat {
@@ -105,6 +125,10 @@ impl std::fmt::Display for OutputFormat {
             OutputFormat::Human => write!(f, "human"),
             OutputFormat::Json => write!(f, "json"),
             OutputFormat::Junit => write!(f, "junit"),
+            OutputFormat::Markdown => write!(f, "markdown"),
+            OutputFormat::Gitlab => write!(f, "gitlab"),
+            OutputFormat::Teamcity => write!(f, "teamcity"),
+            OutputFormat::Ndjson => write!(f, "ndjson"),
         }
     }
 }
@@ -117,13 +141,42 @@ impl std::str::FromStr for OutputFormat {
             "human" | "text" | "console" => Ok(OutputFormat::Human),
             "json" => Ok(OutputFormat::Json),
             "junit" | "xml" => Ok(OutputFormat::Junit),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            "gitlab" | "gitlab-junit" => Ok(OutputFormat::Gitlab),
+            "teamcity" => Ok(OutputFormat::Teamcity),
+            "ndjson" | "jsonl" => Ok(OutputFormat::Ndjson),
             _ => Err(AppError::Config(format!("Unknown output format: {}", s))),
         }
     }
 }
 
+/// Which results make `test` exit with a failure code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+pub enum FailOn {
+    /// Failed scenarios (the default)
+    #[default]
+    #[serde(rename = "failed")]
+    Failed,
+    /// Failed or skipped scenarios
+    #[serde(rename = "failed+skipped")]
+    FailedOrSkipped,
+    /// Never; the exit code is 0 whenever the run completes
+    #[serde(rename = "none")]
+    Never,
+}
+
+impl std::fmt::Display for FailOn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FailOn::Failed => write!(f, "failed"),
+            FailOn::FailedOrSkipped => write!(f, "failed+skipped"),
+            FailOn::Never => write!(f, "none"),
+        }
+    }
+}
+
 /// Authentication method
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum AuthMethod {
     #[default]
@@ -190,14 +243,73 @@ impl std::str::FromStr for AuthMethod {
 }
 
 /// Global configuration settings
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct GlobalConfig {
     #[serde(default)]
     pub cloud: Cloud,
     #[serde(default = "default_timeout")]
     pub timeout_seconds: u64,
+    /// Time allowed for DNS resolution plus the TCP connect (default: 10)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_timeout_seconds: Option<u64>,
+    /// Time allowed for the TLS handshake (default: 10)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_timeout_seconds: Option<u64>,
     #[serde(default)]
     pub output_format: OutputFormat,
+    /// Echo endpoint used to discover the public egress IP (returns the IP as plain text)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip_echo_url: Option<String>,
+    /// DNS-over-HTTPS resolver (JSON API) that `diagnose --doh` compares system DNS with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doh_url: Option<String>,
+    /// Extra TCP ports `diagnose` checks, as `PORT` (every endpoint) or `HOST:PORT`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diagnose_ports: Vec<String>,
+    /// Maximum requests per second across the whole test run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_rps: Option<f64>,
+    /// Retry a throttled (HTTP 429) request once after its Retry-After delay
+    #[serde(default)]
+    pub retry_throttled: bool,
+    /// Count throttled (HTTP 429) results as failures
+    #[serde(default)]
+    pub fail_on_throttle: bool,
+    /// Which results make `test` exit with a failure code
+    #[serde(default)]
+    pub fail_on: FailOn,
+    /// Latency threshold; successful scenarios slower than this are reported as slow
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_latency_ms: Option<u64>,
+    /// Count slow results (over max_latency_ms) as failures instead of warnings
+    #[serde(default)]
+    pub fail_on_slow: bool,
+    /// Extra headers added to every request (e.g. for an API Management gateway)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Header that carries the API key (default: Ocp-Apim-Subscription-Key)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_header: Option<String>,
+    /// Accept plain `http://` endpoints, e.g. containers on a private network
+    #[serde(default)]
+    pub allow_insecure_http: bool,
+}
+
+impl GlobalConfig {
+    /// Echo endpoint for egress IP discovery, falling back to the default
+    pub fn ip_echo_url(&self) -> &str {
+        self.ip_echo_url
+            .as_deref()
+            .unwrap_or(crate::network::DEFAULT_IP_ECHO_URL)
+    }
+
+    /// DoH resolver for `diagnose --doh`, falling back to the default
+    pub fn doh_url(&self) -> &str {
+        self.doh_url
+            .as_deref()
+            .unwrap_or(crate::network::DEFAULT_DOH_URL)
+    }
 }
 
 fn default_timeout() -> u64 {
@@ -205,7 +317,8 @@ fn default_timeout() -> u64 {
 }
 
 /// Entra ID (Azure AD) authentication configuration
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct EntraConfig {
     pub tenant_id: Option<String>,
     pub client_id: Option<String>,
@@ -213,7 +326,8 @@ pub struct EntraConfig {
 }
 
 /// User authentication configuration (device code, managed identity, bearer token)
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct UserAuthConfig {
     /// Tenant ID for device code flow
     pub tenant_id: Option<String>,
@@ -226,10 +340,16 @@ pub struct UserAuthConfig {
 }
 
 /// Authentication configuration
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct AuthConfig {
     #[serde(default)]
     pub default_method: AuthMethod,
+    /// Scope or audience to request Entra ID tokens for (e.g.
+    /// "https://ai.azure.com" or an API Management backend's app ID URI)
+    /// instead of the cloud's Cognitive Services scope
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
     #[serde(default)]
     pub entra: EntraConfig,
     #[serde(default)]
@@ -237,7 +357,8 @@ pub struct AuthConfig {
 }
 
 /// Service-specific configuration
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct ServiceConfig {
     #[serde(default = "default_enabled")]
     pub enabled: bool,
@@ -246,6 +367,66 @@ pub struct ServiceConfig {
     pub api_key: Option<String>,
     #[serde(default)]
     pub test_scenarios: Vec<String>,
+    /// Custom Translator category ID (translator only)
+    pub category: Option<String>,
+    /// Custom project name shared by the language custom scenarios that have no
+    /// own key (custom classification), or Foundry project (ai_foundry)
+    pub project_name: Option<String>,
+    /// Deployment name of the custom project (language), or model deployment
+    /// (ai_foundry, model_inference, openai)
+    pub deployment_name: Option<String>,
+    /// Conversational Language Understanding project name (language only, falls back to project_name)
+    pub clu_project_name: Option<String>,
+    /// Conversational Language Understanding deployment name (language only, falls back to deployment_name)
+    pub clu_deployment_name: Option<String>,
+    /// Custom NER project name (language only, falls back to project_name)
+    pub ner_project_name: Option<String>,
+    /// Custom NER deployment name (language only, falls back to deployment_name)
+    pub ner_deployment_name: Option<String>,
+    /// Question Answering project name (language only, falls back to project_name)
+    pub qa_project_name: Option<String>,
+    /// Question Answering deployment name (language only, falls back to deployment_name)
+    pub qa_deployment_name: Option<String>,
+    /// Realtime model deployment for the realtime_ws scenario (openai only)
+    pub realtime_deployment: Option<String>,
+    /// Voice that voices_list must find, by short or full name, and the voice
+    /// tts uses against a container (speech only)
+    pub expect_voice: Option<String>,
+    /// Custom Neural Voice deployment ID for the custom_voice scenario (speech only)
+    pub custom_voice_deployment: Option<String>,
+    /// Custom Neural Voice name for the custom_voice scenario (speech only)
+    pub custom_voice: Option<String>,
+    /// Custom or prebuilt model ID (document_intelligence only)
+    pub model_id: Option<String>,
+    /// API version pinned for this service's primary API, overriding the
+    /// registered GA or preview version; for document_intelligence, older
+    /// versions are only tried when unset
+    pub api_version: Option<String>,
+    /// Analysis parameters per scenario, merged into the request's `parameters`
+    /// (language only), e.g. `options.sentiment = { opinionMining = true }`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub options: BTreeMap<String, serde_json::Map<String, serde_json::Value>>,
+    /// Request timeout for this service, overriding the global timeout
+    pub timeout_seconds: Option<u64>,
+    /// Per-scenario request timeouts (scenario ID -> seconds)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub scenario_timeouts: HashMap<String, u64>,
+    /// Named resources of this service, selected with `--target`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub resources: BTreeMap<String, ResourceConfig>,
+    /// The endpoint is a disconnected or on-prem Azure AI container (speech,
+    /// language, translator); only container scenarios run against it
+    #[serde(default)]
+    pub container: bool,
+}
+
+/// A named resource of a service (`[services.<name>.resources.<target>]`)
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct ResourceConfig {
+    pub endpoint: Option<String>,
+    pub region: Option<String>,
+    pub api_key: Option<String>,
 }
 
 fn default_enabled() -> bool {
@@ -253,16 +434,243 @@ fn default_enabled() -> bool {
 }
 
 /// Custom input files configuration
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct CustomInputs {
+    /// Audio sent by speech scenarios (--audio-file)
     pub audio_file: Option<String>,
+    /// Document sent by Document Intelligence scenarios (--document-file)
     pub document_file: Option<String>,
+    /// Image sent by vision scenarios (--image-file)
     pub image_file: Option<String>,
+    /// Text sent by language and translator scenarios (--text)
     pub text: Option<String>,
+    /// Strings the analysis of an input document must contain, keyed by its
+    /// file name (checked with --verify-content)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub expected_content: BTreeMap<String, Vec<String>>,
+}
+
+/// User-defined HTTP probe (`[[custom_scenarios]]`)
+///
+/// `url_template`, header values, and the body may reference `${endpoint}`,
+/// `${region}`, `${cloud}`, `${api_key}`, `${token}`, and `${env:NAME}`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct CustomScenario {
+    /// Scenario ID (used with --scenarios and in reports)
+    pub name: String,
+    /// Optional description shown in reports
+    pub description: Option<String>,
+    /// HTTP method
+    #[serde(default = "default_method")]
+    pub method: String,
+    /// Request URL, with variable substitution
+    pub url_template: String,
+    /// Built-in service whose endpoint `${endpoint}` refers to (default: --endpoint or the regional endpoint)
+    pub service: Option<String>,
+    /// Extra request headers, with variable substitution
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Inline request body, with variable substitution
+    pub body: Option<String>,
+    /// Path to a file used as the request body, with variable substitution
+    pub body_file: Option<String>,
+    /// Accepted status codes (a single code or a list; default: any 2xx)
+    #[serde(default, deserialize_with = "deserialize_status_list")]
+    #[schemars(with = "StatusList")]
+    pub expect_status: Vec<u16>,
+    /// Text the response body must contain
+    pub expect_body_contains: Option<String>,
+    /// Send the configured credentials (Ocp-Apim-Subscription-Key or Authorization)
+    #[serde(default = "default_enabled")]
+    pub auth: bool,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+/// Outcome a scenario is expected to have
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Expect {
+    /// The scenario should pass (the default for every scenario)
+    #[default]
+    Pass,
+    /// The scenario should fail, e.g. because policy blocks the service
+    Fail,
+}
+
+impl std::fmt::Display for Expect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expect::Pass => write!(f, "pass"),
+            Expect::Fail => write!(f, "fail"),
+        }
+    }
+}
+
+/// Expected outcome of a service's scenarios (`[[expectations]]`), for
+/// environments where some services are blocked on purpose. An expected
+/// failure doesn't count against the exit code; a scenario expected to fail
+/// that passes is reported as a failure
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct Expectation {
+    /// Service name (a built-in service, `custom`, or a custom service)
+    pub service: String,
+    /// Scenario ID; every scenario of the service when omitted. An entry for
+    /// a scenario takes precedence over one for the whole service
+    pub scenario: Option<String>,
+    /// Expected outcome
+    pub expect: Expect,
+    /// Why, shown with the result (e.g. "blocked by egress policy")
+    pub reason: Option<String>,
+}
+
+/// A service defined in the config file (`[custom_services.<name>]`), e.g. an
+/// internal gateway, tested by name like the built-in services
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct CustomServiceConfig {
+    /// Name shown in reports (default: the table name)
+    pub display_name: Option<String>,
+    /// Endpoint `${endpoint}` expands to when --endpoint isn't given
+    pub endpoint: Option<String>,
+    /// Probes, in the same format as [[custom_scenarios]]
+    #[serde(default)]
+    pub scenarios: Vec<CustomScenario>,
+}
+
+/// Scheduled runs of the `serve` daemon (`[serve]`)
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct ServeConfig {
+    /// Address the status endpoint listens on (default: 127.0.0.1:9464)
+    pub listen: Option<String>,
+    /// Reports kept per job (default: 20)
+    pub history: Option<usize>,
+    /// Directory each report is also written to, as <job>-<timestamp>.json
+    pub history_dir: Option<PathBuf>,
+    /// Deprecated: use [upload] location
+    pub location: Option<String>,
+    /// Deprecated: use [upload] targets
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub report_to: Vec<String>,
+    /// Accept reports POSTed by agents to /reports and serve their matrix
+    #[serde(default)]
+    pub accept_reports: bool,
+    /// Shared secret agents send as a bearer token with their reports;
+    /// required by the controller when set
+    pub token: Option<String>,
+    /// Test suites and their schedules
+    #[serde(default)]
+    pub jobs: Vec<ScheduledJob>,
+}
+
+impl ServeConfig {
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Where every `test` and `serve` report is sent (`[upload]`)
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct UploadConfig {
+    /// Upload targets: a controller's URL (http://<host>:9464/reports),
+    /// blob:<container URL, with a SAS token or using the Entra credential>,
+    /// or log-analytics:<workspace ID>
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<String>,
+    /// Name of this site in uploaded and aggregated reports (default: the host name)
+    pub location: Option<String>,
+    /// Bearer token sent to controllers (their [serve] token)
+    pub token: Option<String>,
+    /// Shared key of the Log Analytics workspace
+    pub log_analytics_key: Option<String>,
+    /// Custom log type; records land in <log_type>_CL (default: AIToolsConnect)
+    pub log_type: Option<String>,
+}
+
+impl UploadConfig {
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// A test suite run on a schedule (`[[serve.jobs]]`)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct ScheduledJob {
+    /// Job name, used in logs, metrics, and history file names
+    pub name: String,
+    /// Cron expression (minute hour day-of-month month day-of-week, UTC),
+    /// e.g. "*/15 * * * *"
+    pub schedule: String,
+    /// Services to test (default: all)
+    #[serde(default = "default_job_services")]
+    pub services: Vec<String>,
+    /// Test profile (smoke, standard, full, or one from [test_profiles])
+    pub profile: Option<String>,
+    /// Scenarios to run (ids or tag:<tag>)
+    pub scenarios: Option<Vec<String>>,
+    /// Scenarios to skip (ids or tag:<tag>)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skip_scenarios: Vec<String>,
+    /// Region (default: the services' region)
+    pub region: Option<String>,
+    /// Regions to sweep instead of one region
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub regions: Vec<String>,
+    /// Named resources ([services.<name>.resources.<target>]) to test
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<String>,
+    /// Custom endpoint (overrides the region-based endpoint)
+    pub endpoint: Option<String>,
+}
+
+fn default_job_services() -> Vec<String> {
+    vec!["all".to_string()]
+}
+
+/// A single status code or a list of them
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum StatusList {
+    One(u16),
+    Many(Vec<u16>),
+}
+
+/// Accept `expect_status = 200` as well as `expect_status = [200, 202]`
+fn deserialize_status_list<'de, D>(deserializer: D) -> std::result::Result<Vec<u16>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(match StatusList::deserialize(deserializer)? {
+        StatusList::One(code) => vec![code],
+        StatusList::Many(codes) => codes,
+    })
+}
+
+/// Merge `overrides` into `base`: tables merge recursively, any other value replaces
+fn merge_tables(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(table)) => {
+                merge_tables(existing, table)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
 }
 
 /// Complete application configuration
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct Config {
     #[serde(default)]
     pub global: GlobalConfig,
@@ -272,6 +680,40 @@ pub struct Config {
     pub services: HashMap<String, ServiceConfig>,
     #[serde(default)]
     pub custom_inputs: CustomInputs,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub custom_scenarios: Vec<CustomScenario>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom_services: BTreeMap<String, CustomServiceConfig>,
+    /// Expected outcomes of scenarios that are blocked on purpose
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expectations: Vec<Expectation>,
+    /// Scenario sets for `test --profile <name>`, by service: ids or
+    /// `tag:<tag>`. Replaces the built-in `smoke`, `standard`, or `full` sets
+    /// of the services listed, or defines a new profile
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub test_profiles: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+    /// Named profiles (`[profile.prod]`) selected with `--config-profile`.
+    /// A profile's settings override the top-level ones key by key
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profile: BTreeMap<String, ConfigProfile>,
+    /// Scheduled runs for `serve`
+    #[serde(default, skip_serializing_if = "ServeConfig::is_empty")]
+    pub serve: ServeConfig,
+    /// Where reports are sent after each run
+    #[serde(default, skip_serializing_if = "UploadConfig::is_empty")]
+    pub upload: UploadConfig,
+}
+
+/// A named set of overrides for the global, auth, and service settings
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct ConfigProfile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub global: Option<GlobalConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<AuthConfig>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub services: HashMap<String, ServiceConfig>,
 }
 
 impl Config {
@@ -288,6 +730,90 @@ impl Config {
         Ok(config)
     }
 
+    /// Per-user config file: `$XDG_CONFIG_HOME/azure-aitoolsconnect/config.toml`
+    /// (or `~/.config/...`), `%APPDATA%\azure-aitoolsconnect\config.toml` on Windows
+    pub fn user_config_path() -> Option<PathBuf> {
+        #[cfg(target_os = "windows")]
+        {
+            std::env::var("APPDATA")
+                .ok()
+                .filter(|p| !p.is_empty())
+                .map(|p| {
+                    PathBuf::from(p)
+                        .join("azure-aitoolsconnect")
+                        .join("config.toml")
+                })
+        }
+
+        #[cfg(not(target_os = "windows"))]
+        {
+            std::env::var("XDG_CONFIG_HOME")
+                .ok()
+                .filter(|p| !p.is_empty())
+                .map(PathBuf::from)
+                .or_else(|| {
+                    std::env::var("HOME")
+                        .ok()
+                        .map(|home| PathBuf::from(home).join(".config"))
+                })
+                .map(|dir| dir.join("azure-aitoolsconnect").join("config.toml"))
+        }
+    }
+
+    /// Locations searched when no `--config` is given, in order: the current
+    /// directory, then the per-user config file
+    pub fn search_paths() -> Vec<PathBuf> {
+        let mut paths = vec![PathBuf::from("config.toml")];
+        paths.extend(Self::user_config_path());
+        paths
+    }
+
+    /// First existing file among [`Config::search_paths`]
+    pub fn discover() -> Option<PathBuf> {
+        Self::search_paths().into_iter().find(|path| path.is_file())
+    }
+
+    /// Load configuration from a TOML file with a named profile applied
+    pub fn from_file_with_profile(path: &Path, profile: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                AppError::FileNotFound(path.display().to_string())
+            } else {
+                AppError::Io(e)
+            }
+        })?;
+        Self::from_str_with_profile(&content, profile)
+    }
+
+    /// Parse a config, merging `[profile.<name>]` over the top-level settings
+    /// so a profile only needs the keys that differ
+    pub fn from_str_with_profile(content: &str, profile: &str) -> Result<Self> {
+        let mut root: toml::Table = toml::from_str(content)?;
+        let overrides = root
+            .get("profile")
+            .and_then(|profiles| profiles.get(profile))
+            .and_then(|p| p.as_table())
+            .cloned();
+        let Some(overrides) = overrides else {
+            let config: Config = toml::from_str(content)?;
+            let available: Vec<&str> = config.profile.keys().map(String::as_str).collect();
+            return Err(AppError::Config(if available.is_empty() {
+                format!(
+                    "Profile '{}' not found: the config has no profiles",
+                    profile
+                )
+            } else {
+                format!(
+                    "Profile '{}' not found (available: {})",
+                    profile,
+                    available.join(", ")
+                )
+            }));
+        };
+        merge_tables(&mut root, overrides);
+        Ok(toml::Value::Table(root).try_into()?)
+    }
+
     /// Create a default configuration
     pub fn default_config() -> Self {
         let mut services = HashMap::new();
@@ -305,6 +831,7 @@ impl Config {
                     "token_exchange".to_string(),
                     "tts".to_string(),
                 ],
+                ..Default::default()
             },
         );
 
@@ -316,6 +843,7 @@ impl Config {
                 api_key: None,
                 endpoint: None,
                 test_scenarios: vec!["languages".to_string(), "detect".to_string()],
+                ..Default::default()
             },
         );
 
@@ -327,6 +855,7 @@ impl Config {
                 api_key: None,
                 endpoint: None,
                 test_scenarios: vec!["sentiment".to_string(), "language_detection".to_string()],
+                ..Default::default()
             },
         );
 
@@ -338,6 +867,7 @@ impl Config {
                 api_key: None,
                 endpoint: None,
                 test_scenarios: vec!["analyze_image".to_string()],
+                ..Default::default()
             },
         );
 
@@ -349,6 +879,7 @@ impl Config {
                 api_key: None,
                 endpoint: None,
                 test_scenarios: vec!["layout".to_string()],
+                ..Default::default()
             },
         );
 
@@ -356,15 +887,37 @@ impl Config {
             global: GlobalConfig {
                 cloud: Cloud::Global,
                 timeout_seconds: DEFAULT_TIMEOUT_SECS,
+                connect_timeout_seconds: None,
+                tls_timeout_seconds: None,
                 output_format: OutputFormat::Human,
+                ip_echo_url: None,
+                doh_url: None,
+                diagnose_ports: Vec::new(),
+                max_rps: None,
+                retry_throttled: false,
+                fail_on_throttle: false,
+                fail_on: FailOn::Failed,
+                max_latency_ms: None,
+                fail_on_slow: false,
+                headers: BTreeMap::new(),
+                api_key_header: None,
+                allow_insecure_http: false,
             },
             auth: AuthConfig {
                 default_method: AuthMethod::Key,
+                scope: None,
                 entra: EntraConfig::default(),
                 user: UserAuthConfig::default(),
             },
             services,
             custom_inputs: CustomInputs::default(),
+            custom_scenarios: Vec::new(),
+            custom_services: BTreeMap::new(),
+            expectations: Vec::new(),
+            test_profiles: BTreeMap::new(),
+            profile: BTreeMap::new(),
+            serve: ServeConfig::default(),
+            upload: UploadConfig::default(),
         }
     }
 
@@ -383,6 +936,47 @@ impl Config {
         self.services.get_mut(name)
     }
 
+    /// The `[upload]` settings, with the deprecated `[serve] report_to` and
+    /// `[serve] location` filled in; controllers from `report_to` get the
+    /// `[serve] token` unless `[upload]` has its own
+    pub fn upload_settings(&self) -> UploadConfig {
+        let mut upload = self.upload.clone();
+        upload.targets.extend(self.serve.report_to.iter().cloned());
+        if upload.location.is_none() {
+            upload.location = self.serve.location.clone();
+        }
+        if upload.token.is_none() && !self.serve.report_to.is_empty() {
+            upload.token = self.serve.token.clone();
+        }
+        upload
+    }
+
+    /// Every setting that holds a key, secret, or token, including those in
+    /// `[profile.<name>]` sections. Both log redaction and the support bundle's
+    /// redacted config are built from this list.
+    pub fn secrets_mut(&mut self) -> Vec<&mut Option<String>> {
+        let mut secrets = vec![
+            &mut self.serve.token,
+            &mut self.upload.token,
+            &mut self.upload.log_analytics_key,
+        ];
+        let mut auths = vec![&mut self.auth];
+        let mut services = vec![&mut self.services];
+        for profile in self.profile.values_mut() {
+            auths.extend(profile.auth.as_mut());
+            services.push(&mut profile.services);
+        }
+        for auth in auths {
+            secrets.push(&mut auth.entra.client_secret);
+            secrets.push(&mut auth.user.bearer_token);
+        }
+        for service in services.into_iter().flat_map(|s| s.values_mut()) {
+            secrets.push(&mut service.api_key);
+            secrets.extend(service.resources.values_mut().map(|r| &mut r.api_key));
+        }
+        secrets
+    }
+
     /// Apply environment variable overrides
     pub fn apply_env_overrides(&mut self) {
         // Global API key
@@ -458,6 +1052,21 @@ impl Config {
                 }
             }
         }
+
+        // Egress IP echo endpoint
+        if let Ok(url) = std::env::var("AZURE_AITOOLSCONNECT_IP_ECHO_URL") {
+            self.global.ip_echo_url = Some(url);
+        }
+
+        // DNS-over-HTTPS resolver
+        if let Ok(url) = std::env::var("AZURE_AITOOLSCONNECT_DOH_URL") {
+            self.global.doh_url = Some(url);
+        }
+
+        // Log Analytics shared key for uploads
+        if let Ok(key) = std::env::var("AZURE_AITOOLSCONNECT_LOG_ANALYTICS_KEY") {
+            self.upload.log_analytics_key = Some(key);
+        }
     }
 }
 
@@ -502,6 +1111,215 @@ pub fn validate_config(config: &Config) -> Result<Vec<String>> {
         }
     }
 
+    // Check service regions against the known region list
+    for (name, service) in &config.services {
+        if let Some(region) = &service.region {
+            if !regions::is_known_region(region, config.global.cloud) {
+                let suggestion = regions::suggest_region(region, config.global.cloud)
+                    .map(|s| format!(" Did you mean '{}'?", s))
+                    .unwrap_or_default();
+                warnings.push(format!(
+                    "Service '{}' uses unknown region '{}' for the {} cloud.{}",
+                    name, region, config.global.cloud, suggestion
+                ));
+            }
+        }
+    }
+
+    // Check timeout overrides and rate limit
+    for (name, service) in &config.services {
+        if service.timeout_seconds == Some(0) {
+            warnings.push(format!("Service '{}' has timeout_seconds = 0", name));
+        }
+        let known: Vec<&str> = crate::services::get_service(name)
+            .map(|s| s.list_scenarios().iter().map(|sc| sc.id).collect())
+            .unwrap_or_default();
+        for scenario in service.scenario_timeouts.keys() {
+            if !known.is_empty() && !known.contains(&scenario.as_str()) {
+                warnings.push(format!(
+                    "Service '{}' sets a timeout for unknown scenario '{}'",
+                    name, scenario
+                ));
+            }
+        }
+        for scenario in service.options.keys() {
+            if !known.is_empty() && !known.contains(&scenario.as_str()) {
+                warnings.push(format!(
+                    "Service '{}' sets options for unknown scenario '{}'",
+                    name, scenario
+                ));
+            }
+        }
+    }
+    for (name, service) in &config.services {
+        for (target, resource) in &service.resources {
+            if resource.endpoint.is_none() && resource.region.is_none() {
+                warnings.push(format!(
+                    "Resource '{}' of service '{}' has neither an endpoint nor a region",
+                    target, name
+                ));
+            }
+        }
+    }
+
+    // Check Key Vault secret references
+    for (name, service) in &config.services {
+        let keys = service.api_key.iter().chain(
+            service
+                .resources
+                .values()
+                .filter_map(|r| r.api_key.as_ref()),
+        );
+        for key in keys.filter(|k| crate::keyvault::is_reference(k)) {
+            if let Err(e) = crate::keyvault::SecretReference::parse(key) {
+                warnings.push(format!("Service '{}': {}", name, e));
+            }
+        }
+    }
+    if matches!(config.global.max_rps, Some(rps) if rps <= 0.0) {
+        warnings.push("max_rps must be greater than 0; the limit will be ignored".to_string());
+    }
+
+    for (name, value) in &config.global.headers {
+        if let Err(e) = crate::services::parse_header(name, value) {
+            warnings.push(format!("[global] headers: {}", e));
+        }
+    }
+    if let Some(name) = &config.global.api_key_header {
+        if reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_err() {
+            warnings.push(format!("Invalid api_key_header '{}'", name));
+        }
+    }
+
+    // Check custom scenarios
+    let mut seen_names = std::collections::HashSet::new();
+    for probe in &config.custom_scenarios {
+        if !seen_names.insert(probe.name.as_str()) {
+            warnings.push(format!("Duplicate custom scenario name '{}'", probe.name));
+        }
+        if probe.url_template.trim().is_empty() {
+            warnings.push(format!(
+                "Custom scenario '{}' has an empty url_template",
+                probe.name
+            ));
+        }
+        if reqwest::Method::from_bytes(probe.method.to_uppercase().as_bytes()).is_err() {
+            warnings.push(format!(
+                "Custom scenario '{}' has an invalid method '{}'",
+                probe.name, probe.method
+            ));
+        }
+        if probe.body.is_some() && probe.body_file.is_some() {
+            warnings.push(format!(
+                "Custom scenario '{}' sets both body and body_file; body_file is ignored",
+                probe.name
+            ));
+        }
+        if let Some(service) = &probe.service {
+            if crate::services::get_service(service).is_none() {
+                warnings.push(format!(
+                    "Custom scenario '{}' references unknown service '{}'",
+                    probe.name, service
+                ));
+            }
+        }
+    }
+
+    // Check services defined in the config file
+    let builtins = crate::services::ServiceRegistry::new();
+    for (name, service) in &config.custom_services {
+        if builtins.get(name).is_some() || name == crate::services::custom::CUSTOM_SERVICE_NAME {
+            warnings.push(format!(
+                "Custom service '{}' has the name of a built-in service",
+                name
+            ));
+        }
+        if service.scenarios.is_empty() {
+            warnings.push(format!("Custom service '{}' has no scenarios", name));
+        }
+        for probe in &service.scenarios {
+            if probe.url_template.trim().is_empty() {
+                warnings.push(format!(
+                    "Custom service '{}' scenario '{}' has an empty url_template",
+                    name, probe.name
+                ));
+            }
+        }
+    }
+
+    // Check expectations
+    for expectation in &config.expectations {
+        let custom = expectation.service == crate::services::custom::CUSTOM_SERVICE_NAME
+            || config.custom_services.contains_key(&expectation.service);
+        if custom {
+            continue;
+        }
+        match builtins.get(&expectation.service) {
+            None => warnings.push(format!(
+                "Expectation references unknown service '{}'",
+                expectation.service
+            )),
+            Some(service) => {
+                if let Some(scenario) = &expectation.scenario {
+                    if !service.list_scenarios().iter().any(|s| s.id == scenario) {
+                        warnings.push(format!(
+                            "Expectation references unknown scenario '{}' of service '{}'",
+                            scenario, expectation.service
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    // Check scheduled jobs
+    let mut job_names = std::collections::HashSet::new();
+    for job in &config.serve.jobs {
+        if !job_names.insert(job.name.as_str()) {
+            warnings.push(format!("Duplicate [[serve.jobs]] name '{}'", job.name));
+        }
+        if let Err(e) = job.schedule.parse::<crate::serve::cron::Schedule>() {
+            warnings.push(format!("Job '{}': {}", job.name, e));
+        }
+    }
+    if let Some(listen) = &config.serve.listen {
+        if listen.parse::<std::net::SocketAddr>().is_err() {
+            warnings.push(format!(
+                "[serve] listen '{}' is not an address like 127.0.0.1:9464",
+                listen
+            ));
+        }
+    }
+    if config.serve.location.is_some() {
+        warnings.push("[serve] location is deprecated; use [upload] location".to_string());
+    }
+    if !config.serve.report_to.is_empty() {
+        warnings.push("[serve] report_to is deprecated; use [upload] targets".to_string());
+    }
+    let upload = config.upload_settings();
+    let mut log_analytics = false;
+    for target in &upload.targets {
+        match target.parse::<crate::upload::UploadTarget>() {
+            Ok(crate::upload::UploadTarget::LogAnalytics(_)) => log_analytics = true,
+            Ok(_) => {}
+            Err(e) => warnings.push(format!("[upload] targets: {}", e)),
+        }
+    }
+    if log_analytics && config.upload.log_analytics_key.is_none() {
+        warnings.push(
+            "[upload] has a log-analytics: target but no log_analytics_key \
+             (or AZURE_AITOOLSCONNECT_LOG_ANALYTICS_KEY)"
+                .to_string(),
+        );
+    }
+    if config.serve.accept_reports && config.serve.token.is_none() {
+        warnings.push(
+            "[serve] accept_reports is on without a token: anyone who can reach the \
+             status endpoint can submit reports"
+                .to_string(),
+        );
+    }
+
     // Check interactive config
     if config.auth.default_method == AuthMethod::Interactive && config.auth.user.tenant_id.is_none()
     {
@@ -578,6 +1396,53 @@ pub fn validate_config(config: &Config) -> Result<Vec<String>> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_search_paths_start_with_current_directory() {
+        let paths = Config::search_paths();
+        assert_eq!(paths[0], PathBuf::from("config.toml"));
+        if let Some(user) = Config::user_config_path() {
+            assert_eq!(paths.last(), Some(&user));
+            assert!(user.ends_with("azure-aitoolsconnect/config.toml"));
+        }
+    }
+
+    #[test]
+    fn test_profile_overrides_top_level() {
+        let content = r#"
+[global]
+timeout_seconds = 10
+
+[services.speech]
+region = "eastus"
+api_key = "top"
+
+[profile.prod.global]
+cloud = "china"
+
+[profile.prod.services.speech]
+region = "chinaeast2"
+
+[profile.dev.auth]
+default_method = "device_code"
+"#;
+        let config = Config::from_str_with_profile(content, "prod").unwrap();
+        assert_eq!(config.global.cloud, Cloud::China);
+        // Keys the profile doesn't set keep their top-level values
+        assert_eq!(config.global.timeout_seconds, 10);
+        let speech = &config.services["speech"];
+        assert_eq!(speech.region.as_deref(), Some("chinaeast2"));
+        assert_eq!(speech.api_key.as_deref(), Some("top"));
+        assert_eq!(config.auth.default_method, AuthMethod::Key);
+        assert_eq!(config.profile.len(), 2);
+
+        let dev = Config::from_str_with_profile(content, "dev").unwrap();
+        assert_eq!(dev.auth.default_method, AuthMethod::DeviceCode);
+        assert_eq!(dev.global.cloud, Cloud::Global);
+
+        let error = Config::from_str_with_profile(content, "staging").unwrap_err();
+        assert!(error.to_string().contains("available: dev, prod"));
+    }
+
     #[test]
     fn test_cloud_endpoints() {
         assert_eq!(
@@ -605,6 +1470,48 @@ mod tests {
         assert!(config.services.contains_key("speech"));
     }
 
+    #[test]
+    fn test_service_category_parse() {
+        let config: Config = toml::from_str(
+            r#"
+            [services.translator]
+            region = "eastus"
+            category = "a2eb72f9-43a8-46bd-82fa-4693c8b64c3c-GENERAL"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.services["translator"].category.as_deref(),
+            Some("a2eb72f9-43a8-46bd-82fa-4693c8b64c3c-GENERAL")
+        );
+        assert!(config.services["translator"].enabled);
+    }
+
+    #[test]
+    fn test_timeout_overrides_parse() {
+        let config: Config = toml::from_str(
+            r#"
+            [global]
+            max_rps = 2.5
+
+            [services.document_intelligence]
+            timeout_seconds = 120
+            scenario_timeouts = { layout = 180, bogus = 5 }
+            "#,
+        )
+        .unwrap();
+        let di = &config.services["document_intelligence"];
+        assert_eq!(di.timeout_seconds, Some(120));
+        assert_eq!(di.scenario_timeouts["layout"], 180);
+        assert_eq!(config.global.max_rps, Some(2.5));
+
+        let warnings = validate_config(&config).unwrap();
+        assert!(warnings
+            .iter()
+            .any(|w| w.contains("unknown scenario 'bogus'")));
+        assert!(!warnings.iter().any(|w| w.contains("'layout'")));
+    }
+
     #[test]
     fn test_token_endpoint_custom_domain() {
         let ep = Cloud::Global.cognitive_token_endpoint_for(
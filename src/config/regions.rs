@@ -0,0 +1,187 @@
+use super::Cloud;
+
+/// Metadata for a known Azure region
+#[derive(Debug, Clone, Copy)]
+pub struct AzureRegion {
+    /// Programmatic name used in endpoints (e.g., "eastus")
+    pub name: &'static str,
+    /// Display name (e.g., "East US")
+    pub display_name: &'static str,
+    /// Geography the region belongs to
+    pub geography: &'static str,
+}
+
+const fn region(
+    name: &'static str,
+    display_name: &'static str,
+    geography: &'static str,
+) -> AzureRegion {
+    AzureRegion {
+        name,
+        display_name,
+        geography,
+    }
+}
+
+/// Public cloud regions. Not every service is offered in each; the
+/// service answers for the ones it lacks.
+pub const GLOBAL_REGIONS: &[AzureRegion] = &[
+    region("australiacentral", "Australia Central", "Australia"),
+    region("australiacentral2", "Australia Central 2", "Australia"),
+    region("australiaeast", "Australia East", "Australia"),
+    region("australiasoutheast", "Australia Southeast", "Australia"),
+    region("belgiumcentral", "Belgium Central", "Belgium"),
+    region("brazilsouth", "Brazil South", "Brazil"),
+    region("brazilsoutheast", "Brazil Southeast", "Brazil"),
+    region("canadacentral", "Canada Central", "Canada"),
+    region("canadaeast", "Canada East", "Canada"),
+    region("centralindia", "Central India", "India"),
+    region("centralus", "Central US", "United States"),
+    region("chilecentral", "Chile Central", "Chile"),
+    region("eastasia", "East Asia", "Asia Pacific"),
+    region("eastus", "East US", "United States"),
+    region("eastus2", "East US 2", "United States"),
+    region("francecentral", "France Central", "France"),
+    region("francesouth", "France South", "France"),
+    region("germanynorth", "Germany North", "Germany"),
+    region("germanywestcentral", "Germany West Central", "Germany"),
+    region("indonesiacentral", "Indonesia Central", "Indonesia"),
+    region("israelcentral", "Israel Central", "Israel"),
+    region("italynorth", "Italy North", "Italy"),
+    region("japaneast", "Japan East", "Japan"),
+    region("japanwest", "Japan West", "Japan"),
+    region("jioindiacentral", "Jio India Central", "India"),
+    region("jioindiawest", "Jio India West", "India"),
+    region("koreacentral", "Korea Central", "Korea"),
+    region("koreasouth", "Korea South", "Korea"),
+    region("malaysiawest", "Malaysia West", "Malaysia"),
+    region("mexicocentral", "Mexico Central", "Mexico"),
+    region("newzealandnorth", "New Zealand North", "New Zealand"),
+    region("northcentralus", "North Central US", "United States"),
+    region("northeurope", "North Europe", "Europe"),
+    region("norwayeast", "Norway East", "Norway"),
+    region("norwaywest", "Norway West", "Norway"),
+    region("polandcentral", "Poland Central", "Poland"),
+    region("qatarcentral", "Qatar Central", "Qatar"),
+    region("southafricanorth", "South Africa North", "South Africa"),
+    region("southafricawest", "South Africa West", "South Africa"),
+    region("southcentralus", "South Central US", "United States"),
+    region("southeastasia", "Southeast Asia", "Asia Pacific"),
+    region("southindia", "South India", "India"),
+    region("spaincentral", "Spain Central", "Spain"),
+    region("swedencentral", "Sweden Central", "Sweden"),
+    region("swedensouth", "Sweden South", "Sweden"),
+    region("switzerlandnorth", "Switzerland North", "Switzerland"),
+    region("switzerlandwest", "Switzerland West", "Switzerland"),
+    region("uaecentral", "UAE Central", "UAE"),
+    region("uaenorth", "UAE North", "UAE"),
+    region("uksouth", "UK South", "United Kingdom"),
+    region("ukwest", "UK West", "United Kingdom"),
+    region("westcentralus", "West Central US", "United States"),
+    region("westeurope", "West Europe", "Europe"),
+    region("westindia", "West India", "India"),
+    region("westus", "West US", "United States"),
+    region("westus2", "West US 2", "United States"),
+    region("westus3", "West US 3", "United States"),
+];
+
+/// Azure China (21Vianet) regions
+pub const CHINA_REGIONS: &[AzureRegion] = &[
+    region("chinaeast", "China East", "China"),
+    region("chinaeast2", "China East 2", "China"),
+    region("chinaeast3", "China East 3", "China"),
+    region("chinanorth", "China North", "China"),
+    region("chinanorth2", "China North 2", "China"),
+    region("chinanorth3", "China North 3", "China"),
+];
+
+/// Get the known regions for a cloud
+pub fn regions_for(cloud: Cloud) -> &'static [AzureRegion] {
+    match cloud {
+        Cloud::Global => GLOBAL_REGIONS,
+        Cloud::China => CHINA_REGIONS,
+    }
+}
+
+/// Check whether a region name is known for the given cloud.
+/// "global" is accepted for the public cloud since Translator uses it.
+pub fn is_known_region(name: &str, cloud: Cloud) -> bool {
+    let name = name.to_lowercase();
+    (cloud == Cloud::Global && name == "global")
+        || regions_for(cloud).iter().any(|r| r.name == name)
+}
+
+/// Suggest the closest known region for a likely typo. The table can lag
+/// behind new regions, so callers warn rather than refuse.
+pub fn suggest_region(name: &str, cloud: Cloud) -> Option<&'static str> {
+    let name = name.to_lowercase().replace([' ', '-', '_'], "");
+    if let Some(r) = regions_for(cloud).iter().find(|r| r.name == name) {
+        // Input only differed by spacing/dashes (e.g., "east-us")
+        return Some(r.name);
+    }
+
+    regions_for(cloud)
+        .iter()
+        .map(|r| (r.name, edit_distance(&name, r.name)))
+        .filter(|(_, d)| *d <= 2)
+        .min_by_key(|(_, d)| *d)
+        .map(|(n, _)| n)
+}
+
+/// Levenshtein distance between two ASCII strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.as_bytes();
+    let b = b.as_bytes();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        curr[0] = i;
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            curr[j] = (prev[j] + 1).min(curr[j - 1] + 1).min(prev[j - 1] + cost);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_regions() {
+        assert!(is_known_region("eastus", Cloud::Global));
+        assert!(is_known_region("SwedenCentral", Cloud::Global));
+        assert!(is_known_region("global", Cloud::Global));
+        assert!(is_known_region("chinanorth3", Cloud::China));
+        assert!(!is_known_region("eastus", Cloud::China));
+        assert!(!is_known_region("eastus1", Cloud::Global));
+        for region in [
+            "norwaywest",
+            "brazilsoutheast",
+            "koreasouth",
+            "westindia",
+            "australiacentral",
+        ] {
+            assert!(is_known_region(region, Cloud::Global), "{}", region);
+        }
+    }
+
+    #[test]
+    fn test_suggest_region() {
+        assert_eq!(suggest_region("eastus1", Cloud::Global), Some("eastus"));
+        assert_eq!(suggest_region("East US 2", Cloud::Global), Some("eastus2"));
+        assert_eq!(
+            suggest_region("westeuorpe", Cloud::Global),
+            Some("westeurope")
+        );
+        assert_eq!(
+            suggest_region("chinaeast4", Cloud::China),
+            Some("chinaeast")
+        );
+        assert_eq!(suggest_region("mars", Cloud::Global), None);
+    }
+}
@@ -0,0 +1,161 @@
+//! JSON Schema for the config file and strict (unknown-key) validation
+//!
+//! serde ignores keys it doesn't know, so a typo like `regoin = "eastus"` is
+//! silently dropped. Strict validation reports every ignored key with its
+//! line and column in the file.
+
+use std::fmt;
+
+use super::Config;
+use crate::error::Result;
+
+/// JSON Schema of the config file, generated from the serde model
+pub fn config_schema() -> String {
+    let schema = schemars::schema_for!(Config);
+    serde_json::to_string_pretty(&schema).unwrap_or_default()
+}
+
+/// A key in the config file that does not map to any setting
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownKey {
+    /// Dotted path of the key (e.g. `services.speech.regoin`)
+    pub path: String,
+    /// 1-based line of the key, when it could be located
+    pub line: Option<usize>,
+    /// 1-based column of the key, when it could be located
+    pub column: Option<usize>,
+}
+
+impl fmt::Display for UnknownKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(
+                f,
+                "line {}, column {}: unknown key '{}'",
+                line, column, self.path
+            ),
+            _ => write!(f, "unknown key '{}'", self.path),
+        }
+    }
+}
+
+/// Parse a config file, collecting keys that serde would silently ignore.
+/// Type mismatches are returned as errors carrying the TOML parser's line and
+/// column.
+pub fn parse_strict(content: &str) -> Result<(Config, Vec<UnknownKey>)> {
+    let mut ignored = Vec::new();
+    let config: Config = serde_ignored::deserialize(toml::Deserializer::new(content), |path| {
+        let mut segments = Vec::new();
+        path_segments(&path, &mut segments);
+        ignored.push(segments);
+    })?;
+
+    let document = toml_edit::ImDocument::parse(content).ok();
+    let unknown = ignored
+        .into_iter()
+        .map(|segments| {
+            let position = document
+                .as_ref()
+                .and_then(|doc| key_offset(doc.as_item(), &segments))
+                .map(|offset| line_column(content, offset));
+            UnknownKey {
+                path: segments.join("."),
+                line: position.map(|(line, _)| line),
+                column: position.map(|(_, column)| column),
+            }
+        })
+        .collect();
+
+    Ok((config, unknown))
+}
+
+fn path_segments(path: &serde_ignored::Path<'_>, segments: &mut Vec<String>) {
+    match path {
+        serde_ignored::Path::Root => {}
+        serde_ignored::Path::Seq { parent, index } => {
+            path_segments(parent, segments);
+            segments.push(index.to_string());
+        }
+        serde_ignored::Path::Map { parent, key } => {
+            path_segments(parent, segments);
+            segments.push(key.clone());
+        }
+        serde_ignored::Path::Some { parent }
+        | serde_ignored::Path::NewtypeStruct { parent }
+        | serde_ignored::Path::NewtypeVariant { parent } => path_segments(parent, segments),
+    }
+}
+
+/// Byte offset of the last key in `segments`
+fn key_offset(root: &toml_edit::Item, segments: &[String]) -> Option<usize> {
+    let mut item = root;
+    let mut offset = None;
+    for segment in segments {
+        if let Some(table) = item.as_table_like() {
+            let (key, value) = table.get_key_value(segment)?;
+            offset = key.span().map(|span| span.start);
+            item = value;
+        } else {
+            item = item.get(segment.parse::<usize>().ok()?)?;
+        }
+    }
+    offset
+}
+
+/// 1-based line and column of a byte offset
+fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_strict() {
+        let (config, unknown) = parse_strict(
+            "[global]\ntimeout_seconds = 10\n\n[services.speech]\nregoin = \"eastus\"\n\n[[custom_scenarios]]\nname = \"health\"\nurl_template = \"https://gw/health\"\n  expect_staus = 200\n",
+        )
+        .unwrap();
+        assert_eq!(config.global.timeout_seconds, 10);
+        assert_eq!(
+            unknown,
+            vec![
+                UnknownKey {
+                    path: "services.speech.regoin".to_string(),
+                    line: Some(5),
+                    column: Some(1),
+                },
+                UnknownKey {
+                    path: "custom_scenarios.0.expect_staus".to_string(),
+                    line: Some(10),
+                    column: Some(3),
+                },
+            ]
+        );
+
+        let error = parse_strict("[global]\ntimeout_seconds = \"ten\"\n").unwrap_err();
+        assert!(error.to_string().contains("line 2"), "{}", error);
+    }
+
+    #[test]
+    fn test_example_config_is_strictly_valid() {
+        let (config, unknown) = parse_strict(include_str!("../../config/example.toml")).unwrap();
+        assert_eq!(unknown, []);
+        assert!(config.services.contains_key("speech"));
+    }
+
+    #[test]
+    fn test_published_schema_is_current() {
+        let published: serde_json::Value =
+            serde_json::from_str(include_str!("../../config/schema.json")).unwrap();
+        let generated: serde_json::Value = serde_json::from_str(&config_schema()).unwrap();
+        assert_eq!(
+            published, generated,
+            "config/schema.json is stale; regenerate it with `validate --schema`"
+        );
+    }
+}
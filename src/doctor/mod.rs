@@ -0,0 +1,634 @@
+//! Guided, layer-by-layer troubleshooting (`doctor` subcommand)
+//!
+//! Checks the environment, then DNS, TCP, TLS, authentication, and one
+//! lightweight scenario per service, stopping at the first layer that fails
+//! and explaining what to do next.
+
+use crate::arm::is_private_address;
+use crate::auth::Credentials;
+use crate::environment::{self, EnvironmentInfo};
+use crate::error::{ExitCode, Result};
+use crate::network::{check_dns, check_tls};
+use crate::services::{get_service, FailureKind};
+use crate::testing::{TestRunner, TestRunnerConfig};
+use serde::Serialize;
+use std::time::Duration;
+
+/// The lightweight scenario used to verify each service
+pub const DOCTOR_SCENARIOS: &[(&str, &str)] = &[
+    ("speech", "voices_list"),
+    ("translator", "detect"),
+    ("language", "language_detection"),
+    ("vision", "analyze_image"),
+    ("document_intelligence", "list_models"),
+    ("ai_foundry", "list_models"),
+    ("model_inference", "model_info"),
+    ("openai", "list_models"),
+];
+
+/// A troubleshooting layer, in the order they are checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Layer {
+    Environment,
+    Dns,
+    Tcp,
+    Tls,
+    Auth,
+    Service,
+}
+
+impl Layer {
+    const ALL: [Layer; 6] = [
+        Layer::Environment,
+        Layer::Dns,
+        Layer::Tcp,
+        Layer::Tls,
+        Layer::Auth,
+        Layer::Service,
+    ];
+}
+
+impl std::fmt::Display for Layer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Layer::Environment => "Environment",
+            Layer::Dns => "DNS",
+            Layer::Tcp => "TCP",
+            Layer::Tls => "TLS",
+            Layer::Auth => "Authentication",
+            Layer::Service => "Service",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// Outcome of a single layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// Result of checking one layer
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorStep {
+    pub layer: Layer,
+    pub status: StepStatus,
+    pub summary: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<String>,
+}
+
+impl DoctorStep {
+    fn new(layer: Layer, status: StepStatus, summary: impl Into<String>) -> Self {
+        Self {
+            layer,
+            status,
+            summary: summary.into(),
+            details: Vec::new(),
+        }
+    }
+
+    fn with_details(mut self, details: Vec<String>) -> Self {
+        self.details = details;
+        self
+    }
+}
+
+/// Full doctor report
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorReport {
+    pub environment: EnvironmentInfo,
+    pub steps: Vec<DoctorStep>,
+    /// First layer that failed, if any
+    pub failed_layer: Option<Layer>,
+    /// Focused explanation of the failure
+    pub explanation: Option<String>,
+    /// Suggested next steps
+    pub next_steps: Vec<String>,
+}
+
+impl DoctorReport {
+    fn fail(&mut self, step: DoctorStep, explanation: String, next_steps: Vec<String>) {
+        self.failed_layer = Some(step.layer);
+        self.steps.push(step);
+        self.explanation = Some(explanation);
+        self.next_steps = next_steps;
+    }
+
+    /// Exit code matching the failed layer
+    pub fn exit_code(&self) -> ExitCode {
+        match self.failed_layer {
+            None => ExitCode::Success,
+            Some(Layer::Environment | Layer::Dns | Layer::Tcp | Layer::Tls) => {
+                ExitCode::NetworkFailure
+            }
+            Some(Layer::Auth) => ExitCode::AuthFailure,
+            Some(Layer::Service) => ExitCode::TestFailure,
+        }
+    }
+}
+
+/// Lightweight scenario for a service
+pub fn doctor_scenario(service: &str) -> Option<&'static str> {
+    DOCTOR_SCENARIOS
+        .iter()
+        .find(|(name, _)| *name == service)
+        .map(|(_, scenario)| *scenario)
+}
+
+/// Distinct hosts the selected services will connect to
+fn target_hosts(config: &TestRunnerConfig) -> Vec<String> {
+    let mut hosts: Vec<String> = Vec::new();
+    for name in &config.services {
+        let Some(service) = get_service(name) else {
+            continue;
+        };
+        let endpoint =
+            service.get_endpoint(&config.region, config.cloud, config.endpoint.as_deref());
+        let host = url::Url::parse(&endpoint)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_string()));
+        if let Some(host) = host {
+            if !hosts.contains(&host) {
+                hosts.push(host);
+            }
+        }
+    }
+    hosts
+}
+
+fn environment_step(env: &EnvironmentInfo) -> DoctorStep {
+    let mut details = Vec::new();
+    for var in &env.proxy {
+        details.push(format!("{}={}", var.name, var.value));
+    }
+    for proxy in &env.system_proxy {
+        details.push(format!(
+            "System proxy ({}): {}",
+            proxy.source,
+            proxy.describe()
+        ));
+    }
+    if let Some(store) = &env.certificate_store {
+        details.push(format!(
+            "{} trusted roots from the Windows certificate store",
+            store.roots
+        ));
+    }
+    if let Some(container) = &env.container {
+        details.push(format!("Inside a container ({})", container));
+    }
+    if let Some(hosting) = &env.hosting {
+        details.push(format!("Running on {}", hosting));
+    }
+    if env.managed_identity {
+        details.push("Managed identity endpoint available".to_string());
+    }
+    if !env.vpn_interfaces.is_empty() {
+        details.push(format!(
+            "VPN-like interfaces: {} (traffic may be routed or resolved differently)",
+            env.vpn_interfaces.join(", ")
+        ));
+    }
+    let platform_warnings = env.platform_warnings();
+    let unreachable: Vec<String> = env
+        .platform
+        .iter()
+        .filter(|c| !c.reachable)
+        .map(|c| c.dependency.to_string())
+        .collect();
+    details.extend(platform_warnings.iter().cloned());
+    if let Some(warning) = env.clock_skew_warning() {
+        details.push(format!("Clock {}", warning));
+        return DoctorStep::new(
+            Layer::Environment,
+            StepStatus::Warn,
+            "Local clock is off; tokens may be rejected as not yet valid or expired",
+        )
+        .with_details(details);
+    }
+    if !platform_warnings.is_empty() {
+        let summary = if unreachable.is_empty() {
+            "Azure platform endpoints are slow from this VM".to_string()
+        } else {
+            format!(
+                "Azure platform endpoint(s) unreachable from this VM: {}",
+                unreachable.join(", ")
+            )
+        };
+        return DoctorStep::new(Layer::Environment, StepStatus::Warn, summary)
+            .with_details(details);
+    }
+    let proxy_warnings = env.system_proxy_warnings();
+    if !proxy_warnings.is_empty() {
+        details.extend(proxy_warnings);
+        return DoctorStep::new(
+            Layer::Environment,
+            StepStatus::Warn,
+            "A proxy auto-config script is configured; requests don't follow it",
+        )
+        .with_details(details);
+    }
+    if env.on_azure() {
+        details
+            .push("Azure platform endpoints (IMDS, wire server, Azure DNS) reachable".to_string());
+    }
+    let summary = if details.is_empty() {
+        "No proxy, VPN, or Azure hosting detected".to_string()
+    } else {
+        format!("{} item(s) detected", details.len())
+    };
+    DoctorStep::new(Layer::Environment, StepStatus::Pass, summary).with_details(details)
+}
+
+/// Failure kinds that point at credentials rather than the service itself
+fn is_auth_kind(kind: FailureKind) -> bool {
+    matches!(
+        kind,
+        FailureKind::AuthKey
+            | FailureKind::AuthToken
+            | FailureKind::AuthRbac
+            | FailureKind::LocalAuthDisabled
+    )
+}
+
+/// Run the guided diagnostics
+pub async fn run_doctor(mut config: TestRunnerConfig) -> Result<DoctorReport> {
+    let mut env = environment::detect();
+    let (platform, skew) = tokio::join!(
+        environment::platform::check_all(),
+        environment::check_clock_skew(config.cloud.login_endpoint(), config.timeout)
+    );
+    env.set_platform(platform);
+    env.clock_skew_secs = skew;
+    let mut report = DoctorReport {
+        environment: env.clone(),
+        steps: vec![environment_step(&env)],
+        failed_layer: None,
+        explanation: None,
+        next_steps: Vec::new(),
+    };
+
+    let hosts = target_hosts(&config);
+
+    // DNS
+    let mut details = Vec::new();
+    for host in &hosts {
+        let dns = check_dns(host).await;
+        if !dns.resolved {
+            let private_hint = host.ends_with(".cognitiveservices.azure.com")
+                || host.ends_with(".cognitiveservices.azure.cn");
+            let mut next_steps = vec![format!("Run 'nslookup {}' to confirm.", host)];
+            if private_hint {
+                next_steps.push(
+                    "If the resource uses a private endpoint, link the privatelink.cognitiveservices.azure.com \
+                     zone to this network or forward it to 168.63.129.16."
+                        .to_string(),
+                );
+            } else {
+                next_steps.push(format!(
+                    "Check the region name ('{}') with 'list-regions'.",
+                    config.region
+                ));
+            }
+            report.fail(
+                DoctorStep::new(
+                    Layer::Dns,
+                    StepStatus::Fail,
+                    format!("{} did not resolve", host),
+                ),
+                format!(
+                    "DNS resolution of {} failed: {}",
+                    host,
+                    dns.error.unwrap_or_default()
+                ),
+                next_steps,
+            );
+            return Ok(report);
+        }
+        let addresses: Vec<std::net::IpAddr> = dns
+            .addresses
+            .iter()
+            .filter_map(|a| a.parse().ok())
+            .collect();
+        let private = !addresses.is_empty() && addresses.iter().all(is_private_address);
+        details.push(format!(
+            "{} -> {}{}",
+            host,
+            dns.addresses.join(", "),
+            if private { " (private endpoint)" } else { "" }
+        ));
+    }
+    report.steps.push(
+        DoctorStep::new(
+            Layer::Dns,
+            StepStatus::Pass,
+            format!("{} host(s) resolved", hosts.len()),
+        )
+        .with_details(details),
+    );
+
+    // TCP
+    let mut blocked = Vec::new();
+    for host in &hosts {
+        let connect = tokio::net::TcpStream::connect((host.as_str(), 443));
+        match tokio::time::timeout(config.timeout.min(Duration::from_secs(10)), connect).await {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => blocked.push(format!("{}:443 - {}", host, e)),
+            Err(_) => blocked.push(format!("{}:443 - timed out", host)),
+        }
+    }
+    if blocked.is_empty() {
+        report.steps.push(DoctorStep::new(
+            Layer::Tcp,
+            StepStatus::Pass,
+            "Port 443 reachable",
+        ));
+    } else if env.has_https_proxy() {
+        report.steps.push(
+            DoctorStep::new(
+                Layer::Tcp,
+                StepStatus::Warn,
+                "Direct connections blocked; relying on the configured proxy",
+            )
+            .with_details(blocked),
+        );
+    } else {
+        report.fail(
+            DoctorStep::new(Layer::Tcp, StepStatus::Fail, "Port 443 unreachable")
+                .with_details(blocked),
+            "Outbound TCP connections on port 443 are refused or dropped.".to_string(),
+            vec![
+                "Allow outbound TCP 443 to the hosts above in your firewall or NSG.".to_string(),
+                "If your network requires a proxy, set HTTPS_PROXY.".to_string(),
+            ],
+        );
+        return Ok(report);
+    }
+
+    // TLS
+    for host in &hosts {
+        let tls = check_tls(host).await;
+        if !tls.success {
+            let error = tls.error.unwrap_or_default();
+            report.fail(
+                DoctorStep::new(
+                    Layer::Tls,
+                    StepStatus::Fail,
+                    format!("Handshake with {} failed", host),
+                ),
+                format!("TLS to {} failed: {}", host, error),
+                vec![
+                    format!(
+                        "Run 'diagnose --tls --revocation -e {}' for certificate details.",
+                        host
+                    ),
+                    "If a proxy inspects TLS, trust its root CA or exempt Azure AI endpoints."
+                        .to_string(),
+                ],
+            );
+            return Ok(report);
+        }
+    }
+    report.steps.push(DoctorStep::new(
+        Layer::Tls,
+        StepStatus::Pass,
+        "Certificate chain trusted",
+    ));
+
+    // Authentication
+    let scenarios: Vec<String> = config
+        .services
+        .iter()
+        .filter_map(|s| doctor_scenario(s))
+        .map(|s| s.to_string())
+        .collect();
+    config.scenarios = Some(scenarios);
+    let runner = TestRunner::new(config);
+    let credentials = match runner.get_credentials().await {
+        Ok(credentials) => credentials,
+        Err(e) => {
+            let mut next_steps = Vec::new();
+            if let Some(hint) = e.hint() {
+                next_steps.push(hint.to_string());
+            }
+            report.fail(
+                DoctorStep::new(
+                    Layer::Auth,
+                    StepStatus::Fail,
+                    "Could not obtain credentials",
+                ),
+                e.to_string(),
+                next_steps,
+            );
+            return Ok(report);
+        }
+    };
+    let auth_summary = match credentials {
+        Credentials::ApiKey(_) => "API key provided (verified by the service check)",
+        Credentials::BearerToken(_) => "Bearer token acquired (verified by the service check)",
+    };
+    report
+        .steps
+        .push(DoctorStep::new(Layer::Auth, StepStatus::Pass, auth_summary));
+
+    // Service
+    let test_report = runner.run_with_credentials(credentials).await?;
+    let mut details = Vec::new();
+    for service in &test_report.services {
+        for result in &service.results {
+            if result.success || result.is_skipped() {
+                details.push(format!(
+                    "{}/{}: {}",
+                    service.service_name,
+                    result.scenario_id,
+                    if result.success { "OK" } else { "skipped" }
+                ));
+                continue;
+            }
+            let layer = match result.failure_kind {
+                Some(kind) if is_auth_kind(kind) => Layer::Auth,
+                _ => Layer::Service,
+            };
+            let kind = result
+                .failure_kind
+                .map(|k| format!(" [{}]", k))
+                .unwrap_or_default();
+            report.fail(
+                DoctorStep::new(
+                    layer,
+                    StepStatus::Fail,
+                    format!(
+                        "{}/{} failed{}",
+                        service.service_name, result.scenario_id, kind
+                    ),
+                )
+                .with_details(details),
+                format!(
+                    "{} ({})",
+                    result.error.clone().unwrap_or_default(),
+                    service.endpoint
+                ),
+                result.hint.clone().into_iter().collect(),
+            );
+            return Ok(report);
+        }
+    }
+    report.steps.push(
+        DoctorStep::new(
+            Layer::Service,
+            StepStatus::Pass,
+            format!("{} service(s) responded", test_report.services.len()),
+        )
+        .with_details(details),
+    );
+
+    Ok(report)
+}
+
+/// Format a doctor report for human-readable output
+pub fn format_doctor(report: &DoctorReport, use_colors: bool) -> String {
+    use console::style;
+
+    let mut output = String::new();
+    output.push_str("\nAzure AI Services Doctor\n");
+    output.push_str("========================\n\n");
+
+    for step in &report.steps {
+        let status = match (step.status, use_colors) {
+            (StepStatus::Pass, true) => style("\u{2713}").green().to_string(),
+            (StepStatus::Warn, true) => style("!").yellow().to_string(),
+            (StepStatus::Fail, true) => style("\u{2717}").red().to_string(),
+            (StepStatus::Pass, false) => "[OK]  ".to_string(),
+            (StepStatus::Warn, false) => "[WARN]".to_string(),
+            (StepStatus::Fail, false) => "[FAIL]".to_string(),
+        };
+        output.push_str(&format!(
+            "  {} {:<15} {}\n",
+            status,
+            step.layer.to_string(),
+            step.summary
+        ));
+        for detail in &step.details {
+            if use_colors {
+                output.push_str(&format!("      {}\n", style(detail).dim()));
+            } else {
+                output.push_str(&format!("      {}\n", detail));
+            }
+        }
+    }
+
+    if let Some(failed) = report.failed_layer {
+        let remaining: Vec<String> = Layer::ALL
+            .iter()
+            .filter(|l| !report.steps.iter().any(|s| s.layer == **l))
+            .map(|l| l.to_string())
+            .collect();
+        if !remaining.is_empty() {
+            output.push_str(&format!("\n  Not checked: {}\n", remaining.join(", ")));
+        }
+
+        output.push_str(&format!("\nProblem ({} layer):\n", failed));
+        if let Some(explanation) = &report.explanation {
+            output.push_str(&format!("  {}\n", explanation));
+        }
+        if !report.next_steps.is_empty() {
+            output.push_str("\nNext steps:\n");
+            for (i, step) in report.next_steps.iter().enumerate() {
+                output.push_str(&format!("  {}. {}\n", i + 1, step));
+            }
+        }
+    } else if use_colors {
+        output.push_str(&format!(
+            "\n{}\n",
+            style("All layers passed.").green().bold()
+        ));
+    } else {
+        output.push_str("\nAll layers passed.\n");
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::get_all_services;
+
+    #[test]
+    fn test_doctor_scenarios_exist() {
+        for service in get_all_services() {
+            let name = service.name();
+            let scenario = doctor_scenario(name).expect("every service has a doctor scenario");
+            assert!(
+                service.list_scenarios().iter().any(|s| s.id == scenario),
+                "{} has no scenario {}",
+                name,
+                scenario
+            );
+        }
+    }
+
+    #[test]
+    fn test_environment_step_warns_on_unreachable_platform() {
+        let check =
+            |dependency: environment::Dependency, reachable: bool| environment::PlatformCheck {
+                dependency,
+                target: dependency.target().to_string(),
+                reachable,
+                latency_ms: reachable.then_some(2),
+                error: (!reachable).then(|| "timed out".to_string()),
+            };
+        let mut env = EnvironmentInfo {
+            azure_vm: true,
+            ..Default::default()
+        };
+        env.set_platform(vec![
+            check(environment::Dependency::Imds, false),
+            check(environment::Dependency::WireServer, true),
+            check(environment::Dependency::AzureDns, true),
+            check(environment::Dependency::TimeSync, true),
+        ]);
+        assert_eq!(env.imds_reachable, Some(false));
+
+        let step = environment_step(&env);
+        assert_eq!(step.status, StepStatus::Warn);
+        assert_eq!(
+            step.summary,
+            "Azure platform endpoint(s) unreachable from this VM: IMDS"
+        );
+        assert!(step.details[0].contains("managed identity tokens"));
+
+        // Off Azure, none of the platform endpoints is expected to answer
+        env.azure_vm = false;
+        env.platform[1].reachable = false;
+        env.platform[2].reachable = false;
+        assert_eq!(environment_step(&env).status, StepStatus::Pass);
+    }
+
+    #[test]
+    fn test_format_stops_at_failed_layer() {
+        let mut report = DoctorReport {
+            environment: EnvironmentInfo::default(),
+            steps: vec![DoctorStep::new(Layer::Environment, StepStatus::Pass, "ok")],
+            failed_layer: None,
+            explanation: None,
+            next_steps: Vec::new(),
+        };
+        report.fail(
+            DoctorStep::new(Layer::Dns, StepStatus::Fail, "x did not resolve"),
+            "DNS resolution of x failed".to_string(),
+            vec!["Run 'nslookup x' to confirm.".to_string()],
+        );
+
+        let output = format_doctor(&report, false);
+        assert!(output.contains("[FAIL] DNS"));
+        assert!(output.contains("Not checked: TCP, TLS, Authentication, Service"));
+        assert!(output.contains("1. Run 'nslookup x'"));
+        assert_eq!(report.exit_code(), ExitCode::NetworkFailure);
+    }
+}
@@ -0,0 +1,305 @@
+//! Reachability of the Azure platform endpoints applications on a VM depend on
+//!
+//! Instance Metadata (IMDS) serves managed identity tokens, the wire server
+//! serves the VM agent and health probes, Azure DNS resolves private DNS zones,
+//! and the time sync host keeps the clock within token tolerance. None of them
+//! is called by the application directly, so when one is blocked (a proxy
+//! without `NO_PROXY`, a host firewall, a custom route) the failure surfaces
+//! somewhere else and is rarely traced back.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::net::UdpSocket;
+
+/// How long each platform endpoint has to answer
+const PLATFORM_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// IMDS answers in a few milliseconds; slower than this, managed identity
+/// token requests from SDKs with short timeouts start failing
+pub const SLOW_IMDS_MS: u64 = 1000;
+
+/// Azure Instance Metadata Service endpoint
+const IMDS_URL: &str = "http://169.254.169.254/metadata/instance?api-version=2021-02-01";
+
+/// Wire server endpoint listing the protocol versions it supports
+const WIRE_SERVER_URL: &str = "http://168.63.129.16/?comp=versions";
+
+/// Azure-provided DNS, on the same virtual IP as the wire server
+const AZURE_DNS: &str = "168.63.129.16:53";
+
+/// Name looked up through Azure DNS
+const DNS_PROBE_NAME: &str = "management.azure.com";
+
+/// NTP server Windows and many Linux images on Azure sync with
+const TIME_SYNC_HOST: &str = "time.windows.com:123";
+
+/// An Azure platform endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dependency {
+    Imds,
+    WireServer,
+    AzureDns,
+    TimeSync,
+}
+
+impl Dependency {
+    pub const ALL: [Dependency; 4] = [
+        Dependency::Imds,
+        Dependency::WireServer,
+        Dependency::AzureDns,
+        Dependency::TimeSync,
+    ];
+
+    /// Address probed
+    pub fn target(self) -> &'static str {
+        match self {
+            Dependency::Imds => "169.254.169.254:80",
+            Dependency::WireServer => "168.63.129.16:80",
+            Dependency::AzureDns => AZURE_DNS,
+            Dependency::TimeSync => TIME_SYNC_HOST,
+        }
+    }
+
+    /// What breaks when the endpoint is unreachable
+    pub fn impact(self) -> &'static str {
+        match self {
+            Dependency::Imds => {
+                "managed identity tokens and instance metadata fail; check that HTTP(S)_PROXY \
+                 exempts 169.254.169.254 (NO_PROXY) and that no firewall or route blocks it"
+            }
+            Dependency::WireServer => {
+                "the VM agent, extensions and load balancer health probes fail; allow \
+                 168.63.129.16 in the guest firewall"
+            }
+            Dependency::AzureDns => {
+                "private DNS zones (privatelink.*) and VNet name resolution fail; allow UDP 53 \
+                 to 168.63.129.16 or check the custom DNS servers forward to it"
+            }
+            Dependency::TimeSync => {
+                "the clock can drift beyond the 5 minutes tokens tolerate; allow UDP 123 or \
+                 sync with the host (chrony with /dev/ptp_hyperv)"
+            }
+        }
+    }
+}
+
+impl fmt::Display for Dependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Dependency::Imds => "IMDS",
+            Dependency::WireServer => "Wire server",
+            Dependency::AzureDns => "Azure DNS",
+            Dependency::TimeSync => "Time sync",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// Result of probing one platform endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlatformCheck {
+    pub dependency: Dependency,
+    pub target: String,
+    pub reachable: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl PlatformCheck {
+    /// `reachable (3ms)`, `unreachable: timed out`
+    pub fn describe(&self) -> String {
+        match (self.reachable, self.latency_ms, &self.error) {
+            (true, Some(ms), _) => format!("reachable ({}ms)", ms),
+            (true, None, _) => "reachable".to_string(),
+            (false, _, Some(error)) => format!("unreachable: {}", error),
+            (false, _, None) => "unreachable".to_string(),
+        }
+    }
+
+    /// Warning for an unreachable or slow endpoint
+    pub fn warning(&self) -> Option<String> {
+        if !self.reachable {
+            return Some(format!(
+                "{} ({}) unreachable: {}",
+                self.dependency,
+                self.target,
+                self.dependency.impact()
+            ));
+        }
+        match (self.dependency, self.latency_ms) {
+            (Dependency::Imds, Some(ms)) if ms > SLOW_IMDS_MS => Some(format!(
+                "IMDS answered in {}ms; managed identity token requests may time out",
+                ms
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Probe every platform endpoint at once
+pub async fn check_all() -> Vec<PlatformCheck> {
+    let (imds, wire_server, dns, time) = tokio::join!(
+        check(Dependency::Imds),
+        check(Dependency::WireServer),
+        check(Dependency::AzureDns),
+        check(Dependency::TimeSync)
+    );
+    vec![imds, wire_server, dns, time]
+}
+
+/// Probe one platform endpoint
+pub async fn check(dependency: Dependency) -> PlatformCheck {
+    let start = Instant::now();
+    let result = tokio::time::timeout(PLATFORM_TIMEOUT, probe(dependency)).await;
+    let latency_ms = start.elapsed().as_millis() as u64;
+    let error = match result {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e),
+        Err(_) => Some("timed out".to_string()),
+    };
+    PlatformCheck {
+        dependency,
+        target: dependency.target().to_string(),
+        reachable: error.is_none(),
+        latency_ms: error.is_none().then_some(latency_ms),
+        error,
+    }
+}
+
+async fn probe(dependency: Dependency) -> Result<(), String> {
+    match dependency {
+        Dependency::Imds => http_probe(IMDS_URL).await,
+        Dependency::WireServer => http_probe(WIRE_SERVER_URL).await,
+        Dependency::AzureDns => {
+            let reply = udp_exchange(AZURE_DNS, &dns_query(DNS_PROBE_NAME)).await?;
+            // Same ID, and the QR bit marks a response
+            if reply.len() >= 12 && reply[..2] == DNS_QUERY_ID && reply[2] & 0x80 != 0 {
+                Ok(())
+            } else {
+                Err("malformed DNS response".to_string())
+            }
+        }
+        Dependency::TimeSync => {
+            let reply = udp_exchange(TIME_SYNC_HOST, &ntp_request()).await?;
+            // 48-byte packet in server mode (4)
+            if reply.len() >= 48 && reply[0] & 0x07 == 4 {
+                Ok(())
+            } else {
+                Err("malformed NTP response".to_string())
+            }
+        }
+    }
+}
+
+/// Any HTTP response proves the endpoint is reachable
+async fn http_probe(url: &str) -> Result<(), String> {
+    // Platform endpoints are link-local or host-only and must never go through a proxy
+    let client = reqwest::Client::builder()
+        .no_proxy()
+        .timeout(PLATFORM_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    client
+        .get(url)
+        .header("Metadata", "true")
+        .send()
+        .await
+        .map(|_| ())
+        .map_err(|e| {
+            if e.is_connect() {
+                "connection failed".to_string()
+            } else {
+                e.to_string()
+            }
+        })
+}
+
+/// Send one datagram to `target` and wait for the reply
+async fn udp_exchange(target: &str, request: &[u8]) -> Result<Vec<u8>, String> {
+    let socket = UdpSocket::bind("0.0.0.0:0")
+        .await
+        .map_err(|e| e.to_string())?;
+    socket.connect(target).await.map_err(|e| e.to_string())?;
+    socket.send(request).await.map_err(|e| e.to_string())?;
+    let mut buf = vec![0u8; 512];
+    let len = socket.recv(&mut buf).await.map_err(|e| e.to_string())?;
+    buf.truncate(len);
+    Ok(buf)
+}
+
+const DNS_QUERY_ID: [u8; 2] = [0x4a, 0x7a];
+
+/// A recursive DNS query for the A record of `name`
+fn dns_query(name: &str) -> Vec<u8> {
+    // ID, flags (recursion desired), 1 question, no answer/authority/additional
+    let mut query = vec![
+        DNS_QUERY_ID[0],
+        DNS_QUERY_ID[1],
+        0x01,
+        0x00,
+        0x00,
+        0x01,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+    ];
+    for label in name.split('.') {
+        query.push(label.len() as u8);
+        query.extend_from_slice(label.as_bytes());
+    }
+    // Root label, QTYPE A, QCLASS IN
+    query.extend_from_slice(&[0, 0, 1, 0, 1]);
+    query
+}
+
+/// An SNTP client request: version 3, client mode
+fn ntp_request() -> [u8; 48] {
+    let mut request = [0u8; 48];
+    request[0] = 0x1b;
+    request
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dns_query() {
+        let query = dns_query("management.azure.com");
+        assert_eq!(&query[..2], &DNS_QUERY_ID);
+        assert_eq!(&query[12..23], b"\x0amanagement");
+        assert!(query.ends_with(b"\x05azure\x03com\x00\x00\x01\x00\x01"));
+    }
+
+    #[test]
+    fn test_platform_warnings() {
+        let imds = |reachable: bool, latency_ms: Option<u64>| PlatformCheck {
+            dependency: Dependency::Imds,
+            target: Dependency::Imds.target().to_string(),
+            reachable,
+            latency_ms,
+            error: (!reachable).then(|| "timed out".to_string()),
+        };
+        assert_eq!(imds(true, Some(3)).warning(), None);
+        assert_eq!(imds(true, Some(3)).describe(), "reachable (3ms)");
+        assert!(imds(true, Some(1500))
+            .warning()
+            .unwrap()
+            .starts_with("IMDS answered in 1500ms"));
+
+        let down = imds(false, None);
+        assert_eq!(down.describe(), "unreachable: timed out");
+        assert!(down
+            .warning()
+            .unwrap()
+            .starts_with("IMDS (169.254.169.254:80) unreachable: managed identity tokens"));
+    }
+}
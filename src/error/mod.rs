@@ -10,10 +10,15 @@ pub fn sanitize_error(body: &str, status: u16) -> String {
     if status >= 500 {
         return format!("Server error (HTTP {})", status);
     }
+    let body = crate::redact::redact(body);
     if body.len() > MAX_ERROR_LEN {
-        format!("{}... (truncated)", &body[..MAX_ERROR_LEN])
+        let mut end = MAX_ERROR_LEN;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}... (truncated)", &body[..end])
     } else {
-        body.to_string()
+        body
     }
 }
 
@@ -32,6 +37,8 @@ pub enum ExitCode {
     ConfigError = 4,
     /// Invalid input
     InvalidInput = 5,
+    /// Stopped by SIGINT/SIGTERM before every test ran (128 + SIGINT)
+    Interrupted = 130,
 }
 
 impl From<ExitCode> for i32 {
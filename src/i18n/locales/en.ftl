@@ -0,0 +1,69 @@
+# English messages; every other locale falls back to these
+
+## Console report (test --output human)
+
+report-title = Azure AI Services Connectivity Test Results
+report-sku = SKU: { $sku }
+report-interrupted = Run interrupted: partial results, scenarios that had not started are missing
+report-hint = Hint:
+report-timing = Timing: { $timing }
+report-regions = Region Comparison
+report-region-line = passed { $passed } | failed { $failed } | skipped { $skipped } | { $avg }
+report-region-avg = avg { $ms }ms
+report-region-avg-none = avg -
+report-region-fastest = (fastest)
+report-regions-failing = Regions with failures: { $regions }
+report-environment = Environment
+report-usage = Quota & Usage
+report-findings = Findings
+report-summary = Summary
+report-total = Total: { $count }
+report-passed = Passed: { $count }
+report-failed = Failed: { $count }
+report-skipped = Skipped: { $count }
+report-throttled = Throttled (HTTP 429, not counted as failures): { $count }
+report-slow = Slow (over the latency threshold, not counted as failures): { $count }
+report-expected-failures = Expected failures (per [[expectations]], not counted as failures): { $count }
+report-failure-kinds = Failures by kind: { $kinds }
+report-duration = Duration: { $ms }ms
+
+## Remediation hints
+
+hint-dns-private = If the resource uses a private endpoint, make sure privatelink.cognitiveservices.azure.com resolves from this network (conditional forwarder to 168.63.129.16 or a linked private DNS zone). Run 'diagnose --dns' to compare results.
+hint-dns = Check the region name ('{ $region }') and your DNS server. Run 'list-regions' and 'diagnose --dns'.
+hint-tcp = Outbound TCP 443 is blocked or refused. Check firewall/NSG rules and HTTPS_PROXY settings.
+hint-tls = A proxy or firewall may be intercepting TLS. Trust its CA or exempt Azure AI endpoints from inspection. Run 'diagnose --tls --revocation'.
+hint-proxy = Check HTTPS_PROXY/NO_PROXY and proxy authentication; the proxy may be blocking this host.
+hint-timeout = Increase --timeout, or check for a proxy or firewall silently dropping connections. Run 'diagnose --latency'.
+hint-auth-key-custom = Check that the key belongs to this resource (keys are per resource; regenerate in the portal if unsure).
+hint-auth-key = Check that the key belongs to a resource in region '{ $region }'. Keys only work with their resource's regional endpoint; pass --region or --endpoint to match.
+hint-auth-token-regional = Entra ID tokens require the resource's custom subdomain endpoint; pass --endpoint https://<resource>.cognitiveservices.azure.com.
+hint-auth-token = The token was rejected. Check it is unexpired, issued by the resource's tenant, and scoped to https://cognitiveservices.azure.com.
+hint-auth-rbac = Assign the 'Cognitive Services User' role to this identity on the resource. New assignments can take a few minutes to apply.
+hint-local-auth-disabled = Use --auth device-code, interactive, or managed-identity with an identity that has the 'Cognitive Services User' role.
+hint-forbidden-key = The resource firewall may be blocking your IP, or public network access is disabled. Re-run with --resource-id to inspect its network ACLs.
+hint-forbidden = Access was denied. Check the resource firewall and the identity's role assignments.
+hint-throttled = Reduce the request rate or retry later. Free (F0) tiers have low rate limits.
+hint-region-unsupported = Run 'capabilities --region { $region }' and use a resource in a region that offers this feature.
+hint-not-found = Check the endpoint URL and that the resource kind supports this service; for custom models, check the project/deployment/model name.
+hint-server-error = The service returned an error. Retry later and check https://status.azure.com.
+hint-slow = Run 'diagnose --latency' to see whether DNS, TCP, TLS, or the service is slow.
+hint-unexpected-success = The policy that should block this scenario is not in effect: check the firewall, network rules, and proxy, or update [[expectations]] if the service is now allowed.
+hint-unexpected-response = Something other than the service may have answered: open the endpoint in a browser to check for a captive portal or proxy login page, and check whether an API gateway rewrites responses. If the body is the service's own, pin the API version it expects with --api-version.
+hint-intercepted = A proxy, firewall or captive portal answered instead of Azure: sign in to the portal named above, or have the network team exempt Azure AI endpoints from TLS inspection (or trust the named CA). Run 'diagnose --tls' to check each endpoint.
+hint-public-endpoint = Public egress to this host is open: block it at the firewall or proxy so traffic can only reach the private endpoint, or drop --assert-blocked if public access is intended.
+
+## Configuration wizard (init --interactive)
+
+wizard-title = Azure AI Tools Connect - Configuration Wizard
+wizard-cloud = Cloud environment
+wizard-region = Azure region
+wizard-auth = Authentication method
+wizard-api-key = API key (leave blank to set later)
+wizard-tenant = Tenant ID
+wizard-token-later = You can set the bearer token later with --bearer-token or AZURE_BEARER_TOKEN.
+wizard-endpoint = Custom endpoint URL (leave blank for regional)
+wizard-available-services = Available services: { $services }
+wizard-services = Services to enable (comma-separated, or 'all')
+wizard-invalid-choice = Invalid choice '{ $input }', using default '{ $default }'
+wizard-ready = Configuration ready.
@@ -0,0 +1,69 @@
+# 简体中文；缺少的消息使用英文
+
+## 控制台报告 (test --output human)
+
+report-title = Azure AI 服务连通性测试结果
+report-sku = SKU：{ $sku }
+report-interrupted = 运行已中断：结果不完整，未开始的场景未列出
+report-hint = 提示：
+report-timing = 耗时：{ $timing }
+report-regions = 区域对比
+report-region-line = 通过 { $passed } | 失败 { $failed } | 跳过 { $skipped } | { $avg }
+report-region-avg = 平均 { $ms }ms
+report-region-avg-none = 平均 -
+report-region-fastest = （最快）
+report-regions-failing = 存在失败的区域：{ $regions }
+report-environment = 环境
+report-usage = 配额与用量
+report-findings = 发现
+report-summary = 汇总
+report-total = 总计：{ $count }
+report-passed = 通过：{ $count }
+report-failed = 失败：{ $count }
+report-skipped = 跳过：{ $count }
+report-throttled = 限流（HTTP 429，不计为失败）：{ $count }
+report-slow = 较慢（超过延迟阈值，不计为失败）：{ $count }
+report-expected-failures = 预期失败（依据 [[expectations]]，不计为失败）：{ $count }
+report-failure-kinds = 按类型统计的失败：{ $kinds }
+report-duration = 用时：{ $ms }ms
+
+## 修复提示
+
+hint-dns-private = 如果资源使用专用终结点，请确认此网络能解析 privatelink.cognitiveservices.azure.com（条件转发到 168.63.129.16，或链接专用 DNS 区域）。运行 'diagnose --dns' 比较解析结果。
+hint-dns = 请检查区域名称（'{ $region }'）和 DNS 服务器。运行 'list-regions' 和 'diagnose --dns'。
+hint-tcp = 出站 TCP 443 被阻止或拒绝。请检查防火墙/NSG 规则和 HTTPS_PROXY 设置。
+hint-tls = 代理或防火墙可能在拦截 TLS。请信任其 CA，或将 Azure AI 终结点排除在检查之外。运行 'diagnose --tls --revocation'。
+hint-proxy = 请检查 HTTPS_PROXY/NO_PROXY 和代理身份验证；代理可能阻止了此主机。
+hint-timeout = 请增大 --timeout，或检查是否有代理或防火墙静默丢弃连接。运行 'diagnose --latency'。
+hint-auth-key-custom = 请确认密钥属于此资源（密钥按资源区分；如不确定，请在门户中重新生成）。
+hint-auth-key = 请确认密钥属于区域 '{ $region }' 中的资源。密钥只能用于其资源所在区域的终结点；请通过 --region 或 --endpoint 指定对应的值。
+hint-auth-token-regional = Entra ID 令牌需要使用资源的自定义子域终结点；请传入 --endpoint https://<resource>.cognitiveservices.azure.com。
+hint-auth-token = 令牌被拒绝。请确认令牌未过期、由资源所在租户颁发，且作用域为 https://cognitiveservices.azure.com。
+hint-auth-rbac = 请在资源上为此标识分配 'Cognitive Services User' 角色。新的角色分配可能需要几分钟才能生效。
+hint-local-auth-disabled = 请使用 --auth device-code、interactive 或 managed-identity，并使用具有 'Cognitive Services User' 角色的标识。
+hint-forbidden-key = 资源防火墙可能阻止了你的 IP，或已禁用公用网络访问。请加上 --resource-id 重新运行以查看其网络 ACL。
+hint-forbidden = 访问被拒绝。请检查资源防火墙和标识的角色分配。
+hint-throttled = 请降低请求速率或稍后重试。免费 (F0) 层的速率限制较低。
+hint-region-unsupported = 运行 'capabilities --region { $region }'，并使用提供此功能的区域中的资源。
+hint-not-found = 请检查终结点 URL，以及资源类型是否支持此服务；对于自定义模型，请检查项目/部署/模型名称。
+hint-server-error = 服务返回了错误。请稍后重试，并查看 https://status.azure.com。
+hint-slow = 运行 'diagnose --latency' 查看是 DNS、TCP、TLS 还是服务本身较慢。
+hint-unexpected-success = 本应阻止此场景的策略未生效：请检查防火墙、网络规则和代理；如果现在允许访问此服务，请更新 [[expectations]]。
+hint-unexpected-response = 应答的可能不是服务本身：请在浏览器中打开终结点，检查是否有强制门户或代理登录页，并检查 API 网关是否改写了响应。如果响应确实来自服务，请用 --api-version 固定其所需的 API 版本。
+hint-intercepted = 代理、防火墙或强制门户代替 Azure 作了应答：请登录上面提到的门户，或请网络团队将 Azure AI 终结点排除在 TLS 检查之外（或信任上面提到的 CA）。运行 'diagnose --tls' 检查每个终结点。
+hint-public-endpoint = 到此主机的公网出站访问是开放的：请在防火墙或代理上阻止它，使流量只能到达专用终结点；如果允许公网访问，请去掉 --assert-blocked。
+
+## 配置向导 (init --interactive)
+
+wizard-title = Azure AI Tools Connect - 配置向导
+wizard-cloud = 云环境
+wizard-region = Azure 区域
+wizard-auth = 身份验证方式
+wizard-api-key = API 密钥（留空以后再设置）
+wizard-tenant = 租户 ID
+wizard-token-later = 之后可以通过 --bearer-token 或 AZURE_BEARER_TOKEN 设置持有者令牌。
+wizard-endpoint = 自定义终结点 URL（留空使用区域终结点）
+wizard-available-services = 可用服务：{ $services }
+wizard-services = 要启用的服务（逗号分隔，或 'all'）
+wizard-invalid-choice = 无效的选项 '{ $input }'，使用默认值 '{ $default }'
+wizard-ready = 配置已就绪。
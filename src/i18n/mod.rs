@@ -0,0 +1,210 @@
+//! Localized user-facing text
+//!
+//! The console report, remediation hints, and the configuration wizard are
+//! looked up by message ID in Fluent (`.ftl`) files embedded in the binary:
+//! English, and Simplified Chinese for operators of the China cloud. A message
+//! a locale doesn't translate falls back to English. Everything meant for
+//! machines (JSON keys, failure kinds, scenario IDs, log lines) stays English.
+//!
+//! The language is chosen once per process: `--lang`, or the `LC_ALL`,
+//! `LC_MESSAGES`, or `LANG` environment variable.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::LazyLock;
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+
+const EN_FTL: &str = include_str!("locales/en.ftl");
+const ZH_CN_FTL: &str = include_str!("locales/zh-CN.ftl");
+
+/// A language user-facing text is available in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Lang {
+    #[default]
+    En,
+    ZhCn,
+}
+
+impl Lang {
+    pub const ALL: [Lang; 2] = [Lang::En, Lang::ZhCn];
+
+    /// BCP 47 tag
+    pub fn tag(self) -> &'static str {
+        match self {
+            Lang::En => "en",
+            Lang::ZhCn => "zh-CN",
+        }
+    }
+
+    /// Language of a BCP 47 tag or POSIX locale (`zh-CN`, `zh_CN.UTF-8`,
+    /// `en_US`); `None` for one without a translation. Traditional Chinese
+    /// (`zh-TW`, `zh-HK`, `zh-Hant`) has none.
+    pub fn from_locale(locale: &str) -> Option<Lang> {
+        let locale = locale
+            .split(['.', '@'])
+            .next()
+            .unwrap_or_default()
+            .replace('_', "-")
+            .to_lowercase();
+        let mut parts = locale.split('-');
+        match parts.next()? {
+            "en" | "c" | "posix" => Some(Lang::En),
+            "zh" => match parts.next() {
+                None | Some("cn" | "sg" | "hans") => Some(Lang::ZhCn),
+                Some(_) => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Language of the environment's locale, English if it has no translation
+    pub fn detect() -> Lang {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|locale| Lang::from_locale(&locale))
+            .unwrap_or_default()
+    }
+
+    /// Message `id` in this language
+    pub fn text(self, id: &str) -> String {
+        self.text_args(id, &[])
+    }
+
+    /// Message `id` in this language, with its `{ $name }` placeables filled
+    pub fn text_args(self, id: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+        let mut fluent_args = FluentArgs::new();
+        for (name, value) in args {
+            fluent_args.set(*name, value.to_string());
+        }
+        [self, Lang::En]
+            .iter()
+            .find_map(|lang| format(&BUNDLES[*lang as usize], id, &fluent_args))
+            .unwrap_or_else(|| id.to_string())
+    }
+}
+
+impl fmt::Display for Lang {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.tag())
+    }
+}
+
+static LANG: AtomicU8 = AtomicU8::new(Lang::En as u8);
+
+/// One bundle per [`Lang`], in declaration order
+static BUNDLES: LazyLock<Vec<FluentBundle<FluentResource>>> = LazyLock::new(|| {
+    Lang::ALL
+        .iter()
+        .map(|lang| {
+            let source = match lang {
+                Lang::En => EN_FTL,
+                Lang::ZhCn => ZH_CN_FTL,
+            };
+            let resource = FluentResource::try_new(source.to_string())
+                .unwrap_or_else(|(resource, _)| resource);
+            let mut bundle =
+                FluentBundle::new_concurrent(vec![lang.tag().parse().unwrap_or_default()]);
+            // Isolation marks show up as stray characters in terminals
+            bundle.set_use_isolating(false);
+            // A duplicate message keeps its first definition
+            let _ = bundle.add_resource(resource);
+            bundle
+        })
+        .collect()
+});
+
+fn format(
+    bundle: &FluentBundle<FluentResource>,
+    id: &str,
+    args: &FluentArgs<'_>,
+) -> Option<String> {
+    let pattern = bundle.get_message(id)?.value()?;
+    let mut errors = Vec::new();
+    let text = bundle.format_pattern(pattern, Some(args), &mut errors);
+    Some(text.into_owned())
+}
+
+/// Set the language of user-facing text for this process (`--lang`)
+pub fn set_lang(lang: Lang) {
+    LANG.store(lang as u8, Ordering::Relaxed);
+}
+
+/// Language of user-facing text
+pub fn lang() -> Lang {
+    match LANG.load(Ordering::Relaxed) {
+        n if n == Lang::ZhCn as u8 => Lang::ZhCn,
+        _ => Lang::En,
+    }
+}
+
+/// Message `id` in the process's language
+pub fn tr(id: &str) -> String {
+    lang().text(id)
+}
+
+/// Message `id` in the process's language, with its placeables filled
+pub fn tr_args(id: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    lang().text_args(id, args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// IDs of the messages in a `.ftl` file
+    fn message_ids(source: &str) -> Vec<&str> {
+        source
+            .lines()
+            .filter(|line| line.starts_with(|c: char| c.is_ascii_alphabetic()))
+            .filter_map(|line| line.split_once(" = ").map(|(id, _)| id))
+            .collect()
+    }
+
+    #[test]
+    fn test_every_message_is_translated() {
+        let en = message_ids(EN_FTL);
+        let zh = message_ids(ZH_CN_FTL);
+        assert!(en.len() > 40);
+        assert_eq!(
+            en, zh,
+            "zh-CN.ftl must define the messages of en.ftl, in order"
+        );
+        for id in en {
+            for lang in Lang::ALL {
+                assert_ne!(lang.text(id), id, "{} is missing from {}", id, lang);
+            }
+        }
+    }
+
+    #[test]
+    fn test_text_fills_arguments_and_falls_back() {
+        assert_eq!(
+            Lang::En.text_args("report-total", &[("count", &3)]),
+            "Total: 3"
+        );
+        assert_eq!(
+            Lang::ZhCn.text_args("report-total", &[("count", &3)]),
+            "总计：3"
+        );
+        assert_eq!(
+            Lang::ZhCn.text_args("hint-dns", &[("region", &"chinaeast2")]),
+            "请检查区域名称（'chinaeast2'）和 DNS 服务器。运行 'list-regions' 和 'diagnose --dns'。"
+        );
+        assert_eq!(Lang::ZhCn.text("no-such-message"), "no-such-message");
+    }
+
+    #[test]
+    fn test_from_locale() {
+        assert_eq!(Lang::from_locale("zh_CN.UTF-8"), Some(Lang::ZhCn));
+        assert_eq!(Lang::from_locale("zh-Hans"), Some(Lang::ZhCn));
+        assert_eq!(Lang::from_locale("zh"), Some(Lang::ZhCn));
+        assert_eq!(Lang::from_locale("zh_TW"), None);
+        assert_eq!(Lang::from_locale("en_US.UTF-8"), Some(Lang::En));
+        assert_eq!(Lang::from_locale("C"), Some(Lang::En));
+        assert_eq!(Lang::from_locale("de_DE"), None);
+    }
+}
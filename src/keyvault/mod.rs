@@ -0,0 +1,230 @@
+//! Azure Key Vault secret references
+//!
+//! An `api_key` of the form
+//! `keyvault:https://<vault>.vault.azure.net/secrets/<name>[/<version>]` is
+//! replaced before the run with the secret's value, read with the run's Entra
+//! credential, so raw keys never have to be stored in config.toml.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use reqwest::Client;
+use serde::Deserialize;
+
+use crate::auth::get_resource_token;
+use crate::config::{AuthMethod, Cloud, EntraConfig, UserAuthConfig};
+use crate::error::{AppError, Result};
+
+/// Prefix marking a config value as a Key Vault secret reference
+pub const SECRET_PREFIX: &str = "keyvault:";
+
+/// Key Vault data-plane API version
+const API_VERSION: &str = "7.4";
+
+/// Whether a config value is a Key Vault secret reference
+pub fn is_reference(value: &str) -> bool {
+    value.starts_with(SECRET_PREFIX)
+}
+
+/// A secret identifier (`https://<vault>/secrets/<name>[/<version>]`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretReference {
+    /// Vault URL (e.g. `https://myvault.vault.azure.net`)
+    pub vault_url: String,
+    pub name: String,
+    /// Specific version; the latest version when not set
+    pub version: Option<String>,
+}
+
+impl SecretReference {
+    /// Parse a secret identifier, with or without the `keyvault:` prefix
+    pub fn parse(value: &str) -> Result<Self> {
+        let identifier = value.strip_prefix(SECRET_PREFIX).unwrap_or(value).trim();
+        let invalid = |reason: &str| {
+            AppError::Config(format!(
+                "Invalid Key Vault secret reference '{}': {} \
+                 (expected https://<vault>.vault.azure.net/secrets/<name>)",
+                identifier, reason
+            ))
+        };
+
+        let url = url::Url::parse(identifier).map_err(|e| invalid(&e.to_string()))?;
+        if url.scheme() != "https" {
+            return Err(invalid("the vault URL must use https"));
+        }
+        let host = url
+            .host_str()
+            .ok_or_else(|| invalid("missing vault host"))?;
+        let segments: Vec<&str> = url
+            .path_segments()
+            .map(|s| s.filter(|s| !s.is_empty()).collect())
+            .unwrap_or_default();
+        let (name, version) = match segments.as_slice() {
+            ["secrets", name] => (*name, None),
+            ["secrets", name, version] => (*name, Some(version.to_string())),
+            _ => return Err(invalid("path must be /secrets/<name>[/<version>]")),
+        };
+
+        let vault_url = match url.port() {
+            Some(port) => format!("https://{}:{}", host, port),
+            None => format!("https://{}", host),
+        };
+        Ok(Self {
+            vault_url,
+            name: name.to_string(),
+            version,
+        })
+    }
+
+    /// URL of the Get Secret request
+    fn request_url(&self) -> String {
+        match &self.version {
+            Some(version) => format!(
+                "{}/secrets/{}/{}?api-version={}",
+                self.vault_url, self.name, version, API_VERSION
+            ),
+            None => format!(
+                "{}/secrets/{}?api-version={}",
+                self.vault_url, self.name, API_VERSION
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for SecretReference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/secrets/{}", self.vault_url, self.name)?;
+        if let Some(version) = &self.version {
+            write!(f, "/{}", version)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct SecretBundle {
+    value: String,
+}
+
+/// Minimal Key Vault client for reading secrets
+pub struct KeyVaultClient {
+    client: Client,
+    token: String,
+    /// Secrets already read, by request URL
+    cache: HashMap<String, String>,
+}
+
+impl KeyVaultClient {
+    pub fn new(token: String, timeout: Duration) -> Result<Self> {
+        let client = crate::network::configure_client(Client::builder())
+            .timeout(timeout)
+            .build()
+            .map_err(|e| AppError::Network(e.to_string()))?;
+        Ok(Self {
+            client,
+            token,
+            cache: HashMap::new(),
+        })
+    }
+
+    /// Read a secret's value
+    pub async fn get_secret(&mut self, reference: &SecretReference) -> Result<String> {
+        let url = reference.request_url();
+        if let Some(value) = self.cache.get(&url) {
+            return Ok(value.clone());
+        }
+
+        let response = self
+            .client
+            .get(&url)
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .map_err(|e| {
+                AppError::Network(format!(
+                    "Could not reach Key Vault {}: {}",
+                    reference.vault_url, e
+                ))
+            })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            let message = format!(
+                "Key Vault returned HTTP {} for secret {}: {}",
+                status,
+                reference,
+                crate::error::sanitize_error(&body, status.as_u16())
+            );
+            return Err(match status.as_u16() {
+                401 | 403 => AppError::Auth(format!(
+                    "{} (the identity needs the 'Key Vault Secrets User' role or a get-secret access policy)",
+                    message
+                )),
+                _ => AppError::Config(message),
+            });
+        }
+
+        let bundle: SecretBundle = response.json().await.map_err(|e| {
+            AppError::Config(format!(
+                "Unexpected Key Vault response for {}: {}",
+                reference, e
+            ))
+        })?;
+        self.cache.insert(url, bundle.value.clone());
+        Ok(bundle.value)
+    }
+}
+
+/// Get a Key Vault token with the run's Entra credential (see
+/// [`get_resource_token`])
+pub async fn get_vault_token(
+    auth_method: AuthMethod,
+    cloud: Cloud,
+    entra_config: Option<&EntraConfig>,
+    user_config: Option<&UserAuthConfig>,
+    quiet: bool,
+) -> Result<String> {
+    get_resource_token(
+        cloud.keyvault_resource(),
+        "Key Vault",
+        auth_method,
+        cloud,
+        entra_config,
+        user_config,
+        quiet,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_secret_reference() {
+        let reference =
+            SecretReference::parse("keyvault:https://myvault.vault.azure.net/secrets/speech-key")
+                .unwrap();
+        assert_eq!(reference.vault_url, "https://myvault.vault.azure.net");
+        assert_eq!(reference.name, "speech-key");
+        assert_eq!(reference.version, None);
+        assert_eq!(
+            reference.request_url(),
+            "https://myvault.vault.azure.net/secrets/speech-key?api-version=7.4"
+        );
+
+        let reference =
+            SecretReference::parse("https://myvault.vault.azure.net/secrets/speech-key/0123abcd/")
+                .unwrap();
+        assert_eq!(reference.version.as_deref(), Some("0123abcd"));
+        assert_eq!(
+            reference.to_string(),
+            "https://myvault.vault.azure.net/secrets/speech-key/0123abcd"
+        );
+
+        assert!(SecretReference::parse("keyvault:https://myvault.vault.azure.net/keys/k").is_err());
+        assert!(SecretReference::parse("keyvault:http://myvault/secrets/k").is_err());
+        assert!(SecretReference::parse("keyvault:speech-key").is_err());
+    }
+}
@@ -26,20 +26,67 @@
 //! # Run network diagnostics
 //! azure-aitoolsconnect diagnose --dns --tls --latency --region eastus
 //! ```
+//!
+//! # Library Usage
+//!
+//! The runner never prints or exits; it returns a typed [`TestReport`] and
+//! reports live progress to any [`ProgressReporter`] given to
+//! [`TestRunner::with_reporter`].
+//!
+//! ```no_run
+//! use azure_aitoolsconnect::{AuthMethod, FailOn, TestRunner, TestRunnerConfig};
+//!
+//! # async fn check() -> azure_aitoolsconnect::Result<()> {
+//! let config = TestRunnerConfig::builder()
+//!     .services(["speech", "language"])
+//!     .auth_method(AuthMethod::Key)
+//!     .api_key(std::env::var("AZURE_AI_KEY").unwrap_or_default())
+//!     .region("eastus")
+//!     .build();
+//! let report = TestRunner::new(config).run().await?;
+//! for service in &report.services {
+//!     for result in service.results.iter().filter(|r| !r.success) {
+//!         println!("{}: {:?} {:?}", result.scenario_id, result.failure_kind, result.error);
+//!     }
+//! }
+//! assert!(report.passes(FailOn::Failed));
+//! # Ok(())
+//! # }
+//! ```
 
+pub mod aggregate;
+pub mod arm;
+pub mod artifacts;
 pub mod auth;
+pub mod capture;
 pub mod cli;
 pub mod config;
+pub mod doctor;
+pub mod environment;
 pub mod error;
+pub mod i18n;
+pub mod keyvault;
+pub mod logging;
+pub mod mock;
 pub mod network;
+pub mod notify;
 pub mod output;
+pub mod redact;
+pub mod selftest;
+pub mod serve;
 pub mod services;
 pub mod testing;
+pub mod tui;
+pub mod upload;
+pub mod usage;
 
 pub use auth::{AuthProvider, Credentials, DeviceCodeAuth, ManagedIdentityAuth, TokenResult};
 pub use cli::{Cli, Commands};
-pub use config::{AuthMethod, Cloud, Config, OutputFormat};
+pub use config::{AuthMethod, Cloud, Config, FailOn, OutputFormat};
 pub use error::{AppError, ExitCode, Result};
-pub use output::{get_formatter, TestReport};
-pub use services::{get_all_services, get_service, AzureService, TestResult};
-pub use testing::{TestRunner, TestRunnerConfig};
+pub use output::{get_formatter, RunMetadata, TestReport, TestSummary};
+pub use services::{
+    get_all_services, get_service, register_service, AzureService, FailureKind, Interrupt,
+    ProgressReporter, RunEvent, ServiceRegistry, ServiceTestResults, TestResult,
+};
+pub use testing::{TestRunner, TestRunnerConfig, TestRunnerConfigBuilder};
@@ -0,0 +1,389 @@
+//! Structured diagnostic logging (`--log-level`, `--log-file`, `--log-format`)
+//!
+//! A small `tracing` subscriber that writes timestamped text or JSON lines to
+//! stderr or a file. Events from this crate are shown down to the configured
+//! level; events from dependencies only at WARN and above so `debug` stays
+//! readable.
+
+use crate::error::{AppError, Result};
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+
+/// Target prefix of events emitted by this crate
+const CRATE_TARGET: &str = "azure_aitoolsconnect";
+
+/// Log line format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// Name and fields of an open span
+struct SpanData {
+    name: &'static str,
+    fields: Vec<(String, Value)>,
+    refs: usize,
+}
+
+thread_local! {
+    /// Spans entered on this thread, innermost last
+    static SPAN_STACK: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Collects an event's or span's fields, keeping numbers and booleans typed
+/// for JSON output
+#[derive(Default)]
+struct FieldVisitor {
+    message: Option<String>,
+    fields: Vec<(String, Value)>,
+}
+
+impl FieldVisitor {
+    fn push(&mut self, field: &Field, value: Value) {
+        self.fields.push((field.name().to_string(), value));
+    }
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = Some(value.to_string());
+        } else {
+            self.push(field, Value::String(value.to_string()));
+        }
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.push(field, Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.push(field, Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.push(field, Value::from(value));
+    }
+
+    /// Non-finite values have no JSON number and are kept as strings
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        let value = serde_json::Number::from_f64(value)
+            .map(Value::Number)
+            .unwrap_or_else(|| Value::String(value.to_string()));
+        self.push(field, value);
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.message = Some(format!("{:?}", value));
+        } else {
+            self.push(field, Value::String(format!("{:?}", value)));
+        }
+    }
+}
+
+/// `tracing` subscriber writing text or JSON lines
+pub struct LogSubscriber {
+    level: LevelFilter,
+    format: LogFormat,
+    writer: Mutex<Box<dyn Write + Send>>,
+    next_id: AtomicU64,
+    spans: Mutex<HashMap<u64, SpanData>>,
+}
+
+impl LogSubscriber {
+    pub fn new(level: LevelFilter, format: LogFormat, writer: Box<dyn Write + Send>) -> Self {
+        Self {
+            level,
+            format,
+            writer: Mutex::new(writer),
+            next_id: AtomicU64::new(1),
+            spans: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Names and fields of the spans entered on this thread, outermost first
+    fn current_spans(&self) -> Vec<(&'static str, Vec<(String, Value)>)> {
+        let ids = SPAN_STACK.with(|stack| stack.borrow().clone());
+        let Ok(spans) = self.spans.lock() else {
+            return Vec::new();
+        };
+        ids.iter()
+            .filter_map(|id| spans.get(id))
+            .map(|span| (span.name, span.fields.clone()))
+            .collect()
+    }
+
+    fn format_line(&self, event: &Event<'_>, visitor: FieldVisitor) -> String {
+        let metadata = event.metadata();
+        let timestamp = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let spans = self.current_spans();
+
+        match self.format {
+            LogFormat::Text => {
+                let mut line = format!("{} {:>5} ", timestamp, metadata.level());
+                for (name, fields) in &spans {
+                    line.push_str(name);
+                    if !fields.is_empty() {
+                        let fields: Vec<String> = fields
+                            .iter()
+                            .map(|(k, v)| format!("{}={}", k, text(v)))
+                            .collect();
+                        let _ = write!(line, "{{{}}}", fields.join(" "));
+                    }
+                    line.push_str(": ");
+                }
+                line.push_str(visitor.message.as_deref().unwrap_or(""));
+                for (key, value) in &visitor.fields {
+                    let _ = write!(line, " {}={}", key, text(value));
+                }
+                line
+            }
+            LogFormat::Json => {
+                let mut object = Map::new();
+                object.insert("timestamp".into(), Value::String(timestamp));
+                object.insert("level".into(), Value::String(metadata.level().to_string()));
+                object.insert(
+                    "target".into(),
+                    Value::String(metadata.target().to_string()),
+                );
+                if !spans.is_empty() {
+                    let spans = spans
+                        .into_iter()
+                        .map(|(name, fields)| {
+                            let mut span = fields_to_map(fields);
+                            span.insert("name".into(), Value::String(name.to_string()));
+                            Value::Object(span)
+                        })
+                        .collect();
+                    object.insert("spans".into(), Value::Array(spans));
+                }
+                if let Some(message) = visitor.message {
+                    object.insert("message".into(), Value::String(message));
+                }
+                if !visitor.fields.is_empty() {
+                    object.insert(
+                        "fields".into(),
+                        Value::Object(fields_to_map(visitor.fields)),
+                    );
+                }
+                Value::Object(object).to_string()
+            }
+        }
+    }
+}
+
+fn fields_to_map(fields: Vec<(String, Value)>) -> Map<String, Value> {
+    fields.into_iter().collect()
+}
+
+/// A field's value as written in text lines: strings without quotes
+fn text(value: &Value) -> std::borrow::Cow<'_, str> {
+    match value {
+        Value::String(s) => s.as_str().into(),
+        other => other.to_string().into(),
+    }
+}
+
+impl Subscriber for LogSubscriber {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        if metadata.level() > &self.level {
+            return false;
+        }
+        metadata.target().starts_with(CRATE_TARGET) || *metadata.level() <= Level::WARN
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(self.level)
+    }
+
+    fn new_span(&self, attrs: &Attributes<'_>) -> Id {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        if let Ok(mut spans) = self.spans.lock() {
+            spans.insert(
+                id,
+                SpanData {
+                    name: attrs.metadata().name(),
+                    fields: visitor.fields,
+                    refs: 1,
+                },
+            );
+        }
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        let mut visitor = FieldVisitor::default();
+        values.record(&mut visitor);
+        if let Ok(mut spans) = self.spans.lock() {
+            if let Some(data) = spans.get_mut(&span.into_u64()) {
+                data.fields.extend(visitor.fields);
+            }
+        }
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        let line = crate::redact::redact(&self.format_line(event, visitor));
+        if let Ok(mut writer) = self.writer.lock() {
+            // Logging must never fail the run
+            let _ = writeln!(writer, "{}", line);
+            let _ = writer.flush();
+        }
+    }
+
+    fn enter(&self, span: &Id) {
+        SPAN_STACK.with(|stack| stack.borrow_mut().push(span.into_u64()));
+    }
+
+    fn exit(&self, span: &Id) {
+        SPAN_STACK.with(|stack| {
+            let mut stack = stack.borrow_mut();
+            if let Some(pos) = stack.iter().rposition(|id| *id == span.into_u64()) {
+                stack.remove(pos);
+            }
+        });
+    }
+
+    fn clone_span(&self, id: &Id) -> Id {
+        if let Ok(mut spans) = self.spans.lock() {
+            if let Some(data) = spans.get_mut(&id.into_u64()) {
+                data.refs += 1;
+            }
+        }
+        id.clone()
+    }
+
+    fn try_close(&self, id: Id) -> bool {
+        let Ok(mut spans) = self.spans.lock() else {
+            return false;
+        };
+        let key = id.into_u64();
+        match spans.get_mut(&key) {
+            Some(data) if data.refs > 1 => {
+                data.refs -= 1;
+                false
+            }
+            Some(_) => {
+                spans.remove(&key);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Install the global log subscriber. Logs go to `file` (appended) or stderr.
+pub fn init(level: LevelFilter, format: LogFormat, file: Option<&Path>) -> Result<()> {
+    if level == LevelFilter::OFF {
+        return Ok(());
+    }
+    let writer: Box<dyn Write + Send> = match file {
+        Some(path) => Box::new(
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?,
+        ),
+        None => Box::new(std::io::stderr()),
+    };
+    tracing::subscriber::set_global_default(LogSubscriber::new(level, format, writer))
+        .map_err(|e| AppError::Config(format!("Failed to initialize logging: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// Writer that appends into a shared buffer
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn capture_logs(format: LogFormat, f: impl FnOnce()) -> String {
+        let buffer = SharedBuffer::default();
+        let subscriber = LogSubscriber::new(LevelFilter::DEBUG, format, Box::new(buffer.clone()));
+        tracing::subscriber::with_default(subscriber, f);
+        let bytes = buffer.0.lock().unwrap().clone();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_text_format_with_span() {
+        let output = capture_logs(LogFormat::Text, || {
+            let span = tracing::info_span!("scenario", service = "speech", id = "tts");
+            let _guard = span.enter();
+            tracing::debug!(status = 200, "response received");
+            tracing::trace!("too detailed");
+        });
+
+        assert!(output.contains("DEBUG scenario{service=speech id=tts}: response received"));
+        assert!(output.contains("status=200"));
+        assert!(!output.contains("too detailed"));
+    }
+
+    #[test]
+    fn test_json_format() {
+        let output = capture_logs(LogFormat::Json, || {
+            let span = tracing::info_span!("scheduled_run", attempt = 2u64);
+            let _guard = span.enter();
+            tracing::warn!(
+                retry_after_ms = 1000,
+                offset = -5,
+                throttled = true,
+                ratio = 0.5,
+                region = "eastus",
+                "throttled"
+            );
+        });
+
+        let line: Value = serde_json::from_str(output.trim()).unwrap();
+        assert_eq!(line["level"], "WARN");
+        assert_eq!(line["message"], "throttled");
+        assert_eq!(line["fields"]["retry_after_ms"], 1000);
+        assert_eq!(line["fields"]["offset"], -5);
+        assert_eq!(line["fields"]["throttled"], true);
+        assert_eq!(line["fields"]["ratio"], 0.5);
+        assert_eq!(line["fields"]["region"], "eastus");
+        assert_eq!(line["spans"][0]["attempt"], 2);
+    }
+
+    #[test]
+    fn test_dependency_events_filtered() {
+        let output = capture_logs(LogFormat::Text, || {
+            tracing::debug!(target: "h2::codec", "frame received");
+            tracing::warn!(target: "rustls::conn", "alert received");
+        });
+
+        assert!(!output.contains("frame received"));
+        assert!(output.contains("alert received"));
+    }
+}
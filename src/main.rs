@@ -1,15 +1,35 @@
 use azure_aitoolsconnect::{
+    capture::{session::Session, CaptureLog},
     cli::{parse_services, Cli, Commands},
-    config::{validate_config, Config},
+    config::{validate_config, AuthMethod, Config},
+    doctor::{format_doctor, run_doctor as run_doctor_checks},
+    environment::{capture as capture_environment, capture_local as capture_local_environment},
     error::ExitCode,
-    network::{format_diagnostics, run_diagnostics},
-    output::{get_formatter, write_output},
-    testing::{format_scenarios, list_scenarios, TestRunner, TestRunnerConfig},
+    mock::{MockServer, MOCK_API_KEY, MOCK_ENDPOINT},
+    network::{
+        detect_public_ip, diagnostic_groups, endpoint_host, format_diagnostics,
+        parse_endpoints_file, run_address_enrichment, run_concurrency_checks, run_diagnostics,
+        run_doh_comparison, run_icmp_checks, run_port_checks, unique_hosts, ServiceTags,
+    },
+    notify::{email::EmailSettings, should_notify, NotifyOn, NotifyTarget, RunState},
+    output::{get_formatter, open_output, spawn_ndjson_stream, write_output, NdjsonFormatter},
+    selftest::{format_selftest, run_selftest as run_selftest_checks},
+    services::capabilities::{format_capabilities, format_regions},
+    services::custom::CUSTOM_SERVICE_NAME,
+    services::ProgressReporter,
+    testing::{
+        format_plan, format_scenarios, list_scenarios, scenario_catalog, AbortOnDrop, TestRunner,
+        TestRunnerConfig,
+    },
+    tui::run_tui,
+    upload,
 };
 use clap::Parser;
 use console::style;
-use std::io::IsTerminal;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::io::{IsTerminal, Write};
 use std::process::ExitCode as StdExitCode;
+use std::sync::{Arc, OnceLock};
 
 #[tokio::main]
 async fn main() -> StdExitCode {
@@ -21,10 +41,20 @@ async fn main() -> StdExitCode {
     let exit_code = match run(cli).await {
         Ok(code) => code,
         Err(e) => {
-            eprintln!("{} {}", style("Error:").red().bold(), e);
+            eprintln!(
+                "{} {}",
+                style("Error:").red().bold(),
+                azure_aitoolsconnect::redact::redact(&e.to_string())
+            );
             if let Some(hint) = e.hint() {
                 eprintln!();
-                eprintln!("{} {}", style("Hint:").yellow().bold(), hint);
+                eprintln!(
+                    "{} {}",
+                    style(azure_aitoolsconnect::i18n::tr("report-hint"))
+                        .yellow()
+                        .bold(),
+                    hint
+                );
             }
             e.exit_code()
         }
@@ -34,74 +64,836 @@ async fn main() -> StdExitCode {
 }
 
 async fn run(cli: Cli) -> azure_aitoolsconnect::Result<ExitCode> {
+    azure_aitoolsconnect::redact::set_show_secrets(cli.show_secrets);
+    azure_aitoolsconnect::network::system::set_use_system_proxy(!cli.no_system_proxy);
+    azure_aitoolsconnect::network::system::set_use_system_certs(!cli.no_system_certs);
+    azure_aitoolsconnect::i18n::set_lang(
+        cli.lang
+            .map(Into::into)
+            .unwrap_or_else(azure_aitoolsconnect::i18n::Lang::detect),
+    );
+    let log_level = match cli.log_level {
+        Some(level) => level.into(),
+        None if cli.verbose => tracing::level_filters::LevelFilter::DEBUG,
+        // The daemon's run summaries are its output
+        None if matches!(cli.command, Commands::Serve(_)) => {
+            tracing::level_filters::LevelFilter::INFO
+        }
+        None => tracing::level_filters::LevelFilter::WARN,
+    };
+    azure_aitoolsconnect::logging::init(log_level, cli.log_format.into(), cli.log_file.as_deref())?;
+
+    // The schema doesn't depend on any config file
+    if let Commands::Validate(args) = &cli.command {
+        if args.schema {
+            println!("{}", azure_aitoolsconnect::config::schema::config_schema());
+            return Ok(ExitCode::Success);
+        }
+    }
+
+    let config_path = match &cli.config {
+        Some(path) => Some(path.clone()),
+        None if cli.no_config => None,
+        None => Config::discover(),
+    };
+    if cli.verbose && !cli.quiet {
+        match &config_path {
+            Some(path) => eprintln!(
+                "{} Using configuration file: {}",
+                style("[*]").cyan(),
+                path.display()
+            ),
+            None => eprintln!(
+                "{} No configuration file; using defaults",
+                style("[*]").cyan()
+            ),
+        }
+    }
+
+    // Editing commands work on the file as written, not the loaded config
+    if let Commands::Config(args) = &cli.command {
+        let path = config_path
+            .clone()
+            .unwrap_or_else(|| std::path::PathBuf::from("./config.toml"));
+        match &args.command {
+            azure_aitoolsconnect::cli::ConfigCommand::Get(args) => {
+                return run_config_get(&path, &args.key);
+            }
+            azure_aitoolsconnect::cli::ConfigCommand::Set(args) => {
+                return run_config_set(&path, &args.key, Some(&args.value), cli.quiet);
+            }
+            azure_aitoolsconnect::cli::ConfigCommand::Unset(args) => {
+                return run_config_set(&path, &args.key, None, cli.quiet);
+            }
+            azure_aitoolsconnect::cli::ConfigCommand::ListProfiles(_) => {}
+        }
+    }
+
     // Load configuration
-    let mut config = if let Some(config_path) = &cli.config {
-        Config::from_file(config_path)?
-    } else {
-        Config::default_config()
+    let mut config = match (&config_path, &cli.config_profile) {
+        (Some(config_path), Some(profile)) => Config::from_file_with_profile(config_path, profile)?,
+        (Some(config_path), None) => Config::from_file(config_path)?,
+        (None, Some(_)) => {
+            return Err(azure_aitoolsconnect::AppError::Config(
+                "--config-profile requires a configuration file (--config, ./config.toml, or the per-user config file)".to_string(),
+            ))
+        }
+        (None, None) => Config::default_config(),
     };
 
     // Apply environment variable overrides
     config.apply_env_overrides();
+    azure_aitoolsconnect::redact::register_config_secrets(&config);
+    azure_aitoolsconnect::services::custom::register_config_services(&config);
 
     match cli.command {
-        Commands::Test(args) => run_test(args, &config, cli.verbose, cli.quiet).await,
-        Commands::Login(args) => run_login(args, cli.quiet).await,
-        Commands::Diagnose(args) => run_diagnose(args, cli.verbose, cli.quiet).await,
+        Commands::Test(args) => {
+            // Apply service-specific CLI overrides onto the config
+            if args.expect_voice.is_some()
+                || args.custom_voice_deployment.is_some()
+                || args.custom_voice.is_some()
+            {
+                let speech = config.services.entry("speech".to_string()).or_default();
+                if let Some(voice) = &args.expect_voice {
+                    speech.expect_voice = Some(voice.clone());
+                }
+                if let Some(deployment) = &args.custom_voice_deployment {
+                    speech.custom_voice_deployment = Some(deployment.clone());
+                }
+                if let Some(voice) = &args.custom_voice {
+                    speech.custom_voice = Some(voice.clone());
+                }
+            }
+            if let Some(category) = &args.translator_category {
+                config
+                    .services
+                    .entry("translator".to_string())
+                    .or_default()
+                    .category = Some(category.clone());
+            }
+            if let Some(model) = &args.di_model {
+                config
+                    .services
+                    .entry("document_intelligence".to_string())
+                    .or_default()
+                    .model_id = Some(model.clone());
+            }
+            for (service, version) in &args.api_versions {
+                if !azure_aitoolsconnect::services::api_versions::is_pinnable(service) {
+                    return Err(azure_aitoolsconnect::AppError::InvalidInput(format!(
+                        "--api-version: service '{}' has no versioned API",
+                        service
+                    )));
+                }
+                config
+                    .services
+                    .entry(service.clone())
+                    .or_default()
+                    .api_version = Some(version.clone());
+            }
+            if args.foundry_project.is_some() || args.foundry_deployment.is_some() {
+                let foundry = config.services.entry("ai_foundry".to_string()).or_default();
+                if let Some(project) = &args.foundry_project {
+                    foundry.project_name = Some(project.clone());
+                }
+                if let Some(deployment) = &args.foundry_deployment {
+                    foundry.deployment_name = Some(deployment.clone());
+                }
+            }
+            if let Some(deployment) = &args.inference_deployment {
+                config
+                    .services
+                    .entry("model_inference".to_string())
+                    .or_default()
+                    .deployment_name = Some(deployment.clone());
+            }
+            if args.openai_deployment.is_some() || args.realtime_deployment.is_some() {
+                let openai = config.services.entry("openai".to_string()).or_default();
+                if let Some(deployment) = &args.openai_deployment {
+                    openai.deployment_name = Some(deployment.clone());
+                }
+                if let Some(deployment) = &args.realtime_deployment {
+                    openai.realtime_deployment = Some(deployment.clone());
+                }
+            }
+            run_test(
+                *args,
+                &config,
+                config_path.as_deref(),
+                cli.verbose,
+                cli.quiet,
+            )
+            .await
+        }
+        Commands::Login(args) => run_login(args, cli.verbose, cli.quiet).await,
+        Commands::Diagnose(args) => run_diagnose(args, &config, cli.verbose, cli.quiet).await,
+        Commands::Doctor(args) => run_doctor(args, &config, cli.verbose, cli.quiet).await,
+        Commands::Selftest(args) => run_selftest(args, cli.quiet),
         Commands::Init(args) => run_init(args),
         Commands::Validate(args) => run_validate(args),
         Commands::ListScenarios(args) => run_list_scenarios(args),
+        Commands::Capabilities(args) => run_capabilities(args),
+        Commands::ListRegions(args) => run_list_regions(args),
+        Commands::Endpoint(args) => run_endpoint(args, cli.quiet),
+        Commands::Discover(args) => run_discover(args, &config, cli.quiet).await,
+        Commands::EgressIp(args) => run_egress_ip(args, &config, cli.quiet).await,
+        Commands::Usage(args) => run_usage(args, &config, cli.quiet).await,
+        Commands::SupportBundle(args) => run_support_bundle(args, &config, cli.quiet).await,
+        Commands::Token(args) => match args.command {
+            azure_aitoolsconnect::cli::TokenCommand::Inspect(args) => {
+                run_token_inspect(args, cli.quiet)
+            }
+        },
+        Commands::Env(args) => run_env(args, &config, cli.quiet),
+        Commands::Config(args) => match args.command {
+            azure_aitoolsconnect::cli::ConfigCommand::ListProfiles(args) => {
+                run_list_profiles(args, &config, cli.config_profile.as_deref())
+            }
+            _ => unreachable!("config get/set/unset run before the config is loaded"),
+        },
+        Commands::Serve(args) => run_serve(args, &config, cli.quiet).await,
+        Commands::Aggregate(args) => run_aggregate(args, cli.quiet),
     }
 }
 
 async fn run_test(
     args: azure_aitoolsconnect::cli::TestArgs,
     config: &Config,
+    config_path: Option<&std::path::Path>,
     verbose: bool,
     quiet: bool,
 ) -> azure_aitoolsconnect::Result<ExitCode> {
-    let services = parse_services(&args.services);
+    if let Some(path) = &args.replay {
+        return replay_session(path, &args, config, verbose, quiet);
+    }
 
-    let runner_config = TestRunnerConfig::from_config(
-        config,
-        services,
-        args.api_key,
-        args.region,
-        Some(args.cloud.into()),
-        Some(args.auth.into()),
-        Some(args.timeout),
-        args.endpoint,
-        args.input_file.map(|p| p.to_string_lossy().to_string()),
-        args.scenarios,
-        args.tenant,
-        args.bearer_token,
-        verbose,
-        quiet,
-        args.show_token,
-        args.no_cache,
-    );
+    let mut services = parse_services(&args.services);
+    let resource_id = args.resource_id.clone();
 
-    let runner = TestRunner::new(runner_config);
-    let report = runner.run().await?;
+    // Custom probes run with "all" when any are configured, or when named explicitly
+    let custom_requested = services.iter().any(|s| s == CUSTOM_SERVICE_NAME);
+    if custom_requested && config.custom_scenarios.is_empty() {
+        return Err(azure_aitoolsconnect::AppError::Config(
+            "Service 'custom' requested but no [[custom_scenarios]] are configured".to_string(),
+        ));
+    }
+    let all_requested = args.services.len() == 1 && args.services[0].eq_ignore_ascii_case("all");
+    if all_requested && !config.custom_scenarios.is_empty() {
+        services.push(CUSTOM_SERVICE_NAME.to_string());
+    }
+    if all_requested {
+        services.extend(
+            config
+                .custom_services
+                .keys()
+                .map(|name| name.to_lowercase().replace('-', "_")),
+        );
+    }
+    let arm_token = args.arm_token.clone();
 
-    // Format output
+    if let Some(rps) = args.max_rps {
+        if rps <= 0.0 || !rps.is_finite() {
+            return Err(azure_aitoolsconnect::AppError::InvalidInput(format!(
+                "--max-rps must be a positive number, got {}",
+                rps
+            )));
+        }
+    }
+
+    let mut builder = TestRunnerConfig::builder()
+        .config(config)
+        .services(services)
+        .cloud(args.cloud.into())
+        .auth_method(args.auth.into())
+        .timeout(std::time::Duration::from_secs(args.timeout))
+        .verbose(verbose)
+        .quiet(quiet)
+        .no_cache(args.no_cache);
+    if let Some(secs) = args.connect_timeout {
+        builder = builder.connect_timeout(std::time::Duration::from_secs(secs));
+    }
+    if let Some(secs) = args.tls_timeout {
+        builder = builder.tls_timeout(std::time::Duration::from_secs(secs));
+    }
+    if let Some(api_key) = api_key_arg(args.api_key, args.keyvault_secret) {
+        builder = builder.api_key(api_key);
+    }
+    if let Some(region) = args.region {
+        builder = builder.region(region);
+    }
+    if let Some(endpoint) = args.endpoint {
+        builder = builder.endpoint(endpoint);
+    }
+    builder = builder
+        .container(args.container)
+        .allow_insecure_http(args.allow_insecure_http)
+        .realistic_payloads(args.realistic_payloads)
+        .verify_content(args.verify_content)
+        .preview_apis(args.preview_apis)
+        .assert_blocked(args.assert_blocked);
+    if let Some(duration) = args.long_poll {
+        builder = builder.long_poll(azure_aitoolsconnect::services::long_poll::LongPoll {
+            duration,
+            interval: std::time::Duration::from_secs(args.poll_interval.max(1)),
+        });
+    }
+    if let Some(input_file) = args.input_file {
+        builder = builder.input_file(input_file.to_string_lossy());
+    }
+    if let Some(audio_file) = args.audio_file {
+        builder = builder.audio_file(audio_file.to_string_lossy());
+    }
+    if let Some(image_file) = args.image_file {
+        builder = builder.image_file(image_file.to_string_lossy());
+    }
+    if let Some(document_file) = args.document_file {
+        builder = builder.document_file(document_file.to_string_lossy());
+    }
+    if let Some(text) = args.text {
+        builder = builder.text(text);
+    }
+    if let Some(scenarios) = args.scenarios {
+        builder = builder.scenarios(scenarios);
+    }
+    if !args.skip_scenarios.is_empty() {
+        builder = builder.skip_scenarios(args.skip_scenarios);
+    }
+    if let Some(profile) = args.profile {
+        builder = builder.profile(profile);
+    }
+    if let Some(tenant) = args.tenant {
+        builder = builder.tenant(tenant);
+    }
+    if let Some(bearer_token) = args.bearer_token {
+        builder = builder.bearer_token(bearer_token);
+    }
+    if let Some(scope) = args.scope {
+        builder = builder.scope(scope);
+    }
+    if let Some(sku) = args.sku {
+        builder = builder.sku(sku);
+    }
+    let mut runner_config = builder.build();
+
+    if args.max_rps.is_some() {
+        runner_config.max_rps = args.max_rps;
+    }
+    runner_config.headers.extend(args.headers);
+    if args.api_key_header.is_some() {
+        runner_config.api_key_header = args.api_key_header;
+    }
+    runner_config.retry_throttled |= args.retry_throttled;
+    runner_config.fail_on_throttle |= args.fail_on_throttle;
+    if args.max_latency_ms.is_some() {
+        runner_config.max_latency_ms = args.max_latency_ms;
+    }
+    runner_config.fail_on_slow |= args.fail_on_slow;
+    runner_config.capture_dir = args.capture.clone();
+    runner_config.artifacts_dir = args.save_artifacts.clone();
+
+    if !args.targets.is_empty() {
+        runner_config.select_targets(args.targets)?;
+    }
+    if args.dry_run || args.plan {
+        return print_plan(runner_config, &args.regions, args.plan, args.output, quiet);
+    }
+
+    // Nothing leaves the machine under --mock, so no real credentials are needed
+    let mock = if args.mock {
+        let server = MockServer::start().await?;
+        runner_config.mock = Some(server.addr());
+        if runner_config.auth_method != AuthMethod::Token {
+            runner_config.auth_method = AuthMethod::Key;
+            runner_config
+                .api_key
+                .get_or_insert_with(|| MOCK_API_KEY.to_string());
+        }
+        runner_config
+            .endpoint
+            .get_or_insert_with(|| MOCK_ENDPOINT.to_string());
+        if !quiet {
+            eprintln!(
+                "{} Mock mode: requests go to an embedded server at {}, not Azure",
+                style("[*]").cyan(),
+                server.addr()
+            );
+        }
+        Some(server)
+    } else {
+        runner_config.resolve_secrets().await?;
+        None
+    };
+    runner_config.check_endpoints()?;
+    if let (None, Some(resource_id), false) = (&runner_config.sku, &resource_id, args.mock) {
+        runner_config.sku = resource_sku(
+            resource_id,
+            arm_token.clone(),
+            runner_config.cloud,
+            runner_config.timeout,
+        )
+        .await;
+    }
+    // Containers take requests without a key; theirs is set when they start
+    let container = runner_config.container
+        || runner_config
+            .services
+            .iter()
+            .filter_map(|s| runner_config.service_configs.get(s))
+            .any(|c| c.container);
+    if container && runner_config.auth_method == AuthMethod::Key {
+        runner_config.api_key.get_or_insert_with(String::new);
+    }
+    for region in &args.regions {
+        if !runner_config.regions.contains(region) {
+            runner_config.regions.push(region.clone());
+        }
+    }
+    if runner_config.regions.is_empty() {
+        check_region(&runner_config.region, runner_config.cloud, quiet)?;
+    } else {
+        for region in &runner_config.regions {
+            check_region(region, runner_config.cloud, quiet)?;
+        }
+    }
+
+    // Keep auth prompts plain under the live view
+    if args.tui {
+        runner_config.quiet = true;
+    }
+
+    let cloud = runner_config.cloud;
+    let region = runner_config.region.clone();
+    let timeout = runner_config.timeout;
+    let title = if runner_config.regions.is_empty() {
+        format!("{} ({})", runner_config.region, cloud)
+    } else {
+        format!("{} ({})", runner_config.regions.join(", "), cloud)
+    };
     let output_format = args.output.into();
-    let use_colors = std::io::stdout().is_terminal() && !quiet;
-    let formatter = get_formatter(output_format, use_colors);
-    let output = formatter.format(&report);
+    let progress_total = runner_config.services.len() * runner_config.regions.len().max(1);
+    // A session file needs the exchanges in memory, written to --capture as well when given
+    let recording = match (&args.record, &args.capture) {
+        (None, _) => None,
+        (Some(_), Some(dir)) => Some(CaptureLog::create(dir)?.keeping_exchanges()),
+        (Some(_), None) => Some(CaptureLog::in_memory()),
+    }
+    .map(|log| {
+        Arc::new(log.with_sensitive_headers(runner_config.api_key_header.iter().cloned().collect()))
+    });
+    // SIGINT/SIGTERM stop the run gracefully; the listener ends with the command
+    let interrupt = azure_aitoolsconnect::Interrupt::new();
+    let _signals = AbortOnDrop(tokio::spawn(interrupt_on_signal(interrupt.clone())));
+    let mut runner = TestRunner::new(runner_config).with_interrupt(interrupt);
+    if let Some(recording) = &recording {
+        runner = runner.with_capture(recording.clone());
+    }
+
+    // The live view replaces the progress bar
+    if !args.tui && !quiet {
+        runner = runner.with_reporter(Arc::new(ProgressBarReporter::new(progress_total as u64)));
+    }
+    // NDJSON streams each scenario as it finishes; the summary line follows the run
+    let mut ndjson_stream = None;
+    if !args.tui && output_format == azure_aitoolsconnect::OutputFormat::Ndjson {
+        let (event_tx, events) = tokio::sync::mpsc::unbounded_channel();
+        let writer = open_output(args.output_file.as_deref())?;
+        runner = runner.with_events(event_tx);
+        ndjson_stream = Some(spawn_ndjson_stream(events, writer));
+    }
+
+    // Authenticate first so device-code prompts aren't hidden by the live view
+    let credentials = runner.get_credentials().await?;
+    // The identity whose role assignments --resource-id checks
+    let principal = match &credentials {
+        azure_aitoolsconnect::Credentials::BearerToken(token) => {
+            azure_aitoolsconnect::auth::TokenClaims::decode(token).ok()
+        }
+        _ => None,
+    };
+    if args.show_token {
+        if let azure_aitoolsconnect::Credentials::BearerToken(token) = &credentials {
+            eprintln!("{}", style("Bearer Token:").bold());
+            eprintln!("{}", token);
+            eprintln!();
+            if args.decode {
+                match azure_aitoolsconnect::auth::TokenInspection::new(token, cloud) {
+                    Ok(inspection) => eprint!(
+                        "{}",
+                        azure_aitoolsconnect::auth::format_token_inspection(
+                            &inspection,
+                            std::io::stderr().is_terminal()
+                        )
+                    ),
+                    Err(e) => eprintln!("{} {}", style("[!]").yellow(), e),
+                }
+                eprintln!();
+            }
+        }
+    }
+    let mut report = if args.tui {
+        run_tui(runner, credentials, title).await?
+    } else {
+        let report = runner.run_with_credentials(credentials).await;
+        // Dropping the runner closes the NDJSON channel so the stream finishes
+        drop(runner);
+        report?
+    };
+    report = report.with_run_context(region, cloud);
+    let metadata = report
+        .metadata
+        .take()
+        .unwrap_or_else(|| azure_aitoolsconnect::RunMetadata::new(Vec::new()));
+    let command_line: Vec<String> = std::env::args().collect();
+    report = report.with_metadata(metadata.with_invocation(&command_line, config_path));
+    let upload_settings = config.upload_settings();
+    let mut uploads = upload::parse_targets(&upload_settings.targets)?;
+    uploads.extend(args.upload.iter().cloned());
+    if !args.report_to.is_empty() {
+        eprintln!(
+            "{} --report-to is deprecated; use --upload",
+            style("[!]").yellow()
+        );
+        uploads.extend(args.report_to.iter().cloned());
+    }
+    let location = args
+        .location
+        .clone()
+        .or(upload_settings.location)
+        .or_else(|| (!uploads.is_empty()).then(upload::default_location));
+    if let Some(location) = location {
+        report = report.with_location(location);
+    }
+
+    drop(mock);
+
+    if args.environment && !args.mock {
+        let environment = capture_environment(config.global.ip_echo_url(), cloud, timeout).await;
+        report = report.with_environment(environment);
+    } else if !args.no_environment && !args.mock {
+        report = report.with_environment(capture_local_environment(cloud, timeout).await);
+    }
+
+    if let Some(resource_id) = resource_id {
+        let client_ip = match report.environment.as_ref().and_then(|e| e.egress_ip) {
+            Some(ip) => Some(ip),
+            None => detect_public_ip(config.global.ip_echo_url(), timeout).await,
+        };
+        if !quiet {
+            eprintln!(
+                "{} Inspecting resource network configuration via ARM...",
+                style("[*]").cyan()
+            );
+        }
+        let (findings, usage) = inspect_resource_network(
+            &resource_id,
+            arm_token,
+            cloud,
+            timeout,
+            client_ip,
+            principal.as_ref(),
+            args.usage,
+            &report,
+        )
+        .await;
+        report = report.with_findings(findings);
+        if let Some(usage) = usage {
+            report = report.with_usage(usage);
+        }
+    }
 
-    // Write output
+    if let Some(dir) = &args.save_artifacts {
+        if !quiet {
+            eprintln!(
+                "{} Saved scenario output in {}",
+                style("[*]").cyan(),
+                dir.display()
+            );
+        }
+    }
+
+    if let Some(dir) = &args.capture {
+        write_capture_reports(dir, &report)?;
+        if !quiet {
+            eprintln!(
+                "{} Captured requests in {} (bundle with 'support-bundle --capture {}')",
+                style("[*]").cyan(),
+                dir.display(),
+                dir.display()
+            );
+        }
+    }
+
+    if let (Some(path), Some(recording)) = (&args.record, &recording) {
+        Session::new(report.clone(), recording.exchanges()).save(path)?;
+        if !quiet {
+            eprintln!(
+                "{} Recorded session in {} (replay with 'test --replay {}')",
+                style("[*]").cyan(),
+                path.display(),
+                path.display()
+            );
+        }
+    }
+
+    if let Some(stream) = ndjson_stream {
+        let mut writer = stream
+            .await
+            .map_err(|e| azure_aitoolsconnect::AppError::InvalidInput(e.to_string()))??;
+        let summary = NdjsonFormatter::summary_line(&report);
+        writer.write_all(azure_aitoolsconnect::redact::redact(&summary).as_bytes())?;
+    } else {
+        // Format output
+        let use_colors = std::io::stdout().is_terminal() && !quiet;
+        let formatter = get_formatter(output_format, use_colors);
+        let output = formatter.format(&report);
+
+        // Write output
+        write_output(&output, args.output_file.as_deref())?;
+    }
+
+    let fail_on = args
+        .fail_on
+        .map(Into::into)
+        .unwrap_or(config.global.fail_on);
+    let failed = !report.passes(fail_on);
+    let targets: Vec<_> = args.notify_url.iter().chain(&args.notify).collect();
+    let email = match (&args.smtp_server, args.email_to.is_empty()) {
+        (Some(server), false) => Some(EmailSettings::new(
+            server.clone(),
+            args.email_from.clone(),
+            args.email_to.clone(),
+            args.email_format.into(),
+        )),
+        _ => None,
+    };
+    if !targets.is_empty() || email.is_some() {
+        notify_run(
+            &targets,
+            email.as_ref(),
+            args.notify_on.into(),
+            &report,
+            failed,
+            timeout,
+            quiet,
+        )
+        .await;
+    }
+    if !uploads.is_empty() {
+        upload_report(config, &uploads, &report, timeout, quiet).await;
+    }
+
+    if report.interrupted {
+        Ok(ExitCode::Interrupted)
+    } else if !failed {
+        Ok(ExitCode::Success)
+    } else {
+        Ok(ExitCode::TestFailure)
+    }
+}
+
+/// Trigger `interrupt` on each SIGINT/SIGTERM: the first stops the run after
+/// the running scenario, the second stops it now
+async fn interrupt_on_signal(interrupt: azure_aitoolsconnect::Interrupt) {
+    loop {
+        let signal = shutdown_signal().await;
+        tracing::info!(signal, "stopping the run");
+        interrupt.trigger();
+    }
+}
+
+/// Wait for SIGINT (Ctrl-C) or, on Unix, SIGTERM, and name it
+async fn shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            return tokio::select! {
+                _ = ctrl_c() => "SIGINT",
+                _ = terminate.recv() => "SIGTERM",
+            };
+        }
+    }
+    ctrl_c().await;
+    "SIGINT"
+}
+
+/// Wait for Ctrl-C; never returns if the handler can't be installed
+async fn ctrl_c() {
+    if tokio::signal::ctrl_c().await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Send the report to each upload target; like notifications, a failed
+/// upload doesn't fail the run
+async fn upload_report(
+    config: &Config,
+    targets: &[upload::UploadTarget],
+    report: &azure_aitoolsconnect::TestReport,
+    timeout: std::time::Duration,
+    quiet: bool,
+) {
+    let uploader = match upload::Uploader::new(config, targets, timeout, quiet).await {
+        Ok(uploader) => uploader,
+        Err(e) => {
+            eprintln!(
+                "{} Uploading the report failed: {}",
+                style("[!]").yellow(),
+                e
+            );
+            return;
+        }
+    };
+    for target in targets {
+        match uploader.upload(target, report).await {
+            Ok(()) if !quiet => {
+                eprintln!(
+                    "{} Sent report to {}",
+                    style("[*]").cyan(),
+                    target.describe()
+                );
+            }
+            Ok(()) => {}
+            Err(e) => eprintln!(
+                "{} Sending report to {} failed: {}",
+                style("[!]").yellow(),
+                target.describe(),
+                e
+            ),
+        }
+    }
+}
+
+/// Render a session recorded with `--record` (`test --replay`). Nothing is
+/// sent; the exit code follows `--fail-on` as if the run had just happened.
+fn replay_session(
+    path: &std::path::Path,
+    args: &azure_aitoolsconnect::cli::TestArgs,
+    config: &Config,
+    verbose: bool,
+    quiet: bool,
+) -> azure_aitoolsconnect::Result<ExitCode> {
+    let session = Session::load(path)?;
+    if !quiet {
+        eprintln!(
+            "{} Replaying {}: recorded {} by version {}, {} requests",
+            style("[*]").cyan(),
+            path.display(),
+            session.recorded_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            session.tool_version,
+            session.exchanges.len()
+        );
+        if verbose && !session.exchanges.is_empty() {
+            eprintln!();
+            eprint!("{}", session.exchange_log());
+        }
+        eprintln!();
+    }
+
+    let report = session.report;
+    let use_colors = std::io::stdout().is_terminal() && !quiet;
+    let output = get_formatter(args.output.clone().into(), use_colors).format(&report);
     write_output(&output, args.output_file.as_deref())?;
 
-    if report.all_passed() {
+    let fail_on = args
+        .fail_on
+        .map(Into::into)
+        .unwrap_or(config.global.fail_on);
+    if report.passes(fail_on) {
         Ok(ExitCode::Success)
     } else {
         Ok(ExitCode::TestFailure)
     }
 }
 
+/// Progress bar for `test`. The bar appears once the first service starts,
+/// so it never overlaps auth prompts.
+struct ProgressBarReporter {
+    total: u64,
+    bar: OnceLock<ProgressBar>,
+}
+
+impl ProgressBarReporter {
+    fn new(total: u64) -> Self {
+        Self {
+            total,
+            bar: OnceLock::new(),
+        }
+    }
+
+    fn bar(&self) -> &ProgressBar {
+        self.bar.get_or_init(|| {
+            let bar = ProgressBar::new(self.total);
+            bar.set_style(
+                ProgressStyle::default_bar()
+                    .template(
+                        "{spinner:.green} [{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} {msg}",
+                    )
+                    .unwrap()
+                    .progress_chars("##-"),
+            );
+            bar
+        })
+    }
+}
+
+impl ProgressReporter for ProgressBarReporter {
+    fn on_service_start(&self, service: &str, _endpoint: &str, _scenarios: &[(String, String)]) {
+        self.bar().set_message(format!("Testing {}", service));
+    }
+
+    fn on_service_complete(&self, _service: &str) {
+        self.bar().inc(1);
+    }
+
+    fn on_notice(&self, message: &str) {
+        let line = format!("  {} {}", style("[*]").cyan(), message);
+        match self.bar.get() {
+            Some(bar) => bar.println(line),
+            None => eprintln!("{}", line),
+        }
+    }
+
+    fn on_run_complete(&self, _report: &azure_aitoolsconnect::TestReport) {
+        if let Some(bar) = self.bar.get() {
+            bar.finish_with_message("Complete");
+        }
+    }
+}
+
+/// `--keyvault-secret` becomes a `keyvault:` reference in place of `--api-key`
+fn api_key_arg(api_key: Option<String>, keyvault_secret: Option<String>) -> Option<String> {
+    match keyvault_secret {
+        Some(secret) if azure_aitoolsconnect::keyvault::is_reference(&secret) => Some(secret),
+        Some(secret) => Some(format!(
+            "{}{}",
+            azure_aitoolsconnect::keyvault::SECRET_PREFIX,
+            secret
+        )),
+        None => api_key,
+    }
+}
+
+/// Save the report and environment next to a request capture
+fn write_capture_reports(
+    dir: &std::path::Path,
+    report: &azure_aitoolsconnect::TestReport,
+) -> azure_aitoolsconnect::Result<()> {
+    use azure_aitoolsconnect::capture::{ENVIRONMENT_FILE, REPORT_FILE};
+
+    let json = get_formatter(azure_aitoolsconnect::OutputFormat::Json, false).format(report);
+    std::fs::write(dir.join(REPORT_FILE), json)?;
+    if let Some(environment) = &report.environment {
+        let json = serde_json::to_string_pretty(environment)
+            .map_err(azure_aitoolsconnect::AppError::Json)?;
+        std::fs::write(dir.join(ENVIRONMENT_FILE), json)?;
+    }
+    Ok(())
+}
+
 async fn run_login(
     args: azure_aitoolsconnect::cli::LoginArgs,
+    verbose: bool,
     quiet: bool,
 ) -> azure_aitoolsconnect::Result<ExitCode> {
     use azure_aitoolsconnect::auth::token_cache::{CachedTokenEntry, TokenCacheFile};
@@ -118,6 +910,10 @@ async fn run_login(
     }
 
     let cloud: Cloud = args.cloud.into();
+    let scope = match &args.scope {
+        Some(scope) => azure_aitoolsconnect::auth::scope_for(scope),
+        None => cloud.cognitive_scope().to_string(),
+    };
 
     match args.auth {
         azure_aitoolsconnect::cli::LoginAuthMethodArg::Interactive => {
@@ -126,9 +922,8 @@ async fn run_login(
                 .ok_or(azure_aitoolsconnect::AppError::MissingTenantId)?;
 
             // Check disk cache first
-            let scope = cloud.cognitive_scope();
             if let Ok(cache) = TokenCacheFile::load() {
-                if let Some(entry) = cache.get_valid_token(scope, &tenant_id) {
+                if let Some(entry) = cache.get_valid_token(&scope, &tenant_id) {
                     if !quiet {
                         eprintln!(
                             "  {} Using cached token ({} minutes remaining)",
@@ -151,6 +946,7 @@ async fn run_login(
                 args.client_id.clone(),
                 &cloud,
             )?
+            .with_scope(&scope)
             .with_quiet(quiet);
 
             let result = auth.authenticate().await?;
@@ -184,9 +980,8 @@ async fn run_login(
                 .ok_or(azure_aitoolsconnect::AppError::MissingTenantId)?;
 
             // Check disk cache first
-            let scope = cloud.cognitive_scope();
             if let Ok(cache) = TokenCacheFile::load() {
-                if let Some(entry) = cache.get_valid_token(scope, &tenant_id) {
+                if let Some(entry) = cache.get_valid_token(&scope, &tenant_id) {
                     if !quiet {
                         eprintln!(
                             "  {} Using cached token ({} minutes remaining)",
@@ -209,6 +1004,7 @@ async fn run_login(
                 args.client_id,
                 &cloud,
             )?
+            .with_scope(&scope)
             .with_quiet(quiet);
 
             let result = auth.authenticate().await?;
@@ -237,11 +1033,30 @@ async fn run_login(
             );
         }
         azure_aitoolsconnect::cli::LoginAuthMethodArg::ManagedIdentity => {
-            let mi = azure_aitoolsconnect::auth::ManagedIdentityAuth::new(&cloud, None)?;
-            use azure_aitoolsconnect::auth::AuthProvider;
-            let creds = mi.get_credentials().await?;
-            if let azure_aitoolsconnect::auth::Credentials::BearerToken(token) = creds {
-                output_token(&token, 60, &args.output);
+            // --client-id picks a user-assigned identity
+            let client_id = args
+                .client_id
+                .or_else(|| std::env::var("AZURE_MI_CLIENT_ID").ok());
+            let mi = azure_aitoolsconnect::auth::ManagedIdentityAuth::new(&cloud, client_id)?
+                .with_resource(azure_aitoolsconnect::auth::audience_of(&scope));
+            if verbose {
+                let (diagnostics, token) = mi.diagnose().await;
+                eprint!(
+                    "{}",
+                    azure_aitoolsconnect::auth::format_managed_identity_diagnostics(
+                        &diagnostics,
+                        std::io::stderr().is_terminal()
+                    )
+                );
+                eprintln!();
+                let minutes = diagnostics.expires_in_minutes.unwrap_or(60).max(0) as u64;
+                output_token(&token?, minutes, &args.output);
+            } else {
+                use azure_aitoolsconnect::auth::AuthProvider;
+                let creds = mi.get_credentials().await?;
+                if let azure_aitoolsconnect::auth::Credentials::BearerToken(token) = creds {
+                    output_token(&token, 60, &args.output);
+                }
             }
         }
     }
@@ -249,7 +1064,88 @@ async fn run_login(
     Ok(ExitCode::Success)
 }
 
+/// Send completion notifications; a failed delivery is a warning, not a run failure
+async fn notify_run(
+    targets: &[&NotifyTarget],
+    email: Option<&EmailSettings>,
+    on: NotifyOn,
+    report: &azure_aitoolsconnect::output::TestReport,
+    failed: bool,
+    timeout: std::time::Duration,
+    quiet: bool,
+) {
+    let mut state = RunState::load();
+    let regressions = state.regressions(report);
+    state.update(report);
+    if let Err(e) = state.save() {
+        tracing::warn!(error = %e, "could not save run state for regression checks");
+    }
+    if !should_notify(on, failed, &regressions) {
+        return;
+    }
+
+    for target in targets {
+        match azure_aitoolsconnect::notify::send(target, report, &regressions, timeout).await {
+            Ok(()) if !quiet => {
+                eprintln!("{} Notified {}", style("[*]").cyan(), target.describe());
+            }
+            Ok(()) => {}
+            Err(e) => eprintln!(
+                "{} Notification to {} failed: {}",
+                style("[!]").yellow(),
+                target.describe(),
+                e
+            ),
+        }
+    }
+    if let Some(email) = email {
+        match azure_aitoolsconnect::notify::email::send(email, report, &regressions, timeout).await
+        {
+            Ok(()) if !quiet => {
+                eprintln!("{} Sent {}", style("[*]").cyan(), email.describe());
+            }
+            Ok(()) => {}
+            Err(e) => eprintln!("{} {}", style("[!]").yellow(), e),
+        }
+    }
+}
+
 /// Output the token in the requested format
+fn run_token_inspect(
+    args: azure_aitoolsconnect::cli::TokenInspectArgs,
+    quiet: bool,
+) -> azure_aitoolsconnect::Result<ExitCode> {
+    use azure_aitoolsconnect::auth::{format_token_inspection, TokenInspection};
+
+    let token = if args.token == "-" {
+        let mut token = String::new();
+        std::io::stdin().read_line(&mut token)?;
+        token
+    } else {
+        args.token
+    };
+    let token = token.trim().trim_start_matches("Bearer ").trim();
+    let inspection = TokenInspection::new(token, args.cloud.into())?;
+
+    match args.output {
+        azure_aitoolsconnect::cli::OutputFormatArg::Json => {
+            let json = serde_json::to_string_pretty(&inspection)
+                .map_err(azure_aitoolsconnect::AppError::Json)?;
+            println!("{}", json);
+        }
+        _ => {
+            let use_colors = std::io::stdout().is_terminal() && !quiet;
+            print!("{}", format_token_inspection(&inspection, use_colors));
+        }
+    }
+
+    if inspection.is_usable() {
+        Ok(ExitCode::Success)
+    } else {
+        Ok(ExitCode::AuthFailure)
+    }
+}
+
 fn output_token(
     token: &str,
     expires_in_minutes: u64,
@@ -278,37 +1174,163 @@ fn output_token(
 
 async fn run_diagnose(
     args: azure_aitoolsconnect::cli::DiagnoseArgs,
+    config: &Config,
     _verbose: bool,
     quiet: bool,
 ) -> azure_aitoolsconnect::Result<ExitCode> {
-    let region = args.region.unwrap_or_else(|| "eastus".to_string());
+    let regions = if args.region.is_empty() {
+        vec!["eastus".to_string()]
+    } else {
+        args.region.clone()
+    };
     let cloud = args.cloud.into();
+    for region in &regions {
+        check_region(region, cloud, quiet)?;
+    }
+
+    let mut endpoints = Vec::new();
+    for endpoint in &args.endpoint {
+        endpoints
+            .push(endpoint_host(endpoint).map_err(azure_aitoolsconnect::AppError::InvalidInput)?);
+    }
+    if let Some(path) = &args.endpoints_file {
+        let content = read_config_file(path)?;
+        endpoints.extend(parse_endpoints_file(&content).map_err(|e| {
+            azure_aitoolsconnect::AppError::InvalidInput(format!("{}: {}", path.display(), e))
+        })?);
+    }
+    let service_tags = match &args.service_tags {
+        Some(path) => Some(
+            ServiceTags::from_json(&read_config_file(path)?).map_err(|e| {
+                azure_aitoolsconnect::AppError::InvalidInput(format!("{}: {}", path.display(), e))
+            })?,
+        ),
+        None => None,
+    };
+    let groups = diagnostic_groups(&regions, cloud, &endpoints);
+    let hosts = unique_hosts(&groups);
+
+    // The DoH comparison is opt-in: it sends the hostnames to a third-party resolver
+    let doh_url = match &args.doh_url {
+        Some(url) => Some(url.as_str()),
+        None if args.doh => Some(config.global.doh_url()),
+        None => None,
+    };
 
     // If no specific checks are requested, run all
-    let (check_dns, check_tls, check_latency) = if !args.dns && !args.tls && !args.latency {
-        (true, true, true)
+    let run_all = !args.dns
+        && !args.tls
+        && !args.latency
+        && !args.revocation
+        && doh_url.is_none()
+        && !args.enrich
+        && args.service_tags.is_none()
+        && !args.icmp
+        && args.ports.is_empty()
+        && args.concurrency.is_none();
+    let (check_dns, check_tls, check_latency, check_revocation) = if run_all {
+        (true, true, true, true)
     } else {
-        (args.dns, args.tls, args.latency)
+        (args.dns, args.tls, args.latency, args.revocation)
     };
 
+    // Ports from the config are checked with --ports and in full runs
+    let mut port_specs = args.ports.clone();
+    if run_all || !args.ports.is_empty() {
+        for spec in &config.global.diagnose_ports {
+            port_specs.push(spec.parse().map_err(|e| {
+                azure_aitoolsconnect::AppError::Config(format!("[global] diagnose_ports: {}", e))
+            })?);
+        }
+    }
+
     if !quiet {
+        let targets = if endpoints.is_empty() {
+            regions.join(", ")
+        } else {
+            format!("{} endpoint(s)", hosts.len())
+        };
         println!(
             "{} Running network diagnostics for {} ({})...",
             style("[*]").cyan(),
-            region,
+            targets,
             cloud
         );
     }
 
-    let diagnostics = run_diagnostics(
-        &region,
-        cloud,
+    let parallelism = if args.sequential {
+        1
+    } else {
+        args.parallel.into()
+    };
+    let mut diagnostics = run_diagnostics(
+        &hosts,
         check_dns,
         check_tls,
         check_latency,
-        args.endpoint.as_deref(),
+        check_revocation,
+        parallelism,
     )
     .await;
+    // Grouped output only when there is more than one target to tell apart
+    if groups.len() > 1 || hosts.len() > 1 && !endpoints.is_empty() {
+        diagnostics.groups = groups;
+    }
+    if let Some(resolver) = doh_url {
+        run_doh_comparison(&mut diagnostics, &hosts, resolver).await;
+    }
+    if args.enrich || service_tags.is_some() {
+        let resolver = args
+            .enrich
+            .then(|| doh_url.unwrap_or(config.global.doh_url()));
+        run_address_enrichment(
+            &mut diagnostics,
+            &hosts,
+            cloud,
+            service_tags.as_ref(),
+            resolver,
+        )
+        .await;
+    }
+    if !port_specs.is_empty() {
+        let timeout = config
+            .global
+            .connect_timeout_seconds
+            .unwrap_or(azure_aitoolsconnect::config::DEFAULT_CONNECT_TIMEOUT_SECS);
+        run_port_checks(
+            &mut diagnostics,
+            &hosts,
+            &port_specs,
+            std::time::Duration::from_secs(timeout),
+        )
+        .await;
+    }
+    if args.icmp {
+        run_icmp_checks(
+            &mut diagnostics,
+            &hosts,
+            args.icmp_count,
+            azure_aitoolsconnect::network::icmp::REPLY_TIMEOUT,
+        )
+        .await;
+    }
+    if let Some(connections) = args.concurrency {
+        run_concurrency_checks(
+            &mut diagnostics,
+            &hosts,
+            connections.into(),
+            std::time::Duration::from_secs(config.global.timeout_seconds),
+        )
+        .await;
+    }
+    let environment_timeout = std::time::Duration::from_secs(config.global.timeout_seconds);
+    if args.environment {
+        diagnostics.environment = Some(
+            capture_environment(config.global.ip_echo_url(), cloud, environment_timeout).await,
+        );
+    } else if !args.no_environment {
+        diagnostics.environment = Some(capture_local_environment(cloud, environment_timeout).await);
+    }
 
     // Format output
     let use_colors = std::io::stdout().is_terminal() && !quiet;
@@ -329,16 +1351,380 @@ async fn run_diagnose(
     let has_dns_failure = diagnostics.dns.iter().any(|r| !r.resolved);
     let has_tls_failure = diagnostics.tls.iter().any(|r| !r.success);
     let has_latency_failure = diagnostics.latency.iter().any(|r| !r.success);
-
-    if has_dns_failure || has_tls_failure || has_latency_failure {
+    let has_revocation_failure = diagnostics.revocation.iter().any(|r| !r.success());
+    let has_port_failure = diagnostics
+        .ports
+        .iter()
+        .any(|r| r.status != azure_aitoolsconnect::network::PortStatus::Open);
+    let has_concurrency_failure = diagnostics.concurrency.iter().any(|r| r.failed > 0);
+
+    if has_dns_failure
+        || has_tls_failure
+        || has_latency_failure
+        || has_revocation_failure
+        || has_port_failure
+        || has_concurrency_failure
+    {
         Ok(ExitCode::NetworkFailure)
     } else {
         Ok(ExitCode::Success)
     }
 }
 
+async fn run_doctor(
+    args: azure_aitoolsconnect::cli::DoctorArgs,
+    config: &Config,
+    verbose: bool,
+    quiet: bool,
+) -> azure_aitoolsconnect::Result<ExitCode> {
+    let mut builder = TestRunnerConfig::builder()
+        .config(config)
+        .services(parse_services(&args.services))
+        .cloud(args.cloud.into())
+        .auth_method(args.auth.into())
+        .timeout(std::time::Duration::from_secs(args.timeout))
+        .verbose(verbose)
+        // The doctor prints its own step-by-step report
+        .quiet(true);
+    if let Some(api_key) = api_key_arg(args.api_key, args.keyvault_secret) {
+        builder = builder.api_key(api_key);
+    }
+    if let Some(region) = args.region {
+        builder = builder.region(region);
+    }
+    if let Some(endpoint) = args.endpoint {
+        builder = builder.endpoint(endpoint);
+    }
+    if let Some(tenant) = args.tenant {
+        builder = builder.tenant(tenant);
+    }
+    if let Some(bearer_token) = args.bearer_token {
+        builder = builder.bearer_token(bearer_token);
+    }
+    if let Some(scope) = args.scope {
+        builder = builder.scope(scope);
+    }
+    let mut runner_config = builder.build();
+
+    check_region(&runner_config.region, runner_config.cloud, quiet)?;
+    runner_config.resolve_secrets().await?;
+
+    if !quiet {
+        println!(
+            "{} Checking environment, DNS, TCP, TLS, authentication, and services...",
+            style("[*]").cyan()
+        );
+    }
+
+    let report = run_doctor_checks(runner_config).await?;
+
+    match args.output {
+        azure_aitoolsconnect::cli::OutputFormatArg::Json => {
+            let json = serde_json::to_string_pretty(&report)
+                .map_err(azure_aitoolsconnect::AppError::Json)?;
+            println!("{}", json);
+        }
+        _ => {
+            let use_colors = std::io::stdout().is_terminal() && !quiet;
+            print!("{}", format_doctor(&report, use_colors));
+        }
+    }
+
+    Ok(report.exit_code())
+}
+
+fn run_selftest(
+    args: azure_aitoolsconnect::cli::SelftestArgs,
+    quiet: bool,
+) -> azure_aitoolsconnect::Result<ExitCode> {
+    let report = run_selftest_checks(&args.dir);
+
+    match args.output {
+        azure_aitoolsconnect::cli::OutputFormatArg::Json => {
+            let json = serde_json::to_string_pretty(&report)
+                .map_err(azure_aitoolsconnect::AppError::Json)?;
+            println!("{}", json);
+        }
+        _ => {
+            let use_colors = std::io::stdout().is_terminal() && !quiet;
+            print!("{}", format_selftest(&report, use_colors));
+        }
+    }
+
+    Ok(report.exit_code())
+}
+
+fn run_config_get(path: &std::path::Path, key: &str) -> azure_aitoolsconnect::Result<ExitCode> {
+    let content = read_config_file(path)?;
+    let value = azure_aitoolsconnect::config::edit::get_value(&content, key)?;
+    // Keys and tokens in the file are redacted like everywhere else
+    azure_aitoolsconnect::redact::register_config_secrets(&toml::from_str(&content)?);
+    match value {
+        Some(value) => {
+            println!("{}", azure_aitoolsconnect::redact::redact(&value));
+            Ok(ExitCode::Success)
+        }
+        None => Err(azure_aitoolsconnect::AppError::Config(format!(
+            "'{}' is not set",
+            key
+        ))),
+    }
+}
+
+fn run_env(
+    args: azure_aitoolsconnect::cli::EnvArgs,
+    config: &Config,
+    quiet: bool,
+) -> azure_aitoolsconnect::Result<ExitCode> {
+    use azure_aitoolsconnect::config::env::{env_report, format_env_report};
+
+    // Per-service variables apply to the services in the config
+    let mut services: Vec<String> = config.services.keys().cloned().collect();
+    services.sort();
+    let mut report = env_report(&services);
+    if args.set {
+        report.retain(|var| var.value.is_some());
+    }
+
+    match args.output {
+        azure_aitoolsconnect::cli::OutputFormatArg::Json => {
+            let json = serde_json::to_string_pretty(&report)
+                .map_err(azure_aitoolsconnect::AppError::Json)?;
+            println!("{}", json);
+        }
+        _ => {
+            let use_colors = std::io::stdout().is_terminal() && !quiet;
+            print!("{}", format_env_report(&report, use_colors));
+        }
+    }
+    Ok(ExitCode::Success)
+}
+
+async fn run_serve(
+    args: azure_aitoolsconnect::cli::ServeArgs,
+    config: &Config,
+    quiet: bool,
+) -> azure_aitoolsconnect::Result<ExitCode> {
+    use azure_aitoolsconnect::serve::{serve, ServeOptions};
+
+    let mut options = ServeOptions::from_config(config)?;
+    if let Some(listen) = args.listen {
+        options.listen = listen;
+    }
+    if let Some(history) = args.history {
+        options.history = history.max(1);
+    }
+    if args.history_dir.is_some() {
+        options.history_dir = args.history_dir;
+    }
+    options.run_now = args.run_now;
+    options.accept_reports |= args.accept_reports;
+
+    if !quiet && !config.serve.jobs.is_empty() {
+        eprintln!(
+            "{} Running {} scheduled job(s); status on http://{} (Ctrl+C to stop)",
+            style("[*]").cyan(),
+            config.serve.jobs.len(),
+            options.listen
+        );
+    }
+    if !quiet && options.accept_reports {
+        eprintln!(
+            "{} Accepting agents' reports at http://{}/reports; matrix at /matrix.json",
+            style("[*]").cyan(),
+            options.listen
+        );
+    }
+    serve(config, options).await?;
+    Ok(ExitCode::Success)
+}
+
+fn run_aggregate(
+    args: azure_aitoolsconnect::cli::AggregateArgs,
+    quiet: bool,
+) -> azure_aitoolsconnect::Result<ExitCode> {
+    use azure_aitoolsconnect::aggregate::{
+        format_matrix, format_matrix_markdown, load_reports, Matrix,
+    };
+
+    let mut matrix = Matrix::new(&load_reports(&args.paths)?);
+    if args.differences {
+        matrix.retain_differences();
+    }
+    let output = match args.output {
+        azure_aitoolsconnect::cli::OutputFormatArg::Json => {
+            serde_json::to_string_pretty(&matrix).map_err(azure_aitoolsconnect::AppError::Json)?
+        }
+        azure_aitoolsconnect::cli::OutputFormatArg::Markdown => format_matrix_markdown(&matrix),
+        _ => {
+            let use_colors =
+                std::io::stdout().is_terminal() && !quiet && args.output_file.is_none();
+            format_matrix(&matrix, use_colors)
+        }
+    };
+    write_output(&output, args.output_file.as_deref())?;
+    Ok(ExitCode::Success)
+}
+
+/// `test --dry-run`: the resolved settings and planned scenarios; with
+/// `--plan`, also every request and the hosts they reach
+fn print_plan(
+    mut runner_config: TestRunnerConfig,
+    regions: &[String],
+    requests: bool,
+    output: azure_aitoolsconnect::cli::OutputFormatArg,
+    quiet: bool,
+) -> azure_aitoolsconnect::Result<ExitCode> {
+    for region in regions {
+        if !runner_config.regions.contains(region) {
+            runner_config.regions.push(region.clone());
+        }
+    }
+    let plan = TestRunner::new(runner_config).plan()?;
+
+    match output {
+        azure_aitoolsconnect::cli::OutputFormatArg::Json => {
+            let json = serde_json::to_string_pretty(&plan)
+                .map_err(azure_aitoolsconnect::AppError::Json)?;
+            println!("{}", azure_aitoolsconnect::redact::redact(&json));
+        }
+        _ => {
+            let use_colors = std::io::stdout().is_terminal() && !quiet;
+            print!(
+                "{}",
+                azure_aitoolsconnect::redact::redact(&format_plan(&plan, requests, use_colors))
+            );
+        }
+    }
+    Ok(ExitCode::Success)
+}
+
+/// Set `key` to `value`, or remove it when `value` is `None`
+fn run_config_set(
+    path: &std::path::Path,
+    key: &str,
+    value: Option<&str>,
+    quiet: bool,
+) -> azure_aitoolsconnect::Result<ExitCode> {
+    use azure_aitoolsconnect::config::edit;
+
+    let content = read_config_file(path)?;
+    let updated = match value {
+        Some(value) => edit::set_value(&content, key, value)?,
+        None => edit::unset_value(&content, key)?,
+    };
+    edit::write_atomic(path, &updated)?;
+
+    if !quiet {
+        let action = if value.is_some() { "Set" } else { "Removed" };
+        println!(
+            "{} {} {} in {}",
+            style("[+]").green(),
+            action,
+            key,
+            path.display()
+        );
+    }
+    Ok(ExitCode::Success)
+}
+
+fn read_config_file(path: &std::path::Path) -> azure_aitoolsconnect::Result<String> {
+    std::fs::read_to_string(path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            azure_aitoolsconnect::AppError::FileNotFound(path.display().to_string())
+        } else {
+            azure_aitoolsconnect::AppError::Io(e)
+        }
+    })
+}
+
+fn run_list_profiles(
+    args: azure_aitoolsconnect::cli::ListProfilesArgs,
+    config: &Config,
+    active: Option<&str>,
+) -> azure_aitoolsconnect::Result<ExitCode> {
+    if let azure_aitoolsconnect::cli::OutputFormatArg::Json = args.output {
+        let profiles: Vec<serde_json::Value> = config
+            .profile
+            .iter()
+            .map(|(name, profile)| {
+                let mut services: Vec<&String> = profile.services.keys().collect();
+                services.sort();
+                serde_json::json!({
+                    "name": name,
+                    "active": active == Some(name.as_str()),
+                    "cloud": profile.global.as_ref().map(|g| g.cloud.to_string()),
+                    "auth_method": profile.auth.as_ref().map(|a| a.default_method.to_string()),
+                    "services": services,
+                })
+            })
+            .collect();
+        let json = serde_json::to_string_pretty(&profiles)
+            .map_err(azure_aitoolsconnect::AppError::Json)?;
+        println!("{}", json);
+        return Ok(ExitCode::Success);
+    }
+
+    if config.profile.is_empty() {
+        println!("No [profile.<name>] sections in the configuration file.");
+        return Ok(ExitCode::Success);
+    }
+
+    println!("{}", style("Profiles:").bold());
+    for (name, profile) in &config.profile {
+        let marker = if active == Some(name.as_str()) {
+            style("*").green().to_string()
+        } else {
+            " ".to_string()
+        };
+        let mut settings = Vec::new();
+        if let Some(global) = &profile.global {
+            settings.push(format!("cloud: {}", global.cloud));
+        }
+        if let Some(auth) = &profile.auth {
+            settings.push(format!("auth: {}", auth.default_method));
+        }
+        if !profile.services.is_empty() {
+            let mut services: Vec<&str> = profile.services.keys().map(String::as_str).collect();
+            services.sort();
+            settings.push(format!("services: {}", services.join(", ")));
+        }
+        println!(
+            "  {} {:<16} {}",
+            marker,
+            name,
+            style(settings.join("; ")).dim()
+        );
+    }
+
+    Ok(ExitCode::Success)
+}
+
 fn run_init(args: azure_aitoolsconnect::cli::InitArgs) -> azure_aitoolsconnect::Result<ExitCode> {
-    let output_path = &args.output;
+    let output_path = match (args.output, args.user) {
+        (Some(path), _) => path,
+        (None, true) => user_config_path()?,
+        (None, false) if args.interactive => match Config::user_config_path() {
+            Some(user_path) => {
+                let location = prompt_choice(
+                    &format!(
+                        "Save to (user: {}, here: ./config.toml)",
+                        user_path.display()
+                    ),
+                    &["user", "here"],
+                    "user",
+                )?;
+                if location == "user" {
+                    user_path
+                } else {
+                    std::path::PathBuf::from("./config.toml")
+                }
+            }
+            None => std::path::PathBuf::from("./config.toml"),
+        },
+        (None, false) => std::path::PathBuf::from("./config.toml"),
+    };
+    let output_path = &output_path;
 
     // Check if file exists
     if output_path.exists() && !args.force {
@@ -357,6 +1743,9 @@ fn run_init(args: azure_aitoolsconnect::cli::InitArgs) -> azure_aitoolsconnect::
     let toml = config.to_toml()?;
 
     // Write to file
+    if let Some(parent) = output_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
     std::fs::write(output_path, toml)?;
 
     println!(
@@ -371,33 +1760,49 @@ fn run_init(args: azure_aitoolsconnect::cli::InitArgs) -> azure_aitoolsconnect::
             style("azure-aitoolsconnect init --interactive").cyan()
         );
     }
+    if !args.user && Config::user_config_path().as_deref() != Some(output_path.as_path()) {
+        println!(
+            "Use {} to write the per-user config file, which is found from any directory.",
+            style("init --user").cyan()
+        );
+    }
 
     Ok(ExitCode::Success)
 }
 
+fn user_config_path() -> azure_aitoolsconnect::Result<std::path::PathBuf> {
+    Config::user_config_path().ok_or_else(|| {
+        azure_aitoolsconnect::AppError::Config(
+            "No per-user config location (HOME, XDG_CONFIG_HOME, or APPDATA is not set)"
+                .to_string(),
+        )
+    })
+}
+
 /// Interactive configuration wizard
 fn run_interactive_init() -> azure_aitoolsconnect::Result<Config> {
     use azure_aitoolsconnect::config::*;
+    use azure_aitoolsconnect::i18n::{tr, tr_args};
     use std::collections::HashMap;
 
     println!();
     println!(
         "{} {}",
         style("[*]").cyan(),
-        style("Azure AI Tools Connect - Configuration Wizard").bold()
+        style(tr("wizard-title")).bold()
     );
     println!();
 
     // Cloud
-    let cloud = prompt_choice("Cloud environment", &["global", "china"], "global")?;
+    let cloud = prompt_choice(&tr("wizard-cloud"), &["global", "china"], "global")?;
     let cloud: Cloud = cloud.parse().unwrap_or(Cloud::Global);
 
     // Region
-    let region = prompt_input("Azure region", "eastus")?;
+    let region = prompt_input(&tr("wizard-region"), "eastus")?;
 
     // Auth method
     let auth_str = prompt_choice(
-        "Authentication method",
+        &tr("wizard-auth"),
         &["key", "token", "device-code", "managed-identity"],
         "key",
     )?;
@@ -409,27 +1814,25 @@ fn run_interactive_init() -> azure_aitoolsconnect::Result<Config> {
 
     match auth_method {
         AuthMethod::Key => {
-            let key = prompt_input("API key (leave blank to set later)", "")?;
+            let key = prompt_input(&tr("wizard-api-key"), "")?;
             if !key.is_empty() {
                 api_key = Some(key);
             }
         }
         AuthMethod::DeviceCode => {
-            let tenant = prompt_input("Tenant ID", "")?;
+            let tenant = prompt_input(&tr("wizard-tenant"), "")?;
             if !tenant.is_empty() {
                 user_config.tenant_id = Some(tenant);
             }
         }
         AuthMethod::Token => {
-            println!(
-                "  You can set the bearer token later with --bearer-token or AZURE_BEARER_TOKEN."
-            );
+            println!("  {}", tr("wizard-token-later"));
         }
         _ => {}
     }
 
     // Endpoint
-    let endpoint_str = prompt_input("Custom endpoint URL (leave blank for regional)", "")?;
+    let endpoint_str = prompt_input(&tr("wizard-endpoint"), "")?;
     let endpoint = if endpoint_str.is_empty() {
         None
     } else {
@@ -438,8 +1841,17 @@ fn run_interactive_init() -> azure_aitoolsconnect::Result<Config> {
 
     // Services
     println!();
-    println!("  Available services: speech, translator, language, vision, document_intelligence");
-    let services_str = prompt_input("Services to enable (comma-separated, or 'all')", "all")?;
+    println!(
+        "  {}",
+        tr_args(
+            "wizard-available-services",
+            &[(
+                "services",
+                &"speech, translator, language, vision, document_intelligence"
+            )]
+        )
+    );
+    let services_str = prompt_input(&tr("wizard-services"), "all")?;
     let service_names = if services_str.to_lowercase() == "all" {
         vec![
             "speech",
@@ -464,6 +1876,7 @@ fn run_interactive_init() -> azure_aitoolsconnect::Result<Config> {
                 api_key: api_key.clone(),
                 endpoint: endpoint.clone(),
                 test_scenarios: vec![],
+                ..Default::default()
             },
         );
     }
@@ -472,19 +1885,41 @@ fn run_interactive_init() -> azure_aitoolsconnect::Result<Config> {
         global: GlobalConfig {
             cloud,
             timeout_seconds: DEFAULT_TIMEOUT_SECS,
+            connect_timeout_seconds: None,
+            tls_timeout_seconds: None,
             output_format: OutputFormat::Human,
+            ip_echo_url: None,
+            doh_url: None,
+            diagnose_ports: Vec::new(),
+            max_rps: None,
+            retry_throttled: false,
+            fail_on_throttle: false,
+            fail_on: FailOn::Failed,
+            max_latency_ms: None,
+            fail_on_slow: false,
+            headers: Default::default(),
+            api_key_header: None,
+            allow_insecure_http: false,
         },
         auth: AuthConfig {
             default_method: auth_method,
+            scope: None,
             entra: EntraConfig::default(),
             user: user_config,
         },
         services,
         custom_inputs: CustomInputs::default(),
+        custom_scenarios: Vec::new(),
+        custom_services: Default::default(),
+        expectations: Default::default(),
+        test_profiles: Default::default(),
+        profile: Default::default(),
+        serve: Default::default(),
+        upload: Default::default(),
     };
 
     println!();
-    println!("  {} Configuration ready.", style("[+]").green());
+    println!("  {} {}", style("[+]").green(), tr("wizard-ready"));
 
     Ok(config)
 }
@@ -537,10 +1972,12 @@ fn prompt_choice(
         Ok(input)
     } else {
         eprintln!(
-            "  {} Invalid choice '{}', using default '{}'",
+            "  {} {}",
             style("[!]").yellow(),
-            input,
-            default
+            azure_aitoolsconnect::i18n::tr_args(
+                "wizard-invalid-choice",
+                &[("input", &input), ("default", &default)]
+            )
         );
         Ok(default.to_string())
     }
@@ -557,9 +1994,26 @@ fn run_validate(
         ));
     }
 
-    let config = Config::from_file(config_path)?;
+    let (config, unknown_keys) = if args.strict {
+        let content = std::fs::read_to_string(config_path)?;
+        azure_aitoolsconnect::config::schema::parse_strict(&content)?
+    } else {
+        (Config::from_file(config_path)?, Vec::new())
+    };
     let warnings = validate_config(&config)?;
 
+    if !unknown_keys.is_empty() {
+        println!(
+            "{} Unknown keys in {}:",
+            style("[-]").red(),
+            config_path.display()
+        );
+        for key in &unknown_keys {
+            println!("  {} {}", style("x").red(), key);
+        }
+        return Ok(ExitCode::ConfigError);
+    }
+
     println!(
         "{} Configuration file is valid: {}",
         style("[+]").green(),
@@ -585,7 +2039,13 @@ fn run_validate(
         } else {
             style("disabled").dim()
         };
-        let has_key = if service.api_key.is_some() {
+        let has_key = if service
+            .api_key
+            .as_deref()
+            .is_some_and(azure_aitoolsconnect::keyvault::is_reference)
+        {
+            style("(key in Key Vault)").green()
+        } else if service.api_key.is_some() {
             style("(key set)").green()
         } else {
             style("(no key)").dim()
@@ -603,6 +2063,22 @@ fn run_validate(
 fn run_list_scenarios(
     args: azure_aitoolsconnect::cli::ListScenariosArgs,
 ) -> azure_aitoolsconnect::Result<ExitCode> {
+    if matches!(
+        args.output,
+        azure_aitoolsconnect::cli::OutputFormatArg::Json
+    ) {
+        let catalog = scenario_catalog(args.service.as_deref()).ok_or_else(|| {
+            azure_aitoolsconnect::AppError::Config(format!(
+                "Unknown service: {}",
+                args.service.as_deref().unwrap_or_default()
+            ))
+        })?;
+        let json =
+            serde_json::to_string_pretty(&catalog).map_err(azure_aitoolsconnect::AppError::Json)?;
+        println!("{}", json);
+        return Ok(ExitCode::Success);
+    }
+
     let scenarios = list_scenarios(args.service.as_deref());
 
     if scenarios.is_empty() {
@@ -619,3 +2095,576 @@ fn run_list_scenarios(
 
     Ok(ExitCode::Success)
 }
+
+fn run_capabilities(
+    args: azure_aitoolsconnect::cli::CapabilitiesArgs,
+) -> azure_aitoolsconnect::Result<ExitCode> {
+    if let Some(service) = &args.service {
+        if azure_aitoolsconnect::get_service(service).is_none() {
+            return Err(azure_aitoolsconnect::AppError::Config(format!(
+                "Unknown service: {}",
+                service
+            )));
+        }
+    }
+
+    let output = format_capabilities(
+        args.region.as_deref(),
+        args.cloud.into(),
+        args.service.as_deref(),
+    );
+    print!("{}", output);
+
+    Ok(ExitCode::Success)
+}
+
+fn run_list_regions(
+    args: azure_aitoolsconnect::cli::ListRegionsArgs,
+) -> azure_aitoolsconnect::Result<ExitCode> {
+    if let Some(service) = &args.service {
+        if azure_aitoolsconnect::get_service(service).is_none() {
+            return Err(azure_aitoolsconnect::AppError::Config(format!(
+                "Unknown service: {}",
+                service
+            )));
+        }
+    }
+
+    let output = format_regions(args.cloud.into(), args.service.as_deref());
+    print!("{}", output);
+
+    Ok(ExitCode::Success)
+}
+
+fn run_endpoint(
+    args: azure_aitoolsconnect::cli::EndpointArgs,
+    quiet: bool,
+) -> azure_aitoolsconnect::Result<ExitCode> {
+    use azure_aitoolsconnect::services::endpoints::{endpoint_report, format_endpoint_report};
+
+    let report = endpoint_report(
+        &parse_services(&args.services),
+        &args.region,
+        args.cloud.into(),
+        args.endpoint.as_deref(),
+        args.custom_domain.as_deref(),
+    )?;
+    match args.output {
+        azure_aitoolsconnect::cli::OutputFormatArg::Json => {
+            let json = serde_json::to_string_pretty(&report)
+                .map_err(azure_aitoolsconnect::AppError::Json)?;
+            println!("{}", json);
+        }
+        _ => {
+            let use_colors = std::io::stdout().is_terminal() && !quiet;
+            print!("{}", format_endpoint_report(&report, use_colors));
+        }
+    }
+    Ok(ExitCode::Success)
+}
+
+/// Validate a region name before making any requests.
+///
+/// Unknown names only produce a warning, as new regions appear over time. A
+/// likely typo (e.g., "eastus1") names the closest known region, since it would
+/// otherwise surface as DNS failures that look like a network problem.
+fn check_region(
+    region: &str,
+    cloud: azure_aitoolsconnect::Cloud,
+    quiet: bool,
+) -> azure_aitoolsconnect::Result<()> {
+    use azure_aitoolsconnect::config::regions::{is_known_region, suggest_region};
+
+    if is_known_region(region, cloud) {
+        return Ok(());
+    }
+
+    if quiet {
+        return Ok(());
+    }
+    match suggest_region(region, cloud) {
+        Some(suggestion) => eprintln!(
+            "{} Region '{}' is not in the known region list for the {} cloud (did you mean '{}'?); continuing anyway",
+            style("[!]").yellow(),
+            region,
+            cloud,
+            suggestion
+        ),
+        None => eprintln!(
+            "{} Region '{}' is not in the known region list for the {} cloud; continuing anyway",
+            style("[!]").yellow(),
+            region,
+            cloud
+        ),
+    }
+    Ok(())
+}
+
+async fn run_discover(
+    args: azure_aitoolsconnect::cli::DiscoverArgs,
+    config: &Config,
+    quiet: bool,
+) -> azure_aitoolsconnect::Result<ExitCode> {
+    use azure_aitoolsconnect::arm::{
+        config_from_accounts, format_accounts, get_token_from_azure_cli, ArmClient,
+    };
+    use std::collections::HashMap;
+
+    let cloud: azure_aitoolsconnect::Cloud = args.cloud.into();
+
+    if let Some(path) = &args.write_config {
+        if path.exists() && !args.force {
+            return Err(azure_aitoolsconnect::AppError::Config(format!(
+                "File already exists: {}. Use --force to overwrite.",
+                path.display()
+            )));
+        }
+    }
+
+    let token = match args.arm_token {
+        Some(token) => token,
+        None => get_token_from_azure_cli(cloud)?,
+    };
+    let client = ArmClient::new(cloud, token, std::time::Duration::from_secs(args.timeout))?;
+
+    let subscriptions = match args.subscription {
+        Some(sub) => vec![sub],
+        None => client.list_subscriptions().await?,
+    };
+
+    let mut accounts = Vec::new();
+    let mut last_error = None;
+    for subscription in &subscriptions {
+        if !quiet {
+            eprintln!(
+                "{} Listing accounts in subscription {}...",
+                style("[*]").cyan(),
+                subscription
+            );
+        }
+        // One subscription the caller can't read (403) doesn't hide the others
+        match client.list_accounts(subscription).await {
+            Ok(found) => accounts.extend(found),
+            Err(e) => {
+                if !quiet {
+                    eprintln!(
+                        "{} Could not list accounts in subscription {}: {}",
+                        style("[!]").yellow(),
+                        subscription,
+                        e
+                    );
+                }
+                last_error = Some(e);
+            }
+        }
+    }
+    if let Some(e) = last_error.filter(|_| accounts.is_empty()) {
+        return Err(e);
+    }
+
+    let mut keys = HashMap::new();
+    if args.fetch_keys {
+        for account in &accounts {
+            if account.disable_local_auth || account.service_names().is_empty() {
+                continue;
+            }
+            match client.list_keys(&account.id).await {
+                Ok(key) => {
+                    keys.insert(account.id.clone(), key);
+                }
+                Err(e) => {
+                    if !quiet {
+                        eprintln!(
+                            "{} Could not fetch keys for {}: {}",
+                            style("[!]").yellow(),
+                            account.name,
+                            e
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    match args.output {
+        azure_aitoolsconnect::cli::OutputFormatArg::Json => {
+            let json = serde_json::to_string_pretty(&accounts)
+                .map_err(azure_aitoolsconnect::AppError::Json)?;
+            println!("{}", json);
+        }
+        _ => {
+            print!("{}", format_accounts(&accounts));
+        }
+    }
+
+    if let Some(path) = &args.write_config {
+        use std::io::Write;
+
+        let mut generated = config_from_accounts(config, &accounts, &keys);
+        generated.global.cloud = cloud;
+        let content = generated.to_toml()?;
+
+        // The file may contain keys; on Unix it is private before they are written
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(path)?;
+        #[cfg(unix)]
+        {
+            // An existing file (--force) keeps its mode; tighten it as well
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        }
+        file.write_all(content.as_bytes())?;
+
+        if !quiet {
+            eprintln!(
+                "{} Configuration file created: {} ({} services, {} keys)",
+                style("[+]").green(),
+                path.display(),
+                generated.services.len(),
+                keys.len()
+            );
+        }
+    }
+
+    Ok(ExitCode::Success)
+}
+
+async fn run_usage(
+    args: azure_aitoolsconnect::cli::UsageArgs,
+    config: &Config,
+    quiet: bool,
+) -> azure_aitoolsconnect::Result<ExitCode> {
+    use azure_aitoolsconnect::arm::{get_token_from_azure_cli, ArmClient};
+    use azure_aitoolsconnect::usage::{collect, format_usage};
+
+    let cloud: azure_aitoolsconnect::Cloud = args.cloud.into();
+    let token = match args.arm_token {
+        Some(token) => token,
+        None => get_token_from_azure_cli(cloud)?,
+    };
+    let client = ArmClient::new(cloud, token, std::time::Duration::from_secs(args.timeout))?;
+
+    let mut accounts = Vec::new();
+    if args.resource_id.is_empty() {
+        let subscriptions = match args.subscription {
+            Some(sub) => vec![sub],
+            None => client.list_subscriptions().await?,
+        };
+        for subscription in &subscriptions {
+            if !quiet {
+                eprintln!(
+                    "{} Listing accounts in subscription {}...",
+                    style("[*]").cyan(),
+                    subscription
+                );
+            }
+            accounts.extend(client.list_accounts(subscription).await?);
+        }
+        // The accounts behind the configured endpoints, if any are among them
+        let host = |endpoint: &str| {
+            url::Url::parse(endpoint)
+                .ok()
+                .and_then(|u| u.host_str().map(str::to_lowercase))
+        };
+        let tested: Vec<String> = config
+            .services
+            .values()
+            .filter_map(|s| s.endpoint.as_deref().and_then(host))
+            .collect();
+        if accounts.iter().any(|a| {
+            a.endpoint
+                .as_deref()
+                .and_then(host)
+                .is_some_and(|h| tested.contains(&h))
+        }) {
+            accounts.retain(|a| {
+                a.endpoint
+                    .as_deref()
+                    .and_then(host)
+                    .is_some_and(|h| tested.contains(&h))
+            });
+        }
+    } else {
+        for resource_id in &args.resource_id {
+            accounts.push(client.get_account(resource_id).await?);
+        }
+    }
+
+    let mut usages = Vec::new();
+    for account in &accounts {
+        if !quiet {
+            eprintln!(
+                "{} Reading quota and usage of {}...",
+                style("[*]").cyan(),
+                account.name
+            );
+        }
+        usages.push(collect(&client, account).await);
+    }
+
+    match args.output {
+        azure_aitoolsconnect::cli::OutputFormatArg::Json => {
+            let json = serde_json::to_string_pretty(&usages)
+                .map_err(azure_aitoolsconnect::AppError::Json)?;
+            println!("{}", json);
+        }
+        _ => {
+            let use_colors = std::io::stdout().is_terminal() && !quiet;
+            print!("{}", format_usage(&usages, use_colors));
+        }
+    }
+
+    Ok(ExitCode::Success)
+}
+
+async fn run_egress_ip(
+    args: azure_aitoolsconnect::cli::EgressIpArgs,
+    config: &Config,
+    quiet: bool,
+) -> azure_aitoolsconnect::Result<ExitCode> {
+    use azure_aitoolsconnect::arm::{
+        check_allowlist, format_egress_check, get_token_from_azure_cli, ArmClient, EgressCheck,
+    };
+
+    let cloud: azure_aitoolsconnect::Cloud = args.cloud.into();
+    let timeout = std::time::Duration::from_secs(args.timeout);
+    let echo_url = config.global.ip_echo_url();
+
+    let egress_ip = detect_public_ip(echo_url, timeout).await.ok_or_else(|| {
+        azure_aitoolsconnect::AppError::Network(format!(
+            "Could not determine the public egress IP via {}",
+            echo_url
+        ))
+    })?;
+
+    let mut check = EgressCheck {
+        egress_ip,
+        echo_url: echo_url.to_string(),
+        account: None,
+        allowlist: None,
+    };
+
+    if let Some(resource_id) = &args.resource_id {
+        if !quiet {
+            eprintln!(
+                "{} Reading network rules for the resource via ARM...",
+                style("[*]").cyan()
+            );
+        }
+        let token = match args.arm_token {
+            Some(token) => token,
+            None => get_token_from_azure_cli(cloud)?,
+        };
+        let account = ArmClient::new(cloud, token, timeout)?
+            .get_account(resource_id)
+            .await?;
+        check.allowlist = Some(check_allowlist(&account, egress_ip));
+        check.account = Some(account);
+    }
+
+    match args.output {
+        azure_aitoolsconnect::cli::OutputFormatArg::Json => {
+            let json = serde_json::to_string_pretty(&check)
+                .map_err(azure_aitoolsconnect::AppError::Json)?;
+            println!("{}", json);
+        }
+        _ => {
+            let use_colors = std::io::stdout().is_terminal() && !quiet;
+            print!("{}", format_egress_check(&check, use_colors));
+        }
+    }
+
+    match &check.allowlist {
+        Some(status) if !status.is_allowed() => Ok(ExitCode::NetworkFailure),
+        _ => Ok(ExitCode::Success),
+    }
+}
+
+async fn run_support_bundle(
+    args: azure_aitoolsconnect::cli::SupportBundleArgs,
+    config: &Config,
+    quiet: bool,
+) -> azure_aitoolsconnect::Result<ExitCode> {
+    use azure_aitoolsconnect::capture::{build_support_bundle, CAPTURE_FILE};
+
+    if !args.capture.join(CAPTURE_FILE).is_file() {
+        return Err(azure_aitoolsconnect::AppError::InvalidInput(format!(
+            "{} is not a capture directory (no {}); run 'test --capture {}' first",
+            args.capture.display(),
+            CAPTURE_FILE,
+            args.capture.display()
+        )));
+    }
+
+    let environment = if args.no_environment {
+        None
+    } else {
+        let timeout = std::time::Duration::from_secs(args.timeout);
+        Some(capture_environment(config.global.ip_echo_url(), config.global.cloud, timeout).await)
+    };
+
+    let bundle = build_support_bundle(&args.capture, config, environment.as_ref())?;
+    let output = args.output.unwrap_or_else(|| {
+        std::path::PathBuf::from(format!(
+            "support-bundle-{}.zip",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ))
+    });
+    std::fs::write(&output, bundle)?;
+
+    if !quiet {
+        eprintln!(
+            "{} Wrote {} (secrets redacted; review before sharing)",
+            style("[OK]").green(),
+            output.display()
+        );
+    }
+    println!("{}", output.display());
+    Ok(ExitCode::Success)
+}
+
+/// Pricing tier of the --resource-id account, to skip the scenarios it
+/// doesn't offer. A failed lookup is left to the inspection after the run
+/// to report.
+async fn resource_sku(
+    resource_id: &str,
+    arm_token: Option<String>,
+    cloud: azure_aitoolsconnect::Cloud,
+    timeout: std::time::Duration,
+) -> Option<String> {
+    use azure_aitoolsconnect::arm::{get_token_from_azure_cli, ArmClient};
+
+    let account = async {
+        let token = match arm_token {
+            Some(token) => token,
+            None => get_token_from_azure_cli(cloud)?,
+        };
+        ArmClient::new(cloud, token, timeout)?
+            .get_account(resource_id)
+            .await
+    }
+    .await;
+    match account {
+        Ok(account) => account.sku,
+        Err(e) => {
+            tracing::debug!(error = %e, "could not read the resource SKU");
+            None
+        }
+    }
+}
+
+/// Fetch the account's network configuration via ARM and correlate it with the
+/// test results, check the role assignments of `principal` (the identity
+/// behind a bearer token) on it, and with `usage` read its quota and usage.
+/// ARM failures are reported as findings rather than errors so the
+/// data-plane results are still shown.
+#[allow(clippy::too_many_arguments)]
+async fn inspect_resource_network(
+    resource_id: &str,
+    arm_token: Option<String>,
+    cloud: azure_aitoolsconnect::Cloud,
+    timeout: std::time::Duration,
+    client_ip: Option<std::net::IpAddr>,
+    principal: Option<&azure_aitoolsconnect::auth::TokenClaims>,
+    usage: bool,
+    report: &azure_aitoolsconnect::TestReport,
+) -> (
+    Vec<String>,
+    Option<azure_aitoolsconnect::usage::AccountUsage>,
+) {
+    use azure_aitoolsconnect::arm::{
+        analyze_auth_config, analyze_network_access, analyze_role_assignments,
+        get_token_from_azure_cli, ArmClient,
+    };
+    use azure_aitoolsconnect::network::check_dns;
+
+    let account = async {
+        let token = match arm_token {
+            Some(token) => token,
+            None => get_token_from_azure_cli(cloud)?,
+        };
+        let client = ArmClient::new(cloud, token, timeout)?;
+        let account = client.get_account(resource_id).await?;
+        Ok::<_, azure_aitoolsconnect::AppError>((client, account))
+    }
+    .await;
+
+    let (client, account) = match account {
+        Ok(found) => found,
+        Err(e) => {
+            return (
+                vec![format!("Could not inspect resource via ARM: {}", e)],
+                None,
+            )
+        }
+    };
+
+    let resolved: Vec<std::net::IpAddr> = match account
+        .endpoint
+        .as_deref()
+        .and_then(|e| url::Url::parse(e).ok())
+        .and_then(|u| u.host_str().map(|h| h.to_string()))
+    {
+        Some(host) => check_dns(&host)
+            .await
+            .addresses
+            .iter()
+            .filter_map(|a| a.parse().ok())
+            .collect(),
+        None => vec![],
+    };
+    let mut findings = analyze_auth_config(&account, report);
+    if let Some((claims, principal_id)) =
+        principal.and_then(|claims| claims.oid.as_deref().map(|oid| (claims, oid)))
+    {
+        // Services of the account that this run called
+        let checked: Vec<&str> = account
+            .service_names()
+            .into_iter()
+            .filter(|name| {
+                azure_aitoolsconnect::get_service(name).is_some_and(|service| {
+                    report
+                        .services
+                        .iter()
+                        .any(|s| s.service_name == service.display_name())
+                })
+            })
+            .collect();
+        let principal_name = claims.identity().unwrap_or(principal_id);
+        match client
+            .list_role_assignments(&account.id, principal_id)
+            .await
+        {
+            Ok(assignments) if !checked.is_empty() => findings.extend(analyze_role_assignments(
+                &account,
+                principal_id,
+                principal_name,
+                &assignments,
+                &checked,
+                report,
+            )),
+            Ok(_) => {}
+            Err(e) => findings.push(format!(
+                "Could not list the role assignments of {}: {}",
+                principal_name, e
+            )),
+        }
+    }
+    findings.extend(analyze_network_access(
+        &account, client_ip, &resolved, report,
+    ));
+    let usage = if usage {
+        let usage = azure_aitoolsconnect::usage::collect(&client, &account).await;
+        findings.extend(azure_aitoolsconnect::usage::usage_findings(&usage, report));
+        Some(usage)
+    } else {
+        None
+    };
+    (findings, usage)
+}
@@ -0,0 +1,813 @@
+//! Embedded mock of the Azure AI endpoints (`test --mock`)
+//!
+//! Every request a scenario sends is redirected to a local plain-HTTP server
+//! that answers with canned Azure responses, so the tool's own parsing,
+//! classification, and formatters can be checked offline and in CI without
+//! credentials. The original host travels in the `X-Mock-Host` header.
+//! WebSocket scenarios are redirected too and get the first message they
+//! wait for (`turn.start`, `session.created`).
+
+use std::net::SocketAddr;
+
+use futures_util::{SinkExt, StreamExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::protocol::Role;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+use crate::error::Result;
+use crate::services::payloads;
+
+/// API key used with `--mock` when none is given
+pub const MOCK_API_KEY: &str = "mock-api-key-0000";
+
+/// Endpoint used with `--mock` when none is given, so endpoint-only services
+/// (Azure OpenAI, Foundry, Model Inference) run too
+pub const MOCK_ENDPOINT: &str = "https://mock-resource.cognitiveservices.azure.com";
+
+/// Requests authenticated with this key are rejected with HTTP 401, to see
+/// how the tool reports an auth failure
+pub const INVALID_KEY: &str = "mock-invalid-key";
+
+/// Header carrying the host a redirected request was meant for
+pub const MOCK_HOST_HEADER: &str = "x-mock-host";
+
+/// Largest request head the mock reads
+const MAX_HEAD_LEN: usize = 64 * 1024;
+
+/// A running mock server; stops when dropped
+pub struct MockServer {
+    addr: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Start the server on a free local port
+    pub async fn start() -> Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+        let addr = listener.local_addr()?;
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream).await {
+                        tracing::debug!(error = %e, "mock connection failed");
+                    }
+                });
+            }
+        });
+        tracing::debug!(%addr, "mock server listening");
+        Ok(Self { addr, task })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Point a URL at the mock server (`https` becomes `http`, `wss` becomes `ws`)
+pub fn redirect_url(url: &url::Url, addr: SocketAddr) -> url::Url {
+    let mut redirected = url.clone();
+    let scheme = match url.scheme() {
+        "wss" | "ws" => "ws",
+        _ => "http",
+    };
+    let _ = redirected.set_scheme(scheme);
+    let _ = redirected.set_ip_host(addr.ip());
+    let _ = redirected.set_port(Some(addr.port()));
+    redirected
+}
+
+/// A parsed request head
+struct MockRequest {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+}
+
+impl MockRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Path without the query string, lowercased
+    fn route(&self) -> String {
+        self.path
+            .split('?')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase()
+    }
+
+    /// `https://<original host>` for absolute URLs in responses
+    fn origin(&self) -> String {
+        let host = self
+            .header(MOCK_HOST_HEADER)
+            .or_else(|| self.header("host"))
+            .unwrap_or("localhost");
+        format!("https://{}", host)
+    }
+
+    fn is_unauthorized(&self) -> bool {
+        ["ocp-apim-subscription-key", "api-key", "authorization"]
+            .iter()
+            .filter_map(|name| self.header(name))
+            .any(|value| value.contains(INVALID_KEY))
+    }
+}
+
+/// A canned response
+struct MockResponse {
+    status: u16,
+    content_type: &'static str,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+    /// Sent with chunked transfer encoding instead of Content-Length
+    chunked: bool,
+}
+
+impl MockResponse {
+    fn json(status: u16, body: serde_json::Value) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            headers: Vec::new(),
+            body: body.to_string().into_bytes(),
+            chunked: false,
+        }
+    }
+
+    fn bytes(content_type: &'static str, body: Vec<u8>) -> Self {
+        Self {
+            status: 200,
+            content_type,
+            headers: Vec::new(),
+            body,
+            chunked: false,
+        }
+    }
+
+    fn error(status: u16, code: &str, message: &str) -> Self {
+        Self::json(
+            status,
+            serde_json::json!({"error": {"code": code, "message": message}}),
+        )
+    }
+
+    /// HTTP 202 pointing at a job to poll
+    fn accepted(operation_location: String) -> Self {
+        let mut response = Self::json(202, serde_json::json!({}));
+        response
+            .headers
+            .push(("Operation-Location", operation_location));
+        response
+    }
+
+    /// Stream the body in chunks, as the TTS service does
+    fn chunked(mut self) -> Self {
+        self.chunked = true;
+        self
+    }
+
+    async fn write_to(&self, stream: &mut TcpStream) -> std::io::Result<()> {
+        let mut head = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nConnection: close\r\n",
+            self.status,
+            reason(self.status),
+            self.content_type,
+        );
+        if self.chunked {
+            head.push_str("Transfer-Encoding: chunked\r\n");
+        } else {
+            head.push_str(&format!("Content-Length: {}\r\n", self.body.len()));
+        }
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str("\r\n");
+        stream.write_all(head.as_bytes()).await?;
+        if self.chunked {
+            for chunk in self.body.chunks(1024) {
+                stream
+                    .write_all(format!("{:x}\r\n", chunk.len()).as_bytes())
+                    .await?;
+                stream.write_all(chunk).await?;
+                stream.write_all(b"\r\n").await?;
+                stream.flush().await?;
+            }
+            stream.write_all(b"0\r\n\r\n").await?;
+        } else {
+            stream.write_all(&self.body).await?;
+        }
+        stream.flush().await
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        202 => "Accepted",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        _ => "Mock",
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream) -> std::io::Result<()> {
+    let (request, body_start) = read_head(&mut stream).await?;
+
+    let upgrade = request
+        .header("upgrade")
+        .is_some_and(|v| v.eq_ignore_ascii_case("websocket"));
+    if upgrade {
+        return handle_websocket(stream, &request).await;
+    }
+
+    drain_body(&mut stream, &request, body_start).await?;
+    let response = if request.is_unauthorized() {
+        MockResponse::error(
+            401,
+            "401",
+            "Access denied due to invalid subscription key or wrong API endpoint. Make sure to provide a valid key for an active subscription and use a correct regional API endpoint for your resource.",
+        )
+    } else {
+        respond(&request)
+    };
+    response.write_to(&mut stream).await
+}
+
+/// Read up to the end of the request head; returns the head and any body
+/// bytes read past it
+async fn read_head(stream: &mut TcpStream) -> std::io::Result<(MockRequest, Vec<u8>)> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let end = loop {
+        if let Some(pos) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos;
+        }
+        if buffer.len() > MAX_HEAD_LEN {
+            return Err(std::io::Error::other("request head too large"));
+        }
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..end]).to_string();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_string();
+    let path = request_line.next().unwrap_or("/").to_string();
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+    Ok((
+        MockRequest {
+            method,
+            path,
+            headers,
+        },
+        buffer[end + 4..].to_vec(),
+    ))
+}
+
+/// Read and discard the request body, so closing the connection doesn't reset
+/// it while the client is still sending
+async fn drain_body(
+    stream: &mut TcpStream,
+    request: &MockRequest,
+    already_read: Vec<u8>,
+) -> std::io::Result<()> {
+    let chunked = request
+        .header("transfer-encoding")
+        .is_some_and(|v| v.to_lowercase().contains("chunked"));
+    if chunked {
+        let mut seen = already_read;
+        let mut chunk = [0u8; 4096];
+        while !seen.ends_with(b"0\r\n\r\n") {
+            let read = stream.read(&mut chunk).await?;
+            if read == 0 {
+                break;
+            }
+            seen.extend_from_slice(&chunk[..read]);
+        }
+        return Ok(());
+    }
+
+    let length: usize = request
+        .header("content-length")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    let remaining = length.saturating_sub(already_read.len());
+    discard(stream, remaining as u64).await
+}
+
+async fn discard<R: AsyncRead + Unpin>(reader: &mut R, length: u64) -> std::io::Result<()> {
+    let copied = tokio::io::copy(&mut reader.take(length), &mut tokio::io::sink()).await?;
+    if copied < length {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
+}
+
+/// Accept the upgrade and send the message the scenario waits for
+async fn handle_websocket(mut stream: TcpStream, request: &MockRequest) -> std::io::Result<()> {
+    let key = request.header("sec-websocket-key").unwrap_or_default();
+    let accept = tokio_tungstenite::tungstenite::handshake::derive_accept_key(key.as_bytes());
+    let response = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept
+    );
+    stream.write_all(response.as_bytes()).await?;
+
+    let mut socket = WebSocketStream::from_raw_socket(stream, Role::Server, None).await;
+    let message = if request.route().contains("/realtime") {
+        serde_json::json!({"type": "session.created", "session": {"id": "mock-session"}})
+            .to_string()
+    } else {
+        "X-RequestId:00000000000000000000000000000000\r\nPath:turn.start\r\nContent-Type:application/json; charset=utf-8\r\n\r\n{\"context\":{\"serviceTag\":\"mock\"}}"
+            .to_string()
+    };
+    socket
+        .send(Message::Text(message))
+        .await
+        .map_err(std::io::Error::other)?;
+    // Keep the socket open until the client closes it
+    while let Some(Ok(message)) = socket.next().await {
+        if message.is_close() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Canned response for a request, by route
+fn respond(request: &MockRequest) -> MockResponse {
+    let route = request.route();
+    let origin = request.origin();
+    let post = request.method.eq_ignore_ascii_case("POST");
+
+    if route.ends_with("/issuetoken") {
+        // Real tokens are JWTs several hundred characters long
+        return MockResponse::bytes(
+            "text/plain",
+            format!("mock.{}", "x".repeat(400)).into_bytes(),
+        );
+    }
+    if route.ends_with("/voices/list") {
+        return MockResponse::json(
+            200,
+            serde_json::json!([{
+                "Name": "Microsoft Server Speech Text to Speech Voice (en-US, JennyNeural)",
+                "DisplayName": "Jenny",
+                "LocalName": "Jenny",
+                "ShortName": "en-US-JennyNeural",
+                "Locale": "en-US",
+                "Gender": "Female",
+                "VoiceType": "Neural"
+            }]),
+        );
+    }
+    if route.ends_with("/avatar/batchsyntheses")
+        || route.ends_with("/videotranslation/translations")
+    {
+        return MockResponse::json(200, serde_json::json!({"value": []}));
+    }
+    if route.ends_with("/cognitiveservices/avatar/relay/token/v1") {
+        return MockResponse::json(
+            200,
+            serde_json::json!({
+                "Urls": ["turn:relay.communication.microsoft.com:3478"],
+                "Username": "mock",
+                "Password": "mock"
+            }),
+        );
+    }
+    if route.ends_with("/speechtotext/transcriptions:transcribe") {
+        return MockResponse::json(
+            200,
+            serde_json::json!({
+                "durationMilliseconds": 1000,
+                "combinedPhrases": [{"text": payloads::SPEECH.text}],
+                "phrases": [{"text": payloads::SPEECH.text, "locale": "en-US", "confidence": 0.9}]
+            }),
+        );
+    }
+    if route.ends_with("/speech/recognition/conversation/cognitiveservices/v1") {
+        return MockResponse::json(
+            200,
+            serde_json::json!({
+                "RecognitionStatus": "Success",
+                "DisplayText": payloads::SPEECH.text,
+                "Offset": 0,
+                "Duration": 10000000
+            }),
+        );
+    }
+    if route.ends_with("/cognitiveservices/v1") && post {
+        return MockResponse::bytes("audio/x-wav", wav_header()).chunked();
+    }
+
+    // Container health endpoints
+    if route == "/ready" {
+        return MockResponse::bytes("text/plain", b"ready".to_vec());
+    }
+    if route == "/status" {
+        return MockResponse::json(
+            200,
+            serde_json::json!({"service": "mock", "apiStatus": "Valid", "apiStatusMessage": "Api Key is valid, no action needed."}),
+        );
+    }
+
+    // Translator
+    if route.ends_with("/languages") {
+        return MockResponse::json(
+            200,
+            serde_json::json!({"translation": {
+                "en": {"name": "English", "nativeName": "English", "dir": "ltr"},
+                "es": {"name": "Spanish", "nativeName": "Español", "dir": "ltr"}
+            }}),
+        );
+    }
+    if route.ends_with("/detect") {
+        return MockResponse::json(
+            200,
+            serde_json::json!([{
+                "language": "en",
+                "score": 1.0,
+                "isTranslationSupported": true,
+                "isTransliterationSupported": false
+            }]),
+        );
+    }
+    if route.ends_with("/transliterate") {
+        return MockResponse::json(
+            200,
+            serde_json::json!([{"text": "konnichiwa", "script": "Latn"}]),
+        );
+    }
+    if route.ends_with("/dictionary/lookup") {
+        return MockResponse::json(
+            200,
+            serde_json::json!([{
+                "normalizedSource": "connection",
+                "displaySource": "connection",
+                "translations": [
+                    {"normalizedTarget": "conexión", "displayTarget": "conexión", "posTag": "NOUN", "confidence": 0.8},
+                    {"normalizedTarget": "enlace", "displayTarget": "enlace", "posTag": "NOUN", "confidence": 0.2}
+                ]
+            }]),
+        );
+    }
+    if route.ends_with("/breaksentence") {
+        return MockResponse::json(200, serde_json::json!([{"sentLen": [36, 22]}]));
+    }
+    if route.ends_with("/translate") {
+        return MockResponse::json(
+            200,
+            serde_json::json!([{
+                "detectedLanguage": {"language": "en", "score": 1.0},
+                "translations": [{"text": "Hola, mundo", "to": "es"}]
+            }]),
+        );
+    }
+
+    // Language
+    if route.ends_with("/language/:analyze-text") {
+        return MockResponse::json(200, language_results());
+    }
+    if route.ends_with("/language/analyze-text/jobs") {
+        return MockResponse::accepted(format!(
+            "{}/language/analyze-text/jobs/00000000-0000-0000-0000-000000000000?api-version=2023-04-01",
+            origin
+        ));
+    }
+    if route.contains("/language/analyze-text/jobs/") {
+        return MockResponse::json(
+            200,
+            serde_json::json!({
+                "jobId": "00000000-0000-0000-0000-000000000000",
+                "status": "succeeded",
+                "tasks": {"completed": 1, "failed": 0, "inProgress": 0, "total": 1, "items": [{
+                    "kind": "ExtractiveSummarizationLROResults",
+                    "status": "succeeded",
+                    "results": language_results()["results"].clone()
+                }]}
+            }),
+        );
+    }
+    if route.ends_with("/language/:analyze-conversations") {
+        return MockResponse::json(
+            200,
+            serde_json::json!({
+                "kind": "ConversationResult",
+                "result": {
+                    "query": "Hello",
+                    "prediction": {
+                        "projectKind": "Conversation",
+                        "topIntent": "Greeting",
+                        "intents": [{"category": "Greeting", "confidenceScore": 0.99}],
+                        "entities": []
+                    }
+                }
+            }),
+        );
+    }
+    if route.ends_with("/language/:query-knowledgebases") {
+        return MockResponse::json(
+            200,
+            serde_json::json!({"answers": [{"answer": "Hello!", "confidenceScore": 0.9, "id": 1}]}),
+        );
+    }
+
+    // Vision
+    if route.ends_with("/computervision/imageanalysis:analyze") {
+        return MockResponse::json(
+            200,
+            serde_json::json!({
+                "modelVersion": "2023-10-01",
+                "metadata": {"width": 100, "height": 100},
+                "tagsResult": {"values": [{"name": "text", "confidence": 0.9}]},
+                "objectsResult": {"values": [{
+                    "boundingBox": {"x": 0, "y": 0, "w": 10, "h": 10},
+                    "tags": [{"name": "sign", "confidence": 0.8}]
+                }]},
+                "readResult": {"blocks": [{"lines": [{"text": payloads::IMAGE.text, "words": []}]}]},
+                "smartCropsResult": {"values": [{
+                    "aspectRatio": 1.0,
+                    "boundingBox": {"x": 0, "y": 0, "w": 100, "h": 100}
+                }]},
+                "peopleResult": {"values": [{
+                    "boundingBox": {"x": 0, "y": 0, "w": 10, "h": 20},
+                    "confidence": 0.9
+                }]}
+            }),
+        );
+    }
+    if route.ends_with("/vision/v3.2/read/analyze") {
+        return MockResponse::accepted(format!(
+            "{}/vision/v3.2/read/analyzeResults/00000000-0000-0000-0000-000000000000",
+            origin
+        ));
+    }
+    if route.contains("/vision/v3.2/read/analyzeresults/") {
+        return MockResponse::json(
+            200,
+            serde_json::json!({
+                "status": "succeeded",
+                "createdDateTime": "2024-01-01T00:00:00Z",
+                "lastUpdatedDateTime": "2024-01-01T00:00:01Z",
+                "analyzeResult": {
+                    "version": "3.2.0",
+                    "modelVersion": "2022-04-30",
+                    "readResults": [{
+                        "page": 1,
+                        "width": 100,
+                        "height": 100,
+                        "unit": "pixel",
+                        "lines": [{"text": payloads::IMAGE.text, "words": []}]
+                    }]
+                }
+            }),
+        );
+    }
+    if route.contains("/computervision/retrieval:vectorize") {
+        return MockResponse::json(
+            200,
+            serde_json::json!({"modelVersion": "2023-04-15", "vector": vec![0.01; 1024]}),
+        );
+    }
+    if route.ends_with("/computervision/imageanalysis:segment") {
+        return MockResponse::bytes("image/png", b"\x89PNG\r\n\x1a\n".to_vec());
+    }
+
+    // Document Intelligence
+    if route.contains("/documentintelligence/documentmodels/") && route.ends_with(":analyze") {
+        let model = route
+            .rsplit('/')
+            .next()
+            .and_then(|m| m.strip_suffix(":analyze"))
+            .unwrap_or("prebuilt-read");
+        return MockResponse::accepted(format!(
+            "{}/documentintelligence/documentModels/{}/analyzeResults/00000000-0000-0000-0000-000000000000?api-version=2024-11-30",
+            origin, model
+        ));
+    }
+    if route.contains("/analyzeresults/") {
+        return MockResponse::json(
+            200,
+            serde_json::json!({
+                "status": "succeeded",
+                "createdDateTime": "2024-01-01T00:00:00Z",
+                "lastUpdatedDateTime": "2024-01-01T00:00:01Z",
+                "analyzeResult": {
+                    "apiVersion": "2024-11-30",
+                    "modelId": "prebuilt-read",
+                    "content": payloads::DOCUMENT.text,
+                    "pages": [{"pageNumber": 1, "width": 8.5, "height": 11, "unit": "inch"}]
+                }
+            }),
+        );
+    }
+    if route.ends_with("/documentintelligence/documentmodels") {
+        return MockResponse::json(
+            200,
+            serde_json::json!({"value": [
+                {"modelId": "prebuilt-read"},
+                {"modelId": "prebuilt-layout"},
+                {"modelId": "prebuilt-invoice"}
+            ]}),
+        );
+    }
+
+    // Azure OpenAI, Foundry, and Model Inference
+    if route.ends_with("/chat/completions") {
+        return MockResponse::json(
+            200,
+            serde_json::json!({
+                "id": "chatcmpl-mock",
+                "object": "chat.completion",
+                "model": "gpt-4o-mini",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "pong"},
+                    "finish_reason": "length"
+                }],
+                "usage": {"prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2}
+            }),
+        );
+    }
+    if route.ends_with("/models") {
+        return MockResponse::json(
+            200,
+            serde_json::json!({"object": "list", "data": [{"id": "gpt-4o-mini", "object": "model"}]}),
+        );
+    }
+    if route.ends_with("/assistants") {
+        return MockResponse::json(200, serde_json::json!({"object": "list", "data": []}));
+    }
+    if route.ends_with("/info") {
+        return MockResponse::json(
+            200,
+            serde_json::json!({
+                "model_name": "mock-model",
+                "model_type": "chat-completion",
+                "model_provider_name": "Mock"
+            }),
+        );
+    }
+
+    MockResponse::error(404, "404", "Resource not found")
+}
+
+/// Results for every Language analyze-text kind at once
+fn language_results() -> serde_json::Value {
+    serde_json::json!({
+        "kind": "MockResults",
+        "results": {
+            "documents": [{
+                "id": "1",
+                "sentiment": "positive",
+                "confidenceScores": {"positive": 0.99, "neutral": 0.01, "negative": 0.0},
+                "sentences": [{
+                    "text": "The documentation is comprehensive.",
+                    "sentiment": "positive",
+                    "rankScore": 1.0,
+                    "offset": 0,
+                    "length": 35,
+                    "targets": [{"text": "documentation", "sentiment": "positive", "offset": 4, "length": 13}],
+                    "assessments": [{"text": "comprehensive", "sentiment": "positive", "offset": 21, "length": 13}]
+                }],
+                "detectedLanguage": {"name": "English", "iso6391Name": "en", "confidenceScore": 1.0},
+                "entities": [{
+                    "text": "Seattle",
+                    "category": "Location",
+                    "offset": 0,
+                    "length": 7,
+                    "confidenceScore": 0.99
+                }],
+                "keyPhrases": ["Seattle"],
+                "redactedText": "*******",
+                "summaries": [{"text": "Hello.", "rankScore": 1.0, "offset": 0, "length": 6}],
+                "warnings": []
+            }],
+            "errors": [],
+            "modelVersion": "2023-04-01"
+        }
+    })
+}
+
+/// A minimal, silent WAV file
+fn wav_header() -> Vec<u8> {
+    // One second of silence, long enough to pass as a synthesized phrase
+    let data_len: u32 = 32000;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&16000u32.to_le_bytes());
+    wav.extend_from_slice(&32000u32.to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    wav.resize(44 + data_len as usize, 0);
+    wav
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::FailureKind;
+    use crate::testing::{TestRunner, TestRunnerConfig};
+
+    fn runner(mock: &MockServer, services: &[&str], api_key: &str) -> TestRunner {
+        let config = TestRunnerConfig::builder()
+            .services(services.iter().copied())
+            .auth_method(crate::config::AuthMethod::Key)
+            .api_key(api_key)
+            .endpoint(MOCK_ENDPOINT)
+            .quiet(true)
+            .mock(mock.addr())
+            .build();
+        TestRunner::new(config)
+    }
+
+    #[tokio::test]
+    async fn test_scenarios_pass_against_mock() {
+        let mock = MockServer::start().await.unwrap();
+        let report = runner(
+            &mock,
+            &["speech", "translator", "vision", "openai"],
+            MOCK_API_KEY,
+        )
+        .run()
+        .await
+        .unwrap();
+
+        let failures: Vec<_> = report
+            .services
+            .iter()
+            .flat_map(|s| &s.results)
+            .filter(|r| !r.success && !r.is_skipped())
+            .map(|r| format!("{}: {:?}", r.scenario_id, r.error))
+            .collect();
+        assert!(failures.is_empty(), "{:?}", failures);
+        assert!(report.summary.passed >= 15, "{:?}", report.summary);
+
+        // Later requests to the mock reuse the first one's connection
+        let reused: Vec<_> = report
+            .services
+            .iter()
+            .flat_map(|s| &s.results)
+            .filter_map(|r| r.timing.as_ref()?.reused_connection)
+            .collect();
+        assert!(
+            reused.contains(&false) && reused.contains(&true),
+            "{:?}",
+            reused
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_key_is_classified() {
+        let mock = MockServer::start().await.unwrap();
+        let report = runner(&mock, &["translator"], INVALID_KEY)
+            .run()
+            .await
+            .unwrap();
+        // The key is rejected once, in the Authentication section, and
+        // scenarios that would send it are skipped
+        let auth = &report.services[0].results[0];
+        assert_eq!(auth.http_status, Some(401));
+        assert_eq!(auth.failure_kind, Some(FailureKind::AuthKey));
+        let detect = report.services[1]
+            .results
+            .iter()
+            .find(|r| r.scenario_id == "detect")
+            .unwrap();
+        assert!(detect.is_skipped(), "{:?}", detect);
+        assert_eq!(report.summary.failed, 1, "{:?}", report.summary);
+
+        // The mock is plain HTTP, so there is no TLS phase
+        let timing = auth.timing.as_ref().unwrap();
+        assert!(timing.connect_ms.is_some() && timing.ttfb_ms.is_some());
+        assert_eq!((timing.tls_ms, timing.failed_phase), (None, None));
+    }
+}
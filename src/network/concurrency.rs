@@ -0,0 +1,180 @@
+//! Concurrent-connection stress probe (`diagnose --concurrency N`)
+//!
+//! Corporate proxies and firewalls often cap the connections per client or
+//! per destination, which a one-request check never hits but an application
+//! sending parallel requests does. The probe times one request on its own,
+//! then opens N TLS connections at once (HTTP/1.1, so requests can't share a
+//! connection) with one lightweight request each. Failed requests, or a
+//! median latency several times the single-request baseline, point at such
+//! a limit.
+
+use std::time::{Duration, Instant};
+
+use reqwest::Client;
+use serde::Serialize;
+
+/// Median latency under load this many times the baseline counts as a collapse
+const COLLAPSE_FACTOR: u64 = 5;
+/// Minimum slowdown for a collapse, so jitter on fast endpoints doesn't count
+const COLLAPSE_MIN_MS: u64 = 500;
+/// Distinct errors kept per endpoint
+const MAX_ERRORS: usize = 3;
+
+/// Outcome of N simultaneous requests to one endpoint
+#[derive(Debug, Clone, Serialize)]
+pub struct ConcurrencyResult {
+    pub endpoint: String,
+    /// Simultaneous connections opened
+    pub connections: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// Latency of a single request before the burst (None if it failed)
+    pub baseline_ms: Option<u64>,
+    /// Median and slowest latency of the requests that got an answer
+    pub median_ms: Option<u64>,
+    pub max_ms: Option<u64>,
+    /// Median latency under load is far above the baseline
+    pub collapsed: bool,
+    /// Distinct errors of the failed requests
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+impl ConcurrencyResult {
+    /// Every request was answered without a latency collapse
+    pub fn success(&self) -> bool {
+        self.failed == 0 && !self.collapsed
+    }
+
+    /// What the result suggests, for the report
+    pub fn describe(&self) -> String {
+        if self.failed > 0 {
+            format!(
+                "{} of {} requests failed; a proxy or firewall may limit concurrent connections",
+                self.failed, self.connections
+            )
+        } else if self.collapsed {
+            "Latency collapsed under load; connections may be queued by a proxy or firewall"
+                .to_string()
+        } else {
+            "All requests answered".to_string()
+        }
+    }
+}
+
+/// Open `connections` simultaneous HTTPS connections to `endpoint`
+pub async fn check_concurrency(
+    endpoint: &str,
+    connections: usize,
+    timeout: Duration,
+) -> ConcurrencyResult {
+    probe(
+        endpoint,
+        &format!("https://{}", endpoint),
+        connections,
+        timeout,
+    )
+    .await
+}
+
+async fn probe(
+    endpoint: &str,
+    url: &str,
+    connections: usize,
+    timeout: Duration,
+) -> ConcurrencyResult {
+    let mut result = ConcurrencyResult {
+        endpoint: endpoint.to_string(),
+        connections,
+        succeeded: 0,
+        failed: connections,
+        baseline_ms: None,
+        median_ms: None,
+        max_ms: None,
+        collapsed: false,
+        errors: Vec::new(),
+    };
+    // The baseline gets a client of its own so the burst can't reuse its connection
+    let build = || {
+        crate::network::configure_client(Client::builder())
+            .timeout(timeout)
+            .http1_only()
+            .build()
+    };
+    let (baseline_client, client) = match (build(), build()) {
+        (Ok(baseline_client), Ok(client)) => (baseline_client, client),
+        (Err(e), _) | (_, Err(e)) => {
+            result
+                .errors
+                .push(format!("Failed to create client: {}", e));
+            return result;
+        }
+    };
+    result.baseline_ms = timed_request(&baseline_client, url).await.ok();
+
+    let requests = (0..connections).map(|_| timed_request(&client, url));
+    let mut latencies = Vec::new();
+    for outcome in futures_util::future::join_all(requests).await {
+        match outcome {
+            Ok(ms) => latencies.push(ms),
+            Err(error) => {
+                if result.errors.len() < MAX_ERRORS && !result.errors.contains(&error) {
+                    result.errors.push(error);
+                }
+            }
+        }
+    }
+    latencies.sort_unstable();
+    result.succeeded = latencies.len();
+    result.failed = connections - latencies.len();
+    result.median_ms = latencies.get(latencies.len() / 2).copied();
+    result.max_ms = latencies.last().copied();
+    result.collapsed = match (result.baseline_ms, result.median_ms) {
+        (Some(baseline), Some(median)) => is_collapse(baseline, median),
+        _ => false,
+    };
+    result
+}
+
+/// Milliseconds until `url` answered with any HTTP status
+async fn timed_request(client: &Client, url: &str) -> Result<u64, String> {
+    let start = Instant::now();
+    match client.head(url).send().await {
+        Ok(_) => Ok(start.elapsed().as_millis() as u64),
+        Err(e) if e.is_timeout() => Err("No response before the timeout".to_string()),
+        Err(e) if e.is_connect() => Err(format!("Connection failed: {}", e)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn is_collapse(baseline_ms: u64, median_ms: u64) -> bool {
+    median_ms >= baseline_ms.max(1) * COLLAPSE_FACTOR && median_ms - baseline_ms >= COLLAPSE_MIN_MS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockServer;
+
+    #[tokio::test]
+    async fn test_concurrency_probe() {
+        assert!(!is_collapse(40, 120));
+        assert!(!is_collapse(2, 300));
+        assert!(is_collapse(100, 900));
+
+        let mock = MockServer::start().await.unwrap();
+        let url = format!("http://{}/", mock.addr());
+        let result = probe("mock", &url, 8, Duration::from_secs(5)).await;
+        assert_eq!(result.succeeded, 8, "{:?}", result);
+        assert!(result.baseline_ms.is_some());
+
+        // Nothing listens on the port once the listener is gone
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        drop(listener);
+        let result = probe("closed", &url, 4, Duration::from_secs(5)).await;
+        assert_eq!(result.failed, 4);
+        assert!(!result.success());
+        assert!(result.describe().starts_with("4 of 4 requests failed"));
+    }
+}
@@ -0,0 +1,278 @@
+//! DNS-over-HTTPS comparison (`diagnose --doh`)
+//!
+//! Corporate networks often answer Azure hostnames differently from the public
+//! internet: a private DNS zone returns a private endpoint's address, or a
+//! filtering resolver returns nothing (or a sinkhole address) for names it
+//! blocks. Resolving the same names through a public DoH resolver, over HTTPS
+//! so the local resolver is bypassed, shows whether the system answer is the
+//! one the rest of the world sees.
+
+use std::collections::BTreeSet;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use super::DnsResult;
+
+/// Default DoH resolver (JSON API)
+pub const DEFAULT_DOH_URL: &str = "https://cloudflare-dns.com/dns-query";
+
+/// DNS record types in a DoH JSON answer
+const RECORD_A: u16 = 1;
+const RECORD_CNAME: u16 = 5;
+pub(crate) const RECORD_PTR: u16 = 12;
+pub(crate) const RECORD_TXT: u16 = 16;
+const RECORD_AAAA: u16 = 28;
+
+/// DNS response codes a DoH resolver reports in `Status`
+const RCODE_NOERROR: u32 = 0;
+const RCODE_NXDOMAIN: u32 = 3;
+
+/// How the system resolver's answer compares with the DoH resolver's
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DnsComparison {
+    /// Both resolvers returned at least one common address, or both found nothing
+    Match,
+    /// Both returned public addresses, but none in common
+    Differ,
+    /// System DNS returns private addresses (or the only answer) where public
+    /// DNS returns public ones, as with a private endpoint's DNS zone
+    SplitHorizon,
+    /// System DNS fails or returns a sinkhole address for a name public DNS resolves
+    Filtered,
+    /// The DoH resolver couldn't be queried, so nothing was compared
+    DohFailed,
+}
+
+impl DnsComparison {
+    /// Whether the answers differ in a way worth flagging
+    pub fn is_mismatch(&self) -> bool {
+        matches!(self, Self::Differ | Self::SplitHorizon | Self::Filtered)
+    }
+
+    /// What the comparison means for the host
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Self::Match => "system DNS and DoH agree",
+            Self::Differ => {
+                "system DNS and DoH return different public addresses (normal for geo-routed names, otherwise a DNS override)"
+            }
+            Self::SplitHorizon => {
+                "split-horizon DNS: system DNS returns a private address that public DNS does not (private endpoint or hosts file)"
+            }
+            Self::Filtered => {
+                "system DNS blocks a name public DNS resolves (DNS filtering or a missing forwarder)"
+            }
+            Self::DohFailed => "DoH resolver unreachable; DNS over HTTPS may be blocked",
+        }
+    }
+}
+
+/// Resolution of a hostname through a DoH resolver, compared with system DNS
+#[derive(Debug, Clone, Serialize)]
+pub struct DohResult {
+    pub hostname: String,
+    /// DoH resolver URL
+    pub resolver: String,
+    pub addresses: Vec<String>,
+    /// CNAME chain returned by the resolver
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub cnames: Vec<String>,
+    /// Addresses returned by the system resolver
+    pub system_addresses: Vec<String>,
+    pub comparison: DnsComparison,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
+
+/// A DoH JSON API response (`application/dns-json`)
+#[derive(Debug, Deserialize)]
+struct DohResponse {
+    #[serde(rename = "Status")]
+    status: u32,
+    #[serde(rename = "Answer", default)]
+    answer: Vec<DohAnswer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DohAnswer {
+    #[serde(rename = "type")]
+    record_type: u16,
+    data: String,
+}
+
+/// Whether an address is a sinkhole answer used by filtering resolvers
+fn is_sinkhole(ip: &IpAddr) -> bool {
+    ip.is_unspecified() || ip.is_loopback()
+}
+
+/// Compare the system resolver's addresses with the DoH resolver's
+pub fn compare(system: &[String], doh: &[String]) -> DnsComparison {
+    let system: BTreeSet<IpAddr> = system.iter().filter_map(|a| a.parse().ok()).collect();
+    let doh: BTreeSet<IpAddr> = doh.iter().filter_map(|a| a.parse().ok()).collect();
+
+    if system.iter().all(is_sinkhole) && !doh.is_empty() {
+        return DnsComparison::Filtered;
+    }
+    if doh.is_empty() || !system.is_disjoint(&doh) {
+        return if doh.is_empty() && !system.is_empty() {
+            DnsComparison::SplitHorizon
+        } else {
+            DnsComparison::Match
+        };
+    }
+    if system.iter().any(crate::arm::is_private_address) {
+        DnsComparison::SplitHorizon
+    } else {
+        DnsComparison::Differ
+    }
+}
+
+/// Query one record type, returning the answers' (type, data); a name that
+/// doesn't exist has none
+pub(crate) async fn query_records(
+    client: &Client,
+    resolver: &str,
+    name: &str,
+    record_type: &str,
+) -> Result<Vec<(u16, String)>, String> {
+    let response = client
+        .get(resolver)
+        .query(&[("name", name), ("type", record_type)])
+        .header("Accept", "application/dns-json")
+        .send()
+        .await
+        .map_err(|e| {
+            if e.is_timeout() {
+                "DoH query timed out".to_string()
+            } else {
+                e.to_string()
+            }
+        })?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("DoH resolver returned HTTP {}", status.as_u16()));
+    }
+    let body: DohResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Unexpected DoH response: {}", e))?;
+    match body.status {
+        RCODE_NOERROR | RCODE_NXDOMAIN => {}
+        rcode => return Err(format!("DoH resolver returned DNS rcode {}", rcode)),
+    }
+    Ok(body
+        .answer
+        .into_iter()
+        .map(|answer| (answer.record_type, answer.data))
+        .collect())
+}
+
+/// Query one address record type, returning (addresses, cnames)
+async fn query(
+    client: &Client,
+    resolver: &str,
+    hostname: &str,
+    record_type: &str,
+) -> Result<(Vec<String>, Vec<String>), String> {
+    let mut addresses = Vec::new();
+    let mut cnames = Vec::new();
+    for (kind, data) in query_records(client, resolver, hostname, record_type).await? {
+        match kind {
+            RECORD_A | RECORD_AAAA => addresses.push(data),
+            RECORD_CNAME => cnames.push(data.trim_end_matches('.').to_string()),
+            _ => {}
+        }
+    }
+    Ok((addresses, cnames))
+}
+
+/// Resolve `hostname` (A and AAAA) through the DoH resolver at `resolver` and
+/// compare the answer with the system resolver's
+pub async fn check_doh(
+    hostname: &str,
+    system: &DnsResult,
+    resolver: &str,
+    timeout: Duration,
+) -> DohResult {
+    let start = Instant::now();
+    let mut result = DohResult {
+        hostname: hostname.to_string(),
+        resolver: resolver.to_string(),
+        addresses: vec![],
+        cnames: vec![],
+        system_addresses: system.addresses.clone(),
+        comparison: DnsComparison::DohFailed,
+        duration_ms: 0,
+        error: None,
+    };
+
+    let client = match crate::network::configure_client(Client::builder())
+        .timeout(timeout)
+        .build()
+    {
+        Ok(c) => c,
+        Err(e) => {
+            result.error = Some(e.to_string());
+            return result;
+        }
+    };
+    let (v4, v6) = tokio::join!(
+        query(&client, resolver, hostname, "A"),
+        query(&client, resolver, hostname, "AAAA")
+    );
+    result.duration_ms = start.elapsed().as_millis() as u64;
+
+    match (v4, v6) {
+        (Err(e), _) | (_, Err(e)) => result.error = Some(e),
+        (Ok((v4, mut cnames)), Ok((v6, v6_cnames))) => {
+            for cname in v6_cnames {
+                if !cnames.contains(&cname) {
+                    cnames.push(cname);
+                }
+            }
+            result.addresses = v4.into_iter().chain(v6).collect();
+            result.cnames = cnames;
+            result.comparison = compare(&result.system_addresses, &result.addresses);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addrs(list: &[&str]) -> Vec<String> {
+        list.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_compare() {
+        let public = addrs(&["20.42.6.200"]);
+        assert_eq!(compare(&public, &public), DnsComparison::Match);
+        assert_eq!(
+            compare(&addrs(&["20.42.6.201"]), &public),
+            DnsComparison::Differ
+        );
+        assert_eq!(
+            compare(&addrs(&["10.1.0.4"]), &public),
+            DnsComparison::SplitHorizon
+        );
+        // A private zone for a name with no public record
+        assert_eq!(
+            compare(&addrs(&["10.1.0.4"]), &[]),
+            DnsComparison::SplitHorizon
+        );
+        assert_eq!(compare(&[], &public), DnsComparison::Filtered);
+        assert_eq!(
+            compare(&addrs(&["0.0.0.0"]), &public),
+            DnsComparison::Filtered
+        );
+        assert_eq!(compare(&[], &[]), DnsComparison::Match);
+        assert!(!DnsComparison::DohFailed.is_mismatch());
+    }
+}
@@ -0,0 +1,387 @@
+//! Reverse DNS, ASN and Azure region of resolved addresses
+//! (`diagnose --enrich`, `--service-tags`)
+//!
+//! A hostname that resolves can still resolve to the wrong place: a stale
+//! hosts file entry, a DNS override, or a Traffic Manager profile sending
+//! `eastus` traffic to another region. Each address a host resolved to is
+//! looked up in the Azure ServiceTags file (the weekly JSON download listing
+//! every Azure range by service and region), and when asked for, its reverse
+//! DNS name and owning network (ASN, from Team Cymru's IP-to-ASN service).
+//! An address in another region's ranges than the one its hostname names is
+//! flagged.
+//!
+//! The ServiceTags lookup is local. Reverse DNS and ASN queries go through
+//! the DoH resolver, so they are opt-in like `--doh`; private addresses are
+//! never sent.
+
+use std::net::IpAddr;
+use std::time::Duration;
+
+use ipnet::IpNet;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use super::doh::{query_records, RECORD_PTR, RECORD_TXT};
+use super::DnsResult;
+use crate::config::regions::is_known_region;
+use crate::config::Cloud;
+
+/// What is known about one address a host resolved to
+#[derive(Debug, Clone, Serialize)]
+pub struct AddressInfo {
+    pub hostname: String,
+    pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reverse_dns: Option<String>,
+    /// Autonomous system announcing the address
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asn: Option<u32>,
+    /// Announced prefix containing the address
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asn_prefix: Option<String>,
+    /// Most specific ServiceTags entry containing the address
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_tag: Option<String>,
+    /// Region of that entry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub azure_region: Option<String>,
+    /// Region the hostname names (`eastus.api.cognitive.microsoft.com`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_region: Option<String>,
+    /// The address is in another region's ranges than `expected_region`
+    pub region_mismatch: bool,
+    /// Lookups that failed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+impl AddressInfo {
+    /// One-line description of what was found, after the address
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(asn) = self.asn {
+            match &self.asn_prefix {
+                Some(prefix) => parts.push(format!("AS{} ({})", asn, prefix)),
+                None => parts.push(format!("AS{}", asn)),
+            }
+        }
+        if let Some(tag) = &self.service_tag {
+            parts.push(tag.clone());
+        }
+        if let Some(name) = &self.reverse_dns {
+            parts.push(format!("rDNS {}", name));
+        }
+        if self.region_mismatch {
+            parts.push(format!(
+                "in {} ranges, expected {}",
+                self.azure_region.as_deref().unwrap_or_default(),
+                self.expected_region.as_deref().unwrap_or_default()
+            ));
+        }
+        parts.join(", ")
+    }
+}
+
+/// A ServiceTags file (`ServiceTags_Public_*.json`)
+#[derive(Debug, Deserialize)]
+struct ServiceTagsFile {
+    values: Vec<ServiceTagValue>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ServiceTagValue {
+    name: String,
+    properties: ServiceTagProperties,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ServiceTagProperties {
+    #[serde(default)]
+    region: String,
+    #[serde(default)]
+    address_prefixes: Vec<String>,
+}
+
+/// One address range of a service tag
+#[derive(Debug, Clone)]
+struct TagRange {
+    network: IpNet,
+    tag: String,
+    /// Empty for global tags
+    region: String,
+}
+
+/// Azure address ranges by service tag and region
+#[derive(Debug, Clone, Default)]
+pub struct ServiceTags {
+    ranges: Vec<TagRange>,
+}
+
+impl ServiceTags {
+    /// Parse a ServiceTags JSON file; prefixes that aren't CIDR ranges are skipped
+    pub fn from_json(content: &str) -> Result<Self, String> {
+        let file: ServiceTagsFile =
+            serde_json::from_str(content).map_err(|e| format!("Not a ServiceTags file: {}", e))?;
+        let ranges = file
+            .values
+            .into_iter()
+            .flat_map(|value| {
+                let ServiceTagValue { name, properties } = value;
+                properties
+                    .address_prefixes
+                    .into_iter()
+                    .filter_map(|prefix| prefix.parse().ok())
+                    .map(move |network| TagRange {
+                        network,
+                        tag: name.clone(),
+                        region: properties.region.clone(),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        Ok(Self { ranges })
+    }
+
+    /// The tag and region of the most specific range containing `ip`, a
+    /// regional tag preferred over a global one
+    pub fn lookup(&self, ip: &IpAddr) -> Option<(&str, Option<&str>)> {
+        self.ranges
+            .iter()
+            .filter(|range| range.network.contains(ip))
+            .max_by_key(|range| (!range.region.is_empty(), range.network.prefix_len()))
+            .map(|range| {
+                let region = (!range.region.is_empty()).then_some(range.region.as_str());
+                (range.tag.as_str(), region)
+            })
+    }
+
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+}
+
+/// Region a hostname names in its first label, for regional endpoints
+pub fn expected_region(hostname: &str, cloud: Cloud) -> Option<String> {
+    let label = hostname.split('.').next()?.to_lowercase();
+    (label != "global" && is_known_region(&label, cloud)).then_some(label)
+}
+
+/// Reversed name of an address under `suffix`: octets for IPv4, nibbles for IPv6
+fn reverse_name(ip: &IpAddr, v4_suffix: &str, v6_suffix: &str) -> String {
+    match ip {
+        IpAddr::V4(v4) => {
+            let octets: Vec<String> = v4.octets().iter().rev().map(u8::to_string).collect();
+            format!("{}.{}", octets.join("."), v4_suffix)
+        }
+        IpAddr::V6(v6) => {
+            let nibbles: Vec<String> = v6
+                .octets()
+                .iter()
+                .rev()
+                .flat_map(|byte| [byte & 0x0f, byte >> 4])
+                .map(|nibble| format!("{:x}", nibble))
+                .collect();
+            format!("{}.{}", nibbles.join("."), v6_suffix)
+        }
+    }
+}
+
+/// ASN and prefix from a Team Cymru origin TXT record
+/// (`"8075 | 20.32.0.0/11 | US | arin | 2017-10-18"`); the first of several
+/// origin ASNs
+fn parse_cymru_origin(txt: &str) -> Option<(u32, String)> {
+    let mut fields = txt.trim_matches('"').split('|').map(str::trim);
+    let asn = fields.next()?.split_whitespace().next()?.parse().ok()?;
+    let prefix = fields.next()?.to_string();
+    Some((asn, prefix))
+}
+
+/// Reverse DNS name and origin of `ip` through the DoH resolver
+async fn lookup_remote(client: &Client, resolver: &str, ip: &IpAddr, info: &mut AddressInfo) {
+    let ptr_name = reverse_name(ip, "in-addr.arpa", "ip6.arpa");
+    let origin_name = reverse_name(ip, "origin.asn.cymru.com", "origin6.asn.cymru.com");
+    let (ptr, origin) = tokio::join!(
+        query_records(client, resolver, &ptr_name, "PTR"),
+        query_records(client, resolver, &origin_name, "TXT")
+    );
+    match ptr {
+        Ok(answers) => {
+            info.reverse_dns = answers
+                .into_iter()
+                .find(|(kind, _)| *kind == RECORD_PTR)
+                .map(|(_, name)| name.trim_end_matches('.').to_string());
+        }
+        Err(e) => info.errors.push(format!("reverse DNS: {}", e)),
+    }
+    match origin {
+        Ok(answers) => {
+            if let Some((asn, prefix)) = answers
+                .iter()
+                .filter(|(kind, _)| *kind == RECORD_TXT)
+                .find_map(|(_, txt)| parse_cymru_origin(txt))
+            {
+                info.asn = Some(asn);
+                info.asn_prefix = Some(prefix);
+            }
+        }
+        Err(e) => info.errors.push(format!("ASN: {}", e)),
+    }
+}
+
+/// Describe each address in `dns`: its ServiceTags entry when `service_tags`
+/// is given, and its reverse DNS name and ASN when `resolver` is
+pub async fn enrich_addresses(
+    dns: &[DnsResult],
+    cloud: Cloud,
+    service_tags: Option<&ServiceTags>,
+    resolver: Option<&str>,
+    timeout: Duration,
+) -> Vec<AddressInfo> {
+    let client = match resolver {
+        Some(_) => crate::network::configure_client(Client::builder())
+            .timeout(timeout)
+            .build()
+            .map_err(|e| e.to_string()),
+        None => Err(String::new()),
+    };
+
+    let lookups = dns.iter().flat_map(|result| {
+        let expected = expected_region(&result.hostname, cloud);
+        let client = &client;
+        result.addresses.iter().map(move |address| {
+            let expected = expected.clone();
+            async move {
+                let mut info = AddressInfo {
+                    hostname: result.hostname.clone(),
+                    address: address.clone(),
+                    reverse_dns: None,
+                    asn: None,
+                    asn_prefix: None,
+                    service_tag: None,
+                    azure_region: None,
+                    expected_region: expected,
+                    region_mismatch: false,
+                    errors: vec![],
+                };
+                let Ok(ip) = address.parse::<IpAddr>() else {
+                    return info;
+                };
+                if let Some((tag, region)) = service_tags.and_then(|tags| tags.lookup(&ip)) {
+                    info.service_tag = Some(tag.to_string());
+                    info.azure_region = region.map(str::to_string);
+                }
+                info.region_mismatch = match (&info.expected_region, &info.azure_region) {
+                    (Some(expected), Some(actual)) => !expected.eq_ignore_ascii_case(actual),
+                    _ => false,
+                };
+                if let Some(resolver) = resolver {
+                    match client {
+                        // Private addresses mean nothing to public DNS and
+                        // shouldn't leave the network
+                        Ok(_) if crate::arm::is_private_address(&ip) => {}
+                        Ok(client) => lookup_remote(client, resolver, &ip, &mut info).await,
+                        Err(e) => info.errors.push(e.clone()),
+                    }
+                }
+                info
+            }
+        })
+    });
+    futures_util::future::join_all(lookups).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERVICE_TAGS: &str = r#"{
+        "changeNumber": 1,
+        "cloud": "Public",
+        "values": [
+            {"name": "AzureCloud", "id": "AzureCloud", "properties": {
+                "region": "", "addressPrefixes": ["20.0.0.0/8"]}},
+            {"name": "AzureCloud.eastus", "id": "AzureCloud.eastus", "properties": {
+                "region": "eastus", "addressPrefixes": ["20.42.0.0/16", "2603:1030::/32"]}},
+            {"name": "CognitiveServicesFrontend.EastUS", "id": "x", "properties": {
+                "region": "eastus", "addressPrefixes": ["20.42.6.0/24", "not-a-prefix"]}},
+            {"name": "AzureCloud.westeurope", "id": "AzureCloud.westeurope", "properties": {
+                "region": "westeurope", "addressPrefixes": ["20.50.0.0/16"]}}
+        ]
+    }"#;
+
+    #[test]
+    fn test_service_tag_lookup() {
+        let tags = ServiceTags::from_json(SERVICE_TAGS).unwrap();
+        assert_eq!(tags.len(), 5);
+        let lookup = |ip: &str| tags.lookup(&ip.parse().unwrap());
+        assert_eq!(
+            lookup("20.42.6.200"),
+            Some(("CognitiveServicesFrontend.EastUS", Some("eastus")))
+        );
+        assert_eq!(
+            lookup("20.42.1.1"),
+            Some(("AzureCloud.eastus", Some("eastus")))
+        );
+        assert_eq!(lookup("20.1.1.1"), Some(("AzureCloud", None)));
+        assert_eq!(
+            lookup("2603:1030::1"),
+            Some(("AzureCloud.eastus", Some("eastus")))
+        );
+        assert_eq!(lookup("10.1.0.4"), None);
+        assert!(ServiceTags::from_json("{}").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_enrich_flags_region_mismatch() {
+        let tags = ServiceTags::from_json(SERVICE_TAGS).unwrap();
+        let dns = [DnsResult {
+            hostname: "eastus.api.cognitive.microsoft.com".to_string(),
+            resolved: true,
+            addresses: vec!["20.42.6.200".to_string(), "20.50.1.2".to_string()],
+            duration_ms: 5,
+            error: None,
+        }];
+        let info = enrich_addresses(
+            &dns,
+            Cloud::Global,
+            Some(&tags),
+            None,
+            Duration::from_secs(1),
+        )
+        .await;
+        assert!(!info[0].region_mismatch);
+        assert!(info[1].region_mismatch);
+        assert_eq!(
+            info[1].describe(),
+            "AzureCloud.westeurope, in westeurope ranges, expected eastus"
+        );
+        assert_eq!(
+            expected_region("api.cognitive.microsofttranslator.com", Cloud::Global),
+            None
+        );
+    }
+
+    #[test]
+    fn test_reverse_names_and_cymru_origin() {
+        let v4: IpAddr = "20.42.6.200".parse().unwrap();
+        assert_eq!(
+            reverse_name(&v4, "in-addr.arpa", "ip6.arpa"),
+            "200.6.42.20.in-addr.arpa"
+        );
+        let v6: IpAddr = "2603:1030::1".parse().unwrap();
+        assert_eq!(
+            reverse_name(&v6, "in-addr.arpa", "ip6.arpa"),
+            "1.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.3.0.1.3.0.6.2.ip6.arpa"
+        );
+        assert_eq!(
+            parse_cymru_origin("\"8075 | 20.32.0.0/11 | US | arin | 2017-10-18\""),
+            Some((8075, "20.32.0.0/11".to_string()))
+        );
+        assert_eq!(parse_cymru_origin("\"\""), None);
+    }
+}
@@ -0,0 +1,233 @@
+//! ICMP echo and path MTU (`diagnose --icmp`)
+//!
+//! The HTTPS latency check can't tell a lossy network from a slow proxy or
+//! service. A series of ICMP echo probes measures the network layer on its
+//! own: packet loss, round-trip times and jitter. The largest packet that
+//! reaches the host unfragmented (IPv4 only) shows a tunnel or VPN shrinking
+//! the path MTU, which stalls TLS handshakes that fit in no smaller packet.
+//!
+//! Probes are sent by the system `ping`, which holds the privileges raw ICMP
+//! sockets need. Many networks (and many Azure front ends) drop ICMP, so
+//! unanswered probes are a warning rather than a failure.
+
+use std::net::IpAddr;
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::process::Command;
+
+/// IPv4 and ICMP headers, added to the payload size to give the packet size
+const IPV4_ICMP_HEADERS: u16 = 28;
+
+/// How long each probe waits for its reply
+pub const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Packet sizes the path MTU search covers (the IPv4 minimum to Ethernet's MTU)
+const MIN_MTU: u16 = 576;
+const MAX_MTU: u16 = 1500;
+
+/// ICMP echo statistics for one endpoint
+#[derive(Debug, Clone, Serialize)]
+pub struct IcmpResult {
+    pub endpoint: String,
+    pub sent: u32,
+    pub received: u32,
+    pub loss_percent: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_ms: Option<f64>,
+    /// Mean difference between consecutive round trips
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jitter_ms: Option<f64>,
+    /// Largest IPv4 packet that reached the host with "don't fragment" set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_mtu: Option<u16>,
+    pub error: Option<String>,
+}
+
+impl IcmpResult {
+    /// Every probe was answered
+    pub fn success(&self) -> bool {
+        self.error.is_none() && self.sent > 0 && self.received == self.sent
+    }
+}
+
+/// Round-trip times of the replies in `ping` output, in milliseconds. A
+/// reply line carries `time=12.3 ms` (Linux, macOS), `time=12ms` or
+/// `time<1ms` (Windows, under a localized label); the summary lines put a
+/// space after `=` and don't match.
+pub fn parse_round_trips(output: &str) -> Vec<f64> {
+    output
+        .lines()
+        .filter_map(|line| {
+            line.match_indices(['=', '<']).find_map(|(i, _)| {
+                let rest = &line[i + 1..];
+                let end = rest
+                    .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                    .unwrap_or(rest.len());
+                let unit = rest[end..].trim_start();
+                (end > 0 && unit.starts_with("ms"))
+                    .then(|| rest[..end].parse().ok())
+                    .flatten()
+            })
+        })
+        .collect()
+}
+
+/// Mean absolute difference between consecutive round trips
+pub fn jitter(round_trips: &[f64]) -> Option<f64> {
+    if round_trips.len() < 2 {
+        return None;
+    }
+    let total: f64 = round_trips.windows(2).map(|w| (w[1] - w[0]).abs()).sum();
+    Some(total / (round_trips.len() - 1) as f64)
+}
+
+/// `ping` arguments for `count` probes, each waiting up to `timeout`, with
+/// "don't fragment" and a payload of `size` bytes when given
+fn ping_args(host: &str, count: u32, timeout: Duration, size: Option<u16>) -> Vec<String> {
+    let count = count.to_string();
+    let mut args: Vec<String> = if cfg!(windows) {
+        let wait = timeout.as_millis().to_string();
+        vec!["-n".into(), count, "-w".into(), wait]
+    } else if cfg!(target_os = "macos") {
+        let wait = timeout.as_millis().to_string();
+        vec!["-n".into(), "-c".into(), count, "-W".into(), wait]
+    } else {
+        let wait = timeout.as_secs().max(1).to_string();
+        vec!["-n".into(), "-c".into(), count, "-W".into(), wait]
+    };
+    if let Some(size) = size {
+        let size = size.to_string();
+        if cfg!(windows) {
+            args.extend(["-f".into(), "-l".into(), size]);
+        } else if cfg!(target_os = "macos") {
+            args.extend(["-D".into(), "-s".into(), size]);
+        } else {
+            args.extend(["-M".into(), "do".into(), "-s".into(), size]);
+        }
+    }
+    args.push(host.to_string());
+    args
+}
+
+/// Run `ping`, returning its output; it exits non-zero when nothing answered,
+/// so only a failure to start is an error
+async fn ping(args: &[String], deadline: Duration) -> Result<String, String> {
+    let output = tokio::time::timeout(deadline, Command::new("ping").args(args).output())
+        .await
+        .map_err(|_| "ping did not finish".to_string())?
+        .map_err(|e| format!("Could not run ping (is it installed?): {}", e))?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Largest packet size up to [`MAX_MTU`] that reaches `host` unfragmented
+async fn path_mtu(host: &str, timeout: Duration) -> Option<u16> {
+    let deadline = timeout + Duration::from_secs(5);
+    let fits = |mtu: u16| {
+        let args = ping_args(host, 1, timeout, Some(mtu - IPV4_ICMP_HEADERS));
+        async move {
+            ping(&args, deadline)
+                .await
+                .is_ok_and(|output| !parse_round_trips(&output).is_empty())
+        }
+    };
+    if !fits(MIN_MTU).await {
+        return None;
+    }
+    let (mut low, mut high) = (MIN_MTU, MAX_MTU);
+    while low < high {
+        let mid = low + (high - low).div_ceil(2);
+        if fits(mid).await {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    Some(low)
+}
+
+/// Send `count` ICMP echo probes to `endpoint`, then search for the path MTU
+/// if any were answered over IPv4
+pub async fn check_icmp(endpoint: &str, count: u32, timeout: Duration) -> IcmpResult {
+    let mut result = IcmpResult {
+        endpoint: endpoint.to_string(),
+        sent: count,
+        received: 0,
+        loss_percent: 100.0,
+        min_ms: None,
+        avg_ms: None,
+        max_ms: None,
+        jitter_ms: None,
+        path_mtu: None,
+        error: None,
+    };
+
+    // Probes go out a second apart
+    let deadline = Duration::from_secs(count.into()) + timeout + Duration::from_secs(5);
+    let output = match ping(&ping_args(endpoint, count, timeout, None), deadline).await {
+        Ok(output) => output,
+        Err(e) => {
+            result.error = Some(e);
+            return result;
+        }
+    };
+    let round_trips = parse_round_trips(&output);
+    result.received = (round_trips.len() as u32).min(count);
+    result.loss_percent = f64::from(count - result.received) * 100.0 / f64::from(count);
+    if round_trips.is_empty() {
+        return result;
+    }
+    result.min_ms = round_trips.iter().copied().reduce(f64::min);
+    result.max_ms = round_trips.iter().copied().reduce(f64::max);
+    result.avg_ms = Some(round_trips.iter().sum::<f64>() / round_trips.len() as f64);
+    result.jitter_ms = jitter(&round_trips);
+
+    // ping uses the first address a name resolves to
+    let ipv4 = match endpoint.parse::<IpAddr>() {
+        Ok(ip) => ip.is_ipv4(),
+        Err(_) => tokio::net::lookup_host((endpoint, 0))
+            .await
+            .is_ok_and(|mut addrs| addrs.next().is_some_and(|a| a.is_ipv4())),
+    };
+    if ipv4 {
+        result.path_mtu = path_mtu(endpoint, timeout).await;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_round_trips() {
+        let linux = "PING 20.42.6.200 (20.42.6.200) 56(84) bytes of data.\n\
+                     64 bytes from 20.42.6.200: icmp_seq=1 ttl=115 time=12.3 ms\n\
+                     64 bytes from 20.42.6.200: icmp_seq=3 ttl=115 time=14.1 ms\n\
+                     \n\
+                     --- 20.42.6.200 ping statistics ---\n\
+                     3 packets transmitted, 2 received, 33.3333% packet loss, time 2003ms\n\
+                     rtt min/avg/max/mdev = 12.300/13.200/14.100/0.900 ms\n";
+        assert_eq!(parse_round_trips(linux), [12.3, 14.1]);
+
+        let windows = "Pinging 20.42.6.200 with 32 bytes of data:\r\n\
+                       Reply from 20.42.6.200: bytes=32 time=12ms TTL=115\r\n\
+                       Reply from 20.42.6.200: bytes=32 time<1ms TTL=115\r\n\
+                       Request timed out.\r\n\
+                       Minimum = 0ms, Maximum = 12ms, Average = 6ms\r\n";
+        assert_eq!(parse_round_trips(windows), [12.0, 1.0]);
+
+        let german = "Antwort von 20.42.6.200: Bytes=32 Zeit=9ms TTL=115\n";
+        assert_eq!(parse_round_trips(german), [9.0]);
+    }
+
+    #[test]
+    fn test_jitter() {
+        assert_eq!(jitter(&[10.0, 12.0, 11.0]), Some(1.5));
+        assert_eq!(jitter(&[10.0]), None);
+    }
+}
@@ -0,0 +1,225 @@
+//! Detection of network appliances answering in place of Azure
+//!
+//! Enterprise networks commonly put something between the client and the
+//! service: a TLS inspection proxy that re-signs certificates with its own CA,
+//! a captive portal, or a proxy that redirects to its login page. Each shows up
+//! as a generic TLS error or an odd response; these checks name the appliance
+//! (the certificate issuer or the portal URL) instead.
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::services::responses;
+
+/// Prefix of every interception message; the classifier keys on it
+pub const INTERCEPTED: &str = "Intercepted by network appliance";
+
+/// Issuer organizations of the certificates Azure AI endpoints present
+const AZURE_ISSUERS: &[&str] = &["Microsoft", "DigiCert"];
+
+/// Words a login or captive portal page contains
+const LOGIN_MARKERS: &[&str] = &[
+    "type=\"password\"",
+    "type='password'",
+    "type=password",
+    "login",
+    "log in",
+    "sign in",
+    "sign-in",
+    "captive",
+    "authenticate",
+];
+
+/// Certificate a host presented
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CertificateInfo {
+    pub subject: String,
+    pub issuer: String,
+    /// Organization (O) of the issuer, if it names one
+    pub issuer_organization: Option<String>,
+}
+
+impl CertificateInfo {
+    /// Parse a DER-encoded certificate
+    pub fn from_der(der: &[u8]) -> Result<Self, String> {
+        let (_, cert) = x509_parser::parse_x509_certificate(der)
+            .map_err(|e| format!("Failed to parse certificate: {}", e))?;
+        let issuer_organization = cert
+            .issuer()
+            .iter_organization()
+            .next()
+            .and_then(|o| o.as_str().ok())
+            .map(str::to_string);
+        Ok(Self {
+            subject: cert.subject().to_string(),
+            issuer: cert.issuer().to_string(),
+            issuer_organization,
+        })
+    }
+
+    /// Whether a CA Azure uses issued the certificate
+    pub fn is_azure_issued(&self) -> bool {
+        let issuer = self
+            .issuer_organization
+            .as_deref()
+            .unwrap_or(&self.issuer)
+            .to_lowercase();
+        AZURE_ISSUERS
+            .iter()
+            .any(|name| issuer.contains(&name.to_lowercase()))
+    }
+
+    /// Describe a certificate re-signed by an inspecting appliance, if it was
+    pub fn interception(&self, host: &str) -> Option<String> {
+        if self.is_azure_issued() {
+            return None;
+        }
+        Some(format!(
+            "{}: certificate for {} issued by '{}', not a Microsoft CA (TLS inspection)",
+            INTERCEPTED, host, self.issuer
+        ))
+    }
+}
+
+/// Fetch the certificate `url`'s host presents without validating it, so a
+/// re-signed certificate that fails validation can still be named
+pub async fn peer_certificate(url: &str, timeout: Duration) -> Result<CertificateInfo, String> {
+    let client = crate::network::configure_client(reqwest::Client::builder())
+        .timeout(timeout)
+        .danger_accept_invalid_certs(true)
+        .tls_info(true)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|e| format!("Failed to create client: {}", e))?;
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| format!("TLS connection failed: {}", e))?;
+    let der = response
+        .extensions()
+        .get::<reqwest::tls::TlsInfo>()
+        .and_then(|info| info.peer_certificate())
+        .ok_or("Server certificate not available")?;
+    CertificateInfo::from_der(der)
+}
+
+/// Whether an error message reports a certificate the client rejected
+pub fn is_certificate_error(error: &str) -> bool {
+    let error = error.to_lowercase();
+    ["certificate", "unknownissuer", "invalid peer"]
+        .iter()
+        .any(|needle| error.contains(needle))
+}
+
+/// Whether a body is an HTML login or captive portal page
+pub fn is_login_page(body: &str) -> bool {
+    if responses::html_page(body).is_none() {
+        return false;
+    }
+    let lower = body.to_lowercase();
+    LOGIN_MARKERS.iter().any(|marker| lower.contains(marker))
+}
+
+/// Describe a response that came from an appliance rather than the service:
+/// a proxy asking for authentication, a redirect to another host's HTML page,
+/// or a login page in place of the API response
+pub fn response_interception(
+    requested: &url::Url,
+    received: &url::Url,
+    status: u16,
+    body: &str,
+) -> Option<String> {
+    if status == 407 {
+        return Some(format!(
+            "{}: HTTP 407 proxy authentication required by {}",
+            INTERCEPTED,
+            received.host_str().unwrap_or_default()
+        ));
+    }
+    let redirected = requested.host_str() != received.host_str();
+    if redirected && responses::html_page(body).is_some() {
+        return Some(format!(
+            "{}: redirected to {}",
+            INTERCEPTED,
+            crate::redact::redact_url(received)
+        ));
+    }
+    if is_login_page(body) {
+        return Some(format!(
+            "{}: login page{} served in place of {}",
+            INTERCEPTED,
+            responses::html_page(body).unwrap_or_default(),
+            crate::redact::redact_url(received)
+        ));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PORTAL: &str = "<!DOCTYPE html><html><head><title>Guest Wi-Fi</title></head>\
+        <body><form><input type=\"password\" name=\"pw\"></form></body></html>";
+
+    fn url(s: &str) -> url::Url {
+        url::Url::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_certificate_interception_names_issuer() {
+        let zscaler = CertificateInfo {
+            subject: "CN=eastus.api.cognitive.microsoft.com".to_string(),
+            issuer: "CN=Zscaler Intermediate Root CA, O=Zscaler Inc.".to_string(),
+            issuer_organization: Some("Zscaler Inc.".to_string()),
+        };
+        let message = zscaler
+            .interception("eastus.api.cognitive.microsoft.com")
+            .unwrap();
+        assert!(message.starts_with(INTERCEPTED));
+        assert!(message.contains("'CN=Zscaler Intermediate Root CA, O=Zscaler Inc.'"));
+
+        let azure = CertificateInfo {
+            issuer: "CN=Microsoft Azure RSA TLS Issuing CA 03, O=Microsoft Corporation".to_string(),
+            issuer_organization: Some("Microsoft Corporation".to_string()),
+            ..zscaler
+        };
+        assert_eq!(
+            azure.interception("eastus.api.cognitive.microsoft.com"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_response_interception() {
+        let api = url("https://eastus.api.cognitive.microsoft.com/language/:analyze-text");
+
+        let redirected = response_interception(
+            &api,
+            &url("https://portal.corp.local/auth?x=1"),
+            200,
+            PORTAL,
+        );
+        assert_eq!(
+            redirected.as_deref(),
+            Some("Intercepted by network appliance: redirected to https://portal.corp.local/auth?x=1")
+        );
+
+        let inline = response_interception(&api, &api, 200, PORTAL).unwrap();
+        assert!(inline.contains("login page ('Guest Wi-Fi') served in place of"));
+
+        let proxy = response_interception(&api, &api, 407, "").unwrap();
+        assert!(proxy.contains("HTTP 407"));
+
+        assert_eq!(
+            response_interception(&api, &api, 200, r#"{"documents":[]}"#),
+            None
+        );
+        assert_eq!(
+            response_interception(&api, &api, 404, "<html><title>Not Found</title></html>"),
+            None
+        );
+    }
+}
@@ -1,7 +1,38 @@
+pub mod concurrency;
+pub mod doh;
+pub mod enrich;
+pub mod icmp;
+pub mod interception;
+pub mod phases;
+pub mod ports;
+pub mod system;
+pub mod targets;
+pub mod websocket;
+
 use crate::config::Cloud;
+use crate::environment::EnvironmentInfo;
+use crate::services::endpoints::CloudDomains;
 use reqwest::Client;
 use serde::Serialize;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
+use tokio_rustls::rustls::client::danger::{
+    HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier,
+};
+use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use tokio_rustls::rustls::{ClientConfig, DigitallySignedStruct, SignatureScheme};
+
+pub use concurrency::{check_concurrency, ConcurrencyResult};
+pub use doh::{check_doh, DnsComparison, DohResult, DEFAULT_DOH_URL};
+pub use enrich::{enrich_addresses, AddressInfo, ServiceTags};
+pub use icmp::{check_icmp, IcmpResult};
+pub use interception::{CertificateInfo, INTERCEPTED};
+pub use phases::{measure_phases, Phase, PhaseTiming};
+pub use ports::{check_port, PortResult, PortSpec, PortStatus};
+pub use system::{configure_client, CertificateStore, ProxySource, SystemProxy};
+pub use targets::{
+    diagnostic_groups, endpoint_host, parse_endpoints_file, unique_hosts, DiagnosticGroup,
+};
 
 /// DNS resolution result
 #[derive(Debug, Clone, Serialize)]
@@ -20,39 +51,275 @@ pub struct TlsResult {
     pub success: bool,
     pub duration_ms: u64,
     pub error: Option<String>,
+    /// Issuer of the certificate the endpoint presented
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+    /// Set when a network appliance answered in place of the endpoint: a
+    /// re-signed certificate, a redirect to a login page, or a captive portal
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interception: Option<String>,
 }
 
 /// Latency measurement result
 #[derive(Debug, Clone, Serialize)]
 pub struct LatencyResult {
     pub endpoint: String,
+    /// A request on a new connection (cold): DNS, TCP connect, TLS, and the request
     pub latency_ms: u64,
+    /// A second request on the connection the first opened (warm), as most
+    /// of an application's requests are
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warm_latency_ms: Option<u64>,
     pub success: bool,
     pub error: Option<String>,
 }
 
+/// Reachability of a single certificate revocation URL (CRL or OCSP)
+#[derive(Debug, Clone, Serialize)]
+pub struct RevocationUrlResult {
+    pub url: String,
+    /// "crl" or "ocsp"
+    pub kind: String,
+    pub reachable: bool,
+    pub http_status: Option<u16>,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Revocation endpoint check result for a TLS endpoint
+#[derive(Debug, Clone, Serialize)]
+pub struct RevocationResult {
+    pub endpoint: String,
+    pub urls: Vec<RevocationUrlResult>,
+    pub error: Option<String>,
+    /// Why the intermediates' endpoints weren't checked, only the leaf's
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub intermediates_error: Option<String>,
+}
+
+impl RevocationResult {
+    /// Whether every discovered revocation URL was reachable
+    pub fn success(&self) -> bool {
+        self.error.is_none() && self.urls.iter().all(|u| u.reachable)
+    }
+}
+
 /// Complete network diagnostics report
 #[derive(Debug, Clone, Serialize)]
 pub struct NetworkDiagnostics {
     pub dns: Vec<DnsResult>,
     pub tls: Vec<TlsResult>,
     pub latency: Vec<LatencyResult>,
+    pub revocation: Vec<RevocationResult>,
+    /// System DNS compared with a DNS-over-HTTPS resolver (`--doh`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub doh: Vec<DohResult>,
+    /// TCP reachability of extra ports (`--ports`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ports: Vec<PortResult>,
+    /// Simultaneous connections per endpoint (`--concurrency`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub concurrency: Vec<ConcurrencyResult>,
+    /// ICMP echo loss, round trips and path MTU (`--icmp`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub icmp: Vec<IcmpResult>,
+    /// Reverse DNS, ASN and Azure region of each resolved address
+    /// (`--enrich`, `--service-tags`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub addresses: Vec<AddressInfo>,
+    /// Client environment captured alongside the diagnostics
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub environment: Option<EnvironmentInfo>,
+    /// Regions or endpoint lists the results are reported under, when there
+    /// is more than one target
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<DiagnosticGroup>,
+}
+
+impl NetworkDiagnostics {
+    /// The results for the hosts `keep` accepts
+    fn filtered(&self, keep: impl Fn(&str) -> bool) -> NetworkDiagnostics {
+        fn select<T: Clone>(results: &[T], host: impl Fn(&T) -> bool) -> Vec<T> {
+            results.iter().filter(|r| host(r)).cloned().collect()
+        }
+        NetworkDiagnostics {
+            dns: select(&self.dns, |r| keep(&r.hostname)),
+            tls: select(&self.tls, |r| keep(&r.endpoint)),
+            latency: select(&self.latency, |r| keep(&r.endpoint)),
+            revocation: select(&self.revocation, |r| keep(&r.endpoint)),
+            doh: select(&self.doh, |r| keep(&r.hostname)),
+            ports: select(&self.ports, |r| keep(&r.endpoint)),
+            concurrency: select(&self.concurrency, |r| keep(&r.endpoint)),
+            icmp: select(&self.icmp, |r| keep(&r.endpoint)),
+            addresses: select(&self.addresses, |r| keep(&r.hostname)),
+            environment: None,
+            groups: Vec::new(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.dns.is_empty()
+            && self.tls.is_empty()
+            && self.latency.is_empty()
+            && self.revocation.is_empty()
+            && self.doh.is_empty()
+            && self.ports.is_empty()
+            && self.concurrency.is_empty()
+            && self.icmp.is_empty()
+            && self.addresses.is_empty()
+    }
+
+    /// Outcome of each check that ran for `host`, in report order
+    pub fn host_matrix(&self, host: &str) -> Vec<(&'static str, CheckStatus)> {
+        fn status<T>(
+            results: &[T],
+            is_host: impl Fn(&T) -> bool,
+            outcome: impl Fn(&T) -> CheckStatus,
+        ) -> CheckStatus {
+            results
+                .iter()
+                .filter(|r| is_host(r))
+                .map(outcome)
+                .max()
+                .unwrap_or(CheckStatus::NotRun)
+        }
+        let pass = |ok: bool| {
+            if ok {
+                CheckStatus::Ok
+            } else {
+                CheckStatus::Fail
+            }
+        };
+        let mut row = Vec::new();
+        if !self.dns.is_empty() {
+            let cell = status(&self.dns, |r| r.hostname == host, |r| pass(r.resolved));
+            row.push(("DNS", cell));
+        }
+        if !self.doh.is_empty() {
+            let cell = status(
+                &self.doh,
+                |r| r.hostname == host,
+                |r| match r.comparison {
+                    DnsComparison::Match => CheckStatus::Ok,
+                    DnsComparison::DohFailed => CheckStatus::Fail,
+                    _ => CheckStatus::Warn,
+                },
+            );
+            row.push(("DoH", cell));
+        }
+        if !self.addresses.is_empty() {
+            let cell = status(
+                &self.addresses,
+                |r| r.hostname == host,
+                |r| {
+                    if r.region_mismatch {
+                        CheckStatus::Warn
+                    } else {
+                        CheckStatus::Ok
+                    }
+                },
+            );
+            row.push(("Region", cell));
+        }
+        if !self.tls.is_empty() {
+            let cell = status(&self.tls, |r| r.endpoint == host, |r| pass(r.success));
+            row.push(("TLS", cell));
+        }
+        if !self.ports.is_empty() {
+            let cell = status(
+                &self.ports,
+                |r| r.endpoint == host,
+                |r| pass(r.status == PortStatus::Open),
+            );
+            row.push(("Ports", cell));
+        }
+        if !self.concurrency.is_empty() {
+            let cell = status(
+                &self.concurrency,
+                |r| r.endpoint == host,
+                |r| match (r.failed > 0, r.collapsed) {
+                    (true, _) => CheckStatus::Fail,
+                    (false, true) => CheckStatus::Warn,
+                    (false, false) => CheckStatus::Ok,
+                },
+            );
+            row.push(("Concurrency", cell));
+        }
+        if !self.latency.is_empty() {
+            let cell = status(&self.latency, |r| r.endpoint == host, |r| pass(r.success));
+            row.push(("Latency", cell));
+        }
+        if !self.icmp.is_empty() {
+            let cell = status(
+                &self.icmp,
+                |r| r.endpoint == host,
+                |r| match (&r.error, r.success()) {
+                    (Some(_), _) => CheckStatus::Fail,
+                    (None, true) => CheckStatus::Ok,
+                    (None, false) => CheckStatus::Warn,
+                },
+            );
+            row.push(("ICMP", cell));
+        }
+        if !self.revocation.is_empty() {
+            let cell = status(
+                &self.revocation,
+                |r| r.endpoint == host,
+                |r| match (r.success(), &r.intermediates_error) {
+                    (false, _) => CheckStatus::Fail,
+                    (true, Some(_)) => CheckStatus::Warn,
+                    (true, None) => CheckStatus::Ok,
+                },
+            );
+            row.push(("Revocation", cell));
+        }
+        row
+    }
+}
+
+/// Outcome of one kind of check for one host, in the summary matrix;
+/// ordered so the worst of several results wins
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CheckStatus {
+    NotRun,
+    Ok,
+    Warn,
+    Fail,
+}
+
+impl CheckStatus {
+    fn label(self) -> &'static str {
+        match self {
+            CheckStatus::NotRun => "-",
+            CheckStatus::Ok => "OK",
+            CheckStatus::Warn => "WARN",
+            CheckStatus::Fail => "FAIL",
+        }
+    }
 }
 
 /// Get common Azure AI Services endpoints for a region
 pub fn get_endpoints_for_region(region: &str, cloud: Cloud) -> Vec<String> {
-    match cloud {
-        Cloud::Global => vec![
-            format!("{}.api.cognitive.microsoft.com", region),
-            "api.cognitive.microsofttranslator.com".to_string(),
-            format!("{}.cognitiveservices.azure.com", region),
-        ],
-        Cloud::China => vec![
-            format!("{}.api.cognitive.azure.cn", region),
-            "api.translator.azure.cn".to_string(),
-            format!("{}.cognitiveservices.azure.cn", region),
-        ],
-    }
+    let domains = CloudDomains::of(cloud);
+    vec![
+        format!("{}.{}", region, domains.cognitive),
+        domains.translator.to_string(),
+        format!("{}.{}", region, domains.custom_subdomain),
+    ]
+}
+
+/// Default service used to discover the public egress IP (returns the IP as plain text)
+pub const DEFAULT_IP_ECHO_URL: &str = "https://api.ipify.org";
+
+/// Detect this machine's public (egress) IP address as seen from the internet,
+/// using an echo endpoint that returns the caller's IP as plain text
+pub async fn detect_public_ip(echo_url: &str, timeout: Duration) -> Option<std::net::IpAddr> {
+    let client = configure_client(Client::builder())
+        .timeout(timeout)
+        .build()
+        .ok()?;
+    let body = client.get(echo_url).send().await.ok()?.text().await.ok()?;
+    body.trim().parse().ok()
 }
 
 /// Perform DNS resolution check
@@ -81,120 +348,549 @@ pub async fn check_dns(hostname: &str) -> DnsResult {
     }
 }
 
-/// Perform TLS handshake check
+/// Clients the checks of a run share, so each is built once rather than per
+/// host; a client that couldn't be built fails the checks that need it
+#[derive(Clone)]
+pub struct DiagnosticClients {
+    /// Records the peer certificate; for the TLS and revocation checks
+    tls: Result<Client, String>,
+    latency: Result<Client, String>,
+}
+
+impl DiagnosticClients {
+    pub fn new() -> Self {
+        Self {
+            tls: tls_client(),
+            latency: latency_client(),
+        }
+    }
+}
+
+impl Default for DiagnosticClients {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn tls_client() -> Result<Client, String> {
+    configure_client(Client::builder())
+        .timeout(Duration::from_secs(10))
+        .tls_info(true)
+        .build()
+        .map_err(|e| format!("Failed to create client: {}", e))
+}
+
+fn latency_client() -> Result<Client, String> {
+    configure_client(Client::builder())
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| format!("Failed to create client: {}", e))
+}
+
+/// Perform TLS handshake check, naming the appliance if the certificate or
+/// the response came from one
 pub async fn check_tls(endpoint: &str) -> TlsResult {
+    tls_check(&tls_client(), endpoint).await
+}
+
+async fn tls_check(client: &Result<Client, String>, endpoint: &str) -> TlsResult {
     let start = Instant::now();
     let url = format!("https://{}", endpoint);
+    let mut result = TlsResult {
+        endpoint: endpoint.to_string(),
+        success: false,
+        duration_ms: 0,
+        error: None,
+        issuer: None,
+        interception: None,
+    };
 
-    let client = match Client::builder().timeout(Duration::from_secs(10)).build() {
+    let client = match client {
         Ok(c) => c,
         Err(e) => {
-            return TlsResult {
-                endpoint: endpoint.to_string(),
-                success: false,
-                duration_ms: start.elapsed().as_millis() as u64,
-                error: Some(format!("Failed to create client: {}", e)),
-            }
+            result.error = Some(e.clone());
+            return result;
         }
     };
 
     match client.get(&url).send().await {
-        Ok(_) => TlsResult {
-            endpoint: endpoint.to_string(),
-            success: true,
-            duration_ms: start.elapsed().as_millis() as u64,
-            error: None,
-        },
+        Ok(response) => {
+            result.duration_ms = start.elapsed().as_millis() as u64;
+            result.success = true;
+            let certificate = response
+                .extensions()
+                .get::<reqwest::tls::TlsInfo>()
+                .and_then(|info| info.peer_certificate())
+                .and_then(|der| CertificateInfo::from_der(der).ok());
+            let received = response.url().clone();
+            let status = response.status().as_u16();
+            let body = response.text().await.unwrap_or_default();
+
+            result.interception = certificate
+                .as_ref()
+                .and_then(|c| c.interception(endpoint))
+                .or_else(|| {
+                    let requested = url::Url::parse(&url).ok()?;
+                    interception::response_interception(&requested, &received, status, &body)
+                });
+            result.issuer = certificate.map(|c| c.issuer);
+        }
         Err(e) => {
+            result.duration_ms = start.elapsed().as_millis() as u64;
             // Check if it's a TLS-specific error
-            let error_msg = if e.is_connect() {
+            result.error = Some(if e.is_connect() {
                 format!("Connection failed: {}", e)
             } else if e.is_timeout() {
                 "Connection timed out".to_string()
             } else {
                 e.to_string()
-            };
+            });
 
-            TlsResult {
-                endpoint: endpoint.to_string(),
-                success: false,
-                duration_ms: start.elapsed().as_millis() as u64,
-                error: Some(error_msg),
+            // A rejected certificate may be an inspecting appliance's: fetch it
+            // again without validation to name its issuer
+            let cause = crate::services::classify::request_error("", &e);
+            if interception::is_certificate_error(&cause) {
+                if let Ok(certificate) =
+                    interception::peer_certificate(&url, Duration::from_secs(10)).await
+                {
+                    result.interception = certificate.interception(endpoint);
+                    result.issuer = Some(certificate.issuer);
+                }
             }
         }
     }
+
+    if let Some(interception) = &result.interception {
+        result.success = false;
+        result.error = Some(interception.clone());
+    }
+    result
 }
 
 /// Measure latency to an endpoint
 pub async fn measure_latency(endpoint: &str) -> LatencyResult {
+    latency_check(&latency_client(), endpoint).await
+}
+
+async fn latency_check(client: &Result<Client, String>, endpoint: &str) -> LatencyResult {
     let start = Instant::now();
     let url = format!("https://{}", endpoint);
 
-    let client = match Client::builder().timeout(Duration::from_secs(10)).build() {
+    let client = match client {
         Ok(c) => c,
         Err(e) => {
             return LatencyResult {
                 endpoint: endpoint.to_string(),
                 latency_ms: 0,
+                warm_latency_ms: None,
                 success: false,
-                error: Some(format!("Failed to create client: {}", e)),
+                error: Some(e.clone()),
             }
         }
     };
 
     match client.head(&url).send().await {
-        Ok(_) => LatencyResult {
-            endpoint: endpoint.to_string(),
-            latency_ms: start.elapsed().as_millis() as u64,
-            success: true,
-            error: None,
-        },
+        Ok(_) => {
+            let latency_ms = start.elapsed().as_millis() as u64;
+            // The first response returned its connection to the client's pool
+            let start = Instant::now();
+            let warm_latency_ms = client
+                .head(&url)
+                .send()
+                .await
+                .ok()
+                .map(|_| start.elapsed().as_millis() as u64);
+            LatencyResult {
+                endpoint: endpoint.to_string(),
+                latency_ms,
+                warm_latency_ms,
+                success: true,
+                error: None,
+            }
+        }
         Err(e) => LatencyResult {
             endpoint: endpoint.to_string(),
             latency_ms: start.elapsed().as_millis() as u64,
+            warm_latency_ms: None,
             success: false,
             error: Some(e.to_string()),
         },
     }
 }
 
-/// Run full network diagnostics
+/// Extract CRL distribution point and OCSP responder URLs from a DER-encoded certificate
+pub fn extract_revocation_urls(der: &[u8]) -> Result<Vec<(String, String)>, String> {
+    use x509_parser::extensions::{DistributionPointName, GeneralName, ParsedExtension};
+
+    let (_, cert) = x509_parser::parse_x509_certificate(der)
+        .map_err(|e| format!("Failed to parse certificate: {}", e))?;
+
+    let mut urls = Vec::new();
+    for ext in cert.extensions() {
+        match ext.parsed_extension() {
+            ParsedExtension::CRLDistributionPoints(points) => {
+                for point in points.iter() {
+                    if let Some(DistributionPointName::FullName(names)) = &point.distribution_point
+                    {
+                        for name in names {
+                            if let GeneralName::URI(uri) = name {
+                                urls.push(("crl".to_string(), uri.to_string()));
+                            }
+                        }
+                    }
+                }
+            }
+            ParsedExtension::AuthorityInfoAccess(aia) => {
+                for desc in &aia.accessdescs {
+                    // id-ad-ocsp (1.3.6.1.5.5.7.48.1)
+                    if desc.access_method.to_id_string() == "1.3.6.1.5.5.7.48.1" {
+                        if let GeneralName::URI(uri) = &desc.access_location {
+                            urls.push(("ocsp".to_string(), uri.to_string()));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(urls)
+}
+
+/// CRL and OCSP URLs of every certificate in a served chain, leaf first, each
+/// URL once. Clients check the intermediates too, so a blocked intermediate
+/// CRL fails validation as surely as the leaf's; intermediates that can't be
+/// parsed are left out.
+fn chain_revocation_urls(chain: &[Vec<u8>]) -> Result<Vec<(String, String)>, String> {
+    let Some((leaf, intermediates)) = chain.split_first() else {
+        return Ok(Vec::new());
+    };
+    let mut urls = extract_revocation_urls(leaf)?;
+    for der in intermediates {
+        for url in extract_revocation_urls(der).unwrap_or_default() {
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+    }
+    Ok(urls)
+}
+
+/// Accepts any certificate chain. Only used to read the chain a server (or a
+/// TLS-inspecting proxy) serves: the request made through reqwest has already
+/// validated it, and nothing is sent over this connection.
+#[derive(Debug)]
+struct CollectChain(Arc<tokio_rustls::rustls::crypto::CryptoProvider>);
+
+impl ServerCertVerifier for CollectChain {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, tokio_rustls::rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        let algorithms = &self.0.signature_verification_algorithms;
+        tokio_rustls::rustls::crypto::verify_tls12_signature(message, cert, dss, algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        let algorithms = &self.0.signature_verification_algorithms;
+        tokio_rustls::rustls::crypto::verify_tls13_signature(message, cert, dss, algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+/// Certificates the server sends after its own, from a handshake of our own
+/// since reqwest only exposes the leaf. The chain isn't verified here, so a
+/// TLS-inspecting proxy's chain is collected as well as a server's.
+async fn served_intermediates(endpoint: &str) -> Result<Vec<Vec<u8>>, String> {
+    let url = url::Url::parse(&format!("https://{}", endpoint)).map_err(|e| e.to_string())?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| "no host".to_string())?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+    let port = url.port_or_known_default().unwrap_or(443);
+    let name = ServerName::try_from(host.clone()).map_err(|e| e.to_string())?;
+    let provider = Arc::new(tokio_rustls::rustls::crypto::ring::default_provider());
+    let config = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(CollectChain(provider)))
+        .with_no_client_auth();
+    let handshake = async {
+        let stream = websocket::open_stream(true, &host, port).await?;
+        tokio_rustls::TlsConnector::from(Arc::new(config))
+            .connect(name, stream)
+            .await
+    };
+    let tls = tokio::time::timeout(Duration::from_secs(10), handshake)
+        .await
+        .map_err(|_| "TLS handshake timed out".to_string())?
+        .map_err(|e| e.to_string())?;
+    let (_, connection) = tls.get_ref();
+    Ok(connection
+        .peer_certificates()
+        .unwrap_or_default()
+        .iter()
+        .skip(1)
+        .map(|certificate| certificate.to_vec())
+        .collect())
+}
+
+/// Check that the CRL/OCSP endpoints referenced by an endpoint's certificate chain are reachable
+pub async fn check_revocation(endpoint: &str) -> RevocationResult {
+    revocation_check(&tls_client(), endpoint).await
+}
+
+async fn revocation_check(client: &Result<Client, String>, endpoint: &str) -> RevocationResult {
+    let url = format!("https://{}", endpoint);
+
+    let failed = |error: String| RevocationResult {
+        endpoint: endpoint.to_string(),
+        urls: vec![],
+        error: Some(error),
+        intermediates_error: None,
+    };
+
+    let client = match client {
+        Ok(c) => c,
+        Err(e) => return failed(e.clone()),
+    };
+
+    let response = match client.get(&url).send().await {
+        Ok(r) => r,
+        Err(e) => return failed(format!("TLS connection failed: {}", e)),
+    };
+
+    let der = match response
+        .extensions()
+        .get::<reqwest::tls::TlsInfo>()
+        .and_then(|info| info.peer_certificate())
+    {
+        Some(der) => der.to_vec(),
+        None => return failed("Server certificate not available".to_string()),
+    };
+
+    let mut chain = vec![der];
+    let intermediates_error = match served_intermediates(endpoint).await {
+        Ok(intermediates) => {
+            chain.extend(intermediates);
+            None
+        }
+        Err(e) => Some(e),
+    };
+    let revocation_urls = match chain_revocation_urls(&chain) {
+        Ok(urls) => urls,
+        Err(e) => return failed(e),
+    };
+
+    let mut urls = Vec::new();
+    for (kind, revocation_url) in revocation_urls {
+        let start = Instant::now();
+        // Revocation endpoints are plain HTTP; any HTTP response proves reachability
+        let result = match client.get(&revocation_url).send().await {
+            Ok(resp) => RevocationUrlResult {
+                url: revocation_url,
+                kind,
+                reachable: true,
+                http_status: Some(resp.status().as_u16()),
+                duration_ms: start.elapsed().as_millis() as u64,
+                error: None,
+            },
+            Err(e) => RevocationUrlResult {
+                url: revocation_url,
+                kind,
+                reachable: false,
+                http_status: None,
+                duration_ms: start.elapsed().as_millis() as u64,
+                error: Some(if e.is_timeout() {
+                    "Connection timed out".to_string()
+                } else {
+                    e.to_string()
+                }),
+            },
+        };
+        urls.push(result);
+    }
+
+    RevocationResult {
+        endpoint: endpoint.to_string(),
+        urls,
+        error: None,
+        intermediates_error,
+    }
+}
+
+/// Run full network diagnostics on `endpoints`, checking up to `parallelism`
+/// hosts at once (1 checks them one after another). A host's own checks run
+/// in order; results keep the order of `endpoints`.
 pub async fn run_diagnostics(
-    region: &str,
-    cloud: Cloud,
+    endpoints: &[String],
     check_dns_flag: bool,
     check_tls_flag: bool,
     check_latency_flag: bool,
-    custom_endpoint: Option<&str>,
+    check_revocation_flag: bool,
+    parallelism: usize,
 ) -> NetworkDiagnostics {
-    let endpoints = if let Some(endpoint) = custom_endpoint {
-        vec![endpoint.to_string()]
-    } else {
-        get_endpoints_for_region(region, cloud)
-    };
+    let clients = DiagnosticClients::new();
+    let permits = tokio::sync::Semaphore::new(parallelism.max(1));
+
+    let checks = endpoints.iter().map(|endpoint| {
+        let (clients, permits) = (&clients, &permits);
+        async move {
+            // The semaphore is never closed, so acquiring only waits
+            let _permit = permits.acquire().await.ok();
+            let dns = if check_dns_flag {
+                Some(check_dns(endpoint).await)
+            } else {
+                None
+            };
+            let tls = if check_tls_flag {
+                Some(tls_check(&clients.tls, endpoint).await)
+            } else {
+                None
+            };
+            let latency = if check_latency_flag {
+                Some(latency_check(&clients.latency, endpoint).await)
+            } else {
+                None
+            };
+            let revocation = if check_revocation_flag {
+                Some(revocation_check(&clients.tls, endpoint).await)
+            } else {
+                None
+            };
+            (dns, tls, latency, revocation)
+        }
+    });
+    let results = futures_util::future::join_all(checks).await;
 
     let mut dns_results = Vec::new();
     let mut tls_results = Vec::new();
     let mut latency_results = Vec::new();
-
-    for endpoint in &endpoints {
-        if check_dns_flag {
-            dns_results.push(check_dns(endpoint).await);
-        }
-
-        if check_tls_flag {
-            tls_results.push(check_tls(endpoint).await);
-        }
-
-        if check_latency_flag {
-            latency_results.push(measure_latency(endpoint).await);
-        }
+    let mut revocation_results = Vec::new();
+    for (dns, tls, latency, revocation) in results {
+        dns_results.extend(dns);
+        tls_results.extend(tls);
+        latency_results.extend(latency);
+        revocation_results.extend(revocation);
     }
 
     NetworkDiagnostics {
         dns: dns_results,
         tls: tls_results,
         latency: latency_results,
+        revocation: revocation_results,
+        doh: vec![],
+        ports: vec![],
+        concurrency: vec![],
+        icmp: vec![],
+        addresses: vec![],
+        environment: None,
+        groups: vec![],
+    }
+}
+
+/// Compare system DNS with the DoH resolver at `resolver` for `endpoints`,
+/// reusing the system answers already in `diagnostics.dns`
+pub async fn run_doh_comparison(
+    diagnostics: &mut NetworkDiagnostics,
+    endpoints: &[String],
+    resolver: &str,
+) {
+    for endpoint in endpoints {
+        let system = match diagnostics.dns.iter().find(|r| &r.hostname == endpoint) {
+            Some(result) => result.clone(),
+            None => check_dns(endpoint).await,
+        };
+        let result = check_doh(endpoint, &system, resolver, Duration::from_secs(10)).await;
+        diagnostics.doh.push(result);
+    }
+}
+
+/// Look up the addresses `endpoints` resolved to in `service_tags`, and their
+/// reverse DNS and ASN through `resolver`, reusing the system answers already
+/// in `diagnostics.dns`
+pub async fn run_address_enrichment(
+    diagnostics: &mut NetworkDiagnostics,
+    endpoints: &[String],
+    cloud: Cloud,
+    service_tags: Option<&ServiceTags>,
+    resolver: Option<&str>,
+) {
+    let mut dns = Vec::new();
+    for endpoint in endpoints {
+        dns.push(
+            match diagnostics.dns.iter().find(|r| &r.hostname == endpoint) {
+                Some(result) => result.clone(),
+                None => check_dns(endpoint).await,
+            },
+        );
+    }
+    diagnostics.addresses =
+        enrich_addresses(&dns, cloud, service_tags, resolver, Duration::from_secs(10)).await;
+}
+
+/// Send `count` ICMP echo probes to each of `endpoints`, all hosts at once
+pub async fn run_icmp_checks(
+    diagnostics: &mut NetworkDiagnostics,
+    endpoints: &[String],
+    count: u32,
+    timeout: Duration,
+) {
+    let checks = endpoints
+        .iter()
+        .map(|endpoint| check_icmp(endpoint, count, timeout));
+    diagnostics.icmp = futures_util::future::join_all(checks).await;
+}
+
+/// Check the TCP ports selected by `specs` on `endpoints`, all at once
+pub async fn run_port_checks(
+    diagnostics: &mut NetworkDiagnostics,
+    endpoints: &[String],
+    specs: &[PortSpec],
+    timeout: Duration,
+) {
+    let targets = ports::port_targets(specs, endpoints);
+    let checks = targets
+        .iter()
+        .map(|(host, port)| check_port(host, *port, timeout));
+    diagnostics.ports = futures_util::future::join_all(checks).await;
+}
+
+/// Open `connections` simultaneous connections to each of `endpoints`, one
+/// host at a time
+pub async fn run_concurrency_checks(
+    diagnostics: &mut NetworkDiagnostics,
+    endpoints: &[String],
+    connections: usize,
+    timeout: Duration,
+) {
+    for endpoint in endpoints {
+        let result = check_concurrency(endpoint, connections, timeout).await;
+        diagnostics.concurrency.push(result);
     }
 }
 
@@ -207,6 +903,111 @@ pub fn format_diagnostics(diagnostics: &NetworkDiagnostics, use_colors: bool) ->
     output.push_str("\nNetwork Diagnostics\n");
     output.push_str("==================\n\n");
 
+    if let Some(environment) = &diagnostics.environment {
+        output.push_str("Environment:\n");
+        for line in environment.summary_lines() {
+            output.push_str(&format!("  {}\n", line));
+        }
+        output.push('\n');
+    }
+
+    if diagnostics.groups.is_empty() {
+        output.push_str(&format_checks(diagnostics, use_colors));
+        return output;
+    }
+
+    for group in &diagnostics.groups {
+        let heading = format!("{} ({} hosts)", group.name, group.hosts.len());
+        if use_colors {
+            output.push_str(&format!("{}\n", style(&heading).bold()));
+        } else {
+            output.push_str(&format!("{}\n", heading));
+        }
+        output.push_str(&format!("{}\n\n", "-".repeat(heading.chars().count())));
+        let results = diagnostics.filtered(|host| group.hosts.iter().any(|h| h == host));
+        output.push_str(&format_checks(&results, use_colors));
+    }
+    // HOST:PORT targets outside every group
+    let hosts = unique_hosts(&diagnostics.groups);
+    let others = diagnostics.filtered(|host| !hosts.iter().any(|h| h == host));
+    if !others.is_empty() {
+        output.push_str("Other hosts\n-----------\n\n");
+        output.push_str(&format_checks(&others, use_colors));
+    }
+    output.push_str(&format_matrix(diagnostics, use_colors));
+    output
+}
+
+/// Summary matrix: one row per host, one column per check that ran
+fn format_matrix(diagnostics: &NetworkDiagnostics, use_colors: bool) -> String {
+    use console::style;
+
+    let hosts = unique_hosts(&diagnostics.groups);
+    let group_of = |host: &str| {
+        diagnostics
+            .groups
+            .iter()
+            .find(|g| g.hosts.iter().any(|h| h == host))
+            .map(|g| g.name.as_str())
+            .unwrap_or_default()
+    };
+    let group_width = diagnostics
+        .groups
+        .iter()
+        .map(|g| g.name.len())
+        .chain(["Group".len()])
+        .max()
+        .unwrap_or_default();
+    let host_width = hosts
+        .iter()
+        .map(|h| h.len())
+        .chain(["Host".len()])
+        .max()
+        .unwrap_or_default();
+
+    let mut output = String::from("Summary:\n");
+    let Some(first) = hosts.first() else {
+        return output;
+    };
+    let columns: Vec<&str> = diagnostics
+        .host_matrix(first)
+        .iter()
+        .map(|(name, _)| *name)
+        .collect();
+    let mut header = format!("  {:<group_width$}  {:<host_width$}", "Group", "Host");
+    for column in &columns {
+        header.push_str(&format!(
+            "  {:<width$}",
+            column,
+            width = column.len().max(4)
+        ));
+    }
+    output.push_str(header.trim_end());
+    output.push('\n');
+    for host in &hosts {
+        let mut line = format!("  {:<group_width$}  {:<host_width$}", group_of(host), host);
+        for (column, status) in diagnostics.host_matrix(host) {
+            let cell = format!("{:<width$}", status.label(), width = column.len().max(4));
+            let cell = match (status, use_colors) {
+                (CheckStatus::Ok, true) => style(cell).green().to_string(),
+                (CheckStatus::Warn, true) => style(cell).yellow().to_string(),
+                (CheckStatus::Fail, true) => style(cell).red().to_string(),
+                _ => cell,
+            };
+            line.push_str(&format!("  {}", cell));
+        }
+        output.push_str(line.trim_end());
+        output.push('\n');
+    }
+    output
+}
+
+/// The per-check sections of the report
+fn format_checks(diagnostics: &NetworkDiagnostics, use_colors: bool) -> String {
+    use console::style;
+
+    let mut output = String::new();
+
     if !diagnostics.dns.is_empty() {
         output.push_str("DNS Resolution:\n");
         for result in &diagnostics.dns {
@@ -246,6 +1047,100 @@ pub fn format_diagnostics(diagnostics: &NetworkDiagnostics, use_colors: bool) ->
         output.push('\n');
     }
 
+    if !diagnostics.addresses.is_empty() {
+        output.push_str("Resolved Addresses:\n");
+        let mut hosts: Vec<&str> = Vec::new();
+        for info in &diagnostics.addresses {
+            if !hosts.contains(&info.hostname.as_str()) {
+                hosts.push(&info.hostname);
+            }
+        }
+        for host in hosts {
+            let infos: Vec<&AddressInfo> = diagnostics
+                .addresses
+                .iter()
+                .filter(|info| info.hostname == host)
+                .collect();
+            let mismatch = infos.iter().any(|info| info.region_mismatch);
+            let status = match (mismatch, use_colors) {
+                (false, true) => style("\u{2713}").green().to_string(),
+                (false, false) => "[OK]".to_string(),
+                (true, true) => style("!").yellow().to_string(),
+                (true, false) => "[WARN]".to_string(),
+            };
+            output.push_str(&format!("  {} {}\n", status, host));
+            for info in infos {
+                let line = format!("{}  {}", info.address, info.describe());
+                let line = line.trim_end();
+                if use_colors && info.region_mismatch {
+                    output.push_str(&format!("    {}\n", style(line).yellow()));
+                } else {
+                    output.push_str(&format!("    {}\n", line));
+                }
+                for error in &info.errors {
+                    if use_colors {
+                        output.push_str(&format!("      {}\n", style(error).dim()));
+                    } else {
+                        output.push_str(&format!("      Error: {}\n", error));
+                    }
+                }
+            }
+        }
+        output.push('\n');
+    }
+
+    if let Some(first) = diagnostics.doh.first() {
+        let resolver = url::Url::parse(&first.resolver)
+            .ok()
+            .and_then(|u| u.host_str().map(str::to_string))
+            .unwrap_or_else(|| first.resolver.clone());
+        output.push_str(&format!("DNS over HTTPS ({}):\n", resolver));
+        for result in &diagnostics.doh {
+            let status = match (result.comparison, use_colors) {
+                (DnsComparison::Match, true) => style("\u{2713}").green().to_string(),
+                (DnsComparison::Match, false) => "[OK]".to_string(),
+                (DnsComparison::DohFailed, true) => style("\u{2717}").red().to_string(),
+                (DnsComparison::DohFailed, false) => "[FAIL]".to_string(),
+                (_, true) => style("!").yellow().to_string(),
+                (_, false) => "[WARN]".to_string(),
+            };
+            output.push_str(&format!(
+                "  {} {} ({}ms)\n",
+                status, result.hostname, result.duration_ms
+            ));
+
+            let note = match &result.error {
+                Some(error) => format!("{}: {}", result.comparison.describe(), error),
+                None => result.comparison.describe().to_string(),
+            };
+            if !use_colors {
+                output.push_str(&format!("    {}\n", note));
+            } else if result.comparison.is_mismatch() {
+                output.push_str(&format!("    {}\n", style(note).yellow()));
+            } else if result.error.is_some() {
+                output.push_str(&format!("    {}\n", style(note).red()));
+            } else {
+                output.push_str(&format!("    {}\n", style(note).dim()));
+            }
+
+            if result.comparison != DnsComparison::DohFailed {
+                let list = |addresses: &[String]| {
+                    if addresses.is_empty() {
+                        "(none)".to_string()
+                    } else {
+                        addresses.join(", ")
+                    }
+                };
+                output.push_str(&format!("    system: {}\n", list(&result.system_addresses)));
+                output.push_str(&format!("    DoH:    {}\n", list(&result.addresses)));
+                if !result.cnames.is_empty() {
+                    output.push_str(&format!("    CNAME:  {}\n", result.cnames.join(" -> ")));
+                }
+            }
+        }
+        output.push('\n');
+    }
+
     if !diagnostics.tls.is_empty() {
         output.push_str("TLS Handshake:\n");
         for result in &diagnostics.tls {
@@ -279,6 +1174,88 @@ pub fn format_diagnostics(diagnostics: &NetworkDiagnostics, use_colors: bool) ->
         output.push('\n');
     }
 
+    if !diagnostics.ports.is_empty() {
+        output.push_str("Port Reachability:\n");
+        for result in &diagnostics.ports {
+            let open = result.status == PortStatus::Open;
+            let status = if open {
+                if use_colors {
+                    style("\u{2713}").green().to_string()
+                } else {
+                    "[OK]".to_string()
+                }
+            } else if use_colors {
+                style("\u{2717}").red().to_string()
+            } else {
+                "[FAIL]".to_string()
+            };
+
+            output.push_str(&format!(
+                "  {} {}:{} {} ({}ms)\n",
+                status, result.endpoint, result.port, result.status, result.duration_ms
+            ));
+
+            if !open {
+                if let Some(error) = &result.error {
+                    if use_colors {
+                        output.push_str(&format!("    {}\n", style(error).red()));
+                    } else {
+                        output.push_str(&format!("    Error: {}\n", error));
+                    }
+                }
+            }
+        }
+        output.push('\n');
+    }
+
+    if let Some(first) = diagnostics.concurrency.first() {
+        output.push_str(&format!(
+            "Concurrent Connections ({}):\n",
+            first.connections
+        ));
+        for result in &diagnostics.concurrency {
+            let status = match (result.failed > 0, result.collapsed, use_colors) {
+                (false, false, true) => style("\u{2713}").green().to_string(),
+                (false, false, false) => "[OK]".to_string(),
+                (true, _, true) => style("\u{2717}").red().to_string(),
+                (true, _, false) => "[FAIL]".to_string(),
+                (false, true, true) => style("!").yellow().to_string(),
+                (false, true, false) => "[WARN]".to_string(),
+            };
+            let ms = |value: Option<u64>| match value {
+                Some(ms) => format!("{}ms", ms),
+                None => "-".to_string(),
+            };
+            output.push_str(&format!(
+                "  {} {} - {}/{} answered, baseline {}, median {}, max {}\n",
+                status,
+                result.endpoint,
+                result.succeeded,
+                result.connections,
+                ms(result.baseline_ms),
+                ms(result.median_ms),
+                ms(result.max_ms)
+            ));
+
+            if !result.success() {
+                let note = result.describe();
+                if use_colors {
+                    output.push_str(&format!("    {}\n", style(note).yellow()));
+                } else {
+                    output.push_str(&format!("    {}\n", note));
+                }
+                for error in &result.errors {
+                    if use_colors {
+                        output.push_str(&format!("    {}\n", style(error).red()));
+                    } else {
+                        output.push_str(&format!("    Error: {}\n", error));
+                    }
+                }
+            }
+        }
+        output.push('\n');
+    }
+
     if !diagnostics.latency.is_empty() {
         output.push_str("Latency:\n");
         for result in &diagnostics.latency {
@@ -294,10 +1271,16 @@ pub fn format_diagnostics(diagnostics: &NetworkDiagnostics, use_colors: bool) ->
                 "[FAIL]".to_string()
             };
 
-            output.push_str(&format!(
-                "  {} {} - {}ms\n",
-                status, result.endpoint, result.latency_ms
-            ));
+            match result.warm_latency_ms {
+                Some(warm) => output.push_str(&format!(
+                    "  {} {} - {}ms cold, {}ms warm\n",
+                    status, result.endpoint, result.latency_ms, warm
+                )),
+                None => output.push_str(&format!(
+                    "  {} {} - {}ms\n",
+                    status, result.endpoint, result.latency_ms
+                )),
+            }
 
             if !result.success {
                 if let Some(error) = &result.error {
@@ -309,6 +1292,121 @@ pub fn format_diagnostics(diagnostics: &NetworkDiagnostics, use_colors: bool) ->
                 }
             }
         }
+        output.push('\n');
+    }
+
+    if let Some(first) = diagnostics.icmp.first() {
+        output.push_str(&format!("ICMP Echo ({} probes):\n", first.sent));
+        for result in &diagnostics.icmp {
+            let status = match (&result.error, result.success(), use_colors) {
+                (Some(_), _, true) => style("\u{2717}").red().to_string(),
+                (Some(_), _, false) => "[FAIL]".to_string(),
+                (None, true, true) => style("\u{2713}").green().to_string(),
+                (None, true, false) => "[OK]".to_string(),
+                (None, false, true) => style("!").yellow().to_string(),
+                (None, false, false) => "[WARN]".to_string(),
+            };
+            let mut summary = format!("{:.0}% loss", result.loss_percent);
+            if let (Some(min), Some(avg), Some(max)) = (result.min_ms, result.avg_ms, result.max_ms)
+            {
+                summary.push_str(&format!(
+                    ", min {:.1}ms, avg {:.1}ms, max {:.1}ms",
+                    min, avg, max
+                ));
+            }
+            if let Some(jitter) = result.jitter_ms {
+                summary.push_str(&format!(", jitter {:.1}ms", jitter));
+            }
+            if let Some(mtu) = result.path_mtu {
+                summary.push_str(&format!(", path MTU {}", mtu));
+            }
+            output.push_str(&format!("  {} {} - {}\n", status, result.endpoint, summary));
+
+            let note = match &result.error {
+                Some(error) => Some(format!("Error: {}", error)),
+                None if result.received == 0 => Some(
+                    "no replies; ICMP may be blocked, compare with the HTTPS latency".to_string(),
+                ),
+                None if !result.success() => Some("packets lost at the network layer".to_string()),
+                None => None,
+            };
+            if let Some(note) = note {
+                if use_colors {
+                    output.push_str(&format!("    {}\n", style(note).yellow()));
+                } else {
+                    output.push_str(&format!("    {}\n", note));
+                }
+            }
+        }
+        output.push('\n');
+    }
+
+    if !diagnostics.revocation.is_empty() {
+        output.push_str("Certificate Revocation (OCSP/CRL):\n");
+        for result in &diagnostics.revocation {
+            let status = if result.success() {
+                if use_colors {
+                    style("\u{2713}").green().to_string()
+                } else {
+                    "[OK]".to_string()
+                }
+            } else if use_colors {
+                style("\u{2717}").red().to_string()
+            } else {
+                "[FAIL]".to_string()
+            };
+
+            output.push_str(&format!("  {} {}\n", status, result.endpoint));
+
+            if let Some(error) = &result.error {
+                if use_colors {
+                    output.push_str(&format!("    {}\n", style(error).red()));
+                } else {
+                    output.push_str(&format!("    Error: {}\n", error));
+                }
+            }
+
+            if let Some(error) = &result.intermediates_error {
+                let note = format!(
+                    "Intermediate certificates not checked, leaf only: {}",
+                    error
+                );
+                if use_colors {
+                    output.push_str(&format!("    {}\n", style(note).yellow()));
+                } else {
+                    output.push_str(&format!("    {}\n", note));
+                }
+            }
+
+            if result.error.is_none() && result.urls.is_empty() {
+                output.push_str("    No CRL/OCSP endpoints referenced by certificate\n");
+            }
+
+            for url in &result.urls {
+                let line = if url.reachable {
+                    format!(
+                        "{} {} ({}ms)",
+                        url.kind.to_uppercase(),
+                        url.url,
+                        url.duration_ms
+                    )
+                } else {
+                    format!(
+                        "{} {} blocked: {}",
+                        url.kind.to_uppercase(),
+                        url.url,
+                        url.error.as_deref().unwrap_or("unreachable")
+                    )
+                };
+                if !use_colors {
+                    output.push_str(&format!("    {}\n", line));
+                } else if url.reachable {
+                    output.push_str(&format!("    {}\n", style(line).dim()));
+                } else {
+                    output.push_str(&format!("    {}\n", style(line).red()));
+                }
+            }
+        }
     }
 
     output
@@ -325,6 +1423,159 @@ mod tests {
         assert!(endpoints.iter().any(|e| e.contains("microsofttranslator")));
     }
 
+    #[test]
+    fn test_extract_revocation_urls_invalid_der() {
+        assert!(extract_revocation_urls(b"not a certificate").is_err());
+    }
+
+    #[test]
+    fn test_chain_revocation_urls_include_intermediates() {
+        let intermediate = include_bytes!("testdata/lets-encrypt-x3-cross-signed.der").to_vec();
+        let garbage = b"not a certificate".to_vec();
+        assert!(chain_revocation_urls(&[garbage.clone(), intermediate.clone()]).is_err());
+
+        // An unparsable intermediate is skipped, a repeated URL listed once
+        let urls = chain_revocation_urls(&[intermediate.clone(), garbage, intermediate]).unwrap();
+        assert_eq!(urls.len(), 2, "{:?}", urls);
+        assert!(urls.contains(&(
+            "crl".to_string(),
+            "http://crl.identrust.com/DSTROOTCAX3CRL.crl".to_string()
+        )));
+        assert!(urls.contains(&(
+            "ocsp".to_string(),
+            "http://isrg.trustid.ocsp.identrust.com".to_string()
+        )));
+    }
+
+    #[test]
+    fn test_format_diagnostics_revocation_blocked() {
+        let diagnostics = NetworkDiagnostics {
+            dns: vec![],
+            tls: vec![],
+            latency: vec![],
+            revocation: vec![RevocationResult {
+                endpoint: "eastus.api.cognitive.microsoft.com".to_string(),
+                urls: vec![RevocationUrlResult {
+                    url: "http://oneocsp.microsoft.com/ocsp".to_string(),
+                    kind: "ocsp".to_string(),
+                    reachable: false,
+                    http_status: None,
+                    duration_ms: 10,
+                    error: Some("Connection timed out".to_string()),
+                }],
+                error: None,
+                intermediates_error: Some("TLS handshake timed out".to_string()),
+            }],
+            doh: vec![DohResult {
+                hostname: "eastus.api.cognitive.microsoft.com".to_string(),
+                resolver: DEFAULT_DOH_URL.to_string(),
+                addresses: vec!["20.42.6.200".to_string()],
+                cnames: vec![],
+                system_addresses: vec!["10.1.0.4".to_string()],
+                comparison: DnsComparison::SplitHorizon,
+                duration_ms: 30,
+                error: None,
+            }],
+            ports: vec![],
+            concurrency: vec![ConcurrencyResult {
+                endpoint: "eastus.api.cognitive.microsoft.com".to_string(),
+                connections: 50,
+                succeeded: 32,
+                failed: 18,
+                baseline_ms: Some(45),
+                median_ms: Some(60),
+                max_ms: Some(310),
+                collapsed: false,
+                errors: vec!["Connection failed: connection reset".to_string()],
+            }],
+            icmp: vec![],
+            addresses: vec![],
+            environment: None,
+            groups: vec![],
+        };
+
+        let output = format_diagnostics(&diagnostics, false);
+        assert!(output.contains("Concurrent Connections (50):\n  [FAIL] eastus.api.cognitive.microsoft.com - 32/50 answered, baseline 45ms, median 60ms, max 310ms\n    18 of 50 requests failed"));
+        assert!(output.contains("Certificate Revocation"));
+        assert!(output.contains("[FAIL] eastus.api.cognitive.microsoft.com"));
+        assert!(output.contains("OCSP http://oneocsp.microsoft.com/ocsp blocked"));
+        assert!(output.contains(
+            "    Intermediate certificates not checked, leaf only: TLS handshake timed out\n"
+        ));
+        assert!(output.contains("DNS over HTTPS (cloudflare-dns.com):"));
+        assert!(output.contains("[WARN] eastus.api.cognitive.microsoft.com"));
+        assert!(output.contains("    system: 10.1.0.4\n    DoH:    20.42.6.200\n"));
+    }
+
+    #[test]
+    fn test_format_diagnostics_groups_and_matrix() {
+        let dns = |host: &str, resolved: bool| DnsResult {
+            hostname: host.to_string(),
+            resolved,
+            addresses: vec![],
+            duration_ms: 5,
+            error: (!resolved).then(|| "NXDOMAIN".to_string()),
+        };
+        let tls = |host: &str| TlsResult {
+            endpoint: host.to_string(),
+            success: true,
+            duration_ms: 40,
+            error: None,
+            issuer: None,
+            interception: None,
+        };
+        let group = |name: &str, hosts: &[&str]| DiagnosticGroup {
+            name: name.to_string(),
+            hosts: hosts.iter().map(|h| h.to_string()).collect(),
+        };
+        let diagnostics = NetworkDiagnostics {
+            dns: vec![
+                dns("eastus.example.com", true),
+                dns("global.example.com", true),
+                dns("westeurope.example.com", false),
+            ],
+            tls: vec![tls("eastus.example.com"), tls("global.example.com")],
+            latency: vec![],
+            revocation: vec![],
+            doh: vec![],
+            ports: vec![],
+            concurrency: vec![],
+            icmp: vec![],
+            addresses: vec![],
+            environment: None,
+            groups: vec![
+                group("eastus", &["eastus.example.com", "global.example.com"]),
+                group(
+                    "westeurope",
+                    &["westeurope.example.com", "global.example.com"],
+                ),
+            ],
+        };
+
+        let output = format_diagnostics(&diagnostics, false);
+        assert!(output.contains("westeurope (2 hosts)\n--------------------\n"));
+        assert!(output.contains(
+            "Summary:\n\
+             \x20 Group       Host                    DNS   TLS\n\
+             \x20 eastus      eastus.example.com      OK    OK\n\
+             \x20 eastus      global.example.com      OK    OK\n\
+             \x20 westeurope  westeurope.example.com  FAIL  -\n"
+        ));
+        // The shared host is reported under both regions
+        assert_eq!(output.matches("[OK] global.example.com").count(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_run_diagnostics_keeps_host_order() {
+        let hosts = ["localhost", "127.0.0.1", "127.0.0.2"].map(String::from);
+        for parallelism in [1, 3] {
+            let diagnostics = run_diagnostics(&hosts, true, false, false, false, parallelism).await;
+            let resolved: Vec<_> = diagnostics.dns.iter().map(|r| &r.hostname).collect();
+            assert_eq!(resolved, hosts.iter().collect::<Vec<_>>());
+            assert!(diagnostics.tls.is_empty());
+        }
+    }
+
     #[test]
     fn test_get_endpoints_china() {
         let endpoints = get_endpoints_for_region("chinaeast2", Cloud::China);
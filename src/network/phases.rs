@@ -0,0 +1,276 @@
+//! Per-phase timing of a connection (DNS, TCP connect, TLS handshake)
+//!
+//! reqwest reports a slow or failed request as one error, so "connection timed
+//! out" can't say whether the TCP SYN or the response was the slow part. Before
+//! a scenario's first request to a host, the phases of a fresh connection are
+//! measured one by one, each with its own timeout.
+
+use std::fmt;
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+use tokio_rustls::TlsConnector;
+
+/// Proxy variables reqwest honors; a direct probe says nothing about a proxied request
+const PROXY_VARS: &[&str] = &[
+    "HTTPS_PROXY",
+    "https_proxy",
+    "HTTP_PROXY",
+    "http_proxy",
+    "ALL_PROXY",
+    "all_proxy",
+];
+
+/// Roots of the HTTP client: the built-in set and the Windows certificate store
+pub(super) static TLS_CONFIG: LazyLock<Arc<ClientConfig>> = LazyLock::new(|| {
+    let mut roots = RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    roots.add_parsable_certificates(super::system::system_roots().iter().cloned());
+    let provider = Arc::new(tokio_rustls::rustls::crypto::ring::default_provider());
+    let config = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .expect("ring supports the default protocol versions")
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Arc::new(config)
+});
+
+/// A phase of a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    Dns,
+    Connect,
+    Tls,
+    /// Waiting for or reading the response
+    Response,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Phase::Dns => "DNS resolution",
+            Phase::Connect => "TCP connect",
+            Phase::Tls => "TLS handshake",
+            Phase::Response => "response",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// Durations of each phase of a scenario's request. Connection phases come
+/// from a fresh connection to the request's host; the response phases are
+/// those of the scenario's last request.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhaseTiming {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dns_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_ms: Option<u64>,
+    /// Not set for plain HTTP
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_ms: Option<u64>,
+    /// Request sent until the response headers arrived
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttfb_ms: Option<u64>,
+    /// Request sent until the response body was read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_ms: Option<u64>,
+    /// Whether the request went out on a connection an earlier request had
+    /// opened (warm) rather than a new one (cold)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reused_connection: Option<bool>,
+    /// Request time on a new connection: measured for a cold request,
+    /// estimated for a warm one by adding the connection phases
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cold_ms: Option<u64>,
+    /// Request time on an open connection: measured for a warm request,
+    /// estimated for a cold one by leaving out the connection phases
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warm_ms: Option<u64>,
+    /// Phase that failed or timed out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failed_phase: Option<Phase>,
+    /// Time spent measuring the connection phases, which is not part of the
+    /// scenario's own duration
+    #[serde(skip)]
+    pub probe_ms: u64,
+}
+
+impl PhaseTiming {
+    /// Whether a connection phase failed, so the request can't have connected either
+    pub fn connection_failed(&self) -> bool {
+        matches!(
+            self.failed_phase,
+            Some(Phase::Dns | Phase::Connect | Phase::Tls)
+        )
+    }
+
+    /// Time to open a connection (DNS, TCP connect, TLS), when it was measured
+    pub fn setup_ms(&self) -> Option<u64> {
+        Some(self.dns_ms? + self.connect_ms? + self.tls_ms.unwrap_or_default())
+    }
+
+    /// Record the request's total time as cold or warm, and estimate the other
+    pub fn split_latency(&mut self, total_ms: u64, reused_connection: bool) {
+        self.reused_connection = Some(reused_connection);
+        let setup = self.setup_ms().filter(|_| !self.connection_failed());
+        if reused_connection {
+            self.warm_ms = Some(total_ms);
+            self.cold_ms = setup.map(|setup| total_ms + setup);
+        } else {
+            self.cold_ms = Some(total_ms);
+            self.warm_ms = setup.map(|setup| total_ms.saturating_sub(setup));
+        }
+    }
+}
+
+impl fmt::Display for PhaseTiming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let phases: Vec<String> = [
+            ("dns", self.dns_ms),
+            ("connect", self.connect_ms),
+            ("tls", self.tls_ms),
+            ("first byte", self.ttfb_ms),
+            ("total", self.total_ms),
+        ]
+        .into_iter()
+        .filter_map(|(name, ms)| ms.map(|ms| format!("{} {}ms", name, ms)))
+        .collect();
+        write!(f, "{}", phases.join(", "))?;
+        match (self.reused_connection, self.cold_ms, self.warm_ms) {
+            (Some(true), Some(cold), _) => write!(f, " (warm connection; ~{}ms cold)", cold)?,
+            (Some(true), None, _) => write!(f, " (warm connection)")?,
+            (Some(false), _, Some(warm)) => write!(f, " (new connection; ~{}ms warm)", warm)?,
+            (Some(false), _, None) => write!(f, " (new connection)")?,
+            (None, _, _) => {}
+        }
+        if let Some(phase) = self.failed_phase {
+            write!(f, " (failed during {})", phase)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether requests to `scheme` go through a proxy from the environment or
+/// Windows settings
+pub fn proxy_configured(scheme: &str) -> bool {
+    let scheme_var = format!("{}_proxy", scheme);
+    PROXY_VARS
+        .iter()
+        .filter(|name| {
+            let name = name.to_ascii_lowercase();
+            name == "all_proxy" || name == scheme_var
+        })
+        .any(|name| std::env::var(name).is_ok_and(|v| !v.trim().is_empty()))
+        || super::system::proxy_in_effect()
+}
+
+/// Time DNS resolution, TCP connect, and (for https) the TLS handshake to the
+/// host of `url`, stopping at the first phase that fails
+pub async fn measure_phases(
+    url: &url::Url,
+    connect_timeout: Duration,
+    tls_timeout: Duration,
+) -> PhaseTiming {
+    let started = Instant::now();
+    let mut timing = PhaseTiming::default();
+    let elapsed_ms = |start: Instant| start.elapsed().as_millis() as u64;
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return timing;
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+
+    let start = Instant::now();
+    let lookup = tokio::time::timeout(connect_timeout, tokio::net::lookup_host((host, port))).await;
+    timing.dns_ms = Some(elapsed_ms(start));
+    let address = match lookup {
+        Ok(Ok(mut addresses)) => addresses.next(),
+        _ => None,
+    };
+    let Some(address) = address else {
+        timing.failed_phase = Some(Phase::Dns);
+        timing.probe_ms = elapsed_ms(started);
+        return timing;
+    };
+
+    let start = Instant::now();
+    let connected = tokio::time::timeout(connect_timeout, TcpStream::connect(address)).await;
+    timing.connect_ms = Some(elapsed_ms(start));
+    let stream = match connected {
+        Ok(Ok(stream)) => stream,
+        _ => {
+            timing.failed_phase = Some(Phase::Connect);
+            timing.probe_ms = elapsed_ms(started);
+            return timing;
+        }
+    };
+
+    if url.scheme() == "https" || url.scheme() == "wss" {
+        let start = Instant::now();
+        let handshake = match ServerName::try_from(host.to_string()) {
+            Ok(name) => {
+                let connector = TlsConnector::from(TLS_CONFIG.clone());
+                tokio::time::timeout(tls_timeout, connector.connect(name, stream))
+                    .await
+                    .is_ok_and(|result| result.is_ok())
+            }
+            Err(_) => false,
+        };
+        timing.tls_ms = Some(elapsed_ms(start));
+        if !handshake {
+            timing.failed_phase = Some(Phase::Tls);
+        }
+    }
+    timing.probe_ms = elapsed_ms(started);
+    timing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_measure_phases() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = url::Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        let timing = measure_phases(&url, Duration::from_secs(5), Duration::from_secs(5)).await;
+        assert!(timing.dns_ms.is_some() && timing.connect_ms.is_some());
+        assert_eq!(timing.tls_ms, None);
+        assert_eq!(timing.failed_phase, None);
+
+        // Nothing listens on the port once the listener is gone
+        drop(listener);
+        let timing = measure_phases(&url, Duration::from_secs(5), Duration::from_secs(5)).await;
+        assert_eq!(timing.failed_phase, Some(Phase::Connect));
+        assert!(timing.to_string().ends_with("(failed during TCP connect)"));
+    }
+
+    #[test]
+    fn test_split_latency() {
+        let mut timing = PhaseTiming {
+            dns_ms: Some(10),
+            connect_ms: Some(20),
+            tls_ms: Some(40),
+            ..PhaseTiming::default()
+        };
+        timing.split_latency(150, false);
+        assert_eq!((timing.cold_ms, timing.warm_ms), (Some(150), Some(80)));
+
+        timing.split_latency(80, true);
+        assert_eq!((timing.cold_ms, timing.warm_ms), (Some(150), Some(80)));
+        timing.total_ms = Some(80);
+        assert_eq!(
+            timing.to_string(),
+            "dns 10ms, connect 20ms, tls 40ms, total 80ms (warm connection; ~150ms cold)"
+        );
+
+        // Behind a proxy the connection phases aren't measured
+        let mut proxied = PhaseTiming::default();
+        proxied.split_latency(80, true);
+        assert_eq!((proxied.cold_ms, proxied.warm_ms), (None, Some(80)));
+    }
+}
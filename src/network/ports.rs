@@ -0,0 +1,182 @@
+//! TCP port reachability matrix (`diagnose --ports`)
+//!
+//! Most Azure AI traffic only needs 443, but containers behind a gateway often
+//! listen on 8443, and hybrid setups that relay through Service Bus or Event
+//! Hubs need AMQP on 5671/5672. Each endpoint:port pair is reported as open,
+//! closed (the host refused the connection), or filtered (no answer before the
+//! timeout, usually a firewall dropping the SYN).
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::net::TcpStream;
+
+use crate::error::AppError;
+
+/// A port to check on every diagnosed endpoint (`8443`) or on one host (`host:5671`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortSpec {
+    pub host: Option<String>,
+    pub port: u16,
+}
+
+impl FromStr for PortSpec {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, AppError> {
+        let s = s.trim();
+        let (host, port) = match s.rsplit_once(':') {
+            Some((host, port)) if !host.is_empty() => {
+                let host = host.trim_start_matches('[').trim_end_matches(']');
+                (Some(host.to_string()), port)
+            }
+            _ => (None, s),
+        };
+        match port.parse::<u16>() {
+            Ok(port) if port > 0 => Ok(Self { host, port }),
+            _ => Err(AppError::InvalidInput(format!(
+                "Invalid port '{}': expected PORT or HOST:PORT with a port from 1 to 65535",
+                s
+            ))),
+        }
+    }
+}
+
+/// Reachability of a TCP port
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PortStatus {
+    /// The connection was accepted
+    Open,
+    /// The host refused the connection: reachable, but nothing listens on the port
+    Closed,
+    /// No answer before the timeout, or the network reported the host unreachable
+    Filtered,
+    /// The hostname didn't resolve
+    Unresolved,
+}
+
+impl fmt::Display for PortStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            PortStatus::Open => "open",
+            PortStatus::Closed => "closed",
+            PortStatus::Filtered => "filtered",
+            PortStatus::Unresolved => "unresolved",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// Result of a TCP connect to one endpoint:port
+#[derive(Debug, Clone, Serialize)]
+pub struct PortResult {
+    pub endpoint: String,
+    pub port: u16,
+    pub status: PortStatus,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
+
+/// The endpoint:port pairs `specs` select for `endpoints`, in order and without
+/// duplicates. Host-specific ports on hosts that aren't diagnosed are included.
+pub fn port_targets(specs: &[PortSpec], endpoints: &[String]) -> Vec<(String, u16)> {
+    let mut targets: Vec<(String, u16)> = Vec::new();
+    let mut add = |host: &str, port: u16| {
+        if !targets.iter().any(|(h, p)| h == host && *p == port) {
+            targets.push((host.to_string(), port));
+        }
+    };
+    for endpoint in endpoints {
+        for spec in specs.iter().filter(|s| s.host.is_none()) {
+            add(endpoint, spec.port);
+        }
+        for spec in specs.iter().filter(|s| s.host.as_deref() == Some(endpoint)) {
+            add(endpoint, spec.port);
+        }
+    }
+    for spec in specs {
+        if let Some(host) = &spec.host {
+            add(host, spec.port);
+        }
+    }
+    targets
+}
+
+/// Try a TCP connection to `host:port`
+pub async fn check_port(host: &str, port: u16, timeout: Duration) -> PortResult {
+    let start = Instant::now();
+    let mut result = PortResult {
+        endpoint: host.to_string(),
+        port,
+        status: PortStatus::Filtered,
+        duration_ms: 0,
+        error: None,
+    };
+
+    let addresses = match tokio::net::lookup_host((host, port)).await {
+        Ok(addresses) => addresses.collect::<Vec<_>>(),
+        Err(e) => {
+            result.status = PortStatus::Unresolved;
+            result.error = Some(e.to_string());
+            result.duration_ms = start.elapsed().as_millis() as u64;
+            return result;
+        }
+    };
+    let Some(address) = addresses.first() else {
+        result.status = PortStatus::Unresolved;
+        result.duration_ms = start.elapsed().as_millis() as u64;
+        return result;
+    };
+
+    match tokio::time::timeout(timeout, TcpStream::connect(address)).await {
+        Ok(Ok(_)) => result.status = PortStatus::Open,
+        Ok(Err(e)) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+            result.status = PortStatus::Closed;
+            result.error = Some(e.to_string());
+        }
+        Ok(Err(e)) => result.error = Some(e.to_string()),
+        Err(_) => result.error = Some(format!("No response within {}s", timeout.as_secs())),
+    }
+    result.duration_ms = start.elapsed().as_millis() as u64;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_port_specs_and_check() {
+        let specs: Vec<PortSpec> = ["8443", "gw.contoso.com:5671"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        assert!("0".parse::<PortSpec>().is_err());
+        assert!("host:https".parse::<PortSpec>().is_err());
+        let endpoints = vec!["a.example.com".to_string(), "gw.contoso.com".to_string()];
+        assert_eq!(
+            port_targets(&specs, &endpoints),
+            vec![
+                ("a.example.com".to_string(), 8443),
+                ("gw.contoso.com".to_string(), 8443),
+                ("gw.contoso.com".to_string(), 5671),
+            ]
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let timeout = Duration::from_secs(5);
+        assert_eq!(
+            check_port("127.0.0.1", port, timeout).await.status,
+            PortStatus::Open
+        );
+        drop(listener);
+        assert_eq!(
+            check_port("127.0.0.1", port, timeout).await.status,
+            PortStatus::Closed
+        );
+    }
+}
@@ -0,0 +1,517 @@
+//! Windows proxy settings and certificate store
+//!
+//! Enterprise Windows desktops get their proxy and their inspection CA from
+//! group policy, not from `HTTPS_PROXY` and a PEM bundle. reqwest already
+//! follows the per-user Internet Settings proxy; this module adds the
+//! machine-wide WinHTTP proxy (what services and scheduled tasks use) when
+//! nothing else is configured, trusts the roots in the Windows certificate
+//! store alongside the built-in ones, and reports what it found, including
+//! proxy auto-config (PAC) scripts, which nothing here evaluates.
+
+use std::fmt;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::LazyLock;
+
+use reqwest::{Certificate, ClientBuilder, NoProxy, Proxy};
+use serde::{Deserialize, Serialize};
+use tokio_rustls::rustls::pki_types::CertificateDer;
+use tokio_rustls::rustls::RootCertStore;
+
+use crate::environment::redact_proxy_url;
+
+/// Registry key holding the per-user (WinINet) proxy settings
+const INTERNET_SETTINGS_KEY: &str =
+    r"HKCU\Software\Microsoft\Windows\CurrentVersion\Internet Settings";
+
+/// Registry key whose `WinHttpSettings` value holds the machine-wide WinHTTP
+/// proxy (what `netsh winhttp set proxy` writes)
+const WINHTTP_SETTINGS_KEY: &str =
+    r"HKLM\SOFTWARE\Microsoft\Windows\CurrentVersion\Internet Settings\Connections";
+
+/// Proxy variables that take precedence over any system setting
+const PROXY_VARS: &[&str] = &[
+    "HTTPS_PROXY",
+    "https_proxy",
+    "HTTP_PROXY",
+    "http_proxy",
+    "ALL_PROXY",
+    "all_proxy",
+];
+
+static USE_SYSTEM_PROXY: AtomicBool = AtomicBool::new(true);
+static USE_SYSTEM_CERTS: AtomicBool = AtomicBool::new(true);
+
+/// Proxy settings of this machine, read once
+static PROXIES: LazyLock<Vec<SystemProxy>> = LazyLock::new(detect_proxies);
+
+/// Roots in the Windows certificate store, read once
+static CERTIFICATES: LazyLock<Option<LoadedStore>> = LazyLock::new(load_certificates);
+
+/// Roots read from the store, with the summary reported for them
+struct LoadedStore {
+    summary: CertificateStore,
+    der: Vec<CertificateDer<'static>>,
+    certificates: Vec<Certificate>,
+}
+
+/// Where a system proxy setting comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxySource {
+    /// Per-user Internet Settings (WinINet, the Internet Options dialog)
+    InternetSettings,
+    /// Machine-wide WinHTTP settings (`netsh winhttp set proxy`)
+    WinHttp,
+}
+
+impl fmt::Display for ProxySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            ProxySource::InternetSettings => "Internet Settings",
+            ProxySource::WinHttp => "WinHTTP",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// A proxy configured in the operating system
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SystemProxy {
+    pub source: ProxySource,
+    /// Whether the proxy server is switched on
+    pub enabled: bool,
+    /// `host:port`, or per-scheme `http=host:port;https=host:port`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    /// Hosts that bypass the proxy, as configured (`*.corp.local`, `<local>`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bypass: Vec<String>,
+    /// Proxy auto-config (PAC) script URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_config_url: Option<String>,
+}
+
+impl SystemProxy {
+    /// `proxy.corp:8080 (bypass: *.corp.local, <local>)`
+    pub fn describe(&self) -> String {
+        let server = match (&self.server, self.enabled) {
+            (Some(server), true) => server.clone(),
+            (Some(server), false) => format!("{} (disabled)", server),
+            (None, _) => "direct".to_string(),
+        };
+        if self.bypass.is_empty() || !self.enabled {
+            server
+        } else {
+            format!("{} (bypass: {})", server, self.bypass.join(", "))
+        }
+    }
+
+    /// Warning for a PAC script: requests go direct where a browser would use
+    /// the proxy the script picks
+    pub fn auto_config_warning(&self) -> Option<String> {
+        self.auto_config_url.as_ref().map(|url| {
+            format!(
+                "{} uses the proxy auto-config script {}, which this tool can't evaluate; \
+                 set HTTPS_PROXY to the proxy it returns",
+                self.source, url
+            )
+        })
+    }
+
+    /// URL of the proxy for HTTPS requests
+    fn https_proxy_url(&self) -> Option<String> {
+        let server = self.server.as_deref()?;
+        let server = if server.contains('=') {
+            let entries: Vec<(&str, &str)> = server
+                .split(';')
+                .filter_map(|entry| entry.split_once('='))
+                .map(|(scheme, host)| (scheme.trim(), host.trim()))
+                .collect();
+            ["https", "http"]
+                .iter()
+                .find_map(|scheme| entries.iter().find(|(s, _)| s == scheme))
+                .map(|(_, host)| *host)?
+        } else {
+            server.trim()
+        };
+        if server.is_empty() {
+            None
+        } else if server.contains("://") {
+            Some(server.to_string())
+        } else {
+            Some(format!("http://{}", server))
+        }
+    }
+
+    /// Bypass list in `NO_PROXY` syntax. `<local>` (hosts without a dot) and
+    /// wildcards other than a leading `*.` or trailing IPv4 octets can't be
+    /// expressed and are left out.
+    fn no_proxy(&self) -> String {
+        self.bypass
+            .iter()
+            .filter_map(|entry| {
+                if let Some(domain) = entry.strip_prefix("*.") {
+                    return (!domain.contains('*')).then(|| domain.to_string());
+                }
+                if !entry.contains('*') {
+                    return (entry != "<local>").then(|| entry.clone());
+                }
+                let octets: Vec<&str> = entry.split('.').collect();
+                let fixed = octets.iter().take_while(|o| **o != "*").count();
+                let all_numeric = octets[..fixed].iter().all(|o| o.parse::<u8>().is_ok());
+                let rest_wild = octets[fixed..].iter().all(|o| *o == "*");
+                (octets.len() <= 4 && fixed > 0 && all_numeric && rest_wild).then(|| {
+                    let mut address = octets[..fixed].to_vec();
+                    address.resize(4, "0");
+                    format!("{}/{}", address.join("."), fixed * 8)
+                })
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// Trusted roots read from the operating system
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CertificateStore {
+    /// Roots added to the built-in (Mozilla) set
+    pub roots: usize,
+    /// Certificates or stores that couldn't be read
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+/// Turn the WinHTTP proxy fallback off for this process (`--no-system-proxy`)
+pub fn set_use_system_proxy(enabled: bool) {
+    USE_SYSTEM_PROXY.store(enabled, Ordering::Relaxed);
+}
+
+/// Stop trusting the Windows certificate store for this process
+/// (`--no-system-certs`)
+pub fn set_use_system_certs(enabled: bool) {
+    USE_SYSTEM_CERTS.store(enabled, Ordering::Relaxed);
+}
+
+/// The operating system's proxy settings; empty except on Windows
+pub fn system_proxies() -> &'static [SystemProxy] {
+    &PROXIES
+}
+
+/// The Windows certificate store, when its roots are trusted
+pub fn certificate_store() -> Option<&'static CertificateStore> {
+    if !USE_SYSTEM_CERTS.load(Ordering::Relaxed) {
+        return None;
+    }
+    CERTIFICATES.as_ref().map(|loaded| &loaded.summary)
+}
+
+/// Roots of the Windows certificate store, when they are trusted
+pub fn system_roots() -> &'static [CertificateDer<'static>] {
+    match CERTIFICATES.as_ref() {
+        Some(loaded) if USE_SYSTEM_CERTS.load(Ordering::Relaxed) => &loaded.der,
+        _ => &[],
+    }
+}
+
+/// Whether any proxy variable is set; they take precedence over the system
+fn env_proxy_set() -> bool {
+    PROXY_VARS
+        .iter()
+        .any(|name| std::env::var(name).is_ok_and(|v| !v.is_empty()))
+}
+
+/// The WinHTTP proxy, when requests would otherwise go direct: no proxy
+/// variable is set and the Internet Settings proxy (which reqwest follows)
+/// is off
+fn winhttp_fallback(proxies: &[SystemProxy]) -> Option<&SystemProxy> {
+    let internet_settings = proxies
+        .iter()
+        .any(|p| p.source == ProxySource::InternetSettings && p.enabled && p.server.is_some());
+    if internet_settings {
+        return None;
+    }
+    proxies
+        .iter()
+        .find(|p| p.source == ProxySource::WinHttp && p.enabled && p.server.is_some())
+}
+
+/// Whether requests go through a Windows proxy: the Internet Settings one,
+/// or the WinHTTP fallback
+pub fn proxy_in_effect() -> bool {
+    PROXIES
+        .iter()
+        .any(|p| p.source == ProxySource::InternetSettings && p.enabled && p.server.is_some())
+        || (USE_SYSTEM_PROXY.load(Ordering::Relaxed) && winhttp_fallback(&PROXIES).is_some())
+}
+
+/// The Windows proxy reqwest would use for HTTPS, with its bypass list in
+/// `NO_PROXY` syntax, for connections reqwest doesn't make
+pub fn https_proxy() -> Option<(String, String)> {
+    if env_proxy_set() {
+        return None;
+    }
+    let proxy = PROXIES
+        .iter()
+        .find(|p| p.source == ProxySource::InternetSettings && p.enabled && p.server.is_some())
+        .or_else(|| {
+            USE_SYSTEM_PROXY
+                .load(Ordering::Relaxed)
+                .then(|| winhttp_fallback(&PROXIES))
+                .flatten()
+        })?;
+    Some((proxy.https_proxy_url()?, proxy.no_proxy()))
+}
+
+/// Apply the Windows certificate store and WinHTTP proxy to an HTTP client
+pub fn configure_client(mut builder: ClientBuilder) -> ClientBuilder {
+    if USE_SYSTEM_CERTS.load(Ordering::Relaxed) {
+        if let Some(loaded) = CERTIFICATES.as_ref() {
+            for certificate in &loaded.certificates {
+                builder = builder.add_root_certificate(certificate.clone());
+            }
+        }
+    }
+    if USE_SYSTEM_PROXY.load(Ordering::Relaxed) && !env_proxy_set() {
+        let proxy = winhttp_fallback(&PROXIES).and_then(|winhttp| {
+            let url = winhttp.https_proxy_url()?;
+            let proxy = Proxy::all(url).ok()?;
+            Some(proxy.no_proxy(NoProxy::from_string(&winhttp.no_proxy())))
+        });
+        if let Some(proxy) = proxy {
+            builder = builder.proxy(proxy);
+        }
+    }
+    builder
+}
+
+/// Run a command and return its standard output, if it succeeded
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn detect_proxies() -> Vec<SystemProxy> {
+    if !cfg!(windows) {
+        return Vec::new();
+    }
+    let internet_settings = command_output("reg", &["query", INTERNET_SETTINGS_KEY])
+        .and_then(|output| parse_internet_settings(&output));
+    // The registry value rather than `netsh winhttp show proxy`, whose labels
+    // are localized
+    let winhttp = command_output(
+        "reg",
+        &["query", WINHTTP_SETTINGS_KEY, "/v", "WinHttpSettings"],
+    )
+    .and_then(|output| parse_winhttp(&output));
+    internet_settings.into_iter().chain(winhttp).collect()
+}
+
+fn load_certificates() -> Option<LoadedStore> {
+    if !cfg!(windows) {
+        return None;
+    }
+    let result = rustls_native_certs::load_native_certs();
+    let mut errors: Vec<String> = result.errors.iter().map(|e| e.to_string()).collect();
+    // One root rustls rejects would fail every client build, so each is
+    // checked on its own first
+    let mut der = Vec::new();
+    let mut certificates = Vec::new();
+    for cert in result.certs {
+        let accepted = RootCertStore::empty()
+            .add(cert.clone())
+            .map_err(|e| e.to_string())
+            .and_then(|()| Certificate::from_der(&cert).map_err(|e| e.to_string()));
+        match accepted {
+            Ok(certificate) => {
+                certificates.push(certificate);
+                der.push(cert);
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    Some(LoadedStore {
+        summary: CertificateStore {
+            roots: der.len(),
+            errors,
+        },
+        der,
+        certificates,
+    })
+}
+
+/// Split a bypass list (`*.corp;<local>`)
+fn split_bypass(list: &str) -> Vec<String> {
+    list.split([';', ','])
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parse `reg query` output for the Internet Settings key; `None` when
+/// neither a proxy server nor a PAC script is configured
+pub fn parse_internet_settings(output: &str) -> Option<SystemProxy> {
+    let value = |name: &str| {
+        output.lines().find_map(|line| {
+            let mut parts = line.split_whitespace();
+            if parts.next()? != name || !parts.next()?.starts_with("REG_") {
+                return None;
+            }
+            Some(parts.collect::<Vec<_>>().join(" "))
+        })
+    };
+    let enabled = value("ProxyEnable")
+        .and_then(|v| u32::from_str_radix(v.trim_start_matches("0x"), 16).ok())
+        .is_some_and(|v| v != 0);
+    let server = value("ProxyServer").filter(|s| !s.is_empty());
+    let auto_config_url = value("AutoConfigURL").filter(|s| !s.is_empty());
+    if server.is_none() && auto_config_url.is_none() {
+        return None;
+    }
+    Some(SystemProxy {
+        source: ProxySource::InternetSettings,
+        enabled,
+        server: server.map(|s| redact_proxy_url(&s)),
+        bypass: value("ProxyOverride")
+            .map(|list| split_bypass(&list))
+            .unwrap_or_default(),
+        auto_config_url,
+    })
+}
+
+/// Parse `reg query` output for the `WinHttpSettings` value; `None` for
+/// direct access. The value is a little-endian structure: a version, a change
+/// counter, flags (2: use a proxy), then the proxy server and the bypass list,
+/// each as a length-prefixed string.
+pub fn parse_winhttp(output: &str) -> Option<SystemProxy> {
+    let hex = output.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        if parts.next()? != "WinHttpSettings" || parts.next()? != "REG_BINARY" {
+            return None;
+        }
+        parts.next()
+    })?;
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    let dword = |at: usize| {
+        let bytes = bytes.get(at..at + 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().ok()?) as usize)
+    };
+    let string = |at: usize| {
+        let len = dword(at)?;
+        let text = bytes.get(at + 4..at + 4 + len)?;
+        Some((
+            String::from_utf8_lossy(text).trim().to_string(),
+            at + 4 + len,
+        ))
+    };
+    const PROXY_TYPE_PROXY: usize = 2;
+    if dword(8)? & PROXY_TYPE_PROXY == 0 {
+        return None;
+    }
+    let (server, next) = string(12)?;
+    if server.is_empty() {
+        return None;
+    }
+    Some(SystemProxy {
+        source: ProxySource::WinHttp,
+        enabled: true,
+        server: Some(redact_proxy_url(&server)),
+        bypass: string(next)
+            .map(|(list, _)| split_bypass(&list))
+            .unwrap_or_default(),
+        auto_config_url: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REG_QUERY: &str = "\r\nHKEY_CURRENT_USER\\Software\\Microsoft\\Windows\\CurrentVersion\\Internet Settings\r\n\
+        \x20   CertificateRevocation    REG_DWORD    0x1\r\n\
+        \x20   ProxyEnable    REG_DWORD    0x0\r\n\
+        \x20   ProxyServer    REG_SZ    http=proxy.corp:80;https=proxy.corp:8443\r\n\
+        \x20   ProxyOverride    REG_SZ    *.corp.local;10.*;<local>\r\n\
+        \x20   AutoConfigURL    REG_SZ    http://wpad.corp.local/proxy.pac\r\n";
+
+    /// `reg query` output for WinHttpSettings, as set by `netsh winhttp set proxy`
+    fn winhttp_settings(flags: u8, server: &str, bypass: &str) -> String {
+        let mut value = vec![0x28, 0, 0, 0, 0x05, 0, 0, 0, flags, 0, 0, 0];
+        for text in [server, bypass] {
+            value.extend((text.len() as u32).to_le_bytes());
+            value.extend(text.as_bytes());
+        }
+        let hex: String = value.iter().map(|b| format!("{:02X}", b)).collect();
+        format!(
+            "\r\nHKEY_LOCAL_MACHINE\\SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Internet Settings\\Connections\r\n\
+             \x20   WinHttpSettings    REG_BINARY    {}\r\n\r\n",
+            hex
+        )
+    }
+
+    #[test]
+    fn test_parse_internet_settings() {
+        let proxy = parse_internet_settings(REG_QUERY).unwrap();
+        assert!(!proxy.enabled);
+        assert_eq!(
+            proxy.server.as_deref(),
+            Some("http=proxy.corp:80;https=proxy.corp:8443")
+        );
+        assert_eq!(proxy.bypass, ["*.corp.local", "10.*", "<local>"]);
+        assert_eq!(
+            proxy.https_proxy_url().as_deref(),
+            Some("http://proxy.corp:8443")
+        );
+        assert_eq!(proxy.no_proxy(), "corp.local,10.0.0.0/8");
+        assert!(proxy
+            .auto_config_warning()
+            .unwrap()
+            .starts_with("Internet Settings uses the proxy auto-config script http://wpad"));
+
+        let direct = "HKEY_CURRENT_USER\\...\r\n    ProxyEnable    REG_DWORD    0x0\r\n";
+        assert_eq!(parse_internet_settings(direct), None);
+    }
+
+    #[test]
+    fn test_parse_winhttp_and_fallback() {
+        let winhttp = parse_winhttp(&winhttp_settings(
+            3,
+            "proxy.corp:8080",
+            "*.corp.local;192.168.*.*;<local>",
+        ))
+        .unwrap();
+        assert_eq!(
+            winhttp.describe(),
+            "proxy.corp:8080 (bypass: *.corp.local, 192.168.*.*, <local>)"
+        );
+        assert_eq!(
+            winhttp.https_proxy_url().as_deref(),
+            Some("http://proxy.corp:8080")
+        );
+        assert_eq!(winhttp.no_proxy(), "corp.local,192.168.0.0/16");
+        // Direct access, after `netsh winhttp reset proxy`
+        assert_eq!(parse_winhttp(&winhttp_settings(1, "", "")), None);
+        assert_eq!(
+            parse_winhttp(&winhttp_settings(1, "proxy.corp:8080", "")),
+            None
+        );
+
+        // The Internet Settings proxy is off, so WinHTTP's applies
+        let internet_settings = parse_internet_settings(REG_QUERY).unwrap();
+        let proxies = vec![internet_settings.clone(), winhttp.clone()];
+        assert_eq!(winhttp_fallback(&proxies), Some(&winhttp));
+
+        let enabled = SystemProxy {
+            enabled: true,
+            ..internet_settings
+        };
+        assert_eq!(winhttp_fallback(&[enabled, winhttp]), None);
+    }
+}
@@ -0,0 +1,146 @@
+//! Hosts `diagnose` checks
+//!
+//! A run covers the endpoints of one or more regions, or a list of custom
+//! endpoints (resource subdomains, private endpoints, API Management
+//! gateways) given with `--endpoint` or read from `--endpoints-file`. Each
+//! host is checked once even when several regions share it (the global
+//! Translator host), and results are reported per group.
+
+use serde::Serialize;
+
+use super::get_endpoints_for_region;
+use crate::config::Cloud;
+
+/// Name of the group of custom endpoints
+pub const ENDPOINTS_GROUP: &str = "endpoints";
+
+/// Hosts reported under one heading: a region's endpoints, or the custom
+/// endpoints
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiagnosticGroup {
+    /// Region, or `endpoints`
+    pub name: String,
+    pub hosts: Vec<String>,
+}
+
+/// Groups to diagnose: the custom endpoints when any are given (the
+/// regions' endpoints are not checked then), else one group per region
+pub fn diagnostic_groups(
+    regions: &[String],
+    cloud: Cloud,
+    endpoints: &[String],
+) -> Vec<DiagnosticGroup> {
+    if !endpoints.is_empty() {
+        let mut hosts: Vec<String> = Vec::new();
+        for endpoint in endpoints {
+            if !hosts.contains(endpoint) {
+                hosts.push(endpoint.clone());
+            }
+        }
+        return vec![DiagnosticGroup {
+            name: ENDPOINTS_GROUP.to_string(),
+            hosts,
+        }];
+    }
+    regions
+        .iter()
+        .map(|region| DiagnosticGroup {
+            name: region.clone(),
+            hosts: get_endpoints_for_region(region, cloud),
+        })
+        .collect()
+}
+
+/// Every host of `groups` once, in order
+pub fn unique_hosts(groups: &[DiagnosticGroup]) -> Vec<String> {
+    let mut hosts: Vec<String> = Vec::new();
+    for host in groups.iter().flat_map(|g| &g.hosts) {
+        if !hosts.contains(host) {
+            hosts.push(host.clone());
+        }
+    }
+    hosts
+}
+
+/// Host of an endpoint: a bare host name, or the host of a URL
+/// (`https://contoso.azure-api.net/openai` -> `contoso.azure-api.net`)
+pub fn endpoint_host(endpoint: &str) -> Result<String, String> {
+    let endpoint = endpoint.trim();
+    if endpoint.contains("://") {
+        return url::Url::parse(endpoint)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .ok_or_else(|| format!("'{}' is not a valid URL", endpoint));
+    }
+    let host = endpoint.split('/').next().unwrap_or_default();
+    if host.is_empty() || host.contains(char::is_whitespace) {
+        return Err(format!("'{}' is not a host name", endpoint));
+    }
+    Ok(host.to_string())
+}
+
+/// Hosts of an endpoints file: one host or URL per line; blank lines and
+/// `#` comments are skipped
+pub fn parse_endpoints_file(content: &str) -> Result<Vec<String>, String> {
+    content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.split('#').next().unwrap_or_default().trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(number, line)| endpoint_host(line).map_err(|e| format!("line {}: {}", number, e)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_endpoints_file() {
+        let hosts = parse_endpoints_file(
+            "# APIM gateways\n\
+             contoso.azure-api.net\n\
+             https://myres.cognitiveservices.azure.com/language  # private endpoint\n\
+             \n\
+             eastus.api.cognitive.microsoft.com\n",
+        )
+        .unwrap();
+        assert_eq!(
+            hosts,
+            [
+                "contoso.azure-api.net",
+                "myres.cognitiveservices.azure.com",
+                "eastus.api.cognitive.microsoft.com"
+            ]
+        );
+        assert_eq!(
+            parse_endpoints_file("ok.example.com\nnot a host\n").unwrap_err(),
+            "line 2: 'not a host' is not a host name"
+        );
+    }
+
+    #[test]
+    fn test_groups_share_hosts() {
+        let regions = ["eastus".to_string(), "westeurope".to_string()];
+        let groups = diagnostic_groups(&regions, Cloud::Global, &[]);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[1].name, "westeurope");
+
+        // The global Translator host is in both regions but checked once
+        let hosts = unique_hosts(&groups);
+        assert_eq!(
+            hosts.len(),
+            groups[0].hosts.len() + groups[1].hosts.len() - 1
+        );
+
+        let endpoints = ["a.example.com".to_string(), "a.example.com".to_string()];
+        let groups = diagnostic_groups(&regions, Cloud::Global, &endpoints);
+        assert_eq!(
+            groups,
+            [DiagnosticGroup {
+                name: ENDPOINTS_GROUP.to_string(),
+                hosts: vec!["a.example.com".to_string()],
+            }]
+        );
+    }
+}
@@ -0,0 +1,251 @@
+//! WebSocket connections through the proxy the HTTP client uses
+//!
+//! tokio-tungstenite opens its own TCP connection, so without this a network
+//! that only lets traffic out through a proxy would fail the WebSocket
+//! scenarios while the HTTP ones pass. [`connect`] picks the proxy reqwest
+//! would (`HTTPS_PROXY`/`HTTP_PROXY`/`ALL_PROXY` minus `NO_PROXY`, then the
+//! Windows settings), opens an HTTP CONNECT tunnel through it, and trusts the
+//! same roots as the HTTP client.
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr};
+
+use base64::Engine;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::error::UrlError;
+use tokio_tungstenite::tungstenite::handshake::client::{Request, Response};
+use tokio_tungstenite::tungstenite::Error;
+use tokio_tungstenite::{Connector, MaybeTlsStream, WebSocketStream};
+
+use crate::environment::redact_proxy_url;
+
+/// An open WebSocket connection
+pub type WebSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Largest proxy response to a CONNECT read before giving up
+const MAX_CONNECT_RESPONSE: usize = 8 * 1024;
+
+/// Open a WebSocket connection for `request`, through a proxy when one applies
+pub async fn connect(request: Request) -> Result<(WebSocket, Response), Error> {
+    let uri = request.uri();
+    let secure = uri.scheme_str() == Some("wss");
+    let host = uri
+        .host()
+        .ok_or(Error::Url(UrlError::NoHostName))?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+    let port = uri.port_u16().unwrap_or(if secure { 443 } else { 80 });
+
+    let stream = open_stream(secure, &host, port).await?;
+    let connector = Connector::Rustls(super::phases::TLS_CONFIG.clone());
+    tokio_tungstenite::client_async_tls_with_config(request, stream, None, Some(connector)).await
+}
+
+/// TCP connection to `host:port`, tunneled through the proxy an HTTP
+/// (`secure`: HTTPS) request to it would use
+pub(super) async fn open_stream(secure: bool, host: &str, port: u16) -> io::Result<TcpStream> {
+    let stream = match proxy_for(if secure { "https" } else { "http" }, host) {
+        Some(proxy) => tunnel(&proxy, host, port).await?,
+        None => TcpStream::connect((host, port)).await?,
+    };
+    let _ = stream.set_nodelay(true);
+    Ok(stream)
+}
+
+/// Proxy for a connection to `host` over `scheme`: the environment's, or
+/// failing that the Windows one, unless the host is on its bypass list
+fn proxy_for(scheme: &str, host: &str) -> Option<String> {
+    let from_env = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| std::env::var(name).ok().filter(|v| !v.trim().is_empty()))
+    };
+    let names: &[&str] = if scheme == "https" {
+        &["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"]
+    } else {
+        &["HTTP_PROXY", "http_proxy", "ALL_PROXY", "all_proxy"]
+    };
+    let (proxy, no_proxy) = match from_env(names) {
+        Some(proxy) => (
+            proxy,
+            from_env(&["NO_PROXY", "no_proxy"]).unwrap_or_default(),
+        ),
+        None => super::system::https_proxy()?,
+    };
+    (!bypasses(&no_proxy, host)).then_some(proxy)
+}
+
+/// Whether a `NO_PROXY` list (`*`, domains, IP addresses, IPv4 CIDR ranges)
+/// covers `host`
+fn bypasses(no_proxy: &str, host: &str) -> bool {
+    let host = host.to_ascii_lowercase();
+    let address = host.parse::<IpAddr>().ok();
+    no_proxy
+        .split(',')
+        .map(|entry| entry.trim().to_ascii_lowercase())
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| {
+            if entry == "*" {
+                return true;
+            }
+            if let Some((network, bits)) = entry.split_once('/') {
+                return match (address, network.parse::<Ipv4Addr>(), bits.parse::<u32>()) {
+                    (Some(IpAddr::V4(address)), Ok(network), Ok(bits)) if bits <= 32 => {
+                        let mask = u32::MAX.checked_shl(32 - bits).unwrap_or(0);
+                        u32::from(address) & mask == u32::from(network) & mask
+                    }
+                    _ => false,
+                };
+            }
+            let domain = entry.trim_start_matches("*.").trim_start_matches('.');
+            host == domain || host.ends_with(&format!(".{}", domain))
+        })
+}
+
+/// Open a TCP connection to `host:port` through an HTTP CONNECT proxy
+async fn tunnel(proxy: &str, host: &str, port: u16) -> io::Result<TcpStream> {
+    let shown = redact_proxy_url(proxy);
+    let url = if proxy.contains("://") {
+        url::Url::parse(proxy)
+    } else {
+        url::Url::parse(&format!("http://{}", proxy))
+    }
+    .map_err(|e| io::Error::other(format!("Invalid proxy URL '{}': {}", shown, e)))?;
+    if url.scheme() != "http" {
+        return Err(io::Error::other(format!(
+            "Proxy '{}' is not an http:// proxy; WebSocket scenarios only tunnel through HTTP CONNECT",
+            shown
+        )));
+    }
+    let (Some(proxy_host), Some(proxy_port)) = (url.host_str(), url.port_or_known_default()) else {
+        return Err(io::Error::other(format!("Proxy '{}' has no host", shown)));
+    };
+
+    let mut stream = TcpStream::connect((proxy_host, proxy_port)).await?;
+    let authority = if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    };
+    let mut connect = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", authority);
+    if !url.username().is_empty() {
+        let decode = |value: &str| {
+            percent_encoding::percent_decode_str(value)
+                .decode_utf8_lossy()
+                .into_owned()
+        };
+        let credentials = format!(
+            "{}:{}",
+            decode(url.username()),
+            decode(url.password().unwrap_or_default())
+        );
+        connect.push_str(&format!(
+            "Proxy-Authorization: Basic {}\r\n",
+            base64::engine::general_purpose::STANDARD.encode(credentials)
+        ));
+    }
+    connect.push_str("\r\n");
+    stream.write_all(connect.as_bytes()).await?;
+
+    // Read byte by byte so nothing after the proxy's headers is consumed
+    let mut response = Vec::new();
+    let mut byte = [0u8; 1];
+    while !response.ends_with(b"\r\n\r\n") {
+        if response.len() >= MAX_CONNECT_RESPONSE {
+            return Err(io::Error::other(format!(
+                "Proxy '{}' sent an oversized CONNECT response",
+                shown
+            )));
+        }
+        if stream.read(&mut byte).await? == 0 {
+            return Err(io::Error::other(format!(
+                "Proxy '{}' closed the connection during CONNECT",
+                shown
+            )));
+        }
+        response.push(byte[0]);
+    }
+    let response = String::from_utf8_lossy(&response);
+    let status_line = response.lines().next().unwrap_or_default();
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok());
+    match status {
+        Some(200..=299) => Ok(stream),
+        _ => Err(io::Error::other(format!(
+            "Proxy '{}' refused the tunnel to {}: {}",
+            shown, authority, status_line
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_bypasses() {
+        assert!(bypasses("*", "eastus.stt.speech.microsoft.com"));
+        assert!(bypasses(
+            "localhost, .microsoft.com",
+            "eastus.stt.speech.microsoft.com"
+        ));
+        assert!(bypasses("*.Microsoft.com", "speech.microsoft.com"));
+        assert!(bypasses("microsoft.com", "microsoft.com"));
+        assert!(!bypasses("microsoft.com", "notmicrosoft.com"));
+        assert!(bypasses("10.0.0.0/8", "10.1.2.3"));
+        assert!(!bypasses("10.0.0.0/8", "11.1.2.3"));
+        assert!(bypasses("127.0.0.1", "127.0.0.1"));
+        assert!(!bypasses("", "127.0.0.1"));
+    }
+
+    /// A proxy that answers one CONNECT with `status` and returns what it was sent
+    async fn fake_proxy(status: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let handle = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = socket.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..n]);
+            }
+            socket
+                .write_all(format!("HTTP/1.1 {}\r\n\r\n", status).as_bytes())
+                .await
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+        (format!("http://user:p%40ss@{}", address), handle)
+    }
+
+    #[tokio::test]
+    async fn test_tunnel_sends_connect_with_credentials() {
+        let (proxy, request) = fake_proxy("200 Connection established").await;
+        tunnel(&proxy, "eastus.stt.speech.microsoft.com", 443)
+            .await
+            .unwrap();
+        let request = request.await.unwrap();
+        assert!(request.starts_with("CONNECT eastus.stt.speech.microsoft.com:443 HTTP/1.1\r\n"));
+        let credentials = base64::engine::general_purpose::STANDARD.encode("user:p@ss");
+        assert!(request.contains(&format!("Proxy-Authorization: Basic {}\r\n", credentials)));
+    }
+
+    #[tokio::test]
+    async fn test_tunnel_reports_refusal_without_credentials() {
+        let (proxy, _) = fake_proxy("407 Proxy Authentication Required").await;
+        let err = tunnel(&proxy, "example.com", 443).await.unwrap_err();
+        let message = err.to_string();
+        assert!(
+            message.contains("407 Proxy Authentication Required"),
+            "{}",
+            message
+        );
+        assert!(!message.contains("p%40ss"), "{}", message);
+    }
+}
@@ -0,0 +1,413 @@
+//! Notifications when a test run completes (`test --notify`, `--email-to`)
+//!
+//! A generic webhook receives the JSON report; Slack and Teams incoming
+//! webhooks get a short summary card, and emails the report (see [`email`]). Webhook URLs embed their own secret, so
+//! they are registered for redaction and only the host is ever printed.
+
+pub mod email;
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::auth::token_cache::TokenCacheFile;
+use crate::error::{AppError, Result};
+use crate::output::TestReport;
+
+/// Failed scenarios listed in a Slack or Teams message
+const MAX_LISTED_FAILURES: usize = 10;
+
+/// Kind of notification endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotifyKind {
+    /// POST the JSON report
+    Webhook,
+    /// Slack incoming webhook
+    Slack,
+    /// Microsoft Teams incoming webhook or workflow
+    Teams,
+}
+
+/// When to send notifications
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NotifyOn {
+    /// After every run
+    #[default]
+    Always,
+    /// Only when the run fails under the exit policy (`--fail-on`)
+    Failure,
+    /// Only when a scenario that passed in the previous run now fails
+    Regression,
+}
+
+/// A notification endpoint (`slack:<url>`, `teams:<url>`, `webhook:<url>`, or a bare URL)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotifyTarget {
+    pub kind: NotifyKind,
+    pub url: String,
+}
+
+impl NotifyTarget {
+    pub fn webhook(url: &str) -> Result<Self> {
+        Self::new(NotifyKind::Webhook, url)
+    }
+
+    fn new(kind: NotifyKind, url: &str) -> Result<Self> {
+        let parsed = url::Url::parse(url)
+            .map_err(|e| AppError::InvalidInput(format!("Invalid notification URL: {}", e)))?;
+        if !matches!(parsed.scheme(), "https" | "http") {
+            return Err(AppError::InvalidInput(
+                "Notification URLs must be http(s) URLs".to_string(),
+            ));
+        }
+        crate::redact::register_secret(url);
+        Ok(Self {
+            kind,
+            url: url.to_string(),
+        })
+    }
+
+    /// Where the notification goes, without the URL's secret path
+    pub fn describe(&self) -> String {
+        let host = url::Url::parse(&self.url)
+            .ok()
+            .and_then(|u| u.host_str().map(str::to_string))
+            .unwrap_or_default();
+        let kind = match self.kind {
+            NotifyKind::Webhook => "webhook",
+            NotifyKind::Slack => "Slack",
+            NotifyKind::Teams => "Teams",
+        };
+        format!("{} ({})", kind, host)
+    }
+}
+
+impl std::str::FromStr for NotifyTarget {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        match s.split_once(':') {
+            Some(("slack", url)) => Self::new(NotifyKind::Slack, url),
+            Some(("teams", url)) => Self::new(NotifyKind::Teams, url),
+            Some(("webhook", url)) => Self::new(NotifyKind::Webhook, url),
+            _ => Self::webhook(s),
+        }
+    }
+}
+
+/// Pass/fail of each scenario in the last notified run, for regression checks
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RunState {
+    /// `<service label>/<scenario id>` -> passed
+    pub scenarios: BTreeMap<String, bool>,
+}
+
+impl RunState {
+    fn path() -> Option<PathBuf> {
+        TokenCacheFile::cache_dir().map(|d| d.join("last-run.json"))
+    }
+
+    /// Load the previous run's state (empty when there is none)
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Record this run's outcomes, keeping scenarios it didn't run
+    pub fn update(&mut self, report: &TestReport) {
+        for service in &report.services {
+            for result in &service.results {
+                if result.is_skipped() || result.is_throttled() || result.is_expected_failure() {
+                    continue;
+                }
+                self.scenarios.insert(
+                    format!("{}/{}", service.label(), result.scenario_id),
+                    result.success,
+                );
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = Self::path() else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Scenarios that passed in the previous run and failed in this one
+    pub fn regressions(&self, report: &TestReport) -> Vec<String> {
+        report
+            .services
+            .iter()
+            .flat_map(|service| {
+                service
+                    .results
+                    .iter()
+                    .filter(|r| {
+                        !r.success
+                            && !r.is_skipped()
+                            && !r.is_throttled()
+                            && !r.is_expected_failure()
+                    })
+                    .filter(|r| {
+                        self.scenarios
+                            .get(&format!("{}/{}", service.label(), r.scenario_id))
+                            .copied()
+                            .unwrap_or(false)
+                    })
+                    .map(|r| format!("{} / {}", service.label(), r.scenario_name))
+            })
+            .collect()
+    }
+}
+
+/// Whether a run should be notified under `on`
+pub fn should_notify(on: NotifyOn, failed: bool, regressions: &[String]) -> bool {
+    match on {
+        NotifyOn::Always => true,
+        NotifyOn::Failure => failed,
+        NotifyOn::Regression => !regressions.is_empty(),
+    }
+}
+
+/// One-line summary, e.g. "5/6 passed, 1 failed (eastus, global)"
+fn headline(report: &TestReport) -> String {
+    let summary = &report.summary;
+    let mut headline = format!(
+        "Azure AI connectivity: {}/{} passed",
+        summary.passed, summary.total
+    );
+    for (count, label) in [
+        (summary.failed, "failed"),
+        (summary.skipped, "skipped"),
+        (summary.throttled, "throttled"),
+        (summary.slow, "slow"),
+        (summary.expected_failures, "expected failures"),
+    ] {
+        if count > 0 {
+            headline.push_str(&format!(", {} {}", count, label));
+        }
+    }
+    let mut context: Vec<String> = report
+        .region
+        .iter()
+        .cloned()
+        .chain(report.cloud.map(|c| c.to_string()))
+        .collect();
+    context.dedup();
+    if !context.is_empty() {
+        headline.push_str(&format!(" ({})", context.join(", ")));
+    }
+    headline
+}
+
+/// "Service / Scenario: error" for the first failed scenarios
+fn failure_lines(report: &TestReport) -> Vec<String> {
+    report
+        .services
+        .iter()
+        .flat_map(|service| {
+            service
+                .results
+                .iter()
+                .filter(|r| {
+                    !r.success && !r.is_skipped() && !r.is_throttled() && !r.is_expected_failure()
+                })
+                .map(move |r| {
+                    format!(
+                        "{} / {}: {}",
+                        service.label(),
+                        r.scenario_name,
+                        r.error.as_deref().unwrap_or("failed")
+                    )
+                })
+        })
+        .take(MAX_LISTED_FAILURES)
+        .collect()
+}
+
+/// Request body for a notification endpoint
+pub fn payload(kind: NotifyKind, report: &TestReport, regressions: &[String]) -> serde_json::Value {
+    let headline = headline(report);
+    let failures = failure_lines(report);
+
+    match kind {
+        NotifyKind::Webhook => serde_json::json!({
+            "text": headline,
+            "regressions": regressions,
+            "report": report,
+        }),
+        NotifyKind::Slack => {
+            let mut text = format!("*{}*", headline);
+            if !regressions.is_empty() {
+                text.push_str(&format!("\nRegressed: {}", regressions.join(", ")));
+            }
+            for line in &failures {
+                text.push_str(&format!("\n• {}", line));
+            }
+            serde_json::json!({
+                "text": headline,
+                "blocks": [{
+                    "type": "section",
+                    "text": {"type": "mrkdwn", "text": text}
+                }]
+            })
+        }
+        NotifyKind::Teams => {
+            let summary = &report.summary;
+            let mut body = vec![
+                serde_json::json!({
+                    "type": "TextBlock",
+                    "text": headline,
+                    "weight": "Bolder",
+                    "wrap": true
+                }),
+                serde_json::json!({
+                    "type": "FactSet",
+                    "facts": [
+                        {"title": "Passed", "value": summary.passed.to_string()},
+                        {"title": "Failed", "value": summary.failed.to_string()},
+                        {"title": "Skipped", "value": summary.skipped.to_string()},
+                        {"title": "Duration", "value": format!("{}ms", report.total_duration_ms)}
+                    ]
+                }),
+            ];
+            if !regressions.is_empty() {
+                body.push(serde_json::json!({
+                    "type": "TextBlock",
+                    "text": format!("Regressed: {}", regressions.join(", ")),
+                    "color": "Attention",
+                    "wrap": true
+                }));
+            }
+            for line in &failures {
+                body.push(serde_json::json!({"type": "TextBlock", "text": line, "wrap": true}));
+            }
+            serde_json::json!({
+                "type": "message",
+                "attachments": [{
+                    "contentType": "application/vnd.microsoft.card.adaptive",
+                    "content": {
+                        "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+                        "type": "AdaptiveCard",
+                        "version": "1.4",
+                        "body": body
+                    }
+                }]
+            })
+        }
+    }
+}
+
+/// POST a notification
+pub async fn send(
+    target: &NotifyTarget,
+    report: &TestReport,
+    regressions: &[String],
+    timeout: Duration,
+) -> Result<()> {
+    // Keys echoed in error messages must not reach a chat channel
+    let body = crate::redact::redact(&payload(target.kind, report, regressions).to_string());
+    let client = crate::network::configure_client(reqwest::Client::builder())
+        .timeout(timeout)
+        .build()
+        .map_err(|e| AppError::Network(e.to_string()))?;
+    let response = client
+        .post(&target.url)
+        .header("Content-Type", "application/json")
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| {
+            AppError::Network(format!(
+                "Could not reach {}: {}",
+                target.describe(),
+                crate::redact::redact(&e.to_string())
+            ))
+        })?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(AppError::Network(format!(
+            "{} returned HTTP {}: {}",
+            target.describe(),
+            status,
+            crate::error::sanitize_error(&body, status.as_u16())
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{ServiceTestResults, TestResult};
+
+    fn report(tts_passes: bool) -> TestReport {
+        let tts = if tts_passes {
+            TestResult::success("tts", "Text-to-Speech", 700)
+        } else {
+            TestResult::failure("tts", "Text-to-Speech", 700, "HTTP 401".to_string())
+        };
+        TestReport::new(vec![ServiceTestResults {
+            service_name: "Speech".to_string(),
+            endpoint: "https://eastus.api.cognitive.microsoft.com".to_string(),
+            region: None,
+            target: None,
+            results: vec![
+                TestResult::success("voices_list", "Get Voices List", 500),
+                tts,
+            ],
+            total_duration_ms: 1200,
+        }])
+    }
+
+    #[test]
+    fn test_parse_notify_target() {
+        let target: NotifyTarget = "slack:https://hooks.slack.com/services/T0/B0/abcdefgh"
+            .parse()
+            .unwrap();
+        assert_eq!(target.kind, NotifyKind::Slack);
+        assert_eq!(
+            target.url,
+            "https://hooks.slack.com/services/T0/B0/abcdefgh"
+        );
+        assert_eq!(target.describe(), "Slack (hooks.slack.com)");
+
+        let target: NotifyTarget = "https://example.com/hook".parse().unwrap();
+        assert_eq!(target.kind, NotifyKind::Webhook);
+        assert!("teams:ftp://example.com".parse::<NotifyTarget>().is_err());
+    }
+
+    #[test]
+    fn test_regressions_and_payloads() {
+        let mut state = RunState::default();
+        state.update(&report(true));
+        let failing = report(false);
+        let regressions = state.regressions(&failing);
+        assert_eq!(regressions, vec!["Speech / Text-to-Speech".to_string()]);
+        assert!(should_notify(NotifyOn::Regression, true, &regressions));
+        assert!(!should_notify(NotifyOn::Regression, true, &[]));
+
+        let slack = payload(NotifyKind::Slack, &failing, &regressions);
+        assert_eq!(slack["text"], "Azure AI connectivity: 1/2 passed, 1 failed");
+        assert!(slack["blocks"][0]["text"]["text"]
+            .as_str()
+            .unwrap()
+            .contains("• Speech / Text-to-Speech: HTTP 401"));
+
+        let teams = payload(NotifyKind::Teams, &failing, &regressions);
+        assert_eq!(teams["attachments"][0]["content"]["type"], "AdaptiveCard");
+    }
+}
@@ -0,0 +1,152 @@
+//! Where and how a report was produced
+//!
+//! A report file attached to a ticket is often read weeks after the run, by
+//! someone who didn't start it. The metadata records the tool version, the
+//! host, the command line (secrets redacted), the configuration file it
+//! loaded, and what each service was tested with.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::{AuthMethod, Cloud};
+
+/// Run-level facts a report needs to explain itself
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunMetadata {
+    /// Version of azure-aitoolsconnect that produced the report
+    pub tool_version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    /// Arguments the run was started with, secrets redacted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub command_line: Vec<String>,
+    /// Configuration file the run loaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_file: Option<String>,
+    /// SHA-256 of the configuration file, comparable with `sha256sum`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_sha256: Option<String>,
+    /// What each service and target was tested with
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub services: Vec<ServiceRunInfo>,
+}
+
+/// Region, cloud, and authentication a service was tested with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceRunInfo {
+    /// Display name, as in the report's service results
+    pub service: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub region: String,
+    pub cloud: Cloud,
+    /// None for containers, which take no credentials
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_method: Option<AuthMethod>,
+}
+
+impl RunMetadata {
+    pub fn new(services: Vec<ServiceRunInfo>) -> Self {
+        Self {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            hostname: crate::environment::hostname(),
+            command_line: Vec::new(),
+            config_file: None,
+            config_sha256: None,
+            services,
+        }
+    }
+
+    /// Record the command line, redacted, and the configuration file loaded.
+    /// A file that can't be read again is named without a hash.
+    pub fn with_invocation(mut self, args: &[String], config_file: Option<&Path>) -> Self {
+        self.command_line = crate::redact::redact_args(args);
+        if let Some(path) = config_file {
+            self.config_file = Some(path.display().to_string());
+            self.config_sha256 = std::fs::read(path)
+                .ok()
+                .map(|content| format!("{:x}", Sha256::digest(content)));
+        }
+        self
+    }
+
+    /// The entry for a service's results: by name and target, and by region
+    /// when the results name one (sweeps)
+    pub fn service(
+        &self,
+        service: &str,
+        target: Option<&str>,
+        region: Option<&str>,
+    ) -> Option<&ServiceRunInfo> {
+        self.services.iter().find(|info| {
+            info.service == service
+                && info.target.as_deref() == target
+                && region.is_none_or(|region| info.region == region)
+        })
+    }
+
+    /// `name`/`value` pairs for report formats with properties (JUnit)
+    pub fn properties(&self) -> Vec<(&'static str, String)> {
+        let mut properties = vec![("tool_version", self.tool_version.clone())];
+        if let Some(hostname) = &self.hostname {
+            properties.push(("hostname", hostname.clone()));
+        }
+        if !self.command_line.is_empty() {
+            properties.push(("command_line", self.command_line.join(" ")));
+        }
+        if let Some(config_file) = &self.config_file {
+            properties.push(("config_file", config_file.clone()));
+        }
+        if let Some(sha256) = &self.config_sha256 {
+            properties.push(("config_sha256", sha256.clone()));
+        }
+        properties
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invocation_and_service_lookup() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config.toml");
+        std::fs::write(&config, "abc").unwrap();
+
+        let speech = |region: &str| ServiceRunInfo {
+            service: "Speech".to_string(),
+            target: None,
+            region: region.to_string(),
+            cloud: Cloud::Global,
+            auth_method: Some(AuthMethod::Key),
+        };
+        let args = [
+            "azure-aitoolsconnect",
+            "test",
+            "--api-key",
+            "secret-key-1234",
+        ]
+        .map(String::from);
+        let metadata = RunMetadata::new(vec![speech("eastus"), speech("westus")])
+            .with_invocation(&args, Some(&config));
+
+        assert_eq!(metadata.tool_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(metadata.command_line[3], "[REDACTED]");
+        assert_eq!(
+            metadata.config_sha256.as_deref(),
+            Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        assert_eq!(
+            metadata.service("Speech", None, Some("westus")),
+            Some(&speech("westus"))
+        );
+        assert_eq!(
+            metadata.service("Speech", None, None),
+            Some(&speech("eastus"))
+        );
+        assert_eq!(metadata.service("Speech", Some("prod"), None), None);
+    }
+}
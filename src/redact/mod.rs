@@ -0,0 +1,382 @@
+//! Central redaction of API keys, bearer tokens, and SAS signatures
+//!
+//! Text that leaves the process — formatted reports, log lines, captures, and
+//! error messages — passes through [`redact`] unless `--show-secrets` was
+//! given. Secrets the run knows about (API keys, client secrets, tokens) are
+//! registered with [`register_secret`] and replaced wherever they appear;
+//! bearer tokens, JWTs, and sensitive query parameters are also caught by
+//! shape, so a key echoed back in an error body or URL is removed too.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+use crate::auth::Credentials;
+use crate::config::Config;
+
+/// Replacement for redacted values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Headers whose values are always redacted
+const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "ocp-apim-subscription-key",
+    "api-key",
+    "x-api-key",
+    "cookie",
+    "set-cookie",
+];
+
+/// Query parameters whose values are always redacted
+const SENSITIVE_QUERY_PARAMS: &[&str] = &[
+    "subscription-key",
+    "api-key",
+    "key",
+    "code",
+    "sig",
+    "token",
+    "access_token",
+    "authorization",
+];
+
+/// Command-line flags whose value is a secret or contains one (webhook URLs)
+const SECRET_FLAGS: &[&str] = &[
+    "--api-key",
+    "--bearer-token",
+    "--arm-token",
+    "--notify-url",
+    "--notify",
+];
+
+/// Command-line flags whose value is a `Name: Value` header
+const HEADER_FLAGS: &[&str] = &["--header", "-H"];
+
+/// Registered values shorter than this are ignored; replacing them would
+/// mangle ordinary text
+const MIN_SECRET_LEN: usize = 8;
+
+static SHOW_SECRETS: AtomicBool = AtomicBool::new(false);
+static SECRETS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Turn redaction off for this process (`--show-secrets`)
+pub fn set_show_secrets(show: bool) {
+    SHOW_SECRETS.store(show, Ordering::Relaxed);
+}
+
+/// Whether `--show-secrets` was given
+pub fn show_secrets() -> bool {
+    SHOW_SECRETS.load(Ordering::Relaxed)
+}
+
+/// Remember a secret so it is replaced wherever it appears in output
+pub fn register_secret(secret: &str) {
+    let secret = secret.trim();
+    if secret.len() < MIN_SECRET_LEN || secret == REDACTED {
+        return;
+    }
+    if let Ok(mut secrets) = SECRETS.write() {
+        if !secrets.iter().any(|s| s == secret) {
+            secrets.push(secret.to_string());
+            // Longest first, so a secret containing another is replaced whole
+            secrets.sort_by_key(|s| std::cmp::Reverse(s.len()));
+        }
+    }
+}
+
+/// Register the keys, secrets, and tokens from a config file, including its
+/// `[profile.<name>]` sections (see [`Config::secrets_mut`])
+pub fn register_config_secrets(config: &Config) {
+    let mut config = config.clone();
+    for value in config
+        .secrets_mut()
+        .into_iter()
+        .filter_map(|v| v.as_deref())
+    {
+        if !crate::keyvault::is_reference(value) {
+            register_secret(value);
+        }
+    }
+}
+
+/// Register the key or token a run authenticates with
+pub fn register_credentials(credentials: &Credentials) {
+    match credentials {
+        Credentials::ApiKey(secret) | Credentials::BearerToken(secret) => register_secret(secret),
+    }
+}
+
+/// Redact secrets from text, unless `--show-secrets` was given
+pub fn redact(text: &str) -> String {
+    if show_secrets() {
+        return text.to_string();
+    }
+    let secrets = SECRETS.read().map(|s| s.clone()).unwrap_or_default();
+    redact_with(text, &secrets)
+}
+
+/// Redact `secrets` and anything shaped like a credential from `text`
+pub fn redact_with(text: &str, secrets: &[String]) -> String {
+    let mut text = text.to_string();
+    for secret in secrets {
+        if text.contains(secret.as_str()) {
+            text = text.replace(secret.as_str(), REDACTED);
+        }
+    }
+    let text = redact_query_params(&text);
+    let text = redact_bearer_tokens(&text);
+    redact_jwts(&text)
+}
+
+/// Redact a command line: the values of secret flags and sensitive headers,
+/// registered secrets, and anything shaped like a credential
+pub fn redact_args(args: &[String]) -> Vec<String> {
+    if show_secrets() {
+        return args.to_vec();
+    }
+    let redact_header = |header: &str| match header.split_once(':') {
+        Some((name, _)) if is_sensitive_header(name.trim(), &[]) => {
+            format!("{}: {}", name, REDACTED)
+        }
+        _ => redact(header),
+    };
+    let mut redacted = Vec::with_capacity(args.len());
+    let mut previous = "";
+    for arg in args {
+        let value = if SECRET_FLAGS.contains(&previous) {
+            REDACTED.to_string()
+        } else if HEADER_FLAGS.contains(&previous) {
+            redact_header(arg)
+        } else {
+            match arg.split_once('=') {
+                Some((flag, _)) if SECRET_FLAGS.contains(&flag) => {
+                    format!("{}={}", flag, REDACTED)
+                }
+                Some((flag, header)) if HEADER_FLAGS.contains(&flag) => {
+                    format!("{}={}", flag, redact_header(header))
+                }
+                _ => redact(arg),
+            }
+        };
+        redacted.push(value);
+        previous = arg;
+    }
+    redacted
+}
+
+/// Whether a header carries credentials
+pub fn is_sensitive_header(name: &str, extra_sensitive: &[String]) -> bool {
+    let name = name.to_lowercase();
+    SENSITIVE_HEADERS.contains(&name.as_str()) || extra_sensitive.contains(&name)
+}
+
+/// Redact sensitive header values and flatten to strings
+pub fn redact_headers(
+    headers: &reqwest::header::HeaderMap,
+    extra_sensitive: &[String],
+) -> std::collections::BTreeMap<String, String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let name = name.as_str().to_lowercase();
+            let value = if !show_secrets() && is_sensitive_header(&name, extra_sensitive) {
+                REDACTED.to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).to_string()
+            };
+            (name, value)
+        })
+        .collect()
+}
+
+/// Redact credentials and sensitive query parameters from a URL
+pub fn redact_url(url: &url::Url) -> String {
+    if show_secrets() {
+        return url.to_string();
+    }
+    let mut url = url.clone();
+    if !url.username().is_empty() || url.password().is_some() {
+        let _ = url.set_username("");
+        let _ = url.set_password(None);
+    }
+    if url.query().is_some() {
+        let pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(k, v)| {
+                let value = if SENSITIVE_QUERY_PARAMS.contains(&k.to_lowercase().as_str()) {
+                    REDACTED.to_string()
+                } else {
+                    v.to_string()
+                };
+                (k.to_string(), value)
+            })
+            .collect();
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    url.to_string()
+}
+
+/// Characters that end a value embedded in free text
+fn ends_value(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '&' | '"' | '\'' | '<' | '>' | ')' | '#' | ',' | '\\')
+}
+
+/// Replace the value after each match of `find(lower, from)`, where the match
+/// ends at the returned byte offset
+fn replace_values(text: &str, mut find: impl FnMut(&str, usize) -> Option<usize>) -> String {
+    // ASCII lowercasing keeps byte offsets valid for `text`
+    let lower = text.to_ascii_lowercase();
+    let mut output = String::with_capacity(text.len());
+    let mut copied = 0;
+    let mut from = 0;
+    while from < text.len() {
+        let Some(value_start) = find(&lower, from) else {
+            break;
+        };
+        let rest = &text[value_start..];
+        let value_len = rest.find(ends_value).unwrap_or(rest.len());
+        from = value_start + value_len.max(1);
+        if value_len == 0 || rest[..value_len].starts_with(REDACTED) {
+            continue;
+        }
+        output.push_str(&text[copied..value_start]);
+        output.push_str(REDACTED);
+        copied = value_start + value_len;
+    }
+    output.push_str(&text[copied..]);
+    output
+}
+
+/// `?sig=...`, `&subscription-key=...` and the like
+fn redact_query_params(text: &str) -> String {
+    replace_values(text, |lower, from| {
+        SENSITIVE_QUERY_PARAMS
+            .iter()
+            .filter_map(|param| {
+                let needle = format!("{}=", param);
+                let mut start = from;
+                while let Some(pos) = lower[start..].find(&needle) {
+                    let pos = start + pos;
+                    // `&amp;` covers URLs inside XML reports
+                    let before = &lower[..pos];
+                    if before.ends_with('?') || before.ends_with('&') || before.ends_with("&amp;") {
+                        return Some(pos + needle.len());
+                    }
+                    start = pos + needle.len();
+                }
+                None
+            })
+            .min()
+    })
+}
+
+/// `Bearer <token>`
+fn redact_bearer_tokens(text: &str) -> String {
+    replace_values(text, |lower, from| {
+        lower[from..]
+            .find("bearer ")
+            .map(|pos| from + pos + "bearer ".len())
+    })
+}
+
+/// Bare JWTs (`eyJ...` with three dot-separated parts)
+fn redact_jwts(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find("eyJ") {
+        output.push_str(&rest[..pos]);
+        let candidate = &rest[pos..];
+        let len = candidate
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+            .unwrap_or(candidate.len());
+        let token = &candidate[..len];
+        if token.split('.').filter(|part| !part.is_empty()).count() >= 3 {
+            output.push_str(REDACTED);
+        } else {
+            output.push_str(token);
+        }
+        rest = &candidate[len.max(3)..];
+    }
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_known_secrets() {
+        let secrets = vec!["0123456789abcdef0123456789abcdef".to_string()];
+        assert_eq!(
+            redact_with(
+                "HTTP 401: key 0123456789abcdef0123456789abcdef is invalid",
+                &secrets
+            ),
+            "HTTP 401: key [REDACTED] is invalid"
+        );
+    }
+
+    #[test]
+    fn test_redact_args() {
+        let args: Vec<String> = [
+            "azure-aitoolsconnect",
+            "test",
+            "--api-key",
+            "0123456789abcdef",
+            "--notify=slack:https://hooks.slack.com/services/T0/B0/XX",
+            "-H",
+            "Ocp-Apim-Subscription-Key: abc",
+            "--header=X-Trace: 1",
+            "--region",
+            "eastus",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        assert_eq!(
+            redact_args(&args),
+            vec![
+                "azure-aitoolsconnect",
+                "test",
+                "--api-key",
+                "[REDACTED]",
+                "--notify=[REDACTED]",
+                "-H",
+                "Ocp-Apim-Subscription-Key: [REDACTED]",
+                "--header=X-Trace: 1",
+                "--region",
+                "eastus",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_redact_by_shape() {
+        assert_eq!(
+            redact_with(
+                "error sending request for url (https://acct.blob.core.windows.net/c/a.wav?sv=2022-11-02&sig=abc%2Bdef&se=2030)",
+                &[]
+            ),
+            "error sending request for url (https://acct.blob.core.windows.net/c/a.wav?sv=2022-11-02&sig=[REDACTED]&se=2030)"
+        );
+        assert_eq!(
+            redact_with("https://gw/x?Subscription-Key=abc123", &[]),
+            "https://gw/x?Subscription-Key=[REDACTED]"
+        );
+        // Only query parameters, not words ending in "key="
+        assert_eq!(redact_with("monkey=1", &[]), "monkey=1");
+        assert_eq!(
+            redact_with("Authorization: Bearer abc.def.ghi", &[]),
+            "Authorization: Bearer [REDACTED]"
+        );
+        assert_eq!(
+            redact_with("token eyJhbGciOi.eyJzdWIiOi.c2lnbmF0dXJl expired", &[]),
+            "token [REDACTED] expired"
+        );
+        // Already redacted values and plain text are left alone
+        assert_eq!(
+            redact_with("sig=[REDACTED] and eyJ alone", &[]),
+            "sig=[REDACTED] and eyJ alone"
+        );
+    }
+}
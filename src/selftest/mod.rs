@@ -0,0 +1,374 @@
+//! Checks of the tool's own environment (`selftest` subcommand)
+//!
+//! Runs without credentials or network access and answers "is this build
+//! usable on this machine?": it builds the HTTP and TLS clients, binds the
+//! localhost listener used by interactive login, writes to the token cache
+//! and output directories, and verifies the assets compiled into the binary.
+
+use crate::auth::token_cache::TokenCacheFile;
+use crate::config::schema::{config_schema, parse_strict};
+use crate::config::Config;
+use crate::error::ExitCode;
+use crate::services::payloads;
+use serde::Serialize;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+
+pub use crate::doctor::StepStatus;
+
+/// Result of one self-test check
+#[derive(Debug, Clone, Serialize)]
+pub struct SelftestCheck {
+    pub name: &'static str,
+    pub status: StepStatus,
+    pub summary: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<String>,
+}
+
+impl SelftestCheck {
+    fn new(name: &'static str, status: StepStatus, summary: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            summary: summary.into(),
+            details: Vec::new(),
+        }
+    }
+
+    fn with_details(mut self, details: Vec<String>) -> Self {
+        self.details = details;
+        self
+    }
+}
+
+/// Full self-test report
+#[derive(Debug, Clone, Serialize)]
+pub struct SelftestReport {
+    pub version: &'static str,
+    pub checks: Vec<SelftestCheck>,
+}
+
+impl SelftestReport {
+    /// Whether every check passed or only warned
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.status != StepStatus::Fail)
+    }
+
+    /// `TestFailure` when any check failed
+    pub fn exit_code(&self) -> ExitCode {
+        if self.passed() {
+            ExitCode::Success
+        } else {
+            ExitCode::TestFailure
+        }
+    }
+}
+
+/// Run every check, writing output-file probes to `output_dir`
+pub fn run_selftest(output_dir: &Path) -> SelftestReport {
+    SelftestReport {
+        version: env!("CARGO_PKG_VERSION"),
+        checks: vec![
+            check_http_client(),
+            check_tls_roots(),
+            check_loopback_listener(),
+            check_token_cache(),
+            check_writable("Output files", output_dir),
+            check_assets(),
+        ],
+    }
+}
+
+/// The reqwest client every scenario uses can be built
+fn check_http_client() -> SelftestCheck {
+    match reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+    {
+        Ok(_) => SelftestCheck::new("HTTP client", StepStatus::Pass, "rustls client created"),
+        Err(e) => SelftestCheck::new(
+            "HTTP client",
+            StepStatus::Fail,
+            format!("Could not create the HTTP client: {}", e),
+        ),
+    }
+}
+
+/// The bundled Mozilla root store is present and loads into a TLS config
+fn check_tls_roots() -> SelftestCheck {
+    let roots = RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    if roots.is_empty() {
+        return SelftestCheck::new("CA roots", StepStatus::Fail, "No bundled CA roots");
+    }
+    let count = roots.len();
+    let provider = Arc::new(tokio_rustls::rustls::crypto::ring::default_provider());
+    match ClientConfig::builder_with_provider(provider).with_safe_default_protocol_versions() {
+        Ok(builder) => {
+            builder.with_root_certificates(roots).with_no_client_auth();
+            SelftestCheck::new(
+                "CA roots",
+                StepStatus::Pass,
+                format!("{} bundled root certificate(s)", count),
+            )
+            .with_details(vec![
+                "The OS certificate store is not used; a TLS-inspecting proxy's CA is not trusted"
+                    .to_string(),
+            ])
+        }
+        Err(e) => SelftestCheck::new(
+            "CA roots",
+            StepStatus::Fail,
+            format!("Could not build the TLS configuration: {}", e),
+        ),
+    }
+}
+
+/// Interactive login receives its redirect on a random 127.0.0.1 port
+fn check_loopback_listener() -> SelftestCheck {
+    let listener = match TcpListener::bind("127.0.0.1:0") {
+        Ok(listener) => listener,
+        Err(e) => {
+            return SelftestCheck::new(
+                "Localhost listener",
+                StepStatus::Fail,
+                format!("Cannot bind 127.0.0.1: {}", e),
+            )
+            .with_details(vec![
+                "`--auth interactive` will not work; use `--auth device-code`".into(),
+            ])
+        }
+    };
+    let addr = match listener.local_addr() {
+        Ok(addr) => addr,
+        Err(e) => {
+            return SelftestCheck::new(
+                "Localhost listener",
+                StepStatus::Fail,
+                format!("Listener has no address: {}", e),
+            )
+        }
+    };
+
+    let connected = TcpStream::connect_timeout(&addr, Duration::from_secs(2)).and_then(|mut s| {
+        s.write_all(b"ping")?;
+        let (mut accepted, _) = listener.accept()?;
+        let mut buf = [0u8; 4];
+        accepted.read_exact(&mut buf)?;
+        Ok(buf == *b"ping")
+    });
+    match connected {
+        Ok(true) => SelftestCheck::new(
+            "Localhost listener",
+            StepStatus::Pass,
+            format!("Bound and connected to {}", addr),
+        ),
+        Ok(false) => SelftestCheck::new(
+            "Localhost listener",
+            StepStatus::Fail,
+            format!("Data sent to {} was altered", addr),
+        ),
+        Err(e) => SelftestCheck::new(
+            "Localhost listener",
+            StepStatus::Fail,
+            format!("Bound {} but could not connect: {}", addr, e),
+        )
+        .with_details(vec![
+            "A local firewall may block loopback connections; the login redirect will not arrive"
+                .into(),
+        ]),
+    }
+}
+
+/// `login` stores tokens in the cache directory
+fn check_token_cache() -> SelftestCheck {
+    match TokenCacheFile::cache_dir() {
+        Some(dir) => {
+            if let Err(e) = std::fs::create_dir_all(&dir) {
+                return SelftestCheck::new(
+                    "Token cache",
+                    StepStatus::Fail,
+                    format!("Cannot create {}: {}", dir.display(), e),
+                );
+            }
+            check_writable("Token cache", &dir)
+        }
+        None => SelftestCheck::new(
+            "Token cache",
+            StepStatus::Warn,
+            "No cache directory (HOME/LOCALAPPDATA is not set)",
+        )
+        .with_details(vec!["Tokens from `login` will not be cached".into()]),
+    }
+}
+
+/// Write, read back and remove a probe file in `dir`
+fn check_writable(name: &'static str, dir: &Path) -> SelftestCheck {
+    let probe = dir.join(format!(
+        ".azure-aitoolsconnect-selftest-{}",
+        std::process::id()
+    ));
+    let result = std::fs::write(&probe, b"selftest").and_then(|_| std::fs::read(&probe));
+    let _ = std::fs::remove_file(&probe);
+    match result {
+        Ok(data) if data == b"selftest" => SelftestCheck::new(
+            name,
+            StepStatus::Pass,
+            format!("{} is writable", dir.display()),
+        ),
+        Ok(_) => SelftestCheck::new(
+            name,
+            StepStatus::Fail,
+            format!("File written to {} read back differently", dir.display()),
+        ),
+        Err(e) => SelftestCheck::new(
+            name,
+            StepStatus::Fail,
+            format!("Cannot write to {}: {}", dir.display(), e),
+        ),
+    }
+}
+
+/// Sample payloads, the config schema and the `init` template are intact
+fn check_assets() -> SelftestCheck {
+    let mut problems = Vec::new();
+
+    let samples: [(&str, &[u8], &[u8]); 3] = [
+        ("speech sample", payloads::SPEECH.data, b"RIFF"),
+        ("image sample", payloads::IMAGE.data, b"\x89PNG\r\n\x1a\n"),
+        ("document sample", payloads::DOCUMENT.data, b"%PDF-"),
+    ];
+    for (name, data, magic) in samples {
+        if !data.starts_with(magic) {
+            problems.push(format!("{} has an unexpected header", name));
+        }
+    }
+    if payloads::SPEECH.data.get(8..12) != Some(b"WAVE".as_slice()) {
+        problems.push("speech sample is not a WAVE file".to_string());
+    }
+
+    if serde_json::from_str::<serde_json::Value>(&config_schema()).is_err() {
+        problems.push("config schema is not valid JSON".to_string());
+    }
+
+    match Config::default_config().to_toml() {
+        Ok(toml) => match parse_strict(&toml) {
+            Ok((_, unknown)) if unknown.is_empty() => {}
+            Ok((_, unknown)) => problems.push(format!(
+                "init template has {} unknown key(s)",
+                unknown.len()
+            )),
+            Err(e) => problems.push(format!("init template does not parse: {}", e)),
+        },
+        Err(e) => problems.push(format!("init template cannot be rendered: {}", e)),
+    }
+
+    if problems.is_empty() {
+        SelftestCheck::new(
+            "Bundled assets",
+            StepStatus::Pass,
+            "Sample payloads, config schema and init template are intact",
+        )
+    } else {
+        SelftestCheck::new(
+            "Bundled assets",
+            StepStatus::Fail,
+            format!("{} problem(s) found", problems.len()),
+        )
+        .with_details(problems)
+    }
+}
+
+/// Human-readable report
+pub fn format_selftest(report: &SelftestReport, use_colors: bool) -> String {
+    use console::style;
+
+    let mut output = String::new();
+    output.push_str(&format!(
+        "\nazure-aitoolsconnect {} self-test\n",
+        report.version
+    ));
+    output.push_str("================================\n\n");
+
+    for check in &report.checks {
+        let status = match (check.status, use_colors) {
+            (StepStatus::Pass, true) => style("\u{2713}").green().to_string(),
+            (StepStatus::Warn, true) => style("!").yellow().to_string(),
+            (StepStatus::Fail, true) => style("\u{2717}").red().to_string(),
+            (StepStatus::Pass, false) => "[OK]  ".to_string(),
+            (StepStatus::Warn, false) => "[WARN]".to_string(),
+            (StepStatus::Fail, false) => "[FAIL]".to_string(),
+        };
+        output.push_str(&format!(
+            "  {} {:<19} {}\n",
+            status, check.name, check.summary
+        ));
+        for detail in &check.details {
+            if use_colors {
+                output.push_str(&format!("      {}\n", style(detail).dim()));
+            } else {
+                output.push_str(&format!("      {}\n", detail));
+            }
+        }
+    }
+
+    let failed = report
+        .checks
+        .iter()
+        .filter(|c| c.status == StepStatus::Fail)
+        .count();
+    let summary = if failed == 0 {
+        "All checks passed.".to_string()
+    } else {
+        format!("{} check(s) failed.", failed)
+    };
+    match (failed, use_colors) {
+        (0, true) => output.push_str(&format!("\n{}\n", style(summary).green().bold())),
+        (_, true) => output.push_str(&format!("\n{}\n", style(summary).red().bold())),
+        _ => output.push_str(&format!("\n{}\n", summary)),
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assets_are_intact() {
+        let check = check_assets();
+        assert_eq!(check.status, StepStatus::Pass, "{:?}", check.details);
+    }
+
+    #[test]
+    fn test_tls_roots_and_loopback() {
+        assert_eq!(check_tls_roots().status, StepStatus::Pass);
+        assert_eq!(check_loopback_listener().status, StepStatus::Pass);
+    }
+
+    #[test]
+    fn test_check_writable() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            check_writable("Output files", dir.path()).status,
+            StepStatus::Pass
+        );
+        // The probe is cleaned up
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        let missing = dir.path().join("missing");
+        let check = check_writable("Output files", &missing);
+        assert_eq!(check.status, StepStatus::Fail);
+        let report = SelftestReport {
+            version: "0",
+            checks: vec![check],
+        };
+        assert_eq!(report.exit_code(), ExitCode::TestFailure);
+        assert!(format_selftest(&report, false).contains("[FAIL] Output files"));
+    }
+}
@@ -0,0 +1,225 @@
+//! Cron expressions for scheduled runs
+//!
+//! The five standard fields: minute, hour, day of month, month, and day of
+//! week. Each is `*`, a value, a range `a-b`, a step `*/n` or `a-b/n`, or a
+//! comma-separated list of these. Day of week runs 0-6 from Sunday (7 is
+//! Sunday too). As in cron, when both day fields are restricted, a day
+//! matching either one fires. `@hourly`, `@daily`, and `@weekly` are
+//! accepted as shorthands. Times are UTC.
+
+use chrono::{DateTime, Datelike, Duration, DurationRound, Timelike, Utc};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::AppError;
+
+/// A parsed cron expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    expression: String,
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    weekdays: u8,
+    /// Whether day of month and day of week are restricted (not `*`)
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+impl Schedule {
+    /// First time after `after` (at a whole minute) the schedule fires;
+    /// `None` if it never does, e.g. `0 0 30 2 *`
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut time = after.duration_trunc(Duration::minutes(1)).ok()? + Duration::minutes(1);
+        // Every combination of days repeats within a few years
+        let limit = time + Duration::days(5 * 366);
+        while time < limit {
+            if !self.day_matches(&time) {
+                time = (time + Duration::days(1)).with_hour(0)?.with_minute(0)?;
+                continue;
+            }
+            if self.hours & (1 << time.hour()) == 0 {
+                time = (time + Duration::hours(1)).with_minute(0)?;
+                continue;
+            }
+            if self.minutes & (1 << time.minute()) == 0 {
+                time += Duration::minutes(1);
+                continue;
+            }
+            return Some(time);
+        }
+        None
+    }
+
+    fn day_matches(&self, time: &DateTime<Utc>) -> bool {
+        if self.months & (1 << time.month()) == 0 {
+            return false;
+        }
+        let day = self.days & (1 << time.day()) != 0;
+        let weekday = self.weekdays & (1 << time.weekday().num_days_from_sunday()) != 0;
+        match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            (true, false) => day,
+            (false, true) => weekday,
+            (false, false) => true,
+        }
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = AppError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: String| {
+            AppError::Config(format!(
+                "Invalid cron expression '{}': {}",
+                expression, reason
+            ))
+        };
+        let fields_text = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            other => other,
+        };
+        let fields: Vec<&str> = fields_text.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(invalid(format!(
+                "expected 5 fields (minute hour day-of-month month day-of-week), got {}",
+                fields.len()
+            )));
+        };
+
+        let mut weekdays = parse_field(weekday, 0, 7, "day of week").map_err(invalid)?;
+        // 7 is Sunday as well as 0
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        Ok(Self {
+            expression: expression.trim().to_string(),
+            minutes: parse_field(minute, 0, 59, "minute").map_err(invalid)?,
+            hours: parse_field(hour, 0, 23, "hour").map_err(invalid)? as u32,
+            days: parse_field(day, 1, 31, "day of month").map_err(invalid)? as u32,
+            months: parse_field(month, 1, 12, "month").map_err(invalid)? as u16,
+            weekdays: (weekdays & 0x7f) as u8,
+            days_restricted: day != "*",
+            weekdays_restricted: weekday != "*",
+        })
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.expression)
+    }
+}
+
+/// Bit set of the values `field` selects within `min..=max`
+fn parse_field(field: &str, min: u32, max: u32, name: &str) -> Result<u64, String> {
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(|| format!("invalid step '{}' in the {} field", step, name))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let value = |text: &str| -> Result<u32, String> {
+            text.parse()
+                .ok()
+                .filter(|v| (min..=max).contains(v))
+                .ok_or_else(|| format!("{} must be {}-{}, got '{}'", name, min, max, text))
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                // `5/15` runs from 5 to the end of the range
+                None if step > 1 => (value(range)?, max),
+                None => {
+                    let v = value(range)?;
+                    (v, v)
+                }
+            },
+        };
+        if start > end {
+            return Err(format!(
+                "range {} is backwards in the {} field",
+                range, name
+            ));
+        }
+        for v in (start..=end).step_by(step as usize) {
+            bits |= 1 << v;
+        }
+    }
+    Ok(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    #[test]
+    fn test_next_after() {
+        let every_5: Schedule = "*/5 * * * *".parse().unwrap();
+        assert_eq!(
+            every_5.next_after(at(2024, 3, 1, 10, 2)),
+            Some(at(2024, 3, 1, 10, 5))
+        );
+        assert_eq!(
+            every_5.next_after(at(2024, 3, 1, 10, 55)),
+            Some(at(2024, 3, 1, 11, 0))
+        );
+
+        // 2024-03-01 is a Friday; weekdays at 06:30
+        let weekdays: Schedule = "30 6 * * 1-5".parse().unwrap();
+        assert_eq!(
+            weekdays.next_after(at(2024, 3, 1, 7, 0)),
+            Some(at(2024, 3, 4, 6, 30))
+        );
+
+        // Either the 1st or a Sunday
+        let either: Schedule = "0 0 1 * 7".parse().unwrap();
+        assert_eq!(
+            either.next_after(at(2024, 3, 1, 0, 0)),
+            Some(at(2024, 3, 3, 0, 0))
+        );
+
+        let daily: Schedule = "@daily".parse().unwrap();
+        assert_eq!(
+            daily.next_after(at(2024, 12, 31, 23, 59)),
+            Some(at(2025, 1, 1, 0, 0))
+        );
+
+        let never: Schedule = "0 0 30 2 *".parse().unwrap();
+        assert_eq!(never.next_after(at(2024, 1, 1, 0, 0)), None);
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        for expression in [
+            "* * * *",
+            "60 * * * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "@often",
+        ] {
+            assert!(
+                expression.parse::<Schedule>().is_err(),
+                "{} should be rejected",
+                expression
+            );
+        }
+    }
+}
@@ -0,0 +1,1104 @@
+//! Scheduled runs with a status endpoint (`serve`)
+//!
+//! Runs the `[[serve.jobs]]` of the config file on their cron schedules and
+//! keeps each job's latest reports, so the tool can be deployed as a sidecar
+//! or VM agent for continuous connectivity checks. A small HTTP endpoint
+//! serves:
+//!
+//! - `/healthz`: the daemon is up (always 200), with each job's last outcome
+//! - `/last-report.json`: the most recent report (`?job=<name>` for one job)
+//! - `/history.json`: summaries of the retained reports
+//! - `/metrics`: run and scenario results in the Prometheus text format
+//!
+//! Instances in other network segments can act as agents: each report is
+//! tagged with the instance's `location` and sent to every `[upload]`
+//! target (see [`crate::upload`]). With `accept_reports`, an instance is the
+//! controller those agents POST to at `/reports`; it keeps each location's
+//! latest report, writes it to `history_dir`, and serves them side by side at
+//! `/matrix.json` (the `aggregate` matrix).
+//!
+//! Jobs run one at a time. A job whose slot passes while another runs starts
+//! when that one finishes; slots missed entirely are skipped. Each run's
+//! outcome and failed scenarios are logged as structured events
+//! (`--log-format json`).
+
+pub mod cron;
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::Instrument;
+
+use crate::aggregate::{LocationReport, Matrix};
+use crate::cli::parse_services;
+use crate::config::{AuthMethod, Config, ScheduledJob};
+use crate::error::{AppError, Result};
+use crate::output::TestReport;
+use crate::services::custom::CUSTOM_SERVICE_NAME;
+use crate::services::TestResult;
+use crate::testing::{TestRunner, TestRunnerConfig};
+use crate::upload::{UploadTarget, Uploader};
+use cron::Schedule;
+
+/// Address the status endpoint listens on by default
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:9464";
+/// Reports kept per job by default
+pub const DEFAULT_HISTORY: usize = 20;
+
+/// Prefix of every metric name
+const METRIC_PREFIX: &str = "azure_aitoolsconnect";
+/// Largest request head the status endpoint reads
+const MAX_HEAD_LEN: usize = 8 * 1024;
+/// Largest report an agent may POST
+const MAX_REPORT_LEN: usize = 16 * 1024 * 1024;
+
+/// Settings of the daemon
+#[derive(Debug, Clone)]
+pub struct ServeOptions {
+    /// Address of the status endpoint
+    pub listen: SocketAddr,
+    /// Reports kept per job, in memory and in `history_dir`
+    pub history: usize,
+    /// Directory each report is also written to
+    pub history_dir: Option<PathBuf>,
+    /// Run every job once at startup instead of waiting for its first slot
+    pub run_now: bool,
+    /// Location recorded in this instance's reports
+    pub location: String,
+    /// Where each report is sent
+    pub upload: Vec<UploadTarget>,
+    /// Accept reports POSTed by agents to `/reports`
+    pub accept_reports: bool,
+    /// Shared secret of agents and controller
+    pub token: Option<String>,
+}
+
+impl ServeOptions {
+    /// Options from the `[serve]` and `[upload]` sections, with defaults for unset values
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let listen = config.serve.listen.as_deref().unwrap_or(DEFAULT_LISTEN);
+        let upload = config.upload_settings();
+        Ok(Self {
+            listen: listen.parse().map_err(|_| {
+                AppError::Config(format!(
+                    "[serve] listen '{}' is not an address like {}",
+                    listen, DEFAULT_LISTEN
+                ))
+            })?,
+            history: config.serve.history.unwrap_or(DEFAULT_HISTORY).max(1),
+            history_dir: config.serve.history_dir.clone(),
+            run_now: false,
+            location: upload
+                .location
+                .unwrap_or_else(crate::upload::default_location),
+            upload: crate::upload::parse_targets(&upload.targets)?,
+            accept_reports: config.serve.accept_reports,
+            token: config.serve.token.clone(),
+        })
+    }
+}
+
+/// A job and its parsed schedule
+struct Job {
+    config: ScheduledJob,
+    schedule: Schedule,
+}
+
+/// Runs and reports of one job
+#[derive(Debug, Default)]
+struct JobState {
+    schedule: String,
+    next_run: Option<DateTime<Utc>>,
+    runs: u64,
+    /// Runs that couldn't start, e.g. without credentials
+    errors: u64,
+    last_error: Option<String>,
+    /// Retained reports, oldest first
+    reports: VecDeque<TestReport>,
+}
+
+/// Reports received from agents (`accept_reports`)
+#[derive(Debug, Default)]
+struct Ingest {
+    token: Option<String>,
+    history_dir: Option<PathBuf>,
+    received: u64,
+    /// Latest report of each location
+    reports: BTreeMap<String, TestReport>,
+}
+
+/// What the status endpoint serves
+#[derive(Debug)]
+pub struct ServeState {
+    started_at: DateTime<Utc>,
+    history: usize,
+    location: String,
+    jobs: BTreeMap<String, JobState>,
+    ingest: Option<Ingest>,
+}
+
+impl ServeState {
+    fn new(jobs: &[Job], options: &ServeOptions) -> Self {
+        Self {
+            started_at: Utc::now(),
+            history: options.history,
+            location: options.location.clone(),
+            ingest: options.accept_reports.then(|| Ingest {
+                token: options.token.clone(),
+                history_dir: options.history_dir.clone(),
+                ..Default::default()
+            }),
+            jobs: jobs
+                .iter()
+                .map(|job| {
+                    let state = JobState {
+                        schedule: job.schedule.to_string(),
+                        ..Default::default()
+                    };
+                    (job.config.name.clone(), state)
+                })
+                .collect(),
+        }
+    }
+
+    fn record(&mut self, job: &str, report: TestReport) {
+        let state = self.jobs.entry(job.to_string()).or_default();
+        state.runs += 1;
+        state.last_error = None;
+        state.reports.push_back(report);
+        while state.reports.len() > self.history {
+            state.reports.pop_front();
+        }
+    }
+
+    fn record_error(&mut self, job: &str, error: String) {
+        let state = self.jobs.entry(job.to_string()).or_default();
+        state.errors += 1;
+        state.last_error = Some(error);
+    }
+
+    /// Newest report of `job`, or of any job
+    fn last_report(&self, job: Option<&str>) -> Option<&TestReport> {
+        self.jobs
+            .iter()
+            .filter(|(name, _)| job.is_none_or(|job| job == name.as_str()))
+            .filter_map(|(_, state)| state.reports.back())
+            .max_by_key(|report| report.timestamp)
+    }
+
+    /// Keep an agent's report as the latest of its location
+    fn record_agent(&mut self, location: String, report: TestReport) {
+        if let Some(ingest) = &mut self.ingest {
+            ingest.received += 1;
+            ingest.reports.insert(location, report);
+        }
+    }
+
+    /// This instance's newest report and the latest report of each agent
+    fn matrix(&self) -> Option<Matrix> {
+        let own = self.last_report(None);
+        let mut reports: Vec<LocationReport> = own
+            .map(|report| LocationReport {
+                location: self.location.clone(),
+                report: report.clone(),
+            })
+            .into_iter()
+            .collect();
+        reports.extend(
+            self.ingest
+                .iter()
+                .flat_map(|ingest| &ingest.reports)
+                .filter(|(location, _)| own.is_none() || **location != self.location)
+                .map(|(location, report)| LocationReport {
+                    location: location.clone(),
+                    report: report.clone(),
+                }),
+        );
+        (!reports.is_empty()).then(|| Matrix::new(&reports))
+    }
+}
+
+/// A job's status in `/healthz`
+#[derive(Serialize)]
+struct JobStatus<'a> {
+    name: &'a str,
+    schedule: &'a str,
+    next_run: Option<DateTime<Utc>>,
+    runs: u64,
+    errors: u64,
+    last_run: Option<DateTime<Utc>>,
+    last_passed: Option<bool>,
+    last_error: Option<&'a str>,
+}
+
+/// A retained report in `/history.json`
+#[derive(Serialize)]
+struct HistoryEntry<'a> {
+    job: &'a str,
+    timestamp: DateTime<Utc>,
+    total: usize,
+    passed: usize,
+    failed: usize,
+    skipped: usize,
+    duration_ms: u64,
+}
+
+/// Run the configured jobs on their schedules and serve their status until
+/// interrupted (Ctrl+C)
+pub async fn serve(config: &Config, options: ServeOptions) -> Result<()> {
+    if config.serve.jobs.is_empty() && !options.accept_reports {
+        return Err(AppError::Config(
+            "No [[serve.jobs]] configured; add a job with a name, schedule, and services, \
+             or accept agents' reports with --accept-reports"
+                .to_string(),
+        ));
+    }
+    if !config.serve.jobs.is_empty()
+        && matches!(
+            config.auth.default_method,
+            AuthMethod::DeviceCode | AuthMethod::Interactive
+        )
+    {
+        return Err(AppError::Config(format!(
+            "serve runs unattended and can't use {} auth; use key, token, \
+             service_principal, or managed_identity",
+            config.auth.default_method
+        )));
+    }
+    let now = Utc::now();
+    let mut jobs = Vec::new();
+    for job in &config.serve.jobs {
+        let schedule: Schedule = job.schedule.parse()?;
+        if schedule.next_after(now).is_none() {
+            return Err(AppError::Config(format!(
+                "Job '{}': schedule '{}' never fires",
+                job.name, schedule
+            )));
+        }
+        jobs.push(Job {
+            config: job.clone(),
+            schedule,
+        });
+    }
+    if let Some(dir) = &options.history_dir {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let state = Arc::new(Mutex::new(ServeState::new(&jobs, &options)));
+    let listener = TcpListener::bind(options.listen).await?;
+    tracing::info!(listen = %options.listen, jobs = jobs.len(), "status endpoint listening");
+    let server = tokio::spawn(serve_status(listener, state.clone()));
+
+    let mut next: Vec<Option<DateTime<Utc>>> = jobs
+        .iter()
+        .map(|job| {
+            if options.run_now {
+                Some(now)
+            } else {
+                job.schedule.next_after(now)
+            }
+        })
+        .collect();
+    loop {
+        {
+            let mut state = state.lock().unwrap();
+            for (job, next_run) in jobs.iter().zip(&next) {
+                if let Some(job_state) = state.jobs.get_mut(&job.config.name) {
+                    job_state.next_run = *next_run;
+                }
+            }
+        }
+        let Some((index, due)) = next
+            .iter()
+            .enumerate()
+            .filter_map(|(index, due)| due.map(|due| (index, due)))
+            .min_by_key(|(_, due)| *due)
+        else {
+            // A controller keeps collecting reports after its last run
+            if options.accept_reports {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+            break;
+        };
+        let wait = (due - Utc::now()).to_std().unwrap_or_default();
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+
+        let job = &jobs[index];
+        tokio::select! {
+            _ = run_job(config, &job.config, &state, &options) => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+        next[index] = job.schedule.next_after(Utc::now().max(due));
+    }
+
+    server.abort();
+    tracing::info!("serve stopped");
+    Ok(())
+}
+
+/// Run one job, log its outcome, and keep its report
+async fn run_job(
+    config: &Config,
+    job: &ScheduledJob,
+    state: &Mutex<ServeState>,
+    options: &ServeOptions,
+) {
+    // Scenario results are logged by the runner, tagged with the job
+    let span = tracing::info_span!("scheduled_run", job = %job.name);
+    tracing::info!(job = %job.name, "scheduled run starting");
+    let mut report = match run_suite(config, job).instrument(span).await {
+        Ok(report) => report.with_location(options.location.clone()),
+        Err(e) => {
+            tracing::error!(job = %job.name, error = %e, "scheduled run could not start");
+            state.lock().unwrap().record_error(&job.name, e.to_string());
+            return;
+        }
+    };
+
+    let summary = &report.summary;
+    tracing::info!(
+        job = %job.name,
+        total = summary.total,
+        passed = summary.passed,
+        failed = summary.failed,
+        skipped = summary.skipped,
+        duration_ms = report.total_duration_ms,
+        "scheduled run finished"
+    );
+
+    let timeout = Duration::from_secs(config.global.timeout_seconds);
+    if !options.upload.is_empty() {
+        // The egress IP tells the sites apart in the controller's matrix
+        let cloud = report.cloud.unwrap_or(config.global.cloud);
+        report = report.with_environment(
+            crate::environment::capture(config.global.ip_echo_url(), cloud, timeout).await,
+        );
+    }
+    if let Some(dir) = &options.history_dir {
+        if let Err(e) = save_report(dir, &job.name, &report, options.history) {
+            tracing::warn!(job = %job.name, error = %e, "could not write report to history directory");
+        }
+    }
+    if !options.upload.is_empty() {
+        // A failed delivery is logged; the report is still kept here. The
+        // uploader is made per run so a Storage token never goes stale
+        match Uploader::new(config, &options.upload, timeout, true).await {
+            Ok(uploader) => {
+                for target in &options.upload {
+                    match uploader.upload(target, &report).await {
+                        Ok(()) => {
+                            tracing::info!(job = %job.name, target = %target.describe(), "report sent")
+                        }
+                        Err(e) => {
+                            tracing::warn!(job = %job.name, target = %target.describe(), error = %e, "could not send report")
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::warn!(job = %job.name, error = %e, "could not get a token for uploads")
+            }
+        }
+    }
+    state.lock().unwrap().record(&job.name, report);
+}
+
+/// Run a job's test suite once
+async fn run_suite(config: &Config, job: &ScheduledJob) -> Result<TestReport> {
+    let mut services = parse_services(&job.services);
+    let all_requested = job.services.len() == 1 && job.services[0].eq_ignore_ascii_case("all");
+    if all_requested {
+        if !config.custom_scenarios.is_empty() {
+            services.push(CUSTOM_SERVICE_NAME.to_string());
+        }
+        services.extend(
+            config
+                .custom_services
+                .keys()
+                .map(|name| name.to_lowercase().replace('-', "_")),
+        );
+    }
+
+    let mut builder = TestRunnerConfig::builder()
+        .config(config)
+        .services(services)
+        .skip_scenarios(job.skip_scenarios.clone())
+        .quiet(true);
+    if let Some(profile) = &job.profile {
+        builder = builder.profile(profile);
+    }
+    if let Some(scenarios) = &job.scenarios {
+        builder = builder.scenarios(scenarios.clone());
+    }
+    if let Some(region) = &job.region {
+        builder = builder.region(region);
+    }
+    if let Some(endpoint) = &job.endpoint {
+        builder = builder.endpoint(endpoint);
+    }
+    let mut runner_config = builder.build();
+    runner_config.regions = job.regions.clone();
+    if !job.targets.is_empty() {
+        runner_config.select_targets(job.targets.clone())?;
+    }
+    runner_config.resolve_secrets().await?;
+    runner_config.check_endpoints()?;
+
+    let region = runner_config.region.clone();
+    let cloud = runner_config.cloud;
+    let report = TestRunner::new(runner_config).run().await?;
+    Ok(report.with_run_context(region, cloud))
+}
+
+/// Write `report` as `<job>-<timestamp>.json`, keeping the newest `keep` of the job's files
+fn save_report(dir: &Path, job: &str, report: &TestReport, keep: usize) -> Result<()> {
+    let path = dir.join(format!(
+        "{}-{}.json",
+        job,
+        report.timestamp.format("%Y%m%dT%H%M%SZ")
+    ));
+    std::fs::write(&path, serde_json::to_string_pretty(report)?)?;
+
+    let prefix = format!("{}-", job);
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.strip_prefix(&prefix)
+                        .and_then(|rest| rest.strip_suffix(".json"))
+                        .is_some_and(|stamp| stamp.len() == 16 && stamp.ends_with('Z'))
+                })
+        })
+        .collect();
+    files.sort();
+    let excess = files.len().saturating_sub(keep);
+    for old in &files[..excess] {
+        std::fs::remove_file(old)?;
+    }
+    Ok(())
+}
+
+/// Accept status requests until the task is aborted
+async fn serve_status(listener: TcpListener, state: Arc<Mutex<ServeState>>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        let state = state.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, &state).await {
+                tracing::debug!(error = %e, "status connection failed");
+            }
+        });
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, state: &Mutex<ServeState>) -> Result<()> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 8192];
+    let head_end = loop {
+        if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end + 4;
+        }
+        if request.len() >= MAX_HEAD_LEN {
+            break request.len();
+        }
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            break request.len();
+        }
+        request.extend_from_slice(&buffer[..read]);
+    };
+    let head = String::from_utf8_lossy(&request[..head_end]).into_owned();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default();
+    let target = request_line.next().unwrap_or("/");
+    let headers: Vec<(&str, &str)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .collect();
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| *value)
+    };
+
+    let (status, content_type, body) = if method == "POST" {
+        let length: usize = header("content-length")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        if length > MAX_REPORT_LEN {
+            error_response(413, "report too large")
+        } else {
+            let mut body = request[head_end..].to_vec();
+            while body.len() < length {
+                let read = stream.read(&mut buffer).await?;
+                if read == 0 {
+                    break;
+                }
+                body.extend_from_slice(&buffer[..read]);
+            }
+            body.truncate(length);
+            receive(
+                &mut state.lock().unwrap(),
+                target,
+                header("authorization"),
+                &body,
+            )
+        }
+    } else {
+        respond(&state.lock().unwrap(), method, target)
+    };
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        match status {
+            200 => "OK",
+            202 => "Accepted",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            403 => "Forbidden",
+            404 => "Not Found",
+            413 => "Payload Too Large",
+            _ => "Method Not Allowed",
+        },
+        content_type,
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// A JSON error response
+fn error_response(status: u16, message: &str) -> (u16, &'static str, String) {
+    (
+        status,
+        "application/json",
+        serde_json::json!({ "error": message }).to_string(),
+    )
+}
+
+/// Status, content type, and body for a report POSTed by an agent
+fn receive(
+    state: &mut ServeState,
+    target: &str,
+    authorization: Option<&str>,
+    body: &[u8],
+) -> (u16, &'static str, String) {
+    let path = target.split_once('?').map_or(target, |(path, _)| path);
+    if path != "/reports" {
+        return error_response(405, "only GET is supported, and POST to /reports");
+    }
+    let Some(ingest) = &state.ingest else {
+        return error_response(
+            403,
+            "this instance doesn't accept reports; start serve with --accept-reports",
+        );
+    };
+    if let Some(token) = &ingest.token {
+        if authorization.and_then(|v| v.strip_prefix("Bearer ")) != Some(token.as_str()) {
+            return error_response(401, "missing or wrong bearer token");
+        }
+    }
+    let report = match crate::aggregate::parse_report(&String::from_utf8_lossy(body)) {
+        Ok(report) => report,
+        Err(e) => return error_response(400, &format!("not a report: {}", e)),
+    };
+    let Some(location) = report.location.clone() else {
+        return error_response(
+            400,
+            "the report has no location; send it with 'test --location' or [upload] location",
+        );
+    };
+
+    if let Some(dir) = &ingest.history_dir {
+        let name = format!("agent-{}", crate::upload::safe_name(&location));
+        if let Err(e) = save_report(dir, &name, &report, state.history) {
+            tracing::warn!(location = %location, error = %e, "could not write report to history directory");
+        }
+    }
+    tracing::info!(
+        location = %location,
+        total = report.summary.total,
+        passed = report.summary.passed,
+        failed = report.summary.failed,
+        "report received"
+    );
+    state.record_agent(location.clone(), report);
+    (
+        202,
+        "application/json",
+        serde_json::json!({ "location": location }).to_string(),
+    )
+}
+
+/// Status, content type, and body for a request to the status endpoint
+fn respond(state: &ServeState, method: &str, target: &str) -> (u16, &'static str, String) {
+    const JSON: &str = "application/json";
+    let error = error_response;
+    if method != "GET" {
+        return error(405, "only GET is supported, and POST to /reports");
+    }
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let job = url::form_urlencoded::parse(query.as_bytes())
+        .find(|(name, _)| name == "job")
+        .map(|(_, value)| value.into_owned());
+    if let Some(job) = &job {
+        if !state.jobs.contains_key(job) {
+            return error(404, &format!("no job named '{}'", job));
+        }
+    }
+
+    match path {
+        "/healthz" => {
+            let jobs: Vec<JobStatus> = state
+                .jobs
+                .iter()
+                .map(|(name, job)| JobStatus {
+                    name,
+                    schedule: &job.schedule,
+                    next_run: job.next_run,
+                    runs: job.runs,
+                    errors: job.errors,
+                    last_run: job.reports.back().map(|r| r.timestamp),
+                    last_passed: job.reports.back().map(|r| r.all_passed()),
+                    last_error: job.last_error.as_deref(),
+                })
+                .collect();
+            let mut body = serde_json::json!({
+                "status": "ok",
+                "started_at": state.started_at,
+                "location": state.location,
+                "jobs": jobs,
+            });
+            if let Some(ingest) = &state.ingest {
+                let agents: Vec<serde_json::Value> = ingest
+                    .reports
+                    .iter()
+                    .map(|(location, report)| {
+                        serde_json::json!({
+                            "location": location,
+                            "last_report": report.timestamp,
+                            "last_passed": report.all_passed(),
+                        })
+                    })
+                    .collect();
+                body["reports_received"] = ingest.received.into();
+                body["agents"] = agents.into();
+            }
+            (200, JSON, body.to_string())
+        }
+        "/last-report.json" => match state.last_report(job.as_deref()) {
+            Some(report) => (
+                200,
+                JSON,
+                serde_json::to_string_pretty(report).unwrap_or_default(),
+            ),
+            None => error(404, "no run has finished yet"),
+        },
+        "/history.json" => {
+            let entries: Vec<HistoryEntry> = state
+                .jobs
+                .iter()
+                .filter(|(name, _)| job.as_deref().is_none_or(|job| job == name.as_str()))
+                .flat_map(|(name, job)| {
+                    job.reports.iter().map(move |report| HistoryEntry {
+                        job: name,
+                        timestamp: report.timestamp,
+                        total: report.summary.total,
+                        passed: report.summary.passed,
+                        failed: report.summary.failed,
+                        skipped: report.summary.skipped,
+                        duration_ms: report.total_duration_ms,
+                    })
+                })
+                .collect();
+            (
+                200,
+                JSON,
+                serde_json::to_string(&entries).unwrap_or_default(),
+            )
+        }
+        "/matrix.json" => match state.matrix() {
+            Some(matrix) => (
+                200,
+                JSON,
+                serde_json::to_string_pretty(&matrix).unwrap_or_default(),
+            ),
+            None => error(404, "no report yet"),
+        },
+        "/metrics" => (200, "text/plain; version=0.0.4", format_metrics(state)),
+        _ => error(
+            404,
+            "not found; try /healthz, /last-report.json, /history.json, /matrix.json, or /metrics",
+        ),
+    }
+}
+
+/// Runs and the last report of each job, and the latest report of each
+/// agent, in the Prometheus text format
+pub fn format_metrics(state: &ServeState) -> String {
+    let mut output = String::new();
+    let mut family = |name: &str, kind: &str, help: &str, samples: Vec<(String, String)>| {
+        let _ = writeln!(output, "# HELP {}_{} {}", METRIC_PREFIX, name, help);
+        let _ = writeln!(output, "# TYPE {}_{} {}", METRIC_PREFIX, name, kind);
+        for (labels, value) in samples {
+            if labels.is_empty() {
+                let _ = writeln!(output, "{}_{} {}", METRIC_PREFIX, name, value);
+            } else {
+                let _ = writeln!(output, "{}_{}{{{}}} {}", METRIC_PREFIX, name, labels, value);
+            }
+        }
+    };
+    let per_job = |value: &dyn Fn(&JobState) -> Option<String>| -> Vec<(String, String)> {
+        state
+            .jobs
+            .iter()
+            .filter_map(|(name, job)| value(job).map(|v| (labels(&[("job", name)]), v)))
+            .collect()
+    };
+
+    family(
+        "runs_total",
+        "counter",
+        "Scheduled runs that finished",
+        per_job(&|job| Some(job.runs.to_string())),
+    );
+    family(
+        "run_errors_total",
+        "counter",
+        "Scheduled runs that could not start",
+        per_job(&|job| Some(job.errors.to_string())),
+    );
+    family(
+        "last_run_timestamp_seconds",
+        "gauge",
+        "When the job's last run finished",
+        per_job(&|job| {
+            job.reports
+                .back()
+                .map(|r| r.timestamp.timestamp().to_string())
+        }),
+    );
+    family(
+        "last_run_success",
+        "gauge",
+        "Whether the job's last run had no failed scenarios",
+        per_job(&|job| {
+            job.reports
+                .back()
+                .map(|r| u8::from(r.all_passed()).to_string())
+        }),
+    );
+    family(
+        "last_run_duration_seconds",
+        "gauge",
+        "Duration of the job's last run",
+        per_job(&|job| job.reports.back().map(|r| seconds(r.total_duration_ms))),
+    );
+
+    let scenarios: Vec<(String, &TestResult)> = state
+        .jobs
+        .iter()
+        .filter_map(|(name, job)| job.reports.back().map(|report| (name, report)))
+        .flat_map(|(name, report)| scenario_samples(("job", name), report))
+        .collect();
+    family(
+        "scenario_success",
+        "gauge",
+        "Whether the scenario passed in the job's last run (skipped scenarios are left out)",
+        scenarios
+            .iter()
+            .map(|(labels, result)| (labels.clone(), u8::from(!is_failure(result)).to_string()))
+            .collect(),
+    );
+    family(
+        "scenario_duration_seconds",
+        "gauge",
+        "Duration of the scenario in the job's last run",
+        scenarios
+            .iter()
+            .map(|(labels, result)| (labels.clone(), seconds(result.duration_ms)))
+            .collect(),
+    );
+
+    let Some(ingest) = &state.ingest else {
+        return output;
+    };
+    let per_agent = |value: &dyn Fn(&TestReport) -> String| -> Vec<(String, String)> {
+        ingest
+            .reports
+            .iter()
+            .map(|(location, report)| (labels(&[("location", location)]), value(report)))
+            .collect()
+    };
+    family(
+        "agent_reports_total",
+        "counter",
+        "Reports received from agents",
+        vec![(String::new(), ingest.received.to_string())],
+    );
+    family(
+        "agent_last_report_timestamp_seconds",
+        "gauge",
+        "When the location's latest report was run",
+        per_agent(&|report| report.timestamp.timestamp().to_string()),
+    );
+    family(
+        "agent_last_report_success",
+        "gauge",
+        "Whether the location's latest report had no failed scenarios",
+        per_agent(&|report| u8::from(report.all_passed()).to_string()),
+    );
+    family(
+        "agent_scenario_success",
+        "gauge",
+        "Whether the scenario passed in the location's latest report (skipped scenarios are left out)",
+        ingest
+            .reports
+            .iter()
+            .flat_map(|(location, report)| scenario_samples(("location", location), report))
+            .map(|(labels, result)| (labels, u8::from(!is_failure(result)).to_string()))
+            .collect(),
+    );
+    output
+}
+
+/// Labels of each scenario `report` ran, after `source` (the job or location)
+fn scenario_samples<'a>(
+    source: (&str, &str),
+    report: &'a TestReport,
+) -> Vec<(String, &'a TestResult)> {
+    let mut samples = Vec::new();
+    for service in &report.services {
+        let region = service
+            .region
+            .as_deref()
+            .or(report.region.as_deref())
+            .unwrap_or_default();
+        for result in service.results.iter().filter(|r| !r.is_skipped()) {
+            let labels = labels(&[
+                source,
+                ("service", &service.service_name),
+                ("scenario", &result.scenario_id),
+                ("region", region),
+                ("target", service.target.as_deref().unwrap_or_default()),
+            ]);
+            samples.push((labels, result));
+        }
+    }
+    samples
+}
+
+/// Failed, and counted as a failure (not skipped, throttled, or expected)
+fn is_failure(result: &TestResult) -> bool {
+    !result.success
+        && !result.is_skipped()
+        && !result.is_throttled()
+        && !result.is_expected_failure()
+}
+
+fn seconds(ms: u64) -> String {
+    format!("{:.3}", ms as f64 / 1000.0)
+}
+
+/// `name="value",...` with values escaped for the Prometheus text format
+fn labels(pairs: &[(&str, &str)]) -> String {
+    pairs
+        .iter()
+        .map(|(name, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", name, value)
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::ServiceTestResults;
+
+    fn state() -> ServeState {
+        let jobs = [Job {
+            config: ScheduledJob {
+                name: "smoke".to_string(),
+                schedule: "*/5 * * * *".to_string(),
+                services: vec!["speech".to_string()],
+                profile: None,
+                scenarios: None,
+                skip_scenarios: Vec::new(),
+                region: None,
+                regions: Vec::new(),
+                targets: Vec::new(),
+                endpoint: None,
+            },
+            schedule: "*/5 * * * *".parse().unwrap(),
+        }];
+        let options = ServeOptions {
+            listen: DEFAULT_LISTEN.parse().unwrap(),
+            history: 2,
+            history_dir: None,
+            run_now: false,
+            location: "hq".to_string(),
+            upload: Vec::new(),
+            accept_reports: true,
+            token: Some("s3cret".to_string()),
+        };
+        let mut state = ServeState::new(&jobs, &options);
+        for _ in 0..3 {
+            let report = TestReport::new(vec![ServiceTestResults {
+                service_name: "Speech".to_string(),
+                endpoint: "https://eastus.api.cognitive.microsoft.com".to_string(),
+                region: None,
+                target: None,
+                total_duration_ms: 300,
+                results: vec![
+                    TestResult::success("voices_list", "Get Voices List", 250),
+                    TestResult::failure("tts", "Text to Speech", 50, "HTTP 403".to_string()),
+                ],
+            }])
+            .with_run_context("eastus".to_string(), crate::config::Cloud::Global);
+            state.record("smoke", report);
+        }
+        state
+    }
+
+    #[test]
+    fn test_history_is_capped() {
+        let state = state();
+        assert_eq!(state.jobs["smoke"].runs, 3);
+        assert_eq!(state.jobs["smoke"].reports.len(), 2);
+    }
+
+    #[test]
+    fn test_format_metrics() {
+        let metrics = format_metrics(&state());
+        assert!(metrics.contains("azure_aitoolsconnect_runs_total{job=\"smoke\"} 3\n"));
+        assert!(metrics.contains("azure_aitoolsconnect_last_run_success{job=\"smoke\"} 0\n"));
+        assert!(metrics.contains(
+            "azure_aitoolsconnect_scenario_success{job=\"smoke\",service=\"Speech\",\
+             scenario=\"voices_list\",region=\"eastus\",target=\"\"} 1\n"
+        ));
+        assert!(metrics.contains("scenario=\"tts\",region=\"eastus\",target=\"\"} 0.050\n"));
+        assert_eq!(labels(&[("job", "a\"b")]), "job=\"a\\\"b\"");
+    }
+
+    #[test]
+    fn test_respond() {
+        let state = state();
+        let (status, _, body) = respond(&state, "GET", "/healthz");
+        assert_eq!(status, 200);
+        assert!(body.contains("\"last_passed\":false"));
+
+        let (status, _, body) = respond(&state, "GET", "/last-report.json?job=smoke");
+        assert_eq!(status, 200);
+        assert!(body.contains("voices_list"));
+
+        let (_, _, body) = respond(&state, "GET", "/history.json");
+        let history: Vec<serde_json::Value> = serde_json::from_str(&body).unwrap();
+        assert_eq!(history.len(), 2);
+
+        assert_eq!(respond(&state, "GET", "/last-report.json?job=other").0, 404);
+        assert_eq!(respond(&state, "GET", "/nope").0, 404);
+        assert_eq!(respond(&state, "POST", "/healthz").0, 405);
+    }
+
+    #[test]
+    fn test_receive_agent_report() {
+        let mut state = state();
+        let report = state.last_report(None).unwrap().clone();
+        let body = serde_json::to_vec(&report).unwrap();
+        let token = Some("Bearer s3cret");
+
+        assert_eq!(receive(&mut state, "/reports", None, &body).0, 401);
+        // Without a location the controller can't place the report
+        let (status, _, message) = receive(&mut state, "/reports", token, &body);
+        assert_eq!(status, 400);
+        assert!(message.contains("[upload] location"), "{}", message);
+        assert_eq!(receive(&mut state, "/healthz", token, &body).0, 405);
+
+        let branch = serde_json::to_vec(&report.with_location("branch".to_string())).unwrap();
+        let (status, _, _) = receive(&mut state, "/reports", token, &branch);
+        assert_eq!(status, 202);
+
+        let (_, _, body) = respond(&state, "GET", "/healthz");
+        assert!(body.contains("\"location\":\"branch\""));
+        let (status, _, body) = respond(&state, "GET", "/matrix.json");
+        assert_eq!(status, 200);
+        let matrix: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(matrix["locations"][0]["name"], "hq");
+        assert_eq!(matrix["locations"][1]["name"], "branch");
+        assert!(format_metrics(&state).contains(
+            "azure_aitoolsconnect_agent_scenario_success{location=\"branch\",service=\"Speech\",\
+             scenario=\"tts\",region=\"eastus\",target=\"\"} 0\n"
+        ));
+
+        state.ingest = None;
+        assert_eq!(receive(&mut state, "/reports", token, &branch).0, 403);
+    }
+
+    #[test]
+    fn test_deprecated_agent_settings_still_upload() {
+        let (config, unknown) = crate::config::schema::parse_strict(
+            r#"
+            [serve]
+            location = "branch"
+            report_to = ["http://controller:9464/reports"]
+            token = "s3cret"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(unknown, []);
+        let options = ServeOptions::from_config(&config).unwrap();
+        assert_eq!(options.location, "branch");
+        assert_eq!(
+            options.upload,
+            [UploadTarget::Controller(
+                "http://controller:9464/reports".to_string()
+            )]
+        );
+        assert_eq!(config.upload_settings().token.as_deref(), Some("s3cret"));
+        let warnings = crate::config::validate_config(&config).unwrap();
+        assert!(warnings
+            .iter()
+            .any(|w| w.contains("report_to is deprecated")));
+    }
+
+    #[test]
+    fn test_save_report_prunes_old_files() {
+        let dir = tempfile::tempdir().unwrap();
+        for stamp in ["20240101T000000Z", "20240101T000500Z", "20240101T001000Z"] {
+            std::fs::write(dir.path().join(format!("smoke-{}.json", stamp)), "{}").unwrap();
+        }
+        std::fs::write(dir.path().join("smoke-notes.json"), "{}").unwrap();
+        let report = state().last_report(None).unwrap().clone();
+        save_report(dir.path(), "smoke", &report, 2).unwrap();
+
+        let mut names: Vec<String> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names.len(), 3);
+        assert_eq!(names[0], "smoke-20240101T001000Z.json");
+        assert!(names.contains(&"smoke-notes.json".to_string()));
+    }
+}
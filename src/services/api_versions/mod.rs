@@ -0,0 +1,269 @@
+//! API versions of the data-plane routes scenarios call
+//!
+//! Every versioned route is registered here with its GA version and, where
+//! one exists, a preview version. `test --preview-apis` switches scenarios to
+//! the preview versions, since gateways and firewalls sometimes route or
+//! filter GA and preview versions differently. A service's `api_version`
+//! setting (`[services.vision] api_version = "2023-10-01"` or
+//! `--api-version vision=2023-10-01`) pins the version of its primary API.
+
+use crate::services::TestContext;
+
+/// A versioned data-plane API
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Api {
+    /// Service the API belongs to
+    pub service: &'static str,
+    /// Display name
+    pub name: &'static str,
+    /// Version used by default
+    pub ga: &'static str,
+    /// Version used with --preview-apis
+    pub preview: Option<&'static str>,
+    /// The service's `api_version` setting applies to this API
+    pub primary: bool,
+}
+
+impl Api {
+    /// Version to call: the service's `api_version` setting for its primary
+    /// API, then the preview version with --preview-apis, then GA
+    pub fn version(self, context: &TestContext) -> &str {
+        self.resolve(
+            context.service_config.api_version.as_deref(),
+            context.preview_apis,
+        )
+    }
+
+    /// Version to call given the service's `api_version` setting and
+    /// --preview-apis, without a test context
+    pub fn resolve(self, pinned: Option<&str>, preview_apis: bool) -> &str {
+        if self.primary {
+            if let Some(version) = pinned {
+                return version;
+            }
+        }
+        match self.preview {
+            Some(preview) if preview_apis => preview,
+            _ => self.ga,
+        }
+    }
+}
+
+/// Image Analysis 4.0 (`/computervision/imageanalysis:analyze`)
+pub const IMAGE_ANALYSIS: Api = Api {
+    service: "vision",
+    name: "Image Analysis",
+    ga: "2024-02-01",
+    preview: Some("2023-04-01-preview"),
+    primary: true,
+};
+
+/// Multimodal embeddings (`/computervision/retrieval:vectorize*`)
+pub const IMAGE_RETRIEVAL: Api = Api {
+    service: "vision",
+    name: "Image Retrieval",
+    ga: "2024-02-01",
+    preview: Some("2023-04-01-preview"),
+    primary: true,
+};
+
+/// Background removal, only ever released as a preview
+pub const IMAGE_SEGMENTATION: Api = Api {
+    service: "vision",
+    name: "Image Segmentation",
+    ga: "2023-02-01-preview",
+    preview: None,
+    primary: false,
+};
+
+/// Language text analysis and jobs (`/language/:analyze-text`)
+pub const LANGUAGE: Api = Api {
+    service: "language",
+    name: "Language",
+    ga: "2023-04-01",
+    preview: Some("2024-11-15-preview"),
+    primary: true,
+};
+
+/// Question Answering (`/language/:query-knowledgebases`)
+pub const QUESTION_ANSWERING: Api = Api {
+    service: "language",
+    name: "Question Answering",
+    ga: "2021-10-01",
+    preview: None,
+    primary: false,
+};
+
+/// Document Intelligence analysis and models (`/documentintelligence/...`)
+pub const DOCUMENT_INTELLIGENCE: Api = Api {
+    service: "document_intelligence",
+    name: "Document Intelligence",
+    ga: "2024-11-30",
+    preview: Some("2024-07-31-preview"),
+    primary: true,
+};
+
+/// Fast transcription (`/speechtotext/transcriptions:transcribe`)
+pub const SPEECH_TRANSCRIPTION: Api = Api {
+    service: "speech",
+    name: "Speech to text",
+    ga: "2024-11-15",
+    preview: Some("2024-05-15-preview"),
+    primary: true,
+};
+
+/// Text to speech avatar batch synthesis (`/avatar/batchsyntheses`)
+pub const SPEECH_AVATAR: Api = Api {
+    service: "speech",
+    name: "Avatar batch synthesis",
+    ga: "2024-08-01",
+    preview: None,
+    primary: false,
+};
+
+/// Video translation (`/videotranslation/translations`), only available as a preview
+pub const SPEECH_VIDEO_TRANSLATION: Api = Api {
+    service: "speech",
+    name: "Video translation",
+    ga: "2024-05-20-preview",
+    preview: None,
+    primary: false,
+};
+
+/// Translator Text
+pub const TRANSLATOR: Api = Api {
+    service: "translator",
+    name: "Translator",
+    ga: "3.0",
+    preview: None,
+    primary: true,
+};
+
+/// Azure OpenAI data plane (models, chat completions)
+pub const OPENAI: Api = Api {
+    service: "openai",
+    name: "Azure OpenAI",
+    ga: "2024-10-21",
+    preview: Some("2025-01-01-preview"),
+    primary: true,
+};
+
+/// Azure OpenAI Assistants, only available as a preview
+pub const OPENAI_ASSISTANTS: Api = Api {
+    service: "openai",
+    name: "Assistants",
+    ga: "2024-05-01-preview",
+    preview: None,
+    primary: false,
+};
+
+/// Azure OpenAI Realtime, only available as a preview
+pub const OPENAI_REALTIME: Api = Api {
+    service: "openai",
+    name: "Realtime",
+    ga: "2024-10-01-preview",
+    preview: None,
+    primary: false,
+};
+
+/// Azure AI model inference (`/info`, `/chat/completions`)
+pub const MODEL_INFERENCE: Api = Api {
+    service: "model_inference",
+    name: "Model inference",
+    ga: "2024-05-01-preview",
+    preview: None,
+    primary: true,
+};
+
+/// Model inference on a Foundry resource (`/models/...`)
+pub const FOUNDRY_INFERENCE: Api = Api {
+    service: "ai_foundry",
+    name: "Model inference",
+    ga: "2024-05-01-preview",
+    preview: None,
+    primary: true,
+};
+
+/// Foundry Agent Service
+pub const FOUNDRY_AGENTS: Api = Api {
+    service: "ai_foundry",
+    name: "Agents",
+    ga: "v1",
+    preview: None,
+    primary: false,
+};
+
+/// Every registered API
+pub const ALL: &[Api] = &[
+    IMAGE_ANALYSIS,
+    IMAGE_RETRIEVAL,
+    IMAGE_SEGMENTATION,
+    LANGUAGE,
+    QUESTION_ANSWERING,
+    DOCUMENT_INTELLIGENCE,
+    SPEECH_TRANSCRIPTION,
+    SPEECH_AVATAR,
+    SPEECH_VIDEO_TRANSLATION,
+    TRANSLATOR,
+    OPENAI,
+    OPENAI_ASSISTANTS,
+    OPENAI_REALTIME,
+    MODEL_INFERENCE,
+    FOUNDRY_INFERENCE,
+    FOUNDRY_AGENTS,
+];
+
+/// Whether `service` has an API its `api_version` setting applies to
+pub fn is_pinnable(service: &str) -> bool {
+    ALL.iter().any(|api| api.primary && api.service == service)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Credentials;
+    use crate::config::{Cloud, ServiceConfig};
+
+    fn context(api_version: Option<&str>, preview_apis: bool) -> TestContext {
+        TestContext::new(
+            Credentials::ApiKey("test".to_string()),
+            Cloud::Global,
+            "eastus".to_string(),
+            std::time::Duration::from_secs(5),
+        )
+        .unwrap()
+        .with_service_config(ServiceConfig {
+            api_version: api_version.map(str::to_string),
+            ..Default::default()
+        })
+        .with_preview_apis(preview_apis)
+    }
+
+    #[test]
+    fn test_version_resolution() {
+        assert_eq!(IMAGE_ANALYSIS.version(&context(None, false)), "2024-02-01");
+        assert_eq!(
+            IMAGE_ANALYSIS.version(&context(None, true)),
+            "2023-04-01-preview"
+        );
+        // An API without a preview stays on GA
+        assert_eq!(TRANSLATOR.version(&context(None, true)), "3.0");
+        // The setting pins the primary API, even with --preview-apis
+        assert_eq!(
+            IMAGE_ANALYSIS.version(&context(Some("2023-10-01"), true)),
+            "2023-10-01"
+        );
+        assert_eq!(
+            IMAGE_SEGMENTATION.version(&context(Some("2023-10-01"), false)),
+            "2023-02-01-preview"
+        );
+    }
+
+    #[test]
+    fn test_every_service_with_apis_is_pinnable() {
+        for api in ALL {
+            assert!(is_pinnable(api.service), "{}", api.service);
+        }
+        assert!(!is_pinnable("custom"));
+    }
+}
@@ -0,0 +1,451 @@
+//! Static region and SKU capability tables.
+//!
+//! Some features are only deployed to a subset of regions, and some are not
+//! offered on the free (F0) pricing tier. Calling them anyway produces a 400,
+//! 403, or 404 that looks like a networking or endpoint problem. These tables
+//! let the runner explain those results as "not available in region" or "not
+//! supported on this SKU" instead of reporting a failure.
+//!
+//! Scenarios that are not listed here are assumed to work in every region and
+//! on every SKU.
+
+use crate::config::{regions, Cloud};
+use console::style;
+
+/// A scenario that is only available in a subset of regions
+#[derive(Debug, Clone, Copy)]
+pub struct RegionRestriction {
+    /// Service name (e.g., "vision")
+    pub service: &'static str,
+    /// Scenario ID within the service
+    pub scenario: &'static str,
+    /// Feature name used in messages
+    pub feature: &'static str,
+    /// Regions where the feature is offered (global cloud only)
+    pub regions: &'static [&'static str],
+}
+
+/// Result of looking up a scenario in the capability table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Availability {
+    /// Scenario is expected to work in this region
+    Available,
+    /// Scenario is not offered in this region
+    Unavailable {
+        feature: &'static str,
+        supported_regions: &'static [&'static str],
+    },
+}
+
+const IMAGE_RETRIEVAL_REGIONS: &[&str] = &[
+    "australiaeast",
+    "eastus",
+    "francecentral",
+    "japaneast",
+    "koreacentral",
+    "northeurope",
+    "southeastasia",
+    "swedencentral",
+    "switzerlandnorth",
+    "westeurope",
+    "westus",
+    "westus2",
+];
+
+const BACKGROUND_REMOVAL_REGIONS: &[&str] = &[
+    "eastus",
+    "francecentral",
+    "koreacentral",
+    "northeurope",
+    "southeastasia",
+    "westeurope",
+    "westus",
+];
+
+const ABSTRACTIVE_SUMMARIZATION_REGIONS: &[&str] = &[
+    "australiaeast",
+    "canadacentral",
+    "centralindia",
+    "eastus",
+    "eastus2",
+    "francecentral",
+    "japaneast",
+    "northeurope",
+    "southcentralus",
+    "southeastasia",
+    "swedencentral",
+    "switzerlandnorth",
+    "uksouth",
+    "westeurope",
+    "westus",
+    "westus2",
+];
+
+const AVATAR_REGIONS: &[&str] = &[
+    "eastus2",
+    "northeurope",
+    "southcentralus",
+    "southeastasia",
+    "swedencentral",
+    "westeurope",
+    "westus2",
+];
+
+/// Scenarios with restricted regional availability
+pub const RESTRICTIONS: &[RegionRestriction] = &[
+    RegionRestriction {
+        service: "vision",
+        scenario: "vectorize_image",
+        feature: "Image Retrieval",
+        regions: IMAGE_RETRIEVAL_REGIONS,
+    },
+    RegionRestriction {
+        service: "vision",
+        scenario: "vectorize_text",
+        feature: "Image Retrieval",
+        regions: IMAGE_RETRIEVAL_REGIONS,
+    },
+    RegionRestriction {
+        service: "vision",
+        scenario: "background_removal",
+        feature: "Background removal",
+        regions: BACKGROUND_REMOVAL_REGIONS,
+    },
+    RegionRestriction {
+        service: "speech",
+        scenario: "avatar_batch",
+        feature: "Text to speech avatar",
+        regions: AVATAR_REGIONS,
+    },
+    RegionRestriction {
+        service: "speech",
+        scenario: "avatar_relay",
+        feature: "Text to speech avatar",
+        regions: AVATAR_REGIONS,
+    },
+    RegionRestriction {
+        service: "language",
+        scenario: "summarization",
+        feature: "Abstractive summarization",
+        regions: ABSTRACTIVE_SUMMARIZATION_REGIONS,
+    },
+];
+
+/// A scenario that some pricing tiers don't offer
+#[derive(Debug, Clone, Copy)]
+pub struct SkuRestriction {
+    /// Service name (e.g., "speech")
+    pub service: &'static str,
+    /// Scenario ID within the service
+    pub scenario: &'static str,
+    /// Feature name used in messages
+    pub feature: &'static str,
+    /// SKUs that don't offer the feature
+    pub skus: &'static [&'static str],
+}
+
+const FREE_TIER: &[&str] = &["F0"];
+
+/// Scenarios that are not offered on every SKU
+pub const SKU_RESTRICTIONS: &[SkuRestriction] = &[
+    SkuRestriction {
+        service: "speech",
+        scenario: "custom_voice",
+        feature: "Custom Neural Voice",
+        skus: FREE_TIER,
+    },
+    SkuRestriction {
+        service: "speech",
+        scenario: "avatar_batch",
+        feature: "Text to speech avatar",
+        skus: FREE_TIER,
+    },
+    SkuRestriction {
+        service: "speech",
+        scenario: "avatar_relay",
+        feature: "Text to speech avatar",
+        skus: FREE_TIER,
+    },
+    SkuRestriction {
+        service: "speech",
+        scenario: "video_translation",
+        feature: "Video translation",
+        skus: FREE_TIER,
+    },
+];
+
+/// Look up whether a scenario is expected to work in the given region
+pub fn check(service: &str, scenario: &str, region: &str, cloud: Cloud) -> Availability {
+    let restriction = RESTRICTIONS
+        .iter()
+        .find(|r| r.service == service && r.scenario == scenario);
+
+    match restriction {
+        None => Availability::Available,
+        Some(r) => {
+            // Restricted features are not deployed to Azure China at all
+            let offered = cloud == Cloud::Global
+                && r.regions.iter().any(|reg| reg.eq_ignore_ascii_case(region));
+            if offered {
+                Availability::Available
+            } else {
+                Availability::Unavailable {
+                    feature: r.feature,
+                    supported_regions: r.regions,
+                }
+            }
+        }
+    }
+}
+
+/// Human-readable skip reason for an unavailable scenario
+pub fn unavailable_reason(
+    feature: &str,
+    region: &str,
+    cloud: Cloud,
+    supported_regions: &[&str],
+) -> String {
+    if cloud == Cloud::China {
+        format!(
+            "Not available in region '{}' ({} is not offered in Azure China)",
+            region, feature
+        )
+    } else {
+        format!(
+            "Not available in region '{}' ({} is offered in: {})",
+            region,
+            feature,
+            supported_regions.join(", ")
+        )
+    }
+}
+
+/// The feature a scenario needs that the given SKU doesn't offer, if any
+pub fn check_sku(service: &str, scenario: &str, sku: &str) -> Option<&'static str> {
+    SKU_RESTRICTIONS
+        .iter()
+        .find(|r| {
+            r.service == service
+                && r.scenario == scenario
+                && r.skus.iter().any(|s| s.eq_ignore_ascii_case(sku))
+        })
+        .map(|r| r.feature)
+}
+
+/// Human-readable skip reason for a scenario the SKU doesn't offer
+pub fn sku_unsupported_reason(feature: &str, sku: &str) -> String {
+    format!(
+        "Not supported on SKU '{}' ({} needs a standard tier)",
+        sku, feature
+    )
+}
+
+/// Whether an error body says the resource's pricing tier lacks the feature.
+/// Rate-limit messages also mention the "pricing tier", so a tier alone
+/// isn't enough: the error must also say the feature isn't supported.
+pub fn is_sku_error(error: &str) -> bool {
+    let error = error.to_lowercase();
+    error.contains("skunotsupported")
+        || error.contains("invalidpricingtier")
+        || ((error.contains("not supported") || error.contains("not available"))
+            && (error.contains("pricing tier")
+                || error.contains("free tier")
+                || error.contains("sku")
+                || mentions_free_sku(&error)))
+}
+
+/// The SKU an error body reveals: free-tier quota and feature errors name it
+pub fn sku_from_error(error: &str) -> Option<&'static str> {
+    let error = error.to_lowercase();
+    (error.contains("free tier") || mentions_free_sku(&error)).then_some("F0")
+}
+
+fn mentions_free_sku(error: &str) -> bool {
+    error
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .any(|word| word.eq_ignore_ascii_case("f0"))
+}
+
+/// Format the capability table for a region (or all restrictions if no region)
+pub fn format_capabilities(
+    region: Option<&str>,
+    cloud: Cloud,
+    service_filter: Option<&str>,
+) -> String {
+    let mut output = String::new();
+    output.push_str("\nRegion Capabilities\n");
+    output.push_str("===================\n\n");
+
+    let services = match service_filter {
+        Some(name) => crate::services::get_service(name).into_iter().collect(),
+        None => crate::services::get_all_services(),
+    };
+
+    match region {
+        Some(region) => {
+            output.push_str(&format!("Region: {} ({})\n\n", style(region).bold(), cloud));
+            for service in services {
+                output.push_str(&format!("{}\n", style(service.display_name()).bold()));
+                for scenario in service.list_scenarios() {
+                    match check(service.name(), scenario.id, region, cloud) {
+                        Availability::Available => {
+                            output.push_str(&format!("  {} {}\n", style("✓").green(), scenario.id));
+                        }
+                        Availability::Unavailable { feature, .. } => {
+                            output.push_str(&format!(
+                                "  {} {} {}\n",
+                                style("✗").red(),
+                                scenario.id,
+                                style(format!("({} not offered here)", feature)).dim()
+                            ));
+                        }
+                    }
+                }
+                output.push('\n');
+            }
+        }
+        None => {
+            output.push_str("Scenarios not listed here are expected to work in every region.\n\n");
+            for service in services {
+                let restrictions: Vec<_> = RESTRICTIONS
+                    .iter()
+                    .filter(|r| r.service == service.name())
+                    .collect();
+                if restrictions.is_empty() {
+                    continue;
+                }
+                output.push_str(&format!("{}\n", style(service.display_name()).bold()));
+                for r in restrictions {
+                    output.push_str(&format!(
+                        "  {} - {}\n    {}\n",
+                        style(r.scenario).cyan(),
+                        r.feature,
+                        style(r.regions.join(", ")).dim()
+                    ));
+                }
+                output.push('\n');
+            }
+        }
+    }
+
+    output
+}
+
+/// Format the known regions for a cloud, optionally annotated with the
+/// region-restricted scenarios of one service that are unavailable there
+pub fn format_regions(cloud: Cloud, service_filter: Option<&str>) -> String {
+    let mut output = String::new();
+    output.push_str(&format!("\nKnown Regions ({})\n", cloud));
+    output.push_str("====================\n\n");
+
+    let service = service_filter.and_then(crate::services::get_service);
+
+    for region in regions::regions_for(cloud) {
+        output.push_str(&format!(
+            "  {} {:<24} {}",
+            style(format!("{:<22}", region.name)).cyan(),
+            region.display_name,
+            style(format!("{:<14}", region.geography)).dim()
+        ));
+
+        if let Some(service) = &service {
+            let unavailable: Vec<&str> = service
+                .list_scenarios()
+                .iter()
+                .filter(|s| {
+                    check(service.name(), s.id, region.name, cloud) != Availability::Available
+                })
+                .map(|s| s.id)
+                .collect();
+            if !unavailable.is_empty() {
+                output.push_str(&format!(
+                    "  {}",
+                    style(format!("unavailable: {}", unavailable.join(", "))).yellow()
+                ));
+            }
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unrestricted_scenario_is_available() {
+        assert_eq!(
+            check("speech", "tts", "swedencentral", Cloud::Global),
+            Availability::Available
+        );
+        assert_eq!(
+            check("language", "sentiment", "chinaeast2", Cloud::China),
+            Availability::Available
+        );
+    }
+
+    #[test]
+    fn test_restricted_scenario_by_region() {
+        assert_eq!(
+            check("vision", "vectorize_image", "EastUS", Cloud::Global),
+            Availability::Available
+        );
+        assert!(matches!(
+            check(
+                "vision",
+                "background_removal",
+                "swedencentral",
+                Cloud::Global
+            ),
+            Availability::Unavailable { .. }
+        ));
+        assert!(matches!(
+            check("vision", "vectorize_image", "chinaeast2", Cloud::China),
+            Availability::Unavailable { .. }
+        ));
+    }
+
+    #[test]
+    fn test_restrictions_reference_real_scenarios() {
+        let scenarios = RESTRICTIONS
+            .iter()
+            .map(|r| (r.service, r.scenario))
+            .chain(SKU_RESTRICTIONS.iter().map(|r| (r.service, r.scenario)));
+        for (service, scenario) in scenarios {
+            let found = crate::services::get_service(service).expect("unknown service");
+            assert!(
+                found.list_scenarios().iter().any(|s| s.id == scenario),
+                "unknown scenario {}:{}",
+                service,
+                scenario
+            );
+        }
+    }
+
+    #[test]
+    fn test_sku_restrictions_and_error_heuristics() {
+        assert_eq!(
+            check_sku("speech", "avatar_relay", "f0"),
+            Some("Text to speech avatar")
+        );
+        assert_eq!(check_sku("speech", "avatar_relay", "S0"), None);
+        assert_eq!(check_sku("speech", "tts", "F0"), None);
+
+        assert!(is_sku_error(
+            "HTTP 403: Custom voice is not supported for the free tier (F0)"
+        ));
+        assert!(is_sku_error("HTTP 400: {\"code\":\"SkuNotSupported\"}"));
+        assert!(!is_sku_error(
+            "HTTP 429: exceeded call rate limit of your current pricing tier"
+        ));
+        assert!(!is_sku_error("HTTP 404: Resource not found"));
+
+        assert_eq!(
+            sku_from_error("HTTP 403: Out of call volume quota for F0 pricing tier"),
+            Some("F0")
+        );
+        assert_eq!(sku_from_error("HTTP 401: Access denied (f0a1b2)"), None);
+    }
+}
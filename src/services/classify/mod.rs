@@ -0,0 +1,425 @@
+//! Error classification shared by all services.
+//!
+//! Services report raw HTTP status codes and error strings. This layer derives a
+//! [`FailureKind`] for every failed result, recognizes well-known failure
+//! signatures, and attaches a remediation hint, so the same diagnosis applies no
+//! matter which service hit it.
+
+use crate::auth::Credentials;
+use crate::i18n::{tr, tr_args};
+use crate::network::INTERCEPTED;
+use crate::services::{responses, FailureKind, TestContext, TestResult};
+use std::error::Error as _;
+
+/// Error codes/messages returned when key-based (local) auth is disabled
+const LOCAL_AUTH_DISABLED_MARKERS: &[&str] = &[
+    "AuthenticationTypeDisabled",
+    "Key based authentication is disabled",
+    "disableLocalAuth",
+];
+
+/// Hint for results slower than the latency threshold
+pub fn slow_hint() -> String {
+    tr("hint-slow")
+}
+
+/// Hint for a scenario that passed although `[[expectations]]` expects it to fail
+pub fn unexpected_success_hint() -> String {
+    tr("hint-unexpected-success")
+}
+
+/// Hint for a success status whose body isn't the documented response
+pub fn unexpected_response_hint() -> String {
+    tr("hint-unexpected-response")
+}
+
+/// Hint for a response or certificate that came from a network appliance
+pub fn intercepted_hint() -> String {
+    tr("hint-intercepted")
+}
+
+/// Describe a transport-level request error including its source chain.
+///
+/// reqwest's `Display` only says "error sending request for url (...)"; the
+/// underlying cause (DNS, connect, TLS, proxy) lives in the source chain and is
+/// what the classifier needs to see.
+pub fn request_error(context: &str, e: &reqwest::Error) -> String {
+    let mut message = format!("{}: {}", context, e);
+    let mut source = e.source();
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    if e.is_timeout() && !message.to_lowercase().contains("timed out") {
+        message.push_str(" (timed out)");
+    }
+    crate::redact::redact(&message)
+}
+
+/// Whether an error body indicates the resource has key-based auth disabled.
+///
+/// Depending on the service this surfaces as a 403 with `AuthenticationTypeDisabled`
+/// or as a 401 whose body tells the caller to use AAD (Entra ID) tokens.
+pub fn is_local_auth_disabled(status: u16, body: &str) -> bool {
+    if status != 401 && status != 403 {
+        return false;
+    }
+    if LOCAL_AUTH_DISABLED_MARKERS.iter().any(|m| body.contains(m)) {
+        return true;
+    }
+    status == 401 && (body.contains("AAD") || body.contains("Azure Active Directory"))
+}
+
+/// Classify a transport error (no HTTP response) from its message
+fn transport_kind(error: &str) -> FailureKind {
+    let error = error.to_lowercase();
+    let has = |needles: &[&str]| needles.iter().any(|n| error.contains(n));
+
+    if has(&[
+        "dns error",
+        "failed to lookup address",
+        "no such host",
+        "name or service not known",
+        "nodename nor servname",
+    ]) {
+        FailureKind::Dns
+    } else if has(&["proxy"]) {
+        FailureKind::Proxy
+    } else if has(&["certificate", "tls", "handshake", "unknownissuer"]) {
+        FailureKind::Tls
+    } else if has(&["timed out", "timeout", "deadline"]) {
+        FailureKind::Timeout
+    } else if has(&[
+        "connection refused",
+        "connection reset",
+        "tcp connect",
+        "network is unreachable",
+        "host is unreachable",
+        "error trying to connect",
+        "broken pipe",
+    ]) {
+        FailureKind::Tcp
+    } else {
+        FailureKind::Other
+    }
+}
+
+/// Derive the failure kind of a failed result
+pub fn failure_kind(result: &TestResult, credentials: &Credentials) -> FailureKind {
+    let error = result.error.as_deref().unwrap_or("");
+    let is_key = matches!(credentials, Credentials::ApiKey(_));
+
+    if error.starts_with(INTERCEPTED) {
+        return FailureKind::Intercepted;
+    }
+
+    let status = match result.http_status {
+        Some(status) if status > 0 => status,
+        _ => return transport_kind(error),
+    };
+
+    if error.contains("not available for this resource")
+        || error.contains("NotSupportedRegion")
+        || error.contains("not available in this region")
+    {
+        return FailureKind::RegionUnsupported;
+    }
+
+    if error.starts_with(responses::UNEXPECTED_RESPONSE) {
+        return FailureKind::UnexpectedResponse;
+    }
+
+    match status {
+        401 | 403 if is_key && is_local_auth_disabled(status, error) => {
+            FailureKind::LocalAuthDisabled
+        }
+        401 if is_key => FailureKind::AuthKey,
+        401 => FailureKind::AuthToken,
+        403 if !is_key && !error.contains("Firewall") && !error.contains("Virtual Network") => {
+            FailureKind::AuthRbac
+        }
+        403 => FailureKind::Forbidden,
+        404 => FailureKind::NotFound,
+        408 | 504 => FailureKind::Timeout,
+        429 => FailureKind::Throttled,
+        500..=599 => FailureKind::ServerError,
+        400..=499 => FailureKind::BadRequest,
+        _ => FailureKind::Other,
+    }
+}
+
+/// Explain a failed result if it matches a known signature
+pub fn explain(result: &TestResult, credentials: &Credentials) -> Option<String> {
+    let status = result.http_status?;
+    let error = result.error.as_deref().unwrap_or("");
+
+    if matches!(credentials, Credentials::ApiKey(_)) && is_local_auth_disabled(status, error) {
+        return Some(
+            "This resource has key-based auth disabled (disableLocalAuth=true); \
+             use --auth device-code, interactive, or managed-identity"
+                .to_string(),
+        );
+    }
+
+    None
+}
+
+/// Whether an endpoint uses a resource-specific custom subdomain
+/// (as opposed to a shared regional endpoint)
+fn is_custom_subdomain(endpoint: &str) -> bool {
+    let host = url::Url::parse(endpoint)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_lowercase()))
+        .unwrap_or_default();
+    [
+        ".cognitiveservices.azure.com",
+        ".cognitiveservices.azure.cn",
+        ".openai.azure.com",
+        ".services.ai.azure.com",
+    ]
+    .iter()
+    .any(|suffix| host.ends_with(suffix))
+}
+
+/// Suggest a next step for a failure of the given kind
+pub fn remediation_hint(
+    kind: FailureKind,
+    credentials: &Credentials,
+    region: &str,
+    endpoint: &str,
+) -> Option<String> {
+    let custom = is_custom_subdomain(endpoint);
+    let args: &[(&str, &dyn std::fmt::Display)] = &[("region", &region)];
+    let hint = match kind {
+        FailureKind::Dns if custom => tr("hint-dns-private"),
+        FailureKind::Dns => tr_args("hint-dns", args),
+        FailureKind::Tcp => tr("hint-tcp"),
+        FailureKind::Tls => tr("hint-tls"),
+        FailureKind::Proxy => tr("hint-proxy"),
+        FailureKind::Timeout => tr("hint-timeout"),
+        FailureKind::AuthKey if custom => tr("hint-auth-key-custom"),
+        FailureKind::AuthKey => tr_args("hint-auth-key", args),
+        FailureKind::AuthToken if !custom => tr("hint-auth-token-regional"),
+        FailureKind::AuthToken => tr("hint-auth-token"),
+        FailureKind::AuthRbac => tr("hint-auth-rbac"),
+        FailureKind::LocalAuthDisabled => tr("hint-local-auth-disabled"),
+        FailureKind::Forbidden if matches!(credentials, Credentials::ApiKey(_)) => {
+            tr("hint-forbidden-key")
+        }
+        FailureKind::Forbidden => tr("hint-forbidden"),
+        FailureKind::Throttled => tr("hint-throttled"),
+        FailureKind::RegionUnsupported => tr_args("hint-region-unsupported", args),
+        FailureKind::NotFound => tr("hint-not-found"),
+        FailureKind::ServerError => tr("hint-server-error"),
+        FailureKind::Slow => slow_hint(),
+        FailureKind::UnexpectedSuccess => unexpected_success_hint(),
+        FailureKind::Intercepted => intercepted_hint(),
+        FailureKind::UnexpectedResponse => unexpected_response_hint(),
+        FailureKind::BadRequest | FailureKind::Other => return None,
+    };
+    Some(hint)
+}
+
+/// Attach a failure kind, an explanation (if one applies), and a remediation
+/// hint to a failed result
+pub fn classify(mut result: TestResult, context: &TestContext, endpoint: &str) -> TestResult {
+    if result.success || result.is_skipped() {
+        return result;
+    }
+    let kind = failure_kind(&result, &context.credentials);
+    result.failure_kind = Some(kind);
+    result.hint = remediation_hint(kind, &context.credentials, &context.region, endpoint);
+    if let Some(explanation) = explain(&result, &context.credentials) {
+        let original = result.error.take().unwrap_or_default();
+        result.error = Some(format!("{} [{}]", explanation, original));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key() -> Credentials {
+        Credentials::ApiKey("k".to_string())
+    }
+
+    fn bearer() -> Credentials {
+        Credentials::BearerToken("t".to_string())
+    }
+
+    fn context(credentials: Credentials) -> TestContext {
+        TestContext::new(
+            credentials,
+            crate::config::Cloud::Global,
+            "eastus".to_string(),
+            std::time::Duration::from_secs(5),
+        )
+        .unwrap()
+    }
+
+    const REGIONAL: &str = "https://eastus.api.cognitive.microsoft.com";
+    const CUSTOM: &str = "https://myres.cognitiveservices.azure.com";
+
+    fn failed(status: Option<u16>, error: &str) -> TestResult {
+        let result = TestResult::failure("id", "Name", 10, error.to_string());
+        match status {
+            Some(status) => result.with_http_status(status),
+            None => result,
+        }
+    }
+
+    #[test]
+    fn test_local_auth_disabled_signatures() {
+        assert!(is_local_auth_disabled(
+            403,
+            r#"{"error":{"code":"AuthenticationTypeDisabled","message":"Key based authentication is disabled for this resource."}}"#
+        ));
+        assert!(is_local_auth_disabled(
+            401,
+            "Please use AAD token to access this resource"
+        ));
+        assert!(!is_local_auth_disabled(
+            401,
+            "Access denied due to invalid subscription key"
+        ));
+        assert!(!is_local_auth_disabled(404, "AuthenticationTypeDisabled"));
+    }
+
+    #[test]
+    fn test_classify_only_applies_to_key_auth() {
+        let failure = failed(Some(403), "HTTP 403: AuthenticationTypeDisabled");
+
+        let classified = classify(failure.clone(), &context(key()), CUSTOM);
+        assert!(classified
+            .error
+            .as_deref()
+            .unwrap()
+            .starts_with("This resource has key-based auth disabled"));
+        assert_eq!(
+            classified.failure_kind,
+            Some(FailureKind::LocalAuthDisabled)
+        );
+
+        let classified = classify(failure, &context(bearer()), CUSTOM);
+        assert_eq!(
+            classified.error.as_deref(),
+            Some("HTTP 403: AuthenticationTypeDisabled")
+        );
+        assert_eq!(classified.failure_kind, Some(FailureKind::AuthRbac));
+    }
+
+    #[test]
+    fn test_failure_kind_transport_errors() {
+        let cases = [
+            ("Request failed: error sending request: client error (Connect): dns error: failed to lookup address information", FailureKind::Dns),
+            ("Request failed: error sending request: client error (Connect): tcp connect error: Connection refused (os error 111)", FailureKind::Tcp),
+            ("Request failed: error sending request: invalid peer certificate: UnknownIssuer", FailureKind::Tls),
+            ("Intercepted by network appliance: certificate for eastus.api.cognitive.microsoft.com issued by 'CN=Zscaler Root CA' [Request failed: error sending request: invalid peer certificate: UnknownIssuer]", FailureKind::Intercepted),
+            ("Request failed: error sending request: unsuccessful tunnel through proxy", FailureKind::Proxy),
+            ("Request failed: error sending request: operation timed out", FailureKind::Timeout),
+            ("Something odd", FailureKind::Other),
+        ];
+        for (error, expected) in cases {
+            assert_eq!(
+                failure_kind(&failed(None, error), &key()),
+                expected,
+                "{}",
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn test_failure_kind_http_status() {
+        assert_eq!(
+            failure_kind(&failed(Some(401), "HTTP 401"), &key()),
+            FailureKind::AuthKey
+        );
+        assert_eq!(
+            failure_kind(&failed(Some(401), "HTTP 401"), &bearer()),
+            FailureKind::AuthToken
+        );
+        assert_eq!(
+            failure_kind(&failed(Some(403), "HTTP 403"), &key()),
+            FailureKind::Forbidden
+        );
+        assert_eq!(
+            failure_kind(&failed(Some(429), "HTTP 429"), &key()),
+            FailureKind::Throttled
+        );
+        assert_eq!(
+            failure_kind(&failed(Some(503), "HTTP 503"), &key()),
+            FailureKind::ServerError
+        );
+        assert_eq!(
+            failure_kind(&failed(Some(404), "HTTP 404"), &key()),
+            FailureKind::NotFound
+        );
+        assert_eq!(
+            failure_kind(
+                &failed(
+                    Some(404),
+                    "HTTP 404: Image Retrieval is not available for this resource"
+                ),
+                &key()
+            ),
+            FailureKind::RegionUnsupported
+        );
+        let portal = format!(
+            "{}: got an HTML page ('Sign in') instead of JSON",
+            responses::UNEXPECTED_RESPONSE
+        );
+        assert_eq!(
+            failure_kind(&failed(Some(200), &portal), &key()),
+            FailureKind::UnexpectedResponse
+        );
+        assert_eq!(
+            failure_kind(
+                &failed(
+                    Some(200),
+                    "Intercepted by network appliance: redirected to https://portal.corp.local/login"
+                ),
+                &key()
+            ),
+            FailureKind::Intercepted
+        );
+    }
+
+    #[test]
+    fn test_classify_leaves_skipped_results_alone() {
+        let skipped = TestResult::skipped("id", "Name", "Requires input".to_string());
+        let classified = classify(skipped, &context(key()), REGIONAL);
+        assert_eq!(classified.failure_kind, None);
+        assert_eq!(classified.hint, None);
+    }
+
+    #[test]
+    fn test_remediation_hints() {
+        let hint = |kind, creds, endpoint| remediation_hint(kind, &creds, "eastus", endpoint);
+
+        assert!(hint(FailureKind::AuthKey, key(), REGIONAL)
+            .unwrap()
+            .contains("region 'eastus'"));
+        assert!(hint(FailureKind::AuthRbac, bearer(), CUSTOM)
+            .unwrap()
+            .contains("Cognitive Services User"));
+        assert!(hint(FailureKind::AuthToken, bearer(), REGIONAL)
+            .unwrap()
+            .contains("custom subdomain"));
+        assert!(hint(FailureKind::Dns, key(), CUSTOM)
+            .unwrap()
+            .contains("privatelink"));
+        assert!(hint(FailureKind::Dns, key(), REGIONAL)
+            .unwrap()
+            .contains("list-regions"));
+        assert!(hint(FailureKind::BadRequest, key(), REGIONAL).is_none());
+    }
+
+    #[test]
+    fn test_classify_attaches_hint() {
+        let classified = classify(failed(Some(401), "HTTP 401"), &context(key()), REGIONAL);
+        assert_eq!(classified.failure_kind, Some(FailureKind::AuthKey));
+        assert!(classified.hint.is_some());
+    }
+}
@@ -0,0 +1,180 @@
+//! Disconnected and on-prem Azure AI containers (`test --container`)
+//!
+//! Speech, Language, and Translator ship as containers that customers run on
+//! their own network, usually at a plain `http://` address such as
+//! `http://localhost:5000`. Containers expose the service API at the root (no
+//! regional host or path prefix), accept requests without a key, and add two
+//! health endpoints: `/ready` answers once the models are loaded, and `/status`
+//! checks the billing key the container was started with against Azure.
+
+use crate::error::{AppError, Result};
+use crate::services::{tags, ScenarioRequest, TestContext, TestResult, TestScenario};
+
+/// Readiness check scenario
+pub const READY_SCENARIO: &str = "container_ready";
+/// Billing key check scenario
+pub const STATUS_SCENARIO: &str = "container_status";
+
+/// Health check scenarios every container-capable service lists
+pub fn health_scenarios() -> Vec<TestScenario> {
+    vec![
+        TestScenario {
+            id: READY_SCENARIO,
+            name: "Container Ready",
+            description: "GET /ready on the container (models loaded, ready for requests)",
+            requires_input: false,
+            input_type: None,
+            tags: &[tags::NO_AUTH, tags::FAST, tags::CONTAINER],
+        },
+        TestScenario {
+            id: STATUS_SCENARIO,
+            name: "Container Billing Status",
+            description:
+                "GET /status on the container (validates the billing API key it was started with)",
+            requires_input: false,
+            input_type: None,
+            tags: &[tags::NO_AUTH, tags::FAST, tags::CONTAINER],
+        },
+    ]
+}
+
+/// Whether a scenario only applies to container endpoints
+pub fn is_health_scenario(scenario_id: &str) -> bool {
+    scenario_id == READY_SCENARIO || scenario_id == STATUS_SCENARIO
+}
+
+/// Refuse plain-HTTP endpoints unless `--allow-insecure-http` was given
+pub fn check_endpoint_scheme(endpoint: &str, allow_insecure_http: bool) -> Result<()> {
+    let url = url::Url::parse(endpoint)
+        .map_err(|e| AppError::InvalidInput(format!("Invalid endpoint '{}': {}", endpoint, e)))?;
+    match url.scheme() {
+        "https" => Ok(()),
+        "http" if allow_insecure_http => Ok(()),
+        "http" => Err(AppError::InvalidInput(format!(
+            "{} is plain HTTP; pass --allow-insecure-http to test it (for example a container on a private network)",
+            endpoint
+        ))),
+        scheme => Err(AppError::InvalidInput(format!(
+            "Endpoint '{}' must be an http(s) URL, not {}",
+            endpoint, scheme
+        ))),
+    }
+}
+
+/// Verdict of a `/status` body. The container answers 200 even when Azure
+/// rejects its billing key, with `apiStatus` and a message saying why; a
+/// body without them (older images) only proves the route.
+pub fn billing_status(body: &str) -> std::result::Result<String, String> {
+    let status: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+    let reason = match status["apiStatusMessage"].as_str() {
+        Some(message) if !message.is_empty() => format!(": {}", message),
+        _ => String::new(),
+    };
+    match status["apiStatus"].as_str() {
+        None | Some("Valid") => Ok("Container billing key is valid".to_string()),
+        Some(api_status) => Err(format!(
+            "Billing key {}{} (check the ApiKey and Billing settings the container was started with, and its outbound access to Azure)",
+            api_status, reason
+        )),
+    }
+}
+
+/// Run a health check scenario against the container at `endpoint`
+pub async fn run_health_check(
+    context: &TestContext,
+    scenario: &TestScenario,
+    endpoint: &str,
+) -> TestResult {
+    let path = if scenario.id == READY_SCENARIO {
+        "ready"
+    } else {
+        "status"
+    };
+    let url = format!("{}/{}", endpoint.trim_end_matches('/'), path);
+
+    ScenarioRequest::get(url)
+        .without_credentials()
+        .extract_text(|body| match scenario.id {
+            READY_SCENARIO => Ok("Container is ready".to_string()),
+            _ => billing_status(&body),
+        })
+        .on_error(|status, body| {
+            let meaning = match (scenario.id, status.as_u16()) {
+                (READY_SCENARIO, 503) => "container is still loading models",
+                (STATUS_SCENARIO, 401 | 403) => {
+                    "the container's billing API key was rejected; check the ApiKey and Billing settings it was started with"
+                }
+                (_, 404) => "no such endpoint; is this an Azure AI container?",
+                _ => "container reported a problem",
+            };
+            let body = body.trim().chars().take(100).collect::<String>();
+            Err(format!("HTTP {}: {} {}", status, meaning, body)
+                .trim()
+                .to_string())
+        })
+        .run(context, scenario)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockServer;
+    use crate::testing::{TestRunner, TestRunnerConfig};
+
+    #[test]
+    fn test_billing_status() {
+        assert!(billing_status(r#"{"apiStatus":"Valid","apiStatusMessage":"ok"}"#).is_ok());
+        assert!(billing_status("ready").is_ok());
+        let error = billing_status(
+            r#"{"apiStatus":"Invalid","apiStatusMessage":"Access denied due to invalid subscription key."}"#,
+        )
+        .unwrap_err();
+        assert!(
+            error.starts_with("Billing key Invalid: Access denied"),
+            "{}",
+            error
+        );
+    }
+
+    #[tokio::test]
+    async fn test_container_run_against_mock() {
+        let mock = MockServer::start().await.unwrap();
+        let builder = || {
+            TestRunnerConfig::builder()
+                .services(["translator", "language", "speech"])
+                .auth_method(crate::config::AuthMethod::Key)
+                .api_key("")
+                .endpoint("http://localhost:5000")
+                .container(true)
+                .quiet(true)
+                .mock(mock.addr())
+        };
+        assert!(TestRunner::new(builder().build()).run().await.is_err());
+
+        let report = TestRunner::new(builder().allow_insecure_http(true).build())
+            .run()
+            .await
+            .unwrap();
+        let translator: Vec<_> = report.services[0]
+            .results
+            .iter()
+            .map(|r| (r.scenario_id.as_str(), r.success))
+            .collect();
+        assert_eq!(
+            translator,
+            vec![
+                ("translate", true),
+                (READY_SCENARIO, true),
+                (STATUS_SCENARIO, true)
+            ]
+        );
+        let speech: Vec<_> = report.services[2]
+            .results
+            .iter()
+            .map(|r| r.scenario_id.as_str())
+            .collect();
+        assert_eq!(speech, ["stt_rest", "tts", READY_SCENARIO, STATUS_SCENARIO]);
+        assert_eq!(report.summary.failed, 0, "{:?}", report.summary);
+    }
+}
@@ -0,0 +1,453 @@
+//! User-defined HTTP probes from `[[custom_scenarios]]` in the config file,
+//! and services made of probes from `[custom_services.<name>]`
+
+use crate::auth::Credentials;
+use crate::config::{Cloud, Config, CustomScenario, CustomServiceConfig};
+use crate::error::sanitize_error;
+use crate::services::endpoints::ServiceEndpoint;
+use crate::services::{
+    apply_expectation, apply_latency_threshold, apply_throttle, apply_timing, classify,
+    classify::request_error, get_service, log_result, measure_time, register_service, AzureService,
+    RunEvent, ScenarioSelection, ServiceTestResults, TestContext, TestResult, TestScenario,
+};
+use async_trait::async_trait;
+use std::time::Instant;
+use tracing::Instrument;
+
+/// Service name used to select custom probes (`--services custom`)
+pub const CUSTOM_SERVICE_NAME: &str = "custom";
+
+/// Display name of the custom probe service in reports
+const SERVICE_DISPLAY_NAME: &str = "Custom";
+
+/// Runs the configured custom scenarios
+pub struct CustomProbeService {
+    probes: Vec<CustomScenario>,
+}
+
+impl CustomProbeService {
+    pub fn new(probes: Vec<CustomScenario>) -> Self {
+        Self { probes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.probes.is_empty()
+    }
+
+    /// Endpoint `${endpoint}` expands to for a probe
+    fn endpoint_for(probe: &CustomScenario, context: &TestContext) -> String {
+        if let Some(service) = probe.service.as_deref().and_then(get_service) {
+            return service.get_endpoint(
+                &context.region,
+                context.cloud,
+                context.endpoint.as_deref(),
+            );
+        }
+        ServiceEndpoint::resolve(&context.region, context.cloud, context.endpoint.as_deref()).url()
+    }
+
+    /// Run all probes (or only those named in `enabled_scenarios`)
+    pub async fn run_all(
+        &self,
+        context: &TestContext,
+        selection: &ScenarioSelection,
+    ) -> ServiceTestResults {
+        let start = Instant::now();
+        let endpoint = match &context.endpoint {
+            Some(endpoint) => endpoint.clone(),
+            None => "(per-scenario URL)".to_string(),
+        };
+        let mut results = Vec::new();
+
+        let probes: Vec<&CustomScenario> = self
+            .probes
+            .iter()
+            .filter(|probe| selection.selects(&probe.name, &[]))
+            .collect();
+        context.emit(RunEvent::ServiceStarted {
+            service: SERVICE_DISPLAY_NAME.to_string(),
+            endpoint: endpoint.clone(),
+            scenarios: probes
+                .iter()
+                .map(|p| {
+                    let name = p.description.as_deref().unwrap_or(&p.name);
+                    (p.name.clone(), name.to_string())
+                })
+                .collect(),
+        });
+
+        for probe in probes {
+            if context.interrupt.is_triggered() {
+                break;
+            }
+            if let Some(capture) = &context.capture {
+                capture.set_scenario(CUSTOM_SERVICE_NAME, &probe.name);
+            }
+            let probe_endpoint = Self::endpoint_for(probe, context);
+            let span =
+                tracing::info_span!("scenario", service = CUSTOM_SERVICE_NAME, id = %probe.name);
+            let name = probe.description.as_deref().unwrap_or(&probe.name);
+            let mut result = context
+                .run_interruptible(
+                    &probe.name,
+                    name,
+                    Self::run_probe(probe, &probe_endpoint, context).instrument(span),
+                )
+                .await;
+            if let Some(timing) = context.take_timing() {
+                result = apply_timing(result, timing);
+            }
+            if let Some(throttle) = context.take_throttle() {
+                result = apply_throttle(result, &throttle, context.throttled_is_failure);
+            }
+            let mut result = classify::classify(result, context, &probe_endpoint);
+            if let Some(max_latency_ms) = context.max_latency_ms {
+                result = apply_latency_threshold(result, max_latency_ms, context.slow_is_failure);
+            }
+            let result = apply_expectation(result, CUSTOM_SERVICE_NAME, &context.expectations);
+            log_result(CUSTOM_SERVICE_NAME, &result);
+            context.emit(RunEvent::ScenarioFinished {
+                service: SERVICE_DISPLAY_NAME.to_string(),
+                result: Box::new(result.clone()),
+            });
+            results.push(result);
+        }
+
+        ServiceTestResults {
+            service_name: SERVICE_DISPLAY_NAME.to_string(),
+            endpoint,
+            region: None,
+            target: None,
+            results,
+            total_duration_ms: start.elapsed().as_millis() as u64,
+        }
+    }
+
+    async fn run_probe(
+        probe: &CustomScenario,
+        endpoint: &str,
+        context: &TestContext,
+    ) -> TestResult {
+        let name = probe.description.as_deref().unwrap_or(&probe.name);
+        let request = match build_request(probe, endpoint, context) {
+            Ok(request) => request,
+            Err(e) => {
+                return TestResult::failure(
+                    &probe.name,
+                    name,
+                    0,
+                    format!("Invalid custom scenario: {}", e),
+                )
+            }
+        };
+
+        let (result, duration_ms) = measure_time(async {
+            match context.send(request).await {
+                Ok(response) => {
+                    let status = response.status().as_u16();
+                    let body = response.text().await.unwrap_or_default();
+                    let status_ok = if probe.expect_status.is_empty() {
+                        (200..300).contains(&status)
+                    } else {
+                        probe.expect_status.contains(&status)
+                    };
+                    if !status_ok {
+                        return Err((
+                            status,
+                            format!(
+                                "HTTP {} (expected {}): {}",
+                                status,
+                                expected_label(&probe.expect_status),
+                                sanitize_error(&body, status)
+                            ),
+                        ));
+                    }
+                    if let Some(needle) = &probe.expect_body_contains {
+                        if !body.contains(needle.as_str()) {
+                            return Err((
+                                status,
+                                format!(
+                                    "HTTP {} but the response does not contain '{}'",
+                                    status, needle
+                                ),
+                            ));
+                        }
+                    }
+                    Ok(format!("HTTP {} ({} bytes)", status, body.len()))
+                }
+                Err(e) => Err((0, request_error("Request failed", &e))),
+            }
+        })
+        .await;
+
+        match result {
+            Ok(details) => {
+                TestResult::success(&probe.name, name, duration_ms).with_details(details)
+            }
+            Err((status, error)) => {
+                let mut result = TestResult::failure(&probe.name, name, duration_ms, error);
+                if status > 0 {
+                    result = result.with_http_status(status);
+                }
+                result
+            }
+        }
+    }
+}
+
+/// A service defined in the config file (`[custom_services.<name>]`)
+pub struct ConfigService {
+    name: &'static str,
+    display_name: &'static str,
+    endpoint: Option<String>,
+    probes: Vec<CustomScenario>,
+    scenarios: Vec<TestScenario>,
+}
+
+impl ConfigService {
+    pub fn new(name: &str, config: &CustomServiceConfig) -> Self {
+        // Services are registered once for the whole run, and the service
+        // trait hands out static names
+        fn leak(value: &str) -> &'static str {
+            Box::leak(value.to_string().into_boxed_str())
+        }
+
+        let scenarios = config
+            .scenarios
+            .iter()
+            .map(|probe| TestScenario {
+                id: leak(&probe.name),
+                name: leak(probe.description.as_deref().unwrap_or(&probe.name)),
+                description: leak(&format!("{} {}", probe.method, probe.url_template)),
+                requires_input: false,
+                input_type: None,
+                tags: &[],
+            })
+            .collect();
+        Self {
+            name: leak(&name.to_lowercase().replace('-', "_")),
+            display_name: leak(config.display_name.as_deref().unwrap_or(name)),
+            endpoint: config.endpoint.clone(),
+            probes: config.scenarios.clone(),
+            scenarios,
+        }
+    }
+}
+
+#[async_trait]
+impl AzureService for ConfigService {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn display_name(&self) -> &'static str {
+        self.display_name
+    }
+
+    fn get_endpoint(&self, region: &str, cloud: Cloud, custom_endpoint: Option<&str>) -> String {
+        ServiceEndpoint::resolve(region, cloud, custom_endpoint.or(self.endpoint.as_deref())).url()
+    }
+
+    fn list_scenarios(&self) -> Vec<TestScenario> {
+        self.scenarios.clone()
+    }
+
+    async fn run_scenario(&self, scenario_id: &str, context: &TestContext) -> TestResult {
+        let Some(probe) = self.probes.iter().find(|p| p.name == scenario_id) else {
+            return TestResult::failure(
+                scenario_id,
+                "Unknown",
+                0,
+                format!("Unknown scenario: {}", scenario_id),
+            );
+        };
+        let endpoint = match probe.service.as_deref().and_then(get_service) {
+            Some(service) => {
+                service.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref())
+            }
+            None => self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref()),
+        };
+        CustomProbeService::run_probe(probe, &endpoint, context).await
+    }
+}
+
+/// Register the config file's `[custom_services]` so they can be tested by name
+pub fn register_config_services(config: &Config) {
+    for (name, service) in &config.custom_services {
+        register_service(Box::new(ConfigService::new(name, service)));
+    }
+}
+
+fn expected_label(codes: &[u16]) -> String {
+    if codes.is_empty() {
+        "2xx".to_string()
+    } else {
+        codes
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>()
+            .join(" or ")
+    }
+}
+
+/// Build the HTTP request for a probe, substituting variables
+fn build_request(
+    probe: &CustomScenario,
+    endpoint: &str,
+    context: &TestContext,
+) -> Result<reqwest::RequestBuilder, String> {
+    let (api_key, token) = match &context.credentials {
+        Credentials::ApiKey(key) => (key.as_str(), ""),
+        Credentials::BearerToken(token) => ("", token.as_str()),
+    };
+    let cloud = context.cloud.to_string();
+    let vars = [
+        ("endpoint", endpoint.trim_end_matches('/')),
+        ("region", context.region.as_str()),
+        ("cloud", cloud.as_str()),
+        ("api_key", api_key),
+        ("token", token),
+    ];
+
+    let url = substitute(&probe.url_template, &vars)?;
+    let method = reqwest::Method::from_bytes(probe.method.to_uppercase().as_bytes())
+        .map_err(|_| format!("unsupported method '{}'", probe.method))?;
+
+    let mut request = context.client.request(method, &url);
+    if probe.auth {
+        request = context.credentials.apply_to_request(request);
+    }
+    for (name, value) in &probe.headers {
+        request = request.header(name, substitute(value, &vars)?);
+    }
+
+    let body = match (&probe.body, &probe.body_file) {
+        (Some(body), _) => Some(body.clone()),
+        (None, Some(path)) => Some(
+            std::fs::read_to_string(path)
+                .map_err(|e| format!("cannot read body_file '{}': {}", path, e))?,
+        ),
+        (None, None) => None,
+    };
+    if let Some(body) = body {
+        request = request.body(substitute(&body, &vars)?);
+    }
+
+    Ok(request)
+}
+
+/// Replace `${name}` and `${env:NAME}` placeholders. Unknown variables are an
+/// error so typos don't silently produce a wrong URL.
+pub fn substitute(template: &str, vars: &[(&str, &str)]) -> Result<String, String> {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("${") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| format!("unterminated variable in '{}'", template))?;
+        let name = &after[..end];
+
+        let value = if let Some(env_name) = name.strip_prefix("env:") {
+            std::env::var(env_name)
+                .map_err(|_| format!("environment variable '{}' is not set", env_name))?
+        } else {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+                .ok_or_else(|| format!("unknown variable '${{{}}}'", name))?
+        };
+        output.push_str(&value);
+        rest = &after[end + 1..];
+    }
+    output.push_str(rest);
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_substitute() {
+        let vars = [("endpoint", "https://gw.contoso.com"), ("region", "eastus")];
+        assert_eq!(
+            substitute("${endpoint}/ai/${region}/status", &vars).unwrap(),
+            "https://gw.contoso.com/ai/eastus/status"
+        );
+        // JSON bodies keep their braces
+        assert_eq!(
+            substitute(r#"{"region": "${region}"}"#, &vars).unwrap(),
+            r#"{"region": "eastus"}"#
+        );
+        assert!(substitute("${regoin}", &vars)
+            .unwrap_err()
+            .contains("unknown variable"));
+        assert!(substitute("${endpoint", &vars).is_err());
+    }
+
+    #[test]
+    fn test_parse_custom_scenarios() {
+        let config: crate::config::Config = toml::from_str(
+            r#"
+            [[custom_scenarios]]
+            name = "apim_language"
+            method = "POST"
+            url_template = "${endpoint}/language/:analyze-text?api-version=2023-04-01"
+            headers = { "Content-Type" = "application/json" }
+            body = '{"kind": "LanguageDetection"}'
+            expect_status = 200
+
+            [[custom_scenarios]]
+            name = "gateway_health"
+            url_template = "https://gw.contoso.com/health"
+            expect_status = [200, 204]
+            auth = false
+            "#,
+        )
+        .unwrap();
+
+        let probes = &config.custom_scenarios;
+        assert_eq!(probes.len(), 2);
+        assert_eq!(probes[0].expect_status, vec![200]);
+        assert_eq!(probes[1].method, "GET");
+        assert_eq!(probes[1].expect_status, vec![200, 204]);
+        assert!(probes[0].auth && !probes[1].auth);
+    }
+
+    #[test]
+    fn test_config_service_registry() {
+        let config: Config = toml::from_str(
+            r#"
+            [custom_services.ai-gateway]
+            display_name = "AI Gateway"
+            endpoint = "https://gw.contoso.com/"
+
+            [[custom_services.ai-gateway.scenarios]]
+            name = "health"
+            url_template = "${endpoint}/health"
+            auth = false
+            "#,
+        )
+        .unwrap();
+
+        let mut registry = crate::services::ServiceRegistry::new();
+        let builtins = registry.all().len();
+        for (name, service) in &config.custom_services {
+            registry.register(Box::new(ConfigService::new(name, service)));
+        }
+        assert_eq!(registry.all().len(), builtins + 1);
+        assert!(registry.get("openai").is_some() && registry.get("AOAI").is_some());
+
+        let gateway = registry.get("ai_gateway").unwrap();
+        assert_eq!(gateway.display_name(), "AI Gateway");
+        assert_eq!(
+            gateway.get_endpoint("eastus", Cloud::Global, None),
+            "https://gw.contoso.com"
+        );
+        assert_eq!(gateway.list_scenarios()[0].id, "health");
+    }
+}
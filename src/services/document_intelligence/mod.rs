@@ -2,9 +2,11 @@ use async_trait::async_trait;
 use std::time::Duration;
 
 use crate::config::Cloud;
-use crate::error::sanitize_error;
+use crate::services::endpoints::ServiceEndpoint;
+use crate::services::responses::document_intelligence::ModelList;
 use crate::services::{
-    measure_time, AzureService, InputType, TestContext, TestResult, TestScenario,
+    api_versions, classify::request_error, http_error, long_poll, measure_time, payloads,
+    responses, tags, AzureService, InputType, TestContext, TestResult, TestScenario,
 };
 
 /// Document Intelligence Service implementation
@@ -46,6 +48,137 @@ startxref
 196
 %%EOF";
 
+/// API versions tried in order after the registered one when none is pinned:
+/// v3.1 and v2.1, which sovereign clouds and older resources still serve
+const OLDER_API_VERSIONS: &[&str] = &["2023-07-31", "2.1"];
+
+/// Route family of a Document Intelligence API version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ApiFamily {
+    /// 2023-10-31 and later: `/documentintelligence/...`
+    DocumentIntelligence,
+    /// v3.0 and v3.1 (2022-08-31 to 2023-07-31): `/formrecognizer/...`
+    FormRecognizer,
+    /// v2.x: `/formrecognizer/v2.1/...`
+    V2,
+}
+
+impl ApiFamily {
+    fn of(api_version: &str) -> Self {
+        match api_version.trim_start_matches('v') {
+            "2.0" | "2.1" => ApiFamily::V2,
+            // Dates compare correctly as strings
+            version if version < "2023-10-31" => ApiFamily::FormRecognizer,
+            _ => ApiFamily::DocumentIntelligence,
+        }
+    }
+}
+
+/// URL that starts an analysis with `model_id`, or `None` when the API
+/// version has no equivalent of the model (v2.1 has no prebuilt-read)
+fn analyze_url(endpoint: &str, api_version: &str, model_id: &str) -> Option<String> {
+    let endpoint = endpoint.trim_end_matches('/');
+    match ApiFamily::of(api_version) {
+        ApiFamily::DocumentIntelligence => Some(format!(
+            "{}/documentintelligence/documentModels/{}:analyze?api-version={}",
+            endpoint, model_id, api_version
+        )),
+        ApiFamily::FormRecognizer => Some(format!(
+            "{}/formrecognizer/documentModels/{}:analyze?api-version={}",
+            endpoint, model_id, api_version
+        )),
+        ApiFamily::V2 => match model_id {
+            "prebuilt-layout" => Some(format!("{}/formrecognizer/v2.1/layout/analyze", endpoint)),
+            "prebuilt-invoice"
+            | "prebuilt-receipt"
+            | "prebuilt-idDocument"
+            | "prebuilt-businessCard" => Some(format!(
+                "{}/formrecognizer/v2.1/prebuilt/{}/analyze",
+                endpoint,
+                model_id.trim_start_matches("prebuilt-")
+            )),
+            model if model.starts_with("prebuilt-") => None,
+            model => Some(format!(
+                "{}/formrecognizer/v2.1/custom/models/{}/analyze",
+                endpoint, model
+            )),
+        },
+    }
+}
+
+/// URL that lists the resource's models
+fn list_models_url(endpoint: &str, api_version: &str) -> String {
+    let endpoint = endpoint.trim_end_matches('/');
+    match ApiFamily::of(api_version) {
+        ApiFamily::DocumentIntelligence => format!(
+            "{}/documentintelligence/documentModels?api-version={}",
+            endpoint, api_version
+        ),
+        ApiFamily::FormRecognizer => format!(
+            "{}/formrecognizer/documentModels?api-version={}",
+            endpoint, api_version
+        ),
+        ApiFamily::V2 => format!("{}/formrecognizer/v2.1/custom/models?op=full", endpoint),
+    }
+}
+
+/// Whether a response means the route or API version isn't served, rather
+/// than a problem with the request itself
+fn api_version_rejected(status: u16, body: &str) -> bool {
+    let body = body.to_ascii_lowercase();
+    status == 404
+        || (status == 400 && (body.contains("apiversion") || body.contains("api-version")))
+}
+
+/// Details suffix naming the API version used, when older versions had to
+/// be tried because newer ones were rejected
+fn fallback_note(api_version: &str, rejected: &[String]) -> String {
+    if rejected.is_empty() {
+        String::new()
+    } else {
+        format!("; API {} ({})", api_version, rejected.join(", "))
+    }
+}
+
+/// Error when every API version tried was rejected, so the 404 isn't
+/// mistaken for a network block
+fn no_version_error(rejected: &[String]) -> String {
+    format!(
+        "No Document Intelligence API version is served by this endpoint ({}); \
+         the host is reachable, so pin a version it supports with \
+         --api-version document_intelligence=<VERSION>",
+        rejected.join(", ")
+    )
+}
+
+/// Page count of an analysis result (v3+ `pages`, v2.1 `readResults`)
+fn page_count(analyze_result: &serde_json::Value) -> usize {
+    analyze_result
+        .get("pages")
+        .or_else(|| analyze_result.get("readResults"))
+        .and_then(|p| p.as_array())
+        .map(|p| p.len())
+        .unwrap_or(0)
+}
+
+/// Recognized text of an analysis result; v2.1 has no `content`, so its
+/// lines are joined
+fn content(analyze_result: &serde_json::Value) -> String {
+    if let Some(content) = analyze_result.get("content").and_then(|c| c.as_str()) {
+        return content.to_string();
+    }
+    analyze_result
+        .get("readResults")
+        .and_then(|r| r.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|page| page.get("lines").and_then(|l| l.as_array()))
+        .flatten()
+        .filter_map(|line| line.get("text").and_then(|t| t.as_str()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 #[async_trait]
 impl AzureService for DocumentIntelligenceService {
     fn name(&self) -> &'static str {
@@ -56,14 +189,12 @@ impl AzureService for DocumentIntelligenceService {
         "Document Intelligence"
     }
 
+    fn aliases(&self) -> &'static [&'static str] {
+        &["document-intelligence", "documentintelligence"]
+    }
+
     fn get_endpoint(&self, region: &str, cloud: Cloud, custom_endpoint: Option<&str>) -> String {
-        if let Some(endpoint) = custom_endpoint {
-            return endpoint.to_string();
-        }
-        match cloud {
-            Cloud::Global => format!("https://{}.api.cognitive.microsoft.com", region),
-            Cloud::China => format!("https://{}.api.cognitive.azure.cn", region),
-        }
+        ServiceEndpoint::resolve(region, cloud, custom_endpoint).url()
     }
 
     fn list_scenarios(&self) -> Vec<TestScenario> {
@@ -74,6 +205,7 @@ impl AzureService for DocumentIntelligenceService {
                 description: "Extract layout and structure from document",
                 requires_input: false,
                 input_type: Some(InputType::Document),
+                tags: &[tags::REQUIRES_INPUT, tags::ASYNC_POLL],
             },
             TestScenario {
                 id: "read",
@@ -81,6 +213,23 @@ impl AzureService for DocumentIntelligenceService {
                 description: "Extract text from document using OCR",
                 requires_input: false,
                 input_type: Some(InputType::Document),
+                tags: &[tags::REQUIRES_INPUT, tags::ASYNC_POLL],
+            },
+            TestScenario {
+                id: "custom_model",
+                name: "Custom Model Analysis",
+                description: "Analyze document with a user-specified model (--di-model)",
+                requires_input: false,
+                input_type: Some(InputType::Document),
+                tags: &[tags::REQUIRES_INPUT, tags::ASYNC_POLL],
+            },
+            TestScenario {
+                id: "list_models",
+                name: "List Models",
+                description: "List available document models (lightweight connectivity check)",
+                requires_input: false,
+                input_type: None,
+                tags: &[tags::FAST],
             },
             // Note: prebuilt-document model was retired in 2024.
             // Key-value extraction is now available via prebuilt-layout with keyValuePairs feature.
@@ -108,6 +257,8 @@ impl AzureService for DocumentIntelligenceService {
         match scenario_id {
             "layout" => self.test_layout(context, &scenario).await,
             "read" => self.test_read(context, &scenario).await,
+            "custom_model" => self.test_custom_model(context, &scenario).await,
+            "list_models" => self.test_list_models(context, &scenario).await,
             _ => TestResult::failure(
                 scenario_id,
                 scenario.name,
@@ -118,16 +269,59 @@ impl AzureService for DocumentIntelligenceService {
     }
 }
 
+/// Check that recognized text contains each expected string, ignoring case
+/// and line breaks. A DLP or TLS inspection appliance that rewrites uploads
+/// still lets the analysis succeed, on a document that no longer says what
+/// was sent.
+fn verify_content(recognized: &str, expected: &[String]) -> Result<String, String> {
+    let normalize = |text: &str| {
+        text.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+    };
+    let recognized = normalize(recognized);
+    let missing: Vec<&str> = expected
+        .iter()
+        .filter(|text| !recognized.contains(&normalize(text)))
+        .map(String::as_str)
+        .collect();
+    if missing.is_empty() {
+        return Ok(format!("all {} expected strings found", expected.len()));
+    }
+    Err(format!(
+        "Analysis succeeded but {} of {} expected strings are missing from the recognized text: \"{}\" (was the upload altered in transit, e.g. by a DLP appliance?)",
+        missing.len(),
+        expected.len(),
+        missing.join("\", \"")
+    ))
+}
+
 impl DocumentIntelligenceService {
     fn get_document_data(context: &TestContext) -> (Vec<u8>, String) {
-        if let Some(input) = &context.input {
+        if let Some(input) = context.input(InputType::Document) {
             (input.data.clone(), input.content_type.clone())
+        } else if let Some(payload) = context.realistic_payload(payloads::DOCUMENT) {
+            (payload.data.to_vec(), payload.content_type.to_string())
         } else {
             // Use embedded minimal PDF for connectivity testing
             (MINIMAL_PDF.to_vec(), "application/pdf".to_string())
         }
     }
 
+    /// API versions to try: the pinned one (--api-version, `api_version`),
+    /// or the registered GA or preview version followed by older ones
+    fn api_versions(context: &TestContext) -> Vec<String> {
+        let version = api_versions::DOCUMENT_INTELLIGENCE.version(context);
+        if context.service_config.api_version.is_some() {
+            return vec![version.to_string()];
+        }
+        std::iter::once(version)
+            .chain(OLDER_API_VERSIONS.iter().copied())
+            .map(str::to_string)
+            .collect()
+    }
+
     async fn analyze_document(
         &self,
         context: &TestContext,
@@ -136,75 +330,107 @@ impl DocumentIntelligenceService {
     ) -> TestResult {
         let endpoint =
             self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
-        let url = format!(
-            "{}/documentintelligence/documentModels/{}:analyze?api-version=2024-11-30",
-            endpoint, model_id
-        );
+        let fallback = context.service_config.api_version.is_none();
 
         let (document_data, content_type) = Self::get_document_data(context);
 
         let (result, duration_ms) = measure_time(async {
-            // Start the analysis operation
-            let request = context
-                .client
-                .post(&url)
-                .header("Content-Type", &content_type)
-                .body(document_data);
-            let request = context.credentials.apply_to_request(request);
+            // Versions rejected so far, as "<version>: <reason>"
+            let mut rejected = Vec::new();
+            for api_version in Self::api_versions(context) {
+                let Some(url) = analyze_url(&endpoint, &api_version, model_id) else {
+                    rejected.push(format!("{}: no {} model", api_version, model_id));
+                    continue;
+                };
 
-            match request.send().await {
-                Ok(response) => {
-                    let status = response.status();
+                // Start the analysis operation
+                let request = context
+                    .client
+                    .post(&url)
+                    .header("Content-Type", &content_type)
+                    .body(document_data.clone());
+                let request = context.credentials.apply_to_request(request);
 
-                    // Document Intelligence returns 202 Accepted for async operations
-                    if status == reqwest::StatusCode::ACCEPTED {
-                        // Get the operation location
-                        if let Some(operation_location) = response
-                            .headers()
-                            .get("operation-location")
-                            .and_then(|v| v.to_str().ok())
-                        {
-                            // Poll for completion
-                            self.poll_operation(context, operation_location).await
-                        } else {
-                            Err((
-                                status.as_u16(),
-                                "No operation-location header in response".to_string(),
-                            ))
-                        }
-                    } else if status.is_success() {
-                        // Some operations might return synchronously
-                        let body: serde_json::Value = response.json().await.unwrap_or_default();
-                        Ok(format!("Analysis complete: {:?}", body.get("status")))
-                    } else {
-                        let body = response.text().await.unwrap_or_default();
-                        Err((
+                let response = context
+                    .send(request)
+                    .await
+                    .map_err(|e| (0, request_error("Request failed", &e)))?;
+                let status = response.status();
+
+                // Document Intelligence returns 202 Accepted for async operations
+                if status == reqwest::StatusCode::ACCEPTED {
+                    let operation_location = response
+                        .headers()
+                        .get("operation-location")
+                        .and_then(|v| v.to_str().ok())
+                        .ok_or((
                             status.as_u16(),
-                            format!(
-                                "HTTP {}: {}",
-                                status,
-                                sanitize_error(&body, status.as_u16())
-                            ),
-                        ))
-                    }
+                            "No operation-location header in response".to_string(),
+                        ))?;
+                    // Poll for completion
+                    return self
+                        .poll_operation(
+                            context,
+                            operation_location,
+                            scenario.id,
+                            context.realistic_payload(payloads::DOCUMENT),
+                        )
+                        .await
+                        .map(|details| details + &fallback_note(&api_version, &rejected));
+                }
+                if status.is_success() {
+                    // Some operations might return synchronously
+                    let body: serde_json::Value = response.json().await.unwrap_or_default();
+                    return Ok(format!(
+                        "Analysis complete: {:?}{}",
+                        body.get("status"),
+                        fallback_note(&api_version, &rejected)
+                    ));
+                }
+                let body = response.text().await.unwrap_or_default();
+                if fallback && api_version_rejected(status.as_u16(), &body) {
+                    rejected.push(format!("{}: HTTP {}", api_version, status.as_u16()));
+                    continue;
                 }
-                Err(e) => Err((0, format!("Request failed: {}", e))),
+                return Err((status.as_u16(), http_error(status, &body)));
             }
+            Err((404, no_version_error(&rejected)))
         })
         .await;
 
-        match result {
-            Ok(details) => {
-                TestResult::success(scenario.id, scenario.name, duration_ms).with_details(details)
-            }
-            Err((status, error)) => {
-                let mut result =
-                    TestResult::failure(scenario.id, scenario.name, duration_ms, error);
-                if status > 0 {
-                    result = result.with_http_status(status);
-                }
-                result
-            }
+        TestResult::from_outcome(scenario, duration_ms, result)
+    }
+
+    /// Details of a succeeded analysis, checking the recognized text of a
+    /// realistic payload
+    fn analysis_details(
+        &self,
+        context: &TestContext,
+        scenario_id: &str,
+        body: &serde_json::Value,
+        expected: Option<payloads::Payload>,
+    ) -> Result<String, (u16, String)> {
+        let analyze_result = body
+            .get("analyzeResult")
+            .unwrap_or(&serde_json::Value::Null);
+        if !analyze_result.is_null() {
+            context.save_json_artifact(self.name(), scenario_id, analyze_result);
+        }
+        let details = format!(
+            "Analysis succeeded: {} pages processed",
+            page_count(analyze_result)
+        );
+        if !context.expected_content.is_empty() {
+            return verify_content(&content(analyze_result), &context.expected_content)
+                .map(|found| format!("{}, {}", details, found))
+                .map_err(|e| (0, e));
+        }
+        match expected {
+            Some(payload) => payload
+                .verify(&content(analyze_result))
+                .map(|recognized| format!("{}, {}", details, recognized))
+                .map_err(|e| (0, e)),
+            None => Ok(details),
         }
     }
 
@@ -212,7 +438,16 @@ impl DocumentIntelligenceService {
         &self,
         context: &TestContext,
         operation_url: &str,
+        scenario_id: &str,
+        expected: Option<payloads::Payload>,
     ) -> Result<String, (u16, String)> {
+        if let Some(long_poll) = context.long_poll {
+            let (body, summary) = long_poll::poll(context, operation_url, long_poll).await?;
+            return self
+                .analysis_details(context, scenario_id, &body, expected)
+                .map(|details| format!("{}; {}", details, summary));
+        }
+
         // Poll for up to 30 seconds
         let max_attempts = 30;
         let poll_interval = Duration::from_secs(1);
@@ -223,7 +458,7 @@ impl DocumentIntelligenceService {
             let request = context.client.get(operation_url);
             let request = context.credentials.apply_to_request(request);
 
-            match request.send().await {
+            match context.send(request).await {
                 Ok(response) => {
                     let status = response.status();
                     if status.is_success() {
@@ -232,16 +467,12 @@ impl DocumentIntelligenceService {
                         if let Some(op_status) = body.get("status").and_then(|s| s.as_str()) {
                             match op_status {
                                 "succeeded" => {
-                                    let pages = body
-                                        .get("analyzeResult")
-                                        .and_then(|r| r.get("pages"))
-                                        .and_then(|p| p.as_array())
-                                        .map(|p| p.len())
-                                        .unwrap_or(0);
-                                    return Ok(format!(
-                                        "Analysis succeeded: {} pages processed",
-                                        pages
-                                    ));
+                                    return self.analysis_details(
+                                        context,
+                                        scenario_id,
+                                        &body,
+                                        expected,
+                                    );
                                 }
                                 "failed" => {
                                     let error = body
@@ -271,7 +502,7 @@ impl DocumentIntelligenceService {
                     }
                 }
                 Err(e) => {
-                    return Err((0, format!("Poll request failed: {}", e)));
+                    return Err((0, request_error("Poll request failed", &e)));
                 }
             }
         }
@@ -288,4 +519,142 @@ impl DocumentIntelligenceService {
         self.analyze_document(context, "prebuilt-read", scenario)
             .await
     }
+
+    async fn test_custom_model(
+        &self,
+        context: &TestContext,
+        scenario: &TestScenario,
+    ) -> TestResult {
+        match context.service_config.model_id.as_deref() {
+            Some(model_id) => self.analyze_document(context, model_id, scenario).await,
+            None => TestResult::skipped(
+                scenario.id,
+                scenario.name,
+                "Requires a model ID (--di-model or model_id in [services.document_intelligence])"
+                    .to_string(),
+            ),
+        }
+    }
+
+    async fn test_list_models(&self, context: &TestContext, scenario: &TestScenario) -> TestResult {
+        let endpoint =
+            self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
+        let fallback = context.service_config.api_version.is_none();
+
+        let (result, duration_ms) = measure_time(async {
+            let mut rejected = Vec::new();
+            for api_version in Self::api_versions(context) {
+                let url = list_models_url(&endpoint, &api_version);
+                let request = context.client.get(&url);
+                let request = context.credentials.apply_to_request(request);
+
+                let response = context
+                    .send(request)
+                    .await
+                    .map_err(|e| (0, request_error("Request failed", &e)))?;
+                let status = response.status();
+                if status.is_success() {
+                    let body = response.text().await.unwrap_or_default();
+                    let list: ModelList =
+                        responses::parse(&body).map_err(|e| (status.as_u16(), e))?;
+                    let models = &list.value;
+                    let custom = models
+                        .iter()
+                        .filter(|m| !m.model_id.starts_with("prebuilt-"))
+                        .count();
+                    return Ok(format!(
+                        "Found {} models ({} custom){}",
+                        models.len(),
+                        custom,
+                        fallback_note(&api_version, &rejected)
+                    ));
+                }
+                let body = response.text().await.unwrap_or_default();
+                if fallback && api_version_rejected(status.as_u16(), &body) {
+                    rejected.push(format!("{}: HTTP {}", api_version, status.as_u16()));
+                    continue;
+                }
+                return Err((status.as_u16(), http_error(status, &body)));
+            }
+            Err((404, no_version_error(&rejected)))
+        })
+        .await;
+
+        TestResult::from_outcome(scenario, duration_ms, result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_family_by_version() {
+        assert_eq!(ApiFamily::of("2024-11-30"), ApiFamily::DocumentIntelligence);
+        assert_eq!(
+            ApiFamily::of("2024-07-31-preview"),
+            ApiFamily::DocumentIntelligence
+        );
+        assert_eq!(ApiFamily::of("2023-07-31"), ApiFamily::FormRecognizer);
+        assert_eq!(ApiFamily::of("2022-08-31"), ApiFamily::FormRecognizer);
+        assert_eq!(ApiFamily::of("2.1"), ApiFamily::V2);
+        assert_eq!(ApiFamily::of("v2.1"), ApiFamily::V2);
+    }
+
+    #[test]
+    fn test_analyze_url_per_version() {
+        let endpoint = "https://contoso.cognitiveservices.azure.com/";
+        assert_eq!(
+            analyze_url(endpoint, "2023-07-31", "prebuilt-read").unwrap(),
+            "https://contoso.cognitiveservices.azure.com/formrecognizer/documentModels/prebuilt-read:analyze?api-version=2023-07-31"
+        );
+        assert_eq!(
+            analyze_url(endpoint, "2.1", "prebuilt-layout").unwrap(),
+            "https://contoso.cognitiveservices.azure.com/formrecognizer/v2.1/layout/analyze"
+        );
+        assert!(analyze_url(endpoint, "2.1", "prebuilt-read").is_none());
+        assert!(analyze_url(endpoint, "2.1", "my-model")
+            .unwrap()
+            .ends_with("/formrecognizer/v2.1/custom/models/my-model/analyze"));
+    }
+
+    #[test]
+    fn test_api_version_rejected() {
+        assert!(api_version_rejected(404, "Resource not found"));
+        assert!(api_version_rejected(
+            400,
+            r#"{"error":{"code":"InvalidApiVersion"}}"#
+        ));
+        assert!(!api_version_rejected(
+            400,
+            r#"{"error":{"code":"InvalidRequest"}}"#
+        ));
+        assert!(!api_version_rejected(401, "Access denied"));
+    }
+
+    #[test]
+    fn test_v2_result_content_and_pages() {
+        let result = serde_json::json!({"readResults": [
+            {"page": 1, "lines": [{"text": "Hello"}]},
+            {"page": 2, "lines": [{"text": "world"}]}
+        ]});
+        assert_eq!(page_count(&result), 2);
+        assert_eq!(content(&result), "Hello world");
+    }
+
+    #[test]
+    fn test_verify_content() {
+        let expected = ["Invoice INV-1001".to_string(), "Contoso".to_string()];
+        assert_eq!(
+            verify_content("CONTOSO LTD\nInvoice\nINV-1001", &expected).unwrap(),
+            "all 2 expected strings found"
+        );
+        let error = verify_content("C0nt0s0 Invoice INV-1001", &expected).unwrap_err();
+        assert!(
+            error.contains("1 of 2 expected strings are missing"),
+            "{}",
+            error
+        );
+        assert!(error.contains("\"Contoso\""), "{}", error);
+    }
 }
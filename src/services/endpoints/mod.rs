@@ -0,0 +1,586 @@
+//! Endpoint construction, endpoint URLs, and endpoint validation
+//!
+//! Every service reaches its data plane through a [`ServiceEndpoint`]: the
+//! cloud's shared regional endpoint, a resource's custom subdomain or any
+//! user-given base URL, or an on-prem container. Host names per cloud live in
+//! [`CloudDomains`], so adding a sovereign cloud is one table entry; Speech's
+//! dedicated TTS, STT, and Custom Voice hosts are [`SpeechHost`]s.
+//!
+//! The `endpoint` command lists every URL a service would use, and checks
+//! user-provided endpoints for the usual mistakes: a portal or studio URL
+//! copied from the browser, or an endpoint with an API path or query already
+//! on it.
+
+use console::style;
+use serde::Serialize;
+
+use crate::config::Cloud;
+use crate::error::{AppError, Result};
+use crate::services::routes::{UrlContext, ROUTES};
+use crate::services::speech::SpeechService;
+use crate::services::{get_service, AzureService};
+
+/// Portal and studio hosts that are copied instead of the resource endpoint
+const PORTAL_HOSTS: &[(&str, &str)] = &[
+    ("portal.azure.com", "Azure portal"),
+    ("portal.azure.cn", "Azure portal"),
+    ("ai.azure.com", "Azure AI Foundry portal"),
+    ("oai.azure.com", "Azure OpenAI Studio"),
+    ("speech.microsoft.com", "Speech Studio"),
+    ("language.cognitive.azure.com", "Language Studio"),
+    (
+        "documentintelligence.ai.azure.com",
+        "Document Intelligence Studio",
+    ),
+    (
+        "formrecognizer.appliedai.azure.com",
+        "Document Intelligence Studio",
+    ),
+    ("portal.vision.cognitive.azure.com", "Vision Studio"),
+];
+
+/// Host names of a cloud's AI services. A new sovereign cloud needs an
+/// entry here, and every service picks it up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CloudDomains {
+    /// Shared regional endpoints: `{region}.<cognitive>`
+    pub cognitive: &'static str,
+    /// Resource custom subdomains: `{name}.<custom_subdomain>`
+    pub custom_subdomain: &'static str,
+    /// Azure OpenAI custom subdomains
+    pub openai: &'static str,
+    /// AI Foundry resources
+    pub foundry: &'static str,
+    /// Dedicated Speech hosts: `{region}.{tts,stt,voice}.<speech>`
+    pub speech: &'static str,
+    /// Global Translator endpoint
+    pub translator: &'static str,
+    /// Custom Translator portal
+    pub custom_translator_portal: &'static str,
+}
+
+const GLOBAL_DOMAINS: CloudDomains = CloudDomains {
+    cognitive: "api.cognitive.microsoft.com",
+    custom_subdomain: "cognitiveservices.azure.com",
+    openai: "openai.azure.com",
+    foundry: "services.ai.azure.com",
+    speech: "speech.microsoft.com",
+    translator: "api.cognitive.microsofttranslator.com",
+    custom_translator_portal: "portal.customtranslator.azure.ai",
+};
+
+const CHINA_DOMAINS: CloudDomains = CloudDomains {
+    cognitive: "api.cognitive.azure.cn",
+    custom_subdomain: "cognitiveservices.azure.cn",
+    openai: "openai.azure.cn",
+    foundry: "services.ai.azure.com",
+    speech: "speech.azure.cn",
+    translator: "api.translator.azure.cn",
+    custom_translator_portal: "portal.customtranslator.azure.cn",
+};
+
+impl CloudDomains {
+    pub fn of(cloud: Cloud) -> &'static CloudDomains {
+        match cloud {
+            Cloud::Global => &GLOBAL_DOMAINS,
+            Cloud::China => &CHINA_DOMAINS,
+        }
+    }
+}
+
+/// Dedicated Speech hosts, outside the shared regional endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpeechHost {
+    /// Voices list and synthesis
+    Tts,
+    /// Short-audio recognition and the SDK WebSocket
+    Stt,
+    /// Custom Neural Voice deployments
+    Voice,
+}
+
+impl SpeechHost {
+    /// `https://{region}.{tts,stt,voice}.speech.microsoft.com`
+    pub fn url(self, region: &str, cloud: Cloud) -> String {
+        let kind = match self {
+            SpeechHost::Tts => "tts",
+            SpeechHost::Stt => "stt",
+            SpeechHost::Voice => "voice",
+        };
+        format!(
+            "https://{}.{}.{}",
+            region,
+            kind,
+            CloudDomains::of(cloud).speech
+        )
+    }
+}
+
+/// Where a service's data plane is reached
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServiceEndpoint {
+    /// The cloud's shared regional endpoint
+    Regional { region: String, cloud: Cloud },
+    /// A resource's custom subdomain, or any base URL given by the user
+    /// (private endpoint, gateway)
+    Custom(String),
+    /// An on-prem Azure AI container
+    Container(String),
+}
+
+impl ServiceEndpoint {
+    /// `https://{region}.api.cognitive.microsoft.com` or its sovereign equivalent
+    pub fn regional(region: &str, cloud: Cloud) -> Self {
+        ServiceEndpoint::Regional {
+            region: region.to_string(),
+            cloud,
+        }
+    }
+
+    /// A base URL, without its trailing slash
+    pub fn custom(endpoint: &str) -> Self {
+        ServiceEndpoint::Custom(endpoint.trim_end_matches('/').to_string())
+    }
+
+    /// A resource's custom subdomain endpoint. `name` is the subdomain, which
+    /// gets the service's domain in `cloud`, or a full host used as is.
+    pub fn custom_subdomain(service: &str, name: &str, cloud: Cloud) -> Self {
+        if name.contains('.') {
+            return Self::custom(&format!("https://{}", name));
+        }
+        let domains = CloudDomains::of(cloud);
+        let domain = match service {
+            "openai" => domains.openai,
+            "foundry" => domains.foundry,
+            _ => domains.custom_subdomain,
+        };
+        Self::custom(&format!("https://{}.{}", name, domain))
+    }
+
+    /// A container's address (usually plain `http://`)
+    pub fn container(address: &str) -> Self {
+        ServiceEndpoint::Container(address.trim_end_matches('/').to_string())
+    }
+
+    /// The custom endpoint when one is set, else the regional one
+    pub fn resolve(region: &str, cloud: Cloud, custom_endpoint: Option<&str>) -> Self {
+        match custom_endpoint {
+            Some(endpoint) => Self::custom(endpoint),
+            None => Self::regional(region, cloud),
+        }
+    }
+
+    /// Base URL, without a trailing slash
+    pub fn url(&self) -> String {
+        match self {
+            ServiceEndpoint::Regional { region, cloud } => {
+                format!("https://{}.{}", region, CloudDomains::of(*cloud).cognitive)
+            }
+            ServiceEndpoint::Custom(url) | ServiceEndpoint::Container(url) => url.clone(),
+        }
+    }
+}
+
+/// Whether a service takes an endpoint with a path: AI Foundry project
+/// endpoints, serverless model endpoints, and custom probes
+fn accepts_path(service: Option<&str>, path: &str) -> bool {
+    match service {
+        Some("foundry") => path.starts_with("/api/projects/"),
+        Some("model_inference") | Some("custom") => true,
+        _ => false,
+    }
+}
+
+/// Check a user-provided endpoint. A URL that can't be an endpoint (not a
+/// URL, or a portal page) is an error; a path or query string, which the
+/// tool appends its own to, is a warning, since API Management gateways
+/// legitimately put the services under a path.
+pub fn validate_endpoint(endpoint: &str, service: Option<&str>) -> Result<Vec<String>> {
+    let url = url::Url::parse(endpoint).map_err(|e| {
+        let hint = if endpoint.contains("://") {
+            String::new()
+        } else {
+            format!(" (did you mean https://{}?)", endpoint)
+        };
+        AppError::InvalidInput(format!("Invalid endpoint '{}': {}{}", endpoint, e, hint))
+    })?;
+    if !matches!(url.scheme(), "https" | "http") {
+        return Err(AppError::InvalidInput(format!(
+            "Endpoint '{}' must be an http(s) URL, not {}",
+            endpoint,
+            url.scheme()
+        )));
+    }
+    let host = url.host_str().unwrap_or_default().to_lowercase();
+    if let Some((_, portal)) = PORTAL_HOSTS.iter().find(|(h, _)| {
+        host == *h || (*h == "portal.azure.com" && host.ends_with(".portal.azure.com"))
+    }) {
+        return Err(AppError::InvalidInput(format!(
+            "'{}' points at the {}, not the resource endpoint; copy the Endpoint from the resource's \
+             Keys and Endpoint page (e.g. https://<resource>.cognitiveservices.azure.com)",
+            endpoint, portal
+        )));
+    }
+
+    let mut warnings = Vec::new();
+    let path = url.path().trim_end_matches('/');
+    if !path.is_empty() && !accepts_path(service, path) {
+        warnings.push(format!(
+            "The endpoint includes a path ('{}'); each scenario appends its own API path, so use \
+             {}://{} unless the path is a gateway prefix",
+            path,
+            url.scheme(),
+            url.host_str().unwrap_or_default()
+        ));
+    }
+    if url.query().is_some() {
+        warnings.push(
+            "The endpoint includes a query string; the API version and parameters are added per \
+             request"
+                .to_string(),
+        );
+    }
+    if !url.username().is_empty() || url.password().is_some() {
+        warnings.push(
+            "The endpoint includes credentials; pass the key with --api-key instead".to_string(),
+        );
+    }
+    Ok(warnings)
+}
+
+/// A named URL a service uses
+#[derive(Debug, Clone, Serialize)]
+pub struct NamedUrl {
+    pub name: String,
+    pub url: String,
+}
+
+/// A scenario's request
+#[derive(Debug, Clone, Serialize)]
+pub struct ScenarioUrl {
+    pub scenario: String,
+    pub method: String,
+    pub url: String,
+}
+
+/// Every URL one service would use
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceEndpoints {
+    pub service: String,
+    pub display_name: String,
+    /// Data-plane endpoint
+    pub endpoint: String,
+    /// Token and dedicated hosts
+    pub urls: Vec<NamedUrl>,
+    pub scenarios: Vec<ScenarioUrl>,
+}
+
+/// Endpoints of the selected services, with the checks of a custom endpoint
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointReport {
+    pub region: String,
+    pub cloud: Cloud,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_endpoint: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    pub services: Vec<ServiceEndpoints>,
+}
+
+/// URLs `service` uses in `region`, or with `custom_endpoint`
+pub fn service_endpoints(
+    service: &dyn AzureService,
+    region: &str,
+    cloud: Cloud,
+    custom_endpoint: Option<&str>,
+) -> ServiceEndpoints {
+    let endpoint = service.get_endpoint(region, cloud, custom_endpoint);
+    let named = |name: &str, url: String| NamedUrl {
+        name: name.to_string(),
+        url,
+    };
+    let mut urls = Vec::new();
+    // Services without a shared endpoint have a placeholder until one is given
+    if !endpoint.contains('<') {
+        urls.push(named(
+            "Token",
+            cloud.cognitive_token_endpoint_for(region, custom_endpoint),
+        ));
+    }
+    match service.name() {
+        "speech" => {
+            urls.push(named("Text to speech", SpeechHost::Tts.url(region, cloud)));
+            urls.push(named("Speech to text", SpeechHost::Stt.url(region, cloud)));
+            urls.push(named("Custom voice", SpeechHost::Voice.url(region, cloud)));
+            urls.push(named(
+                "Speech SDK WebSocket",
+                SpeechService::get_sdk_websocket_url(region, cloud, custom_endpoint),
+            ));
+        }
+        "translator" => urls.push(named(
+            "Custom Translator portal",
+            format!(
+                "https://{}",
+                CloudDomains::of(cloud).custom_translator_portal
+            ),
+        )),
+        _ => {}
+    }
+
+    let context = UrlContext {
+        endpoint: &endpoint,
+        region,
+        cloud,
+        custom_endpoint: custom_endpoint.is_some(),
+        api_version: None,
+        preview_apis: false,
+    };
+    let scenarios = ROUTES
+        .iter()
+        .filter(|r| r.service == service.name())
+        .map(|r| ScenarioUrl {
+            scenario: r.scenario.to_string(),
+            method: r.method.to_string(),
+            url: r.url(&context),
+        })
+        .collect();
+
+    ServiceEndpoints {
+        service: service.name().to_string(),
+        display_name: service.display_name().to_string(),
+        endpoint,
+        urls,
+        scenarios,
+    }
+}
+
+/// URLs of `services` in `region`, at a resource's `custom_domain`, or at
+/// `endpoint`, which is checked against each service first
+pub fn endpoint_report(
+    services: &[String],
+    region: &str,
+    cloud: Cloud,
+    endpoint: Option<&str>,
+    custom_domain: Option<&str>,
+) -> Result<EndpointReport> {
+    let mut warnings: Vec<String> = Vec::new();
+    let mut found = Vec::new();
+    for name in services {
+        let service = get_service(name)
+            .ok_or_else(|| AppError::Config(format!("Unknown service: {}", name)))?;
+        if let Some(endpoint) = endpoint {
+            for warning in validate_endpoint(endpoint, Some(service.name()))? {
+                if !warnings.contains(&warning) {
+                    warnings.push(warning);
+                }
+            }
+        }
+        let custom = custom_domain
+            .map(|name| ServiceEndpoint::custom_subdomain(service.name(), name, cloud).url());
+        found.push(service_endpoints(
+            service.as_ref(),
+            region,
+            cloud,
+            endpoint.or(custom.as_deref()),
+        ));
+    }
+    Ok(EndpointReport {
+        region: region.to_string(),
+        cloud,
+        custom_endpoint: endpoint.map(str::to_string),
+        warnings,
+        services: found,
+    })
+}
+
+/// Format the endpoint report as text
+pub fn format_endpoint_report(report: &EndpointReport, use_colors: bool) -> String {
+    let paint = |text: &str, f: fn(console::StyledObject<&str>) -> console::StyledObject<&str>| {
+        if use_colors {
+            f(style(text)).to_string()
+        } else {
+            text.to_string()
+        }
+    };
+    let mut output = String::new();
+    output.push_str("\nService Endpoints\n");
+    output.push_str("=================\n\n");
+    match &report.custom_endpoint {
+        Some(endpoint) => {
+            output.push_str(&format!("Endpoint: {}\n", endpoint));
+            if report.warnings.is_empty() {
+                output.push_str(&format!(
+                    "  {} Looks like a resource endpoint\n",
+                    paint("✓", |s| s.green())
+                ));
+            }
+            for warning in &report.warnings {
+                output.push_str(&format!("  {} {}\n", paint("!", |s| s.yellow()), warning));
+            }
+        }
+        None => output.push_str(&format!("Region: {} ({})\n", report.region, report.cloud)),
+    }
+    output.push('\n');
+
+    for service in &report.services {
+        output.push_str(&format!("{}\n", paint(&service.display_name, |s| s.bold())));
+        let width = service
+            .urls
+            .iter()
+            .map(|u| u.name.len())
+            .chain(service.scenarios.iter().map(|s| s.scenario.len() + 2))
+            .chain(["Endpoint".len()])
+            .max()
+            .unwrap_or(0);
+        output.push_str(&format!(
+            "  {:<width$}  {}\n",
+            "Endpoint",
+            service.endpoint,
+            width = width
+        ));
+        for url in &service.urls {
+            output.push_str(&format!(
+                "  {:<width$}  {}\n",
+                url.name,
+                url.url,
+                width = width
+            ));
+        }
+        if !service.scenarios.is_empty() {
+            output.push_str(&format!("  {}\n", paint("Scenarios", |s| s.dim())));
+        }
+        for scenario in &service.scenarios {
+            output.push_str(&format!(
+                "    {:<width$}  {} {}\n",
+                scenario.scenario,
+                scenario.method,
+                scenario.url,
+                width = width - 2
+            ));
+        }
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_endpoint() {
+        assert!(
+            validate_endpoint("https://myres.cognitiveservices.azure.com/", Some("speech"))
+                .unwrap()
+                .is_empty()
+        );
+        assert!(validate_endpoint(
+            "https://portal.azure.com/#@contoso/resource/subscriptions/x",
+            None
+        )
+        .is_err());
+        assert!(validate_endpoint("https://ai.azure.com/build/overview", None).is_err());
+        assert!(validate_endpoint("myres.cognitiveservices.azure.com", None)
+            .unwrap_err()
+            .to_string()
+            .contains("did you mean https://myres.cognitiveservices.azure.com"));
+
+        let warnings = validate_endpoint(
+            "https://myres.openai.azure.com/openai/deployments/gpt/chat/completions?api-version=1",
+            Some("openai"),
+        )
+        .unwrap();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("use https://myres.openai.azure.com"));
+
+        assert!(validate_endpoint(
+            "https://myres.services.ai.azure.com/api/projects/proj",
+            Some("foundry")
+        )
+        .unwrap()
+        .is_empty());
+    }
+
+    #[test]
+    fn test_service_endpoints() {
+        assert_eq!(
+            ServiceEndpoint::custom_subdomain("openai", "contoso", Cloud::Global).url(),
+            "https://contoso.openai.azure.com"
+        );
+        assert_eq!(
+            ServiceEndpoint::custom_subdomain("speech", "contoso", Cloud::China).url(),
+            "https://contoso.cognitiveservices.azure.cn"
+        );
+        assert_eq!(
+            ServiceEndpoint::resolve("eastus", Cloud::Global, Some("https://pe.contoso.com/")),
+            ServiceEndpoint::Custom("https://pe.contoso.com".to_string())
+        );
+        assert_eq!(
+            ServiceEndpoint::regional("chinaeast2", Cloud::China).url(),
+            "https://chinaeast2.api.cognitive.azure.cn"
+        );
+        assert_eq!(
+            SpeechHost::Voice.url("westus2", Cloud::Global),
+            "https://westus2.voice.speech.microsoft.com"
+        );
+
+        let report = endpoint_report(
+            &["speech".to_string()],
+            "westus2",
+            Cloud::Global,
+            None,
+            None,
+        )
+        .unwrap();
+        let speech = &report.services[0];
+        assert_eq!(
+            speech.endpoint,
+            "https://westus2.api.cognitive.microsoft.com"
+        );
+        let url = |name: &str| {
+            speech
+                .urls
+                .iter()
+                .find(|u| u.name == name)
+                .map(|u| u.url.as_str())
+        };
+        assert_eq!(
+            url("Token"),
+            Some("https://westus2.api.cognitive.microsoft.com/sts/v1.0/issueToken")
+        );
+        assert_eq!(
+            url("Text to speech"),
+            Some("https://westus2.tts.speech.microsoft.com")
+        );
+        assert!(speech
+            .scenarios
+            .iter()
+            .any(|s| s.url
+                == "https://westus2.tts.speech.microsoft.com/cognitiveservices/voices/list"));
+
+        // A placeholder endpoint has no token URL
+        let report =
+            endpoint_report(&["openai".to_string()], "eastus", Cloud::Global, None, None).unwrap();
+        assert!(report.services[0].urls.is_empty());
+        let report = endpoint_report(
+            &["openai".to_string()],
+            "eastus",
+            Cloud::Global,
+            None,
+            Some("contoso"),
+        )
+        .unwrap();
+        assert_eq!(
+            report.services[0].endpoint,
+            "https://contoso.openai.azure.com"
+        );
+        assert!(endpoint_report(
+            &["speech".to_string()],
+            "eastus",
+            Cloud::Global,
+            Some("https://speech.microsoft.com/portal"),
+            None
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,303 @@
+//! Azure AI Foundry (AI Services account) endpoints
+//!
+//! Foundry resources are reached on `https://<resource>.services.ai.azure.com`
+//! rather than the `cognitiveservices.azure.com` hostnames, with project-scoped
+//! routes under `/api/projects/<project>`. There is no regional endpoint, so
+//! every scenario needs `--endpoint`.
+
+use async_trait::async_trait;
+
+use crate::auth::Credentials;
+use crate::config::Cloud;
+use crate::error::sanitize_error;
+use crate::services::responses::openai::{ChatCompletion, List};
+use crate::services::{
+    api_versions, classify::request_error, http_error, measure_time, tags, AzureService,
+    ScenarioRequest, TestContext, TestResult, TestScenario,
+};
+
+/// Shown when no endpoint was given
+const PLACEHOLDER_ENDPOINT: &str = "https://<resource>.services.ai.azure.com";
+
+/// AI Foundry Service implementation
+pub struct FoundryService;
+
+impl FoundryService {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for FoundryService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Split a project endpoint as shown in the Foundry portal
+/// (`https://<resource>.services.ai.azure.com/api/projects/<project>`) into the
+/// resource endpoint and the project name. Resource endpoints pass through.
+pub fn split_project_endpoint(endpoint: &str) -> (String, Option<String>) {
+    let endpoint = endpoint.trim_end_matches('/');
+    match endpoint.split_once("/api/projects/") {
+        Some((base, project)) => {
+            let project = project.split('/').next().unwrap_or_default();
+            let project = (!project.is_empty()).then(|| project.to_string());
+            (base.to_string(), project)
+        }
+        None => (endpoint.to_string(), None),
+    }
+}
+
+#[async_trait]
+impl AzureService for FoundryService {
+    fn name(&self) -> &'static str {
+        "ai_foundry"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "AI Foundry"
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["ai-foundry", "foundry"]
+    }
+
+    fn get_endpoint(&self, _region: &str, _cloud: Cloud, custom_endpoint: Option<&str>) -> String {
+        match custom_endpoint {
+            Some(endpoint) => split_project_endpoint(endpoint).0,
+            None => PLACEHOLDER_ENDPOINT.to_string(),
+        }
+    }
+
+    fn list_scenarios(&self) -> Vec<TestScenario> {
+        vec![
+            TestScenario {
+                id: "endpoint_check",
+                name: "Endpoint Reachability",
+                description: "Verify DNS, TCP, and TLS to the services.ai.azure.com endpoint",
+                requires_input: false,
+                input_type: None,
+                tags: &[tags::NO_AUTH, tags::FAST],
+            },
+            TestScenario {
+                id: "list_models",
+                name: "List Models (OpenAI v1)",
+                description: "List models through the /openai/v1 route",
+                requires_input: false,
+                input_type: None,
+                tags: &[tags::FAST],
+            },
+            TestScenario {
+                id: "model_inference",
+                name: "Model Inference",
+                description: "One-token chat completion on /models (needs a deployment name)",
+                requires_input: false,
+                input_type: None,
+                tags: &[],
+            },
+            TestScenario {
+                id: "agents_list",
+                name: "List Agents",
+                description: "List agents in a Foundry project (needs a project and Entra ID auth)",
+                requires_input: false,
+                input_type: None,
+                tags: &[tags::FAST],
+            },
+        ]
+    }
+
+    async fn run_scenario(&self, scenario_id: &str, context: &TestContext) -> TestResult {
+        let scenario = self
+            .list_scenarios()
+            .into_iter()
+            .find(|s| s.id == scenario_id);
+
+        let scenario = match scenario {
+            Some(s) => s,
+            None => {
+                return TestResult::failure(
+                    scenario_id,
+                    "Unknown",
+                    0,
+                    format!("Unknown scenario: {}", scenario_id),
+                )
+            }
+        };
+
+        let Some(endpoint) = context.endpoint.as_deref() else {
+            return TestResult::skipped(
+                scenario.id,
+                scenario.name,
+                format!("Requires --endpoint {}", PLACEHOLDER_ENDPOINT),
+            );
+        };
+        let (base, endpoint_project) = split_project_endpoint(endpoint);
+
+        match scenario_id {
+            "endpoint_check" => self.test_endpoint_check(context, &scenario, &base).await,
+            "list_models" => {
+                ScenarioRequest::get(format!("{}/openai/v1/models", base))
+                    .extract(|list: List| Ok(format!("Found {} models", list.data.len())))
+                    .run(context, &scenario)
+                    .await
+            }
+            "model_inference" => self.test_model_inference(context, &scenario, &base).await,
+            "agents_list" => {
+                let project = context
+                    .service_config
+                    .project_name
+                    .clone()
+                    .or(endpoint_project);
+                self.test_agents_list(context, &scenario, &base, project)
+                    .await
+            }
+            _ => TestResult::failure(
+                scenario_id,
+                scenario.name,
+                0,
+                format!("Scenario '{}' not implemented", scenario_id),
+            ),
+        }
+    }
+}
+
+impl FoundryService {
+    async fn test_endpoint_check(
+        &self,
+        context: &TestContext,
+        scenario: &TestScenario,
+        base: &str,
+    ) -> TestResult {
+        let (result, duration_ms) = measure_time(async {
+            match context.send(context.client.get(base)).await {
+                Ok(response) => {
+                    let status = response.status();
+                    if status.as_u16() < 500 {
+                        Ok(format!("Endpoint reachable (HTTP {})", status))
+                    } else {
+                        let body = response.text().await.unwrap_or_default();
+                        Err((
+                            status.as_u16(),
+                            format!(
+                                "HTTP {}: {}",
+                                status,
+                                sanitize_error(&body, status.as_u16())
+                            ),
+                        ))
+                    }
+                }
+                Err(e) => Err((0, request_error("Connection failed", &e))),
+            }
+        })
+        .await;
+
+        TestResult::from_outcome(scenario, duration_ms, result)
+    }
+
+    async fn test_model_inference(
+        &self,
+        context: &TestContext,
+        scenario: &TestScenario,
+        base: &str,
+    ) -> TestResult {
+        let Some(deployment) = context.service_config.deployment_name.as_deref() else {
+            return TestResult::skipped(
+                scenario.id,
+                scenario.name,
+                "Requires a model deployment name (--foundry-deployment or deployment_name in [services.ai_foundry])"
+                    .to_string(),
+            );
+        };
+
+        let url = format!(
+            "{}/models/chat/completions?api-version={}",
+            base,
+            api_versions::FOUNDRY_INFERENCE.version(context)
+        );
+        let body = serde_json::json!({
+            "model": deployment,
+            "messages": [{"role": "user", "content": "ping"}],
+            "max_tokens": 1
+        });
+        ScenarioRequest::post(url)
+            .json(&body)
+            .extract(|completion: ChatCompletion| {
+                let model = completion.model.as_deref().unwrap_or(deployment);
+                Ok(format!("Completion returned by {}", model))
+            })
+            .run(context, scenario)
+            .await
+    }
+
+    async fn test_agents_list(
+        &self,
+        context: &TestContext,
+        scenario: &TestScenario,
+        base: &str,
+        project: Option<String>,
+    ) -> TestResult {
+        let Some(project) = project else {
+            return TestResult::skipped(
+                scenario.id,
+                scenario.name,
+                "Requires a project (--foundry-project, project_name in [services.ai_foundry], or a project endpoint)"
+                    .to_string(),
+            );
+        };
+        if matches!(context.credentials, Credentials::ApiKey(_)) {
+            return TestResult::skipped(
+                scenario.id,
+                scenario.name,
+                "Agents don't accept API keys; use Entra ID auth (e.g. --auth device-code)"
+                    .to_string(),
+            );
+        }
+
+        let url = format!(
+            "{}/api/projects/{}/assistants?api-version={}&limit=1",
+            base,
+            project,
+            api_versions::FOUNDRY_AGENTS.version(context)
+        );
+        ScenarioRequest::get(url)
+            .extract(|_: List| Ok(format!("Agents reachable in project '{}'", project)))
+            .on_error(|status, body| {
+                let mut error = http_error(status, body);
+                if status == reqwest::StatusCode::UNAUTHORIZED
+                    && matches!(context.credentials, Credentials::BearerToken(_))
+                {
+                    error.push_str(
+                        " (Foundry project APIs expect a token for the https://ai.azure.com audience)",
+                    );
+                }
+                Err(error)
+            })
+            .run(context, scenario)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_project_endpoint() {
+        assert_eq!(
+            split_project_endpoint("https://contoso.services.ai.azure.com/api/projects/chatbot/"),
+            (
+                "https://contoso.services.ai.azure.com".to_string(),
+                Some("chatbot".to_string())
+            )
+        );
+        assert_eq!(
+            split_project_endpoint("https://contoso.services.ai.azure.com/"),
+            ("https://contoso.services.ai.azure.com".to_string(), None)
+        );
+        assert_eq!(
+            FoundryService::new().get_endpoint("eastus", Cloud::Global, None),
+            PLACEHOLDER_ENDPOINT
+        );
+    }
+}
@@ -0,0 +1,204 @@
+//! Azure AI model inference endpoints (serverless / Models-as-a-Service)
+//!
+//! Serverless deployments (Llama, Phi, Mistral, ...) get their own hostname,
+//! e.g. `https://<name>.<region>.models.ai.azure.com` or
+//! `https://<name>.<region>.inference.ai.azure.com`, and take the key as a
+//! bearer token. The same routes also work on a Foundry resource's
+//! `https://<resource>.services.ai.azure.com/models` endpoint, where the
+//! deployment name selects the model.
+
+use async_trait::async_trait;
+
+use crate::auth::Credentials;
+use crate::config::Cloud;
+use crate::services::responses::openai::{ChatCompletion, ModelInfo};
+use crate::services::{
+    api_versions, tags, AzureService, ScenarioRequest, TestContext, TestResult, TestScenario,
+};
+
+/// Shown when no endpoint was given
+const PLACEHOLDER_ENDPOINT: &str = "https://<deployment>.<region>.models.ai.azure.com";
+
+/// Model Inference Service implementation
+pub struct ModelInferenceService;
+
+impl ModelInferenceService {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for ModelInferenceService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Base URL for the inference routes. The portal's target URI often includes
+/// `/chat/completions`; strip it so either form can be pasted.
+pub fn inference_base(endpoint: &str) -> String {
+    let endpoint = endpoint.trim_end_matches('/');
+    endpoint
+        .strip_suffix("/chat/completions")
+        .unwrap_or(endpoint)
+        .to_string()
+}
+
+#[async_trait]
+impl AzureService for ModelInferenceService {
+    fn name(&self) -> &'static str {
+        "model_inference"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Model Inference"
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["model-inference", "inference"]
+    }
+
+    fn get_endpoint(&self, _region: &str, _cloud: Cloud, custom_endpoint: Option<&str>) -> String {
+        match custom_endpoint {
+            Some(endpoint) => inference_base(endpoint),
+            None => PLACEHOLDER_ENDPOINT.to_string(),
+        }
+    }
+
+    fn list_scenarios(&self) -> Vec<TestScenario> {
+        vec![
+            TestScenario {
+                id: "model_info",
+                name: "Model Info",
+                description: "Get the deployed model's name and provider (/info)",
+                requires_input: false,
+                input_type: None,
+                tags: &[tags::FAST],
+            },
+            TestScenario {
+                id: "chat_completions",
+                name: "Chat Completions",
+                description: "One-token chat completion (/chat/completions)",
+                requires_input: false,
+                input_type: None,
+                tags: &[],
+            },
+        ]
+    }
+
+    async fn run_scenario(&self, scenario_id: &str, context: &TestContext) -> TestResult {
+        let scenario = self
+            .list_scenarios()
+            .into_iter()
+            .find(|s| s.id == scenario_id);
+
+        let scenario = match scenario {
+            Some(s) => s,
+            None => {
+                return TestResult::failure(
+                    scenario_id,
+                    "Unknown",
+                    0,
+                    format!("Unknown scenario: {}", scenario_id),
+                )
+            }
+        };
+
+        let Some(endpoint) = context.endpoint.as_deref() else {
+            return TestResult::skipped(
+                scenario.id,
+                scenario.name,
+                format!("Requires --endpoint {}", PLACEHOLDER_ENDPOINT),
+            );
+        };
+        let base = inference_base(endpoint);
+        let deployment = context.service_config.deployment_name.as_deref();
+
+        match scenario_id {
+            "model_info" => {
+                let mut url = format!(
+                    "{}/info?api-version={}",
+                    base,
+                    api_versions::MODEL_INFERENCE.version(context)
+                );
+                if let Some(deployment) = deployment {
+                    url.push_str(&format!("&model={}", deployment));
+                }
+                let request = ScenarioRequest::get(url);
+                Self::run_request(context, &scenario, request, |info: ModelInfo| {
+                    let provider = info
+                        .model_provider_name
+                        .as_deref()
+                        .unwrap_or("unknown provider");
+                    format!("Model: {} ({})", info.model_name, provider)
+                })
+                .await
+            }
+            "chat_completions" => {
+                let url = format!(
+                    "{}/chat/completions?api-version={}",
+                    base,
+                    api_versions::MODEL_INFERENCE.version(context)
+                );
+                let mut body = serde_json::json!({
+                    "messages": [{"role": "user", "content": "ping"}],
+                    "max_tokens": 1
+                });
+                if let Some(deployment) = deployment {
+                    body["model"] = serde_json::Value::String(deployment.to_string());
+                }
+                let request = ScenarioRequest::post(url).json(&body);
+                Self::run_request(context, &scenario, request, |completion: ChatCompletion| {
+                    let model = completion.model.as_deref().unwrap_or("the deployment");
+                    format!("Completion returned by {}", model)
+                })
+                .await
+            }
+            _ => TestResult::failure(
+                scenario_id,
+                scenario.name,
+                0,
+                format!("Scenario '{}' not implemented", scenario_id),
+            ),
+        }
+    }
+}
+
+impl ModelInferenceService {
+    /// Send a request authorized with the key or token as a bearer token, and
+    /// describe a successful JSON response
+    async fn run_request<'a, T: serde::de::DeserializeOwned>(
+        context: &TestContext,
+        scenario: &TestScenario,
+        request: ScenarioRequest<'a>,
+        describe: impl FnOnce(T) -> String + Send + 'a,
+    ) -> TestResult {
+        let secret = match &context.credentials {
+            Credentials::ApiKey(key) => key,
+            Credentials::BearerToken(token) => token,
+        };
+        request
+            .without_credentials()
+            .header("Authorization", format!("Bearer {}", secret))
+            .extract(|response| Ok(describe(response)))
+            .run(context, scenario)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inference_base() {
+        assert_eq!(
+            inference_base("https://llama-3-70b.eastus2.models.ai.azure.com/chat/completions"),
+            "https://llama-3-70b.eastus2.models.ai.azure.com"
+        );
+        assert_eq!(
+            inference_base("https://contoso.services.ai.azure.com/models/"),
+            "https://contoso.services.ai.azure.com/models"
+        );
+    }
+}
@@ -2,8 +2,14 @@ use async_trait::async_trait;
 
 use crate::config::Cloud;
 use crate::error::sanitize_error;
+use crate::services::endpoints::ServiceEndpoint;
+use crate::services::responses::language::{
+    AnalyzeJob, AnalyzeTextResponse, ConversationResponse, Document, KnowledgeBaseAnswers,
+};
 use crate::services::{
-    measure_time, AzureService, InputType, TestContext, TestResult, TestScenario,
+    api_versions, classify::request_error, container, http_error, long_poll, measure_time,
+    responses, tags, AzureService, InputType, ScenarioRequest, TestContext, TestResult,
+    TestScenario,
 };
 
 /// Language Service implementation
@@ -32,23 +38,26 @@ impl AzureService for LanguageService {
     }
 
     fn get_endpoint(&self, region: &str, cloud: Cloud, custom_endpoint: Option<&str>) -> String {
-        if let Some(endpoint) = custom_endpoint {
-            return endpoint.to_string();
-        }
-        match cloud {
-            Cloud::Global => format!("https://{}.api.cognitive.microsoft.com", region),
-            Cloud::China => format!("https://{}.api.cognitive.azure.cn", region),
-        }
+        ServiceEndpoint::resolve(region, cloud, custom_endpoint).url()
     }
 
     fn list_scenarios(&self) -> Vec<TestScenario> {
-        vec![
+        let mut scenarios = vec![
             TestScenario {
                 id: "sentiment",
                 name: "Sentiment Analysis",
                 description: "Analyze sentiment of text",
                 requires_input: false,
                 input_type: Some(InputType::Text),
+                tags: &[tags::FAST, tags::REQUIRES_INPUT],
+            },
+            TestScenario {
+                id: "sentiment_opinion_mining",
+                name: "Sentiment with Opinion Mining",
+                description: "Analyze sentiment with aspect-based opinion mining",
+                requires_input: false,
+                input_type: Some(InputType::Text),
+                tags: &[tags::FAST, tags::REQUIRES_INPUT],
             },
             TestScenario {
                 id: "language_detection",
@@ -56,6 +65,7 @@ impl AzureService for LanguageService {
                 description: "Detect language of text",
                 requires_input: false,
                 input_type: Some(InputType::Text),
+                tags: &[tags::FAST, tags::REQUIRES_INPUT],
             },
             TestScenario {
                 id: "entities",
@@ -63,6 +73,7 @@ impl AzureService for LanguageService {
                 description: "Extract named entities from text",
                 requires_input: false,
                 input_type: Some(InputType::Text),
+                tags: &[tags::FAST, tags::REQUIRES_INPUT],
             },
             TestScenario {
                 id: "key_phrases",
@@ -70,6 +81,7 @@ impl AzureService for LanguageService {
                 description: "Extract key phrases from text",
                 requires_input: false,
                 input_type: Some(InputType::Text),
+                tags: &[tags::FAST, tags::REQUIRES_INPUT],
             },
             TestScenario {
                 id: "pii_detection",
@@ -77,6 +89,7 @@ impl AzureService for LanguageService {
                 description: "Detect personally identifiable information",
                 requires_input: false,
                 input_type: Some(InputType::Text),
+                tags: &[tags::FAST, tags::REQUIRES_INPUT],
             },
             TestScenario {
                 id: "entity_linking",
@@ -84,6 +97,7 @@ impl AzureService for LanguageService {
                 description: "Link entities to Wikipedia knowledge base",
                 requires_input: false,
                 input_type: Some(InputType::Text),
+                tags: &[tags::FAST, tags::REQUIRES_INPUT],
             },
             TestScenario {
                 id: "summarization",
@@ -91,7 +105,72 @@ impl AzureService for LanguageService {
                 description: "Generate abstractive summary of text",
                 requires_input: false,
                 input_type: Some(InputType::Text),
+                tags: &[tags::REQUIRES_INPUT, tags::ASYNC_POLL],
+            },
+            TestScenario {
+                id: "extractive_summarization",
+                name: "Extractive Summarization",
+                description: "Extract the most important sentences of text",
+                requires_input: false,
+                input_type: Some(InputType::Text),
+                tags: &[tags::REQUIRES_INPUT, tags::ASYNC_POLL],
+            },
+            TestScenario {
+                id: "clu",
+                name: "Conversational Language Understanding",
+                description: "Query a deployed CLU project (requires clu_project_name/clu_deployment_name)",
+                requires_input: false,
+                input_type: Some(InputType::Text),
+                tags: &[tags::REQUIRES_INPUT],
+            },
+            TestScenario {
+                id: "custom_ner",
+                name: "Custom Entity Recognition",
+                description:
+                    "Run a deployed custom NER project (requires ner_project_name/ner_deployment_name)",
+                requires_input: false,
+                input_type: Some(InputType::Text),
+                tags: &[tags::REQUIRES_INPUT, tags::ASYNC_POLL],
+            },
+            TestScenario {
+                id: "healthcare",
+                name: "Text Analytics for Health",
+                description: "Extract medical entities and relations (offered in selected regions)",
+                requires_input: false,
+                input_type: Some(InputType::Text),
+                tags: &[tags::REQUIRES_INPUT, tags::ASYNC_POLL],
+            },
+            TestScenario {
+                id: "custom_classification",
+                name: "Custom Text Classification",
+                description:
+                    "Run a deployed single-label classification project (requires project_name/deployment_name)",
+                requires_input: false,
+                input_type: Some(InputType::Text),
+                tags: &[tags::REQUIRES_INPUT, tags::ASYNC_POLL],
+            },
+            TestScenario {
+                id: "question_answering",
+                name: "Question Answering",
+                description: "Query a deployed Question Answering project (requires qa_project_name/qa_deployment_name)",
+                requires_input: false,
+                input_type: Some(InputType::Text),
+                tags: &[tags::REQUIRES_INPUT],
             },
+        ];
+        scenarios.extend(container::health_scenarios());
+        scenarios
+    }
+
+    fn container_scenarios(&self) -> &'static [&'static str] {
+        &[
+            "sentiment",
+            "language_detection",
+            "entities",
+            "key_phrases",
+            "pii_detection",
+            "container_ready",
+            "container_status",
         ]
     }
 
@@ -115,12 +194,26 @@ impl AzureService for LanguageService {
 
         match scenario_id {
             "sentiment" => self.test_sentiment(context, &scenario).await,
+            "sentiment_opinion_mining" => self.test_opinion_mining(context, &scenario).await,
             "language_detection" => self.test_language_detection(context, &scenario).await,
             "entities" => self.test_entities(context, &scenario).await,
             "key_phrases" => self.test_key_phrases(context, &scenario).await,
             "pii_detection" => self.test_pii_detection(context, &scenario).await,
             "entity_linking" => self.test_entity_linking(context, &scenario).await,
             "summarization" => self.test_summarization(context, &scenario).await,
+            "extractive_summarization" => {
+                self.test_extractive_summarization(context, &scenario).await
+            }
+            "clu" => self.test_clu(context, &scenario).await,
+            "custom_ner" => self.test_custom_ner(context, &scenario).await,
+            "question_answering" => self.test_question_answering(context, &scenario).await,
+            "healthcare" => self.test_healthcare(context, &scenario).await,
+            "custom_classification" => self.test_custom_classification(context, &scenario).await,
+            container::READY_SCENARIO | container::STATUS_SCENARIO => {
+                let endpoint =
+                    self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
+                container::run_health_check(context, &scenario, &endpoint).await
+            }
             _ => TestResult::failure(
                 scenario_id,
                 scenario.name,
@@ -134,8 +227,7 @@ impl AzureService for LanguageService {
 impl LanguageService {
     fn get_sample_text(context: &TestContext) -> String {
         context
-            .input
-            .as_ref()
+            .input(InputType::Text)
             .and_then(|i| i.text.clone())
             .unwrap_or_else(|| {
                 "The Azure AI services are excellent. Microsoft has done a great job with their cloud platform. \
@@ -144,13 +236,55 @@ impl LanguageService {
             })
     }
 
+    /// A longer text for summarization
+    fn get_summary_text(context: &TestContext) -> String {
+        context
+            .input(InputType::Text)
+            .and_then(|i| i.text.clone())
+            .unwrap_or_else(|| {
+                "Azure Cognitive Services are cloud-based artificial intelligence services that help \
+                 developers build cognitive intelligence into applications without having direct AI or \
+                 data science skills or knowledge. They are available through REST APIs and client \
+                 library SDKs in popular development languages. Azure Cognitive Services enables \
+                 developers to easily add cognitive features into their applications with cognitive \
+                 solutions that can see, hear, speak, and analyze. The catalog of cognitive services \
+                 covers five main pillars: Vision, Speech, Language, Decision, and Azure OpenAI Service. \
+                 These services help build applications for many use cases across many industries.".to_string()
+            })
+    }
+
+    /// Merge the scenario's `[services.language] options` into the request's
+    /// `parameters` (of the first task, for jobs), overriding the defaults
+    fn apply_options(body: &mut serde_json::Value, context: &TestContext, scenario_id: &str) {
+        let Some(options) = context.service_config.options.get(scenario_id) else {
+            return;
+        };
+        let target = if body.get("tasks").is_some() {
+            &mut body["tasks"][0]
+        } else {
+            body
+        };
+        if !target.get("parameters").is_some_and(|p| p.is_object()) {
+            target["parameters"] = serde_json::json!({});
+        }
+        if let Some(parameters) = target["parameters"].as_object_mut() {
+            for (key, value) in options {
+                parameters.insert(key.clone(), value.clone());
+            }
+        }
+    }
+
     async fn test_sentiment(&self, context: &TestContext, scenario: &TestScenario) -> TestResult {
         let endpoint =
             self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
-        let url = format!("{}/language/:analyze-text?api-version=2023-04-01", endpoint);
+        let url = format!(
+            "{}/language/:analyze-text?api-version={}",
+            endpoint,
+            api_versions::LANGUAGE.version(context)
+        );
 
         let text = Self::get_sample_text(context);
-        let body = serde_json::json!({
+        let mut body = serde_json::json!({
             "kind": "SentimentAnalysis",
             "analysisInput": {
                 "documents": [
@@ -158,67 +292,17 @@ impl LanguageService {
                 ]
             }
         });
+        Self::apply_options(&mut body, context, scenario.id);
 
-        let (result, duration_ms) = measure_time(async {
-            let request = context
-                .client
-                .post(&url)
-                .header("Content-Type", "application/json")
-                .json(&body);
-            let request = context.credentials.apply_to_request(request);
-
-            match request.send().await {
-                Ok(response) => {
-                    let status = response.status();
-                    if status.is_success() {
-                        let body: serde_json::Value = response.json().await.unwrap_or_default();
-                        if let Some(docs) = body
-                            .get("results")
-                            .and_then(|r| r.get("documents"))
-                            .and_then(|d| d.as_array())
-                        {
-                            if let Some(doc) = docs.first() {
-                                let sentiment = doc
-                                    .get("sentiment")
-                                    .and_then(|s| s.as_str())
-                                    .unwrap_or("unknown");
-                                Ok(format!("Sentiment: {}", sentiment))
-                            } else {
-                                Err((status.as_u16(), "No documents in response".to_string()))
-                            }
-                        } else {
-                            Err((status.as_u16(), "Invalid response format".to_string()))
-                        }
-                    } else {
-                        let body = response.text().await.unwrap_or_default();
-                        Err((
-                            status.as_u16(),
-                            format!(
-                                "HTTP {}: {}",
-                                status,
-                                sanitize_error(&body, status.as_u16())
-                            ),
-                        ))
-                    }
-                }
-                Err(e) => Err((0, format!("Request failed: {}", e))),
-            }
-        })
-        .await;
-
-        match result {
-            Ok(details) => {
-                TestResult::success(scenario.id, scenario.name, duration_ms).with_details(details)
-            }
-            Err((status, error)) => {
-                let mut result =
-                    TestResult::failure(scenario.id, scenario.name, duration_ms, error);
-                if status > 0 {
-                    result = result.with_http_status(status);
-                }
-                result
-            }
-        }
+        ScenarioRequest::post(url)
+            .json(&body)
+            .extract(|response: AnalyzeTextResponse| {
+                let doc = response.results.first_document()?;
+                let sentiment = doc.sentiment.as_deref().unwrap_or("unknown");
+                Ok(format!("Sentiment: {}", sentiment))
+            })
+            .run(context, scenario)
+            .await
     }
 
     async fn test_language_detection(
@@ -228,10 +312,14 @@ impl LanguageService {
     ) -> TestResult {
         let endpoint =
             self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
-        let url = format!("{}/language/:analyze-text?api-version=2023-04-01", endpoint);
+        let url = format!(
+            "{}/language/:analyze-text?api-version={}",
+            endpoint,
+            api_versions::LANGUAGE.version(context)
+        );
 
         let text = Self::get_sample_text(context);
-        let body = serde_json::json!({
+        let mut body = serde_json::json!({
             "kind": "LanguageDetection",
             "analysisInput": {
                 "documents": [
@@ -239,77 +327,33 @@ impl LanguageService {
                 ]
             }
         });
+        Self::apply_options(&mut body, context, scenario.id);
 
-        let (result, duration_ms) = measure_time(async {
-            let request = context
-                .client
-                .post(&url)
-                .header("Content-Type", "application/json")
-                .json(&body);
-            let request = context.credentials.apply_to_request(request);
-
-            match request.send().await {
-                Ok(response) => {
-                    let status = response.status();
-                    if status.is_success() {
-                        let body: serde_json::Value = response.json().await.unwrap_or_default();
-                        if let Some(docs) = body
-                            .get("results")
-                            .and_then(|r| r.get("documents"))
-                            .and_then(|d| d.as_array())
-                        {
-                            if let Some(doc) = docs.first() {
-                                let lang = doc
-                                    .get("detectedLanguage")
-                                    .and_then(|l| l.get("name"))
-                                    .and_then(|n| n.as_str())
-                                    .unwrap_or("unknown");
-                                Ok(format!("Detected: {}", lang))
-                            } else {
-                                Err((status.as_u16(), "No documents in response".to_string()))
-                            }
-                        } else {
-                            Err((status.as_u16(), "Invalid response format".to_string()))
-                        }
-                    } else {
-                        let body = response.text().await.unwrap_or_default();
-                        Err((
-                            status.as_u16(),
-                            format!(
-                                "HTTP {}: {}",
-                                status,
-                                sanitize_error(&body, status.as_u16())
-                            ),
-                        ))
-                    }
-                }
-                Err(e) => Err((0, format!("Request failed: {}", e))),
-            }
-        })
-        .await;
-
-        match result {
-            Ok(details) => {
-                TestResult::success(scenario.id, scenario.name, duration_ms).with_details(details)
-            }
-            Err((status, error)) => {
-                let mut result =
-                    TestResult::failure(scenario.id, scenario.name, duration_ms, error);
-                if status > 0 {
-                    result = result.with_http_status(status);
-                }
-                result
-            }
-        }
+        ScenarioRequest::post(url)
+            .json(&body)
+            .extract(|response: AnalyzeTextResponse| {
+                let doc = response.results.first_document()?;
+                let lang = doc
+                    .detected_language
+                    .as_ref()
+                    .map_or("unknown", |l| l.name.as_str());
+                Ok(format!("Detected: {}", lang))
+            })
+            .run(context, scenario)
+            .await
     }
 
     async fn test_entities(&self, context: &TestContext, scenario: &TestScenario) -> TestResult {
         let endpoint =
             self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
-        let url = format!("{}/language/:analyze-text?api-version=2023-04-01", endpoint);
+        let url = format!(
+            "{}/language/:analyze-text?api-version={}",
+            endpoint,
+            api_versions::LANGUAGE.version(context)
+        );
 
         let text = Self::get_sample_text(context);
-        let body = serde_json::json!({
+        let mut body = serde_json::json!({
             "kind": "EntityRecognition",
             "analysisInput": {
                 "documents": [
@@ -317,77 +361,30 @@ impl LanguageService {
                 ]
             }
         });
+        Self::apply_options(&mut body, context, scenario.id);
 
-        let (result, duration_ms) = measure_time(async {
-            let request = context
-                .client
-                .post(&url)
-                .header("Content-Type", "application/json")
-                .json(&body);
-            let request = context.credentials.apply_to_request(request);
-
-            match request.send().await {
-                Ok(response) => {
-                    let status = response.status();
-                    if status.is_success() {
-                        let body: serde_json::Value = response.json().await.unwrap_or_default();
-                        if let Some(docs) = body
-                            .get("results")
-                            .and_then(|r| r.get("documents"))
-                            .and_then(|d| d.as_array())
-                        {
-                            if let Some(doc) = docs.first() {
-                                let entities = doc
-                                    .get("entities")
-                                    .and_then(|e| e.as_array())
-                                    .map(|e| e.len())
-                                    .unwrap_or(0);
-                                Ok(format!("Found {} entities", entities))
-                            } else {
-                                Err((status.as_u16(), "No documents in response".to_string()))
-                            }
-                        } else {
-                            Err((status.as_u16(), "Invalid response format".to_string()))
-                        }
-                    } else {
-                        let body = response.text().await.unwrap_or_default();
-                        Err((
-                            status.as_u16(),
-                            format!(
-                                "HTTP {}: {}",
-                                status,
-                                sanitize_error(&body, status.as_u16())
-                            ),
-                        ))
-                    }
-                }
-                Err(e) => Err((0, format!("Request failed: {}", e))),
-            }
-        })
-        .await;
-
-        match result {
-            Ok(details) => {
-                TestResult::success(scenario.id, scenario.name, duration_ms).with_details(details)
-            }
-            Err((status, error)) => {
-                let mut result =
-                    TestResult::failure(scenario.id, scenario.name, duration_ms, error);
-                if status > 0 {
-                    result = result.with_http_status(status);
-                }
-                result
-            }
-        }
+        ScenarioRequest::post(url)
+            .json(&body)
+            .extract(|response: AnalyzeTextResponse| {
+                let doc = response.results.first_document()?;
+                let count = doc.entities.len();
+                Ok(format!("Found {} entities", count))
+            })
+            .run(context, scenario)
+            .await
     }
 
     async fn test_key_phrases(&self, context: &TestContext, scenario: &TestScenario) -> TestResult {
         let endpoint =
             self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
-        let url = format!("{}/language/:analyze-text?api-version=2023-04-01", endpoint);
+        let url = format!(
+            "{}/language/:analyze-text?api-version={}",
+            endpoint,
+            api_versions::LANGUAGE.version(context)
+        );
 
         let text = Self::get_sample_text(context);
-        let body = serde_json::json!({
+        let mut body = serde_json::json!({
             "kind": "KeyPhraseExtraction",
             "analysisInput": {
                 "documents": [
@@ -395,68 +392,17 @@ impl LanguageService {
                 ]
             }
         });
+        Self::apply_options(&mut body, context, scenario.id);
 
-        let (result, duration_ms) = measure_time(async {
-            let request = context
-                .client
-                .post(&url)
-                .header("Content-Type", "application/json")
-                .json(&body);
-            let request = context.credentials.apply_to_request(request);
-
-            match request.send().await {
-                Ok(response) => {
-                    let status = response.status();
-                    if status.is_success() {
-                        let body: serde_json::Value = response.json().await.unwrap_or_default();
-                        if let Some(docs) = body
-                            .get("results")
-                            .and_then(|r| r.get("documents"))
-                            .and_then(|d| d.as_array())
-                        {
-                            if let Some(doc) = docs.first() {
-                                let phrases = doc
-                                    .get("keyPhrases")
-                                    .and_then(|p| p.as_array())
-                                    .map(|p| p.len())
-                                    .unwrap_or(0);
-                                Ok(format!("Extracted {} key phrases", phrases))
-                            } else {
-                                Err((status.as_u16(), "No documents in response".to_string()))
-                            }
-                        } else {
-                            Err((status.as_u16(), "Invalid response format".to_string()))
-                        }
-                    } else {
-                        let body = response.text().await.unwrap_or_default();
-                        Err((
-                            status.as_u16(),
-                            format!(
-                                "HTTP {}: {}",
-                                status,
-                                sanitize_error(&body, status.as_u16())
-                            ),
-                        ))
-                    }
-                }
-                Err(e) => Err((0, format!("Request failed: {}", e))),
-            }
-        })
-        .await;
-
-        match result {
-            Ok(details) => {
-                TestResult::success(scenario.id, scenario.name, duration_ms).with_details(details)
-            }
-            Err((status, error)) => {
-                let mut result =
-                    TestResult::failure(scenario.id, scenario.name, duration_ms, error);
-                if status > 0 {
-                    result = result.with_http_status(status);
-                }
-                result
-            }
-        }
+        ScenarioRequest::post(url)
+            .json(&body)
+            .extract(|response: AnalyzeTextResponse| {
+                let doc = response.results.first_document()?;
+                let count = doc.key_phrases.len();
+                Ok(format!("Extracted {} key phrases", count))
+            })
+            .run(context, scenario)
+            .await
     }
 
     async fn test_pii_detection(
@@ -466,12 +412,15 @@ impl LanguageService {
     ) -> TestResult {
         let endpoint =
             self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
-        let url = format!("{}/language/:analyze-text?api-version=2023-04-01", endpoint);
+        let url = format!(
+            "{}/language/:analyze-text?api-version={}",
+            endpoint,
+            api_versions::LANGUAGE.version(context)
+        );
 
         // Use sample text with PII for testing
         let text = context
-            .input
-            .as_ref()
+            .input(InputType::Text)
             .and_then(|i| i.text.clone())
             .unwrap_or_else(|| {
                 "My name is John Smith and my email is john.smith@example.com. \
@@ -479,7 +428,7 @@ impl LanguageService {
                     .to_string()
             });
 
-        let body = serde_json::json!({
+        let mut body = serde_json::json!({
             "kind": "PiiEntityRecognition",
             "analysisInput": {
                 "documents": [
@@ -487,68 +436,17 @@ impl LanguageService {
                 ]
             }
         });
+        Self::apply_options(&mut body, context, scenario.id);
 
-        let (result, duration_ms) = measure_time(async {
-            let request = context
-                .client
-                .post(&url)
-                .header("Content-Type", "application/json")
-                .json(&body);
-            let request = context.credentials.apply_to_request(request);
-
-            match request.send().await {
-                Ok(response) => {
-                    let status = response.status();
-                    if status.is_success() {
-                        let body: serde_json::Value = response.json().await.unwrap_or_default();
-                        if let Some(docs) = body
-                            .get("results")
-                            .and_then(|r| r.get("documents"))
-                            .and_then(|d| d.as_array())
-                        {
-                            if let Some(doc) = docs.first() {
-                                let entities = doc
-                                    .get("entities")
-                                    .and_then(|e| e.as_array())
-                                    .map(|e| e.len())
-                                    .unwrap_or(0);
-                                Ok(format!("Found {} PII entities", entities))
-                            } else {
-                                Err((status.as_u16(), "No documents in response".to_string()))
-                            }
-                        } else {
-                            Err((status.as_u16(), "Invalid response format".to_string()))
-                        }
-                    } else {
-                        let body = response.text().await.unwrap_or_default();
-                        Err((
-                            status.as_u16(),
-                            format!(
-                                "HTTP {}: {}",
-                                status,
-                                sanitize_error(&body, status.as_u16())
-                            ),
-                        ))
-                    }
-                }
-                Err(e) => Err((0, format!("Request failed: {}", e))),
-            }
-        })
-        .await;
-
-        match result {
-            Ok(details) => {
-                TestResult::success(scenario.id, scenario.name, duration_ms).with_details(details)
-            }
-            Err((status, error)) => {
-                let mut result =
-                    TestResult::failure(scenario.id, scenario.name, duration_ms, error);
-                if status > 0 {
-                    result = result.with_http_status(status);
-                }
-                result
-            }
-        }
+        ScenarioRequest::post(url)
+            .json(&body)
+            .extract(|response: AnalyzeTextResponse| {
+                let doc = response.results.first_document()?;
+                let count = doc.entities.len();
+                Ok(format!("Found {} PII entities", count))
+            })
+            .run(context, scenario)
+            .await
     }
 
     async fn test_entity_linking(
@@ -558,12 +456,15 @@ impl LanguageService {
     ) -> TestResult {
         let endpoint =
             self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
-        let url = format!("{}/language/:analyze-text?api-version=2023-04-01", endpoint);
+        let url = format!(
+            "{}/language/:analyze-text?api-version={}",
+            endpoint,
+            api_versions::LANGUAGE.version(context)
+        );
 
         // Use sample text with linkable entities
         let text = context
-            .input
-            .as_ref()
+            .input(InputType::Text)
             .and_then(|i| i.text.clone())
             .unwrap_or_else(|| {
                 "Microsoft was founded by Bill Gates and Paul Allen in Albuquerque, New Mexico. \
@@ -571,7 +472,7 @@ impl LanguageService {
                     .to_string()
             });
 
-        let body = serde_json::json!({
+        let mut body = serde_json::json!({
             "kind": "EntityLinking",
             "analysisInput": {
                 "documents": [
@@ -579,68 +480,17 @@ impl LanguageService {
                 ]
             }
         });
+        Self::apply_options(&mut body, context, scenario.id);
 
-        let (result, duration_ms) = measure_time(async {
-            let request = context
-                .client
-                .post(&url)
-                .header("Content-Type", "application/json")
-                .json(&body);
-            let request = context.credentials.apply_to_request(request);
-
-            match request.send().await {
-                Ok(response) => {
-                    let status = response.status();
-                    if status.is_success() {
-                        let body: serde_json::Value = response.json().await.unwrap_or_default();
-                        if let Some(docs) = body
-                            .get("results")
-                            .and_then(|r| r.get("documents"))
-                            .and_then(|d| d.as_array())
-                        {
-                            if let Some(doc) = docs.first() {
-                                let entities = doc
-                                    .get("entities")
-                                    .and_then(|e| e.as_array())
-                                    .map(|e| e.len())
-                                    .unwrap_or(0);
-                                Ok(format!("Linked {} entities", entities))
-                            } else {
-                                Err((status.as_u16(), "No documents in response".to_string()))
-                            }
-                        } else {
-                            Err((status.as_u16(), "Invalid response format".to_string()))
-                        }
-                    } else {
-                        let body = response.text().await.unwrap_or_default();
-                        Err((
-                            status.as_u16(),
-                            format!(
-                                "HTTP {}: {}",
-                                status,
-                                sanitize_error(&body, status.as_u16())
-                            ),
-                        ))
-                    }
-                }
-                Err(e) => Err((0, format!("Request failed: {}", e))),
-            }
-        })
-        .await;
-
-        match result {
-            Ok(details) => {
-                TestResult::success(scenario.id, scenario.name, duration_ms).with_details(details)
-            }
-            Err((status, error)) => {
-                let mut result =
-                    TestResult::failure(scenario.id, scenario.name, duration_ms, error);
-                if status > 0 {
-                    result = result.with_http_status(status);
-                }
-                result
-            }
-        }
+        ScenarioRequest::post(url)
+            .json(&body)
+            .extract(|response: AnalyzeTextResponse| {
+                let doc = response.results.first_document()?;
+                let count = doc.entities.len();
+                Ok(format!("Linked {} entities", count))
+            })
+            .run(context, scenario)
+            .await
     }
 
     async fn test_summarization(
@@ -653,27 +503,14 @@ impl LanguageService {
 
         // Summarization uses the async analyze-text/jobs endpoint
         let url = format!(
-            "{}/language/analyze-text/jobs?api-version=2023-04-01",
-            endpoint
+            "{}/language/analyze-text/jobs?api-version={}",
+            endpoint,
+            api_versions::LANGUAGE.version(context)
         );
 
-        // Use a longer text for summarization
-        let text = context
-            .input
-            .as_ref()
-            .and_then(|i| i.text.clone())
-            .unwrap_or_else(|| {
-                "Azure Cognitive Services are cloud-based artificial intelligence services that help \
-                 developers build cognitive intelligence into applications without having direct AI or \
-                 data science skills or knowledge. They are available through REST APIs and client \
-                 library SDKs in popular development languages. Azure Cognitive Services enables \
-                 developers to easily add cognitive features into their applications with cognitive \
-                 solutions that can see, hear, speak, and analyze. The catalog of cognitive services \
-                 covers five main pillars: Vision, Speech, Language, Decision, and Azure OpenAI Service. \
-                 These services help build applications for many use cases across many industries.".to_string()
-            });
+        let text = Self::get_summary_text(context);
 
-        let body = serde_json::json!({
+        let mut body = serde_json::json!({
             "displayName": "Summarization Test",
             "analysisInput": {
                 "documents": [
@@ -690,6 +527,7 @@ impl LanguageService {
                 }
             ]
         });
+        Self::apply_options(&mut body, context, scenario.id);
 
         let (result, duration_ms) = measure_time(async {
             let request = context
@@ -699,7 +537,7 @@ impl LanguageService {
                 .json(&body);
             let request = context.credentials.apply_to_request(request);
 
-            match request.send().await {
+            match context.send(request).await {
                 Ok(response) => {
                     let status = response.status();
                     if status.as_u16() == 202 {
@@ -708,6 +546,13 @@ impl LanguageService {
                             response.headers().get("operation-location")
                         {
                             let op_url = operation_location.to_str().unwrap_or_default();
+                            if let Some(long_poll) = context.long_poll {
+                                let (body, summary) =
+                                    long_poll::poll(context, op_url, long_poll).await?;
+                                let details =
+                                    Self::summarization_details(body).map_err(|e| (200, e))?;
+                                return Ok(format!("{}; {}", details, summary));
+                            }
                             // Poll for result (with timeout)
                             for _ in 0..10 {
                                 tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
@@ -716,7 +561,7 @@ impl LanguageService {
                                 let poll_request =
                                     context.credentials.apply_to_request(poll_request);
 
-                                if let Ok(poll_response) = poll_request.send().await {
+                                if let Ok(poll_response) = context.send(poll_request).await {
                                     if poll_response.status().is_success() {
                                         let poll_body: serde_json::Value =
                                             poll_response.json().await.unwrap_or_default();
@@ -726,33 +571,8 @@ impl LanguageService {
                                             .unwrap_or("");
 
                                         if job_status == "succeeded" {
-                                            // Get summary from results
-                                            if let Some(tasks) = poll_body
-                                                .get("tasks")
-                                                .and_then(|t| t.get("items"))
-                                                .and_then(|i| i.as_array())
-                                            {
-                                                if let Some(task) = tasks.first() {
-                                                    if let Some(docs) = task
-                                                        .get("results")
-                                                        .and_then(|r| r.get("documents"))
-                                                        .and_then(|d| d.as_array())
-                                                    {
-                                                        if let Some(doc) = docs.first() {
-                                                            let summaries = doc
-                                                                .get("summaries")
-                                                                .and_then(|s| s.as_array())
-                                                                .map(|s| s.len())
-                                                                .unwrap_or(0);
-                                                            return Ok(format!(
-                                                                "Generated {} summary/summaries",
-                                                                summaries
-                                                            ));
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                            return Ok("Summarization completed".to_string());
+                                            return Self::summarization_details(poll_body)
+                                                .map_err(|e| (200, e));
                                         } else if job_status == "failed" {
                                             return Err((
                                                 200,
@@ -772,33 +592,755 @@ impl LanguageService {
                         Ok("Summarization submitted".to_string())
                     } else {
                         let body = response.text().await.unwrap_or_default();
-                        Err((
-                            status.as_u16(),
-                            format!(
-                                "HTTP {}: {}",
-                                status,
-                                sanitize_error(&body, status.as_u16())
-                            ),
-                        ))
+                        Err((status.as_u16(), http_error(status, &body)))
                     }
                 }
-                Err(e) => Err((0, format!("Request failed: {}", e))),
+                Err(e) => Err((0, request_error("Request failed", &e))),
             }
         })
         .await;
 
-        match result {
-            Ok(details) => {
-                TestResult::success(scenario.id, scenario.name, duration_ms).with_details(details)
+        TestResult::from_outcome(scenario, duration_ms, result)
+    }
+
+    async fn test_opinion_mining(
+        &self,
+        context: &TestContext,
+        scenario: &TestScenario,
+    ) -> TestResult {
+        let endpoint =
+            self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
+        let url = format!(
+            "{}/language/:analyze-text?api-version={}",
+            endpoint,
+            api_versions::LANGUAGE.version(context)
+        );
+
+        let text = Self::get_sample_text(context);
+        let mut body = serde_json::json!({
+            "kind": "SentimentAnalysis",
+            "parameters": {
+                "opinionMining": true
+            },
+            "analysisInput": {
+                "documents": [
+                    {"id": "1", "text": text, "language": "en"}
+                ]
+            }
+        });
+        Self::apply_options(&mut body, context, scenario.id);
+
+        ScenarioRequest::post(url)
+            .json(&body)
+            .extract(|response: AnalyzeTextResponse| {
+                let doc = response.results.first_document()?;
+                let sentiment = doc.sentiment.as_deref().unwrap_or("unknown");
+                let opinions: Vec<&str> = doc
+                    .sentences
+                    .iter()
+                    .flat_map(|s| &s.targets)
+                    .map(|t| t.text.as_str())
+                    .collect();
+                Ok(format!(
+                    "Sentiment: {}, {} opinion target(s){}",
+                    sentiment,
+                    opinions.len(),
+                    if opinions.is_empty() {
+                        String::new()
+                    } else {
+                        format!(": {}", opinions.join(", "))
+                    }
+                ))
+            })
+            .run(context, scenario)
+            .await
+    }
+
+    async fn test_extractive_summarization(
+        &self,
+        context: &TestContext,
+        scenario: &TestScenario,
+    ) -> TestResult {
+        let text = Self::get_summary_text(context);
+        let mut body = serde_json::json!({
+            "displayName": "Extractive Summarization Test",
+            "analysisInput": {
+                "documents": [
+                    {"id": "1", "text": text, "language": "en"}
+                ]
+            },
+            "tasks": [
+                {
+                    "kind": "ExtractiveSummarization",
+                    "taskName": "Extract",
+                    "parameters": {
+                        "sentenceCount": 3
+                    }
+                }
+            ]
+        });
+        Self::apply_options(&mut body, context, scenario.id);
+
+        let (result, duration_ms) = measure_time(async {
+            let doc = self
+                .run_analyze_job(context, &body, 10, tokio::time::Duration::from_millis(500))
+                .await?;
+            Ok::<_, (u16, String)>(format!("Extracted {} sentence(s)", doc.sentences.len()))
+        })
+        .await;
+
+        TestResult::from_outcome(scenario, duration_ms, result)
+    }
+
+    /// Details of a completed summarization job
+    fn summarization_details(body: serde_json::Value) -> Result<String, String> {
+        let job: AnalyzeJob = responses::from_value(body)?;
+        Ok(
+            match job.first_results().and_then(|r| r.documents.first()) {
+                Some(doc) => format!("Generated {} summary/summaries", doc.summaries.len()),
+                None => "Summarization completed".to_string(),
+            },
+        )
+    }
+
+    /// Get the configured custom project and deployment, if both are set
+    fn get_project(context: &TestContext) -> Option<(String, String)> {
+        let config = &context.service_config;
+        match (&config.project_name, &config.deployment_name) {
+            (Some(project), Some(deployment)) => Some((project.clone(), deployment.clone())),
+            _ => None,
+        }
+    }
+
+    /// Get a scenario's own project and deployment, each falling back to the
+    /// generic custom project
+    fn get_own_project(
+        context: &TestContext,
+        project: &Option<String>,
+        deployment: &Option<String>,
+    ) -> Option<(String, String)> {
+        let config = &context.service_config;
+        let project = project.as_ref().or(config.project_name.as_ref());
+        let deployment = deployment.as_ref().or(config.deployment_name.as_ref());
+        match (project, deployment) {
+            (Some(project), Some(deployment)) => Some((project.clone(), deployment.clone())),
+            _ => None,
+        }
+    }
+
+    /// Get the CLU project, falling back to the generic custom project
+    fn get_clu_project(context: &TestContext) -> Option<(String, String)> {
+        let config = &context.service_config;
+        Self::get_own_project(
+            context,
+            &config.clu_project_name,
+            &config.clu_deployment_name,
+        )
+    }
+
+    /// Get the custom NER project, falling back to the generic custom project
+    fn get_ner_project(context: &TestContext) -> Option<(String, String)> {
+        let config = &context.service_config;
+        Self::get_own_project(
+            context,
+            &config.ner_project_name,
+            &config.ner_deployment_name,
+        )
+    }
+
+    /// Get the Question Answering project, falling back to the generic custom project
+    fn get_qa_project(context: &TestContext) -> Option<(String, String)> {
+        let config = &context.service_config;
+        Self::get_own_project(context, &config.qa_project_name, &config.qa_deployment_name)
+    }
+
+    /// Submit an analyze-text job and poll until it completes.
+    /// Returns the first document of the first task's results on success.
+    async fn run_analyze_job(
+        &self,
+        context: &TestContext,
+        body: &serde_json::Value,
+        max_attempts: u32,
+        poll_interval: tokio::time::Duration,
+    ) -> Result<Document, (u16, String)> {
+        let endpoint =
+            self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
+        let url = format!(
+            "{}/language/analyze-text/jobs?api-version={}",
+            endpoint,
+            api_versions::LANGUAGE.version(context)
+        );
+
+        let request = context
+            .client
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .json(body);
+        let request = context.credentials.apply_to_request(request);
+
+        let response = context
+            .send(request)
+            .await
+            .map_err(|e| (0, request_error("Request failed", &e)))?;
+        let status = response.status();
+        if status.as_u16() != 202 {
+            let body = response.text().await.unwrap_or_default();
+            return Err((status.as_u16(), http_error(status, &body)));
+        }
+
+        let op_url = response
+            .headers()
+            .get("operation-location")
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string())
+            .ok_or((status.as_u16(), "No operation-location header".to_string()))?;
+
+        for _ in 0..max_attempts {
+            tokio::time::sleep(poll_interval).await;
+
+            let poll_request = context.client.get(&op_url);
+            let poll_request = context.credentials.apply_to_request(poll_request);
+            let poll_response = match context.send(poll_request).await {
+                Ok(r) => r,
+                Err(e) => return Err((0, request_error("Poll request failed", &e))),
+            };
+            let poll_status = poll_response.status();
+            if !poll_status.is_success() {
+                let body = poll_response.text().await.unwrap_or_default();
+                return Err((
+                    poll_status.as_u16(),
+                    format!(
+                        "HTTP {}: {}",
+                        poll_status,
+                        sanitize_error(&body, poll_status.as_u16())
+                    ),
+                ));
             }
-            Err((status, error)) => {
-                let mut result =
-                    TestResult::failure(scenario.id, scenario.name, duration_ms, error);
-                if status > 0 {
-                    result = result.with_http_status(status);
+
+            let poll_body = poll_response.text().await.unwrap_or_default();
+            let job: AnalyzeJob =
+                responses::parse(&poll_body).map_err(|e| (poll_status.as_u16(), e))?;
+            match job.status.as_str() {
+                "succeeded" => {
+                    return job
+                        .first_results()
+                        .and_then(|results| results.first_document().ok())
+                        .cloned()
+                        .ok_or((
+                            poll_status.as_u16(),
+                            "Job succeeded without document results".to_string(),
+                        ));
+                }
+                "failed" | "cancelled" => {
+                    let error = job
+                        .errors
+                        .first()
+                        .map_or("Unknown error", |e| e.message.as_str());
+                    return Err((poll_status.as_u16(), format!("Job failed: {}", error)));
                 }
-                result
+                // notStarted / running: keep polling
+                _ => {}
             }
         }
+
+        Err((
+            0,
+            format!(
+                "Job did not complete after {} polls (endpoint responsive)",
+                max_attempts
+            ),
+        ))
+    }
+
+    async fn test_clu(&self, context: &TestContext, scenario: &TestScenario) -> TestResult {
+        let (project, deployment) = match Self::get_clu_project(context) {
+            Some(p) => p,
+            None => {
+                return TestResult::skipped(
+                    scenario.id,
+                    scenario.name,
+                    "Requires project: set clu_project_name and clu_deployment_name in [services.language]"
+                        .to_string(),
+                )
+            }
+        };
+
+        let endpoint =
+            self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
+        let url = format!(
+            "{}/language/:analyze-conversations?api-version={}",
+            endpoint,
+            api_versions::LANGUAGE.version(context)
+        );
+
+        let text = context
+            .input(InputType::Text)
+            .and_then(|i| i.text.clone())
+            .unwrap_or_else(|| "Book a flight to Seattle tomorrow".to_string());
+
+        let body = serde_json::json!({
+            "kind": "Conversation",
+            "analysisInput": {
+                "conversationItem": {
+                    "id": "1",
+                    "participantId": "1",
+                    "text": text
+                }
+            },
+            "parameters": {
+                "projectName": project,
+                "deploymentName": deployment,
+                "stringIndexType": "TextElement_V8"
+            }
+        });
+
+        ScenarioRequest::post(url)
+            .json(&body)
+            .extract(|response: ConversationResponse| {
+                let top_intent = response
+                    .result
+                    .prediction
+                    .top_intent
+                    .unwrap_or_else(|| "none".to_string());
+                Ok(format!("Top intent: {}", top_intent))
+            })
+            .run(context, scenario)
+            .await
+    }
+
+    async fn test_custom_ner(&self, context: &TestContext, scenario: &TestScenario) -> TestResult {
+        let (project, deployment) = match Self::get_ner_project(context) {
+            Some(p) => p,
+            None => {
+                return TestResult::skipped(
+                    scenario.id,
+                    scenario.name,
+                    "Requires project: set ner_project_name and ner_deployment_name in [services.language]"
+                        .to_string(),
+                )
+            }
+        };
+
+        let text = Self::get_sample_text(context);
+        let body = serde_json::json!({
+            "displayName": "Custom NER Test",
+            "analysisInput": {
+                "documents": [
+                    {"id": "1", "text": text, "language": "en"}
+                ]
+            },
+            "tasks": [
+                {
+                    "kind": "CustomEntityRecognition",
+                    "taskName": "CustomNER",
+                    "parameters": {
+                        "projectName": project,
+                        "deploymentName": deployment
+                    }
+                }
+            ]
+        });
+
+        let (result, duration_ms) = measure_time(async {
+            let doc = self
+                .run_analyze_job(context, &body, 10, tokio::time::Duration::from_millis(500))
+                .await?;
+            Ok::<_, (u16, String)>(format!("Custom NER found {} entities", doc.entities.len()))
+        })
+        .await;
+
+        TestResult::from_outcome(scenario, duration_ms, result)
+    }
+
+    async fn test_question_answering(
+        &self,
+        context: &TestContext,
+        scenario: &TestScenario,
+    ) -> TestResult {
+        let (project, deployment) = match Self::get_qa_project(context) {
+            Some(p) => p,
+            None => {
+                return TestResult::skipped(
+                    scenario.id,
+                    scenario.name,
+                    "Requires project: set qa_project_name and qa_deployment_name in [services.language]"
+                        .to_string(),
+                )
+            }
+        };
+
+        let endpoint =
+            self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
+        let url = match url::Url::parse_with_params(
+            &format!(
+                "{}/language/:query-knowledgebases",
+                endpoint.trim_end_matches('/')
+            ),
+            &[
+                ("projectName", project.as_str()),
+                ("deploymentName", deployment.as_str()),
+                (
+                    "api-version",
+                    api_versions::QUESTION_ANSWERING.version(context),
+                ),
+            ],
+        ) {
+            Ok(u) => u,
+            Err(e) => {
+                return TestResult::failure(
+                    scenario.id,
+                    scenario.name,
+                    0,
+                    format!("Invalid endpoint URL: {}", e),
+                )
+            }
+        };
+
+        let question = context
+            .input(InputType::Text)
+            .and_then(|i| i.text.clone())
+            .unwrap_or_else(|| "How do I test connectivity?".to_string());
+        let body = serde_json::json!({
+            "question": question,
+            "top": 1
+        });
+
+        ScenarioRequest::post(url)
+            .json(&body)
+            .extract(|response: KnowledgeBaseAnswers| {
+                let answers = response.answers;
+                let confidence = answers.first().map_or(0.0, |a| a.confidence_score);
+                Ok(format!(
+                    "{} answer(s) returned (top confidence: {:.2})",
+                    answers.len(),
+                    confidence
+                ))
+            })
+            .run(context, scenario)
+            .await
+    }
+
+    async fn test_healthcare(&self, context: &TestContext, scenario: &TestScenario) -> TestResult {
+        let text = context
+            .input(InputType::Text)
+            .and_then(|i| i.text.clone())
+            .unwrap_or_else(|| {
+                "Patient was prescribed 100mg ibuprofen twice daily for a headache.".to_string()
+            });
+
+        let body = serde_json::json!({
+            "displayName": "Healthcare Test",
+            "analysisInput": {
+                "documents": [
+                    {"id": "1", "text": text, "language": "en"}
+                ]
+            },
+            "tasks": [
+                {
+                    "kind": "Healthcare",
+                    "taskName": "HealthcareAnalysis",
+                    "parameters": {
+                        "modelVersion": "latest"
+                    }
+                }
+            ]
+        });
+
+        let (result, duration_ms) = measure_time(async {
+            // Healthcare jobs are slower than other analyze-text tasks
+            let doc = self
+                .run_analyze_job(context, &body, 30, tokio::time::Duration::from_secs(1))
+                .await?;
+            Ok::<_, (u16, String)>(format!(
+                "Healthcare analysis found {} entities, {} relations",
+                doc.entities.len(),
+                doc.relations.len()
+            ))
+        })
+        .await;
+
+        TestResult::from_outcome(scenario, duration_ms, result)
+    }
+
+    async fn test_custom_classification(
+        &self,
+        context: &TestContext,
+        scenario: &TestScenario,
+    ) -> TestResult {
+        let (project, deployment) = match Self::get_project(context) {
+            Some(p) => p,
+            None => {
+                return TestResult::skipped(
+                    scenario.id,
+                    scenario.name,
+                    "Requires project_name and deployment_name in [services.language]".to_string(),
+                )
+            }
+        };
+
+        let text = Self::get_sample_text(context);
+        let body = serde_json::json!({
+            "displayName": "Custom Classification Test",
+            "analysisInput": {
+                "documents": [
+                    {"id": "1", "text": text, "language": "en"}
+                ]
+            },
+            "tasks": [
+                {
+                    "kind": "CustomSingleLabelClassification",
+                    "taskName": "CustomClassification",
+                    "parameters": {
+                        "projectName": project,
+                        "deploymentName": deployment
+                    }
+                }
+            ]
+        });
+
+        let (result, duration_ms) = measure_time(async {
+            let doc = self
+                .run_analyze_job(context, &body, 10, tokio::time::Duration::from_millis(500))
+                .await?;
+            let category = doc.class.first().map_or("none", |c| c.category.as_str());
+            Ok::<_, (u16, String)>(format!("Classified as: {}", category))
+        })
+        .await;
+
+        TestResult::from_outcome(scenario, duration_ms, result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Credentials;
+    use crate::config::ServiceConfig;
+    use std::time::Duration;
+
+    fn context_with(service_config: ServiceConfig) -> TestContext {
+        TestContext::new(
+            Credentials::ApiKey("test".to_string()),
+            Cloud::Global,
+            "eastus".to_string(),
+            Duration::from_secs(5),
+        )
+        .unwrap()
+        .with_service_config(service_config)
+    }
+
+    #[test]
+    fn test_qa_project_falls_back_to_custom_project() {
+        let context = context_with(ServiceConfig {
+            project_name: Some("clu".to_string()),
+            deployment_name: Some("prod".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            LanguageService::get_qa_project(&context),
+            Some(("clu".to_string(), "prod".to_string()))
+        );
+
+        let context = context_with(ServiceConfig {
+            project_name: Some("clu".to_string()),
+            deployment_name: Some("prod".to_string()),
+            qa_project_name: Some("faq".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            LanguageService::get_qa_project(&context),
+            Some(("faq".to_string(), "prod".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_apply_options() {
+        let context = context_with(ServiceConfig {
+            options: toml::from_str(
+                r#"
+                sentiment_opinion_mining = { opinionMining = false, stringIndexType = "Utf16CodeUnit" }
+                extractive_summarization = { sentenceCount = 6, sortBy = "Rank" }
+                "#,
+            )
+            .unwrap(),
+            ..Default::default()
+        });
+
+        let mut body =
+            serde_json::json!({"kind": "SentimentAnalysis", "parameters": {"opinionMining": true}});
+        LanguageService::apply_options(&mut body, &context, "sentiment_opinion_mining");
+        assert_eq!(
+            body["parameters"],
+            serde_json::json!({"opinionMining": false, "stringIndexType": "Utf16CodeUnit"})
+        );
+
+        let mut job = serde_json::json!({"tasks": [{"kind": "ExtractiveSummarization", "parameters": {"sentenceCount": 3}}]});
+        LanguageService::apply_options(&mut job, &context, "extractive_summarization");
+        assert_eq!(
+            job["tasks"][0]["parameters"],
+            serde_json::json!({"sentenceCount": 6, "sortBy": "Rank"})
+        );
+
+        // Scenarios without options are sent unchanged
+        let mut plain = serde_json::json!({"kind": "KeyPhraseExtraction"});
+        LanguageService::apply_options(&mut plain, &context, "key_phrases");
+        assert_eq!(plain, serde_json::json!({"kind": "KeyPhraseExtraction"}));
+    }
+
+    #[tokio::test]
+    async fn test_async_job_scenarios_against_mock() {
+        let mock = crate::mock::MockServer::start().await.unwrap();
+        let config = crate::testing::TestRunnerConfig::builder()
+            .services(["language"])
+            .scenarios(["healthcare", "custom_classification"])
+            .auth_method(crate::config::AuthMethod::Key)
+            .api_key(crate::mock::MOCK_API_KEY)
+            .region("eastus")
+            .quiet(true)
+            .mock(mock.addr())
+            .build();
+        let report = crate::testing::TestRunner::new(config).run().await.unwrap();
+        let results: Vec<_> = report
+            .services
+            .iter()
+            .filter(|s| s.service_name != crate::services::preflight::SECTION_NAME)
+            .flat_map(|s| &s.results)
+            .map(|r| (r.scenario_id.as_str(), r.success, r.is_skipped()))
+            .collect();
+        // Custom classification needs a deployed project
+        assert_eq!(
+            results,
+            [
+                ("healthcare", true, false),
+                ("custom_classification", false, true)
+            ]
+        );
+    }
+
+    /// Scenario IDs `run_scenario` dispatches on, read from its source
+    fn dispatched_scenarios() -> Vec<&'static str> {
+        let source = include_str!("mod.rs");
+        let start = source.find("async fn run_scenario").unwrap();
+        let end = start + source[start..].find("\nimpl LanguageService").unwrap();
+        source[start..end]
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix('"'))
+            .filter_map(|line| line.split_once("\" =>").map(|(id, _)| id))
+            .collect()
+    }
+
+    #[test]
+    fn test_every_dispatched_scenario_is_listed() {
+        let listed: Vec<&str> = LanguageService::new()
+            .list_scenarios()
+            .iter()
+            .map(|s| s.id)
+            .collect();
+        let dispatched = dispatched_scenarios();
+        assert!(dispatched.contains(&"question_answering"));
+        for id in dispatched {
+            assert!(listed.contains(&id), "{} is dispatched but not listed", id);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_clu_and_custom_ner_use_their_own_projects() {
+        let mock = crate::mock::MockServer::start().await.unwrap();
+        let run = |language: &str| {
+            let mut config = crate::config::Config::default_config();
+            config.services.insert(
+                "language".to_string(),
+                toml::from_str::<ServiceConfig>(language).unwrap(),
+            );
+            let capture = std::sync::Arc::new(crate::capture::CaptureLog::in_memory());
+            let runner = crate::testing::TestRunner::new(
+                crate::testing::TestRunnerConfig::builder()
+                    .config(&config)
+                    .services(["language"])
+                    .scenarios(["clu", "custom_ner"])
+                    .auth_method(crate::config::AuthMethod::Key)
+                    .api_key(crate::mock::MOCK_API_KEY)
+                    .region("eastus")
+                    .quiet(true)
+                    .mock(mock.addr())
+                    .build(),
+            )
+            .with_capture(capture.clone());
+            (runner, capture)
+        };
+        let results = |report: crate::output::TestReport| -> Vec<(String, bool, bool)> {
+            report
+                .services
+                .into_iter()
+                .filter(|s| s.service_name != crate::services::preflight::SECTION_NAME)
+                .flat_map(|s| s.results)
+                .map(|r| (r.scenario_id.clone(), r.success, r.is_skipped()))
+                .collect()
+        };
+
+        // Each scenario is sent its own project
+        let (runner, capture) = run(r#"
+            clu_project_name = "bookings"
+            clu_deployment_name = "clu-prod"
+            ner_project_name = "contracts"
+            ner_deployment_name = "ner-prod"
+            "#);
+        let report = runner.run().await.unwrap();
+        assert_eq!(
+            results(report),
+            [
+                ("clu".to_string(), true, false),
+                ("custom_ner".to_string(), true, false)
+            ]
+        );
+        let body = |path: &str| -> serde_json::Value {
+            let exchange = capture
+                .exchanges()
+                .into_iter()
+                .find(|e| e.method == "POST" && e.url.contains(path))
+                .unwrap();
+            serde_json::from_str(exchange.request_body.as_deref().unwrap()).unwrap()
+        };
+        let clu = body(":analyze-conversations");
+        assert_eq!(clu["parameters"]["projectName"], "bookings");
+        assert_eq!(clu["parameters"]["deploymentName"], "clu-prod");
+        let ner = body("/analyze-text/jobs");
+        assert_eq!(ner["tasks"][0]["parameters"]["projectName"], "contracts");
+        assert_eq!(ner["tasks"][0]["parameters"]["deploymentName"], "ner-prod");
+
+        // A CLU project alone doesn't make custom NER runnable
+        let (runner, _) = run(r#"
+            clu_project_name = "bookings"
+            clu_deployment_name = "clu-prod"
+            "#);
+        let report = runner.run().await.unwrap();
+        let skipped = report
+            .services
+            .iter()
+            .flat_map(|s| &s.results)
+            .find(|r| r.scenario_id == "custom_ner")
+            .unwrap();
+        assert!(skipped.is_skipped());
+        assert!(skipped
+            .error
+            .as_deref()
+            .unwrap_or_default()
+            .contains("ner_project_name"));
+        assert_eq!(
+            results(report),
+            [
+                ("clu".to_string(), true, false),
+                ("custom_ner".to_string(), false, true)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_project_requires_both_names() {
+        let context = context_with(ServiceConfig {
+            project_name: Some("clu".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(LanguageService::get_project(&context), None);
     }
 }
@@ -0,0 +1,205 @@
+//! Long-running job resilience (`test --long-poll <DURATION>`)
+//!
+//! Async APIs (Document Intelligence analysis, Language summarization, Vision
+//! Read 3.2) are polled every few seconds for minutes in production, so a NAT
+//! gateway, firewall, or proxy that drops idle or long-lived connections
+//! breaks them while a quick test passes. In this mode the job's operation
+//! URL is polled at a realistic interval for the whole period, and keeps
+//! being polled after the job completes (the result stays readable), so the
+//! path is exercised for as long as a real job would be. The first poll that
+//! fails is reported with how long the job had been running.
+
+use std::time::{Duration, Instant};
+
+use crate::error::{sanitize_error, AppError, Result};
+use crate::services::{classify::request_error, TestContext};
+
+/// Seconds between polls unless `--poll-interval` is given
+pub const DEFAULT_INTERVAL_SECS: u64 = 15;
+
+/// How long to poll, and how often
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LongPoll {
+    pub duration: Duration,
+    pub interval: Duration,
+}
+
+/// Parse a duration such as `600`, `90s`, `10m`, or `1h` (plain numbers are seconds)
+pub fn parse_duration(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(at) => value.split_at(at),
+        None => (value, "s"),
+    };
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        _ => 0,
+    };
+    match number.parse::<u64>() {
+        Ok(n) if n > 0 && multiplier > 0 => Ok(Duration::from_secs(n * multiplier)),
+        _ => Err(AppError::InvalidInput(format!(
+            "Invalid duration '{}' (expected e.g. 600, 90s, 10m, or 1h)",
+            value
+        ))),
+    }
+}
+
+/// `5m 30s`, `45s`
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    match (secs / 60, secs % 60) {
+        (0, s) => format!("{}s", s),
+        (m, 0) => format!("{}m", m),
+        (m, s) => format!("{}m {}s", m, s),
+    }
+}
+
+/// Message of a failed job (Document Intelligence `error`, Language `errors`)
+fn job_error(body: &serde_json::Value) -> &str {
+    body.pointer("/error/message")
+        .or_else(|| body.pointer("/errors/0/message"))
+        .and_then(|m| m.as_str())
+        .unwrap_or("Unknown error")
+}
+
+/// Poll `operation_url` for the whole period. Returns the job's first
+/// completed response and a summary of the polling.
+pub async fn poll(
+    context: &TestContext,
+    operation_url: &str,
+    long_poll: LongPoll,
+) -> std::result::Result<(serde_json::Value, String), (u16, String)> {
+    let started = Instant::now();
+    let mut polls = 0;
+    let mut completed = None;
+    let mut job_status = String::new();
+
+    while started.elapsed() < long_poll.duration {
+        let remaining = long_poll.duration - started.elapsed();
+        tokio::time::sleep(long_poll.interval.min(remaining)).await;
+        polls += 1;
+        let at = format_elapsed(started.elapsed());
+
+        let request = context
+            .credentials
+            .apply_to_request(context.client.get(operation_url));
+        let response = match context.send(request).await {
+            Ok(response) => response,
+            Err(e) => {
+                let error = request_error("Poll request failed", &e);
+                return Err((
+                    0,
+                    if polls == 1 {
+                        error
+                    } else {
+                        format!(
+                            "Poll {} at {} failed after {} answered: {}. A NAT gateway, firewall, \
+                             or proxy on the path likely dropped the connection (idle or lifetime \
+                             timeout)",
+                            polls,
+                            at,
+                            polls - 1,
+                            error
+                        )
+                    },
+                ));
+            }
+        };
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err((
+                status.as_u16(),
+                format!(
+                    "Poll {} at {}: HTTP {}: {}",
+                    polls,
+                    at,
+                    status,
+                    sanitize_error(&body, status.as_u16())
+                ),
+            ));
+        }
+
+        let body: serde_json::Value = response.json().await.unwrap_or_default();
+        job_status = body
+            .get("status")
+            .and_then(|s| s.as_str())
+            .unwrap_or_default()
+            .to_string();
+        match job_status.as_str() {
+            "failed" | "cancelled" => {
+                return Err((
+                    status.as_u16(),
+                    format!("Job {} at {}: {}", job_status, at, job_error(&body)),
+                ))
+            }
+            "succeeded" | "partiallySucceeded" if completed.is_none() => {
+                completed = Some((at, body));
+            }
+            // notStarted / running, or polling on after completion
+            _ => {}
+        }
+    }
+
+    let summary = format!(
+        "{} polls over {} every {}, all answered",
+        polls,
+        format_elapsed(long_poll.duration),
+        format_elapsed(long_poll.interval)
+    );
+    match completed {
+        Some((at, body)) => Ok((body, format!("{}; job finished at {}", summary, at))),
+        None => Err((0, format!("Job still '{}' after {}", job_status, summary))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockServer, MOCK_API_KEY};
+    use crate::testing::{TestRunner, TestRunnerConfig};
+
+    #[tokio::test]
+    async fn test_long_poll_against_mock() {
+        assert_eq!(parse_duration("10m").unwrap(), Duration::from_secs(600));
+        assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+        assert!(parse_duration("10d").is_err());
+        assert_eq!(format_elapsed(Duration::from_secs(330)), "5m 30s");
+
+        let mock = MockServer::start().await.unwrap();
+        let config = TestRunnerConfig::builder()
+            .services(["document_intelligence", "language"])
+            .scenarios(["layout", "summarization"])
+            .auth_method(crate::config::AuthMethod::Key)
+            .api_key(MOCK_API_KEY)
+            .long_poll(LongPoll {
+                duration: Duration::from_millis(300),
+                interval: Duration::from_millis(100),
+            })
+            .quiet(true)
+            .mock(mock.addr())
+            .build();
+        let report = TestRunner::new(config).run().await.unwrap();
+        let results: Vec<_> = report
+            .services
+            .iter()
+            .flat_map(|s| &s.results)
+            .filter(|r| r.scenario_id == "layout" || r.scenario_id == "summarization")
+            .collect();
+        assert_eq!(results.len(), 2);
+        for result in results {
+            assert!(result.success, "{:?}", result);
+            assert!(
+                result
+                    .details
+                    .as_deref()
+                    .unwrap()
+                    .contains("polls over 0s every 0s, all answered; job finished"),
+                "{:?}",
+                result.details
+            );
+        }
+    }
+}
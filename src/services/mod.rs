@@ -1,16 +1,45 @@
+pub mod api_versions;
+pub mod capabilities;
+pub mod classify;
+pub mod container;
+pub mod custom;
 pub mod document_intelligence;
+pub mod endpoints;
+pub mod foundry;
+pub mod inference;
 pub mod language;
+pub mod long_poll;
+pub mod openai;
+pub mod payloads;
+pub mod preflight;
+pub mod profiles;
+pub mod public_endpoints;
+pub mod responses;
+pub mod routes;
 pub mod speech;
+pub mod tags;
 pub mod translator;
 pub mod vision;
 
+use crate::artifacts::ArtifactDir;
 use crate::auth::Credentials;
-use crate::config::Cloud;
+use crate::capture::{CaptureLog, CapturedExchange};
+use crate::config::{
+    Cloud, Expect, Expectation, ServiceConfig, DEFAULT_CONNECT_TIMEOUT_SECS,
+    DEFAULT_TLS_TIMEOUT_SECS,
+};
 use crate::error::Result;
+use crate::network::{Phase, PhaseTiming};
 use async_trait::async_trait;
-use reqwest::Client;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Client, RequestBuilder, Response};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, LazyLock, Mutex, RwLock};
 use std::time::{Duration, Instant};
+use tracing::Instrument;
+
+pub use tags::ScenarioSelection;
 
 /// Test scenario definition
 #[derive(Debug, Clone)]
@@ -25,6 +54,8 @@ pub struct TestScenario {
     pub requires_input: bool,
     /// Type of input required (if any)
     pub input_type: Option<InputType>,
+    /// Tags for selecting scenarios with `tag:<tag>` (see [`tags`])
+    pub tags: &'static [&'static str],
 }
 
 /// Type of input file required
@@ -48,6 +79,83 @@ impl std::fmt::Display for InputType {
     }
 }
 
+/// Class of problem behind a failed scenario
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// Hostname could not be resolved
+    Dns,
+    /// TCP connection refused, reset, or unreachable
+    Tcp,
+    /// TLS handshake or certificate validation failed
+    Tls,
+    /// Proxy rejected or could not establish the connection
+    Proxy,
+    /// Request or operation timed out
+    Timeout,
+    /// API key rejected (wrong key or key/region mismatch)
+    AuthKey,
+    /// Bearer token rejected (expired, wrong audience or tenant)
+    AuthToken,
+    /// Bearer token accepted but the identity lacks a role assignment
+    AuthRbac,
+    /// Key-based auth is disabled on the resource
+    LocalAuthDisabled,
+    /// Request forbidden (typically firewall / network ACLs)
+    Forbidden,
+    /// Rate limited (HTTP 429)
+    Throttled,
+    /// Feature not offered in the resource's region or SKU
+    RegionUnsupported,
+    /// Endpoint path or resource not found
+    NotFound,
+    /// Request rejected as invalid (other 4xx)
+    BadRequest,
+    /// A network appliance answered in place of the service (TLS inspection
+    /// certificate, captive portal, proxy login page)
+    Intercepted,
+    /// Endpoint answered, but not with the response the API documents
+    /// (captive portal, gateway rewriting responses, API version mismatch)
+    UnexpectedResponse,
+    /// Service-side error (5xx)
+    ServerError,
+    /// Succeeded, but slower than the latency threshold (--max-latency-ms)
+    Slow,
+    /// Succeeded, but `[[expectations]]` expects it to fail, or a public
+    /// endpoint answered under --assert-blocked
+    UnexpectedSuccess,
+    /// Anything not recognized above
+    Other,
+}
+
+impl std::fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            FailureKind::Dns => "DNS",
+            FailureKind::Tcp => "TCP",
+            FailureKind::Tls => "TLS",
+            FailureKind::Proxy => "Proxy",
+            FailureKind::Timeout => "Timeout",
+            FailureKind::AuthKey => "Auth (key)",
+            FailureKind::AuthToken => "Auth (token)",
+            FailureKind::AuthRbac => "Auth (RBAC)",
+            FailureKind::LocalAuthDisabled => "Local auth disabled",
+            FailureKind::Forbidden => "Forbidden",
+            FailureKind::Throttled => "Throttled",
+            FailureKind::RegionUnsupported => "Region unsupported",
+            FailureKind::NotFound => "Not found",
+            FailureKind::BadRequest => "Bad request",
+            FailureKind::Intercepted => "Intercepted by network appliance",
+            FailureKind::UnexpectedResponse => "Unexpected response",
+            FailureKind::ServerError => "Server error",
+            FailureKind::Slow => "Slow",
+            FailureKind::UnexpectedSuccess => "Unexpected success",
+            FailureKind::Other => "Other",
+        };
+        write!(f, "{}", label)
+    }
+}
+
 /// Result of a single test scenario
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TestResult {
@@ -65,6 +173,26 @@ pub struct TestResult {
     pub details: Option<String>,
     /// HTTP status code if applicable
     pub http_status: Option<u16>,
+    /// Class of failure (set for failed, non-skipped results)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_kind: Option<FailureKind>,
+    /// Suggested next step for a failed result
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+    /// Failed because the service rate-limited the request (HTTP 429); not
+    /// counted as a failure unless throttling is configured to fail the run
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub throttled: bool,
+    /// Succeeded, but slower than the latency threshold; a warning unless
+    /// slow results are configured to fail the run
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub slow: bool,
+    /// Failed as `[[expectations]]` expects; not counted as a failure
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub expected_failure: bool,
+    /// Per-phase durations (DNS, connect, TLS, first byte, total)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timing: Option<PhaseTiming>,
 }
 
 impl TestResult {
@@ -77,6 +205,12 @@ impl TestResult {
             error: None,
             details: None,
             http_status: None,
+            failure_kind: None,
+            hint: None,
+            throttled: false,
+            slow: false,
+            expected_failure: false,
+            timing: None,
         }
     }
 
@@ -94,6 +228,34 @@ impl TestResult {
             error: Some(error),
             details: None,
             http_status: None,
+            failure_kind: None,
+            hint: None,
+            throttled: false,
+            slow: false,
+            expected_failure: false,
+            timing: None,
+        }
+    }
+
+    /// Build the result of a scenario that produced either details or an
+    /// error with the HTTP status it came from (0 when there was no response)
+    pub fn from_outcome(
+        scenario: &TestScenario,
+        duration_ms: u64,
+        outcome: std::result::Result<String, (u16, String)>,
+    ) -> Self {
+        match outcome {
+            Ok(details) => {
+                Self::success(scenario.id, scenario.name, duration_ms).with_details(details)
+            }
+            Err((status, error)) => {
+                let result = Self::failure(scenario.id, scenario.name, duration_ms, error);
+                if status > 0 {
+                    result.with_http_status(status)
+                } else {
+                    result
+                }
+            }
         }
     }
 
@@ -107,6 +269,31 @@ impl TestResult {
         self
     }
 
+    pub fn with_failure_kind(mut self, kind: FailureKind) -> Self {
+        self.failure_kind = Some(kind);
+        self
+    }
+
+    /// Whether this result was rate limited and reported as throttled
+    pub fn is_throttled(&self) -> bool {
+        !self.success && self.throttled
+    }
+
+    /// Whether this result failed as its expectation says it should
+    pub fn is_expected_failure(&self) -> bool {
+        !self.success && self.expected_failure
+    }
+
+    /// Whether this result was skipped rather than run
+    pub fn is_skipped(&self) -> bool {
+        !self.success
+            && self
+                .error
+                .as_ref()
+                .map(|e| e.starts_with("Skipped"))
+                .unwrap_or(false)
+    }
+
     pub fn skipped(scenario_id: &str, scenario_name: &str, reason: String) -> Self {
         Self {
             scenario_id: scenario_id.to_string(),
@@ -116,6 +303,12 @@ impl TestResult {
             error: Some(format!("Skipped: {}", reason)),
             details: None,
             http_status: None,
+            failure_kind: None,
+            hint: None,
+            throttled: false,
+            slow: false,
+            expected_failure: false,
+            timing: None,
         }
     }
 }
@@ -127,6 +320,12 @@ pub struct ServiceTestResults {
     pub service_name: String,
     /// Service endpoint that was tested
     pub endpoint: String,
+    /// Region tested, set for multi-region sweeps (`--regions`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// Named resource tested (`--target`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
     /// Individual test results
     pub results: Vec<TestResult>,
     /// Total duration in milliseconds
@@ -139,12 +338,138 @@ impl ServiceTestResults {
     }
 
     pub fn failed(&self) -> usize {
-        self.results.iter().filter(|r| !r.success).count()
+        self.results
+            .iter()
+            .filter(|r| !r.success && !r.is_throttled() && !r.is_expected_failure())
+            .count()
     }
 
     pub fn all_passed(&self) -> bool {
         self.results.iter().all(|r| r.success)
     }
+
+    /// Service name, with the target and region appended when set
+    pub fn label(&self) -> String {
+        let qualifiers: Vec<&str> = [&self.target, &self.region]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        if qualifiers.is_empty() {
+            self.service_name.clone()
+        } else {
+            format!("{} [{}]", self.service_name, qualifiers.join(", "))
+        }
+    }
+}
+
+/// Live progress of a test run, sent to `TestContext::events` as it happens
+#[derive(Debug, Clone)]
+pub enum RunEvent {
+    /// A service is about to run these (id, name) scenarios
+    ServiceStarted {
+        service: String,
+        endpoint: String,
+        scenarios: Vec<(String, String)>,
+    },
+    /// A scenario started running
+    ScenarioStarted {
+        service: String,
+        scenario_id: String,
+    },
+    /// A scenario finished (including skipped scenarios)
+    ScenarioFinished {
+        service: String,
+        result: Box<TestResult>,
+    },
+    /// Every target of a service (by its `--services` name) finished in one region
+    ServiceFinished { service: String },
+    /// Something the user should know that isn't a result (e.g. a cached token was used)
+    Notice { message: String },
+}
+
+impl RunEvent {
+    /// Deliver the event to the matching [`ProgressReporter`] method
+    pub fn report_to(&self, reporter: &dyn ProgressReporter) {
+        match self {
+            RunEvent::ServiceStarted {
+                service,
+                endpoint,
+                scenarios,
+            } => reporter.on_service_start(service, endpoint, scenarios),
+            RunEvent::ScenarioStarted {
+                service,
+                scenario_id,
+            } => reporter.on_scenario_start(service, scenario_id),
+            RunEvent::ScenarioFinished { service, result } => {
+                reporter.on_scenario_complete(service, result)
+            }
+            RunEvent::ServiceFinished { service } => reporter.on_service_complete(service),
+            RunEvent::Notice { message } => reporter.on_notice(message),
+        }
+    }
+}
+
+/// Receiver side is typically a UI running as its own task (e.g. `--tui`)
+pub type EventSender = tokio::sync::mpsc::UnboundedSender<RunEvent>;
+
+/// Receives progress as a run happens. Every method does nothing by default,
+/// so implementations only handle what they display.
+pub trait ProgressReporter: Send + Sync {
+    /// A service is about to run these (id, name) scenarios
+    fn on_service_start(&self, _service: &str, _endpoint: &str, _scenarios: &[(String, String)]) {}
+
+    fn on_scenario_start(&self, _service: &str, _scenario_id: &str) {}
+
+    /// A scenario finished (including skipped scenarios)
+    fn on_scenario_complete(&self, _service: &str, _result: &TestResult) {}
+
+    /// Every target of a service (by its `--services` name) finished in one region
+    fn on_service_complete(&self, _service: &str) {}
+
+    /// Something the user should know that isn't a result
+    fn on_notice(&self, _message: &str) {}
+
+    /// The run finished with this report
+    fn on_run_complete(&self, _report: &crate::output::TestReport) {}
+}
+
+/// Forwards each event to a channel. The receiver going away (e.g. the UI
+/// closed) must not stop the run, so send errors are ignored.
+impl ProgressReporter for EventSender {
+    fn on_service_start(&self, service: &str, endpoint: &str, scenarios: &[(String, String)]) {
+        let _ = self.send(RunEvent::ServiceStarted {
+            service: service.to_string(),
+            endpoint: endpoint.to_string(),
+            scenarios: scenarios.to_vec(),
+        });
+    }
+
+    fn on_scenario_start(&self, service: &str, scenario_id: &str) {
+        let _ = self.send(RunEvent::ScenarioStarted {
+            service: service.to_string(),
+            scenario_id: scenario_id.to_string(),
+        });
+    }
+
+    fn on_scenario_complete(&self, service: &str, result: &TestResult) {
+        let _ = self.send(RunEvent::ScenarioFinished {
+            service: service.to_string(),
+            result: Box::new(result.clone()),
+        });
+    }
+
+    fn on_service_complete(&self, service: &str) {
+        let _ = self.send(RunEvent::ServiceFinished {
+            service: service.to_string(),
+        });
+    }
+
+    fn on_notice(&self, message: &str) {
+        let _ = self.send(RunEvent::Notice {
+            message: message.to_string(),
+        });
+    }
 }
 
 /// Input data for tests
@@ -199,7 +524,360 @@ impl TestInput {
     }
 }
 
+/// Inputs for a run, one per input type, so a run that tests speech and
+/// vision can send audio to one and an image to the other
+#[derive(Debug, Clone, Default)]
+pub struct TestInputs {
+    pub audio: Option<TestInput>,
+    pub image: Option<TestInput>,
+    pub document: Option<TestInput>,
+    pub text: Option<TestInput>,
+    /// File given with `--input-file`, sent to audio, image, and document
+    /// scenarios that have no input of their own type
+    pub any: Option<TestInput>,
+}
+
+impl TestInputs {
+    /// The input for scenarios that take `input_type`
+    pub fn get(&self, input_type: InputType) -> Option<&TestInput> {
+        match input_type {
+            InputType::Audio => self.audio.as_ref().or(self.any.as_ref()),
+            InputType::Image => self.image.as_ref().or(self.any.as_ref()),
+            InputType::Document => self.document.as_ref().or(self.any.as_ref()),
+            InputType::Text => self.text.as_ref(),
+        }
+    }
+}
+
+/// Header that carries the API key unless `api_key_header` overrides it
+pub const DEFAULT_API_KEY_HEADER: &str = "Ocp-Apim-Subscription-Key";
+
+/// Longest Retry-After delay honored before retrying a throttled request
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Delay used when a 429 response carries no Retry-After header
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// How long a running scenario may take to finish once the run is interrupted
+pub const INTERRUPT_GRACE: Duration = Duration::from_secs(10);
+
+/// Rate-limit details observed on an HTTP 429 response
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ThrottleInfo {
+    /// Delay requested by the service (Retry-After / x-ms-retry-after-ms)
+    pub retry_after: Option<Duration>,
+    /// Quota headers (x-ratelimit-*) from the response
+    pub quota_headers: Vec<(String, String)>,
+    /// Whether the request was retried once after the delay
+    pub retried: bool,
+}
+
+impl ThrottleInfo {
+    pub fn from_headers(headers: &reqwest::header::HeaderMap) -> Self {
+        let quota_headers = headers
+            .iter()
+            .filter(|(name, _)| {
+                let name = name.as_str();
+                name.starts_with("x-ratelimit-") || name.starts_with("x-ms-ratelimit-")
+            })
+            .filter_map(|(name, value)| {
+                value
+                    .to_str()
+                    .ok()
+                    .map(|v| (name.as_str().to_string(), v.to_string()))
+            })
+            .collect();
+        Self {
+            retry_after: parse_retry_after(headers),
+            quota_headers,
+            retried: false,
+        }
+    }
+
+    /// Human-readable summary of the rate-limit headers
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(delay) = self.retry_after {
+            parts.push(format!("Retry-After {}ms", delay.as_millis()));
+        }
+        for (name, value) in &self.quota_headers {
+            parts.push(format!("{}: {}", name, value));
+        }
+        if parts.is_empty() {
+            "no rate-limit headers".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+/// Parse the delay requested by a throttled response.
+///
+/// Azure AI services use `x-ms-retry-after-ms` or `retry-after-ms` (milliseconds)
+/// and the standard `Retry-After` (seconds or an HTTP date).
+pub fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    for name in ["x-ms-retry-after-ms", "retry-after-ms"] {
+        if let Some(ms) = header(name).and_then(|v| v.trim().parse::<u64>().ok()) {
+            return Some(Duration::from_millis(ms));
+        }
+    }
+    let value = header("retry-after")?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delay = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(delay.to_std().unwrap_or(Duration::ZERO))
+}
+
+/// Fold an observed 429 into a scenario result: add the quota headers to the
+/// details and, unless throttling counts as failure, mark it as throttled
+pub fn apply_throttle(
+    mut result: TestResult,
+    throttle: &ThrottleInfo,
+    throttled_is_failure: bool,
+) -> TestResult {
+    let note = if result.success {
+        if !throttle.retried {
+            return result;
+        }
+        format!(
+            "Succeeded after retrying an HTTP 429 ({})",
+            throttle.describe()
+        )
+    } else if result.http_status == Some(429) {
+        result.throttled = !throttled_is_failure;
+        format!("Rate limited: {}", throttle.describe())
+    } else {
+        return result;
+    };
+    result.details = Some(match result.details.take() {
+        Some(details) => format!("{}; {}", details, note),
+        None => note,
+    });
+    result
+}
+
+/// Fail a result whose response came from a network appliance, naming it
+/// ahead of the scenario's own error
+pub fn apply_interception(mut result: TestResult, interception: String) -> TestResult {
+    if result.is_skipped() {
+        return result;
+    }
+    result.error = Some(match result.error.take() {
+        Some(error) if !result.success => format!("{} [{}]", interception, error),
+        _ => interception,
+    });
+    result.success = false;
+    result
+}
+
+/// Hold a result to its `[[expectations]]` entry: a failure of a scenario
+/// expected to fail becomes an expected failure, and a success of one becomes
+/// a failure. Skipped and throttled results are left as they are
+pub fn apply_expectation(
+    mut result: TestResult,
+    service: &str,
+    expectations: &[Expectation],
+) -> TestResult {
+    let Some(expectation) = find_expectation(expectations, service, &result.scenario_id) else {
+        return result;
+    };
+    if expectation.expect != Expect::Fail || result.is_skipped() || result.is_throttled() {
+        return result;
+    }
+    let reason = expectation
+        .reason
+        .as_deref()
+        .map(|reason| format!(" ({})", reason))
+        .unwrap_or_default();
+    if result.success {
+        result.success = false;
+        result.slow = false;
+        result.error = Some(format!("Unexpected success: expected to fail{}", reason));
+        result.failure_kind = Some(FailureKind::UnexpectedSuccess);
+        result.hint = Some(classify::unexpected_success_hint());
+    } else {
+        result.expected_failure = true;
+        let note = format!("Expected failure{}", reason);
+        result.details = Some(match result.details.take() {
+            Some(details) => format!("{}; {}", note, details),
+            None => note,
+        });
+    }
+    result
+}
+
+/// Expectation for a scenario: an entry for the scenario, else one for its service
+fn find_expectation<'a>(
+    expectations: &'a [Expectation],
+    service: &str,
+    scenario: &str,
+) -> Option<&'a Expectation> {
+    let for_service = |e: &&Expectation| e.service.eq_ignore_ascii_case(service);
+    expectations
+        .iter()
+        .filter(for_service)
+        .find(|e| e.scenario.as_deref() == Some(scenario))
+        .or_else(|| {
+            expectations
+                .iter()
+                .filter(for_service)
+                .find(|e| e.scenario.is_none())
+        })
+}
+
+/// Attach a scenario's phase timing, leaving the time spent measuring the
+/// connection phases out of its duration
+pub fn apply_timing(mut result: TestResult, timing: PhaseTiming) -> TestResult {
+    result.duration_ms = result.duration_ms.saturating_sub(timing.probe_ms);
+    result.timing = Some(timing);
+    result
+}
+
+/// Hold a successful result to the latency threshold (--max-latency-ms): mark
+/// it slow, or fail it when slow results count as failures
+pub fn apply_latency_threshold(
+    mut result: TestResult,
+    max_latency_ms: u64,
+    slow_is_failure: bool,
+) -> TestResult {
+    if !result.success || result.duration_ms <= max_latency_ms {
+        return result;
+    }
+    let note = format!(
+        "Slow: {}ms exceeds the {}ms latency threshold",
+        result.duration_ms, max_latency_ms
+    );
+    if slow_is_failure {
+        result.success = false;
+        result.error = Some(note);
+        result.failure_kind = Some(FailureKind::Slow);
+        result.hint = Some(classify::slow_hint());
+    } else {
+        result.slow = true;
+        result.details = Some(match result.details.take() {
+            Some(details) => format!("{}; {}", details, note),
+            None => note,
+        });
+    }
+    result
+}
+
+/// Spaces out requests so a test run stays under a requests-per-second limit
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    next: tokio::sync::Mutex<tokio::time::Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(max_rps: f64) -> Self {
+        Self {
+            interval: Duration::from_secs_f64(1.0 / max_rps.max(0.01)),
+            next: tokio::sync::Mutex::new(tokio::time::Instant::now()),
+        }
+    }
+
+    /// Wait until the next request slot is available
+    pub async fn acquire(&self) {
+        let mut next = self.next.lock().await;
+        let now = tokio::time::Instant::now();
+        if *next > now {
+            tokio::time::sleep_until(*next).await;
+        }
+        *next = (*next).max(now) + self.interval;
+    }
+}
+
+/// Stop request for a run, triggered by the caller (the CLI on SIGINT/SIGTERM).
+/// Once triggered, no new scenario starts and the running one has
+/// [`INTERRUPT_GRACE`] to finish; triggering it again gives up on the running
+/// scenario at once.
+#[derive(Debug, Clone)]
+pub struct Interrupt {
+    /// Times triggered
+    count: Arc<tokio::sync::watch::Sender<u8>>,
+}
+
+impl Interrupt {
+    pub fn new() -> Self {
+        Self {
+            count: Arc::new(tokio::sync::watch::Sender::new(0)),
+        }
+    }
+
+    pub fn trigger(&self) {
+        self.count
+            .send_modify(|count| *count = count.saturating_add(1));
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.count.borrow() > 0
+    }
+
+    /// Wait until the interrupt has been triggered `times` times
+    pub(crate) async fn triggered(&self, times: u8) {
+        let mut receiver = self.count.subscribe();
+        let _ = receiver.wait_for(|count| *count >= times).await;
+    }
+
+    /// Wait until a running scenario should be given up: the grace period
+    /// after an interrupt ran out, or the interrupt was triggered again
+    pub async fn grace_expired(&self) {
+        self.triggered(1).await;
+        tokio::select! {
+            _ = tokio::time::sleep(INTERRUPT_GRACE) => {}
+            _ = self.triggered(2) => {}
+        }
+    }
+}
+
+impl Default for Interrupt {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Validate a header name and value
+pub fn parse_header(name: &str, value: &str) -> Result<(HeaderName, HeaderValue)> {
+    let header_name = HeaderName::from_bytes(name.trim().as_bytes()).map_err(|_| {
+        crate::error::AppError::InvalidInput(format!("Invalid header name '{}'", name))
+    })?;
+    let header_value = HeaderValue::from_str(value.trim()).map_err(|_| {
+        crate::error::AppError::InvalidInput(format!("Invalid value for header '{}'", name))
+    })?;
+    Ok((header_name, header_value))
+}
+
+/// Key of a URL's connection: scheme, host, and port
+fn host_key(url: &url::Url) -> String {
+    format!(
+        "{}://{}:{}",
+        url.scheme(),
+        url.host_str().unwrap_or_default(),
+        url.port_or_known_default().unwrap_or_default()
+    )
+}
+
+/// HTTP client for scenario requests. reqwest's connect timeout covers the
+/// TLS handshake as well, so it gets both budgets.
+fn build_client(
+    timeout: Duration,
+    connect_timeout: Duration,
+    tls_timeout: Duration,
+) -> Result<Client> {
+    crate::network::configure_client(Client::builder())
+        .timeout(timeout)
+        .connect_timeout(connect_timeout + tls_timeout)
+        .build()
+        .map_err(|e| crate::error::AppError::Network(e.to_string()))
+}
+
 /// Test context passed to service implementations
+#[derive(Clone)]
 pub struct TestContext {
     /// HTTP client
     pub client: Client,
@@ -207,16 +885,76 @@ pub struct TestContext {
     pub credentials: Credentials,
     /// Request timeout
     pub timeout: Duration,
+    /// Time allowed for DNS resolution plus the TCP connect
+    pub connect_timeout: Duration,
+    /// Time allowed for the TLS handshake
+    pub tls_timeout: Duration,
     /// Cloud environment
     pub cloud: Cloud,
     /// Region
     pub region: String,
     /// Optional custom endpoint
     pub endpoint: Option<String>,
-    /// Optional input data
-    pub input: Option<TestInput>,
+    /// Input data given by the user, by input type
+    pub inputs: TestInputs,
     /// Verbose output
     pub verbose: bool,
+    /// Service-specific settings from the config file and CLI
+    pub service_config: ServiceConfig,
+    /// Shared request throttle (--max-rps)
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+    /// Retry once after Retry-After when a request gets HTTP 429
+    pub retry_throttled: bool,
+    /// Report HTTP 429 results as failures instead of throttled
+    pub throttled_is_failure: bool,
+    /// Latency threshold for successful scenarios (--max-latency-ms)
+    pub max_latency_ms: Option<u64>,
+    /// Report slow results as failures instead of warnings
+    pub slow_is_failure: bool,
+    /// Expected outcomes of scenarios (`[[expectations]]`)
+    pub expectations: Vec<Expectation>,
+    /// Extra headers added to every request (--header, [global] headers)
+    pub extra_headers: HeaderMap,
+    /// Header that carries the API key instead of Ocp-Apim-Subscription-Key
+    pub api_key_header: Option<HeaderName>,
+    /// Request/response recorder (--capture)
+    pub capture: Option<Arc<CaptureLog>>,
+    /// Live progress listeners
+    pub reporters: Vec<Arc<dyn ProgressReporter>>,
+    /// Mock server every request is redirected to (--mock)
+    pub mock: Option<std::net::SocketAddr>,
+    /// The endpoint is an on-prem Azure AI container (--container)
+    pub container: bool,
+    /// Send embedded assets with real content when no input is given (--realistic-payloads)
+    pub realistic_payloads: bool,
+    /// Strings the input document's analysis must contain (--verify-content)
+    pub expected_content: Vec<String>,
+    /// Directory scenario output is saved in (--save-artifacts)
+    pub artifacts: Option<ArtifactDir>,
+    /// The pre-flight check rejected the credentials; scenarios that send them are skipped
+    pub auth_rejected: bool,
+    /// Poll async jobs for an extended period (--long-poll)
+    pub long_poll: Option<long_poll::LongPoll>,
+    /// Call preview API versions instead of GA (--preview-apis)
+    pub preview_apis: bool,
+    /// Pricing tier of the resource (e.g., "F0"), when known
+    pub sku: Option<String>,
+    /// Set when the run is asked to stop
+    pub interrupt: Interrupt,
+    /// Last 429 observed by `send`, taken after each scenario
+    last_throttle: Arc<std::sync::Mutex<Option<ThrottleInfo>>>,
+    /// Last network appliance seen answering in place of the service, taken
+    /// after each scenario
+    last_interception: Arc<Mutex<Option<String>>>,
+    /// Appliance named by each host's certificate, checked once per host
+    probed_certificates: Arc<Mutex<HashMap<String, Option<String>>>>,
+    /// Connection phases already measured, by scheme, host, and port
+    probed_hosts: Arc<Mutex<HashMap<String, PhaseTiming>>>,
+    /// Hosts the client has had a response from, so a pooled connection is
+    /// likely open, by scheme, host, and port
+    connected_hosts: Arc<Mutex<HashSet<String>>>,
+    /// Phase timing of the running scenario, taken after each scenario
+    timing: Arc<Mutex<Option<PhaseTiming>>>,
 }
 
 impl TestContext {
@@ -226,37 +964,643 @@ impl TestContext {
         region: String,
         timeout: Duration,
     ) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(timeout)
-            .build()
-            .map_err(|e| crate::error::AppError::Network(e.to_string()))?;
+        let connect_timeout = Duration::from_secs(DEFAULT_CONNECT_TIMEOUT_SECS);
+        let tls_timeout = Duration::from_secs(DEFAULT_TLS_TIMEOUT_SECS);
+        let client = build_client(timeout, connect_timeout, tls_timeout)?;
 
         Ok(Self {
             client,
             credentials,
             timeout,
+            connect_timeout,
+            tls_timeout,
             cloud,
             region,
             endpoint: None,
-            input: None,
+            inputs: TestInputs::default(),
             verbose: false,
+            service_config: ServiceConfig::default(),
+            rate_limiter: None,
+            retry_throttled: false,
+            throttled_is_failure: false,
+            max_latency_ms: None,
+            slow_is_failure: false,
+            expectations: Vec::new(),
+            extra_headers: HeaderMap::new(),
+            api_key_header: None,
+            capture: None,
+            reporters: Vec::new(),
+            mock: None,
+            container: false,
+            realistic_payloads: false,
+            expected_content: Vec::new(),
+            artifacts: None,
+            auth_rejected: false,
+            long_poll: None,
+            preview_apis: false,
+            sku: None,
+            interrupt: Interrupt::new(),
+            last_throttle: Arc::new(std::sync::Mutex::new(None)),
+            last_interception: Arc::new(Mutex::new(None)),
+            probed_certificates: Arc::new(Mutex::new(HashMap::new())),
+            probed_hosts: Arc::new(Mutex::new(HashMap::new())),
+            connected_hosts: Arc::new(Mutex::new(HashSet::new())),
+            timing: Arc::new(Mutex::new(None)),
         })
     }
 
+    /// Replace the request timeout, rebuilding the HTTP client
+    pub fn with_timeout(mut self, timeout: Duration) -> Result<Self> {
+        self.client = build_client(timeout, self.connect_timeout, self.tls_timeout)?;
+        self.connected_hosts = Arc::new(Mutex::new(HashSet::new()));
+        self.timeout = timeout;
+        Ok(self)
+    }
+
+    /// Replace the connect and TLS handshake timeouts, rebuilding the HTTP client
+    pub fn with_phase_timeouts(
+        mut self,
+        connect_timeout: Duration,
+        tls_timeout: Duration,
+    ) -> Result<Self> {
+        self.client = build_client(self.timeout, connect_timeout, tls_timeout)?;
+        self.connected_hosts = Arc::new(Mutex::new(HashSet::new()));
+        self.connect_timeout = connect_timeout;
+        self.tls_timeout = tls_timeout;
+        Ok(self)
+    }
+
+    pub fn with_rate_limiter(mut self, rate_limiter: Option<Arc<RateLimiter>>) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    pub fn with_throttle_policy(mut self, retry: bool, throttled_is_failure: bool) -> Self {
+        self.retry_throttled = retry;
+        self.throttled_is_failure = throttled_is_failure;
+        self
+    }
+
+    pub fn with_latency_threshold(
+        mut self,
+        max_latency_ms: Option<u64>,
+        slow_is_failure: bool,
+    ) -> Self {
+        self.max_latency_ms = max_latency_ms;
+        self.slow_is_failure = slow_is_failure;
+        self
+    }
+
+    pub fn with_expectations(mut self, expectations: Vec<Expectation>) -> Self {
+        self.expectations = expectations;
+        self
+    }
+
+    pub fn with_capture(mut self, capture: Option<Arc<CaptureLog>>) -> Self {
+        self.capture = capture;
+        self
+    }
+
+    pub fn with_reporters(mut self, reporters: Vec<Arc<dyn ProgressReporter>>) -> Self {
+        self.reporters = reporters;
+        self
+    }
+
+    pub fn with_mock(mut self, mock: Option<std::net::SocketAddr>) -> Self {
+        self.mock = mock;
+        self
+    }
+
+    pub fn with_container(mut self, container: bool) -> Self {
+        self.container = container;
+        self
+    }
+
+    pub fn with_realistic_payloads(mut self, realistic_payloads: bool) -> Self {
+        self.realistic_payloads = realistic_payloads;
+        self
+    }
+
+    pub fn with_expected_content(mut self, expected_content: Vec<String>) -> Self {
+        self.expected_content = expected_content;
+        self
+    }
+
+    pub fn with_artifacts(mut self, artifacts: Option<ArtifactDir>) -> Self {
+        self.artifacts = artifacts;
+        self
+    }
+
+    pub fn with_auth_rejected(mut self, auth_rejected: bool) -> Self {
+        self.auth_rejected = auth_rejected;
+        self
+    }
+
+    pub fn with_long_poll(mut self, long_poll: Option<long_poll::LongPoll>) -> Self {
+        self.long_poll = long_poll;
+        self
+    }
+
+    pub fn with_preview_apis(mut self, preview_apis: bool) -> Self {
+        self.preview_apis = preview_apis;
+        self
+    }
+
+    /// Skip scenarios the resource's pricing tier doesn't offer
+    pub fn with_sku(mut self, sku: Option<String>) -> Self {
+        self.sku = sku;
+        self
+    }
+
+    /// Stop starting scenarios when `interrupt` is triggered
+    pub fn with_interrupt(mut self, interrupt: Interrupt) -> Self {
+        self.interrupt = interrupt;
+        self
+    }
+
+    /// Run a scenario to completion, or until the grace period after an
+    /// interrupt runs out, which reports it as skipped
+    pub async fn run_interruptible(
+        &self,
+        scenario_id: &str,
+        scenario_name: &str,
+        scenario: impl std::future::Future<Output = TestResult>,
+    ) -> TestResult {
+        tokio::select! {
+            result = scenario => result,
+            _ = self.interrupt.grace_expired() => TestResult::skipped(
+                scenario_id,
+                scenario_name,
+                "Interrupted before it finished".to_string(),
+            ),
+        }
+    }
+
+    /// Save a scenario's output under --save-artifacts. A file that can't be
+    /// written is logged and doesn't fail the scenario.
+    pub fn save_artifact(&self, service: &str, scenario: &str, extension: &str, data: &[u8]) {
+        let Some(artifacts) = &self.artifacts else {
+            return;
+        };
+        match artifacts.save(service, scenario, extension, data) {
+            Ok(path) => tracing::debug!(path = %path.display(), "saved artifact"),
+            Err(e) => tracing::warn!(service, scenario, error = %e, "could not save artifact"),
+        }
+    }
+
+    /// Save a scenario's JSON response, pretty-printed, under --save-artifacts
+    pub fn save_json_artifact(&self, service: &str, scenario: &str, body: &serde_json::Value) {
+        if self.artifacts.is_some() {
+            let json = serde_json::to_vec_pretty(body).unwrap_or_default();
+            self.save_artifact(service, scenario, "json", &json);
+        }
+    }
+
+    /// `payload` when it replaces the minimal embedded asset: realistic
+    /// payloads were requested and no input of its type was given
+    pub fn realistic_payload(&self, payload: payloads::Payload) -> Option<payloads::Payload> {
+        (self.realistic_payloads && self.input(payload.input_type).is_none()).then_some(payload)
+    }
+
+    /// WebSocket URL to connect to: `url`, or the mock server under --mock
+    pub fn websocket_url(&self, url: &str) -> String {
+        match (self.mock, url::Url::parse(url)) {
+            (Some(addr), Ok(parsed)) => crate::mock::redirect_url(&parsed, addr).to_string(),
+            _ => url.to_string(),
+        }
+    }
+
+    /// Report a progress event to every listener
+    pub fn emit(&self, event: RunEvent) {
+        for reporter in &self.reporters {
+            event.report_to(reporter.as_ref());
+        }
+    }
+
+    /// Add headers to every request and optionally move the API key to a
+    /// different header, e.g. for an API Management gateway
+    pub fn with_extra_headers(
+        mut self,
+        headers: &[(String, String)],
+        api_key_header: Option<&str>,
+    ) -> Result<Self> {
+        for (name, value) in headers {
+            let (name, value) = parse_header(name, value)?;
+            self.extra_headers.append(name, value);
+        }
+        self.api_key_header = api_key_header
+            .map(|name| {
+                HeaderName::from_bytes(name.as_bytes()).map_err(|_| {
+                    crate::error::AppError::InvalidInput(format!(
+                        "Invalid API key header name '{}'",
+                        name
+                    ))
+                })
+            })
+            .transpose()?;
+        Ok(self)
+    }
+
+    /// Apply the extra headers and API key header override to a request
+    fn apply_headers(&self, request: &mut reqwest::Request) {
+        let headers = request.headers_mut();
+        if let Some(key_header) = &self.api_key_header {
+            if let Some(key) = headers.remove(DEFAULT_API_KEY_HEADER) {
+                headers.insert(key_header.clone(), key);
+            }
+        }
+        // Configured headers replace any the scenario set itself
+        headers.extend(self.extra_headers.clone());
+    }
+
+    /// Send a request, waiting for the rate limiter first if one is configured.
+    ///
+    /// Extra headers are applied here so every scenario picks them up. HTTP 429
+    /// responses are recorded (see `take_throttle`) and, if enabled, retried once
+    /// after the delay the service asked for.
+    pub async fn send(&self, request: RequestBuilder) -> reqwest::Result<Response> {
+        self.send_with(request, false).await
+    }
+
+    /// Send a request like `send`, but hand back the response as soon as its
+    /// head arrives, so the scenario can observe how the body streams in.
+    /// Only the time to first byte is recorded, and --capture records the
+    /// exchange without its body.
+    pub async fn send_streamed(&self, request: RequestBuilder) -> reqwest::Result<Response> {
+        self.send_with(request, true).await
+    }
+
+    async fn send_with(
+        &self,
+        request: RequestBuilder,
+        streamed: bool,
+    ) -> reqwest::Result<Response> {
+        let (client, request) = request.build_split();
+        let mut request = request?;
+        self.apply_headers(&mut request);
+        let retry = if self.retry_throttled {
+            request.try_clone()
+        } else {
+            None
+        };
+
+        let response = self.execute(&client, request, streamed).await?;
+        if response.status() != reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Ok(response);
+        }
+
+        let mut throttle = ThrottleInfo::from_headers(response.headers());
+        let Some(retry) = retry else {
+            self.record_throttle(throttle);
+            return Ok(response);
+        };
+
+        tokio::time::sleep(
+            throttle
+                .retry_after
+                .unwrap_or(DEFAULT_RETRY_AFTER)
+                .min(MAX_RETRY_AFTER),
+        )
+        .await;
+        throttle.retried = true;
+        self.record_throttle(throttle.clone());
+
+        let response = self.execute(&client, retry, streamed).await?;
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let mut again = ThrottleInfo::from_headers(response.headers());
+            again.retried = true;
+            self.record_throttle(again);
+        }
+        Ok(response)
+    }
+
+    /// Execute one attempt, recording it when capture is enabled
+    async fn execute(
+        &self,
+        client: &Client,
+        request: reqwest::Request,
+        streamed: bool,
+    ) -> reqwest::Result<Response> {
+        self.wait_for_rate_limit().await;
+
+        let mut request = request;
+        if let Some(addr) = self.mock {
+            let original = request.url().clone();
+            *request.url_mut() = crate::mock::redirect_url(&original, addr);
+            if let Ok(host) = original.host_str().unwrap_or_default().parse() {
+                request
+                    .headers_mut()
+                    .insert(crate::mock::MOCK_HOST_HEADER, host);
+            }
+        }
+
+        self.measure_connection(request.url()).await;
+
+        let method = request.method().clone();
+        let requested = request.url().clone();
+        let url = crate::capture::redact_url(&requested);
+        tracing::debug!(%method, %url, "sending request");
+        let start = Instant::now();
+        let result = self.execute_timed(client, request, streamed).await;
+        let duration_ms = start.elapsed().as_millis() as u64;
+        match &result {
+            Ok(response) => tracing::debug!(
+                status = response.status().as_u16(),
+                duration_ms,
+                "response received"
+            ),
+            Err(e) => {
+                tracing::debug!(%method, %url, duration_ms, error = %e, "request failed");
+                self.record_certificate_interception(&requested, e).await;
+            }
+        }
+        result
+    }
+
+    /// Execute a request and read its body (unless `streamed`), timing the
+    /// first byte and the whole response, and record it in the capture log
+    /// when enabled
+    async fn execute_timed(
+        &self,
+        client: &Client,
+        request: reqwest::Request,
+        streamed: bool,
+    ) -> reqwest::Result<Response> {
+        let capture = self.capture.as_deref();
+        let mut exchange = capture
+            .map(|capture| CapturedExchange::from_request(&request, capture.sensitive_headers()));
+        let record = |exchange: Option<CapturedExchange>| {
+            if let (Some(capture), Some(exchange)) = (capture, exchange) {
+                capture.record(exchange);
+            }
+        };
+        let requested = request.url().clone();
+        let host = host_key(&requested);
+        let reused = self
+            .connected_hosts
+            .lock()
+            .is_ok_and(|hosts| hosts.contains(&host));
+        let start = Instant::now();
+        let response = match client.execute(request).await {
+            Ok(response) => response,
+            Err(e) => {
+                let duration_ms = start.elapsed().as_millis() as u64;
+                self.record_response_timing(None, duration_ms, Some(&e), reused);
+                if let Some(exchange) = exchange.as_mut() {
+                    exchange.duration_ms = duration_ms;
+                    exchange.error = Some(e.to_string());
+                }
+                record(exchange);
+                return Err(e);
+            }
+        };
+        let ttfb_ms = start.elapsed().as_millis() as u64;
+        let received = response.url().clone();
+        if let Ok(mut hosts) = self.connected_hosts.lock() {
+            hosts.insert(host);
+        }
+
+        if streamed {
+            self.record_response_interception(&requested, &received, response.status(), "");
+            if let Ok(mut slot) = self.timing.lock() {
+                slot.get_or_insert_with(PhaseTiming::default).ttfb_ms = Some(ttfb_ms);
+            }
+            if let (Some(exchange), Some(capture)) = (exchange.as_mut(), capture) {
+                exchange.duration_ms = ttfb_ms;
+                exchange.status = Some(response.status().as_u16());
+                exchange.response_headers =
+                    crate::capture::redact_headers(response.headers(), capture.sensitive_headers());
+            }
+            record(exchange);
+            return Ok(response);
+        }
+
+        // Read the body so it can be timed and recorded, then hand the
+        // scenario an equivalent response
+        let status = response.status();
+        let version = response.version();
+        let headers = response.headers().clone();
+        let body = response.bytes().await;
+        let duration_ms = start.elapsed().as_millis() as u64;
+        self.record_response_timing(Some(ttfb_ms), duration_ms, body.as_ref().err(), reused);
+        if let (Some(exchange), Some(capture)) = (exchange.as_mut(), capture) {
+            exchange.duration_ms = duration_ms;
+            exchange.status = Some(status.as_u16());
+            exchange.response_headers =
+                crate::capture::redact_headers(&headers, capture.sensitive_headers());
+        }
+        let body = match body {
+            Ok(body) => body,
+            Err(e) => {
+                if let Some(exchange) = exchange.as_mut() {
+                    exchange.error = Some(e.to_string());
+                }
+                record(exchange);
+                return Err(e);
+            }
+        };
+        if let Some(exchange) = exchange.as_mut() {
+            exchange.response_body = Some(crate::capture::summarize_body(&body));
+        }
+        record(exchange);
+        self.record_response_interception(
+            &requested,
+            &received,
+            status,
+            &String::from_utf8_lossy(&body),
+        );
+
+        let mut rebuilt = http::Response::new(body.to_vec());
+        *rebuilt.status_mut() = status;
+        *rebuilt.version_mut() = version;
+        *rebuilt.headers_mut() = headers;
+        Ok(Response::from(rebuilt))
+    }
+
+    /// Measure the connection phases to `url`'s host before the scenario's
+    /// first request. Each host is measured once per context; a proxy hides
+    /// the phases, so they aren't measured through one.
+    async fn measure_connection(&self, url: &url::Url) {
+        if self.timing.lock().map_or(true, |timing| timing.is_some()) {
+            return;
+        }
+        let key = host_key(url);
+        let cached = self
+            .probed_hosts
+            .lock()
+            .ok()
+            .and_then(|hosts| hosts.get(&key).cloned());
+        let timing = match cached {
+            Some(timing) => PhaseTiming {
+                probe_ms: 0,
+                ..timing
+            },
+            None => {
+                let timing = if crate::network::phases::proxy_configured(url.scheme()) {
+                    PhaseTiming::default()
+                } else {
+                    crate::network::measure_phases(url, self.connect_timeout, self.tls_timeout)
+                        .await
+                };
+                if let Ok(mut hosts) = self.probed_hosts.lock() {
+                    hosts.insert(key, timing.clone());
+                }
+                timing
+            }
+        };
+        tracing::debug!(%timing, "connection phases");
+        if let Ok(mut slot) = self.timing.lock() {
+            *slot = Some(timing);
+        }
+    }
+
+    /// Record the response phases of a request in the scenario's timing, and
+    /// for a completed request whether it was cold or warm
+    fn record_response_timing(
+        &self,
+        ttfb_ms: Option<u64>,
+        total_ms: u64,
+        error: Option<&reqwest::Error>,
+        reused_connection: bool,
+    ) {
+        let Ok(mut slot) = self.timing.lock() else {
+            return;
+        };
+        let timing = slot.get_or_insert_with(PhaseTiming::default);
+        timing.ttfb_ms = ttfb_ms;
+        timing.total_ms = Some(total_ms);
+        if error.is_none() {
+            timing.split_latency(total_ms, reused_connection);
+        }
+        timing.failed_phase = match error {
+            None => None,
+            // The measured connection already shows which phase failed
+            Some(e) if e.is_connect() && timing.connection_failed() => timing.failed_phase,
+            Some(e) if e.is_connect() => Some(Phase::Connect),
+            Some(e) if e.is_timeout() || e.is_body() => Some(Phase::Response),
+            Some(_) => timing.failed_phase,
+        };
+    }
+
+    /// Take the phase timing of the scenario that just ran
+    pub fn take_timing(&self) -> Option<PhaseTiming> {
+        self.timing.lock().ok().and_then(|mut timing| timing.take())
+    }
+
+    async fn wait_for_rate_limit(&self) {
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire().await;
+        }
+    }
+
+    fn record_throttle(&self, throttle: ThrottleInfo) {
+        tracing::info!(
+            retry_after_ms = throttle.retry_after.map(|d| d.as_millis() as u64),
+            retried = throttle.retried,
+            "throttled (HTTP 429)"
+        );
+        if let Ok(mut last) = self.last_throttle.lock() {
+            *last = Some(throttle);
+        }
+    }
+
+    /// Record a response a network appliance sent in place of the service's
+    fn record_response_interception(
+        &self,
+        requested: &url::Url,
+        received: &url::Url,
+        status: reqwest::StatusCode,
+        body: &str,
+    ) {
+        let interception = crate::network::interception::response_interception(
+            requested,
+            received,
+            status.as_u16(),
+            body,
+        );
+        if let Some(interception) = interception {
+            tracing::info!(%interception, "response intercepted");
+            if let Ok(mut last) = self.last_interception.lock() {
+                *last = Some(interception);
+            }
+        }
+    }
+
+    /// After a certificate error, fetch the host's certificate without
+    /// validation and record the appliance that issued it, if it isn't Azure's
+    async fn record_certificate_interception(&self, url: &url::Url, error: &reqwest::Error) {
+        use crate::network::interception;
+
+        if !interception::is_certificate_error(&classify::request_error("", error)) {
+            return;
+        }
+        let host = url.host_str().unwrap_or_default().to_string();
+        let cached = self
+            .probed_certificates
+            .lock()
+            .ok()
+            .and_then(|hosts| hosts.get(&host).cloned());
+        let found = match cached {
+            Some(found) => found,
+            None => {
+                let found = interception::peer_certificate(url.as_str(), self.tls_timeout)
+                    .await
+                    .ok()
+                    .and_then(|certificate| certificate.interception(&host));
+                if let Ok(mut hosts) = self.probed_certificates.lock() {
+                    hosts.insert(host, found.clone());
+                }
+                found
+            }
+        };
+        if let Some(interception) = found {
+            tracing::info!(%interception, "certificate intercepted");
+            if let Ok(mut last) = self.last_interception.lock() {
+                *last = Some(interception);
+            }
+        }
+    }
+
+    /// Take the last network appliance interception observed since the previous call
+    pub fn take_interception(&self) -> Option<String> {
+        self.last_interception
+            .lock()
+            .ok()
+            .and_then(|mut last| last.take())
+    }
+
+    /// Take the last HTTP 429 observed since the previous call
+    pub fn take_throttle(&self) -> Option<ThrottleInfo> {
+        self.last_throttle
+            .lock()
+            .ok()
+            .and_then(|mut last| last.take())
+    }
+
     pub fn with_endpoint(mut self, endpoint: Option<String>) -> Self {
         self.endpoint = endpoint;
         self
     }
 
-    pub fn with_input(mut self, input: Option<TestInput>) -> Self {
-        self.input = input;
+    pub fn with_inputs(mut self, inputs: TestInputs) -> Self {
+        self.inputs = inputs;
         self
     }
 
+    /// User input for scenarios that take `input_type`
+    pub fn input(&self, input_type: InputType) -> Option<&TestInput> {
+        self.inputs.get(input_type)
+    }
+
     pub fn with_verbose(mut self, verbose: bool) -> Self {
         self.verbose = verbose;
         self
     }
+
+    pub fn with_service_config(mut self, service_config: ServiceConfig) -> Self {
+        self.service_config = service_config;
+        self
+    }
 }
 
 /// Trait for Azure AI Service implementations
@@ -268,38 +1612,78 @@ pub trait AzureService: Send + Sync {
     /// Human-readable display name
     fn display_name(&self) -> &'static str;
 
+    /// Other names accepted by `--services` (lowercase)
+    fn aliases(&self) -> &'static [&'static str] {
+        &[]
+    }
+
     /// Get the base endpoint URL for this service
     fn get_endpoint(&self, region: &str, cloud: Cloud, custom_endpoint: Option<&str>) -> String;
 
     /// List available test scenarios
     fn list_scenarios(&self) -> Vec<TestScenario>;
 
+    /// Scenarios that run against an on-prem container (`--container`); empty
+    /// when the service has no container
+    fn container_scenarios(&self) -> &'static [&'static str] {
+        &[]
+    }
+
     /// Run a specific test scenario
     async fn run_scenario(&self, scenario_id: &str, context: &TestContext) -> TestResult;
 
     /// Run all enabled scenarios
+    /// Scenarios a run executes: the selected ones, and only container
+    /// health checks against a container
+    fn selected_scenarios(
+        &self,
+        selection: &ScenarioSelection,
+        container: bool,
+    ) -> Vec<TestScenario> {
+        self.list_scenarios()
+            .into_iter()
+            .filter(|scenario| selection.selects_scenario(scenario))
+            .filter(|scenario| {
+                if container {
+                    self.container_scenarios().contains(&scenario.id)
+                } else {
+                    !container::is_health_scenario(scenario.id)
+                }
+            })
+            .collect()
+    }
+
     async fn run_all_scenarios(
         &self,
         context: &TestContext,
-        enabled_scenarios: Option<&[String]>,
+        selection: &ScenarioSelection,
     ) -> ServiceTestResults {
-        let scenarios = self.list_scenarios();
+        let scenarios = self.selected_scenarios(selection, context.container);
         let endpoint =
             self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
         let start = Instant::now();
         let mut results = Vec::new();
+        context.emit(RunEvent::ServiceStarted {
+            service: self.display_name().to_string(),
+            endpoint: endpoint.clone(),
+            scenarios: scenarios
+                .iter()
+                .map(|s| (s.id.to_string(), s.name.to_string()))
+                .collect(),
+        });
 
-        for scenario in &scenarios {
-            // Check if this scenario is in the enabled list
-            if let Some(enabled) = enabled_scenarios {
-                if !enabled.iter().any(|s| s == scenario.id) {
-                    continue;
-                }
+        for scenario in scenarios {
+            // An interrupted run starts nothing new
+            if context.interrupt.is_triggered() {
+                break;
             }
 
             // Check if we have required input
-            if scenario.requires_input && context.input.is_none() {
-                results.push(TestResult::skipped(
+            let has_input = scenario
+                .input_type
+                .is_some_and(|t| context.input(t).is_some());
+            if scenario.requires_input && !has_input {
+                let result = TestResult::skipped(
                     scenario.id,
                     scenario.name,
                     format!(
@@ -309,23 +1693,189 @@ pub trait AzureService: Send + Sync {
                             .map(|t| t.to_string())
                             .unwrap_or_default()
                     ),
-                ));
+                );
+                context.emit(RunEvent::ScenarioFinished {
+                    service: self.display_name().to_string(),
+                    result: Box::new(result.clone()),
+                });
+                results.push(result);
+                continue;
+            }
+
+            // Features the pricing tier lacks are known not to work
+            if let Some(feature) = context
+                .sku
+                .as_deref()
+                .filter(|_| !context.container)
+                .and_then(|sku| capabilities::check_sku(self.name(), scenario.id, sku))
+            {
+                let result = TestResult::skipped(
+                    scenario.id,
+                    scenario.name,
+                    capabilities::sku_unsupported_reason(
+                        feature,
+                        context.sku.as_deref().unwrap_or_default(),
+                    ),
+                );
+                context.emit(RunEvent::ScenarioFinished {
+                    service: self.display_name().to_string(),
+                    result: Box::new(result.clone()),
+                });
+                results.push(result);
                 continue;
             }
 
-            let result = self.run_scenario(scenario.id, context).await;
+            // A rejected credential is reported once, in the Authentication section
+            if context.auth_rejected && !scenario.tags.contains(&tags::NO_AUTH) {
+                let result = TestResult::skipped(
+                    scenario.id,
+                    scenario.name,
+                    "credentials rejected by the pre-flight check (see Authentication)".to_string(),
+                );
+                context.emit(RunEvent::ScenarioFinished {
+                    service: self.display_name().to_string(),
+                    result: Box::new(result.clone()),
+                });
+                results.push(result);
+                continue;
+            }
+
+            // Per-scenario timeout overrides from the config file
+            let scoped;
+            let scenario_context = match context.service_config.scenario_timeouts.get(scenario.id) {
+                Some(&secs) => match context.clone().with_timeout(Duration::from_secs(secs)) {
+                    Ok(c) => {
+                        scoped = c;
+                        &scoped
+                    }
+                    Err(_) => context,
+                },
+                None => context,
+            };
+
+            if let Some(capture) = &context.capture {
+                capture.set_scenario(self.name(), scenario.id);
+            }
+            context.emit(RunEvent::ScenarioStarted {
+                service: self.display_name().to_string(),
+                scenario_id: scenario.id.to_string(),
+            });
+            let span = tracing::info_span!("scenario", service = self.name(), id = scenario.id);
+            let mut result = context
+                .run_interruptible(
+                    scenario.id,
+                    scenario.name,
+                    self.run_scenario(scenario.id, scenario_context)
+                        .instrument(span),
+                )
+                .await;
+
+            if let Some(timing) = context.take_timing() {
+                result = apply_timing(result, timing);
+            }
+            if let Some(throttle) = context.take_throttle() {
+                result = apply_throttle(result, &throttle, context.throttled_is_failure);
+            }
+            if let Some(interception) = context.take_interception() {
+                result = apply_interception(result, interception);
+            }
+
+            // A 400/404 from a feature that isn't deployed in this region is expected,
+            // so report it as skipped rather than as a connectivity failure
+            if !result.success
+                && !context.container
+                && matches!(result.http_status, Some(400) | Some(404))
+            {
+                if let capabilities::Availability::Unavailable {
+                    feature,
+                    supported_regions,
+                } = capabilities::check(self.name(), scenario.id, &context.region, context.cloud)
+                {
+                    result = TestResult::skipped(
+                        scenario.id,
+                        scenario.name,
+                        capabilities::unavailable_reason(
+                            feature,
+                            &context.region,
+                            context.cloud,
+                            supported_regions,
+                        ),
+                    )
+                    .with_http_status(result.http_status.unwrap_or_default());
+                }
+            }
+            // Likewise for a feature the resource's pricing tier doesn't offer
+            if !result.success
+                && !result.is_skipped()
+                && !context.container
+                && matches!(result.http_status, Some(400) | Some(403) | Some(404))
+                && result
+                    .error
+                    .as_deref()
+                    .is_some_and(capabilities::is_sku_error)
+            {
+                let error = result.error.clone().unwrap_or_default();
+                result = TestResult::skipped(
+                    scenario.id,
+                    scenario.name,
+                    format!("Not supported on this SKU ({})", error),
+                )
+                .with_http_status(result.http_status.unwrap_or_default());
+            }
+
+            let mut result = classify::classify(result, context, &endpoint);
+            if let Some(max_latency_ms) = context.max_latency_ms {
+                result = apply_latency_threshold(result, max_latency_ms, context.slow_is_failure);
+            }
+            let result = apply_expectation(result, self.name(), &context.expectations);
+            log_result(self.name(), &result);
+            context.emit(RunEvent::ScenarioFinished {
+                service: self.display_name().to_string(),
+                result: Box::new(result.clone()),
+            });
             results.push(result);
         }
 
         ServiceTestResults {
             service_name: self.display_name().to_string(),
             endpoint,
+            region: None,
+            target: None,
             results,
             total_duration_ms: start.elapsed().as_millis() as u64,
         }
     }
 }
 
+/// Log the outcome of a scenario
+pub fn log_result(service: &str, result: &TestResult) {
+    if result.is_skipped() {
+        tracing::info!(
+            service,
+            id = %result.scenario_id,
+            reason = result.error.as_deref().unwrap_or(""),
+            "scenario skipped"
+        );
+    } else if result.success {
+        tracing::info!(
+            service,
+            id = %result.scenario_id,
+            duration_ms = result.duration_ms,
+            "scenario passed"
+        );
+    } else {
+        tracing::info!(
+            service,
+            id = %result.scenario_id,
+            duration_ms = result.duration_ms,
+            status = result.http_status,
+            kind = result.failure_kind.map(tracing::field::display),
+            error = result.error.as_deref().unwrap_or(""),
+            "scenario failed"
+        );
+    }
+}
+
 /// Helper function to measure execution time
 pub async fn measure_time<F, T>(f: F) -> (T, u64)
 where
@@ -337,33 +1887,624 @@ where
     (result, duration_ms)
 }
 
-/// List of all supported service names
-const SERVICE_NAMES: &[&str] = &[
-    "speech",
-    "translator",
-    "language",
-    "vision",
-    "document_intelligence",
-];
+/// "HTTP <status>: <sanitized body>", the message for an error response
+pub fn http_error(status: reqwest::StatusCode, body: &str) -> String {
+    format!(
+        "HTTP {}: {}",
+        status,
+        crate::error::sanitize_error(body, status.as_u16())
+    )
+}
+
+type Extractor<'a, T> = Box<dyn FnOnce(T) -> std::result::Result<String, String> + Send + 'a>;
+type ErrorHandler<'a> =
+    Box<dyn FnOnce(reqwest::StatusCode, &str) -> std::result::Result<String, String> + Send + 'a>;
+
+/// How a successful response body is turned into result details
+enum Extract<'a> {
+    Status,
+    Json(Extractor<'a, serde_json::Value>),
+    Text(Extractor<'a, String>),
+    Bytes(Extractor<'a, Vec<u8>>),
+}
+
+enum RequestBody {
+    Json(serde_json::Value),
+    Raw(reqwest::Body),
+    Multipart(reqwest::multipart::Form),
+}
+
+/// A single request/response scenario: send one request, time it, and turn
+/// the response into a `TestResult`.
+///
+/// Requests go through `TestContext::send`, so rate limiting, configured
+/// headers, throttling retries and capture all apply. Transport errors are
+/// classified with `classify::request_error`, error responses are reported as
+/// "HTTP <status>: <sanitized body>" unless `on_error` says otherwise, and a
+/// successful body is handed to the extractor to describe.
+pub struct ScenarioRequest<'a> {
+    method: reqwest::Method,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Option<RequestBody>,
+    authenticated: bool,
+    extract: Extract<'a>,
+    on_error: Option<ErrorHandler<'a>>,
+}
+
+impl<'a> ScenarioRequest<'a> {
+    pub fn new(method: reqwest::Method, url: impl Into<String>) -> Self {
+        Self {
+            method,
+            url: url.into(),
+            headers: Vec::new(),
+            body: None,
+            authenticated: true,
+            extract: Extract::Status,
+            on_error: None,
+        }
+    }
+
+    pub fn get(url: impl Into<String>) -> Self {
+        Self::new(reqwest::Method::GET, url)
+    }
+
+    pub fn post(url: impl Into<String>) -> Self {
+        Self::new(reqwest::Method::POST, url)
+    }
+
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Send a JSON body
+    pub fn json(mut self, body: &impl Serialize) -> Self {
+        self.body = Some(RequestBody::Json(
+            serde_json::to_value(body).unwrap_or_default(),
+        ));
+        self
+    }
+
+    /// Send a raw body with the given content type
+    pub fn body(mut self, content_type: impl Into<String>, body: impl Into<reqwest::Body>) -> Self {
+        self.body = Some(RequestBody::Raw(body.into()));
+        self.header("Content-Type", content_type)
+    }
+
+    pub fn multipart(mut self, form: reqwest::multipart::Form) -> Self {
+        self.body = Some(RequestBody::Multipart(form));
+        self
+    }
+
+    /// Don't apply the context's credentials: public routes, and scenarios
+    /// that set their own auth header
+    pub fn without_credentials(mut self) -> Self {
+        self.authenticated = false;
+        self
+    }
+
+    /// Describe a successful response deserialized as `T`; a body of another
+    /// shape fails as an unexpected response (see [`responses`])
+    pub fn extract<T: serde::de::DeserializeOwned>(
+        self,
+        f: impl FnOnce(T) -> std::result::Result<String, String> + Send + 'a,
+    ) -> Self {
+        self.extract_json(move |body| f(responses::from_value(body)?))
+    }
+
+    /// Describe a successful JSON response (an empty body is `null`, a body
+    /// that isn't JSON fails as an unexpected response)
+    pub fn extract_json(
+        mut self,
+        f: impl FnOnce(serde_json::Value) -> std::result::Result<String, String> + Send + 'a,
+    ) -> Self {
+        self.extract = Extract::Json(Box::new(f));
+        self
+    }
+
+    /// Describe a successful text response
+    pub fn extract_text(
+        mut self,
+        f: impl FnOnce(String) -> std::result::Result<String, String> + Send + 'a,
+    ) -> Self {
+        self.extract = Extract::Text(Box::new(f));
+        self
+    }
+
+    /// Describe a successful binary response (audio, images)
+    pub fn extract_bytes(
+        mut self,
+        f: impl FnOnce(Vec<u8>) -> std::result::Result<String, String> + Send + 'a,
+    ) -> Self {
+        self.extract = Extract::Bytes(Box::new(f));
+        self
+    }
+
+    /// Handle an error response: `Ok` reports the scenario as passed with
+    /// those details, `Err` replaces the default error message
+    pub fn on_error(
+        mut self,
+        f: impl FnOnce(reqwest::StatusCode, &str) -> std::result::Result<String, String> + Send + 'a,
+    ) -> Self {
+        self.on_error = Some(Box::new(f));
+        self
+    }
+
+    /// Send the request and build the scenario result
+    pub async fn run(self, context: &TestContext, scenario: &TestScenario) -> TestResult {
+        let (outcome, duration_ms) = measure_time(self.execute(context)).await;
+        TestResult::from_outcome(scenario, duration_ms, outcome)
+    }
+
+    async fn execute(self, context: &TestContext) -> std::result::Result<String, (u16, String)> {
+        let mut request = context.client.request(self.method, &self.url);
+        for (name, value) in &self.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        request = match self.body {
+            Some(RequestBody::Json(body)) => request.json(&body),
+            Some(RequestBody::Raw(body)) => request.body(body),
+            Some(RequestBody::Multipart(form)) => request.multipart(form),
+            None => request,
+        };
+        if self.authenticated {
+            request = context.credentials.apply_to_request(request);
+        }
+
+        let response = context
+            .send(request)
+            .await
+            .map_err(|e| (0, classify::request_error("Request failed", &e)))?;
+        let status = response.status();
+        let code = status.as_u16();
+
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return match self.on_error {
+                Some(f) => f(status, &body),
+                None => Err(http_error(status, &body)),
+            }
+            .map_err(|e| (code, e));
+        }
+
+        match self.extract {
+            Extract::Status => Ok(format!("HTTP {}", status)),
+            Extract::Json(f) => {
+                let body = response.text().await.unwrap_or_default();
+                if body.trim().is_empty() {
+                    f(serde_json::Value::Null)
+                } else {
+                    responses::parse(&body).and_then(f)
+                }
+            }
+            Extract::Text(f) => f(response.text().await.unwrap_or_default()),
+            Extract::Bytes(f) => f(response
+                .bytes()
+                .await
+                .map(|b| b.to_vec())
+                .unwrap_or_default()),
+        }
+        .map_err(|e| (code, e))
+    }
+}
+
+/// Services that can be tested by name: the built-ins plus any registered by
+/// an embedding crate or defined in the config file (`[custom_services]`)
+pub struct ServiceRegistry {
+    services: Vec<Arc<dyn AzureService>>,
+}
+
+impl ServiceRegistry {
+    /// A registry with the built-in services
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        registry.register(Box::new(speech::SpeechService::new()));
+        registry.register(Box::new(translator::TranslatorService::new()));
+        registry.register(Box::new(language::LanguageService::new()));
+        registry.register(Box::new(vision::VisionService::new()));
+        registry.register(Box::new(
+            document_intelligence::DocumentIntelligenceService::new(),
+        ));
+        registry.register(Box::new(foundry::FoundryService::new()));
+        registry.register(Box::new(inference::ModelInferenceService::new()));
+        registry.register(Box::new(openai::OpenAiService::new()));
+        registry
+    }
+
+    /// A registry without any services
+    pub fn empty() -> Self {
+        Self {
+            services: Vec::new(),
+        }
+    }
+
+    /// Add a service, replacing any registered under the same name
+    pub fn register(&mut self, service: Box<dyn AzureService>) {
+        let service: Arc<dyn AzureService> = Arc::from(service);
+        match self
+            .services
+            .iter()
+            .position(|s| s.name() == service.name())
+        {
+            Some(index) => self.services[index] = service,
+            None => self.services.push(service),
+        }
+    }
+
+    /// Look up a service by name or alias (case-insensitive)
+    pub fn get(&self, name: &str) -> Option<Arc<dyn AzureService>> {
+        let name = name.to_lowercase();
+        self.services
+            .iter()
+            .find(|s| s.name() == name || s.aliases().contains(&name.as_str()))
+            .cloned()
+    }
+
+    /// All services, built-ins first, in registration order
+    pub fn all(&self) -> Vec<Arc<dyn AzureService>> {
+        self.services.clone()
+    }
+}
+
+impl Default for ServiceRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Process-wide registry used by `--services`, the runner, and the doctor
+static REGISTRY: LazyLock<RwLock<ServiceRegistry>> =
+    LazyLock::new(|| RwLock::new(ServiceRegistry::new()));
+
+/// Add a service to the process-wide registry, replacing any with the same name
+pub fn register_service(service: Box<dyn AzureService>) {
+    if let Ok(mut registry) = REGISTRY.write() {
+        registry.register(service);
+    }
+}
 
 /// Get all available services
-pub fn get_all_services() -> Vec<Box<dyn AzureService>> {
-    SERVICE_NAMES
-        .iter()
-        .filter_map(|name| get_service(name))
-        .collect()
+pub fn get_all_services() -> Vec<Arc<dyn AzureService>> {
+    REGISTRY.read().map(|r| r.all()).unwrap_or_default()
 }
 
 /// Get a service by name
-pub fn get_service(name: &str) -> Option<Box<dyn AzureService>> {
-    match name.to_lowercase().as_str() {
-        "speech" => Some(Box::new(speech::SpeechService::new())),
-        "translator" => Some(Box::new(translator::TranslatorService::new())),
-        "language" => Some(Box::new(language::LanguageService::new())),
-        "vision" => Some(Box::new(vision::VisionService::new())),
-        "document_intelligence" | "document-intelligence" | "documentintelligence" => Some(
-            Box::new(document_intelligence::DocumentIntelligenceService::new()),
-        ),
-        _ => None,
+pub fn get_service(name: &str) -> Option<Arc<dyn AzureService>> {
+    REGISTRY.read().ok().and_then(|r| r.get(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extra_headers_and_api_key_header() {
+        let context = TestContext::new(
+            Credentials::ApiKey("secret".to_string()),
+            Cloud::Global,
+            "eastus".to_string(),
+            Duration::from_secs(5),
+        )
+        .unwrap()
+        .with_extra_headers(
+            &[("x-tenant".to_string(), "contoso".to_string())],
+            Some("x-gateway-key"),
+        )
+        .unwrap();
+
+        let builder = context
+            .client
+            .get("https://apim.contoso.com/language")
+            .header("x-tenant", "scenario-default");
+        let mut request = context
+            .credentials
+            .apply_to_request(builder)
+            .build()
+            .unwrap();
+        context.apply_headers(&mut request);
+
+        let headers = request.headers();
+        assert_eq!(headers["x-gateway-key"], "secret");
+        assert!(headers.get(DEFAULT_API_KEY_HEADER).is_none());
+        assert_eq!(headers.get_all("x-tenant").iter().count(), 1);
+        assert_eq!(headers["x-tenant"], "contoso");
+
+        assert!(TestContext::new(
+            Credentials::ApiKey("k".to_string()),
+            Cloud::Global,
+            "eastus".to_string(),
+            Duration::from_secs(5),
+        )
+        .unwrap()
+        .with_extra_headers(&[("bad header".to_string(), "v".to_string())], None)
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        use reqwest::header::{HeaderMap, HeaderValue};
+
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", HeaderValue::from_static("3"));
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(3)));
+
+        headers.insert("x-ms-retry-after-ms", HeaderValue::from_static("1500"));
+        assert_eq!(
+            parse_retry_after(&headers),
+            Some(Duration::from_millis(1500))
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "retry-after",
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(parse_retry_after(&headers), Some(Duration::ZERO));
+
+        assert_eq!(parse_retry_after(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn test_apply_throttle() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("retry-after", "2".parse().unwrap());
+        headers.insert("x-ratelimit-remaining-requests", "0".parse().unwrap());
+        let throttle = ThrottleInfo::from_headers(&headers);
+
+        let failed =
+            TestResult::failure("tts", "TTS", 10, "HTTP 429".to_string()).with_http_status(429);
+        let result = apply_throttle(failed.clone(), &throttle, false);
+        assert!(result.is_throttled());
+        assert_eq!(
+            result.details.as_deref(),
+            Some("Rate limited: Retry-After 2000ms, x-ratelimit-remaining-requests: 0")
+        );
+
+        let result = apply_throttle(failed, &throttle, true);
+        assert!(!result.is_throttled());
+
+        let retried = ThrottleInfo {
+            retried: true,
+            ..throttle
+        };
+        let ok = apply_throttle(TestResult::success("tts", "TTS", 10), &retried, false);
+        assert!(ok
+            .details
+            .unwrap()
+            .starts_with("Succeeded after retrying an HTTP 429"));
+    }
+
+    #[test]
+    fn test_apply_interception() {
+        let portal = "Intercepted by network appliance: redirected to https://portal.corp.local/";
+
+        let passed = apply_interception(
+            TestResult::success("languages", "Languages", 10),
+            portal.to_string(),
+        );
+        assert!(!passed.success);
+        assert_eq!(passed.error.as_deref(), Some(portal));
+
+        let failed = TestResult::failure(
+            "detect",
+            "Detect",
+            10,
+            "Endpoint reachable but response shape unexpected".to_string(),
+        );
+        let failed = apply_interception(failed, portal.to_string());
+        assert_eq!(
+            failed.error.as_deref(),
+            Some("Intercepted by network appliance: redirected to https://portal.corp.local/ [Endpoint reachable but response shape unexpected]")
+        );
+
+        let skipped = TestResult::skipped("qa", "QA", "Requires a project".to_string());
+        assert!(apply_interception(skipped, portal.to_string()).is_skipped());
+    }
+
+    #[test]
+    fn test_apply_latency_threshold() {
+        let fast = apply_latency_threshold(TestResult::success("tts", "TTS", 900), 1500, false);
+        assert!(fast.success && !fast.slow);
+
+        let slow = TestResult::success("tts", "TTS", 2100).with_details("24kHz".to_string());
+        let warned = apply_latency_threshold(slow.clone(), 1500, false);
+        assert!(warned.success && warned.slow);
+        assert_eq!(
+            warned.details.as_deref(),
+            Some("24kHz; Slow: 2100ms exceeds the 1500ms latency threshold")
+        );
+
+        let failed = apply_latency_threshold(slow, 1500, true);
+        assert!(!failed.success);
+        assert_eq!(failed.failure_kind, Some(FailureKind::Slow));
+    }
+
+    #[test]
+    fn test_apply_expectation() {
+        let expectations = vec![
+            Expectation {
+                service: "speech".to_string(),
+                scenario: None,
+                expect: Expect::Fail,
+                reason: Some("blocked by egress policy".to_string()),
+            },
+            Expectation {
+                service: "speech".to_string(),
+                scenario: Some("voices_list".to_string()),
+                expect: Expect::Pass,
+                reason: None,
+            },
+        ];
+
+        let blocked = TestResult::failure("tts", "TTS", 30, "Connection refused".to_string());
+        let expected = apply_expectation(blocked, "speech", &expectations);
+        assert!(expected.is_expected_failure());
+        assert_eq!(
+            expected.details.as_deref(),
+            Some("Expected failure (blocked by egress policy)")
+        );
+
+        let reachable = apply_expectation(
+            TestResult::success("tts", "TTS", 30),
+            "speech",
+            &expectations,
+        );
+        assert!(!reachable.success && !reachable.is_expected_failure());
+        assert_eq!(reachable.failure_kind, Some(FailureKind::UnexpectedSuccess));
+
+        // A scenario's own entry overrides the service-wide one
+        let voices = TestResult::failure("voices_list", "Voices", 30, "HTTP 500".to_string());
+        assert!(!apply_expectation(voices, "speech", &expectations).is_expected_failure());
+        let skipped = TestResult::skipped("stt_short", "STT", "no input".to_string());
+        assert!(apply_expectation(skipped, "speech", &expectations).is_skipped());
+        let other = TestResult::success("translate", "Translate", 30);
+        assert!(apply_expectation(other, "translator", &expectations).success);
+    }
+
+    #[test]
+    fn test_progress_reporters() {
+        #[derive(Default)]
+        struct Counter(std::sync::Mutex<Vec<String>>);
+        impl ProgressReporter for Counter {
+            fn on_scenario_complete(&self, service: &str, result: &TestResult) {
+                let mut seen = self.0.lock().unwrap();
+                seen.push(format!("{}/{}", service, result.scenario_id));
+            }
+        }
+
+        let counter = Arc::new(Counter::default());
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let context = TestContext::new(
+            Credentials::ApiKey("key".to_string()),
+            Cloud::Global,
+            "eastus".to_string(),
+            Duration::from_secs(5),
+        )
+        .unwrap()
+        .with_reporters(vec![counter.clone(), Arc::new(tx)]);
+
+        context.emit(RunEvent::ScenarioStarted {
+            service: "Speech".to_string(),
+            scenario_id: "tts".to_string(),
+        });
+        context.emit(RunEvent::ScenarioFinished {
+            service: "Speech".to_string(),
+            result: Box::new(TestResult::success("tts", "TTS", 90)),
+        });
+
+        assert_eq!(*counter.0.lock().unwrap(), vec!["Speech/tts".to_string()]);
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(RunEvent::ScenarioStarted { .. })
+        ));
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(RunEvent::ScenarioFinished { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_scenario_request() {
+        let mock = crate::mock::MockServer::start().await.unwrap();
+        let context = TestContext::new(
+            Credentials::ApiKey("key".to_string()),
+            Cloud::Global,
+            "eastus".to_string(),
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        let scenario = TestScenario {
+            id: "probe",
+            name: "Probe",
+            description: "",
+            requires_input: false,
+            input_type: None,
+            tags: &[],
+        };
+
+        let result = ScenarioRequest::get(format!("http://{}/status", mock.addr()))
+            .extract_json(|body| Ok(format!("API status: {}", body["apiStatus"])))
+            .run(&context, &scenario)
+            .await;
+        assert!(result.success);
+        assert_eq!(result.details.as_deref(), Some("API status: \"Valid\""));
+
+        let result = ScenarioRequest::get(format!("http://{}/missing", mock.addr()))
+            .run(&context, &scenario)
+            .await;
+        assert!(!result.success);
+        assert_eq!(result.http_status, Some(404));
+        assert!(result.error.unwrap().starts_with("HTTP 404 Not Found"));
+
+        // An error status the scenario accepts passes, and the extractor can fail
+        let result = ScenarioRequest::get(format!("http://{}/missing", mock.addr()))
+            .on_error(|status, _| Ok(format!("Route answered ({})", status.as_u16())))
+            .run(&context, &scenario)
+            .await;
+        assert_eq!(result.details.as_deref(), Some("Route answered (404)"));
+        let result = ScenarioRequest::get(format!("http://{}/status", mock.addr()))
+            .extract_text(|_| Err("Unexpected body".to_string()))
+            .run(&context, &scenario)
+            .await;
+        assert_eq!(result.error.as_deref(), Some("Unexpected body"));
+        assert_eq!(result.http_status, Some(200));
+
+        // No response: no HTTP status on the result
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        drop(listener);
+        let result = ScenarioRequest::get(url).run(&context, &scenario).await;
+        assert!(!result.success);
+        assert_eq!(result.http_status, None);
+    }
+
+    #[tokio::test]
+    async fn test_interrupt() {
+        let interrupt = Interrupt::new();
+        let context = TestContext::new(
+            Credentials::ApiKey("key".to_string()),
+            Cloud::Global,
+            "eastus".to_string(),
+            Duration::from_secs(5),
+        )
+        .unwrap()
+        .with_interrupt(interrupt.clone());
+
+        let result = context
+            .run_interruptible("tts", "TTS", async {
+                TestResult::success("tts", "TTS", 90)
+            })
+            .await;
+        assert!(result.success);
+
+        // Interrupted runs start no scenario
+        interrupt.trigger();
+        let service = get_service("translator").unwrap();
+        let results = service
+            .run_all_scenarios(&context, &ScenarioSelection::default())
+            .await;
+        assert!(results.results.is_empty());
+
+        // A second interrupt gives up on the running scenario without waiting
+        interrupt.trigger();
+        let start = Instant::now();
+        let result = context
+            .run_interruptible("tts", "TTS", std::future::pending())
+            .await;
+        assert!(result.is_skipped());
+        assert!(result.error.unwrap().contains("Interrupted"));
+        assert!(start.elapsed() < INTERRUPT_GRACE);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_spaces_requests() {
+        let limiter = RateLimiter::new(20.0);
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+        // The first slot is immediate; the next two wait 50ms each
+        assert!(start.elapsed() >= Duration::from_millis(95));
     }
 }
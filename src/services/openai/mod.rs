@@ -0,0 +1,361 @@
+//! Azure OpenAI (`https://<resource>.openai.azure.com`)
+//!
+//! Besides chat, voice and agent workloads depend on the Assistants routes and
+//! the realtime WebSocket (`wss://.../openai/realtime`), which proxies and
+//! firewalls that only allow plain HTTPS commonly break.
+
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message};
+
+use crate::auth::Credentials;
+use crate::config::Cloud;
+use crate::error::sanitize_error;
+use crate::services::endpoints::ServiceEndpoint;
+use crate::services::responses::openai::{ChatCompletion, List};
+use crate::services::{
+    api_versions, measure_time, tags, AzureService, ScenarioRequest, TestContext, TestResult,
+    TestScenario,
+};
+
+/// Shown when no endpoint was given
+const PLACEHOLDER_ENDPOINT: &str = "https://<resource>.openai.azure.com";
+
+/// Azure OpenAI Service implementation
+pub struct OpenAiService;
+
+impl OpenAiService {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for OpenAiService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Realtime WebSocket URL for an endpoint (`https://` becomes `wss://`)
+pub fn realtime_url(endpoint: &str, deployment: &str, api_version: &str) -> String {
+    let endpoint = endpoint.trim_end_matches('/');
+    let host = endpoint
+        .strip_prefix("https://")
+        .or_else(|| endpoint.strip_prefix("http://"))
+        .unwrap_or(endpoint);
+    format!(
+        "wss://{}/openai/realtime?api-version={}&deployment={}",
+        host, api_version, deployment
+    )
+}
+
+#[async_trait]
+impl AzureService for OpenAiService {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Azure OpenAI"
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["azure_openai", "azure-openai", "aoai"]
+    }
+
+    fn get_endpoint(&self, _region: &str, _cloud: Cloud, custom_endpoint: Option<&str>) -> String {
+        match custom_endpoint {
+            Some(endpoint) => ServiceEndpoint::custom(endpoint).url(),
+            None => PLACEHOLDER_ENDPOINT.to_string(),
+        }
+    }
+
+    fn list_scenarios(&self) -> Vec<TestScenario> {
+        vec![
+            TestScenario {
+                id: "list_models",
+                name: "List Models",
+                description: "List models available to the resource (lightweight auth check)",
+                requires_input: false,
+                input_type: None,
+                tags: &[tags::FAST],
+            },
+            TestScenario {
+                id: "chat_completions",
+                name: "Chat Completions",
+                description: "One-token chat completion (needs a deployment name)",
+                requires_input: false,
+                input_type: None,
+                tags: &[],
+            },
+            TestScenario {
+                id: "assistants_list",
+                name: "List Assistants",
+                description: "List assistants through the Assistants API routes",
+                requires_input: false,
+                input_type: None,
+                tags: &[tags::FAST],
+            },
+            TestScenario {
+                id: "realtime_ws",
+                name: "Realtime WebSocket",
+                description: "Open the realtime API WebSocket and wait for session.created",
+                requires_input: false,
+                input_type: None,
+                tags: &[tags::WEBSOCKET],
+            },
+        ]
+    }
+
+    async fn run_scenario(&self, scenario_id: &str, context: &TestContext) -> TestResult {
+        let scenario = self
+            .list_scenarios()
+            .into_iter()
+            .find(|s| s.id == scenario_id);
+
+        let scenario = match scenario {
+            Some(s) => s,
+            None => {
+                return TestResult::failure(
+                    scenario_id,
+                    "Unknown",
+                    0,
+                    format!("Unknown scenario: {}", scenario_id),
+                )
+            }
+        };
+
+        let Some(endpoint) = context.endpoint.as_deref() else {
+            return TestResult::skipped(
+                scenario.id,
+                scenario.name,
+                format!("Requires --endpoint {}", PLACEHOLDER_ENDPOINT),
+            );
+        };
+        let endpoint = endpoint.trim_end_matches('/');
+
+        match scenario_id {
+            "list_models" => {
+                let url = format!(
+                    "{}/openai/models?api-version={}",
+                    endpoint,
+                    api_versions::OPENAI.version(context)
+                );
+                let request = ScenarioRequest::get(url);
+                Self::run_request(context, &scenario, request, |list: List| {
+                    let count = list.data.len();
+                    format!("Found {} models", count)
+                })
+                .await
+            }
+            "chat_completions" => {
+                self.test_chat_completions(context, &scenario, endpoint)
+                    .await
+            }
+            "assistants_list" => {
+                let url = format!(
+                    "{}/openai/assistants?api-version={}&limit=1",
+                    endpoint,
+                    api_versions::OPENAI_ASSISTANTS.version(context)
+                );
+                let request = ScenarioRequest::get(url);
+                Self::run_request(context, &scenario, request, |list: List| {
+                    let count = list.data.len();
+                    format!("Assistants API reachable ({} returned)", count)
+                })
+                .await
+            }
+            "realtime_ws" => self.test_realtime(context, &scenario, endpoint).await,
+            _ => TestResult::failure(
+                scenario_id,
+                scenario.name,
+                0,
+                format!("Scenario '{}' not implemented", scenario_id),
+            ),
+        }
+    }
+}
+
+impl OpenAiService {
+    /// Auth header for the credentials: `api-key` for keys, bearer otherwise
+    fn auth_header(credentials: &Credentials) -> (&'static str, String) {
+        match credentials {
+            Credentials::ApiKey(key) => ("api-key", key.clone()),
+            Credentials::BearerToken(token) => ("Authorization", format!("Bearer {}", token)),
+        }
+    }
+
+    /// Send an authenticated request and describe a successful JSON response
+    async fn run_request<'a, T: serde::de::DeserializeOwned>(
+        context: &TestContext,
+        scenario: &TestScenario,
+        request: ScenarioRequest<'a>,
+        describe: impl FnOnce(T) -> String + Send + 'a,
+    ) -> TestResult {
+        let (name, value) = Self::auth_header(&context.credentials);
+        request
+            .without_credentials()
+            .header(name, value)
+            .extract(|response| Ok(describe(response)))
+            .run(context, scenario)
+            .await
+    }
+
+    async fn test_chat_completions(
+        &self,
+        context: &TestContext,
+        scenario: &TestScenario,
+        endpoint: &str,
+    ) -> TestResult {
+        let Some(deployment) = context.service_config.deployment_name.as_deref() else {
+            return TestResult::skipped(
+                scenario.id,
+                scenario.name,
+                "Requires a deployment name (--openai-deployment or deployment_name in [services.openai])"
+                    .to_string(),
+            );
+        };
+
+        let url = format!(
+            "{}/openai/deployments/{}/chat/completions?api-version={}",
+            endpoint,
+            deployment,
+            api_versions::OPENAI.version(context)
+        );
+        let body = serde_json::json!({
+            "messages": [{"role": "user", "content": "ping"}],
+            "max_tokens": 1
+        });
+        let request = ScenarioRequest::post(url).json(&body);
+        Self::run_request(context, scenario, request, |completion: ChatCompletion| {
+            let model = completion.model.as_deref().unwrap_or(deployment);
+            format!("Completion returned by {}", model)
+        })
+        .await
+    }
+
+    async fn test_realtime(
+        &self,
+        context: &TestContext,
+        scenario: &TestScenario,
+        endpoint: &str,
+    ) -> TestResult {
+        let Some(deployment) = context.service_config.realtime_deployment.as_deref() else {
+            return TestResult::skipped(
+                scenario.id,
+                scenario.name,
+                "Requires a realtime model deployment (--realtime-deployment or realtime_deployment in [services.openai])"
+                    .to_string(),
+            );
+        };
+        let ws_url = realtime_url(
+            endpoint,
+            deployment,
+            api_versions::OPENAI_REALTIME.version(context),
+        );
+
+        let (result, duration_ms) = measure_time(async {
+            let connect_start = std::time::Instant::now();
+            let mut request = match context.websocket_url(&ws_url).into_client_request() {
+                Ok(r) => r,
+                Err(e) => return Err((0, format!("Invalid WebSocket URL: {}", e))),
+            };
+            let headers = request.headers_mut();
+            let (name, value) = Self::auth_header(&context.credentials);
+            if let Ok(value) = value.parse() {
+                headers.insert(name, value);
+            }
+            for (name, value) in &context.extra_headers {
+                if let (Ok(name), Ok(value)) = (
+                    name.as_str()
+                        .parse::<tokio_tungstenite::tungstenite::http::HeaderName>(),
+                    value.as_bytes().try_into(),
+                ) {
+                    headers.insert(name, value);
+                }
+            }
+
+            let connect = tokio::time::timeout(
+                context.timeout,
+                crate::network::websocket::connect(request),
+            )
+            .await;
+            let mut socket = match connect {
+                Ok(Ok((socket, _))) => socket,
+                Ok(Err(tokio_tungstenite::tungstenite::Error::Http(response))) => {
+                    let status = response.status().as_u16();
+                    return Err((
+                        status,
+                        format!("WebSocket upgrade rejected (HTTP {})", status),
+                    ));
+                }
+                Ok(Err(e)) => return Err((0, format!("WebSocket connection failed: {}", e))),
+                Err(_) => return Err((0, "WebSocket connection timed out".to_string())),
+            };
+            let connect_ms = connect_start.elapsed().as_millis();
+
+            // The service announces the session as soon as the socket is open
+            let session_start = std::time::Instant::now();
+            let wait = tokio::time::timeout(context.timeout, async {
+                while let Some(message) = socket.next().await {
+                    match message {
+                        Ok(Message::Text(text)) if text.contains("\"session.created\"") => {
+                            return Ok(());
+                        }
+                        Ok(Message::Text(text)) if text.contains("\"error\"") => {
+                            return Err(format!(
+                                "Realtime error: {}",
+                                sanitize_error(&text, 400)
+                            ));
+                        }
+                        Ok(Message::Close(frame)) => {
+                            let reason = frame
+                                .map(|f| format!("{} {}", f.code, f.reason))
+                                .unwrap_or_default();
+                            return Err(format!("Service closed the connection: {}", reason));
+                        }
+                        Ok(_) => continue,
+                        Err(e) => return Err(format!("WebSocket error: {}", e)),
+                    }
+                }
+                Err("Connection ended before session.created".to_string())
+            })
+            .await;
+            let _ = socket.close(None).await;
+
+            match wait {
+                Ok(Ok(())) => Ok(format!(
+                    "WebSocket connect {}ms, session.created after {}ms",
+                    connect_ms,
+                    session_start.elapsed().as_millis()
+                )),
+                Ok(Err(e)) => Err((0, e)),
+                Err(_) => Err((
+                    0,
+                    "Timed out waiting for session.created (proxy may be buffering WebSocket frames)"
+                        .to_string(),
+                )),
+            }
+        })
+        .await;
+
+        TestResult::from_outcome(scenario, duration_ms, result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_realtime_url() {
+        assert_eq!(
+            realtime_url(
+                "https://contoso.openai.azure.com/",
+                "gpt-4o-realtime",
+                "2024-10-01-preview"
+            ),
+            "wss://contoso.openai.azure.com/openai/realtime?api-version=2024-10-01-preview&deployment=gpt-4o-realtime"
+        );
+    }
+}
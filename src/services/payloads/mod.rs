@@ -0,0 +1,163 @@
+//! Embedded assets with real content (`test --realistic-payloads`)
+//!
+//! The default embedded WAV is silence and the PNG a blank square, so a passing
+//! Speech or Vision scenario only proves the request got through. These assets
+//! carry content the service has to process: a spoken phrase, printed text, and
+//! a PDF with a text layer. Scenarios that send them report what the service
+//! recognized and fail when it doesn't match what the asset contains.
+
+use crate::services::InputType;
+
+/// An embedded asset and the text a service should recognize in it
+#[derive(Debug, Clone, Copy)]
+pub struct Payload {
+    pub data: &'static [u8],
+    pub content_type: &'static str,
+    /// Input it stands in for; user input of this type takes precedence
+    pub input_type: InputType,
+    /// Text spoken, printed, or written in the asset
+    pub text: &'static str,
+}
+
+/// 16kHz mono PCM WAV of a spoken phrase (about 4 seconds)
+pub const SPEECH: Payload = Payload {
+    data: include_bytes!("../../../samples/test-speech.wav"),
+    content_type: "audio/wav",
+    input_type: InputType::Audio,
+    text: "Hello, this is a connectivity test",
+};
+
+/// PNG with two lines of large printed text
+pub const IMAGE: Payload = Payload {
+    data: include_bytes!("../../../samples/test-text-image.png"),
+    content_type: "image/png",
+    input_type: InputType::Image,
+    text: "AZURE AI CONNECTIVITY TEST",
+};
+
+/// One-page PDF with a text layer
+pub const DOCUMENT: Payload = Payload {
+    data: include_bytes!("../../../samples/test-document.pdf"),
+    content_type: "application/pdf",
+    input_type: InputType::Document,
+    text: "Azure AI Services Document Intelligence Test",
+};
+
+/// Share of the expected words that must be recognized; speech and OCR may
+/// miss or split a word without the request having gone wrong
+const MIN_WORD_MATCH: f64 = 0.75;
+
+/// Longest recognized text quoted in result details
+const MAX_QUOTED_CHARS: usize = 80;
+
+/// Lowercase alphanumeric words of `text`
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+fn quote(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() > MAX_QUOTED_CHARS {
+        let truncated: String = text.chars().take(MAX_QUOTED_CHARS).collect();
+        format!("\"{}...\"", truncated)
+    } else {
+        format!("\"{}\"", text)
+    }
+}
+
+impl Payload {
+    /// Whether `recognized` contains (most of) the words of the payload's text
+    pub fn matches(&self, recognized: &str) -> bool {
+        let expected = words(self.text);
+        let recognized = words(recognized);
+        let found = expected.iter().filter(|w| recognized.contains(w)).count();
+        found as f64 >= expected.len() as f64 * MIN_WORD_MATCH
+    }
+
+    /// Details for a result whose service recognized `recognized`, or the
+    /// error when that isn't the payload's content
+    pub fn verify(&self, recognized: &str) -> Result<String, String> {
+        if self.matches(recognized) {
+            Ok(format!("recognized {}", quote(recognized)))
+        } else if recognized.trim().is_empty() {
+            Err(format!(
+                "Request succeeded but nothing was recognized in the realistic payload (expected {})",
+                quote(self.text)
+            ))
+        } else {
+            Err(format!(
+                "Request succeeded but the realistic payload was misrecognized: expected {}, got {}",
+                quote(self.text),
+                quote(recognized)
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_recognized_text() {
+        assert_eq!(&SPEECH.data[8..12], b"WAVE");
+        assert!(IMAGE.data.starts_with(b"\x89PNG"));
+        assert!(DOCUMENT.data.starts_with(b"%PDF"));
+
+        assert_eq!(
+            SPEECH
+                .verify("Hello, this is a connectivity test.")
+                .unwrap(),
+            "recognized \"Hello, this is a connectivity test.\""
+        );
+        // OCR splitting the text across lines, and one missed word
+        assert!(IMAGE.matches("AZURE AI\nCONNECTIVITY TEST"));
+        assert!(IMAGE.matches("AZURE AI CONNECTIVITY"));
+        assert!(!IMAGE.matches("AZURE"));
+        assert!(SPEECH
+            .verify("")
+            .unwrap_err()
+            .contains("nothing was recognized"));
+        assert!(DOCUMENT
+            .verify("Invoice")
+            .unwrap_err()
+            .contains("misrecognized"));
+    }
+
+    #[tokio::test]
+    async fn test_realistic_run_against_mock() {
+        let mock = crate::mock::MockServer::start().await.unwrap();
+        let config = crate::testing::TestRunnerConfig::builder()
+            .services(["speech", "vision", "document_intelligence"])
+            .scenarios([
+                "stt_short",
+                "stt_rest",
+                "analyze_image",
+                "read_text",
+                "read",
+            ])
+            .auth_method(crate::config::AuthMethod::Key)
+            .api_key(crate::mock::MOCK_API_KEY)
+            .region("eastus")
+            .realistic_payloads(true)
+            .quiet(true)
+            .mock(mock.addr())
+            .build();
+        let report = crate::testing::TestRunner::new(config).run().await.unwrap();
+        let results: Vec<_> = report
+            .services
+            .iter()
+            .filter(|s| s.service_name != crate::services::preflight::SECTION_NAME)
+            .flat_map(|s| &s.results)
+            .collect();
+        assert_eq!(results.len(), 5);
+        for result in results {
+            assert!(result.success, "{:?}", result);
+            let details = result.details.as_deref().unwrap_or_default();
+            assert!(details.contains("recognized \""), "{}", details);
+        }
+    }
+}
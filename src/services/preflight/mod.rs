@@ -0,0 +1,289 @@
+//! Credential check before the scenarios run
+//!
+//! A wrong key or an expired token fails every scenario with the same 401,
+//! which buries the cause among per-service results. So the run first checks
+//! the credential on its own: an API key is exchanged for a token at the
+//! Cognitive Services token service (STS), and a bearer token (given, or from
+//! Entra ID or a managed identity) is decoded to check its expiry and
+//! audience. The outcome is the report's Authentication section; when the
+//! credential is rejected, scenarios that would send it are skipped.
+
+use chrono::Utc;
+
+use crate::auth::{Credentials, TokenClaims, TokenProblem};
+use crate::config::AuthMethod;
+use crate::services::{
+    apply_timing,
+    classify::{self, request_error},
+    http_error, log_result, measure_time, FailureKind, RunEvent, ServiceTestResults, TestContext,
+    TestResult,
+};
+
+/// Name of the report section
+pub const SECTION_NAME: &str = "Authentication";
+/// Service name in capture files and logs
+const SERVICE_ID: &str = "auth";
+
+/// API key exchanged at the token service
+pub const KEY_SCENARIO: &str = "key_validation";
+/// Bearer token claims checked locally
+pub const TOKEN_SCENARIO: &str = "token_validation";
+
+/// Validate the run's credential and report it as the Authentication section.
+/// `scope` is a token scope requested with `--scope`; the token's audience
+/// isn't checked against Azure AI services then.
+pub async fn validate(
+    context: &TestContext,
+    method: AuthMethod,
+    scope: Option<&str>,
+) -> ServiceTestResults {
+    let (scenario_id, scenario_name, endpoint) = match &context.credentials {
+        Credentials::ApiKey(_) => (
+            KEY_SCENARIO,
+            "API Key",
+            context
+                .cloud
+                .cognitive_token_endpoint_for(&context.region, context.endpoint.as_deref()),
+        ),
+        Credentials::BearerToken(_) => (
+            TOKEN_SCENARIO,
+            "Bearer Token",
+            format!("token claims, {}", method),
+        ),
+    };
+    context.emit(RunEvent::ServiceStarted {
+        service: SECTION_NAME.to_string(),
+        endpoint: endpoint.clone(),
+        scenarios: vec![(scenario_id.to_string(), scenario_name.to_string())],
+    });
+    context.emit(RunEvent::ScenarioStarted {
+        service: SECTION_NAME.to_string(),
+        scenario_id: scenario_id.to_string(),
+    });
+    if let Some(capture) = &context.capture {
+        capture.set_scenario(SERVICE_ID, scenario_id);
+    }
+
+    let result = match &context.credentials {
+        Credentials::ApiKey(_) => check_key(context, scenario_name, &endpoint).await,
+        Credentials::BearerToken(token) => {
+            check_token(context, scenario_name, token, method, scope)
+        }
+    };
+    log_result(SERVICE_ID, &result);
+    context.emit(RunEvent::ScenarioFinished {
+        service: SECTION_NAME.to_string(),
+        result: Box::new(result.clone()),
+    });
+
+    ServiceTestResults {
+        service_name: SECTION_NAME.to_string(),
+        endpoint,
+        region: None,
+        target: None,
+        total_duration_ms: result.duration_ms,
+        results: vec![result],
+    }
+}
+
+/// Whether the credential was rejected, so scenarios sending it can't pass
+pub fn is_rejected(section: &ServiceTestResults) -> bool {
+    section.results.iter().any(|result| {
+        matches!(
+            result.failure_kind,
+            Some(FailureKind::AuthKey | FailureKind::AuthToken | FailureKind::LocalAuthDisabled)
+        )
+    })
+}
+
+/// Exchange the key for a token at the token service
+async fn check_key(context: &TestContext, name: &str, token_endpoint: &str) -> TestResult {
+    if let Some(header) = &context.api_key_header {
+        return TestResult::skipped(
+            KEY_SCENARIO,
+            name,
+            format!("the key is sent in {} to a gateway, not validated", header),
+        );
+    }
+
+    let (result, duration_ms) = measure_time(async {
+        let request = context
+            .client
+            .post(token_endpoint)
+            .header("Content-Length", "0");
+        let request = context.credentials.apply_to_request(request);
+        match context.send(request).await {
+            Ok(response) if response.status().is_success() => Ok(()),
+            Ok(response) => {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                Err((status.as_u16(), http_error(status, &body)))
+            }
+            Err(e) => Err((0, request_error("Request failed", &e))),
+        }
+    })
+    .await;
+
+    match result {
+        Ok(()) => TestResult::success(KEY_SCENARIO, name, duration_ms).with_details(format!(
+            "Key accepted by the token service for {}",
+            context.region
+        )),
+        Err((status, error)) => {
+            let mut result = TestResult::failure(KEY_SCENARIO, name, duration_ms, error);
+            if status > 0 {
+                result = result.with_http_status(status);
+            }
+            if let Some(timing) = context.take_timing() {
+                result = apply_timing(result, timing);
+            }
+            let result = classify::classify(result, context, token_endpoint);
+            // Network problems show up in the service results; only a
+            // rejected key is an authentication failure
+            if is_auth_failure(&result) {
+                result
+            } else {
+                TestResult::skipped(
+                    KEY_SCENARIO,
+                    name,
+                    format!(
+                        "token service didn't answer, key not validated ({})",
+                        result.error.unwrap_or_default()
+                    ),
+                )
+            }
+        }
+    }
+}
+
+fn is_auth_failure(result: &TestResult) -> bool {
+    matches!(
+        result.failure_kind,
+        Some(FailureKind::AuthKey | FailureKind::LocalAuthDisabled)
+    )
+}
+
+/// Check a bearer token's expiry and audience from its claims
+fn check_token(
+    context: &TestContext,
+    name: &str,
+    token: &str,
+    method: AuthMethod,
+    scope: Option<&str>,
+) -> TestResult {
+    let source = match method {
+        AuthMethod::ManagedIdentity => "managed identity",
+        AuthMethod::ServicePrincipal => "service principal",
+        AuthMethod::DeviceCode | AuthMethod::Interactive => "signed-in user",
+        _ => "given token",
+    };
+    let claims = match TokenClaims::decode(token) {
+        Ok(claims) => claims,
+        Err(_) => {
+            return TestResult::skipped(
+                TOKEN_SCENARIO,
+                name,
+                "not a JWT, expiry and audience not checked".to_string(),
+            )
+        }
+    };
+    let now = Utc::now();
+    if let Some(problem) = claims
+        .problems(context.cloud, now)
+        .into_iter()
+        .find(|problem| scope.is_none() || !matches!(problem, TokenProblem::WrongAudience(_)))
+    {
+        let mut result = TestResult::failure(
+            TOKEN_SCENARIO,
+            name,
+            0,
+            format!("Token ({}) {}", source, problem),
+        )
+        .with_failure_kind(FailureKind::AuthToken);
+        result.hint = Some(problem.hint(context.cloud));
+        return result;
+    }
+
+    let mut details = format!("Token from {}", source);
+    if let Some(expires_at) = claims.expires_at() {
+        details.push_str(&format!(
+            ", valid for {} more minutes",
+            (expires_at - now).num_minutes()
+        ));
+    }
+    if let Some(tenant) = &claims.tid {
+        details.push_str(&format!(" (tenant {})", tenant));
+    }
+    if let (Some(_), Some(aud)) = (scope, &claims.aud) {
+        details.push_str(&format!(", audience {} as requested", aud));
+    }
+    TestResult::success(TOKEN_SCENARIO, name, 0).with_details(details)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockServer, MOCK_API_KEY};
+    use crate::testing::{TestRunner, TestRunnerConfig};
+    use base64::Engine;
+
+    fn bearer(claims: serde_json::Value) -> TestContext {
+        let engine = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        let token = format!("e30.{}.sig", engine.encode(claims.to_string()));
+        TestContext::new(
+            Credentials::BearerToken(token),
+            crate::config::Cloud::Global,
+            "eastus".to_string(),
+            std::time::Duration::from_secs(5),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_preflight() {
+        let exp = Utc::now().timestamp() + 3600;
+        let valid = bearer(serde_json::json!({
+            "aud": "https://cognitiveservices.azure.com", "exp": exp, "tid": "contoso"
+        }));
+        let section = validate(&valid, AuthMethod::ManagedIdentity, None).await;
+        assert!(section.all_passed(), "{:?}", section);
+        assert!(!is_rejected(&section));
+
+        let arm = bearer(serde_json::json!({"aud": "https://management.azure.com", "exp": exp}));
+        let section = validate(&arm, AuthMethod::Token, None).await;
+        assert!(is_rejected(&section));
+        assert!(section.results[0]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("management.azure.com"));
+        let requested = validate(
+            &arm,
+            AuthMethod::Token,
+            Some("https://management.azure.com/.default"),
+        )
+        .await;
+        assert!(requested.all_passed(), "{:?}", requested);
+
+        let expired =
+            bearer(serde_json::json!({"aud": "https://cognitiveservices.azure.com", "exp": 1}));
+        assert!(is_rejected(
+            &validate(&expired, AuthMethod::Token, None).await
+        ));
+
+        // An API key is exchanged at the token service first
+        let mock = MockServer::start().await.unwrap();
+        let config = TestRunnerConfig::builder()
+            .services(["translator"])
+            .auth_method(AuthMethod::Key)
+            .api_key(MOCK_API_KEY)
+            .region("eastus")
+            .quiet(true)
+            .mock(mock.addr())
+            .build();
+        let report = TestRunner::new(config).run().await.unwrap();
+        assert_eq!(report.services[0].service_name, SECTION_NAME);
+        assert_eq!(report.services[0].results[0].scenario_id, KEY_SCENARIO);
+        assert_eq!(report.summary.failed, 0, "{:?}", report.summary);
+    }
+}
@@ -0,0 +1,198 @@
+//! Named scenario sets (`test --profile smoke|standard|full`)
+//!
+//! A profile picks the scenarios to run for each service, so CI can run a
+//! quick smoke check while incident response runs everything. `smoke` checks
+//! reachability and makes one authenticated call per service, `standard` adds
+//! the core API of each service, and `full` runs every scenario. Profiles can
+//! be added or changed per service in the config file under
+//! `[test_profiles.<name>]`, with the same selectors as `--scenarios`.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::error::{AppError, Result};
+use crate::services::container::{READY_SCENARIO, STATUS_SCENARIO};
+
+/// Reachability plus one authenticated call per service
+pub const SMOKE: &str = "smoke";
+/// The core API of every service
+pub const STANDARD: &str = "standard";
+/// Every scenario
+pub const FULL: &str = "full";
+
+/// Built-in profile names
+pub const BUILTIN: &[&str] = &[SMOKE, STANDARD, FULL];
+
+/// Scenarios per service in the built-in profiles. Services that aren't
+/// listed (such as custom services) run all their scenarios.
+fn builtin_scenarios(profile: &str) -> Option<&'static [(&'static str, &'static [&'static str])]> {
+    match profile {
+        SMOKE => Some(&[
+            ("speech", &["endpoint_check", "voices_list", READY_SCENARIO]),
+            (
+                "translator",
+                &["endpoint_check", "translate", READY_SCENARIO],
+            ),
+            ("language", &["language_detection", READY_SCENARIO]),
+            ("vision", &["analyze_image"]),
+            ("document_intelligence", &["list_models"]),
+            ("foundry", &["endpoint_check", "list_models"]),
+            ("inference", &["model_info"]),
+            ("openai", &["list_models"]),
+        ]),
+        STANDARD => Some(&[
+            (
+                "speech",
+                &[
+                    "endpoint_check",
+                    "voices_list",
+                    "token_exchange",
+                    "stt_short",
+                    "stt_rest",
+                    "tts",
+                    READY_SCENARIO,
+                    STATUS_SCENARIO,
+                ],
+            ),
+            (
+                "translator",
+                &[
+                    "endpoint_check",
+                    "languages",
+                    "detect",
+                    "translate",
+                    READY_SCENARIO,
+                    STATUS_SCENARIO,
+                ],
+            ),
+            (
+                "language",
+                &[
+                    "sentiment",
+                    "language_detection",
+                    "entities",
+                    "key_phrases",
+                    "pii_detection",
+                    READY_SCENARIO,
+                    STATUS_SCENARIO,
+                ],
+            ),
+            ("vision", &["analyze_image", "read_text", "detect_objects"]),
+            ("document_intelligence", &["list_models", "layout", "read"]),
+            (
+                "foundry",
+                &["endpoint_check", "list_models", "model_inference"],
+            ),
+            ("inference", &["model_info", "chat_completions"]),
+            ("openai", &["list_models", "chat_completions"]),
+        ]),
+        FULL => Some(&[]),
+        _ => None,
+    }
+}
+
+/// A resolved profile: the scenario selectors for each service it restricts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    scenarios: HashMap<String, Vec<String>>,
+}
+
+impl Profile {
+    /// Look up a profile by name. `[test_profiles.<name>]` entries in the
+    /// config file replace the built-in scenarios of the services they list,
+    /// or define a new profile.
+    pub fn resolve(
+        name: &str,
+        configured: &BTreeMap<String, BTreeMap<String, Vec<String>>>,
+    ) -> Result<Self> {
+        let builtin = builtin_scenarios(name);
+        let overrides = configured.get(name);
+        if builtin.is_none() && overrides.is_none() {
+            let mut known: Vec<&str> = BUILTIN.to_vec();
+            known.extend(
+                configured
+                    .keys()
+                    .map(String::as_str)
+                    .filter(|name| !BUILTIN.contains(name)),
+            );
+            return Err(AppError::InvalidInput(format!(
+                "Unknown profile '{}' (available: {})",
+                name,
+                known.join(", ")
+            )));
+        }
+
+        let mut scenarios: HashMap<String, Vec<String>> = builtin
+            .unwrap_or_default()
+            .iter()
+            .map(|(service, ids)| {
+                let ids = ids.iter().map(|id| id.to_string()).collect();
+                (service.to_string(), ids)
+            })
+            .collect();
+        for (service, selectors) in overrides.into_iter().flatten() {
+            scenarios.insert(service.clone(), selectors.clone());
+        }
+        Ok(Self {
+            name: name.to_string(),
+            scenarios,
+        })
+    }
+
+    /// Selectors for `service`, or `None` when the profile runs all of its scenarios
+    pub fn scenarios(&self, service: &str) -> Option<&[String]> {
+        self.scenarios.get(service).map(Vec::as_slice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::get_service;
+
+    #[test]
+    fn test_resolve_profiles() {
+        // Every built-in profile names scenarios that exist
+        for name in BUILTIN {
+            for (service, ids) in builtin_scenarios(name).unwrap() {
+                let scenarios = get_service(service).unwrap().list_scenarios();
+                for id in *ids {
+                    assert!(scenarios.iter().any(|s| s.id == *id), "{} {}", service, id);
+                }
+            }
+        }
+
+        let configured: BTreeMap<String, BTreeMap<String, Vec<String>>> = toml::from_str(
+            r#"
+            [smoke]
+            speech = ["endpoint_check"]
+
+            [nightly]
+            vision = ["tag:fast"]
+            "#,
+        )
+        .unwrap();
+        let smoke = Profile::resolve(SMOKE, &configured).unwrap();
+        assert_eq!(smoke.scenarios("speech").unwrap(), ["endpoint_check"]);
+        assert_eq!(smoke.scenarios("openai").unwrap(), ["list_models"]);
+        assert_eq!(smoke.scenarios("custom"), None);
+
+        let nightly = Profile::resolve("nightly", &configured).unwrap();
+        assert_eq!(nightly.scenarios("vision").unwrap(), ["tag:fast"]);
+        assert_eq!(nightly.scenarios("speech"), None);
+        assert_eq!(
+            Profile::resolve(FULL, &configured)
+                .unwrap()
+                .scenarios("speech"),
+            None
+        );
+
+        let err = Profile::resolve("weekly", &configured).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("available: smoke, standard, full, nightly"),
+            "{}",
+            err
+        );
+    }
+}
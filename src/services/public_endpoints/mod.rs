@@ -0,0 +1,217 @@
+//! Check that public endpoints are blocked (`test --assert-blocked`)
+//!
+//! In a zero-trust network the services must be reachable through their
+//! private endpoints only, so a passing run proves half of the policy. This
+//! check connects to every public host the selected services' scenarios call
+//! when no custom endpoint is set (the regional Cognitive Services, Speech,
+//! and Translator hosts) and passes only when the connection fails. Any HTTP
+//! answer means public egress is open, and fails the run. The outcome is the
+//! report's Public Endpoints section; the scenarios themselves run against the
+//! private endpoint as usual.
+
+use std::collections::BTreeMap;
+
+use futures_util::future::join_all;
+
+use crate::config::Cloud;
+use crate::services::routes::{UrlContext, ROUTES};
+use crate::services::{
+    classify::{self, request_error},
+    get_service, log_result, measure_time, FailureKind, RunEvent, ServiceTestResults, TestContext,
+    TestResult,
+};
+
+/// Name of the report section
+pub const SECTION_NAME: &str = "Public Endpoints";
+/// Service name in capture files and logs
+const SERVICE_ID: &str = "public_endpoints";
+
+/// Remediation when a public endpoint answers
+pub fn public_endpoint_hint() -> String {
+    crate::i18n::tr("hint-public-endpoint")
+}
+
+/// Public hosts (as `https://<host>`) the services' scenarios call without a
+/// custom endpoint in `regions`, with the display names of the services using
+/// each. Services without a shared public endpoint (Azure OpenAI, AI Foundry,
+/// model inference) have none.
+pub fn public_hosts(
+    services: &[String],
+    regions: &[String],
+    cloud: Cloud,
+) -> BTreeMap<String, Vec<&'static str>> {
+    let mut hosts: BTreeMap<String, Vec<&'static str>> = BTreeMap::new();
+    for region in regions {
+        for service in services.iter().filter_map(|name| get_service(name)) {
+            let endpoint = service.get_endpoint(region, cloud, None);
+            let context = UrlContext {
+                endpoint: &endpoint,
+                region,
+                cloud,
+                custom_endpoint: false,
+                api_version: None,
+                preview_apis: false,
+            };
+            for route in ROUTES.iter().filter(|r| r.service == service.name()) {
+                // Placeholder endpoints like <resource>.openai.azure.com don't parse
+                let Ok(url) = url::Url::parse(&route.url(&context)) else {
+                    continue;
+                };
+                let Some(host) = url.host_str() else {
+                    continue;
+                };
+                let users = hosts.entry(format!("https://{}", host)).or_default();
+                if !users.contains(&service.display_name()) {
+                    users.push(service.display_name());
+                }
+            }
+        }
+    }
+    hosts
+}
+
+/// Probe the public hosts of `services` in `regions` and report them as the
+/// Public Endpoints section
+pub async fn check(
+    context: &TestContext,
+    services: &[String],
+    regions: &[String],
+) -> ServiceTestResults {
+    let hosts = public_hosts(services, regions, context.cloud);
+    let scenarios: Vec<(String, String)> = hosts
+        .keys()
+        .map(|url| {
+            let host = url.trim_start_matches("https://").to_string();
+            (host.clone(), host)
+        })
+        .collect();
+    context.emit(RunEvent::ServiceStarted {
+        service: SECTION_NAME.to_string(),
+        endpoint: format!("{} public hosts", hosts.len()),
+        scenarios: scenarios.clone(),
+    });
+    if let Some(capture) = &context.capture {
+        capture.set_scenario(SERVICE_ID, "probe");
+    }
+
+    // Blocked hosts often drop packets, so wait for their timeouts together
+    let results = join_all(hosts.iter().map(|(url, users)| async move {
+        let host = url.trim_start_matches("https://");
+        context.emit(RunEvent::ScenarioStarted {
+            service: SECTION_NAME.to_string(),
+            scenario_id: host.to_string(),
+        });
+        let result = probe(context, host, url, users).await;
+        log_result(SERVICE_ID, &result);
+        context.emit(RunEvent::ScenarioFinished {
+            service: SECTION_NAME.to_string(),
+            result: Box::new(result.clone()),
+        });
+        result
+    }))
+    .await;
+
+    ServiceTestResults {
+        service_name: SECTION_NAME.to_string(),
+        endpoint: format!("{} public hosts", hosts.len()),
+        region: None,
+        target: None,
+        total_duration_ms: results.iter().map(|r| r.duration_ms).max().unwrap_or(0),
+        results,
+    }
+}
+
+/// Connect to `url` without credentials: a failed connection passes, any
+/// HTTP response fails
+async fn probe(context: &TestContext, host: &str, url: &str, users: &[&str]) -> TestResult {
+    let used_by = users.join(", ");
+    let (outcome, duration_ms) = measure_time(context.send(context.client.get(url))).await;
+    match outcome {
+        Ok(response) => {
+            let status = response.status();
+            let mut result = TestResult::failure(
+                host,
+                host,
+                duration_ms,
+                format!(
+                    "Public endpoint reachable: HTTP {} (used by {})",
+                    status, used_by
+                ),
+            )
+            .with_http_status(status.as_u16())
+            .with_failure_kind(FailureKind::UnexpectedSuccess);
+            result.hint = Some(public_endpoint_hint());
+            result
+        }
+        Err(e) => {
+            let error = request_error("Request failed", &e);
+            let blocked = classify::classify(
+                TestResult::failure(host, host, duration_ms, error.clone()),
+                context,
+                url,
+            );
+            let kind = blocked.failure_kind.unwrap_or(FailureKind::Other);
+            TestResult::success(host, host, duration_ms).with_details(format!(
+                "Blocked ({}): {}; used by {}",
+                kind, error, used_by
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Credentials;
+    use crate::mock::MockServer;
+    use std::time::Duration;
+
+    fn context() -> TestContext {
+        TestContext::new(
+            Credentials::ApiKey("k".to_string()),
+            Cloud::Global,
+            "eastus".to_string(),
+            Duration::from_secs(5),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_public_hosts() {
+        let services = ["speech", "translator", "vision", "openai"].map(String::from);
+        let hosts = public_hosts(&services, &["eastus".to_string()], Cloud::Global);
+
+        assert_eq!(
+            hosts["https://eastus.api.cognitive.microsoft.com"],
+            vec!["Speech", "Vision"]
+        );
+        assert!(hosts.contains_key("https://eastus.tts.speech.microsoft.com"));
+        assert!(hosts.contains_key("https://api.cognitive.microsofttranslator.com"));
+        assert!(hosts.keys().all(|url| !url.contains("openai.azure.com")));
+    }
+
+    #[tokio::test]
+    async fn test_probe() {
+        // Nothing listens on port 1, as when egress is blocked
+        let result = probe(&context(), "127.0.0.1", "http://127.0.0.1:1", &["Speech"]).await;
+        assert!(result.success, "{:?}", result);
+        assert!(result.details.unwrap().starts_with("Blocked (TCP)"));
+
+        let server = MockServer::start().await.unwrap();
+        let context = context().with_mock(Some(server.addr()));
+        let url = "https://eastus.api.cognitive.microsoft.com";
+        let result = probe(
+            &context,
+            "eastus.api.cognitive.microsoft.com",
+            url,
+            &["Speech"],
+        )
+        .await;
+        assert!(!result.success);
+        assert_eq!(result.failure_kind, Some(FailureKind::UnexpectedSuccess));
+        assert_eq!(
+            result.hint.as_deref(),
+            Some(public_endpoint_hint().as_str())
+        );
+    }
+}
@@ -0,0 +1,687 @@
+//! Typed response models for the routes scenarios report on
+//!
+//! Each service's models follow the shape of the API version its scenarios
+//! request by default (see [`api_versions`](crate::services::api_versions)).
+//! Unknown fields are ignored, so additions in newer versions don't break a
+//! scenario; fields a scenario reports on are required, so a response that
+//! lacks them is reported as a shape mismatch rather than a misleading count.
+//!
+//! A 200 that isn't the expected JSON usually means something other than the
+//! service answered: a captive portal or proxy login page, or an API gateway
+//! rewriting the response. [`parse`] and [`from_value`] report that distinctly,
+//! and [`classify`](crate::services::classify) files it under
+//! `FailureKind::UnexpectedResponse`. Binary responses (synthesized audio,
+//! segmented images) are checked the same way by [`audio`] and [`png`]: a
+//! mangled body is reported as tampered with, not as a success by byte count.
+
+use serde::de::DeserializeOwned;
+
+/// Prefix of every shape-mismatch error, recognized by the classifier
+pub const UNEXPECTED_RESPONSE: &str = "Endpoint reachable but response shape unexpected";
+
+const INTERCEPTION: &str = "possible gateway interception or API version mismatch";
+
+/// Parse a successful response body as `T`
+pub fn parse<T: DeserializeOwned>(body: &str) -> Result<T, String> {
+    if body.trim().is_empty() {
+        return Err(format!(
+            "{}: empty body ({})",
+            UNEXPECTED_RESPONSE, INTERCEPTION
+        ));
+    }
+    if let Some(page) = html_page(body) {
+        return Err(format!(
+            "{}: got an HTML page{} instead of JSON (possible captive portal, proxy login \
+             page or gateway interception)",
+            UNEXPECTED_RESPONSE, page
+        ));
+    }
+    serde_json::from_str(body).map_err(|e| mismatch(&e))
+}
+
+/// Convert an already parsed JSON body to `T`
+pub fn from_value<T: DeserializeOwned>(body: serde_json::Value) -> Result<T, String> {
+    if body.is_null() {
+        return Err(format!(
+            "{}: empty body ({})",
+            UNEXPECTED_RESPONSE, INTERCEPTION
+        ));
+    }
+    serde_json::from_value(body).map_err(|e| mismatch(&e))
+}
+
+fn mismatch(error: &serde_json::Error) -> String {
+    let what = if error.is_syntax() || error.is_eof() {
+        "body is not valid JSON"
+    } else {
+        "JSON does not match the documented response"
+    };
+    format!(
+        "{}: {} ({}; {})",
+        UNEXPECTED_RESPONSE, what, error, INTERCEPTION
+    )
+}
+
+/// `" ('<title>')"` for an HTML body, `""` for an untitled one, `None` if
+/// the body isn't HTML
+pub fn html_page(body: &str) -> Option<String> {
+    let lower = body.trim_start().to_lowercase();
+    if !(lower.starts_with("<!doctype html") || lower.starts_with("<html")) {
+        return None;
+    }
+    let title = lower
+        .find("<title>")
+        .map(|start| start + "<title>".len())
+        .and_then(|start| {
+            let end = lower[start..].find("</title>")? + start;
+            // Offsets of the lowercased copy match the original for ASCII tags
+            body.trim_start().get(start..end)
+        })
+        .map(str::trim)
+        .filter(|t| !t.is_empty());
+    Some(match title {
+        Some(title) => format!(" ('{}')", title.chars().take(80).collect::<String>()),
+        None => String::new(),
+    })
+}
+
+const TAMPERED: &str = "response tampered with or intercepted in transit";
+
+/// Audio shorter than this can't hold a synthesized sentence
+const MIN_AUDIO_SECS: f64 = 0.3;
+
+/// MP3 bitrates (kbit/s) by bitrate index: MPEG-1 and MPEG-2/2.5 Layer III
+const MP3_BITRATES: [[u32; 15]; 2] = [
+    [
+        0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+    ],
+    [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+];
+
+/// What a body that isn't the expected binary format looks like
+fn describe_body(bytes: &[u8]) -> String {
+    let text = String::from_utf8_lossy(&bytes[..bytes.len().min(512)]);
+    if bytes.is_empty() {
+        "an empty body".to_string()
+    } else if let Some(page) = html_page(&text) {
+        format!("an HTML page{}", page)
+    } else if text.trim_start().starts_with(['{', '[']) {
+        "JSON".to_string()
+    } else {
+        let head: Vec<String> = bytes.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+        format!("{} bytes starting {}", bytes.len(), head.join(" "))
+    }
+}
+
+/// Duration in seconds of a RIFF/WAVE file, from its byte rate and the
+/// audio that follows the `data` chunk header
+fn wav_seconds(bytes: &[u8]) -> Option<f64> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return None;
+    }
+    let le32 = |at: usize| Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?));
+    let mut byte_rate = None;
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let size = le32(offset + 4)? as usize;
+        match &bytes[offset..offset + 4] {
+            b"fmt " => byte_rate = le32(offset + 16).filter(|&rate| rate > 0),
+            // Streamed WAV leaves the data size unset; count what arrived
+            b"data" => return Some((bytes.len() - offset - 8) as f64 / f64::from(byte_rate?)),
+            _ => {}
+        }
+        offset = offset.checked_add(8 + size)?;
+    }
+    None
+}
+
+/// Duration in seconds of a constant-bitrate MP3, from the bitrate of its
+/// first frame after any ID3v2 tag
+fn mp3_seconds(bytes: &[u8]) -> Option<f64> {
+    let mut offset = 0;
+    if bytes.starts_with(b"ID3") && bytes.len() >= 10 {
+        // Syncsafe integer: 7 bits per byte
+        let size = bytes[6..10]
+            .iter()
+            .fold(0usize, |n, b| (n << 7) | usize::from(b & 0x7f));
+        offset = 10 + size;
+    }
+    let header = bytes.get(offset..offset + 4)?;
+    let layer_iii = (header[1] >> 1) & 0b11 == 0b01;
+    if header[0] != 0xff || header[1] & 0xe0 != 0xe0 || !layer_iii {
+        return None;
+    }
+    let table = if (header[1] >> 3) & 0b11 == 0b11 {
+        0
+    } else {
+        1
+    };
+    let kbps = *MP3_BITRATES[table].get(usize::from(header[2] >> 4))?;
+    if kbps == 0 {
+        return None;
+    }
+    Some((bytes.len() - offset) as f64 * 8.0 / f64::from(kbps * 1000))
+}
+
+/// Check synthesized speech: a WAV or MP3 file (by its magic bytes) long
+/// enough to hold a sentence; describes its format and duration
+pub fn audio(bytes: &[u8]) -> Result<String, String> {
+    let (format, seconds) = match (wav_seconds(bytes), mp3_seconds(bytes)) {
+        (Some(seconds), _) => ("WAV", seconds),
+        (None, Some(seconds)) => ("MP3", seconds),
+        (None, None) => {
+            return Err(format!(
+                "{}: synthesized audio is not MP3 or WAV, got {} ({})",
+                UNEXPECTED_RESPONSE,
+                describe_body(bytes),
+                TAMPERED
+            ))
+        }
+    };
+    if seconds < MIN_AUDIO_SECS {
+        return Err(format!(
+            "{}: {} audio of {:.2} s ({} bytes) is too short for the synthesized text ({})",
+            UNEXPECTED_RESPONSE,
+            format,
+            seconds,
+            bytes.len(),
+            TAMPERED
+        ));
+    }
+    Ok(format!("{}, {:.1} s", format, seconds))
+}
+
+/// Check that an image result is a PNG
+pub fn png(bytes: &[u8]) -> Result<(), String> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Ok(());
+    }
+    Err(format!(
+        "{}: expected a PNG image, got {} ({})",
+        UNEXPECTED_RESPONSE,
+        describe_body(bytes),
+        TAMPERED
+    ))
+}
+
+/// Check the `modelVersion` a Vision response reports: a dated version
+/// (`2023-10-01`), and the one requested when the request pins it
+pub fn model_version(found: &str, expected: Option<&str>) -> Result<(), String> {
+    let dated = found.len() >= 10
+        && found.bytes().take(10).enumerate().all(|(i, b)| match i {
+            4 | 7 => b == b'-',
+            _ => b.is_ascii_digit(),
+        });
+    if !dated {
+        return Err(format!(
+            "{}: modelVersion '{}' is not a model version ({})",
+            UNEXPECTED_RESPONSE, found, TAMPERED
+        ));
+    }
+    match expected {
+        Some(expected) if found != expected => Err(format!(
+            "{}: modelVersion '{}' instead of the requested '{}' ({})",
+            UNEXPECTED_RESPONSE, found, expected, TAMPERED
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Language analyze-text and conversations (2023-04-01), question answering
+/// (2021-10-01)
+pub mod language {
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    pub struct AnalyzeTextResponse {
+        pub results: AnalyzeTextResults,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct AnalyzeTextResults {
+        pub documents: Vec<Document>,
+        #[serde(default)]
+        pub errors: Vec<DocumentError>,
+    }
+
+    impl AnalyzeTextResults {
+        /// The first document, or why the service returned none
+        pub fn first_document(&self) -> Result<&Document, String> {
+            if let Some(doc) = self.documents.first() {
+                return Ok(doc);
+            }
+            match self.errors.first() {
+                Some(rejected) => Err(format!(
+                    "Document rejected: {}: {}",
+                    rejected.error.code, rejected.error.message
+                )),
+                None => Err("No documents in response".to_string()),
+            }
+        }
+    }
+
+    /// A document result; which fields are present depends on the task kind
+    #[derive(Debug, Clone, Default, Deserialize)]
+    #[serde(default, rename_all = "camelCase")]
+    pub struct Document {
+        pub sentiment: Option<String>,
+        pub sentences: Vec<Sentence>,
+        pub detected_language: Option<DetectedLanguage>,
+        pub entities: Vec<Entity>,
+        pub key_phrases: Vec<String>,
+        pub relations: Vec<serde::de::IgnoredAny>,
+        pub summaries: Vec<Summary>,
+        pub class: Vec<DocumentClass>,
+    }
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    #[serde(default)]
+    pub struct Sentence {
+        pub targets: Vec<Target>,
+    }
+
+    #[derive(Debug, Clone, Deserialize)]
+    pub struct Target {
+        pub text: String,
+    }
+
+    #[derive(Debug, Clone, Deserialize)]
+    pub struct DetectedLanguage {
+        pub name: String,
+    }
+
+    #[derive(Debug, Clone, Deserialize)]
+    pub struct Entity {
+        pub text: String,
+    }
+
+    #[derive(Debug, Clone, Deserialize)]
+    pub struct Summary {
+        pub text: String,
+    }
+
+    #[derive(Debug, Clone, Deserialize)]
+    pub struct DocumentClass {
+        pub category: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct DocumentError {
+        pub error: ErrorDetail,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct ErrorDetail {
+        pub code: String,
+        pub message: String,
+    }
+
+    /// State of an analyze-text job (`analyze-text/jobs/{id}`)
+    #[derive(Debug, Deserialize)]
+    pub struct AnalyzeJob {
+        pub status: String,
+        #[serde(default)]
+        pub tasks: Option<JobTasks>,
+        #[serde(default)]
+        pub errors: Vec<ErrorDetail>,
+    }
+
+    impl AnalyzeJob {
+        /// Results of the job's first task, once it has completed
+        pub fn first_results(&self) -> Option<&AnalyzeTextResults> {
+            self.tasks.as_ref()?.items.first()?.results.as_ref()
+        }
+    }
+
+    #[derive(Debug, Default, Deserialize)]
+    #[serde(default)]
+    pub struct JobTasks {
+        pub items: Vec<JobTask>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct JobTask {
+        #[serde(default)]
+        pub results: Option<AnalyzeTextResults>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct ConversationResponse {
+        pub result: ConversationResult,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct ConversationResult {
+        pub prediction: Prediction,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Prediction {
+        /// Absent when no intent scored above the project's threshold
+        #[serde(default)]
+        pub top_intent: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct KnowledgeBaseAnswers {
+        pub answers: Vec<Answer>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Answer {
+        #[serde(default)]
+        pub confidence_score: f64,
+    }
+}
+
+/// Translator text API (3.0)
+pub mod translator {
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Deserialize)]
+    pub struct Languages {
+        pub translation: HashMap<String, Language>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Language {
+        pub name: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct DetectResult {
+        pub language: String,
+        pub score: f64,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct TranslateResult {
+        pub translations: Vec<Translation>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Translation {
+        pub text: String,
+        pub to: String,
+    }
+
+    /// `{"text": "konnichiwa", "script": "Latn"}`
+    #[derive(Debug, Deserialize)]
+    pub struct TransliterateResult {
+        pub text: String,
+        #[serde(default)]
+        pub script: Option<String>,
+    }
+
+    /// `{"displaySource": "connection", "translations": [{"displayTarget": "conexión"}, ...]}`
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct DictionaryLookupResult {
+        pub display_source: String,
+        pub translations: Vec<DictionaryTranslation>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct DictionaryTranslation {
+        pub display_target: String,
+    }
+
+    /// `{"sentLen": [36, 22]}`
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct BreakSentenceResult {
+        pub sent_len: Vec<u32>,
+    }
+}
+
+/// Image Analysis 4.0 and multimodal embeddings (2024-02-01)
+pub mod vision {
+    use serde::Deserialize;
+
+    /// Only the requested features are present
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ImageAnalysis {
+        /// Present on every analysis, so a body without it is something else
+        pub model_version: String,
+        #[serde(default)]
+        pub tags_result: Option<Values<Tag>>,
+        #[serde(default)]
+        pub objects_result: Option<Values<serde::de::IgnoredAny>>,
+        #[serde(default)]
+        pub read_result: Option<ReadResult>,
+        #[serde(default)]
+        pub smart_crops_result: Option<Values<serde::de::IgnoredAny>>,
+        #[serde(default)]
+        pub people_result: Option<Values<serde::de::IgnoredAny>>,
+    }
+
+    impl ImageAnalysis {
+        /// Text lines found by the read feature, joined with spaces
+        pub fn read_text(&self) -> String {
+            self.read_result
+                .iter()
+                .flat_map(|r| &r.blocks)
+                .flat_map(|b| &b.lines)
+                .map(|l| l.text.as_str())
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
+
+        /// Names of the tags found by the tags feature
+        pub fn tag_names(&self) -> Vec<&str> {
+            self.tags_result
+                .iter()
+                .flat_map(|t| &t.values)
+                .map(|t| t.name.as_str())
+                .collect()
+        }
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Values<T> {
+        pub values: Vec<T>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Tag {
+        pub name: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct ReadResult {
+        pub blocks: Vec<Block>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Block {
+        pub lines: Vec<Line>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Line {
+        pub text: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Vectorization {
+        pub model_version: String,
+        pub vector: Vec<f32>,
+    }
+}
+
+/// Speech REST APIs: voices list and short-audio recognition (v1), fast
+/// transcription (2024-11-15)
+pub mod speech {
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    pub struct Voice {
+        pub name: String,
+        pub short_name: String,
+        pub locale: String,
+    }
+
+    /// Short-audio recognition (`format=simple`)
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    pub struct SimpleRecognition {
+        pub recognition_status: String,
+        /// Absent unless `RecognitionStatus` is `Success`
+        #[serde(default)]
+        pub display_text: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Transcription {
+        pub combined_phrases: Vec<Phrase>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Phrase {
+        pub text: String,
+    }
+}
+
+/// OpenAI-compatible routes shared by Azure OpenAI, Foundry and Model
+/// Inference (2024-10-21)
+pub mod openai {
+    use serde::Deserialize;
+
+    /// `{"object": "list", "data": [...]}`, used for models and assistants
+    #[derive(Debug, Deserialize)]
+    pub struct List {
+        pub data: Vec<serde::de::IgnoredAny>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct ChatCompletion {
+        #[serde(default)]
+        pub model: Option<String>,
+        pub choices: Vec<serde::de::IgnoredAny>,
+    }
+
+    /// Model Inference `/info`
+    #[derive(Debug, Deserialize)]
+    pub struct ModelInfo {
+        pub model_name: String,
+        #[serde(default)]
+        pub model_provider_name: Option<String>,
+    }
+}
+
+/// Document Intelligence model listing (2024-11-30, and v2.1's `modelList`)
+pub mod document_intelligence {
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    pub struct ModelList {
+        #[serde(alias = "modelList")]
+        pub value: Vec<ModelSummary>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ModelSummary {
+        pub model_id: String,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reports_shape_mismatches() {
+        let detect: Vec<translator::DetectResult> =
+            parse(r#"[{"language": "en", "score": 1.0, "isTranslationSupported": true}]"#).unwrap();
+        assert_eq!(detect[0].language, "en");
+
+        let portal = "<!DOCTYPE html><html><head><title>Guest Wi-Fi Login</title></head></html>";
+        let error = parse::<Vec<translator::DetectResult>>(portal).unwrap_err();
+        assert!(error.starts_with(UNEXPECTED_RESPONSE));
+        assert!(error.contains("HTML page ('Guest Wi-Fi Login')"));
+
+        let error = parse::<language::AnalyzeTextResponse>(r#"{"value": []}"#).unwrap_err();
+        assert!(error.starts_with(UNEXPECTED_RESPONSE));
+        assert!(error.contains("missing field `results`"));
+        assert!(parse::<openai::List>("")
+            .unwrap_err()
+            .contains("empty body"));
+        assert!(parse::<openai::List>("not json")
+            .unwrap_err()
+            .contains("not valid JSON"));
+        assert!(from_value::<openai::List>(serde_json::Value::Null)
+            .unwrap_err()
+            .contains("empty body"));
+    }
+
+    #[test]
+    fn test_first_document_reports_rejections() {
+        let results: language::AnalyzeTextResponse = parse(
+            r#"{"results": {"documents": [], "errors": [{"id": "1", "error":
+                {"code": "InvalidArgument", "message": "Invalid language code."}}]}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            results.results.first_document().unwrap_err(),
+            "Document rejected: InvalidArgument: Invalid language code."
+        );
+    }
+
+    #[test]
+    fn test_audio_checks_format_and_duration() {
+        // 16 kHz 16-bit mono WAV: 32000 bytes per second
+        let wav = |data_len: usize| {
+            let mut wav = b"RIFF\0\0\0\0WAVEfmt \x10\0\0\0\x01\0\x01\0".to_vec();
+            wav.extend_from_slice(&16000u32.to_le_bytes());
+            wav.extend_from_slice(&32000u32.to_le_bytes());
+            wav.extend_from_slice(b"\x02\0\x10\0data\xff\xff\xff\xff");
+            wav.resize(wav.len() + data_len, 0);
+            wav
+        };
+        assert_eq!(audio(&wav(48000)).unwrap(), "WAV, 1.5 s");
+        let error = audio(&wav(3200)).unwrap_err();
+        assert!(error.starts_with(UNEXPECTED_RESPONSE));
+        assert!(error.contains("WAV audio of 0.10 s"), "{}", error);
+
+        // ID3 tag, then an MPEG-1 Layer III frame at 128 kbit/s: 16000 bytes per second
+        let mut mp3 = b"ID3\x04\0\0\0\0\0\x05tag..".to_vec();
+        mp3.extend_from_slice(&[0xff, 0xfb, 0x90, 0x64]);
+        mp3.resize(mp3.len() + 31996, 0);
+        assert_eq!(audio(&mp3).unwrap(), "MP3, 2.0 s");
+
+        let blocked = b"<html><head><title>Blocked by DLP</title></head></html>";
+        let error = audio(blocked).unwrap_err();
+        assert!(error.starts_with(UNEXPECTED_RESPONSE));
+        assert!(error.contains("HTML page ('Blocked by DLP')"), "{}", error);
+        assert!(audio(&[0u8; 4000]).unwrap_err().contains("starting 00 00"));
+    }
+
+    #[test]
+    fn test_png_and_model_version() {
+        assert!(png(b"\x89PNG\r\n\x1a\n....").is_ok());
+        assert!(png(br#"{"error": "blocked"}"#)
+            .unwrap_err()
+            .contains("got JSON"));
+
+        assert!(model_version("2023-10-01", None).is_ok());
+        assert!(model_version("2023-04-15", Some("2023-04-15")).is_ok());
+        let error = model_version("2022-04-11", Some("2023-04-15")).unwrap_err();
+        assert!(
+            error.contains("instead of the requested '2023-04-15'"),
+            "{}",
+            error
+        );
+        assert!(model_version("", None)
+            .unwrap_err()
+            .starts_with(UNEXPECTED_RESPONSE));
+    }
+}
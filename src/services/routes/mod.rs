@@ -0,0 +1,704 @@
+//! Data-plane routes each scenario calls (`test --plan`)
+//!
+//! Network teams review the hosts and paths a run will reach before allowing
+//! it in production. Every built-in scenario is registered here with its HTTP
+//! method, URL template and the kind of payload it sends, so a plan can be
+//! printed without running anything.
+//!
+//! URL templates use these placeholders, filled in by [`Route::url`]:
+//! `{endpoint}` (the service endpoint), `{tts}`, `{stt}` and `{voice}` (the
+//! regional Speech hosts), `{portal}` (the Custom Translator portal) and
+//! `{api-version}`. Values that come from scenario settings (`{model}`,
+//! `{deployment}`, `{project}`, ...) stay in the URL as templates.
+
+use crate::config::Cloud;
+use crate::services::api_versions::{self, Api};
+use crate::services::container;
+use crate::services::endpoints::{CloudDomains, SpeechHost};
+use serde::Serialize;
+use std::fmt;
+
+/// What a scenario sends in the request body
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Payload {
+    /// No body
+    None,
+    /// Small JSON document
+    Json,
+    /// WAV audio (sample or --input-audio)
+    Audio,
+    /// Multipart form with WAV audio and a JSON definition
+    MultipartAudio,
+    /// SSML text
+    Ssml,
+    /// Image (sample or --input-image)
+    Image,
+    /// PDF document (sample or --input-document)
+    Document,
+    /// WAV audio streamed as WebSocket frames
+    #[serde(rename = "websocket_audio")]
+    WebSocketAudio,
+    /// Body from a custom probe's `body` or `body_file`
+    Custom,
+}
+
+impl fmt::Display for Payload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Payload::None => "no body",
+            Payload::Json => "JSON",
+            Payload::Audio => "WAV audio",
+            Payload::MultipartAudio => "multipart (WAV audio, JSON)",
+            Payload::Ssml => "SSML",
+            Payload::Image => "image",
+            Payload::Document => "PDF document",
+            Payload::WebSocketAudio => "WAV audio frames",
+            Payload::Custom => "configured body",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+/// The request a scenario sends
+#[derive(Debug, Clone, Copy)]
+pub struct Route {
+    /// Service name (e.g., "vision"); `*` for routes every service shares
+    pub service: &'static str,
+    /// Scenario ID within the service
+    pub scenario: &'static str,
+    /// HTTP method, or `WebSocket` for an upgrade
+    pub method: &'static str,
+    /// URL template
+    pub url: &'static str,
+    /// URL template used instead with a custom subdomain endpoint
+    pub custom_url: Option<&'static str>,
+    /// API whose version fills `{api-version}`
+    pub api: Option<Api>,
+    pub payload: Payload,
+}
+
+const fn route(
+    service: &'static str,
+    scenario: &'static str,
+    method: &'static str,
+    url: &'static str,
+    api: Option<Api>,
+    payload: Payload,
+) -> Route {
+    Route {
+        service,
+        scenario,
+        method,
+        url,
+        custom_url: None,
+        api,
+        payload,
+    }
+}
+
+impl Route {
+    const fn custom(mut self, url: &'static str) -> Self {
+        self.custom_url = Some(url);
+        self
+    }
+}
+
+/// Settings a URL template is rendered with
+#[derive(Debug, Clone, Copy)]
+pub struct UrlContext<'a> {
+    /// Service endpoint (`AzureService::get_endpoint`)
+    pub endpoint: &'a str,
+    pub region: &'a str,
+    pub cloud: Cloud,
+    /// Whether a custom subdomain endpoint is set
+    pub custom_endpoint: bool,
+    /// The service's `api_version` setting
+    pub api_version: Option<&'a str>,
+    pub preview_apis: bool,
+}
+
+impl Route {
+    /// URL the scenario calls, with settings-dependent parts left templated
+    pub fn url(&self, context: &UrlContext) -> String {
+        let template = match self.custom_url {
+            Some(custom) if context.custom_endpoint => custom,
+            _ => self.url,
+        };
+        let mut url = template
+            .replace("{endpoint}", context.endpoint.trim_end_matches('/'))
+            .replace("{tts}", &SpeechHost::Tts.url(context.region, context.cloud))
+            .replace("{stt}", &SpeechHost::Stt.url(context.region, context.cloud))
+            .replace(
+                "{voice}",
+                &SpeechHost::Voice.url(context.region, context.cloud),
+            )
+            .replace(
+                "{portal}",
+                &format!(
+                    "https://{}",
+                    CloudDomains::of(context.cloud).custom_translator_portal
+                ),
+            );
+        if let Some(api) = self.api {
+            url = url.replace(
+                "{api-version}",
+                api.resolve(context.api_version, context.preview_apis),
+            );
+        }
+        if self.method == "WebSocket" {
+            url = url
+                .replacen("https://", "wss://", 1)
+                .replacen("http://", "ws://", 1);
+        }
+        url
+    }
+}
+
+/// Every built-in scenario's route
+pub const ROUTES: &[Route] = &[
+    // Speech
+    route("speech", "endpoint_check", "GET", "{endpoint}", None, Payload::None),
+    route(
+        "speech",
+        "voices_list",
+        "GET",
+        "{tts}/cognitiveservices/voices/list",
+        None,
+        Payload::None,
+    ),
+    route(
+        "speech",
+        "token_exchange",
+        "POST",
+        "{endpoint}/sts/v1.0/issueToken",
+        None,
+        Payload::None,
+    ),
+    route(
+        "speech",
+        "stt_short",
+        "POST",
+        "{endpoint}/speechtotext/transcriptions:transcribe?api-version={api-version}",
+        Some(api_versions::SPEECH_TRANSCRIPTION),
+        Payload::MultipartAudio,
+    ),
+    route(
+        "speech",
+        "stt_rest",
+        "POST",
+        "{stt}/speech/recognition/conversation/cognitiveservices/v1?language=en-US&format=simple",
+        None,
+        Payload::Audio,
+    )
+    .custom("{endpoint}/speechtotext/speech/recognition/conversation/cognitiveservices/v1?language=en-US&format=simple"),
+    route(
+        "speech",
+        "tts",
+        "POST",
+        "{tts}/cognitiveservices/v1",
+        None,
+        Payload::Ssml,
+    )
+    .custom("{endpoint}/texttospeech/cognitiveservices/v1"),
+    route(
+        "speech",
+        "tts_streaming",
+        "POST",
+        "{tts}/cognitiveservices/v1",
+        None,
+        Payload::Ssml,
+    )
+    .custom("{endpoint}/texttospeech/cognitiveservices/v1"),
+    route(
+        "speech",
+        "custom_voice",
+        "POST",
+        "{voice}/cognitiveservices/v1?deploymentId={deployment}",
+        None,
+        Payload::Ssml,
+    ),
+    route(
+        "speech",
+        "avatar_batch",
+        "GET",
+        "{endpoint}/avatar/batchsyntheses?api-version={api-version}&maxpagesize=1",
+        Some(api_versions::SPEECH_AVATAR),
+        Payload::None,
+    ),
+    route(
+        "speech",
+        "avatar_relay",
+        "GET",
+        "{tts}/cognitiveservices/avatar/relay/token/v1",
+        None,
+        Payload::None,
+    ),
+    route(
+        "speech",
+        "video_translation",
+        "GET",
+        "{endpoint}/videotranslation/translations?api-version={api-version}&maxpagesize=1",
+        Some(api_versions::SPEECH_VIDEO_TRANSLATION),
+        Payload::None,
+    ),
+    route(
+        "speech",
+        "sdk_connect",
+        "WebSocket",
+        "{stt}/speech/recognition/conversation/cognitiveservices/v1?language=en-US&format=simple",
+        None,
+        Payload::WebSocketAudio,
+    )
+    .custom("{endpoint}/stt/speech/recognition/conversation/cognitiveservices/v1?language=en-US&format=simple"),
+    // Translator
+    route("translator", "endpoint_check", "GET", "{endpoint}", None, Payload::None),
+    route(
+        "translator",
+        "languages",
+        "GET",
+        "{endpoint}/languages?api-version={api-version}",
+        Some(api_versions::TRANSLATOR),
+        Payload::None,
+    ),
+    route(
+        "translator",
+        "detect",
+        "POST",
+        "{endpoint}/detect?api-version={api-version}",
+        Some(api_versions::TRANSLATOR),
+        Payload::Json,
+    ),
+    route(
+        "translator",
+        "translate",
+        "POST",
+        "{endpoint}/translate?api-version={api-version}&to=es",
+        Some(api_versions::TRANSLATOR),
+        Payload::Json,
+    ),
+    route(
+        "translator",
+        "translate_category",
+        "POST",
+        "{endpoint}/translate?api-version={api-version}&to=es&category={category}",
+        Some(api_versions::TRANSLATOR),
+        Payload::Json,
+    ),
+    route(
+        "translator",
+        "transliterate",
+        "POST",
+        "{endpoint}/transliterate?api-version={api-version}&language=ja&fromScript=Jpan&toScript=Latn",
+        Some(api_versions::TRANSLATOR),
+        Payload::Json,
+    ),
+    route(
+        "translator",
+        "dictionary_lookup",
+        "POST",
+        "{endpoint}/dictionary/lookup?api-version={api-version}&from=en&to=es",
+        Some(api_versions::TRANSLATOR),
+        Payload::Json,
+    ),
+    route(
+        "translator",
+        "break_sentence",
+        "POST",
+        "{endpoint}/breaksentence?api-version={api-version}",
+        Some(api_versions::TRANSLATOR),
+        Payload::Json,
+    ),
+    route(
+        "translator",
+        "custom_translator_portal",
+        "GET",
+        "{portal}",
+        None,
+        Payload::None,
+    ),
+    // Language
+    route(
+        "language",
+        "sentiment",
+        "POST",
+        "{endpoint}/language/:analyze-text?api-version={api-version}",
+        Some(api_versions::LANGUAGE),
+        Payload::Json,
+    ),
+    route(
+        "language",
+        "sentiment_opinion_mining",
+        "POST",
+        "{endpoint}/language/:analyze-text?api-version={api-version}",
+        Some(api_versions::LANGUAGE),
+        Payload::Json,
+    ),
+    route(
+        "language",
+        "language_detection",
+        "POST",
+        "{endpoint}/language/:analyze-text?api-version={api-version}",
+        Some(api_versions::LANGUAGE),
+        Payload::Json,
+    ),
+    route(
+        "language",
+        "entities",
+        "POST",
+        "{endpoint}/language/:analyze-text?api-version={api-version}",
+        Some(api_versions::LANGUAGE),
+        Payload::Json,
+    ),
+    route(
+        "language",
+        "key_phrases",
+        "POST",
+        "{endpoint}/language/:analyze-text?api-version={api-version}",
+        Some(api_versions::LANGUAGE),
+        Payload::Json,
+    ),
+    route(
+        "language",
+        "pii_detection",
+        "POST",
+        "{endpoint}/language/:analyze-text?api-version={api-version}",
+        Some(api_versions::LANGUAGE),
+        Payload::Json,
+    ),
+    route(
+        "language",
+        "entity_linking",
+        "POST",
+        "{endpoint}/language/:analyze-text?api-version={api-version}",
+        Some(api_versions::LANGUAGE),
+        Payload::Json,
+    ),
+    route(
+        "language",
+        "summarization",
+        "POST",
+        "{endpoint}/language/analyze-text/jobs?api-version={api-version}",
+        Some(api_versions::LANGUAGE),
+        Payload::Json,
+    ),
+    route(
+        "language",
+        "extractive_summarization",
+        "POST",
+        "{endpoint}/language/analyze-text/jobs?api-version={api-version}",
+        Some(api_versions::LANGUAGE),
+        Payload::Json,
+    ),
+    route(
+        "language",
+        "clu",
+        "POST",
+        "{endpoint}/language/:analyze-conversations?api-version={api-version}",
+        Some(api_versions::LANGUAGE),
+        Payload::Json,
+    ),
+    route(
+        "language",
+        "custom_ner",
+        "POST",
+        "{endpoint}/language/analyze-text/jobs?api-version={api-version}",
+        Some(api_versions::LANGUAGE),
+        Payload::Json,
+    ),
+    route(
+        "language",
+        "question_answering",
+        "POST",
+        "{endpoint}/language/:query-knowledgebases?projectName={project}&deploymentName={deployment}&api-version={api-version}",
+        Some(api_versions::QUESTION_ANSWERING),
+        Payload::Json,
+    ),
+    route(
+        "language",
+        "healthcare",
+        "POST",
+        "{endpoint}/language/analyze-text/jobs?api-version={api-version}",
+        Some(api_versions::LANGUAGE),
+        Payload::Json,
+    ),
+    route(
+        "language",
+        "custom_classification",
+        "POST",
+        "{endpoint}/language/analyze-text/jobs?api-version={api-version}",
+        Some(api_versions::LANGUAGE),
+        Payload::Json,
+    ),
+    // Vision
+    route(
+        "vision",
+        "analyze_image",
+        "POST",
+        "{endpoint}/computervision/imageanalysis:analyze?api-version={api-version}&features=tags,objects,read",
+        Some(api_versions::IMAGE_ANALYSIS),
+        Payload::Image,
+    ),
+    route(
+        "vision",
+        "read_text",
+        "POST",
+        "{endpoint}/computervision/imageanalysis:analyze?api-version={api-version}&features=read",
+        Some(api_versions::IMAGE_ANALYSIS),
+        Payload::Image,
+    ),
+    route(
+        "vision",
+        "read_v32",
+        "POST",
+        "{endpoint}/vision/v3.2/read/analyze",
+        None,
+        Payload::Image,
+    ),
+    route(
+        "vision",
+        "detect_objects",
+        "POST",
+        "{endpoint}/computervision/imageanalysis:analyze?api-version={api-version}&features=objects",
+        Some(api_versions::IMAGE_ANALYSIS),
+        Payload::Image,
+    ),
+    route(
+        "vision",
+        "smart_crops",
+        "POST",
+        "{endpoint}/computervision/imageanalysis:analyze?api-version={api-version}&features=smartCrops&smartCrops-aspect-ratios=1.0,1.5",
+        Some(api_versions::IMAGE_ANALYSIS),
+        Payload::Image,
+    ),
+    route(
+        "vision",
+        "people_detection",
+        "POST",
+        "{endpoint}/computervision/imageanalysis:analyze?api-version={api-version}&features=people",
+        Some(api_versions::IMAGE_ANALYSIS),
+        Payload::Image,
+    ),
+    route(
+        "vision",
+        "vectorize_image",
+        "POST",
+        "{endpoint}/computervision/retrieval:vectorizeImage?api-version={api-version}&model-version=2023-04-15",
+        Some(api_versions::IMAGE_RETRIEVAL),
+        Payload::Image,
+    ),
+    route(
+        "vision",
+        "vectorize_text",
+        "POST",
+        "{endpoint}/computervision/retrieval:vectorizeText?api-version={api-version}&model-version=2023-04-15",
+        Some(api_versions::IMAGE_RETRIEVAL),
+        Payload::Json,
+    ),
+    route(
+        "vision",
+        "background_removal",
+        "POST",
+        "{endpoint}/computervision/imageanalysis:segment?api-version={api-version}&mode=backgroundRemoval",
+        Some(api_versions::IMAGE_SEGMENTATION),
+        Payload::Image,
+    ),
+    // Document Intelligence
+    route(
+        "document_intelligence",
+        "layout",
+        "POST",
+        "{endpoint}/documentintelligence/documentModels/prebuilt-layout:analyze?api-version={api-version}",
+        Some(api_versions::DOCUMENT_INTELLIGENCE),
+        Payload::Document,
+    ),
+    route(
+        "document_intelligence",
+        "read",
+        "POST",
+        "{endpoint}/documentintelligence/documentModels/prebuilt-read:analyze?api-version={api-version}",
+        Some(api_versions::DOCUMENT_INTELLIGENCE),
+        Payload::Document,
+    ),
+    route(
+        "document_intelligence",
+        "custom_model",
+        "POST",
+        "{endpoint}/documentintelligence/documentModels/{model}:analyze?api-version={api-version}",
+        Some(api_versions::DOCUMENT_INTELLIGENCE),
+        Payload::Document,
+    ),
+    route(
+        "document_intelligence",
+        "list_models",
+        "GET",
+        "{endpoint}/documentintelligence/documentModels?api-version={api-version}",
+        Some(api_versions::DOCUMENT_INTELLIGENCE),
+        Payload::None,
+    ),
+    // AI Foundry
+    route("ai_foundry", "endpoint_check", "GET", "{endpoint}", None, Payload::None),
+    route(
+        "ai_foundry",
+        "list_models",
+        "GET",
+        "{endpoint}/openai/v1/models",
+        None,
+        Payload::None,
+    ),
+    route(
+        "ai_foundry",
+        "model_inference",
+        "POST",
+        "{endpoint}/models/chat/completions?api-version={api-version}",
+        Some(api_versions::FOUNDRY_INFERENCE),
+        Payload::Json,
+    ),
+    route(
+        "ai_foundry",
+        "agents_list",
+        "GET",
+        "{endpoint}/api/projects/{project}/assistants?api-version={api-version}&limit=1",
+        Some(api_versions::FOUNDRY_AGENTS),
+        Payload::None,
+    ),
+    // Model inference
+    route(
+        "model_inference",
+        "model_info",
+        "GET",
+        "{endpoint}/info?api-version={api-version}",
+        Some(api_versions::MODEL_INFERENCE),
+        Payload::None,
+    ),
+    route(
+        "model_inference",
+        "chat_completions",
+        "POST",
+        "{endpoint}/chat/completions?api-version={api-version}",
+        Some(api_versions::MODEL_INFERENCE),
+        Payload::Json,
+    ),
+    // Azure OpenAI
+    route(
+        "openai",
+        "list_models",
+        "GET",
+        "{endpoint}/openai/models?api-version={api-version}",
+        Some(api_versions::OPENAI),
+        Payload::None,
+    ),
+    route(
+        "openai",
+        "chat_completions",
+        "POST",
+        "{endpoint}/openai/deployments/{deployment}/chat/completions?api-version={api-version}",
+        Some(api_versions::OPENAI),
+        Payload::Json,
+    ),
+    route(
+        "openai",
+        "assistants_list",
+        "GET",
+        "{endpoint}/openai/assistants?api-version={api-version}&limit=1",
+        Some(api_versions::OPENAI_ASSISTANTS),
+        Payload::None,
+    ),
+    route(
+        "openai",
+        "realtime_ws",
+        "WebSocket",
+        "{endpoint}/openai/realtime?api-version={api-version}&deployment={deployment}",
+        Some(api_versions::OPENAI_REALTIME),
+        Payload::None,
+    ),
+    // Container health checks, for every service with a container
+    route(
+        "*",
+        container::READY_SCENARIO,
+        "GET",
+        "{endpoint}/ready",
+        None,
+        Payload::None,
+    ),
+    route(
+        "*",
+        container::STATUS_SCENARIO,
+        "GET",
+        "{endpoint}/status",
+        None,
+        Payload::None,
+    ),
+];
+
+/// Route of a built-in scenario
+pub fn lookup(service: &str, scenario: &str) -> Option<&'static Route> {
+    ROUTES
+        .iter()
+        .find(|r| (r.service == service || r.service == "*") && r.scenario == scenario)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(endpoint: &str, custom_endpoint: bool) -> UrlContext<'_> {
+        UrlContext {
+            endpoint,
+            region: "westus2",
+            cloud: Cloud::Global,
+            custom_endpoint,
+            api_version: None,
+            preview_apis: false,
+        }
+    }
+
+    #[test]
+    fn test_every_scenario_has_a_route() {
+        for service in crate::services::get_all_services() {
+            let scenarios = service
+                .list_scenarios()
+                .into_iter()
+                .map(|s| s.id)
+                .chain(service.container_scenarios().iter().copied());
+            for scenario in scenarios {
+                assert!(
+                    lookup(service.name(), scenario).is_some(),
+                    "no route for {}:{}",
+                    service.name(),
+                    scenario
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_url_fills_hosts_and_api_version() {
+        let regional = context("https://westus2.api.cognitive.microsoft.com", false);
+        let tts = lookup("speech", "tts").unwrap();
+        assert_eq!(
+            tts.url(&regional),
+            "https://westus2.tts.speech.microsoft.com/cognitiveservices/v1"
+        );
+        let custom = context("https://contoso.cognitiveservices.azure.com/", true);
+        assert_eq!(
+            tts.url(&custom),
+            "https://contoso.cognitiveservices.azure.com/texttospeech/cognitiveservices/v1"
+        );
+
+        let analyze = lookup("vision", "read_text").unwrap();
+        assert!(analyze.url(&regional).contains("api-version=2024-02-01&"));
+        let pinned = UrlContext {
+            api_version: Some("2023-10-01"),
+            ..regional
+        };
+        assert!(analyze.url(&pinned).contains("api-version=2023-10-01&"));
+
+        let realtime = lookup("openai", "realtime_ws").unwrap();
+        assert_eq!(
+            realtime.url(&context("https://contoso.openai.azure.com", true)),
+            "wss://contoso.openai.azure.com/openai/realtime?api-version=2024-10-01-preview&deployment={deployment}"
+        );
+        assert!(lookup("speech", container::READY_SCENARIO).is_some());
+    }
+}
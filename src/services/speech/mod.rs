@@ -1,10 +1,16 @@
 use async_trait::async_trait;
-use serde::Deserialize;
+use futures_util::{SinkExt, StreamExt};
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message};
 
+use crate::auth::Credentials;
 use crate::config::Cloud;
 use crate::error::sanitize_error;
+use crate::services::endpoints::{ServiceEndpoint, SpeechHost};
+use crate::services::responses::speech::{SimpleRecognition, Transcription, Voice};
 use crate::services::{
-    measure_time, AzureService, InputType, TestContext, TestResult, TestScenario,
+    api_versions, classify::request_error, container, http_error, measure_time, payloads,
+    responses, tags, AzureService, InputType, ScenarioRequest, TestContext, TestResult,
+    TestScenario,
 };
 
 /// Minimal valid WAV file: PCM 16kHz, 16-bit, mono, ~0.1s silence (1600 samples)
@@ -88,31 +94,73 @@ impl SpeechService {
         Self
     }
 
-    /// Get audio data from user input or fall back to embedded minimal WAV
+    /// Root of the container at `--endpoint` when testing one (`--container`)
+    fn container_endpoint(context: &TestContext) -> Option<String> {
+        match (&context.endpoint, context.container) {
+            (Some(endpoint), true) => Some(ServiceEndpoint::container(endpoint).url()),
+            _ => None,
+        }
+    }
+
+    /// Get audio data from user input, the spoken phrase (--realistic-payloads),
+    /// or fall back to embedded minimal WAV
     fn get_audio_data(context: &TestContext) -> (Vec<u8>, String) {
-        if let Some(input) = &context.input {
+        if let Some(input) = context.input(InputType::Audio) {
             (input.data.clone(), input.content_type.clone())
+        } else if let Some(payload) = context.realistic_payload(payloads::SPEECH) {
+            (payload.data.to_vec(), payload.content_type.to_string())
         } else {
             (MINIMAL_WAV.to_vec(), "audio/wav".to_string())
         }
     }
 
-    /// Get the dedicated TTS endpoint for voices list and speech synthesis.
-    /// Uses {region}.tts.speech.microsoft.com (not the generic cognitive services endpoint).
-    fn get_tts_endpoint(region: &str, cloud: Cloud) -> String {
-        match cloud {
-            Cloud::Global => format!("https://{}.tts.speech.microsoft.com", region),
-            Cloud::China => format!("https://{}.tts.speech.azure.cn", region),
-        }
+    /// Get the WebSocket URL the Speech SDK uses for conversation recognition.
+    /// Custom subdomains route through the /stt path prefix.
+    pub(crate) fn get_sdk_websocket_url(
+        region: &str,
+        cloud: Cloud,
+        custom_endpoint: Option<&str>,
+    ) -> String {
+        let base = if let Some(custom) = custom_endpoint {
+            format!("{}/stt", ServiceEndpoint::custom(custom).url())
+        } else {
+            SpeechHost::Stt.url(region, cloud)
+        };
+        let base = base
+            .replacen("https://", "wss://", 1)
+            .replacen("http://", "ws://", 1);
+        format!(
+            "{}/speech/recognition/conversation/cognitiveservices/v1?language=en-US&format=simple",
+            base
+        )
     }
 
-    /// Get the dedicated STT endpoint for speech recognition REST API.
-    /// Uses {region}.stt.speech.microsoft.com (not the generic cognitive services endpoint).
-    fn get_stt_endpoint(region: &str, cloud: Cloud) -> String {
-        match cloud {
-            Cloud::Global => format!("https://{}.stt.speech.microsoft.com", region),
-            Cloud::China => format!("https://{}.stt.speech.azure.cn", region),
-        }
+    /// Build a Speech protocol text frame (headers, blank line, body)
+    fn build_text_message(path: &str, request_id: &str, body: &str) -> String {
+        format!(
+            "Path: {}\r\nX-RequestId: {}\r\nX-Timestamp: {}\r\nContent-Type: application/json\r\n\r\n{}",
+            path,
+            request_id,
+            chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            body
+        )
+    }
+
+    /// Build a Speech protocol binary audio frame: 2-byte big-endian header length,
+    /// the header block, then the raw audio bytes.
+    fn build_audio_message(request_id: &str, audio: &[u8]) -> Vec<u8> {
+        let header = format!(
+            "Path: audio\r\nX-RequestId: {}\r\nX-Timestamp: {}\r\nContent-Type: audio/x-wav\r\n",
+            request_id,
+            chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+        );
+        let header_len = header.len() as u16;
+
+        let mut message = Vec::with_capacity(2 + header.len() + audio.len());
+        message.extend_from_slice(&header_len.to_be_bytes());
+        message.extend_from_slice(header.as_bytes());
+        message.extend_from_slice(audio);
+        message
     }
 }
 
@@ -122,19 +170,120 @@ impl Default for SpeechService {
     }
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "PascalCase")]
-struct Voice {
-    #[serde(rename = "Name")]
-    _name: String,
-    #[serde(rename = "DisplayName")]
-    _display_name: String,
-    #[serde(rename = "LocalName")]
-    _local_name: String,
-    #[serde(rename = "ShortName")]
-    _short_name: String,
-    #[serde(rename = "Locale")]
-    _locale: String,
+/// Shortest transfer for which a late first byte indicates buffering; faster
+/// responses can arrive in one read even when streamed
+const STREAMING_MIN_TOTAL_MS: u64 = 1000;
+
+/// How a streamed TTS response arrived
+#[derive(Debug, Clone, Copy)]
+struct StreamStats {
+    /// Content-Length of the response, absent when streamed
+    content_length: Option<u64>,
+    bytes: usize,
+    chunks: usize,
+    /// Time from sending the request to the first audio bytes
+    first_byte_ms: u64,
+    /// Time from sending the request to the end of the audio
+    total_ms: u64,
+}
+
+/// Whether audio was streamed to the client as it was synthesized, rather
+/// than buffered and delivered at once by a proxy on the path
+fn streaming_verdict(stats: StreamStats) -> Result<String, String> {
+    let timing = format!(
+        "first audio after {} ms, complete after {} ms",
+        stats.first_byte_ms, stats.total_ms
+    );
+    if stats.bytes == 0 {
+        return Err("No audio received".to_string());
+    }
+    if let Some(length) = stats.content_length {
+        return Err(format!(
+            "Response was not streamed: it carried Content-Length {} instead of chunked \
+             transfer encoding, so a proxy may be buffering it ({})",
+            length, timing
+        ));
+    }
+    if stats.total_ms >= STREAMING_MIN_TOTAL_MS && stats.first_byte_ms * 10 >= stats.total_ms * 9 {
+        return Err(format!(
+            "Audio arrived all at once ({}); a proxy on the path appears to buffer the response",
+            timing
+        ));
+    }
+    Ok(format!(
+        "Streamed {} bytes in {} chunks: {}",
+        stats.bytes, stats.chunks, timing
+    ))
+}
+
+/// Details of a list of avatar batch syntheses or video translations
+fn job_list_details(feature: &str, body: &serde_json::Value) -> String {
+    let jobs = body
+        .get("value")
+        .and_then(|v| v.as_array())
+        .map_or(0, |v| v.len());
+    format!("{} reachable ({} jobs listed)", feature, jobs)
+}
+
+/// Relay hosts a real-time avatar's WebRTC session connects to, from the
+/// ICE server URLs of a relay token (`turn:relay.example.com:3478`)
+fn relay_hosts(body: &serde_json::Value) -> Vec<String> {
+    let mut hosts: Vec<String> = body
+        .get("Urls")
+        .and_then(|u| u.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|url| url.as_str())
+        .filter_map(|url| url.split_once(':').map(|(_, rest)| rest))
+        .map(|rest| rest.split('?').next().unwrap_or(rest).to_string())
+        .collect();
+    hosts.sort();
+    hosts.dedup();
+    hosts
+}
+
+/// Whether a 400 from a recognition route is the service rejecting the
+/// placeholder audio rather than the request itself
+/// Error of a speech request. Speech-to-text and neural text-to-speech ship
+/// as separate containers, so a 404 from a container means it serves the
+/// other one.
+fn container_route_error(context: &TestContext, status: reqwest::StatusCode, body: &str) -> String {
+    if context.container && status == reqwest::StatusCode::NOT_FOUND {
+        format!(
+            "HTTP {}: this container doesn't serve the route; speech-to-text and text-to-speech run in separate containers (select one with --scenarios)",
+            status
+        )
+    } else {
+        http_error(status, body)
+    }
+}
+
+fn is_audio_validation_error(body: &str) -> bool {
+    ["audio", "InvalidRequest", "duration", "USP"]
+        .iter()
+        .any(|marker| body.contains(marker))
+}
+
+/// Details of a voices list, checking that the expected voice (matched by
+/// short or full name) is offered
+fn voices_details(voices: &[Voice], expected: Option<&str>) -> Result<String, String> {
+    let Some(expected) = expected else {
+        return Ok(format!("Retrieved {} voices", voices.len()));
+    };
+    match voices.iter().find(|v| {
+        v.short_name.eq_ignore_ascii_case(expected) || v.name.eq_ignore_ascii_case(expected)
+    }) {
+        Some(voice) => Ok(format!(
+            "Retrieved {} voices, including {}",
+            voices.len(),
+            voice.short_name
+        )),
+        None => Err(format!(
+            "Voice '{}' is not among the {} voices offered in this region",
+            expected,
+            voices.len()
+        )),
+    }
 }
 
 #[async_trait]
@@ -148,23 +297,18 @@ impl AzureService for SpeechService {
     }
 
     fn get_endpoint(&self, region: &str, cloud: Cloud, custom_endpoint: Option<&str>) -> String {
-        if let Some(endpoint) = custom_endpoint {
-            return endpoint.trim_end_matches('/').to_string();
-        }
-        match cloud {
-            Cloud::Global => format!("https://{}.api.cognitive.microsoft.com", region),
-            Cloud::China => format!("https://{}.api.cognitive.azure.cn", region),
-        }
+        ServiceEndpoint::resolve(region, cloud, custom_endpoint).url()
     }
 
     fn list_scenarios(&self) -> Vec<TestScenario> {
-        vec![
+        let mut scenarios = vec![
             TestScenario {
                 id: "endpoint_check",
                 name: "Endpoint Reachability",
                 description: "Verify endpoint DNS, TLS, and connectivity",
                 requires_input: false,
                 input_type: None,
+                tags: &[tags::NO_AUTH, tags::FAST],
             },
             TestScenario {
                 id: "voices_list",
@@ -172,6 +316,7 @@ impl AzureService for SpeechService {
                 description: "Retrieve available TTS voices",
                 requires_input: false,
                 input_type: None,
+                tags: &[tags::FAST],
             },
             TestScenario {
                 id: "token_exchange",
@@ -179,6 +324,7 @@ impl AzureService for SpeechService {
                 description: "Exchange API key for short-lived token",
                 requires_input: false,
                 input_type: None,
+                tags: &[tags::FAST],
             },
             TestScenario {
                 id: "stt_short",
@@ -186,6 +332,7 @@ impl AzureService for SpeechService {
                 description: "Transcribe audio using Fast Transcription API",
                 requires_input: false,
                 input_type: Some(InputType::Audio),
+                tags: &[tags::REQUIRES_INPUT],
             },
             TestScenario {
                 id: "stt_rest",
@@ -193,6 +340,7 @@ impl AzureService for SpeechService {
                 description: "Transcribe audio using traditional REST API",
                 requires_input: false,
                 input_type: Some(InputType::Audio),
+                tags: &[tags::REQUIRES_INPUT],
             },
             TestScenario {
                 id: "tts",
@@ -200,8 +348,64 @@ impl AzureService for SpeechService {
                 description: "Synthesize speech from text",
                 requires_input: false,
                 input_type: None,
+                tags: &[],
+            },
+            TestScenario {
+                id: "tts_streaming",
+                name: "Text-to-Speech (Streaming)",
+                description: "Check that synthesized audio streams through the network path",
+                requires_input: false,
+                input_type: None,
+                tags: &[],
+            },
+            TestScenario {
+                id: "custom_voice",
+                name: "Custom Neural Voice",
+                description: "Synthesize speech with a Custom Neural Voice deployment",
+                requires_input: false,
+                input_type: None,
+                tags: &[],
+            },
+            TestScenario {
+                id: "avatar_batch",
+                name: "Avatar Batch Synthesis",
+                description: "List text to speech avatar batch synthesis jobs",
+                requires_input: false,
+                input_type: None,
+                tags: &[tags::FAST],
+            },
+            TestScenario {
+                id: "avatar_relay",
+                name: "Avatar Relay Token",
+                description: "Get the WebRTC relay token used by real-time avatars",
+                requires_input: false,
+                input_type: None,
+                tags: &[tags::FAST],
+            },
+            TestScenario {
+                id: "video_translation",
+                name: "Video Translation",
+                description: "List video translation jobs",
+                requires_input: false,
+                input_type: None,
+                tags: &[tags::FAST],
+            },
+            TestScenario {
+                id: "sdk_connect",
+                name: "Speech SDK Connection (WebSocket)",
+                description:
+                    "Simulate the Speech SDK handshake: token, WSS connect, await turn.start",
+                requires_input: false,
+                input_type: Some(InputType::Audio),
+                tags: &[tags::REQUIRES_INPUT, tags::WEBSOCKET],
             },
-        ]
+        ];
+        scenarios.extend(container::health_scenarios());
+        scenarios
+    }
+
+    fn container_scenarios(&self) -> &'static [&'static str] {
+        &["stt_rest", "tts", "container_ready", "container_status"]
     }
 
     async fn run_scenario(&self, scenario_id: &str, context: &TestContext) -> TestResult {
@@ -229,6 +433,52 @@ impl AzureService for SpeechService {
             "stt_short" => self.test_stt_short(context, &scenario).await,
             "stt_rest" => self.test_stt_rest(context, &scenario).await,
             "tts" => self.test_tts(context, &scenario).await,
+            "tts_streaming" => self.test_tts_streaming(context, &scenario).await,
+            "custom_voice" => self.test_custom_voice(context, &scenario).await,
+            "avatar_batch" => {
+                let url = format!(
+                    "{}/avatar/batchsyntheses?api-version={}&maxpagesize=1",
+                    self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref()),
+                    api_versions::SPEECH_AVATAR.version(context)
+                );
+                self.test_json_route(context, &scenario, &url, |body| {
+                    job_list_details("Avatar batch synthesis", body)
+                })
+                .await
+            }
+            "avatar_relay" => {
+                let url = format!(
+                    "{}/cognitiveservices/avatar/relay/token/v1",
+                    SpeechHost::Tts.url(&context.region, context.cloud)
+                );
+                self.test_json_route(context, &scenario, &url, |body| {
+                    match relay_hosts(body).as_slice() {
+                        [] => "Relay token issued".to_string(),
+                        hosts => format!(
+                            "Relay token issued; WebRTC media relays through {}",
+                            hosts.join(", ")
+                        ),
+                    }
+                })
+                .await
+            }
+            "video_translation" => {
+                let url = format!(
+                    "{}/videotranslation/translations?api-version={}&maxpagesize=1",
+                    self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref()),
+                    api_versions::SPEECH_VIDEO_TRANSLATION.version(context)
+                );
+                self.test_json_route(context, &scenario, &url, |body| {
+                    job_list_details("Video translation", body)
+                })
+                .await
+            }
+            "sdk_connect" => self.test_sdk_connect(context, &scenario).await,
+            container::READY_SCENARIO | container::STATUS_SCENARIO => {
+                let endpoint =
+                    self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
+                container::run_health_check(context, &scenario, &endpoint).await
+            }
             _ => TestResult::failure(
                 scenario_id,
                 scenario.name,
@@ -249,21 +499,14 @@ impl SpeechService {
             self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
 
         let (result, duration_ms) = measure_time(async {
-            match context.client.get(&endpoint).send().await {
+            match context.send(context.client.get(&endpoint)).await {
                 Ok(response) => {
                     let status = response.status();
                     if status.as_u16() < 500 {
                         Ok(format!("Endpoint reachable (HTTP {})", status))
                     } else {
                         let body = response.text().await.unwrap_or_default();
-                        Err((
-                            status.as_u16(),
-                            format!(
-                                "HTTP {}: {}",
-                                status,
-                                sanitize_error(&body, status.as_u16())
-                            ),
-                        ))
+                        Err((status.as_u16(), http_error(status, &body)))
                     }
                 }
                 Err(e) => {
@@ -285,69 +528,19 @@ impl SpeechService {
         })
         .await;
 
-        match result {
-            Ok(details) => {
-                TestResult::success(scenario.id, scenario.name, duration_ms).with_details(details)
-            }
-            Err((status, error)) => {
-                let mut result =
-                    TestResult::failure(scenario.id, scenario.name, duration_ms, error);
-                if status > 0 {
-                    result = result.with_http_status(status);
-                }
-                result
-            }
-        }
+        TestResult::from_outcome(scenario, duration_ms, result)
     }
 
     async fn test_voices_list(&self, context: &TestContext, scenario: &TestScenario) -> TestResult {
-        let endpoint = Self::get_tts_endpoint(&context.region, context.cloud);
+        let endpoint = SpeechHost::Tts.url(&context.region, context.cloud);
         let url = format!("{}/cognitiveservices/voices/list", endpoint);
 
-        let (result, duration_ms) = measure_time(async {
-            let request = context.client.get(&url);
-            let request = context.credentials.apply_to_request(request);
-
-            match request.send().await {
-                Ok(response) => {
-                    let status = response.status();
-                    if status.is_success() {
-                        match response.json::<Vec<Voice>>().await {
-                            Ok(voices) => Ok(format!("Retrieved {} voices", voices.len())),
-                            Err(e) => {
-                                Err((status.as_u16(), format!("Failed to parse response: {}", e)))
-                            }
-                        }
-                    } else {
-                        let body = response.text().await.unwrap_or_default();
-                        Err((
-                            status.as_u16(),
-                            format!(
-                                "HTTP {}: {}",
-                                status,
-                                sanitize_error(&body, status.as_u16())
-                            ),
-                        ))
-                    }
-                }
-                Err(e) => Err((0, format!("Request failed: {}", e))),
-            }
-        })
-        .await;
-
-        match result {
-            Ok(details) => {
-                TestResult::success(scenario.id, scenario.name, duration_ms).with_details(details)
-            }
-            Err((status, error)) => {
-                let mut result =
-                    TestResult::failure(scenario.id, scenario.name, duration_ms, error);
-                if status > 0 {
-                    result = result.with_http_status(status);
-                }
-                result
-            }
-        }
+        ScenarioRequest::get(url)
+            .extract(|voices: Vec<Voice>| {
+                voices_details(&voices, context.service_config.expect_voice.as_deref())
+            })
+            .run(context, scenario)
+            .await
     }
 
     async fn test_token_exchange(
@@ -359,143 +552,90 @@ impl SpeechService {
             .cloud
             .cognitive_token_endpoint_for(&context.region, context.endpoint.as_deref());
 
-        let (result, duration_ms) = measure_time(async {
-            let request = context
-                .client
-                .post(&token_endpoint)
-                .header("Content-Length", "0");
-            let request = context.credentials.apply_to_request(request);
-
-            match request.send().await {
-                Ok(response) => {
-                    let status = response.status();
-                    if status.is_success() {
-                        match response.text().await {
-                            Ok(token) => {
-                                if token.len() > 100 {
-                                    Ok(format!("Token received ({} chars)", token.len()))
-                                } else {
-                                    Err((status.as_u16(), "Invalid token received".to_string()))
-                                }
-                            }
-                            Err(e) => {
-                                Err((status.as_u16(), format!("Failed to read token: {}", e)))
-                            }
-                        }
-                    } else {
-                        let body = response.text().await.unwrap_or_default();
-                        Err((
-                            status.as_u16(),
-                            format!(
-                                "HTTP {}: {}",
-                                status,
-                                sanitize_error(&body, status.as_u16())
-                            ),
-                        ))
-                    }
+        ScenarioRequest::post(token_endpoint)
+            .header("Content-Length", "0")
+            .extract_text(|token| {
+                if token.len() > 100 {
+                    Ok(format!("Token received ({} chars)", token.len()))
+                } else {
+                    Err("Invalid token received".to_string())
                 }
-                Err(e) => Err((0, format!("Request failed: {}", e))),
-            }
-        })
-        .await;
-
-        match result {
-            Ok(details) => {
-                TestResult::success(scenario.id, scenario.name, duration_ms).with_details(details)
-            }
-            Err((status, error)) => {
-                let mut result =
-                    TestResult::failure(scenario.id, scenario.name, duration_ms, error);
-                if status > 0 {
-                    result = result.with_http_status(status);
-                }
-                result
-            }
-        }
+            })
+            .run(context, scenario)
+            .await
     }
 
     async fn test_stt_short(&self, context: &TestContext, scenario: &TestScenario) -> TestResult {
         let (audio_data, content_type) = Self::get_audio_data(context);
+        let expected = context.realistic_payload(payloads::SPEECH);
 
         let endpoint =
             self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
         let url = format!(
-            "{}/speechtotext/transcriptions:transcribe?api-version=2024-11-15",
-            endpoint
+            "{}/speechtotext/transcriptions:transcribe?api-version={}",
+            endpoint,
+            api_versions::SPEECH_TRANSCRIPTION.version(context)
         );
 
-        let (result, duration_ms) = measure_time(async {
-            let form = reqwest::multipart::Form::new()
-                .text("definition", r#"{"locales":["en-US"]}"#)
-                .part(
-                    "audio",
-                    reqwest::multipart::Part::bytes(audio_data)
-                        .file_name("audio.wav")
-                        .mime_str(&content_type)
-                        .unwrap(),
-                );
-
-            let request = context.client.post(&url).multipart(form);
-            let request = context.credentials.apply_to_request(request);
+        let form = reqwest::multipart::Form::new()
+            .text("definition", r#"{"locales":["en-US"]}"#)
+            .part(
+                "audio",
+                reqwest::multipart::Part::bytes(audio_data)
+                    .file_name("audio.wav")
+                    .mime_str(&content_type)
+                    .unwrap(),
+            );
 
-            match request.send().await {
-                Ok(response) => {
-                    let status = response.status();
-                    if status.is_success() {
-                        let body = response.text().await.unwrap_or_default();
-                        Ok(format!("Transcription received: {} chars", body.len()))
-                    } else if status.as_u16() == 400 {
-                        let body = response.text().await.unwrap_or_default();
-                        if body.contains("audio")
-                            || body.contains("InvalidRequest")
-                            || body.contains("duration")
-                        {
-                            Ok(format!(
-                                "Endpoint responsive (audio validation: HTTP {})",
-                                status
-                            ))
-                        } else {
-                            Err((status.as_u16(), format!("HTTP {}: {}", status, body)))
-                        }
-                    } else {
-                        let body = response.text().await.unwrap_or_default();
-                        Err((
-                            status.as_u16(),
-                            format!(
-                                "HTTP {}: {}",
-                                status,
-                                sanitize_error(&body, status.as_u16())
-                            ),
-                        ))
-                    }
+        ScenarioRequest::post(url)
+            .multipart(form)
+            .extract_text(|body| {
+                let transcription: Transcription = responses::parse(&body)?;
+                let text = transcription
+                    .combined_phrases
+                    .first()
+                    .map_or("", |p| p.text.as_str());
+                context.save_artifact(self.name(), scenario.id, "txt", text.as_bytes());
+                match expected {
+                    Some(payload) => payload
+                        .verify(text)
+                        .map(|details| format!("Transcription received: {}", details)),
+                    None => Ok(format!("Transcription received: {} chars", body.len())),
                 }
-                Err(e) => Err((0, format!("Request failed: {}", e))),
-            }
-        })
-        .await;
-
-        match result {
-            Ok(details) => {
-                TestResult::success(scenario.id, scenario.name, duration_ms).with_details(details)
-            }
-            Err((status, error)) => {
-                let mut result =
-                    TestResult::failure(scenario.id, scenario.name, duration_ms, error);
-                if status > 0 {
-                    result = result.with_http_status(status);
+            })
+            .on_error(|status, body| {
+                // Without real audio, a validation error still proves the route works
+                if status == reqwest::StatusCode::BAD_REQUEST
+                    && expected.is_none()
+                    && is_audio_validation_error(body)
+                {
+                    Ok(format!(
+                        "Endpoint responsive (audio validation: HTTP {})",
+                        status
+                    ))
+                } else {
+                    Err(http_error(status, body))
                 }
-                result
-            }
-        }
+            })
+            .run(context, scenario)
+            .await
     }
 
     async fn test_stt_rest(&self, context: &TestContext, scenario: &TestScenario) -> TestResult {
         let (audio_data, content_type) = Self::get_audio_data(context);
+        let expected = context.realistic_payload(payloads::SPEECH);
 
         // Use custom endpoint for bearer token auth, otherwise use dedicated STT endpoint
-        // Custom subdomain uses different API path
-        let (_endpoint, url) = if let Some(custom) = context.endpoint.as_deref() {
-            let ep = custom.trim_end_matches('/').to_string();
+        // Custom subdomain uses different API path; a container serves the
+        // regional path at its root
+        let (_endpoint, url) = if let Some(ep) = Self::container_endpoint(context) {
+            let u = format!(
+                "{}/speech/recognition/conversation/cognitiveservices/v1?language=en-US&format=simple",
+                ep
+            );
+            (ep, u)
+        } else if let Some(custom) = context.endpoint.as_deref() {
+            let ep = ServiceEndpoint::custom(custom).url();
             // Custom subdomain uses the newer speechtotext API
             let u = format!(
                 "{}/speechtotext/speech/recognition/conversation/cognitiveservices/v1?language=en-US&format=simple",
@@ -503,7 +643,7 @@ impl SpeechService {
             );
             (ep, u)
         } else {
-            let ep = Self::get_stt_endpoint(&context.region, context.cloud);
+            let ep = SpeechHost::Stt.url(&context.region, context.cloud);
             let u = format!(
                 "{}/speech/recognition/conversation/cognitiveservices/v1?language=en-US&format=simple",
                 ep
@@ -511,80 +651,132 @@ impl SpeechService {
             (ep, u)
         };
 
-        let (result, duration_ms) = measure_time(async {
-            let request = context
-                .client
-                .post(&url)
-                .header("Content-Type", &content_type)
-                .body(audio_data);
-            let request = context.credentials.apply_to_request(request);
-
-            match request.send().await {
-                Ok(response) => {
-                    let status = response.status();
-                    if status.is_success() {
-                        let body = response.text().await.unwrap_or_default();
-                        Ok(format!("Recognition result: {} chars", body.len()))
-                    } else if status.as_u16() == 400 {
-                        let body = response.text().await.unwrap_or_default();
-                        if body.contains("audio")
-                            || body.contains("InvalidRequest")
-                            || body.contains("duration")
-                            || body.contains("USP")
-                        {
-                            Ok(format!(
-                                "Endpoint responsive (audio validation: HTTP {})",
-                                status
-                            ))
-                        } else {
-                            Err((status.as_u16(), format!("HTTP {}: {}", status, body)))
-                        }
-                    } else {
-                        let body = response.text().await.unwrap_or_default();
-                        Err((
-                            status.as_u16(),
-                            format!(
-                                "HTTP {}: {}",
-                                status,
-                                sanitize_error(&body, status.as_u16())
-                            ),
-                        ))
-                    }
+        ScenarioRequest::post(url)
+            .body(content_type, audio_data)
+            .extract_text(|body| {
+                let recognition: SimpleRecognition = responses::parse(&body)?;
+                let text = recognition.display_text.as_deref().unwrap_or_default();
+                context.save_artifact(self.name(), scenario.id, "txt", text.as_bytes());
+                match expected {
+                    Some(payload) => payload
+                        .verify(text)
+                        .map(|details| format!("Recognition result: {}", details)),
+                    None => Ok(format!("Recognition result: {} chars", body.len())),
                 }
-                Err(e) => Err((0, format!("Request failed: {}", e))),
-            }
-        })
-        .await;
-
-        match result {
-            Ok(details) => {
-                TestResult::success(scenario.id, scenario.name, duration_ms).with_details(details)
-            }
-            Err((status, error)) => {
-                let mut result =
-                    TestResult::failure(scenario.id, scenario.name, duration_ms, error);
-                if status > 0 {
-                    result = result.with_http_status(status);
+            })
+            .on_error(|status, body| {
+                // Without real audio, a validation error still proves the route works
+                if status == reqwest::StatusCode::BAD_REQUEST
+                    && expected.is_none()
+                    && is_audio_validation_error(body)
+                {
+                    Ok(format!(
+                        "Endpoint responsive (audio validation: HTTP {})",
+                        status
+                    ))
+                } else {
+                    Err(container_route_error(context, status, body))
                 }
-                result
-            }
-        }
+            })
+            .run(context, scenario)
+            .await
     }
 
     async fn test_tts(&self, context: &TestContext, scenario: &TestScenario) -> TestResult {
         // Use custom endpoint for bearer token auth, otherwise use dedicated TTS endpoint
         // Custom subdomain uses different API path
+        let url = if let Some(endpoint) = Self::container_endpoint(context) {
+            format!("{}/cognitiveservices/v1", endpoint)
+        } else if let Some(custom) = context.endpoint.as_deref() {
+            format!(
+                "{}/texttospeech/cognitiveservices/v1",
+                ServiceEndpoint::custom(custom).url()
+            )
+        } else {
+            let endpoint = SpeechHost::Tts.url(&context.region, context.cloud);
+            format!("{}/cognitiveservices/v1", endpoint)
+        };
+
+        // A neural TTS container serves only the voice its image ships
+        let voice = match (context.container, &context.service_config.expect_voice) {
+            (true, Some(voice)) => voice.as_str(),
+            _ => "en-US-JennyNeural",
+        };
+        let ssml = format!(
+            "<speak version='1.0' xmlns='http://www.w3.org/2001/10/synthesis' xml:lang='en-US'><voice name='{}'>Hello, this is a connectivity test.</voice></speak>",
+            voice
+        );
+
+        ScenarioRequest::post(url)
+            .body("application/ssml+xml", ssml)
+            .header(
+                "X-Microsoft-OutputFormat",
+                "audio-16khz-128kbitrate-mono-mp3",
+            )
+            .header("User-Agent", "azure-aitoolsconnect/0.1.0")
+            .extract_bytes(|bytes| {
+                context.save_artifact(self.name(), scenario.id, "mp3", &bytes);
+                let audio = responses::audio(&bytes)?;
+                Ok(format!(
+                    "Audio synthesized: {} bytes ({})",
+                    bytes.len(),
+                    audio
+                ))
+            })
+            .on_error(|status, body| Err(container_route_error(context, status, body)))
+            .run(context, scenario)
+            .await
+    }
+}
+
+impl SpeechService {
+    /// GET a JSON route and describe the response with `describe`. Avatar and
+    /// video translation routes are offered in fewer regions than Speech
+    /// itself, so a 404 says so.
+    async fn test_json_route(
+        &self,
+        context: &TestContext,
+        scenario: &TestScenario,
+        url: &str,
+        describe: fn(&serde_json::Value) -> String,
+    ) -> TestResult {
+        ScenarioRequest::get(url)
+            .extract_json(|body| Ok(describe(&body)))
+            .on_error(|status, body| {
+                let mut error = http_error(status, body);
+                if status == reqwest::StatusCode::NOT_FOUND {
+                    error.push_str(&format!(
+                        " ({} may not be offered in region '{}')",
+                        scenario.name, context.region
+                    ));
+                }
+                Err(error)
+            })
+            .run(context, scenario)
+            .await
+    }
+
+    /// The TTS service sends audio as it's synthesized with chunked transfer
+    /// encoding; a proxy that buffers whole responses delivers the same bytes
+    /// but breaks low-latency voice applications, so time to first byte is
+    /// compared with the total
+    async fn test_tts_streaming(
+        &self,
+        context: &TestContext,
+        scenario: &TestScenario,
+    ) -> TestResult {
         let url = if let Some(custom) = context.endpoint.as_deref() {
             format!(
                 "{}/texttospeech/cognitiveservices/v1",
-                custom.trim_end_matches('/')
+                ServiceEndpoint::custom(custom).url()
             )
         } else {
-            let endpoint = Self::get_tts_endpoint(&context.region, context.cloud);
+            let endpoint = SpeechHost::Tts.url(&context.region, context.cloud);
             format!("{}/cognitiveservices/v1", endpoint)
         };
 
-        let ssml = "<speak version='1.0' xmlns='http://www.w3.org/2001/10/synthesis' xml:lang='en-US'><voice name='en-US-JennyNeural'>Hello, this is a connectivity test.</voice></speak>";
+        // Long enough that synthesis takes noticeably longer than the first chunk
+        let ssml = "<speak version='1.0' xmlns='http://www.w3.org/2001/10/synthesis' xml:lang='en-US'><voice name='en-US-JennyNeural'>Hello, this is a connectivity test of streaming speech synthesis. The audio should arrive in pieces while the rest of this text is still being synthesized. If it arrives all at once, something on the network path is holding the response back until it is complete.</voice></speak>";
 
         let (result, duration_ms) = measure_time(async {
             let request = context
@@ -593,48 +785,271 @@ impl SpeechService {
                 .header("Content-Type", "application/ssml+xml")
                 .header(
                     "X-Microsoft-OutputFormat",
-                    "audio-16khz-128kbitrate-mono-mp3",
+                    "audio-24khz-48kbitrate-mono-mp3",
                 )
                 .header("User-Agent", "azure-aitoolsconnect/0.1.0")
                 .body(ssml);
             let request = context.credentials.apply_to_request(request);
 
-            match request.send().await {
-                Ok(response) => {
-                    let status = response.status();
-                    if status.is_success() {
-                        let bytes = response.bytes().await.unwrap_or_default();
-                        Ok(format!("Audio synthesized: {} bytes", bytes.len()))
-                    } else {
-                        let body = response.text().await.unwrap_or_default();
-                        Err((
+            let started = std::time::Instant::now();
+            let mut response = context
+                .send_streamed(request)
+                .await
+                .map_err(|e| (0, request_error("Request failed", &e)))?;
+            let status = response.status();
+            if !status.is_success() {
+                let body = response.text().await.unwrap_or_default();
+                return Err((status.as_u16(), http_error(status, &body)));
+            }
+
+            let content_length = response.content_length();
+            let mut audio = Vec::new();
+            let mut chunks = 0;
+            let mut first_byte_ms = None;
+            loop {
+                match response.chunk().await {
+                    Ok(Some(chunk)) => {
+                        first_byte_ms.get_or_insert(started.elapsed().as_millis() as u64);
+                        chunks += 1;
+                        audio.extend_from_slice(&chunk);
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        return Err((
                             status.as_u16(),
-                            format!(
-                                "HTTP {}: {}",
-                                status,
-                                sanitize_error(&body, status.as_u16())
+                            request_error(
+                                &format!("Stream interrupted after {} bytes", audio.len()),
+                                &e,
                             ),
                         ))
                     }
                 }
-                Err(e) => Err((0, format!("Request failed: {}", e))),
             }
+            let total_ms = started.elapsed().as_millis() as u64;
+            context.save_artifact(self.name(), scenario.id, "mp3", &audio);
+
+            let verdict = streaming_verdict(StreamStats {
+                content_length,
+                bytes: audio.len(),
+                chunks,
+                first_byte_ms: first_byte_ms.unwrap_or(total_ms),
+                total_ms,
+            })
+            .map_err(|e| (0, e))?;
+            responses::audio(&audio)
+                .map(|audio| format!("{} ({})", verdict, audio))
+                .map_err(|e| (status.as_u16(), e))
         })
         .await;
 
-        match result {
-            Ok(details) => {
-                TestResult::success(scenario.id, scenario.name, duration_ms).with_details(details)
+        TestResult::from_outcome(scenario, duration_ms, result)
+    }
+
+    /// Custom Neural Voice deployments are served from their own host and
+    /// selected with a `deploymentId` query parameter, unlike standard TTS
+    async fn test_custom_voice(
+        &self,
+        context: &TestContext,
+        scenario: &TestScenario,
+    ) -> TestResult {
+        let (Some(deployment), Some(voice)) = (
+            context.service_config.custom_voice_deployment.as_deref(),
+            context.service_config.custom_voice.as_deref(),
+        ) else {
+            return TestResult::skipped(
+                scenario.id,
+                scenario.name,
+                "Requires a Custom Neural Voice deployment ID and voice name (--custom-voice-deployment and --custom-voice, or custom_voice_deployment and custom_voice in [services.speech])"
+                    .to_string(),
+            );
+        };
+
+        let endpoint = SpeechHost::Voice.url(&context.region, context.cloud);
+        let url = match url::Url::parse_with_params(
+            &format!("{}/cognitiveservices/v1", endpoint),
+            &[("deploymentId", deployment)],
+        ) {
+            Ok(u) => u,
+            Err(e) => {
+                return TestResult::failure(
+                    scenario.id,
+                    scenario.name,
+                    0,
+                    format!("Invalid deployment ID: {}", e),
+                )
+            }
+        };
+        let ssml = format!(
+            "<speak version='1.0' xmlns='http://www.w3.org/2001/10/synthesis' xml:lang='en-US'><voice name='{}'>Hello, this is a connectivity test.</voice></speak>",
+            voice
+        );
+
+        ScenarioRequest::post(url)
+            .body("application/ssml+xml", ssml)
+            .header(
+                "X-Microsoft-OutputFormat",
+                "audio-16khz-128kbitrate-mono-mp3",
+            )
+            .header("User-Agent", "azure-aitoolsconnect/0.1.0")
+            .extract_bytes(|bytes| {
+                context.save_artifact(self.name(), scenario.id, "mp3", &bytes);
+                let audio = responses::audio(&bytes)?;
+                Ok(format!(
+                    "Audio synthesized with {}: {} bytes ({})",
+                    voice,
+                    bytes.len(),
+                    audio
+                ))
+            })
+            .run(context, scenario)
+            .await
+    }
+
+    async fn test_sdk_connect(&self, context: &TestContext, scenario: &TestScenario) -> TestResult {
+        let (audio_data, _) = Self::get_audio_data(context);
+        let ws_url = Self::get_sdk_websocket_url(
+            &context.region,
+            context.cloud,
+            context.endpoint.as_deref(),
+        );
+        let token_endpoint = context
+            .cloud
+            .cognitive_token_endpoint_for(&context.region, context.endpoint.as_deref());
+
+        let (result, duration_ms) = measure_time(async {
+            // Step 1: the SDK exchanges an API key for a short-lived token before connecting
+            let token_start = std::time::Instant::now();
+            let token = match &context.credentials {
+                Credentials::ApiKey(_) => {
+                    let request = context
+                        .client
+                        .post(&token_endpoint)
+                        .header("Content-Length", "0");
+                    let request = context.credentials.apply_to_request(request);
+                    match context.send(request).await {
+                        Ok(response) if response.status().is_success() => {
+                            response.text().await.unwrap_or_default()
+                        }
+                        Ok(response) => {
+                            let status = response.status();
+                            let body = response.text().await.unwrap_or_default();
+                            return Err((
+                                status.as_u16(),
+                                format!(
+                                    "Token exchange failed: HTTP {}: {}",
+                                    status,
+                                    sanitize_error(&body, status.as_u16())
+                                ),
+                            ));
+                        }
+                        Err(e) => return Err((0, request_error("Token exchange failed", &e))),
+                    }
+                }
+                Credentials::BearerToken(token) => token.clone(),
+            };
+            let token_ms = token_start.elapsed().as_millis();
+
+            // Step 2: open the WebSocket connection
+            let connect_start = std::time::Instant::now();
+            let connection_id = uuid::Uuid::new_v4().simple().to_string().to_uppercase();
+            let mut request = match context.websocket_url(&ws_url).into_client_request() {
+                Ok(r) => r,
+                Err(e) => return Err((0, format!("Invalid WebSocket URL: {}", e))),
+            };
+            let headers = request.headers_mut();
+            if let Ok(value) = format!("Bearer {}", token).parse() {
+                headers.insert("Authorization", value);
+            }
+            if let Ok(value) = connection_id.parse() {
+                headers.insert("X-ConnectionId", value);
             }
-            Err((status, error)) => {
-                let mut result =
-                    TestResult::failure(scenario.id, scenario.name, duration_ms, error);
-                if status > 0 {
-                    result = result.with_http_status(status);
+            for (name, value) in &context.extra_headers {
+                if let (Ok(name), Ok(value)) = (
+                    name.as_str().parse::<tokio_tungstenite::tungstenite::http::HeaderName>(),
+                    value.as_bytes().try_into(),
+                ) {
+                    headers.insert(name, value);
                 }
-                result
             }
-        }
+
+            let connect = tokio::time::timeout(
+                context.timeout,
+                crate::network::websocket::connect(request),
+            )
+            .await;
+            let mut socket = match connect {
+                Ok(Ok((socket, _))) => socket,
+                Ok(Err(tokio_tungstenite::tungstenite::Error::Http(response))) => {
+                    let status = response.status().as_u16();
+                    return Err((
+                        status,
+                        format!("WebSocket upgrade rejected (HTTP {})", status),
+                    ));
+                }
+                Ok(Err(e)) => return Err((0, format!("WebSocket connection failed: {}", e))),
+                Err(_) => return Err((0, "WebSocket connection timed out".to_string())),
+            };
+            let connect_ms = connect_start.elapsed().as_millis();
+
+            // Step 3: send speech.config and the audio header, as the SDK does
+            let request_id = uuid::Uuid::new_v4().simple().to_string().to_uppercase();
+            let speech_config = serde_json::json!({
+                "context": {
+                    "system": {"name": "SpeechSDK", "version": "1.0.0", "build": "azure-aitoolsconnect"},
+                    "os": {"platform": std::env::consts::OS, "name": std::env::consts::OS, "version": ""}
+                }
+            });
+            let config_message =
+                Self::build_text_message("speech.config", &request_id, &speech_config.to_string());
+            if let Err(e) = socket.send(Message::Text(config_message)).await {
+                return Err((0, format!("Failed to send speech.config: {}", e)));
+            }
+            let audio_message = Self::build_audio_message(&request_id, &audio_data);
+            if let Err(e) = socket.send(Message::Binary(audio_message)).await {
+                return Err((0, format!("Failed to send audio: {}", e)));
+            }
+
+            // Step 4: wait for the service to acknowledge the turn
+            let turn_start = std::time::Instant::now();
+            let wait = tokio::time::timeout(context.timeout, async {
+                while let Some(message) = socket.next().await {
+                    match message {
+                        Ok(Message::Text(text)) if text.contains("Path:turn.start") => {
+                            return Ok(());
+                        }
+                        Ok(Message::Close(frame)) => {
+                            let reason = frame
+                                .map(|f| format!("{} {}", f.code, f.reason))
+                                .unwrap_or_default();
+                            return Err(format!("Service closed the connection: {}", reason));
+                        }
+                        Ok(_) => continue,
+                        Err(e) => return Err(format!("WebSocket error: {}", e)),
+                    }
+                }
+                Err("Connection ended before turn.start".to_string())
+            })
+            .await;
+            let _ = socket.close(None).await;
+
+            match wait {
+                Ok(Ok(())) => Ok(format!(
+                    "Token {}ms, WebSocket connect {}ms, turn.start after {}ms",
+                    token_ms,
+                    connect_ms,
+                    turn_start.elapsed().as_millis()
+                )),
+                Ok(Err(e)) => Err((0, e)),
+                Err(_) => Err((
+                    0,
+                    "Timed out waiting for turn.start (proxy may be buffering WebSocket frames)"
+                        .to_string(),
+                )),
+            }
+        })
+        .await;
+
+        TestResult::from_outcome(scenario, duration_ms, result)
     }
 }
 
@@ -642,6 +1057,106 @@ impl SpeechService {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_relay_hosts() {
+        let body = serde_json::json!({
+            "Urls": [
+                "turn:relay.communication.microsoft.com:3478",
+                "turn:relay.communication.microsoft.com:3478?transport=tcp"
+            ],
+            "Username": "user",
+            "Password": "secret"
+        });
+        assert_eq!(
+            relay_hosts(&body),
+            vec!["relay.communication.microsoft.com:3478"]
+        );
+        assert!(relay_hosts(&serde_json::json!({})).is_empty());
+    }
+
+    #[test]
+    fn test_streaming_verdict() {
+        let streamed = StreamStats {
+            content_length: None,
+            bytes: 48_000,
+            chunks: 12,
+            first_byte_ms: 180,
+            total_ms: 2400,
+        };
+        assert_eq!(
+            streaming_verdict(streamed).unwrap(),
+            "Streamed 48000 bytes in 12 chunks: first audio after 180 ms, complete after 2400 ms"
+        );
+
+        let buffered = StreamStats {
+            first_byte_ms: 2350,
+            ..streamed
+        };
+        assert!(streaming_verdict(buffered)
+            .unwrap_err()
+            .contains("appears to buffer"));
+
+        let with_length = StreamStats {
+            content_length: Some(48_000),
+            ..streamed
+        };
+        assert!(streaming_verdict(with_length)
+            .unwrap_err()
+            .contains("Content-Length 48000"));
+
+        // A fast response arriving in one read isn't evidence of buffering
+        let fast = StreamStats {
+            chunks: 1,
+            first_byte_ms: 90,
+            total_ms: 95,
+            ..streamed
+        };
+        assert!(streaming_verdict(fast).is_ok());
+    }
+
+    #[test]
+    fn test_voices_details_checks_expected_voice() {
+        let voices: Vec<Voice> = serde_json::from_value(serde_json::json!([{
+            "Name": "Microsoft Server Speech Text to Speech Voice (en-GB, SoniaNeural)",
+            "DisplayName": "Sonia",
+            "LocalName": "Sonia",
+            "ShortName": "en-GB-SoniaNeural",
+            "Locale": "en-GB"
+        }]))
+        .unwrap();
+        assert_eq!(voices_details(&voices, None).unwrap(), "Retrieved 1 voices");
+        assert!(voices_details(&voices, Some("en-gb-sonianeural"))
+            .unwrap()
+            .ends_with("including en-GB-SoniaNeural"));
+        assert!(voices_details(&voices, Some("en-US-JennyNeural")).is_err());
+    }
+
+    #[test]
+    fn test_sdk_websocket_url() {
+        assert_eq!(
+            SpeechService::get_sdk_websocket_url("eastus", Cloud::Global, None),
+            "wss://eastus.stt.speech.microsoft.com/speech/recognition/conversation/cognitiveservices/v1?language=en-US&format=simple"
+        );
+        assert_eq!(
+            SpeechService::get_sdk_websocket_url(
+                "eastus",
+                Cloud::Global,
+                Some("https://myres.cognitiveservices.azure.com/")
+            ),
+            "wss://myres.cognitiveservices.azure.com/stt/speech/recognition/conversation/cognitiveservices/v1?language=en-US&format=simple"
+        );
+    }
+
+    #[test]
+    fn test_audio_message_framing() {
+        let message = SpeechService::build_audio_message("ABC", &[1, 2, 3]);
+        let header_len = u16::from_be_bytes([message[0], message[1]]) as usize;
+        let header = std::str::from_utf8(&message[2..2 + header_len]).unwrap();
+        assert!(header.starts_with("Path: audio\r\n"));
+        assert!(header.contains("X-RequestId: ABC"));
+        assert_eq!(&message[2 + header_len..], &[1, 2, 3]);
+    }
+
     #[test]
     fn test_minimal_wav_valid_header() {
         // Verify RIFF header
@@ -0,0 +1,121 @@
+//! Scenario tags and selection (`--scenarios`, `--skip-scenarios`)
+//!
+//! Each selector is a scenario id (`voices_list`) or a tag (`tag:fast`). A
+//! scenario runs when it matches any `--scenarios` selector (or none were
+//! given) and no `--skip-scenarios` selector.
+
+use crate::error::{AppError, Result};
+use crate::services::TestScenario;
+
+/// Sends no credentials; only proves the host is reachable
+pub const NO_AUTH: &str = "no-auth";
+/// One lightweight request, normally answered in well under a second
+pub const FAST: &str = "fast";
+/// Sends an input (audio, image, document, or text); embedded samples are
+/// used unless one is given
+pub const REQUIRES_INPUT: &str = "requires-input";
+/// Starts a long-running operation and polls it until it completes
+pub const ASYNC_POLL: &str = "async-poll";
+/// Connects over WebSocket instead of plain HTTPS
+pub const WEBSOCKET: &str = "websocket";
+/// Health check of an on-prem container (`--container`)
+pub const CONTAINER: &str = "container";
+
+/// Every tag a scenario may carry
+pub const ALL: &[&str] = &[
+    NO_AUTH,
+    FAST,
+    REQUIRES_INPUT,
+    ASYNC_POLL,
+    WEBSOCKET,
+    CONTAINER,
+];
+
+/// Prefix of a tag selector
+const TAG_PREFIX: &str = "tag:";
+
+/// Which scenarios of a service to run
+#[derive(Debug, Clone, Default)]
+pub struct ScenarioSelection {
+    /// Run only scenarios matching one of these (all when `None`)
+    pub include: Option<Vec<String>>,
+    /// Never run scenarios matching one of these
+    pub exclude: Vec<String>,
+}
+
+impl ScenarioSelection {
+    /// Selection from `--scenarios` and `--skip-scenarios`, refusing unknown tags
+    pub fn new(include: Option<Vec<String>>, exclude: Vec<String>) -> Result<Self> {
+        for selector in include.iter().flatten().chain(&exclude) {
+            if let Some(tag) = selector.strip_prefix(TAG_PREFIX) {
+                if !ALL.contains(&tag) {
+                    return Err(AppError::InvalidInput(format!(
+                        "Unknown scenario tag '{}' (known tags: {})",
+                        tag,
+                        ALL.join(", ")
+                    )));
+                }
+            }
+        }
+        Ok(Self { include, exclude })
+    }
+
+    /// Whether a scenario with this id and these tags runs
+    pub fn selects(&self, id: &str, tags: &[&str]) -> bool {
+        let matches = |selector: &String| match selector.strip_prefix(TAG_PREFIX) {
+            Some(tag) => tags.contains(&tag),
+            None => selector == id,
+        };
+        self.include
+            .as_ref()
+            .is_none_or(|include| include.iter().any(matches))
+            && !self.exclude.iter().any(matches)
+    }
+
+    /// Whether `scenario` runs
+    pub fn selects_scenario(&self, scenario: &TestScenario) -> bool {
+        self.selects(scenario.id, scenario.tags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::get_all_services;
+
+    fn list(selectors: &[&str]) -> Vec<String> {
+        selectors.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_selection() {
+        let selection =
+            ScenarioSelection::new(Some(list(&["tag:fast", "layout"])), list(&["tag:no-auth"]))
+                .unwrap();
+        assert!(selection.selects("voices_list", &[FAST]));
+        assert!(selection.selects("layout", &[ASYNC_POLL]));
+        assert!(!selection.selects("endpoint_check", &[FAST, NO_AUTH]));
+        assert!(!selection.selects("tts", &[]));
+        assert!(ScenarioSelection::default().selects("tts", &[]));
+
+        let err = ScenarioSelection::new(None, list(&["tag:slow"])).unwrap_err();
+        assert!(err.to_string().contains("Unknown scenario tag 'slow'"));
+
+        // Every built-in scenario's tags are known, and inputs are tagged
+        for service in get_all_services() {
+            for scenario in service.list_scenarios() {
+                assert!(
+                    scenario.tags.iter().all(|t| ALL.contains(t)),
+                    "{}",
+                    scenario.id
+                );
+                assert_eq!(
+                    scenario.tags.contains(&REQUIRES_INPUT),
+                    scenario.input_type.is_some(),
+                    "{}",
+                    scenario.id
+                );
+            }
+        }
+    }
+}
@@ -1,9 +1,12 @@
 use async_trait::async_trait;
 
 use crate::config::Cloud;
-use crate::error::sanitize_error;
+use crate::services::endpoints::ServiceEndpoint;
+use crate::services::responses::vision::{ImageAnalysis, Vectorization};
 use crate::services::{
-    measure_time, AzureService, InputType, TestContext, TestResult, TestScenario,
+    api_versions, classify::request_error, http_error, long_poll, measure_time, payloads,
+    responses, tags, AzureService, InputType, ScenarioRequest, TestContext, TestResult,
+    TestScenario,
 };
 
 /// Vision Service implementation
@@ -21,6 +24,10 @@ impl Default for VisionService {
     }
 }
 
+/// Multimodal embeddings model the vectorize scenarios request, and the
+/// `modelVersion` the responses must report
+const RETRIEVAL_MODEL_VERSION: &str = "2023-04-15";
+
 // Minimal 50x50 pixel PNG for testing (when no image provided)
 // Azure Vision API requires minimum 50x50 pixels
 const MINIMAL_PNG: &[u8] = &[
@@ -45,13 +52,7 @@ impl AzureService for VisionService {
     }
 
     fn get_endpoint(&self, region: &str, cloud: Cloud, custom_endpoint: Option<&str>) -> String {
-        if let Some(endpoint) = custom_endpoint {
-            return endpoint.to_string();
-        }
-        match cloud {
-            Cloud::Global => format!("https://{}.api.cognitive.microsoft.com", region),
-            Cloud::China => format!("https://{}.api.cognitive.azure.cn", region),
-        }
+        ServiceEndpoint::resolve(region, cloud, custom_endpoint).url()
     }
 
     fn list_scenarios(&self) -> Vec<TestScenario> {
@@ -62,6 +63,7 @@ impl AzureService for VisionService {
                 description: "Extract tags, objects, and text from image",
                 requires_input: false,
                 input_type: Some(InputType::Image),
+                tags: &[tags::REQUIRES_INPUT],
             },
             TestScenario {
                 id: "read_text",
@@ -69,6 +71,15 @@ impl AzureService for VisionService {
                 description: "Extract text from image using OCR",
                 requires_input: false,
                 input_type: Some(InputType::Image),
+                tags: &[tags::REQUIRES_INPUT],
+            },
+            TestScenario {
+                id: "read_v32",
+                name: "Read 3.2 (Legacy OCR)",
+                description: "Extract text with the async Read 3.2 API (submit, then poll)",
+                requires_input: false,
+                input_type: Some(InputType::Image),
+                tags: &[tags::REQUIRES_INPUT, tags::ASYNC_POLL],
             },
             TestScenario {
                 id: "detect_objects",
@@ -76,6 +87,7 @@ impl AzureService for VisionService {
                 description: "Detect and locate objects in image",
                 requires_input: false,
                 input_type: Some(InputType::Image),
+                tags: &[tags::REQUIRES_INPUT],
             },
             TestScenario {
                 id: "smart_crops",
@@ -83,6 +95,7 @@ impl AzureService for VisionService {
                 description: "Generate smart-cropped thumbnails",
                 requires_input: false,
                 input_type: Some(InputType::Image),
+                tags: &[tags::REQUIRES_INPUT],
             },
             TestScenario {
                 id: "people_detection",
@@ -90,6 +103,31 @@ impl AzureService for VisionService {
                 description: "Detect people in image",
                 requires_input: false,
                 input_type: Some(InputType::Image),
+                tags: &[tags::REQUIRES_INPUT],
+            },
+            TestScenario {
+                id: "vectorize_image",
+                name: "Image Retrieval (Vectorize Image)",
+                description: "Generate an image embedding with the multimodal retrieval API",
+                requires_input: false,
+                input_type: Some(InputType::Image),
+                tags: &[tags::REQUIRES_INPUT],
+            },
+            TestScenario {
+                id: "vectorize_text",
+                name: "Image Retrieval (Vectorize Text)",
+                description: "Generate a text embedding with the multimodal retrieval API",
+                requires_input: false,
+                input_type: None,
+                tags: &[tags::FAST],
+            },
+            TestScenario {
+                id: "background_removal",
+                name: "Background Removal",
+                description: "Segment foreground from background (limited regions)",
+                requires_input: false,
+                input_type: Some(InputType::Image),
+                tags: &[tags::REQUIRES_INPUT],
             },
         ]
     }
@@ -115,9 +153,13 @@ impl AzureService for VisionService {
         match scenario_id {
             "analyze_image" => self.test_analyze_image(context, &scenario).await,
             "read_text" => self.test_read_text(context, &scenario).await,
+            "read_v32" => self.test_read_v32(context, &scenario).await,
             "detect_objects" => self.test_detect_objects(context, &scenario).await,
             "smart_crops" => self.test_smart_crops(context, &scenario).await,
             "people_detection" => self.test_people_detection(context, &scenario).await,
+            "vectorize_image" => self.test_vectorize_image(context, &scenario).await,
+            "vectorize_text" => self.test_vectorize_text(context, &scenario).await,
+            "background_removal" => self.test_background_removal(context, &scenario).await,
             _ => TestResult::failure(
                 scenario_id,
                 scenario.name,
@@ -128,10 +170,77 @@ impl AzureService for VisionService {
     }
 }
 
+/// Error codes the service answers with when the resource's region or tier lacks a feature
+const UNSUPPORTED_CODES: &[&str] = &["NotSupportedFeature", "NotSupportedRegion"];
+
+/// Build an error message for features with limited regional/SKU availability.
+///
+/// Image Retrieval and segmentation answer with a NotSupported error code when the
+/// resource's region or pricing tier does not offer them, which is easy to mistake for
+/// a wrong endpoint. Say so explicitly in that case only; any other error, including
+/// a plain 404 from a wrong path or endpoint, is reported as is.
+fn feature_error(feature: &str, region: &str, status: reqwest::StatusCode, body: &str) -> String {
+    let error = serde_json::from_str::<serde_json::Value>(body).ok();
+    let codes = ["/error/code", "/error/innererror/code", "/code"]
+        .iter()
+        .filter_map(|pointer| error.as_ref()?.pointer(pointer)?.as_str());
+    let message = error
+        .as_ref()
+        .and_then(|e| {
+            e.pointer("/error/message")
+                .or_else(|| e.pointer("/message"))
+        })
+        .and_then(|m| m.as_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let unsupported = codes
+        .into_iter()
+        .any(|code| UNSUPPORTED_CODES.contains(&code))
+        || message.contains("not supported in this region")
+        || message.contains("not available in this region");
+    if unsupported {
+        format!(
+            "HTTP {}: {} is not available for this resource. It is only offered in \
+             selected regions (current: {}) and on the S1 tier; create the resource in a \
+             supported region to use it",
+            status, feature, region
+        )
+    } else {
+        http_error(status, body)
+    }
+}
+
+/// Text lines of a completed Read 3.2 operation, across all pages
+fn read_v32_lines(body: &serde_json::Value) -> Vec<&str> {
+    body.pointer("/analyzeResult/readResults")
+        .and_then(|r| r.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|page| page.get("lines").and_then(|l| l.as_array()))
+        .flatten()
+        .filter_map(|line| line.get("text").and_then(|t| t.as_str()))
+        .collect()
+}
+
 impl VisionService {
+    /// Save the raw analysis as an artifact, then parse it
+    fn save_analysis(
+        &self,
+        context: &TestContext,
+        scenario: &TestScenario,
+        body: serde_json::Value,
+    ) -> Result<ImageAnalysis, String> {
+        context.save_json_artifact(self.name(), scenario.id, &body);
+        let analysis: ImageAnalysis = responses::from_value(body)?;
+        responses::model_version(&analysis.model_version, None)?;
+        Ok(analysis)
+    }
+
     fn get_image_data(context: &TestContext) -> (Vec<u8>, String) {
-        if let Some(input) = &context.input {
+        if let Some(input) = context.input(InputType::Image) {
             (input.data.clone(), input.content_type.clone())
+        } else if let Some(payload) = context.realistic_payload(payloads::IMAGE) {
+            (payload.data.to_vec(), payload.content_type.to_string())
         } else {
             // Use embedded minimal PNG for connectivity testing
             (MINIMAL_PNG.to_vec(), "image/png".to_string())
@@ -148,70 +257,73 @@ impl VisionService {
         // Note: Using tags,objects,read features which are available in all regions.
         // caption/denseCaptions are NOT available in some regions (e.g., swedencentral).
         let url = format!(
-            "{}/computervision/imageanalysis:analyze?api-version=2024-02-01&features=tags,objects,read",
-            endpoint
+            "{}/computervision/imageanalysis:analyze?api-version={}&features=tags,objects,read",
+            endpoint,
+            api_versions::IMAGE_ANALYSIS.version(context)
         );
 
         let (image_data, content_type) = Self::get_image_data(context);
 
-        let (result, duration_ms) = measure_time(async {
-            let request = context
-                .client
-                .post(&url)
-                .header("Content-Type", &content_type)
-                .body(image_data);
-            let request = context.credentials.apply_to_request(request);
-
-            match request.send().await {
-                Ok(response) => {
-                    let status = response.status();
-                    if status.is_success() {
-                        let body: serde_json::Value = response.json().await.unwrap_or_default();
-                        let has_tags = body.get("tagsResult").is_some();
-                        let has_objects = body.get("objectsResult").is_some();
-                        let has_read = body.get("readResult").is_some();
-                        Ok(format!(
-                            "Analysis complete (tags: {}, objects: {}, read: {})",
-                            has_tags, has_objects, has_read
-                        ))
-                    } else {
-                        let body = response.text().await.unwrap_or_default();
-                        Err((
-                            status.as_u16(),
-                            format!(
-                                "HTTP {}: {}",
-                                status,
-                                sanitize_error(&body, status.as_u16())
-                            ),
-                        ))
-                    }
+        ScenarioRequest::post(url)
+            .body(content_type, image_data)
+            .extract_json(|body| {
+                let analysis = self.save_analysis(context, scenario, body)?;
+                if let Some(payload) = context.realistic_payload(payloads::IMAGE) {
+                    return payload.verify(&analysis.read_text()).map(|details| {
+                        format!(
+                            "Analysis complete: {} (tags: {})",
+                            details,
+                            analysis.tag_names().join(", ")
+                        )
+                    });
                 }
-                Err(e) => Err((0, format!("Request failed: {}", e))),
-            }
-        })
-        .await;
+                Ok(format!(
+                    "Analysis complete (tags: {}, objects: {}, read: {})",
+                    analysis.tags_result.is_some(),
+                    analysis.objects_result.is_some(),
+                    analysis.read_result.is_some()
+                ))
+            })
+            .run(context, scenario)
+            .await
+    }
 
-        match result {
-            Ok(details) => {
-                TestResult::success(scenario.id, scenario.name, duration_ms).with_details(details)
-            }
-            Err((status, error)) => {
-                let mut result =
-                    TestResult::failure(scenario.id, scenario.name, duration_ms, error);
-                if status > 0 {
-                    result = result.with_http_status(status);
+    async fn test_read_text(&self, context: &TestContext, scenario: &TestScenario) -> TestResult {
+        let endpoint =
+            self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
+        let url = format!(
+            "{}/computervision/imageanalysis:analyze?api-version={}&features=read",
+            endpoint,
+            api_versions::IMAGE_ANALYSIS.version(context)
+        );
+
+        let (image_data, content_type) = Self::get_image_data(context);
+
+        ScenarioRequest::post(url)
+            .body(content_type, image_data)
+            .extract_json(|body| {
+                let analysis = self.save_analysis(context, scenario, body)?;
+                let blocks = analysis.read_result.as_ref().map_or(0, |r| r.blocks.len());
+                match context.realistic_payload(payloads::IMAGE) {
+                    Some(payload) => payload.verify(&analysis.read_text()).map(|details| {
+                        format!("Read complete: {} text blocks found, {}", blocks, details)
+                    }),
+                    None => Ok(format!("Read complete: {} text blocks found", blocks)),
                 }
-                result
-            }
-        }
+            })
+            .run(context, scenario)
+            .await
     }
 
-    async fn test_read_text(&self, context: &TestContext, scenario: &TestScenario) -> TestResult {
+    /// Legacy async OCR: submit to `vision/v3.2/read/analyze`, then poll the
+    /// `Operation-Location` it returns, as integrations predating Image
+    /// Analysis 4.0 do
+    async fn test_read_v32(&self, context: &TestContext, scenario: &TestScenario) -> TestResult {
         let endpoint =
             self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
         let url = format!(
-            "{}/computervision/imageanalysis:analyze?api-version=2024-02-01&features=read",
-            endpoint
+            "{}/vision/v3.2/read/analyze",
+            endpoint.trim_end_matches('/')
         );
 
         let (image_data, content_type) = Self::get_image_data(context);
@@ -224,48 +336,87 @@ impl VisionService {
                 .body(image_data);
             let request = context.credentials.apply_to_request(request);
 
-            match request.send().await {
-                Ok(response) => {
-                    let status = response.status();
-                    if status.is_success() {
-                        let body: serde_json::Value = response.json().await.unwrap_or_default();
-                        let blocks = body
-                            .get("readResult")
-                            .and_then(|r| r.get("blocks"))
-                            .and_then(|b| b.as_array())
-                            .map(|b| b.len())
-                            .unwrap_or(0);
-                        Ok(format!("Read complete: {} text blocks found", blocks))
-                    } else {
-                        let body = response.text().await.unwrap_or_default();
-                        Err((
-                            status.as_u16(),
-                            format!(
-                                "HTTP {}: {}",
-                                status,
-                                sanitize_error(&body, status.as_u16())
-                            ),
-                        ))
-                    }
+            let response = context
+                .send(request)
+                .await
+                .map_err(|e| (0, request_error("Request failed", &e)))?;
+            let status = response.status();
+            if status.as_u16() != 202 {
+                let body = response.text().await.unwrap_or_default();
+                return Err((status.as_u16(), http_error(status, &body)));
+            }
+            let operation_url = response
+                .headers()
+                .get("operation-location")
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+                .ok_or((status.as_u16(), "No Operation-Location header".to_string()))?;
+
+            let (body, summary) = match context.long_poll {
+                Some(long_poll) => {
+                    let (body, summary) =
+                        long_poll::poll(context, &operation_url, long_poll).await?;
+                    (body, Some(summary))
                 }
-                Err(e) => Err((0, format!("Request failed: {}", e))),
+                None => (Self::poll_read_v32(context, &operation_url).await?, None),
+            };
+            context.save_json_artifact(self.name(), scenario.id, &body);
+            let model_version = body
+                .pointer("/analyzeResult/modelVersion")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default();
+            responses::model_version(model_version, None).map_err(|e| (status.as_u16(), e))?;
+
+            let lines = read_v32_lines(&body);
+            let mut details = match context.realistic_payload(payloads::IMAGE) {
+                Some(payload) => payload
+                    .verify(&lines.join(" "))
+                    .map(|details| format!("Read 3.2 complete: {} lines, {}", lines.len(), details))
+                    .map_err(|e| (0, e))?,
+                None => format!("Read 3.2 complete: {} lines found", lines.len()),
+            };
+            if let Some(summary) = summary {
+                details.push_str(&format!("; {}", summary));
             }
+            Ok(details)
         })
         .await;
 
-        match result {
-            Ok(details) => {
-                TestResult::success(scenario.id, scenario.name, duration_ms).with_details(details)
+        TestResult::from_outcome(scenario, duration_ms, result)
+    }
+
+    /// Poll a Read 3.2 operation every second for up to 30 seconds
+    async fn poll_read_v32(
+        context: &TestContext,
+        operation_url: &str,
+    ) -> Result<serde_json::Value, (u16, String)> {
+        for _ in 0..30 {
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+
+            let request = context
+                .credentials
+                .apply_to_request(context.client.get(operation_url));
+            let response = context
+                .send(request)
+                .await
+                .map_err(|e| (0, request_error("Poll request failed", &e)))?;
+            let status = response.status();
+            if !status.is_success() {
+                let body = response.text().await.unwrap_or_default();
+                return Err((status.as_u16(), http_error(status, &body)));
             }
-            Err((status, error)) => {
-                let mut result =
-                    TestResult::failure(scenario.id, scenario.name, duration_ms, error);
-                if status > 0 {
-                    result = result.with_http_status(status);
-                }
-                result
+            let body: serde_json::Value = response.json().await.unwrap_or_default();
+            match body.get("status").and_then(|s| s.as_str()).unwrap_or("") {
+                "succeeded" => return Ok(body),
+                "failed" => return Err((status.as_u16(), "Read operation failed".to_string())),
+                // notStarted / running: keep polling
+                _ => {}
             }
         }
+        Err((
+            0,
+            "Read operation did not complete within 30 seconds".to_string(),
+        ))
     }
 
     async fn test_detect_objects(
@@ -276,62 +427,22 @@ impl VisionService {
         let endpoint =
             self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
         let url = format!(
-            "{}/computervision/imageanalysis:analyze?api-version=2024-02-01&features=objects",
-            endpoint
+            "{}/computervision/imageanalysis:analyze?api-version={}&features=objects",
+            endpoint,
+            api_versions::IMAGE_ANALYSIS.version(context)
         );
 
         let (image_data, content_type) = Self::get_image_data(context);
 
-        let (result, duration_ms) = measure_time(async {
-            let request = context
-                .client
-                .post(&url)
-                .header("Content-Type", &content_type)
-                .body(image_data);
-            let request = context.credentials.apply_to_request(request);
-
-            match request.send().await {
-                Ok(response) => {
-                    let status = response.status();
-                    if status.is_success() {
-                        let body: serde_json::Value = response.json().await.unwrap_or_default();
-                        let objects = body
-                            .get("objectsResult")
-                            .and_then(|r| r.get("values"))
-                            .and_then(|v| v.as_array())
-                            .map(|v| v.len())
-                            .unwrap_or(0);
-                        Ok(format!("Detection complete: {} objects found", objects))
-                    } else {
-                        let body = response.text().await.unwrap_or_default();
-                        Err((
-                            status.as_u16(),
-                            format!(
-                                "HTTP {}: {}",
-                                status,
-                                sanitize_error(&body, status.as_u16())
-                            ),
-                        ))
-                    }
-                }
-                Err(e) => Err((0, format!("Request failed: {}", e))),
-            }
-        })
-        .await;
-
-        match result {
-            Ok(details) => {
-                TestResult::success(scenario.id, scenario.name, duration_ms).with_details(details)
-            }
-            Err((status, error)) => {
-                let mut result =
-                    TestResult::failure(scenario.id, scenario.name, duration_ms, error);
-                if status > 0 {
-                    result = result.with_http_status(status);
-                }
-                result
-            }
-        }
+        ScenarioRequest::post(url)
+            .body(content_type, image_data)
+            .extract_json(|body| {
+                let analysis = self.save_analysis(context, scenario, body)?;
+                let count = analysis.objects_result.map_or(0, |r| r.values.len());
+                Ok(format!("Detection complete: {} objects found", count))
+            })
+            .run(context, scenario)
+            .await
     }
 
     async fn test_smart_crops(&self, context: &TestContext, scenario: &TestScenario) -> TestResult {
@@ -339,65 +450,51 @@ impl VisionService {
             self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
         // smartCrops requires aspect ratios - using common thumbnail ratios
         let url = format!(
-            "{}/computervision/imageanalysis:analyze?api-version=2024-02-01&features=smartCrops&smartCrops-aspect-ratios=1.0,1.5",
-            endpoint
+            "{}/computervision/imageanalysis:analyze?api-version={}&features=smartCrops&smartCrops-aspect-ratios=1.0,1.5",
+            endpoint,
+            api_versions::IMAGE_ANALYSIS.version(context)
         );
 
         let (image_data, content_type) = Self::get_image_data(context);
 
-        let (result, duration_ms) = measure_time(async {
-            let request = context
-                .client
-                .post(&url)
-                .header("Content-Type", &content_type)
-                .body(image_data);
-            let request = context.credentials.apply_to_request(request);
+        ScenarioRequest::post(url)
+            .body(content_type, image_data)
+            .extract_json(|body| {
+                let analysis = self.save_analysis(context, scenario, body)?;
+                let count = analysis.smart_crops_result.map_or(0, |r| r.values.len());
+                Ok(format!("Smart crops complete: {} crop regions", count))
+            })
+            .run(context, scenario)
+            .await
+    }
 
-            match request.send().await {
-                Ok(response) => {
-                    let status = response.status();
-                    if status.is_success() {
-                        let body: serde_json::Value = response.json().await.unwrap_or_default();
-                        let crops = body
-                            .get("smartCropsResult")
-                            .and_then(|r| r.get("values"))
-                            .and_then(|v| v.as_array())
-                            .map(|v| v.len())
-                            .unwrap_or(0);
-                        Ok(format!("Smart crops complete: {} crop regions", crops))
-                    } else {
-                        let body = response.text().await.unwrap_or_default();
-                        Err((
-                            status.as_u16(),
-                            format!(
-                                "HTTP {}: {}",
-                                status,
-                                sanitize_error(&body, status.as_u16())
-                            ),
-                        ))
-                    }
-                }
-                Err(e) => Err((0, format!("Request failed: {}", e))),
-            }
-        })
-        .await;
+    async fn test_people_detection(
+        &self,
+        context: &TestContext,
+        scenario: &TestScenario,
+    ) -> TestResult {
+        let endpoint =
+            self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
+        let url = format!(
+            "{}/computervision/imageanalysis:analyze?api-version={}&features=people",
+            endpoint,
+            api_versions::IMAGE_ANALYSIS.version(context)
+        );
 
-        match result {
-            Ok(details) => {
-                TestResult::success(scenario.id, scenario.name, duration_ms).with_details(details)
-            }
-            Err((status, error)) => {
-                let mut result =
-                    TestResult::failure(scenario.id, scenario.name, duration_ms, error);
-                if status > 0 {
-                    result = result.with_http_status(status);
-                }
-                result
-            }
-        }
+        let (image_data, content_type) = Self::get_image_data(context);
+
+        ScenarioRequest::post(url)
+            .body(content_type, image_data)
+            .extract_json(|body| {
+                let analysis = self.save_analysis(context, scenario, body)?;
+                let count = analysis.people_result.map_or(0, |r| r.values.len());
+                Ok(format!("People detection complete: {} people found", count))
+            })
+            .run(context, scenario)
+            .await
     }
 
-    async fn test_people_detection(
+    async fn test_vectorize_image(
         &self,
         context: &TestContext,
         scenario: &TestScenario,
@@ -405,64 +502,162 @@ impl VisionService {
         let endpoint =
             self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
         let url = format!(
-            "{}/computervision/imageanalysis:analyze?api-version=2024-02-01&features=people",
-            endpoint
+            "{}/computervision/retrieval:vectorizeImage?api-version={}&model-version={}",
+            endpoint,
+            api_versions::IMAGE_RETRIEVAL.version(context),
+            RETRIEVAL_MODEL_VERSION
         );
 
         let (image_data, content_type) = Self::get_image_data(context);
 
-        let (result, duration_ms) = measure_time(async {
-            let request = context
-                .client
-                .post(&url)
-                .header("Content-Type", &content_type)
-                .body(image_data);
-            let request = context.credentials.apply_to_request(request);
+        ScenarioRequest::post(url)
+            .body(content_type, image_data)
+            .extract(|result: Vectorization| {
+                responses::model_version(&result.model_version, Some(RETRIEVAL_MODEL_VERSION))?;
+                let dimensions = result.vector.len();
+                Ok(format!("Image vectorized: {} dimensions", dimensions))
+            })
+            .on_error(|status, body| {
+                Err(feature_error(
+                    "Image Retrieval",
+                    &context.region,
+                    status,
+                    body,
+                ))
+            })
+            .run(context, scenario)
+            .await
+    }
 
-            match request.send().await {
-                Ok(response) => {
-                    let status = response.status();
-                    if status.is_success() {
-                        let body: serde_json::Value = response.json().await.unwrap_or_default();
-                        let people = body
-                            .get("peopleResult")
-                            .and_then(|r| r.get("values"))
-                            .and_then(|v| v.as_array())
-                            .map(|v| v.len())
-                            .unwrap_or(0);
-                        Ok(format!(
-                            "People detection complete: {} people found",
-                            people
-                        ))
-                    } else {
-                        let body = response.text().await.unwrap_or_default();
-                        Err((
-                            status.as_u16(),
-                            format!(
-                                "HTTP {}: {}",
-                                status,
-                                sanitize_error(&body, status.as_u16())
-                            ),
-                        ))
-                    }
-                }
-                Err(e) => Err((0, format!("Request failed: {}", e))),
-            }
-        })
-        .await;
+    async fn test_vectorize_text(
+        &self,
+        context: &TestContext,
+        scenario: &TestScenario,
+    ) -> TestResult {
+        let endpoint =
+            self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
+        let url = format!(
+            "{}/computervision/retrieval:vectorizeText?api-version={}&model-version={}",
+            endpoint,
+            api_versions::IMAGE_RETRIEVAL.version(context),
+            RETRIEVAL_MODEL_VERSION
+        );
 
-        match result {
-            Ok(details) => {
-                TestResult::success(scenario.id, scenario.name, duration_ms).with_details(details)
-            }
-            Err((status, error)) => {
-                let mut result =
-                    TestResult::failure(scenario.id, scenario.name, duration_ms, error);
-                if status > 0 {
-                    result = result.with_http_status(status);
-                }
-                result
-            }
-        }
+        let body = serde_json::json!({
+            "text": "a photo of a cat sitting on a windowsill"
+        });
+
+        ScenarioRequest::post(url)
+            .json(&body)
+            .extract(|result: Vectorization| {
+                responses::model_version(&result.model_version, Some(RETRIEVAL_MODEL_VERSION))?;
+                let dimensions = result.vector.len();
+                Ok(format!("Text vectorized: {} dimensions", dimensions))
+            })
+            .on_error(|status, body| {
+                Err(feature_error(
+                    "Image Retrieval",
+                    &context.region,
+                    status,
+                    body,
+                ))
+            })
+            .run(context, scenario)
+            .await
+    }
+
+    async fn test_background_removal(
+        &self,
+        context: &TestContext,
+        scenario: &TestScenario,
+    ) -> TestResult {
+        let endpoint =
+            self.get_endpoint(&context.region, context.cloud, context.endpoint.as_deref());
+        let url = format!(
+            "{}/computervision/imageanalysis:segment?api-version={}&mode=backgroundRemoval",
+            endpoint,
+            api_versions::IMAGE_SEGMENTATION.version(context)
+        );
+
+        let (image_data, content_type) = Self::get_image_data(context);
+
+        // The segment API returns the resulting PNG directly
+        ScenarioRequest::post(url)
+            .body(content_type, image_data)
+            .extract_bytes(|bytes| {
+                context.save_artifact(self.name(), scenario.id, "png", &bytes);
+                responses::png(&bytes)?;
+                Ok(format!(
+                    "Background removed: {} bytes returned",
+                    bytes.len()
+                ))
+            })
+            .on_error(|status, body| {
+                Err(feature_error(
+                    "Background removal",
+                    &context.region,
+                    status,
+                    body,
+                ))
+            })
+            .run(context, scenario)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feature_error_explains_region_404() {
+        let msg = feature_error(
+            "Image Retrieval",
+            "swedencentral",
+            reqwest::StatusCode::NOT_FOUND,
+            r#"{"error":{"code":"NotSupportedFeature","message":"Feature not supported"}}"#,
+        );
+        assert!(msg.contains("not available"));
+        assert!(msg.contains("swedencentral"));
+
+        let msg = feature_error(
+            "Background removal",
+            "westus",
+            reqwest::StatusCode::BAD_REQUEST,
+            r#"{"error":{"code":"InvalidRequest","innererror":{"code":"NotSupportedRegion"}}}"#,
+        );
+        assert!(msg.contains("not available"));
+
+        // A 404 without the code is a wrong path or endpoint, not a missing feature
+        let msg = feature_error(
+            "Image Retrieval",
+            "eastus",
+            reqwest::StatusCode::NOT_FOUND,
+            r#"{"error":{"code":"404","message":"Resource not found"}}"#,
+        );
+        assert!(!msg.contains("not available"), "{}", msg);
+        assert!(msg.starts_with("HTTP 404"), "{}", msg);
+        assert!(msg.contains("Resource not found"), "{}", msg);
+
+        let msg = feature_error(
+            "Image Retrieval",
+            "eastus",
+            reqwest::StatusCode::UNAUTHORIZED,
+            "Access denied",
+        );
+        assert!(!msg.contains("not available"));
+        assert!(msg.starts_with("HTTP 401"));
+    }
+
+    #[test]
+    fn test_read_v32_lines_spans_pages() {
+        let body = serde_json::json!({
+            "analyzeResult": {"readResults": [
+                {"page": 1, "lines": [{"text": "first"}, {"text": "second"}]},
+                {"page": 2, "lines": [{"text": "third"}]}
+            ]}
+        });
+        assert_eq!(read_v32_lines(&body), vec!["first", "second", "third"]);
+        assert!(read_v32_lines(&serde_json::json!({})).is_empty());
     }
 }
@@ -0,0 +1,153 @@
+//! Machine-readable scenario catalog (`list-scenarios --output json`)
+//!
+//! External tooling (UIs, policy checks, firewall request generators) needs
+//! the scenario set as data rather than text. The catalog lists every
+//! built-in scenario under its stable `service` and `id`, with its tags,
+//! input, credentials and the request it sends. Scenario IDs are what
+//! `--scenarios`, profiles and `[[expectations]]` select by, so they are kept
+//! stable; `catalog_version` changes when the shape of the catalog does.
+
+use crate::services::routes::{self, Payload};
+use crate::services::{get_all_services, get_service, tags, AzureService, InputType};
+use serde::Serialize;
+
+/// Version of the catalog format
+pub const CATALOG_VERSION: u32 = 1;
+
+/// Credentials a scenario sends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthRequirement {
+    /// None; the scenario only proves the host is reachable
+    None,
+    /// The run's API key or bearer token
+    KeyOrToken,
+}
+
+/// The request a scenario sends, from the route table
+#[derive(Debug, Clone, Serialize)]
+pub struct CatalogRequest {
+    /// HTTP method, or `WebSocket`
+    pub method: &'static str,
+    /// URL template; see `test --plan` for the placeholders
+    pub url: &'static str,
+    /// URL template used instead with a custom subdomain endpoint
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_url: Option<&'static str>,
+    /// GA version filling `{api-version}`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_version: Option<&'static str>,
+    pub payload: Payload,
+}
+
+/// One built-in scenario
+#[derive(Debug, Clone, Serialize)]
+pub struct CatalogScenario {
+    /// Service name, as given to `--services`
+    pub service: &'static str,
+    /// Service display name, as in reports
+    pub service_name: &'static str,
+    /// Scenario ID, as given to `--scenarios`
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub tags: &'static [&'static str],
+    pub requires_input: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_type: Option<InputType>,
+    pub auth: AuthRequirement,
+    /// Runs against an on-prem container (`--container`)
+    pub container: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request: Option<CatalogRequest>,
+}
+
+/// Every built-in scenario, or those of one service
+#[derive(Debug, Clone, Serialize)]
+pub struct ScenarioCatalog {
+    pub catalog_version: u32,
+    pub tool_version: &'static str,
+    pub scenarios: Vec<CatalogScenario>,
+}
+
+/// Catalog of `service`'s scenarios, or of every service; `None` for an
+/// unknown service
+pub fn scenario_catalog(service: Option<&str>) -> Option<ScenarioCatalog> {
+    let services = match service {
+        Some(name) => vec![get_service(name)?],
+        None => get_all_services(),
+    };
+    Some(ScenarioCatalog {
+        catalog_version: CATALOG_VERSION,
+        tool_version: env!("CARGO_PKG_VERSION"),
+        scenarios: services
+            .iter()
+            .flat_map(|service| catalog_scenarios(service.as_ref()))
+            .collect(),
+    })
+}
+
+fn catalog_scenarios(service: &dyn AzureService) -> Vec<CatalogScenario> {
+    service
+        .list_scenarios()
+        .into_iter()
+        .map(|scenario| {
+            let container = service.container_scenarios().contains(&scenario.id);
+            CatalogScenario {
+                service: service.name(),
+                service_name: service.display_name(),
+                id: scenario.id,
+                name: scenario.name,
+                description: scenario.description,
+                tags: scenario.tags,
+                requires_input: scenario.requires_input,
+                input_type: scenario.input_type,
+                auth: if scenario.tags.contains(&tags::NO_AUTH)
+                    || scenario.tags.contains(&tags::CONTAINER)
+                {
+                    AuthRequirement::None
+                } else {
+                    AuthRequirement::KeyOrToken
+                },
+                container,
+                request: routes::lookup(service.name(), scenario.id).map(|route| CatalogRequest {
+                    method: route.method,
+                    url: route.url,
+                    custom_url: route.custom_url,
+                    api_version: route.api.map(|api| api.ga),
+                    payload: route.payload,
+                }),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_is_complete_and_unique() {
+        let catalog = scenario_catalog(None).unwrap();
+        assert_eq!(catalog.catalog_version, CATALOG_VERSION);
+
+        let mut keys: Vec<_> = catalog
+            .scenarios
+            .iter()
+            .map(|s| (s.service, s.id))
+            .collect();
+        let total = keys.len();
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), total, "scenario IDs must be unique per service");
+        assert!(catalog.scenarios.iter().all(|s| s.request.is_some()));
+
+        let speech = scenario_catalog(Some("speech")).unwrap();
+        assert!(speech.scenarios.iter().all(|s| s.service == "speech"));
+        let json = serde_json::to_value(&speech).unwrap();
+        assert_eq!(json["scenarios"][0]["service_name"], "Speech");
+        assert!(json["scenarios"][0]["request"]["method"].is_string());
+
+        assert!(scenario_catalog(Some("nope")).is_none());
+    }
+}
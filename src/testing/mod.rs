@@ -1,12 +1,40 @@
+use crate::artifacts::ArtifactDir;
 use crate::auth::{AuthManager, Credentials};
-use crate::config::{AuthMethod, Cloud, Config, EntraConfig, UserAuthConfig, MAX_INPUT_FILE_SIZE};
+use crate::capture::CaptureLog;
+use crate::config::{
+    AuthMethod, Cloud, Config, CustomInputs, CustomScenario, EntraConfig, Expectation,
+    ResourceConfig, ServiceConfig, UserAuthConfig, DEFAULT_CONNECT_TIMEOUT_SECS,
+    DEFAULT_TLS_TIMEOUT_SECS, MAX_INPUT_FILE_SIZE,
+};
 use crate::error::{AppError, Result};
-use crate::output::TestReport;
-use crate::services::{get_service, TestContext, TestInput};
-use indicatif::{ProgressBar, ProgressStyle};
-use std::path::Path;
+use crate::keyvault;
+use crate::output::{RunMetadata, ServiceRunInfo, TestReport};
+use crate::redact;
+use crate::services::custom::{CustomProbeService, CUSTOM_SERVICE_NAME};
+use crate::services::long_poll::LongPoll;
+use crate::services::preflight;
+use crate::services::profiles::Profile;
+use crate::services::public_endpoints;
+use crate::services::{
+    capabilities, container, get_service, EventSender, InputType, Interrupt, ProgressReporter,
+    RateLimiter, RunEvent, ScenarioSelection, ServiceRegistry, TestContext, TestInput, TestInputs,
+    INTERRUPT_GRACE,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
 
+mod catalog;
+mod plan;
+
+pub use catalog::{
+    scenario_catalog, AuthRequirement, CatalogRequest, CatalogScenario, ScenarioCatalog,
+    CATALOG_VERSION,
+};
+pub use plan::{format_plan, PlannedScenario, PlannedService, RunPlan};
+
 /// Test runner configuration
 pub struct TestRunnerConfig {
     /// Services to test
@@ -15,183 +43,740 @@ pub struct TestRunnerConfig {
     pub api_key: Option<String>,
     /// Azure region
     pub region: String,
+    /// Regions to sweep (`--regions`); empty tests only `region`
+    pub regions: Vec<String>,
+    /// Named resources to test (`--target`); empty tests the default endpoint
+    pub targets: Vec<String>,
     /// Cloud environment
     pub cloud: Cloud,
     /// Authentication method
     pub auth_method: AuthMethod,
     /// Request timeout
     pub timeout: Duration,
+    /// Time allowed for DNS resolution plus the TCP connect
+    pub connect_timeout: Duration,
+    /// Time allowed for the TLS handshake
+    pub tls_timeout: Duration,
     /// Custom endpoint
     pub endpoint: Option<String>,
-    /// Input file path
+    /// Input file path, sent to any scenario without an input of its own type
     pub input_file: Option<String>,
-    /// Specific scenarios to run
+    /// Audio, image, and document files and text, by input type
+    pub inputs: CustomInputs,
+    /// Specific scenarios to run: ids or `tag:<tag>`
     pub scenarios: Option<Vec<String>>,
+    /// Scenarios not to run: ids or `tag:<tag>`
+    pub skip_scenarios: Vec<String>,
+    /// Named scenario set per service (`smoke`, `standard`, `full`, or from the config)
+    pub profile: Option<String>,
+    /// Profiles defined in the config file
+    pub test_profiles: BTreeMap<String, BTreeMap<String, Vec<String>>>,
     /// Entra configuration
     pub entra_config: Option<EntraConfig>,
     /// User auth configuration
     pub user_config: Option<UserAuthConfig>,
+    /// Scope of Entra ID tokens, instead of the cloud's Cognitive Services scope
+    pub scope: Option<String>,
     /// Show verbose output
     pub verbose: bool,
-    /// Quiet mode (no progress indicators)
+    /// Quiet mode (no progress indicators while authenticating)
     pub quiet: bool,
-    /// Display the bearer token after authentication
-    pub show_token: bool,
     /// Skip reading cached tokens from disk
     pub no_cache: bool,
+    /// Per-service settings from the config file
+    pub service_configs: HashMap<String, ServiceConfig>,
+    /// Maximum requests per second across the whole run
+    pub max_rps: Option<f64>,
+    /// Retry a throttled (HTTP 429) request once after Retry-After
+    pub retry_throttled: bool,
+    /// Count throttled results as failures
+    pub fail_on_throttle: bool,
+    /// Latency threshold for successful scenarios
+    pub max_latency_ms: Option<u64>,
+    /// Count slow results as failures
+    pub fail_on_slow: bool,
+    /// Expected outcomes of scenarios (`[[expectations]]`)
+    pub expectations: Vec<Expectation>,
+    /// User-defined HTTP probes, run as the "custom" service
+    pub custom_scenarios: Vec<CustomScenario>,
+    /// Extra headers added to every request
+    pub headers: Vec<(String, String)>,
+    /// Header that carries the API key instead of Ocp-Apim-Subscription-Key
+    pub api_key_header: Option<String>,
+    /// Directory to record sanitized request/response metadata into
+    pub capture_dir: Option<PathBuf>,
+    /// Directory to save scenario output (audio, transcripts, analysis JSON) into
+    pub artifacts_dir: Option<PathBuf>,
+    /// Redirect every request to this mock server (see [`crate::mock`])
+    pub mock: Option<SocketAddr>,
+    /// Test every service as an on-prem Azure AI container (`--container`)
+    pub container: bool,
+    /// Accept plain `http://` endpoints
+    pub allow_insecure_http: bool,
+    /// Send embedded assets with real content instead of silence and blank images
+    pub realistic_payloads: bool,
+    /// Check the input document's analysis for its expected content
+    pub verify_content: bool,
+    /// Poll async jobs for an extended period at a realistic interval
+    pub long_poll: Option<LongPoll>,
+    /// Call preview API versions instead of GA where a preview exists
+    pub preview_apis: bool,
+    /// Check that the services' public endpoints are unreachable, and test
+    /// them through the given (private) endpoint only
+    pub assert_blocked: bool,
+    /// Pricing tier of the resource (`--sku`, or read via ARM); scenarios it
+    /// doesn't offer are skipped
+    pub sku: Option<String>,
 }
 
 impl TestRunnerConfig {
-    /// Create from CLI args and config file
-    #[allow(clippy::too_many_arguments)]
-    pub fn from_config(
-        config: &Config,
-        services: Vec<String>,
-        api_key: Option<String>,
-        region: Option<String>,
-        cloud: Option<Cloud>,
-        auth_method: Option<AuthMethod>,
-        timeout: Option<u64>,
-        endpoint: Option<String>,
-        input_file: Option<String>,
-        scenarios: Option<Vec<String>>,
-        tenant: Option<String>,
-        bearer_token: Option<String>,
-        verbose: bool,
-        quiet: bool,
-        show_token: bool,
-        no_cache: bool,
-    ) -> Self {
-        // Use provided values or fall back to config
-        let api_key = api_key.or_else(|| {
-            // Try to find an API key from any configured service
-            for service_name in &services {
-                if let Some(svc) = config.services.get(service_name) {
-                    if svc.api_key.is_some() {
-                        return svc.api_key.clone();
-                    }
-                }
-            }
-            None
-        });
+    /// Start building a runner configuration
+    pub fn builder() -> TestRunnerConfigBuilder {
+        TestRunnerConfigBuilder::default()
+    }
+
+    /// Scope Entra ID tokens are requested for
+    pub fn token_scope(&self) -> &str {
+        self.scope
+            .as_deref()
+            .unwrap_or_else(|| self.cloud.cognitive_scope())
+    }
+}
+
+/// Builder for [`TestRunnerConfig`]. Settings that aren't given fall back to
+/// the config file (or [`Config::default_config`] when none is given).
+///
+/// ```no_run
+/// # async fn run() -> azure_aitoolsconnect::Result<()> {
+/// use azure_aitoolsconnect::{AuthMethod, TestRunner, TestRunnerConfig};
+///
+/// let config = TestRunnerConfig::builder()
+///     .services(["speech", "translator"])
+///     .auth_method(AuthMethod::Key)
+///     .api_key("<key>")
+///     .region("westeurope")
+///     .build();
+/// let report = TestRunner::new(config).run().await?;
+/// assert_eq!(report.summary.failed, 0);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TestRunnerConfigBuilder {
+    config: Option<Config>,
+    services: Vec<String>,
+    api_key: Option<String>,
+    region: Option<String>,
+    cloud: Option<Cloud>,
+    auth_method: Option<AuthMethod>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    tls_timeout: Option<Duration>,
+    endpoint: Option<String>,
+    input_file: Option<String>,
+    inputs: CustomInputs,
+    scenarios: Option<Vec<String>>,
+    skip_scenarios: Vec<String>,
+    profile: Option<String>,
+    tenant: Option<String>,
+    scope: Option<String>,
+    bearer_token: Option<String>,
+    verbose: bool,
+    quiet: bool,
+    no_cache: bool,
+    mock: Option<SocketAddr>,
+    container: bool,
+    allow_insecure_http: bool,
+    realistic_payloads: bool,
+    verify_content: bool,
+    long_poll: Option<LongPoll>,
+    preview_apis: bool,
+    assert_blocked: bool,
+    sku: Option<String>,
+}
+
+impl TestRunnerConfigBuilder {
+    /// Config file to fall back to for unset values and per-service settings
+    pub fn config(mut self, config: &Config) -> Self {
+        self.config = Some(config.clone());
+        self
+    }
+
+    /// Services to test (e.g. "speech", "translator")
+    pub fn services<I, S>(mut self, services: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.services = services.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    pub fn region(mut self, region: impl Into<String>) -> Self {
+        self.region = Some(region.into());
+        self
+    }
+
+    pub fn cloud(mut self, cloud: Cloud) -> Self {
+        self.cloud = Some(cloud);
+        self
+    }
+
+    pub fn auth_method(mut self, auth_method: AuthMethod) -> Self {
+        self.auth_method = Some(auth_method);
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Time allowed for DNS resolution plus the TCP connect
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Time allowed for the TLS handshake
+    pub fn tls_timeout(mut self, timeout: Duration) -> Self {
+        self.tls_timeout = Some(timeout);
+        self
+    }
 
-        let region = region
+    /// Custom endpoint instead of the regional one
+    pub fn endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = Some(endpoint.into());
+        self
+    }
+
+    /// Audio, image, or document sent by scenarios that need input
+    pub fn input_file(mut self, input_file: impl Into<String>) -> Self {
+        self.input_file = Some(input_file.into());
+        self
+    }
+
+    /// Audio sent by speech scenarios
+    pub fn audio_file(mut self, audio_file: impl Into<String>) -> Self {
+        self.inputs.audio_file = Some(audio_file.into());
+        self
+    }
+
+    /// Image sent by vision scenarios
+    pub fn image_file(mut self, image_file: impl Into<String>) -> Self {
+        self.inputs.image_file = Some(image_file.into());
+        self
+    }
+
+    /// Document sent by Document Intelligence scenarios
+    pub fn document_file(mut self, document_file: impl Into<String>) -> Self {
+        self.inputs.document_file = Some(document_file.into());
+        self
+    }
+
+    /// Text sent by language and translator scenarios
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.inputs.text = Some(text.into());
+        self
+    }
+
+    /// Run only these scenarios: ids, or `tag:<tag>` for every scenario with the tag
+    pub fn scenarios<I, S>(mut self, scenarios: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.scenarios = Some(scenarios.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Don't run these scenarios: ids, or `tag:<tag>` for every scenario with the tag
+    pub fn skip_scenarios<I, S>(mut self, scenarios: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.skip_scenarios = scenarios.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Run the scenario set of a named profile (`smoke`, `standard`, `full`,
+    /// or one defined under `[test_profiles]`)
+    pub fn profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = Some(profile.into());
+        self
+    }
+
+    /// Tenant for Entra ID auth, overriding the config file
+    pub fn tenant(mut self, tenant: impl Into<String>) -> Self {
+        self.tenant = Some(tenant.into());
+        self
+    }
+
+    /// Scope or audience to request Entra ID tokens for (see [`crate::auth::scope_for`])
+    pub fn scope(mut self, scope: impl Into<String>) -> Self {
+        self.scope = Some(scope.into());
+        self
+    }
+
+    /// Pre-acquired bearer token for token auth
+    pub fn bearer_token(mut self, bearer_token: impl Into<String>) -> Self {
+        self.bearer_token = Some(bearer_token.into());
+        self
+    }
+
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Hide progress indicators while authenticating
+    pub fn quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// Skip reading cached tokens from disk
+    pub fn no_cache(mut self, no_cache: bool) -> Self {
+        self.no_cache = no_cache;
+        self
+    }
+
+    /// Redirect every request to a [`crate::mock::MockServer`]
+    pub fn mock(mut self, addr: SocketAddr) -> Self {
+        self.mock = Some(addr);
+        self
+    }
+
+    /// Treat the endpoint as an on-prem Azure AI container
+    pub fn container(mut self, container: bool) -> Self {
+        self.container = container;
+        self
+    }
+
+    /// Accept plain `http://` endpoints, e.g. a container on a private network
+    pub fn allow_insecure_http(mut self, allow: bool) -> Self {
+        self.allow_insecure_http = allow;
+        self
+    }
+
+    /// Send a spoken phrase, an image with text, and a PDF with text when no
+    /// input file is given, and check what the services recognize in them
+    pub fn realistic_payloads(mut self, realistic_payloads: bool) -> Self {
+        self.realistic_payloads = realistic_payloads;
+        self
+    }
+
+    /// Check that Document Intelligence recognizes the expected content of
+    /// the input document (`[custom_inputs] expected_content`)
+    pub fn verify_content(mut self, verify_content: bool) -> Self {
+        self.verify_content = verify_content;
+        self
+    }
+
+    /// Poll Document Intelligence and summarization jobs for an extended period
+    pub fn long_poll(mut self, long_poll: LongPoll) -> Self {
+        self.long_poll = Some(long_poll);
+        self
+    }
+
+    /// Switch scenarios to the preview versions of their APIs
+    pub fn preview_apis(mut self, preview_apis: bool) -> Self {
+        self.preview_apis = preview_apis;
+        self
+    }
+
+    /// Check that the public endpoints are blocked while the services are
+    /// tested through a private endpoint
+    pub fn assert_blocked(mut self, assert_blocked: bool) -> Self {
+        self.assert_blocked = assert_blocked;
+        self
+    }
+
+    /// Pricing tier of the resource (e.g., "F0")
+    pub fn sku(mut self, sku: impl Into<String>) -> Self {
+        self.sku = Some(sku.into());
+        self
+    }
+
+    pub fn build(self) -> TestRunnerConfig {
+        let config = self.config.unwrap_or_else(Config::default_config);
+        let services = self.services;
+
+        // Fall back to the first selected service that has a key or region
+        let api_key = self.api_key.or_else(|| {
+            services
+                .iter()
+                .filter_map(|s| config.services.get(s))
+                .find_map(|svc| svc.api_key.clone())
+        });
+        let region = self
+            .region
             .or_else(|| {
-                // Try to find a region from any configured service
-                for service_name in &services {
-                    if let Some(svc) = config.services.get(service_name) {
-                        if svc.region.is_some() {
-                            return svc.region.clone();
-                        }
-                    }
-                }
-                None
+                services
+                    .iter()
+                    .filter_map(|s| config.services.get(s))
+                    .find_map(|svc| svc.region.clone())
             })
             .unwrap_or_else(|| "eastus".to_string());
 
-        // Build user auth config
         let mut user_config = config.auth.user.clone();
-        // Override with CLI args if provided
-        if tenant.is_some() {
-            user_config.tenant_id = tenant;
+        if self.tenant.is_some() {
+            user_config.tenant_id = self.tenant;
         }
-        if bearer_token.is_some() {
-            user_config.bearer_token = bearer_token;
+        if self.bearer_token.is_some() {
+            user_config.bearer_token = self.bearer_token;
         }
 
-        Self {
+        TestRunnerConfig {
             services,
             api_key,
             region,
-            cloud: cloud.unwrap_or(config.global.cloud),
-            auth_method: auth_method.unwrap_or(config.auth.default_method),
-            timeout: Duration::from_secs(timeout.unwrap_or(config.global.timeout_seconds)),
-            endpoint,
-            input_file: input_file.or(config.custom_inputs.audio_file.clone()),
-            scenarios,
+            regions: Vec::new(),
+            targets: Vec::new(),
+            cloud: self.cloud.unwrap_or(config.global.cloud),
+            auth_method: self.auth_method.unwrap_or(config.auth.default_method),
+            timeout: self
+                .timeout
+                .unwrap_or(Duration::from_secs(config.global.timeout_seconds)),
+            connect_timeout: self.connect_timeout.unwrap_or(Duration::from_secs(
+                config
+                    .global
+                    .connect_timeout_seconds
+                    .unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECS),
+            )),
+            tls_timeout: self.tls_timeout.unwrap_or(Duration::from_secs(
+                config
+                    .global
+                    .tls_timeout_seconds
+                    .unwrap_or(DEFAULT_TLS_TIMEOUT_SECS),
+            )),
+            endpoint: self.endpoint,
+            input_file: self.input_file,
+            inputs: CustomInputs {
+                audio_file: self.inputs.audio_file.or(config.custom_inputs.audio_file),
+                image_file: self.inputs.image_file.or(config.custom_inputs.image_file),
+                document_file: self
+                    .inputs
+                    .document_file
+                    .or(config.custom_inputs.document_file),
+                text: self.inputs.text.or(config.custom_inputs.text),
+                expected_content: config.custom_inputs.expected_content,
+            },
+            scenarios: self.scenarios,
+            skip_scenarios: self.skip_scenarios,
+            profile: self.profile,
+            test_profiles: config.test_profiles.clone(),
             entra_config: Some(config.auth.entra.clone()),
             user_config: Some(user_config),
-            verbose,
-            quiet,
-            show_token,
-            no_cache,
+            scope: self
+                .scope
+                .or(config.auth.scope)
+                .map(|scope| crate::auth::scope_for(&scope)),
+            verbose: self.verbose,
+            quiet: self.quiet,
+            no_cache: self.no_cache,
+            max_rps: config.global.max_rps,
+            retry_throttled: config.global.retry_throttled,
+            fail_on_throttle: config.global.fail_on_throttle,
+            max_latency_ms: config.global.max_latency_ms,
+            fail_on_slow: config.global.fail_on_slow,
+            expectations: config.expectations.clone(),
+            headers: config
+                .global
+                .headers
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+            api_key_header: config.global.api_key_header.clone(),
+            capture_dir: None,
+            artifacts_dir: None,
+            mock: self.mock,
+            container: self.container,
+            allow_insecure_http: self.allow_insecure_http || config.global.allow_insecure_http,
+            realistic_payloads: self.realistic_payloads,
+            verify_content: self.verify_content,
+            long_poll: self.long_poll,
+            preview_apis: self.preview_apis,
+            assert_blocked: self.assert_blocked,
+            sku: self.sku,
+            custom_scenarios: config.custom_scenarios,
+            service_configs: config.services,
+        }
+    }
+}
+
+impl TestRunnerConfig {
+    /// Test each service against its named resources (`--target`) instead of
+    /// the default endpoint. Every target must be defined for at least one of
+    /// the selected services.
+    pub fn select_targets(&mut self, targets: Vec<String>) -> Result<()> {
+        let resources = |target: &str| -> Vec<&ResourceConfig> {
+            self.services
+                .iter()
+                .filter_map(|s| self.service_configs.get(s))
+                .filter_map(|c| c.resources.get(target))
+                .collect()
+        };
+
+        for target in &targets {
+            if resources(target).is_empty() {
+                return Err(AppError::Config(format!(
+                    "Target '{}' is not defined for any selected service \
+                     (add [services.<name>.resources.{}] to the config file)",
+                    target, target
+                )));
+            }
         }
+
+        // Key auth needs a key up front; resources without one fall back to it
+        if self.api_key.is_none() {
+            self.api_key = targets
+                .iter()
+                .flat_map(|t| resources(t))
+                .find_map(|r| r.api_key.clone());
+        }
+        self.targets = targets;
+        Ok(())
+    }
+}
+
+impl TestRunnerConfig {
+    /// Check the endpoints each service will be tested against: plain HTTP
+    /// needs `allow_insecure_http`, a container needs an endpoint and a
+    /// service that ships as a container, and `assert_blocked` needs the
+    /// private endpoint of each service with a public one
+    pub fn check_endpoints(&self) -> Result<()> {
+        for name in &self.services {
+            let Some(service) = get_service(name) else {
+                continue;
+            };
+            let service_config = self.service_configs.get(name);
+            let mut endpoints: Vec<&String> = self.endpoint.iter().collect();
+            if let Some(service_config) = service_config {
+                endpoints.extend(
+                    self.targets
+                        .iter()
+                        .filter_map(|t| service_config.resources.get(t))
+                        .filter_map(|r| r.endpoint.as_ref()),
+                );
+            }
+            let is_container = self.container || service_config.is_some_and(|c| c.container);
+            if is_container && endpoints.is_empty() {
+                endpoints.extend(service_config.and_then(|c| c.endpoint.as_ref()));
+            }
+            for endpoint in &endpoints {
+                container::check_endpoint_scheme(endpoint, self.allow_insecure_http)?;
+                for warning in crate::services::endpoints::validate_endpoint(endpoint, Some(name))?
+                {
+                    tracing::warn!(service = %name, endpoint = %endpoint, "{}", warning);
+                }
+            }
+
+            if self.assert_blocked
+                && endpoints.is_empty()
+                && !public_endpoints::public_hosts(
+                    std::slice::from_ref(name),
+                    std::slice::from_ref(&self.region),
+                    self.cloud,
+                )
+                .is_empty()
+            {
+                return Err(AppError::InvalidInput(format!(
+                    "--assert-blocked tests {} through its private endpoint; give it with --endpoint \
+                     or a --target resource's endpoint",
+                    service.display_name()
+                )));
+            }
+
+            if is_container {
+                if service.container_scenarios().is_empty() {
+                    return Err(AppError::InvalidInput(format!(
+                        "{} has no Azure AI container; --container applies to speech, language, and translator",
+                        service.display_name()
+                    )));
+                }
+                if endpoints.is_empty() {
+                    return Err(AppError::InvalidInput(format!(
+                        "Testing {} as a container needs its address, e.g. --endpoint http://localhost:5000",
+                        service.display_name()
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace `keyvault:` references in API keys (the key in use, and each
+    /// service's and named resource's `api_key`) with the secrets' values
+    pub async fn resolve_secrets(&mut self) -> Result<()> {
+        let mut keys: Vec<&mut String> = self.api_key.iter_mut().collect();
+        for service in self.service_configs.values_mut() {
+            keys.extend(service.api_key.iter_mut());
+            for resource in service.resources.values_mut() {
+                keys.extend(resource.api_key.iter_mut());
+            }
+        }
+        keys.retain(|key| keyvault::is_reference(key));
+        if keys.is_empty() {
+            return Ok(());
+        }
+
+        let token = keyvault::get_vault_token(
+            self.auth_method,
+            self.cloud,
+            self.entra_config.as_ref(),
+            self.user_config.as_ref(),
+            self.quiet,
+        )
+        .await?;
+        let mut client = keyvault::KeyVaultClient::new(token, self.timeout)?;
+        for key in keys {
+            let reference = keyvault::SecretReference::parse(key)?;
+            tracing::debug!(secret = %reference, "reading API key from Key Vault");
+            *key = client.get_secret(&reference).await?;
+            redact::register_secret(key);
+        }
+        Ok(())
     }
 }
 
 /// Test runner
 pub struct TestRunner {
     config: TestRunnerConfig,
+    reporters: Vec<Arc<dyn ProgressReporter>>,
+    capture: Option<Arc<CaptureLog>>,
+    interrupt: Interrupt,
 }
 
 impl TestRunner {
     pub fn new(config: TestRunnerConfig) -> Self {
-        Self { config }
+        let secrets = config
+            .api_key
+            .iter()
+            .chain(config.entra_config.iter().flat_map(|c| &c.client_secret))
+            .chain(config.user_config.iter().flat_map(|c| &c.bearer_token))
+            .chain(config.service_configs.values().flat_map(|service| {
+                service
+                    .api_key
+                    .iter()
+                    .chain(service.resources.values().flat_map(|r| &r.api_key))
+            }));
+        for secret in secrets {
+            redact::register_secret(secret);
+        }
+        Self {
+            config,
+            reporters: Vec::new(),
+            capture: None,
+            interrupt: Interrupt::new(),
+        }
     }
 
-    /// Load input file if specified
-    fn load_input(&self) -> Result<Option<TestInput>> {
-        let path_str = match &self.config.input_file {
-            Some(p) => p,
-            None => return Ok(None),
-        };
+    /// Report live progress to `reporter`; may be called more than once to
+    /// add listeners
+    pub fn with_reporter(mut self, reporter: Arc<dyn ProgressReporter>) -> Self {
+        self.reporters.push(reporter);
+        self
+    }
 
-        let path = Path::new(path_str);
+    /// Send live progress events (service/scenario start and finish) to a
+    /// channel, for listeners running as their own task
+    pub fn with_events(self, events: EventSender) -> Self {
+        self.with_reporter(Arc::new(events))
+    }
 
-        // Canonicalize to resolve symlinks and ../ sequences (prevents path traversal)
-        let canonical = path.canonicalize().map_err(|e| {
-            if e.kind() == std::io::ErrorKind::NotFound {
-                AppError::FileNotFound(path_str.clone())
-            } else {
-                AppError::InvalidInput(format!("Invalid path '{}': {}", path_str, e))
-            }
-        })?;
-
-        // Check file size limit
-        let metadata = std::fs::metadata(&canonical)?;
-        if metadata.len() > MAX_INPUT_FILE_SIZE {
-            return Err(AppError::InvalidInput(format!(
-                "File exceeds 10MB limit ({} bytes)",
-                metadata.len()
-            )));
+    /// Record every request in `capture` instead of a log created from
+    /// `capture_dir`, so the caller can read the exchanges afterwards
+    pub fn with_capture(mut self, capture: Arc<CaptureLog>) -> Self {
+        self.capture = Some(capture);
+        self
+    }
+
+    /// Stop the run when `interrupt` is triggered. The runner installs no
+    /// signal handlers of its own; the caller decides what triggers it.
+    pub fn with_interrupt(mut self, interrupt: Interrupt) -> Self {
+        self.interrupt = interrupt;
+        self
+    }
+
+    fn emit(&self, event: RunEvent) {
+        for reporter in &self.reporters {
+            event.report_to(reporter.as_ref());
         }
+    }
 
-        let data = std::fs::read(&canonical)?;
-        let extension = canonical
-            .extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("")
-            .to_lowercase();
-
-        let content_type = match extension.as_str() {
-            "wav" => "audio/wav",
-            "mp3" => "audio/mpeg",
-            "ogg" => "audio/ogg",
-            "png" => "image/png",
-            "jpg" | "jpeg" => "image/jpeg",
-            "gif" => "image/gif",
-            "bmp" => "image/bmp",
-            "pdf" => "application/pdf",
-            "tiff" | "tif" => "image/tiff",
-            _ => "application/octet-stream",
-        };
+    /// Whether the run's credentials are validated before the scenarios:
+    /// not for containers, which take none, or for custom probes only, which
+    /// may send them anywhere
+    fn runs_preflight(&self) -> bool {
+        let builtin = ServiceRegistry::new();
+        !self.config.container
+            && self
+                .config
+                .services
+                .iter()
+                .any(|name| builtin.get(name).is_some())
+    }
+
+    /// Load the input files and text given for the run, by input type
+    fn load_input(&self) -> Result<TestInputs> {
+        let inputs = &self.config.inputs;
+        Ok(TestInputs {
+            audio: load_typed_input(inputs.audio_file.as_deref(), InputType::Audio)?,
+            image: load_typed_input(inputs.image_file.as_deref(), InputType::Image)?,
+            document: load_typed_input(inputs.document_file.as_deref(), InputType::Document)?,
+            text: inputs.text.clone().map(TestInput::text),
+            any: self
+                .config
+                .input_file
+                .as_deref()
+                .map(load_input_file)
+                .transpose()?,
+        })
+    }
 
-        Ok(Some(TestInput {
-            data,
-            content_type: content_type.to_string(),
-            file_name: canonical
-                .file_name()
-                .map(|n| n.to_string_lossy().to_string()),
-            text: None,
-        }))
+    /// Strings the input document's analysis must contain under
+    /// --verify-content; an error when it has none configured
+    fn expected_content(&self, inputs: &TestInputs) -> Result<Vec<String>> {
+        if !self.config.verify_content {
+            return Ok(Vec::new());
+        }
+        let file_name = inputs
+            .get(InputType::Document)
+            .and_then(|input| input.file_name.as_deref())
+            .ok_or_else(|| {
+                AppError::InvalidInput(
+                    "--verify-content needs a document with known content (--document-file)"
+                        .to_string(),
+                )
+            })?;
+        self.config
+            .inputs
+            .expected_content
+            .iter()
+            .find(|(key, _)| Path::new(key).file_name().and_then(|n| n.to_str()) == Some(file_name))
+            .map(|(_, expected)| expected.clone())
+            .filter(|expected| !expected.is_empty())
+            .ok_or_else(|| {
+                AppError::InvalidInput(format!(
+                    "--verify-content: no expected_content for '{}' in [custom_inputs]",
+                    file_name
+                ))
+            })
     }
 
     /// Get credentials based on auth method
-    async fn get_credentials(&self) -> Result<Credentials> {
+    pub async fn get_credentials(&self) -> Result<Credentials> {
         // Try disk cache first for device-code auth (unless --no-cache)
         if !self.config.no_cache && self.config.auth_method == AuthMethod::DeviceCode {
             if let Ok(cache) = crate::auth::token_cache::TokenCacheFile::load() {
-                let scope = self.config.cloud.cognitive_scope();
+                let scope = self.config.token_scope();
                 let tenant_id = self
                     .config
                     .user_config
@@ -199,20 +784,26 @@ impl TestRunner {
                     .and_then(|c| c.tenant_id.as_deref())
                     .unwrap_or("");
                 if let Some(entry) = cache.get_valid_token(scope, tenant_id) {
-                    if !self.config.quiet {
-                        eprintln!(
-                            "  {} Using cached token ({} minutes remaining)",
-                            console::style("[*]").cyan(),
+                    tracing::debug!(
+                        remaining_minutes = entry.remaining_minutes(),
+                        "using cached token"
+                    );
+                    self.emit(RunEvent::Notice {
+                        message: format!(
+                            "Using cached token ({} minutes remaining)",
                             entry.remaining_minutes()
-                        );
-                    }
+                        ),
+                    });
+                    redact::register_secret(&entry.access_token);
                     return Ok(Credentials::BearerToken(entry.access_token.clone()));
                 }
             }
         }
 
+        tracing::debug!(method = ?self.config.auth_method, cloud = %self.config.cloud, "acquiring credentials");
+
         // Create AuthManager with the current configuration
-        let auth_manager = AuthManager::new_with_options(
+        let mut auth_manager = AuthManager::new_with_options(
             self.config.api_key.clone(),
             self.config.entra_config.as_ref(),
             self.config.user_config.as_ref(),
@@ -220,85 +811,327 @@ impl TestRunner {
             self.config.auth_method,
             self.config.quiet,
         )?;
+        if let Some(scope) = &self.config.scope {
+            auth_manager = auth_manager.with_scope(scope);
+        }
 
         // Get the provider and fetch credentials
         let provider = auth_manager.get_provider()?;
-        provider.get_credentials().await
+        let credentials = provider.get_credentials().await?;
+        redact::register_credentials(&credentials);
+        Ok(credentials)
     }
 
     /// Run tests for all configured services
     pub async fn run(&self) -> Result<TestReport> {
         let credentials = self.get_credentials().await?;
+        self.run_with_credentials(credentials).await
+    }
 
-        // Display token if requested
-        if self.config.show_token {
-            if let Credentials::BearerToken(ref token) = credentials {
-                eprintln!("{}", console::style("Bearer Token:").bold());
-                eprintln!("{}", token);
-                eprintln!();
-            }
-        }
-        let input = self.load_input()?;
+    /// Run tests for all configured services with already-acquired credentials
+    pub async fn run_with_credentials(&self, credentials: Credentials) -> Result<TestReport> {
+        self.config.check_endpoints()?;
+        let inputs = self.load_input()?;
+        let expected_content = self.expected_content(&inputs)?;
+        let selection = ScenarioSelection::new(
+            self.config.scenarios.clone(),
+            self.config.skip_scenarios.clone(),
+        )?;
+        let profile = match &self.config.profile {
+            Some(name) => Some(Profile::resolve(name, &self.config.test_profiles)?),
+            None => None,
+        };
 
         let mut all_results = Vec::new();
+        let mut service_runs = Vec::new();
 
-        // Create progress bar if not quiet
-        let progress = if !self.config.quiet {
-            let pb = ProgressBar::new(self.config.services.len() as u64);
-            pb.set_style(
-                ProgressStyle::default_bar()
-                    .template(
-                        "{spinner:.green} [{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} {msg}",
-                    )
-                    .unwrap()
-                    .progress_chars("##-"),
-            );
-            Some(pb)
+        // Tell listeners when an interrupt stops the run; the task ends with the run
+        let _notice = AbortOnDrop(tokio::spawn(notify_interrupt(
+            self.interrupt.clone(),
+            self.reporters.clone(),
+        )));
+
+        let regions = if self.config.regions.is_empty() {
+            vec![self.config.region.clone()]
         } else {
-            None
+            self.config.regions.clone()
         };
+        let sweep = !self.config.regions.is_empty();
 
-        for service_name in &self.config.services {
-            if let Some(pb) = &progress {
-                pb.set_message(format!("Testing {}", service_name));
-            }
+        let rate_limiter = self
+            .config
+            .max_rps
+            .filter(|rps| *rps > 0.0)
+            .map(|rps| Arc::new(RateLimiter::new(rps)));
 
-            let service = match get_service(service_name) {
-                Some(s) => s,
-                None => {
-                    if self.config.verbose {
-                        eprintln!("Unknown service: {}", service_name);
-                    }
-                    continue;
-                }
-            };
+        let capture = match (&self.capture, &self.config.capture_dir) {
+            (Some(capture), _) => Some(capture.clone()),
+            (None, Some(dir)) => Some(Arc::new(
+                CaptureLog::create(dir)?
+                    .with_sensitive_headers(self.config.api_key_header.iter().cloned().collect()),
+            )),
+            (None, None) => None,
+        };
+        let artifacts = self
+            .config
+            .artifacts_dir
+            .as_deref()
+            .map(ArtifactDir::create)
+            .transpose()?;
 
+        // Validate the credentials once, before any scenario sends them
+        let preflight = if self.runs_preflight() {
             let context = TestContext::new(
                 credentials.clone(),
                 self.config.cloud,
                 self.config.region.clone(),
                 self.config.timeout,
             )?
+            .with_phase_timeouts(self.config.connect_timeout, self.config.tls_timeout)?
             .with_endpoint(self.config.endpoint.clone())
-            .with_input(input.clone())
-            .with_verbose(self.config.verbose);
+            .with_rate_limiter(rate_limiter.clone())
+            .with_extra_headers(&self.config.headers, self.config.api_key_header.as_deref())?
+            .with_capture(capture.clone())
+            .with_mock(self.config.mock)
+            .with_reporters(self.reporters.clone());
+            tokio::select! {
+                results = preflight::validate(
+                    &context,
+                    self.config.auth_method,
+                    self.config.scope.as_deref(),
+                ) => Some(results),
+                _ = self.interrupt.grace_expired() => None,
+            }
+        } else {
+            None
+        };
+        let auth_rejected = preflight.as_ref().is_some_and(preflight::is_rejected);
+        all_results.extend(preflight);
+
+        // The public endpoints must be unreachable before the private one is tested
+        if self.config.assert_blocked {
+            let context = TestContext::new(
+                credentials.clone(),
+                self.config.cloud,
+                self.config.region.clone(),
+                self.config.timeout,
+            )?
+            .with_phase_timeouts(self.config.connect_timeout, self.config.tls_timeout)?
+            .with_capture(capture.clone())
+            .with_mock(self.config.mock)
+            .with_reporters(self.reporters.clone());
+            tokio::select! {
+                results = public_endpoints::check(&context, &self.config.services, &regions) => {
+                    all_results.push(results);
+                }
+                _ = self.interrupt.grace_expired() => {}
+            }
+        }
+
+        for region in &regions {
+            if self.interrupt.is_triggered() {
+                break;
+            }
+            for service_name in &self.config.services {
+                if self.interrupt.is_triggered() {
+                    break;
+                }
+                let service = get_service(service_name);
+                let is_custom = service_name == CUSTOM_SERVICE_NAME;
+                if service.is_none() && !is_custom {
+                    tracing::warn!(service = %service_name, "unknown service, skipping");
+                    continue;
+                }
+
+                // A profile narrows the scenarios per service
+                let selection = match profile.as_ref().and_then(|p| p.scenarios(service_name)) {
+                    Some(scenarios) => {
+                        ScenarioSelection::new(Some(scenarios.to_vec()), selection.exclude.clone())?
+                    }
+                    None => selection.clone(),
+                };
+
+                let service_config = self
+                    .config
+                    .service_configs
+                    .get(service_name)
+                    .cloned()
+                    .unwrap_or_default();
+                let timeout = service_config
+                    .timeout_seconds
+                    .map(Duration::from_secs)
+                    .unwrap_or(self.config.timeout);
+
+                let targets: Vec<Option<(&String, &ResourceConfig)>> =
+                    if self.config.targets.is_empty() || is_custom {
+                        vec![None]
+                    } else {
+                        self.config
+                            .targets
+                            .iter()
+                            .filter_map(|t| service_config.resources.get_key_value(t))
+                            .map(Some)
+                            .collect()
+                    };
+                if targets.is_empty() {
+                    tracing::info!(service = %service_name, "no selected target defined, skipping");
+                }
+
+                for target in targets {
+                    if self.interrupt.is_triggered() {
+                        break;
+                    }
+                    // Keep artifacts of each swept region and target apart
+                    let artifacts = artifacts.as_ref().map(|dir| {
+                        let dir = if sweep {
+                            dir.subdir(region)
+                        } else {
+                            dir.clone()
+                        };
+                        match target {
+                            Some((name, _)) => dir.subdir(name),
+                            None => dir,
+                        }
+                    });
+                    let mut region = region.clone();
+                    let mut endpoint = self.config.endpoint.clone();
+                    let mut credentials = credentials.clone();
+                    let mut auth_rejected = auth_rejected;
+                    let container = self.config.container || service_config.container;
+                    // A container's address may come from its [services] entry
+                    if container && endpoint.is_none() {
+                        endpoint = service_config.endpoint.clone();
+                    }
+                    if let Some((_, resource)) = target {
+                        if let Some(resource_region) = &resource.region {
+                            region = resource_region.clone();
+                        }
+                        if resource.endpoint.is_some() {
+                            endpoint = resource.endpoint.clone();
+                        }
+                        if let (Credentials::ApiKey(_), Some(key)) =
+                            (&credentials, &resource.api_key)
+                        {
+                            credentials = Credentials::ApiKey(key.clone());
+                            auth_rejected = false;
+                        }
+                    }
+
+                    let context =
+                        TestContext::new(credentials, self.config.cloud, region.clone(), timeout)?
+                            .with_phase_timeouts(
+                                self.config.connect_timeout,
+                                self.config.tls_timeout,
+                            )?
+                            .with_endpoint(endpoint)
+                            .with_inputs(inputs.clone())
+                            .with_verbose(self.config.verbose)
+                            .with_service_config(service_config.clone())
+                            .with_rate_limiter(rate_limiter.clone())
+                            .with_throttle_policy(
+                                self.config.retry_throttled,
+                                self.config.fail_on_throttle,
+                            )
+                            .with_latency_threshold(
+                                self.config.max_latency_ms,
+                                self.config.fail_on_slow,
+                            )
+                            .with_expectations(self.config.expectations.clone())
+                            .with_extra_headers(
+                                &self.config.headers,
+                                self.config.api_key_header.as_deref(),
+                            )?
+                            .with_capture(capture.clone())
+                            .with_mock(self.config.mock)
+                            .with_container(container)
+                            .with_realistic_payloads(self.config.realistic_payloads)
+                            .with_expected_content(expected_content.clone())
+                            .with_long_poll(self.config.long_poll)
+                            .with_preview_apis(self.config.preview_apis)
+                            .with_sku(self.config.sku.clone())
+                            .with_artifacts(artifacts)
+                            .with_auth_rejected(auth_rejected && !container)
+                            .with_reporters(self.reporters.clone())
+                            .with_interrupt(self.interrupt.clone());
 
-            let results = service
-                .run_all_scenarios(&context, self.config.scenarios.as_deref())
-                .await;
+                    let mut results = match &service {
+                        Some(service) => service.run_all_scenarios(&context, &selection).await,
+                        None => {
+                            CustomProbeService::new(self.config.custom_scenarios.clone())
+                                .run_all(&context, &selection)
+                                .await
+                        }
+                    };
 
-            all_results.push(results);
+                    if sweep {
+                        results.region = Some(region.clone());
+                    }
+                    results.target = target.map(|(name, _)| name.clone());
+                    service_runs.push(ServiceRunInfo {
+                        service: results.service_name.clone(),
+                        target: results.target.clone(),
+                        region,
+                        cloud: self.config.cloud,
+                        auth_method: (!container).then_some(self.config.auth_method),
+                    });
+                    all_results.push(results);
+                }
 
-            if let Some(pb) = &progress {
-                pb.inc(1);
+                self.emit(RunEvent::ServiceFinished {
+                    service: service_name.clone(),
+                });
             }
         }
 
-        if let Some(pb) = progress {
-            pb.finish_with_message("Complete");
+        let report = TestReport::new(all_results).with_metadata(RunMetadata::new(service_runs));
+        // Without a known SKU, free-tier errors still reveal it
+        let sku = self.config.sku.clone().or_else(|| {
+            report
+                .services
+                .iter()
+                .flat_map(|s| &s.results)
+                .filter_map(|r| r.error.as_deref())
+                .find_map(capabilities::sku_from_error)
+                .map(|sku| format!("{} (inferred from errors)", sku))
+        });
+        let report = match sku {
+            Some(sku) => report.with_sku(sku),
+            None => report,
+        };
+        let report = if self.interrupt.is_triggered() {
+            report.with_interrupted()
+        } else {
+            report
+        };
+        for reporter in &self.reporters {
+            reporter.on_run_complete(&report);
         }
+        Ok(report)
+    }
+}
+
+/// Aborts a task when dropped, so it can't outlive the run or command that spawned it
+pub struct AbortOnDrop(pub tokio::task::JoinHandle<()>);
 
-        Ok(TestReport::new(all_results))
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Report a notice once `interrupt` is first triggered: the run stops after
+/// the running scenario unless it is triggered again
+async fn notify_interrupt(interrupt: Interrupt, reporters: Vec<Arc<dyn ProgressReporter>>) {
+    interrupt.triggered(1).await;
+    let notice = RunEvent::Notice {
+        message: format!(
+            "Interrupted: finishing the running scenario (up to {}s) for a partial report; \
+             interrupt again to stop now",
+            INTERRUPT_GRACE.as_secs()
+        ),
+    };
+    for reporter in &reporters {
+        notice.report_to(reporter.as_ref());
     }
 }
 
@@ -347,20 +1180,107 @@ pub fn format_scenarios(scenarios: &[(String, Vec<crate::services::TestScenario>
             } else {
                 String::new()
             };
+            let tags = if scenario.tags.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", scenario.tags.join(", "))
+            };
 
             output.push_str(&format!(
-                "  {} - {}{}\n",
+                "  {} - {}{}{}\n",
                 style(scenario.id).cyan(),
                 scenario.description,
-                style(input_marker).dim()
+                style(input_marker).dim(),
+                style(tags).dim()
             ));
         }
         output.push('\n');
     }
+    output.push_str(&format!(
+        "Select by tag with --scenarios tag:<tag> or --skip-scenarios tag:<tag> (tags: {})\n",
+        crate::services::tags::ALL.join(", ")
+    ));
 
     output
 }
 
+/// Read an input file, with the content type its extension implies
+fn load_input_file(path_str: &str) -> Result<TestInput> {
+    let path = Path::new(path_str);
+
+    // Canonicalize to resolve symlinks and ../ sequences (prevents path traversal)
+    let canonical = path.canonicalize().map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            AppError::FileNotFound(path_str.to_string())
+        } else {
+            AppError::InvalidInput(format!("Invalid path '{}': {}", path_str, e))
+        }
+    })?;
+
+    // Check file size limit
+    let metadata = std::fs::metadata(&canonical)?;
+    if metadata.len() > MAX_INPUT_FILE_SIZE {
+        return Err(AppError::InvalidInput(format!(
+            "File exceeds 10MB limit ({} bytes)",
+            metadata.len()
+        )));
+    }
+
+    let data = std::fs::read(&canonical)?;
+    let extension = canonical
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    let content_type = match extension.as_str() {
+        "wav" => "audio/wav",
+        "mp3" => "audio/mpeg",
+        "ogg" => "audio/ogg",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        "pdf" => "application/pdf",
+        "tiff" | "tif" => "image/tiff",
+        _ => "application/octet-stream",
+    };
+
+    Ok(TestInput {
+        data,
+        content_type: content_type.to_string(),
+        file_name: canonical
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string()),
+        text: None,
+    })
+}
+
+/// Read the input file for `input_type`, refusing files of another kind
+/// (an image given as `--audio-file`). Document models also accept images.
+fn load_typed_input(path_str: Option<&str>, input_type: InputType) -> Result<Option<TestInput>> {
+    let Some(path_str) = path_str else {
+        return Ok(None);
+    };
+    let input = load_input_file(path_str)?;
+    let content_type = input.content_type.as_str();
+    let accepted = match input_type {
+        InputType::Audio => content_type.starts_with("audio/"),
+        InputType::Image => content_type.starts_with("image/"),
+        InputType::Document => {
+            content_type == "application/pdf" || content_type.starts_with("image/")
+        }
+        InputType::Text => true,
+    };
+    if !accepted && content_type != "application/octet-stream" {
+        return Err(AppError::InvalidInput(format!(
+            "{} is {}, not {} input",
+            path_str, content_type, input_type
+        )));
+    }
+    Ok(Some(input))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -382,4 +1302,151 @@ mod tests {
         assert_eq!(scenarios.len(), 1);
         assert_eq!(scenarios[0].0, "Speech");
     }
+
+    #[test]
+    fn test_select_targets() {
+        let config: Config = toml::from_str(
+            r#"
+            [services.speech.resources.prod]
+            endpoint = "https://contoso-speech-prod.cognitiveservices.azure.com"
+            api_key = "prod-key"
+
+            [services.speech.resources.staging]
+            region = "westeurope"
+            "#,
+        )
+        .unwrap();
+        let runner_config = || {
+            TestRunnerConfig::builder()
+                .config(&config)
+                .services(["speech"])
+                .quiet(true)
+                .build()
+        };
+
+        let mut selected = runner_config();
+        selected
+            .select_targets(vec!["staging".to_string(), "prod".to_string()])
+            .unwrap();
+        assert_eq!(selected.targets, vec!["staging", "prod"]);
+        assert_eq!(selected.api_key.as_deref(), Some("prod-key"));
+
+        let err = runner_config()
+            .select_targets(vec!["qa".to_string()])
+            .unwrap_err();
+        assert!(err.to_string().contains("Target 'qa'"));
+    }
+
+    #[test]
+    fn test_load_typed_inputs() {
+        let sample = |name: &str| format!("{}/samples/{}", env!("CARGO_MANIFEST_DIR"), name);
+        let runner = TestRunner::new(
+            TestRunnerConfig::builder()
+                .audio_file(sample("test-speech.wav"))
+                .image_file(sample("test-image.png"))
+                .input_file(sample("test-document.pdf"))
+                .text("Bonjour")
+                .quiet(true)
+                .build(),
+        );
+        let inputs = runner.load_input().unwrap();
+        let content_type = |t| inputs.get(t).map(|i| i.content_type.as_str());
+        assert_eq!(content_type(InputType::Audio), Some("audio/wav"));
+        assert_eq!(content_type(InputType::Image), Some("image/png"));
+        // --input-file fills in for types without their own input
+        assert_eq!(content_type(InputType::Document), Some("application/pdf"));
+        assert_eq!(
+            inputs.get(InputType::Text).and_then(|i| i.text.as_deref()),
+            Some("Bonjour")
+        );
+
+        let err = load_typed_input(Some(&sample("test-image.png")), InputType::Audio).unwrap_err();
+        assert!(err.to_string().contains("not audio input"), "{}", err);
+        assert!(load_typed_input(Some(&sample("test-image.png")), InputType::Document).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_verify_content_against_mock() {
+        let mock = crate::mock::MockServer::start().await.unwrap();
+        let document = format!("{}/samples/test-document.pdf", env!("CARGO_MANIFEST_DIR"));
+        let run = |expected: &[&str]| {
+            let mut config = Config::default_config();
+            config.custom_inputs.expected_content.insert(
+                "samples/test-document.pdf".to_string(),
+                expected.iter().map(|s| s.to_string()).collect(),
+            );
+            TestRunner::new(
+                TestRunnerConfig::builder()
+                    .config(&config)
+                    .services(["document_intelligence"])
+                    .scenarios(["read"])
+                    .auth_method(AuthMethod::Key)
+                    .api_key(crate::mock::MOCK_API_KEY)
+                    .region("eastus")
+                    .document_file(document.clone())
+                    .verify_content(true)
+                    .quiet(true)
+                    .mock(mock.addr())
+                    .build(),
+            )
+        };
+
+        let read = |report: TestReport| {
+            report
+                .services
+                .into_iter()
+                .find(|s| s.service_name != preflight::SECTION_NAME)
+                .and_then(|s| s.results.into_iter().next())
+                .unwrap()
+        };
+        let result = read(run(&["document intelligence test"]).run().await.unwrap());
+        assert!(result.success, "{:?}", result.error);
+        let result = read(run(&["Contoso"]).run().await.unwrap());
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("\"Contoso\""));
+
+        let err = run(&[]).run().await.unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("no expected_content for 'test-document.pdf'"));
+    }
+
+    #[tokio::test]
+    async fn test_interrupt_stops_run_with_notice() {
+        #[derive(Default)]
+        struct Notices(std::sync::Mutex<Vec<String>>);
+        impl ProgressReporter for Notices {
+            fn on_notice(&self, message: &str) {
+                self.0.lock().unwrap().push(message.to_string());
+            }
+        }
+
+        let mock = crate::mock::MockServer::start().await.unwrap();
+        let notices = Arc::new(Notices::default());
+        let interrupt = Interrupt::new();
+        interrupt.trigger();
+        let report = TestRunner::new(
+            TestRunnerConfig::builder()
+                .services(["translator"])
+                .auth_method(AuthMethod::Key)
+                .api_key(crate::mock::MOCK_API_KEY)
+                .region("eastus")
+                .quiet(true)
+                .mock(mock.addr())
+                .build(),
+        )
+        .with_reporter(notices.clone())
+        .with_interrupt(interrupt)
+        .run()
+        .await
+        .unwrap();
+
+        assert!(report.interrupted);
+        let notices = notices.0.lock().unwrap();
+        assert!(
+            notices.iter().any(|n| n.starts_with("Interrupted:")),
+            "{:?}",
+            notices
+        );
+    }
 }
@@ -0,0 +1,481 @@
+//! What a run would do, without doing it (`test --dry-run`, `test --plan`)
+//!
+//! Resolves the settings from the config file, environment, and CLI the same
+//! way a real run does, and lists the scenarios each service would execute,
+//! without authenticating or sending any request. `--plan` adds the method,
+//! URL and payload of every request from the route table.
+
+use super::TestRunner;
+use crate::config::{AuthMethod, Cloud};
+use crate::error::Result;
+use crate::keyvault;
+use crate::services::capabilities::{self, Availability};
+use crate::services::custom::CUSTOM_SERVICE_NAME;
+use crate::services::profiles::Profile;
+use crate::services::routes::{self, Payload, UrlContext};
+use crate::services::{get_service, ScenarioSelection};
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+/// A scenario a run would execute
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedScenario {
+    pub id: String,
+    pub name: String,
+    /// HTTP method, or `WebSocket`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    /// Request URL; `{...}` parts depend on scenario settings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload: Option<Payload>,
+    /// Why the scenario would be skipped or is likely to be
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// One service, in one region, against one target
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedService {
+    pub service: String,
+    pub region: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub endpoint: String,
+    pub scenarios: Vec<PlannedScenario>,
+}
+
+/// Effective settings and planned scenarios of a run
+#[derive(Debug, Clone, Serialize)]
+pub struct RunPlan {
+    pub cloud: Cloud,
+    pub auth_method: AuthMethod,
+    pub regions: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    /// Where the API key comes from, never the key itself
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    pub timeout_secs: u64,
+    pub connect_timeout_secs: u64,
+    pub tls_timeout_secs: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_rps: Option<f64>,
+    /// Names of the extra headers sent with every request
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<String>,
+    pub container: bool,
+    pub preview_apis: bool,
+    pub services: Vec<PlannedService>,
+    /// Every host the planned requests reach, sorted
+    pub hosts: Vec<String>,
+}
+
+impl TestRunner {
+    /// The run this runner would perform, resolved without network access
+    pub fn plan(&self) -> Result<RunPlan> {
+        let config = &self.config;
+        let inputs = self.load_input()?;
+        let selection =
+            ScenarioSelection::new(config.scenarios.clone(), config.skip_scenarios.clone())?;
+        let profile = match &config.profile {
+            Some(name) => Some(Profile::resolve(name, &config.test_profiles)?),
+            None => None,
+        };
+        let regions = if config.regions.is_empty() {
+            vec![config.region.clone()]
+        } else {
+            config.regions.clone()
+        };
+
+        let mut services = Vec::new();
+        for region in &regions {
+            for service_name in &config.services {
+                let selection = match profile.as_ref().and_then(|p| p.scenarios(service_name)) {
+                    Some(scenarios) => {
+                        ScenarioSelection::new(Some(scenarios.to_vec()), selection.exclude.clone())?
+                    }
+                    None => selection.clone(),
+                };
+
+                if service_name == CUSTOM_SERVICE_NAME {
+                    services.push(PlannedService {
+                        service: service_name.clone(),
+                        region: region.clone(),
+                        target: None,
+                        endpoint: config
+                            .endpoint
+                            .clone()
+                            .unwrap_or_else(|| "(per-scenario URL)".to_string()),
+                        scenarios: config
+                            .custom_scenarios
+                            .iter()
+                            .filter(|probe| selection.selects(&probe.name, &[]))
+                            .map(|probe| PlannedScenario {
+                                id: probe.name.clone(),
+                                name: probe.description.clone().unwrap_or(probe.name.clone()),
+                                method: Some(probe.method.to_uppercase()),
+                                url: Some(probe.url_template.clone()),
+                                payload: Some(
+                                    if probe.body.is_some() || probe.body_file.is_some() {
+                                        Payload::Custom
+                                    } else {
+                                        Payload::None
+                                    },
+                                ),
+                                note: None,
+                            })
+                            .collect(),
+                    });
+                    continue;
+                }
+                let Some(service) = get_service(service_name) else {
+                    continue;
+                };
+
+                let service_config = config
+                    .service_configs
+                    .get(service_name)
+                    .cloned()
+                    .unwrap_or_default();
+                let container = config.container || service_config.container;
+                let targets: Vec<Option<&String>> = if config.targets.is_empty() {
+                    vec![None]
+                } else {
+                    config
+                        .targets
+                        .iter()
+                        .filter(|t| service_config.resources.contains_key(*t))
+                        .map(Some)
+                        .collect()
+                };
+
+                for target in targets {
+                    let resource = target.and_then(|t| service_config.resources.get(t));
+                    let region = resource
+                        .and_then(|r| r.region.clone())
+                        .unwrap_or_else(|| region.clone());
+                    let mut endpoint = resource
+                        .and_then(|r| r.endpoint.clone())
+                        .or_else(|| config.endpoint.clone());
+                    if container && endpoint.is_none() {
+                        endpoint = service_config.endpoint.clone();
+                    }
+
+                    let service_endpoint =
+                        service.get_endpoint(&region, config.cloud, endpoint.as_deref());
+                    let url_context = UrlContext {
+                        endpoint: &service_endpoint,
+                        region: &region,
+                        cloud: config.cloud,
+                        custom_endpoint: endpoint.is_some(),
+                        api_version: service_config.api_version.as_deref(),
+                        preview_apis: config.preview_apis,
+                    };
+
+                    let scenarios = service
+                        .selected_scenarios(&selection, container)
+                        .into_iter()
+                        .map(|scenario| {
+                            let missing_input = scenario
+                                .input_type
+                                .filter(|t| scenario.requires_input && inputs.get(*t).is_none());
+                            let note = match missing_input {
+                                Some(input_type) => {
+                                    Some(format!("skipped: requires {} input", input_type))
+                                }
+                                None if container => None,
+                                None => match capabilities::check(
+                                    service_name,
+                                    scenario.id,
+                                    &region,
+                                    config.cloud,
+                                ) {
+                                    Availability::Unavailable { feature, .. } => {
+                                        Some(format!("{} is not offered in {}", feature, region))
+                                    }
+                                    Availability::Available => {
+                                        config.sku.as_deref().and_then(|sku| {
+                                            capabilities::check_sku(service_name, scenario.id, sku)
+                                                .map(|feature| {
+                                                    format!("{} is not offered on {}", feature, sku)
+                                                })
+                                        })
+                                    }
+                                },
+                            };
+                            let route = routes::lookup(service_name, scenario.id);
+                            PlannedScenario {
+                                id: scenario.id.to_string(),
+                                name: scenario.name.to_string(),
+                                method: route.map(|r| r.method.to_string()),
+                                url: route.map(|r| r.url(&url_context)),
+                                payload: route.map(|r| r.payload),
+                                note,
+                            }
+                        })
+                        .collect();
+
+                    services.push(PlannedService {
+                        service: service_name.clone(),
+                        endpoint: service_endpoint,
+                        region,
+                        target: target.cloned(),
+                        scenarios,
+                    });
+                }
+            }
+        }
+
+        let api_key = config.api_key.as_deref().map(|key| {
+            if key.is_empty() {
+                "(none)".to_string()
+            } else if keyvault::is_reference(key) {
+                format!("Key Vault secret {}", key)
+            } else {
+                "set".to_string()
+            }
+        });
+        let tenant = config
+            .user_config
+            .as_ref()
+            .and_then(|u| u.tenant_id.clone())
+            .or_else(|| {
+                config
+                    .entra_config
+                    .as_ref()
+                    .and_then(|e| e.tenant_id.clone())
+            });
+
+        let hosts: BTreeSet<String> = services
+            .iter()
+            .flat_map(|s| s.scenarios.iter())
+            .filter_map(|s| s.url.as_deref())
+            .filter_map(|url| url::Url::parse(url).ok())
+            .filter_map(|url| url.host_str().map(str::to_string))
+            .collect();
+
+        Ok(RunPlan {
+            cloud: config.cloud,
+            auth_method: config.auth_method,
+            regions,
+            endpoint: config.endpoint.clone(),
+            api_key,
+            tenant,
+            scope: config.scope.clone(),
+            timeout_secs: config.timeout.as_secs(),
+            connect_timeout_secs: config.connect_timeout.as_secs(),
+            tls_timeout_secs: config.tls_timeout.as_secs(),
+            profile: config.profile.clone(),
+            max_rps: config.max_rps,
+            headers: config
+                .headers
+                .iter()
+                .map(|(name, _)| name.clone())
+                .collect(),
+            container: config.container,
+            preview_apis: config.preview_apis,
+            services,
+            hosts: hosts.into_iter().collect(),
+        })
+    }
+}
+
+/// Human-readable plan; `requests` adds each scenario's method, URL and
+/// payload, and the hosts they reach (`--plan`)
+pub fn format_plan(plan: &RunPlan, requests: bool, use_colors: bool) -> String {
+    use console::style;
+
+    let heading = |text: &str| {
+        if use_colors {
+            style(text).bold().to_string()
+        } else {
+            text.to_string()
+        }
+    };
+
+    let mut output = String::new();
+    output.push_str(&format!("\n{}\n", heading("Effective configuration")));
+    let mut settings = vec![
+        ("Cloud", plan.cloud.to_string()),
+        ("Auth method", plan.auth_method.to_string()),
+        ("Region", plan.regions.join(", ")),
+    ];
+    if let Some(endpoint) = &plan.endpoint {
+        settings.push(("Endpoint", endpoint.clone()));
+    }
+    if let Some(api_key) = &plan.api_key {
+        settings.push(("API key", api_key.clone()));
+    }
+    if let Some(tenant) = &plan.tenant {
+        settings.push(("Tenant", tenant.clone()));
+    }
+    if let Some(scope) = &plan.scope {
+        settings.push(("Scope", scope.clone()));
+    }
+    settings.push((
+        "Timeouts",
+        format!(
+            "request {}s, connect {}s, TLS {}s",
+            plan.timeout_secs, plan.connect_timeout_secs, plan.tls_timeout_secs
+        ),
+    ));
+    if let Some(profile) = &plan.profile {
+        settings.push(("Profile", profile.clone()));
+    }
+    if let Some(rps) = plan.max_rps {
+        settings.push(("Max RPS", rps.to_string()));
+    }
+    if !plan.headers.is_empty() {
+        settings.push(("Extra headers", plan.headers.join(", ")));
+    }
+    if plan.container {
+        settings.push(("Container", "yes".to_string()));
+    }
+    if plan.preview_apis {
+        settings.push(("Preview APIs", "yes".to_string()));
+    }
+    for (label, value) in settings {
+        output.push_str(&format!("  {:<14} {}\n", label, value));
+    }
+
+    output.push_str(&format!("\n{}\n", heading("Planned scenarios")));
+    let mut total = 0;
+    for service in &plan.services {
+        let mut label = format!("{} ({})", service.service, service.region);
+        if let Some(target) = &service.target {
+            label.push_str(&format!(" [{}]", target));
+        }
+        output.push_str(&format!("  {} -> {}\n", label, service.endpoint));
+        if service.scenarios.is_empty() {
+            output.push_str("    (no scenarios selected)\n");
+        }
+        for scenario in &service.scenarios {
+            total += 1;
+            match &scenario.note {
+                Some(note) if use_colors => output.push_str(&format!(
+                    "    {:<24} {}\n",
+                    scenario.id,
+                    style(note).yellow()
+                )),
+                Some(note) => output.push_str(&format!("    {:<24} {}\n", scenario.id, note)),
+                None => output.push_str(&format!("    {}\n", scenario.id)),
+            }
+            if !requests {
+                continue;
+            }
+            if let (Some(method), Some(url)) = (&scenario.method, &scenario.url) {
+                let payload = scenario
+                    .payload
+                    .map(|p| format!(" ({})", p))
+                    .unwrap_or_default();
+                let request = format!("{} {}{}", method, url, payload);
+                if use_colors {
+                    output.push_str(&format!("      {}\n", style(request).dim()));
+                } else {
+                    output.push_str(&format!("      {}\n", request));
+                }
+            }
+        }
+    }
+
+    if requests {
+        output.push_str(&format!("\n{}\n", heading("Hosts contacted")));
+        if plan.hosts.is_empty() {
+            output.push_str("  (none)\n");
+        }
+        for host in &plan.hosts {
+            output.push_str(&format!("  {}\n", host));
+        }
+    }
+    output.push_str(&format!(
+        "\n{} scenario(s) planned. Dry run: nothing was sent.\n",
+        total
+    ));
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRunnerConfig;
+
+    #[test]
+    fn test_plan_applies_selection_and_notes() {
+        let config = TestRunnerConfig::builder()
+            .services(["speech", "vision"])
+            .api_key("secret-key")
+            .region("centralus")
+            .scenarios(["voices_list", "stt_rest", "background_removal"])
+            .build();
+        let plan = TestRunner::new(config).plan().unwrap();
+
+        assert_eq!(plan.api_key.as_deref(), Some("set"));
+        assert_eq!(plan.services.len(), 2);
+        let speech = &plan.services[0];
+        assert_eq!(
+            speech.endpoint,
+            "https://centralus.api.cognitive.microsoft.com"
+        );
+        let ids: Vec<&str> = speech.scenarios.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["voices_list", "stt_rest"]);
+        assert!(speech.scenarios.iter().all(|s| s.note.is_none()));
+
+        // Region-restricted scenarios carry a note instead of failing later
+        let vision = &plan.services[1];
+        assert_eq!(vision.scenarios.len(), 1);
+        assert!(vision.scenarios[0]
+            .note
+            .as_deref()
+            .is_some_and(|note| note.contains("centralus")));
+
+        let text = format_plan(&plan, false, false);
+        assert!(text.contains("API key        set"));
+        assert!(!text.contains("secret-key"));
+        assert!(text.contains("Dry run: nothing was sent."));
+        assert!(!text.contains("Hosts contacted"));
+    }
+
+    #[test]
+    fn test_plan_lists_requests_and_hosts() {
+        let config = TestRunnerConfig::builder()
+            .services(["speech"])
+            .region("westus2")
+            .scenarios(["voices_list", "stt_short"])
+            .preview_apis(true)
+            .build();
+        let plan = TestRunner::new(config).plan().unwrap();
+
+        let scenarios = &plan.services[0].scenarios;
+        assert_eq!(scenarios[0].method.as_deref(), Some("GET"));
+        assert_eq!(
+            scenarios[0].url.as_deref(),
+            Some("https://westus2.tts.speech.microsoft.com/cognitiveservices/voices/list")
+        );
+        assert_eq!(scenarios[1].payload, Some(Payload::MultipartAudio));
+        assert!(scenarios[1]
+            .url
+            .as_deref()
+            .is_some_and(|url| url.ends_with("api-version=2024-05-15-preview")));
+        assert_eq!(
+            plan.hosts,
+            [
+                "westus2.api.cognitive.microsoft.com",
+                "westus2.tts.speech.microsoft.com"
+            ]
+        );
+
+        let text = format_plan(&plan, true, false);
+        assert!(text.contains(
+            "      GET https://westus2.tts.speech.microsoft.com/cognitiveservices/voices/list (no body)"
+        ));
+        assert!(text.contains("Hosts contacted\n  westus2.api.cognitive.microsoft.com\n"));
+    }
+}
@@ -0,0 +1,597 @@
+//! Full-screen live view of a test run (`test --tui`)
+//!
+//! Shows every service/scenario with its status and duration as results come
+//! in, plus a detail pane for the selected row. Drawn with ratatui on stderr,
+//! so stdout stays free for the report printed after the view closes.
+
+use crate::auth::Credentials;
+use crate::error::{AppError, Result};
+use crate::output::TestReport;
+use crate::services::{RunEvent, ServiceTestResults, TestResult};
+use crate::testing::TestRunner;
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::{cursor, execute, terminal};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row as TableRow, Table, TableState, Wrap};
+use ratatui::{Frame, Terminal};
+use std::io::IsTerminal;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Lines reserved for the detail pane (including its separator)
+const DETAIL_HEIGHT: u16 = 8;
+
+/// Header line and the blank line under it
+const HEADER_HEIGHT: u16 = 2;
+
+/// Spinner frames for running scenarios
+const SPINNER: &[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RowState {
+    Pending,
+    Running,
+    Done,
+}
+
+/// One scenario in the table
+#[derive(Debug, Clone)]
+struct Row {
+    service: String,
+    endpoint: String,
+    scenario_id: String,
+    scenario_name: String,
+    state: RowState,
+    started: Option<Instant>,
+    result: Option<TestResult>,
+}
+
+/// Everything the screen shows
+struct TuiState {
+    title: String,
+    rows: Vec<Row>,
+    endpoints: Vec<(String, String)>,
+    selected: usize,
+    table: TableState,
+    /// Table rows visible at once, for page up/down
+    page: usize,
+    started: Instant,
+    finished: bool,
+    tick: usize,
+}
+
+impl TuiState {
+    fn new(title: String) -> Self {
+        Self {
+            title,
+            rows: Vec::new(),
+            endpoints: Vec::new(),
+            selected: 0,
+            table: TableState::default(),
+            page: 1,
+            started: Instant::now(),
+            finished: false,
+            tick: 0,
+        }
+    }
+
+    fn apply(&mut self, event: RunEvent) {
+        match event {
+            RunEvent::ServiceStarted {
+                service,
+                endpoint,
+                scenarios,
+            } => {
+                self.endpoints.push((service.clone(), endpoint.clone()));
+                self.rows
+                    .extend(scenarios.into_iter().map(|(id, name)| Row {
+                        service: service.clone(),
+                        endpoint: endpoint.clone(),
+                        scenario_id: id,
+                        scenario_name: name,
+                        state: RowState::Pending,
+                        started: None,
+                        result: None,
+                    }));
+            }
+            RunEvent::ScenarioStarted {
+                service,
+                scenario_id,
+            } => {
+                if let Some(row) = self.find(&service, &scenario_id) {
+                    row.state = RowState::Running;
+                    row.started = Some(Instant::now());
+                }
+            }
+            RunEvent::ScenarioFinished { service, result } => {
+                if let Some(row) = self.find(&service, &result.scenario_id) {
+                    row.state = RowState::Done;
+                    row.result = Some(*result);
+                }
+            }
+            RunEvent::ServiceFinished { .. } | RunEvent::Notice { .. } => {}
+        }
+    }
+
+    /// First unfinished row for a scenario. Services run one after another, so
+    /// with `--regions` this is the row of the region currently running.
+    fn find(&mut self, service: &str, scenario_id: &str) -> Option<&mut Row> {
+        self.rows.iter_mut().find(|r| {
+            r.service == service && r.scenario_id == scenario_id && r.state != RowState::Done
+        })
+    }
+
+    /// Handle a key press; returns true when the user asked to quit
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let last = self.rows.len().saturating_sub(1);
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return true,
+            KeyCode::Char('q') | KeyCode::Esc => return true,
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.selected = (self.selected + 1).min(last),
+            KeyCode::PageUp => self.selected = self.selected.saturating_sub(self.page),
+            KeyCode::PageDown => self.selected = (self.selected + self.page).min(last),
+            KeyCode::Home => self.selected = 0,
+            KeyCode::End => self.selected = last,
+            KeyCode::Char('f') => {
+                // Jump to the next failure, wrapping around
+                let count = self.rows.len();
+                if let Some(offset) = (1..=count).find(|offset| {
+                    let row = &self.rows[(self.selected + offset) % count];
+                    row.result.as_ref().is_some_and(is_failure)
+                }) {
+                    self.selected = (self.selected + offset) % count;
+                }
+            }
+            _ => {}
+        }
+        false
+    }
+
+    fn counts(&self) -> (usize, usize, usize, usize) {
+        let mut passed = 0;
+        let mut failed = 0;
+        let mut skipped = 0;
+        let mut done = 0;
+        for result in self.rows.iter().filter_map(|r| r.result.as_ref()) {
+            done += 1;
+            if result.success {
+                passed += 1;
+            } else if result.is_skipped() || result.is_throttled() || result.is_expected_failure() {
+                skipped += 1;
+            } else {
+                failed += 1;
+            }
+        }
+        (done, passed, failed, skipped)
+    }
+
+    /// Draw the header, the scenario table, the detail pane, and the key help
+    fn render(&mut self, frame: &mut Frame) {
+        let [header, table, detail, help] = Layout::vertical([
+            Constraint::Length(HEADER_HEIGHT),
+            Constraint::Min(2),
+            Constraint::Length(DETAIL_HEIGHT),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let (done, passed, failed, skipped) = self.counts();
+        let elapsed = self.started.elapsed().as_secs();
+        let state = if self.finished {
+            "done".green()
+        } else {
+            "running".cyan()
+        };
+        let title = Line::from(vec![
+            self.title.clone().bold(),
+            " - ".into(),
+            "azure-aitoolsconnect".dim(),
+            format!(
+                " - {}/{} done, {} passed, {} failed, {} skipped - {:02}:{:02} ",
+                done,
+                self.rows.len(),
+                passed,
+                failed,
+                skipped,
+                elapsed / 60,
+                elapsed % 60
+            )
+            .into(),
+            state,
+        ]);
+        frame.render_widget(Paragraph::new(title), header);
+
+        // The column titles take one line of the table area
+        self.page = (table.height as usize).saturating_sub(1).max(1);
+        let rows: Vec<TableRow> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let (status, time) = self.status_cell(row);
+                TableRow::new(vec![
+                    Cell::from(row.service.clone()),
+                    Cell::from(row.scenario_name.clone()),
+                    Cell::from(status),
+                    Cell::from(Line::from(time).right_aligned()),
+                ])
+            })
+            .collect();
+        let widths = [
+            Constraint::Length(22),
+            Constraint::Min(12),
+            Constraint::Length(12),
+            Constraint::Length(8),
+        ];
+        let columns = TableRow::new(vec!["SERVICE", "SCENARIO", "STATUS", "    TIME"]).bold();
+        let view = Table::new(rows, widths)
+            .header(columns)
+            .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+        self.table
+            .select((!self.rows.is_empty()).then_some(self.selected));
+        frame.render_stateful_widget(view, table, &mut self.table);
+
+        let pane = Paragraph::new(self.detail_lines())
+            .block(
+                Block::new()
+                    .borders(Borders::TOP)
+                    .border_style(Style::new().dim()),
+            )
+            .wrap(Wrap { trim: false });
+        frame.render_widget(pane, detail);
+
+        let keys = if self.finished {
+            "↑/↓ select  f next failure  q exit and print report"
+        } else {
+            "↑/↓ select  f next failure  q stop"
+        };
+        frame.render_widget(Paragraph::new(keys.dim()), help);
+    }
+
+    fn status_cell(&self, row: &Row) -> (Span<'static>, String) {
+        match (&row.state, &row.result) {
+            (RowState::Done, Some(result)) => {
+                let time = format!("{}ms", result.duration_ms);
+                let status = if result.success {
+                    "✓ pass".green()
+                } else if result.is_throttled() {
+                    "⧖ throttled".yellow()
+                } else if result.is_expected_failure() {
+                    "⊘ expected".cyan()
+                } else if result.is_skipped() {
+                    "○ skipped".dim()
+                } else {
+                    "✗ FAIL".red().bold()
+                };
+                (status, time)
+            }
+            (RowState::Running, _) => {
+                let frame = SPINNER[self.tick % SPINNER.len()];
+                let time = row
+                    .started
+                    .map(|s| format!("{:.1}s", s.elapsed().as_secs_f64()))
+                    .unwrap_or_default();
+                (
+                    Span::styled(format!("{} running", frame), Color::Cyan),
+                    time,
+                )
+            }
+            _ => ("· pending".dim(), String::new()),
+        }
+    }
+
+    fn detail_lines(&self) -> Vec<Line<'static>> {
+        let Some(row) = self.rows.get(self.selected) else {
+            return vec![Line::from("Waiting for results...")];
+        };
+        let mut lines = vec![Line::from(vec![
+            row.service.clone().bold(),
+            format!(" / {} ({})", row.scenario_name, row.scenario_id).into(),
+        ])];
+        lines.push(Line::from(format!("  Endpoint: {}", row.endpoint)));
+
+        match &row.result {
+            Some(result) => {
+                let mut status = if result.success {
+                    "passed".to_string()
+                } else if result.is_skipped() {
+                    "skipped".to_string()
+                } else if result.is_throttled() {
+                    "throttled".to_string()
+                } else if result.is_expected_failure() {
+                    "expected failure".to_string()
+                } else {
+                    "failed".to_string()
+                };
+                if let Some(code) = result.http_status {
+                    status.push_str(&format!(", HTTP {}", code));
+                }
+                if let Some(kind) = result.failure_kind {
+                    status.push_str(&format!(", {}", kind));
+                }
+                lines.push(Line::from(format!(
+                    "  Status: {} in {}ms",
+                    status, result.duration_ms
+                )));
+                if let Some(error) = &result.error {
+                    lines.push(Line::from(format!(
+                        "  Error: {}",
+                        crate::redact::redact(error)
+                    )));
+                }
+                if let Some(hint) = &result.hint {
+                    lines.push(Line::from(format!("  Hint: {}", hint)));
+                }
+                if let Some(details) = &result.details {
+                    lines.push(Line::from(format!(
+                        "  Details: {}",
+                        crate::redact::redact(details)
+                    )));
+                }
+            }
+            None if row.state == RowState::Running => lines.push(Line::from("  Running...")),
+            None => lines.push(Line::from("  Not started yet")),
+        }
+        lines
+    }
+
+    /// Report of the scenarios that finished, for runs stopped early
+    fn partial_report(&self) -> TestReport {
+        let services = self
+            .endpoints
+            .iter()
+            .map(|(service, endpoint)| {
+                let results: Vec<TestResult> = self
+                    .rows
+                    .iter()
+                    .filter(|r| r.service == *service && r.endpoint == *endpoint)
+                    .filter_map(|r| r.result.clone())
+                    .collect();
+                ServiceTestResults {
+                    service_name: service.clone(),
+                    endpoint: endpoint.clone(),
+                    region: None,
+                    target: None,
+                    total_duration_ms: results.iter().map(|r| r.duration_ms).sum(),
+                    results,
+                }
+            })
+            .filter(|s| !s.results.is_empty())
+            .collect();
+        TestReport::new(services).with_interrupted()
+    }
+}
+
+fn is_failure(result: &TestResult) -> bool {
+    !result.success
+        && !result.is_skipped()
+        && !result.is_throttled()
+        && !result.is_expected_failure()
+}
+
+/// Puts stderr in raw mode on the alternate screen and restores it when dropped
+struct ScreenGuard;
+
+impl ScreenGuard {
+    fn enter() -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        let guard = Self;
+        execute!(
+            std::io::stderr(),
+            terminal::EnterAlternateScreen,
+            cursor::Hide
+        )?;
+        Ok(guard)
+    }
+}
+
+impl Drop for ScreenGuard {
+    fn drop(&mut self) {
+        let _ = execute!(
+            std::io::stderr(),
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn draw<B: Backend>(terminal: &mut Terminal<B>, state: &mut TuiState) -> Result<()> {
+    terminal.draw(|frame| state.render(frame))?;
+    Ok(())
+}
+
+/// Run the tests with a live full-screen view. The view stays open after the
+/// run finishes until the user presses `q`; quitting early stops the run and
+/// returns the results gathered so far.
+pub async fn run_tui(
+    runner: TestRunner,
+    credentials: Credentials,
+    title: String,
+) -> Result<TestReport> {
+    if !std::io::stderr().is_terminal() {
+        return Err(AppError::InvalidInput(
+            "--tui requires an interactive terminal".to_string(),
+        ));
+    }
+
+    let (event_tx, mut events) = tokio::sync::mpsc::unbounded_channel();
+    let runner = Arc::new(runner.with_events(event_tx));
+    let mut run = tokio::spawn({
+        let runner = runner.clone();
+        async move { runner.run_with_credentials(credentials).await }
+    });
+    drop(runner);
+
+    // Terminal events are read on a plain thread, since reading blocks; it
+    // ends once the view closes and stops listening
+    let (key_tx, mut keys) = tokio::sync::mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        while !key_tx.is_closed() {
+            match event::poll(Duration::from_millis(100)) {
+                Ok(true) => match event::read() {
+                    Ok(event) => {
+                        let _ = key_tx.send(event);
+                    }
+                    Err(_) => break,
+                },
+                Ok(false) => {}
+                Err(_) => break,
+            }
+        }
+    });
+
+    let _screen = ScreenGuard::enter()?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(std::io::stderr()))?;
+    let mut state = TuiState::new(title);
+    let mut ticker = tokio::time::interval(Duration::from_millis(200));
+    let mut report = None;
+    let mut events_open = true;
+
+    loop {
+        tokio::select! {
+            event = events.recv(), if events_open => match event {
+                Some(event) => state.apply(event),
+                None => events_open = false,
+            },
+            result = &mut run, if report.is_none() => {
+                state.finished = true;
+                report = Some(result.map_err(|e| AppError::InvalidInput(e.to_string()))??);
+            }
+            key = keys.recv() => match key {
+                Some(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                    if state.handle_key(key) {
+                        break;
+                    }
+                }
+                // Resizes and other events only need a redraw
+                Some(_) => {}
+                None => break,
+            },
+            _ = ticker.tick() => state.tick += 1,
+        }
+        draw(&mut terminal, &mut state)?;
+    }
+    drop(keys);
+
+    match report {
+        Some(report) => Ok(report),
+        None => {
+            run.abort();
+            // Pick up results that arrived but weren't drawn yet
+            while let Ok(event) = events.try_recv() {
+                state.apply(event);
+            }
+            Ok(state.partial_report())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn started(state: &mut TuiState) {
+        state.apply(RunEvent::ServiceStarted {
+            service: "Speech".to_string(),
+            endpoint: "https://eastus.tts.speech.microsoft.com".to_string(),
+            scenarios: vec![
+                ("voices_list".to_string(), "Voices list".to_string()),
+                ("tts".to_string(), "Text-to-Speech".to_string()),
+                ("stt_short".to_string(), "Speech-to-Text".to_string()),
+            ],
+        });
+    }
+
+    #[test]
+    fn test_events_update_rows() {
+        let mut state = TuiState::new("eastus".to_string());
+        started(&mut state);
+        state.apply(RunEvent::ScenarioStarted {
+            service: "Speech".to_string(),
+            scenario_id: "voices_list".to_string(),
+        });
+        assert_eq!(state.rows[0].state, RowState::Running);
+
+        state.apply(RunEvent::ScenarioFinished {
+            service: "Speech".to_string(),
+            result: Box::new(TestResult::success("voices_list", "Voices list", 120)),
+        });
+        state.apply(RunEvent::ScenarioFinished {
+            service: "Speech".to_string(),
+            result: Box::new(
+                TestResult::failure("tts", "Text-to-Speech", 80, "HTTP 401".to_string())
+                    .with_http_status(401),
+            ),
+        });
+
+        assert_eq!(state.counts(), (2, 1, 1, 0));
+        let report = state.partial_report();
+        assert_eq!(report.services.len(), 1);
+        assert_eq!(report.services[0].results.len(), 2);
+        assert!(report.interrupted);
+    }
+
+    #[test]
+    fn test_keys_and_next_failure() {
+        let mut state = TuiState::new("eastus".to_string());
+        started(&mut state);
+        state.apply(RunEvent::ScenarioFinished {
+            service: "Speech".to_string(),
+            result: Box::new(TestResult::failure(
+                "stt_short",
+                "Speech-to-Text",
+                5,
+                "DNS".to_string(),
+            )),
+        });
+
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        assert!(!state.handle_key(key(KeyCode::Char('f'))));
+        assert_eq!(state.selected, 2);
+        assert!(!state.handle_key(key(KeyCode::Up)));
+        assert_eq!(state.selected, 1);
+        assert!(!state.handle_key(key(KeyCode::End)));
+        assert_eq!(state.selected, 2);
+        assert!(state.handle_key(key(KeyCode::Char('q'))));
+        assert!(state.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)));
+    }
+
+    #[test]
+    fn test_render_fits_screen() {
+        let mut state = TuiState::new("eastus".to_string());
+        started(&mut state);
+        state.apply(RunEvent::ScenarioFinished {
+            service: "Speech".to_string(),
+            result: Box::new(TestResult::failure(
+                "voices_list",
+                "Voices list",
+                5,
+                "HTTP 401".to_string(),
+            )),
+        });
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(60, 20)).unwrap();
+        draw(&mut terminal, &mut state).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let lines: Vec<String> = (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect()
+            })
+            .collect();
+        assert!(lines.iter().any(|l| l.contains("> Speech")), "{:#?}", lines);
+        assert!(lines.iter().any(|l| l.contains("✗ FAIL")), "{:#?}", lines);
+        assert!(
+            lines.iter().any(|l| l.contains("Error: HTTP 401")),
+            "{:#?}",
+            lines
+        );
+        assert_eq!(state.page, 20 - 2 - 8 - 1 - 1);
+    }
+}
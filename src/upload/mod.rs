@@ -0,0 +1,453 @@
+//! Sending each report somewhere central (`--upload`, `[upload]`)
+//!
+//! Runs on kiosks, VMs, and agents in other network segments push their
+//! report after every run to any of:
+//!
+//! - a `serve` controller's `/reports` endpoint (`http(s)://<host>:9464/reports`),
+//!   which keeps each location's latest report for its matrix
+//! - `blob:<container URL>`: the report as `<location>-<timestamp>.json`,
+//!   authorized by the URL's SAS token or, without one, by the configured
+//!   Entra credential (the identity needs Storage Blob Data Contributor)
+//! - `log-analytics:<workspace-id>`: one record per scenario in the
+//!   `<log_type>_CL` table, through the HTTP Data Collector API with the
+//!   workspace's shared key
+//!
+//! Each report is tagged with its `location`, the column it gets in
+//! `aggregate`. A failed upload is a warning; it never fails the run.
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::aggregate::CellStatus;
+use crate::auth::get_resource_token;
+use crate::config::{Cloud, Config};
+use crate::error::{AppError, Result};
+use crate::output::TestReport;
+
+/// Location used when none is configured and the host name is unknown
+const FALLBACK_LOCATION: &str = "agent";
+/// Token audience of Azure Storage, in every cloud
+const STORAGE_RESOURCE: &str = "https://storage.azure.com";
+/// Blob service version for requests authorized with an Entra token
+const STORAGE_API_VERSION: &str = "2021-08-06";
+/// Custom log type (table `<type>_CL`) when `[upload] log_type` isn't set
+pub const DEFAULT_LOG_TYPE: &str = "AIToolsConnect";
+
+/// Where a report is uploaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UploadTarget {
+    /// POST to a `serve` controller's `/reports` endpoint
+    Controller(String),
+    /// PUT into a blob container; the URL may carry a SAS token
+    Blob(String),
+    /// Log Analytics workspace ID
+    LogAnalytics(String),
+}
+
+impl UploadTarget {
+    /// Whether the target needs an Entra token for Azure Storage
+    fn needs_storage_token(&self) -> bool {
+        matches!(self, Self::Blob(url) if !url.contains('?'))
+    }
+
+    /// Where reports go, without the URL's path or SAS token
+    pub fn describe(&self) -> String {
+        let host = |url: &str| {
+            url::Url::parse(url)
+                .ok()
+                .and_then(|u| u.host_str().map(str::to_string))
+                .unwrap_or_default()
+        };
+        match self {
+            Self::Controller(url) => format!("controller ({})", host(url)),
+            Self::Blob(url) => format!("blob container ({})", host(url)),
+            Self::LogAnalytics(workspace) => format!("Log Analytics workspace {}", workspace),
+        }
+    }
+}
+
+impl FromStr for UploadTarget {
+    type Err = AppError;
+
+    /// `blob:<container-url>`, `log-analytics:<workspace-id>`, or a controller's `/reports` URL
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if let Some(workspace) = s.strip_prefix("log-analytics:") {
+            let workspace = workspace.trim();
+            if uuid::Uuid::parse_str(workspace).is_err() {
+                return Err(AppError::InvalidInput(format!(
+                    "log-analytics: expects the workspace ID (a GUID), got '{}'",
+                    workspace
+                )));
+            }
+            return Ok(Self::LogAnalytics(workspace.to_string()));
+        }
+        let (blob, url) = match s.strip_prefix("blob:") {
+            Some(url) => (true, url),
+            None => (false, s),
+        };
+        let parsed = url::Url::parse(url)
+            .map_err(|e| AppError::InvalidInput(format!("Invalid upload URL '{}': {}", s, e)))?;
+        if !matches!(parsed.scheme(), "https" | "http") {
+            return Err(AppError::InvalidInput(format!(
+                "Upload URL '{}' must be an http(s) URL",
+                s
+            )));
+        }
+        Ok(if blob {
+            Self::Blob(url.to_string())
+        } else {
+            Self::Controller(url.to_string())
+        })
+    }
+}
+
+/// Parse `[upload] targets`, naming the setting in errors
+pub fn parse_targets(targets: &[String]) -> Result<Vec<UploadTarget>> {
+    targets
+        .iter()
+        .map(|target| {
+            target
+                .parse()
+                .map_err(|e: AppError| AppError::Config(format!("[upload] targets: {}", e)))
+        })
+        .collect()
+}
+
+/// This machine's host name, the default location of its reports
+pub fn default_location() -> String {
+    ["HOSTNAME", "COMPUTERNAME"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .chain(std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .find(|name| !name.is_empty())
+        .unwrap_or_else(|| FALLBACK_LOCATION.to_string())
+}
+
+/// `name` with characters outside `[A-Za-z0-9._-]` replaced, for file and blob names
+pub fn safe_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Blob name of a report: `<location>-<timestamp>.json`
+pub fn blob_name(report: &TestReport) -> String {
+    format!(
+        "{}-{}.json",
+        safe_name(report.location.as_deref().unwrap_or(FALLBACK_LOCATION)),
+        report.timestamp.format("%Y%m%dT%H%M%SZ")
+    )
+}
+
+/// URL of `name` inside the container at `container_url`, keeping its SAS token
+fn blob_url(container_url: &str, name: &str) -> Result<url::Url> {
+    let mut url = url::Url::parse(container_url)
+        .map_err(|e| AppError::InvalidInput(format!("Invalid container URL: {}", e)))?;
+    url.path_segments_mut()
+        .map_err(|_| AppError::InvalidInput("Invalid container URL".to_string()))?
+        .pop_if_empty()
+        .push(name);
+    Ok(url)
+}
+
+/// One Log Analytics record per scenario, flattened for queries
+fn log_records(report: &TestReport) -> Vec<serde_json::Value> {
+    let mut records = Vec::new();
+    for service in &report.services {
+        for result in &service.results {
+            records.push(serde_json::json!({
+                "timestamp": report.timestamp,
+                "location": report.location,
+                "cloud": report.cloud,
+                "region": service.region.as_ref().or(report.region.as_ref()),
+                "service": service.service_name,
+                "target": service.target,
+                "endpoint": service.endpoint,
+                "scenario_id": result.scenario_id,
+                "scenario_name": result.scenario_name,
+                "status": CellStatus::of(result),
+                "success": result.success,
+                "duration_ms": result.duration_ms,
+                "http_status": result.http_status,
+                "failure_kind": result.failure_kind,
+                "error": result.error,
+            }));
+        }
+    }
+    records
+}
+
+/// `Authorization` header of a Data Collector API request
+fn shared_key_signature(
+    workspace: &str,
+    key: &str,
+    content_length: usize,
+    date: &str,
+) -> Result<String> {
+    let key = base64::engine::general_purpose::STANDARD
+        .decode(key.trim())
+        .map_err(|_| {
+            AppError::Config("[upload] log_analytics_key is not a base64 shared key".to_string())
+        })?;
+    let string_to_sign = format!(
+        "POST\n{}\napplication/json\nx-ms-date:{}\n/api/logs",
+        content_length, date
+    );
+    let mut mac = Hmac::<Sha256>::new_from_slice(&key)
+        .map_err(|e| AppError::Config(format!("[upload] log_analytics_key: {}", e)))?;
+    mac.update(string_to_sign.as_bytes());
+    let signature = base64::engine::general_purpose::STANDARD.encode(mac.finalize().into_bytes());
+    Ok(format!("SharedKey {}:{}", workspace, signature))
+}
+
+/// Sends reports to upload targets
+pub struct Uploader {
+    client: reqwest::Client,
+    cloud: Cloud,
+    /// Bearer token for `serve` controllers
+    controller_token: Option<String>,
+    log_analytics_key: Option<String>,
+    log_type: String,
+    /// Entra token for blob containers without a SAS token
+    storage_token: Option<String>,
+}
+
+impl Uploader {
+    /// An uploader for `targets` with the `[upload]` settings of `config`.
+    /// Gets a Storage token with the configured credential first when a blob
+    /// target has no SAS token.
+    pub async fn new(
+        config: &Config,
+        targets: &[UploadTarget],
+        timeout: Duration,
+        quiet: bool,
+    ) -> Result<Self> {
+        let cloud = config.global.cloud;
+        let storage_token = if targets.iter().any(UploadTarget::needs_storage_token) {
+            Some(
+                get_resource_token(
+                    STORAGE_RESOURCE,
+                    "Azure Storage",
+                    config.auth.default_method,
+                    cloud,
+                    Some(&config.auth.entra),
+                    Some(&config.auth.user),
+                    quiet,
+                )
+                .await?,
+            )
+        } else {
+            None
+        };
+        if let Some(token) = &storage_token {
+            crate::redact::register_secret(token);
+        }
+        let client = crate::network::configure_client(reqwest::Client::builder())
+            .timeout(timeout)
+            .build()
+            .map_err(|e| AppError::Network(e.to_string()))?;
+        Ok(Self {
+            client,
+            cloud,
+            controller_token: config.upload_settings().token,
+            log_analytics_key: config.upload.log_analytics_key.clone(),
+            log_type: config
+                .upload
+                .log_type
+                .clone()
+                .unwrap_or_else(|| DEFAULT_LOG_TYPE.to_string()),
+            storage_token,
+        })
+    }
+
+    /// Send `report` to `target`
+    pub async fn upload(&self, target: &UploadTarget, report: &TestReport) -> Result<()> {
+        let request = match target {
+            UploadTarget::Controller(url) => {
+                let request = self.client.post(url);
+                match &self.controller_token {
+                    Some(token) => request.bearer_auth(token),
+                    None => request,
+                }
+                .body(crate::redact::redact(&serde_json::to_string(report)?))
+            }
+            UploadTarget::Blob(url) => {
+                let request = self
+                    .client
+                    .put(blob_url(url, &blob_name(report))?)
+                    .header("x-ms-blob-type", "BlockBlob");
+                match &self.storage_token {
+                    Some(token) if target.needs_storage_token() => request
+                        .bearer_auth(token)
+                        .header("x-ms-version", STORAGE_API_VERSION),
+                    _ => request,
+                }
+                .body(crate::redact::redact(&serde_json::to_string(report)?))
+            }
+            UploadTarget::LogAnalytics(workspace) => {
+                let key = self.log_analytics_key.as_deref().ok_or_else(|| {
+                    AppError::Config(
+                        "log-analytics: uploads need the workspace's shared key in \
+                         [upload] log_analytics_key or AZURE_AITOOLSCONNECT_LOG_ANALYTICS_KEY"
+                            .to_string(),
+                    )
+                })?;
+                let records = log_records(report);
+                if records.is_empty() {
+                    return Ok(());
+                }
+                let body = crate::redact::redact(&serde_json::Value::Array(records).to_string());
+                let date = httpdate(Utc::now());
+                let suffix = match self.cloud {
+                    Cloud::Global => "azure.com",
+                    Cloud::China => "azure.cn",
+                };
+                self.client
+                    .post(format!(
+                        "https://{}.ods.opinsights.{}/api/logs?api-version=2016-04-01",
+                        workspace, suffix
+                    ))
+                    .header(
+                        "Authorization",
+                        shared_key_signature(workspace, key, body.len(), &date)?,
+                    )
+                    .header("Log-Type", &self.log_type)
+                    .header("x-ms-date", date)
+                    .header("time-generated-field", "timestamp")
+                    .body(body)
+            }
+        };
+        let response = request
+            .header("Content-Type", "application/json")
+            .send()
+            .await
+            .map_err(|e| {
+                AppError::Network(format!(
+                    "Could not reach {}: {}",
+                    target.describe(),
+                    crate::redact::redact(&e.to_string())
+                ))
+            })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::Network(format!(
+                "{} returned HTTP {}: {}",
+                target.describe(),
+                status,
+                crate::error::sanitize_error(&body, status.as_u16())
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// RFC 1123 date, as the `x-ms-date` header
+fn httpdate(time: DateTime<Utc>) -> String {
+    time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{ServiceTestResults, TestResult};
+
+    #[test]
+    fn test_parse_upload_target() {
+        assert_eq!(
+            "http://10.0.0.4:9464/reports"
+                .parse::<UploadTarget>()
+                .unwrap(),
+            UploadTarget::Controller("http://10.0.0.4:9464/reports".to_string())
+        );
+        let blob: UploadTarget = "blob:https://acct.blob.core.windows.net/reports?sv=1&sig=x"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            blob.describe(),
+            "blob container (acct.blob.core.windows.net)"
+        );
+        assert!(!blob.needs_storage_token());
+        let entra: UploadTarget = "blob:https://acct.blob.core.windows.net/reports"
+            .parse()
+            .unwrap();
+        assert!(entra.needs_storage_token());
+        assert_eq!(
+            "log-analytics:0f8fad5b-d9cb-469f-a165-70867728950e"
+                .parse::<UploadTarget>()
+                .unwrap(),
+            UploadTarget::LogAnalytics("0f8fad5b-d9cb-469f-a165-70867728950e".to_string())
+        );
+        assert!("log-analytics:my-workspace"
+            .parse::<UploadTarget>()
+            .is_err());
+        assert!("ftp://host/reports".parse::<UploadTarget>().is_err());
+    }
+
+    #[test]
+    fn test_blob_url() {
+        let mut report = TestReport::new(Vec::new()).with_location("branch office/1".to_string());
+        report.timestamp = "2024-03-01T10:05:00Z".parse().unwrap();
+        let name = blob_name(&report);
+        assert_eq!(name, "branch_office_1-20240301T100500Z.json");
+        assert_eq!(
+            blob_url("https://acct.blob.core.windows.net/reports/?sv=1&sig=x", &name)
+                .unwrap()
+                .as_str(),
+            "https://acct.blob.core.windows.net/reports/branch_office_1-20240301T100500Z.json?sv=1&sig=x"
+        );
+    }
+
+    #[test]
+    fn test_log_analytics_request() {
+        let date = httpdate("2024-03-01T10:05:00Z".parse().unwrap());
+        assert_eq!(date, "Fri, 01 Mar 2024 10:05:00 GMT");
+        // Matches the Data Collector API's documented signing scheme
+        assert_eq!(
+            shared_key_signature(
+                "ws",
+                "MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=",
+                42,
+                &date
+            )
+            .unwrap(),
+            "SharedKey ws:6447uP18MBSBWppri48Mg8jIu9ylmnygVj8dehD2F6g="
+        );
+        assert!(shared_key_signature("ws", "not base64!", 42, &date).is_err());
+
+        let report = TestReport::new(vec![ServiceTestResults {
+            service_name: "Speech".to_string(),
+            endpoint: "https://eastus.api.cognitive.microsoft.com".to_string(),
+            region: None,
+            target: None,
+            total_duration_ms: 300,
+            results: vec![TestResult::failure(
+                "tts",
+                "Text to Speech",
+                50,
+                "HTTP 403".to_string(),
+            )],
+        }])
+        .with_run_context("eastus".to_string(), Cloud::Global)
+        .with_location("kiosk-12".to_string());
+        let records = log_records(&report);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["location"], "kiosk-12");
+        assert_eq!(records[0]["region"], "eastus");
+        assert_eq!(records[0]["status"], "failed");
+    }
+}
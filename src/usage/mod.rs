@@ -0,0 +1,498 @@
+//! Quota and usage of the tested accounts (`usage`, `test --usage`)
+//!
+//! Connectivity complaints are often exhausted quota in disguise: HTTP 429s
+//! from a deployment sized below the load, or a regional model quota used up
+//! by other accounts in the subscription. Via ARM this reads an account's
+//! usage counters, the rate limits of its model deployments, and for
+//! accounts with deployments the subscription's model quota in the region.
+//! Per-tier rate limits (Speech concurrency, transactions per second) aren't
+//! exposed by ARM, so the documented defaults for the account's kind and SKU
+//! are listed alongside.
+
+use console::style;
+use serde::{Deserialize, Serialize};
+
+use crate::arm::{subscription_of, ArmClient, CognitiveAccount, Deployment, QuotaUsage};
+use crate::output::TestReport;
+use crate::services::FailureKind;
+
+/// Share of a limit from which a counter is reported as near its limit
+const NEAR_LIMIT: f64 = 0.9;
+
+/// Service and its documented limit
+type Limits = &'static [(&'static str, &'static str)];
+
+/// Documented default rate limits of the S0 tier of each service in a
+/// multi-service account
+const MULTI_SERVICE_S0: Limits = &[
+    (
+        "Speech",
+        "speech to text: 100 concurrent real-time requests",
+    ),
+    ("Translator", "40 million characters per hour"),
+    ("Language", "1,000 requests per minute"),
+    ("Vision", "10 transactions per second"),
+    ("Document Intelligence", "15 analyze requests per second"),
+];
+
+/// Documented default rate limits by account kind and SKU, as (service,
+/// limit). Most can be raised with a support request.
+const DOCUMENTED_LIMITS: &[(&str, &str, Limits)] = &[
+    (
+        "SpeechServices",
+        "F0",
+        &[
+            ("Speech", "speech to text: 1 concurrent request"),
+            ("Speech", "text to speech: 20 transactions per 60 seconds"),
+        ],
+    ),
+    (
+        "SpeechServices",
+        "S0",
+        &[
+            (
+                "Speech",
+                "speech to text: 100 concurrent real-time requests",
+            ),
+            ("Speech", "text to speech: 200 transactions per second"),
+        ],
+    ),
+    (
+        "TextTranslation",
+        "F0",
+        &[("Translator", "2 million characters per hour")],
+    ),
+    (
+        "TextTranslation",
+        "S1",
+        &[("Translator", "40 million characters per hour")],
+    ),
+    (
+        "TextAnalytics",
+        "F0",
+        &[("Language", "100 requests per minute")],
+    ),
+    (
+        "TextAnalytics",
+        "S",
+        &[("Language", "1,000 requests per minute")],
+    ),
+    ("Language", "F0", &[("Language", "100 requests per minute")]),
+    (
+        "Language",
+        "S",
+        &[("Language", "1,000 requests per minute")],
+    ),
+    (
+        "ComputerVision",
+        "F0",
+        &[("Vision", "20 transactions per minute")],
+    ),
+    (
+        "ComputerVision",
+        "S1",
+        &[("Vision", "10 transactions per second")],
+    ),
+    (
+        "FormRecognizer",
+        "F0",
+        &[("Document Intelligence", "1 analyze request per second")],
+    ),
+    (
+        "FormRecognizer",
+        "S0",
+        &[("Document Intelligence", "15 analyze requests per second")],
+    ),
+    ("CognitiveServices", "S0", MULTI_SERVICE_S0),
+    ("AIServices", "S0", MULTI_SERVICE_S0),
+];
+
+/// A documented default limit of an account's tier
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentedLimit {
+    pub service: String,
+    pub limit: String,
+}
+
+/// Quota and usage of one account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountUsage {
+    pub account: String,
+    pub resource_id: String,
+    pub kind: String,
+    pub location: String,
+    pub sku: Option<String>,
+    /// The account's usage counters
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub usages: Vec<QuotaUsage>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deployments: Vec<Deployment>,
+    /// The subscription's quota in the region for the deployed models
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub regional_quota: Vec<QuotaUsage>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub documented_limits: Vec<DocumentedLimit>,
+    /// ARM reads that failed; the rest is still shown
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+impl AccountUsage {
+    /// Counters of the account and region at or above [`NEAR_LIMIT`]
+    pub fn near_limit(&self) -> Vec<&QuotaUsage> {
+        self.usages
+            .iter()
+            .chain(&self.regional_quota)
+            .filter(|u| u.fraction_used().is_some_and(|f| f >= NEAR_LIMIT))
+            .collect()
+    }
+
+    /// One line per counter, deployment, and documented limit
+    pub fn summary_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "{} ({}, {}, {})",
+            self.account,
+            self.kind,
+            self.location,
+            self.sku.as_deref().unwrap_or("unknown SKU")
+        )];
+        for usage in &self.usages {
+            lines.push(format!("Usage: {}", describe_usage(usage)));
+        }
+        for deployment in &self.deployments {
+            lines.push(format!("Deployment: {}", describe_deployment(deployment)));
+        }
+        for usage in &self.regional_quota {
+            lines.push(format!("Regional quota: {}", describe_usage(usage)));
+        }
+        for limit in &self.documented_limits {
+            lines.push(format!(
+                "Default limit: {} {} (documented)",
+                limit.service, limit.limit
+            ));
+        }
+        for error in &self.errors {
+            lines.push(format!("Not available: {}", error));
+        }
+        lines
+    }
+}
+
+/// "Tokens Per Minute (thousands) - GPT-4o: 430 / 450 (96%)"
+fn describe_usage(usage: &QuotaUsage) -> String {
+    let mut text = format!(
+        "{}: {} / {}",
+        usage.display_name,
+        format_number(usage.current_value),
+        format_number(usage.limit)
+    );
+    if let Some(fraction) = usage.fraction_used() {
+        text.push_str(&format!(" ({:.0}%)", fraction * 100.0));
+    }
+    text
+}
+
+/// "chat (gpt-4o 2024-08-06, Standard): 30,000 tokens/min, 180 requests/min"
+fn describe_deployment(deployment: &Deployment) -> String {
+    let model = [
+        deployment.model.as_deref(),
+        deployment.model_version.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(" ");
+    let mut context: Vec<String> = Vec::new();
+    if !model.is_empty() {
+        context.push(model);
+    }
+    context.extend(deployment.sku.clone());
+    let mut limits = Vec::new();
+    if let Some(tpm) = deployment.tokens_per_minute {
+        limits.push(format!("{} tokens/min", format_number(tpm as f64)));
+    }
+    if let Some(rpm) = deployment.requests_per_minute {
+        limits.push(format!("{} requests/min", format_number(rpm as f64)));
+    }
+    if limits.is_empty() {
+        if let Some(capacity) = deployment.capacity {
+            limits.push(format!("capacity {}", capacity));
+        }
+    }
+    let mut text = deployment.name.clone();
+    if !context.is_empty() {
+        text.push_str(&format!(" ({})", context.join(", ")));
+    }
+    if !limits.is_empty() {
+        text.push_str(&format!(": {}", limits.join(", ")));
+    }
+    text
+}
+
+/// Whole numbers with thousands separators, others with two decimals
+fn format_number(value: f64) -> String {
+    if value.fract() != 0.0 {
+        return format!("{:.2}", value);
+    }
+    let digits = format!("{}", value.abs() as u64);
+    let mut grouped = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    if value < 0.0 {
+        format!("-{}", grouped)
+    } else {
+        grouped
+    }
+}
+
+/// Documented default limits of an account's kind and SKU
+pub fn documented_limits(kind: &str, sku: Option<&str>) -> Vec<DocumentedLimit> {
+    let Some(sku) = sku else {
+        return Vec::new();
+    };
+    DOCUMENTED_LIMITS
+        .iter()
+        .filter(|(k, s, _)| *k == kind && s.eq_ignore_ascii_case(sku))
+        .flat_map(|(_, _, limits)| limits.iter())
+        .map(|(service, limit)| DocumentedLimit {
+            service: service.to_string(),
+            limit: limit.to_string(),
+        })
+        .collect()
+}
+
+/// Read an account's quota and usage via ARM. Failed reads are recorded in
+/// `errors`, so one missing permission doesn't hide the rest.
+pub async fn collect(client: &ArmClient, account: &CognitiveAccount) -> AccountUsage {
+    let mut usage = AccountUsage {
+        account: account.name.clone(),
+        resource_id: account.id.clone(),
+        kind: account.kind.clone(),
+        location: account.location.clone(),
+        sku: account.sku.clone(),
+        usages: Vec::new(),
+        deployments: Vec::new(),
+        regional_quota: Vec::new(),
+        documented_limits: documented_limits(&account.kind, account.sku.as_deref()),
+        errors: Vec::new(),
+    };
+
+    match client.list_usages(&account.id).await {
+        Ok(usages) => usage.usages = usages,
+        Err(e) => usage.errors.push(format!("usage counters ({})", e)),
+    }
+
+    if !matches!(account.kind.as_str(), "OpenAI" | "AIServices") {
+        return usage;
+    }
+    match client.list_deployments(&account.id).await {
+        Ok(deployments) => usage.deployments = deployments,
+        Err(e) => usage.errors.push(format!("deployments ({})", e)),
+    }
+    if usage.deployments.is_empty() {
+        return usage;
+    }
+    let Some(subscription) = subscription_of(&account.id) else {
+        return usage;
+    };
+    match client
+        .list_location_usages(subscription, &account.location)
+        .await
+    {
+        // Only the deployed models' quota, or quota something uses
+        Ok(regional) => {
+            usage.regional_quota = regional
+                .into_iter()
+                .filter(|u| {
+                    u.current_value > 0.0
+                        || usage.deployments.iter().any(|d| {
+                            d.model
+                                .as_deref()
+                                .is_some_and(|m| u.name.to_lowercase().ends_with(&m.to_lowercase()))
+                        })
+                })
+                .collect()
+        }
+        Err(e) => usage.errors.push(format!(
+            "regional quota of subscription {} ({})",
+            subscription, e
+        )),
+    }
+    usage
+}
+
+/// Results of the run that were rate limited (HTTP 429)
+fn throttled_results(report: &TestReport) -> usize {
+    report
+        .services
+        .iter()
+        .flat_map(|s| &s.results)
+        .filter(|r| r.is_throttled() || r.failure_kind == Some(FailureKind::Throttled))
+        .count()
+}
+
+/// Findings relating the account's quota to the run: counters near their
+/// limit, and the deployments' rate limits when requests were throttled
+pub fn usage_findings(usage: &AccountUsage, report: &TestReport) -> Vec<String> {
+    let throttled = throttled_results(report);
+    let explains = if throttled > 0 {
+        format!(
+            ". This likely explains the {} throttled (HTTP 429) result(s)",
+            throttled
+        )
+    } else {
+        String::new()
+    };
+    let mut findings: Vec<String> = usage
+        .near_limit()
+        .into_iter()
+        .map(|u| {
+            format!(
+                "Quota near its limit on {}: {}{}",
+                usage.account,
+                describe_usage(u),
+                explains
+            )
+        })
+        .collect();
+    if throttled > 0 && findings.is_empty() {
+        let limits: Vec<String> = usage
+            .deployments
+            .iter()
+            .filter(|d| d.tokens_per_minute.is_some() || d.requests_per_minute.is_some())
+            .map(describe_deployment)
+            .collect();
+        if !limits.is_empty() {
+            findings.push(format!(
+                "{} result(s) were throttled (HTTP 429) while no quota counter is near its \
+                 limit; the deployments' rate limits are {}. Raise a deployment's capacity \
+                 if the load needs more",
+                throttled,
+                limits.join("; ")
+            ));
+        }
+    }
+    findings
+}
+
+/// Format the quota and usage of accounts for display
+pub fn format_usage(usages: &[AccountUsage], use_colors: bool) -> String {
+    let mut output = String::new();
+    output.push_str("\nQuota & Usage\n");
+    output.push_str("=============\n\n");
+
+    if usages.is_empty() {
+        output.push_str("  No accounts found.\n");
+        return output;
+    }
+
+    for usage in usages {
+        let mut lines = usage.summary_lines().into_iter();
+        if let Some(header) = lines.next() {
+            if use_colors {
+                output.push_str(&format!("{}\n", style(header).bold()));
+            } else {
+                output.push_str(&format!("{}\n", header));
+            }
+        }
+        let near: Vec<&str> = usage
+            .near_limit()
+            .iter()
+            .map(|u| u.display_name.as_str())
+            .collect();
+        for line in lines {
+            let flagged = near.iter().any(|name| line.contains(name));
+            if use_colors && flagged {
+                output.push_str(&format!("  {}\n", style(line).yellow()));
+            } else if flagged {
+                output.push_str(&format!("  {} [near limit]\n", line));
+            } else {
+                output.push_str(&format!("  {}\n", line));
+            }
+        }
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{ServiceTestResults, TestResult};
+
+    fn usage() -> AccountUsage {
+        AccountUsage {
+            account: "aoai1".to_string(),
+            resource_id: "/subscriptions/sub/resourceGroups/rg/providers/Microsoft.CognitiveServices/accounts/aoai1".to_string(),
+            kind: "OpenAI".to_string(),
+            location: "eastus".to_string(),
+            sku: Some("S0".to_string()),
+            usages: Vec::new(),
+            deployments: vec![Deployment {
+                name: "chat".to_string(),
+                model: Some("gpt-4o".to_string()),
+                model_version: Some("2024-08-06".to_string()),
+                sku: Some("Standard".to_string()),
+                capacity: Some(30),
+                requests_per_minute: Some(180),
+                tokens_per_minute: Some(30000),
+            }],
+            regional_quota: vec![QuotaUsage {
+                name: "OpenAI.Standard.gpt-4o".to_string(),
+                display_name: "Tokens Per Minute (thousands) - GPT-4o".to_string(),
+                current_value: 440.0,
+                limit: 450.0,
+                unit: "Count".to_string(),
+                period: None,
+            }],
+            documented_limits: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_summary_and_limits() {
+        let lines = usage().summary_lines();
+        assert_eq!(lines[0], "aoai1 (OpenAI, eastus, S0)");
+        assert_eq!(
+            lines[1],
+            "Deployment: chat (gpt-4o 2024-08-06, Standard): 30,000 tokens/min, 180 requests/min"
+        );
+        assert_eq!(
+            lines[2],
+            "Regional quota: Tokens Per Minute (thousands) - GPT-4o: 440 / 450 (98%)"
+        );
+        assert_eq!(documented_limits("SpeechServices", Some("S0")).len(), 2);
+        assert!(documented_limits("OpenAI", Some("S0")).is_empty());
+    }
+
+    #[test]
+    fn test_usage_findings() {
+        let mut throttled =
+            TestResult::failure("chat", "Chat Completions", 80, "HTTP 429".to_string());
+        throttled.throttled = true;
+        let report = TestReport::new(vec![ServiceTestResults {
+            service_name: "Azure OpenAI".to_string(),
+            endpoint: "https://aoai1.openai.azure.com".to_string(),
+            region: None,
+            target: None,
+            results: vec![throttled],
+            total_duration_ms: 80,
+        }]);
+
+        let findings = usage_findings(&usage(), &report);
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0].starts_with("Quota near its limit on aoai1"));
+        assert!(findings[0].ends_with("the 1 throttled (HTTP 429) result(s)"));
+
+        let mut plenty = usage();
+        plenty.regional_quota[0].current_value = 30.0;
+        let findings = usage_findings(&plenty, &report);
+        assert!(findings[0].contains("chat (gpt-4o 2024-08-06, Standard): 30,000 tokens/min"));
+        assert!(usage_findings(&plenty, &TestReport::new(Vec::new())).is_empty());
+    }
+}